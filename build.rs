@@ -0,0 +1,22 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // The gRPC API is served from the descriptor set of its proto
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/backoffice.proto");
+        let descriptors = protox::Compiler::new(["proto"])
+            .and_then(|mut compiler| {
+                compiler.include_imports(true);
+                compiler.open_file("backoffice.proto")?;
+                Ok(compiler.encode_file_descriptor_set())
+            })
+            .unwrap_or_else(|e| panic!("Invalid proto/backoffice.proto: {}", e));
+        let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+        std::fs::write(
+            std::path::Path::new(&out_dir).join("backoffice_descriptor.bin"),
+            descriptors,
+        )
+        .expect("Failed to write the gRPC descriptor set");
+    }
+}
@@ -0,0 +1,313 @@
+//! Access log
+//!
+//! Every request is logged once it is answered, to the `access` tracing
+//! target, with its method, path, status, latency, user and request id, in
+//! the Apache combined format or as JSON (`server.access_log.format`).
+//!
+//! Values of query parameters and payload fields whose names look sensitive,
+//! such as `password`, `api_key` or `refresh_token`, are replaced with
+//! `[REDACTED]` before they are logged, here and wherever payloads are.
+
+use crate::auth::AuthContext;
+use crate::config::{AccessLogConfig, AccessLogFormat, SecurityConfig};
+use axum::{
+    extract::{ConnectInfo, OriginalUri, Query, Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Parts of names whose values are always redacted
+const SENSITIVE: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "access_key",
+    "private_key",
+    "authorization",
+    "credential",
+    "card_number",
+    "cvv",
+];
+
+/// Redaction of sensitive names, with the configured extra names
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    extra: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(config: &AccessLogConfig) -> Self {
+        Self {
+            extra: config
+                .redact
+                .iter()
+                .map(|name| name.to_lowercase())
+                .collect(),
+        }
+    }
+
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        SENSITIVE.iter().any(|part| name.contains(part)) || self.extra.contains(&name)
+    }
+
+    /// A value with the members of sensitive names redacted, at any depth
+    pub fn value(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(members) => Value::Object(self.members(members.iter())),
+            Value::Array(values) => values.iter().map(|value| self.value(value)).collect(),
+            value => value.clone(),
+        }
+    }
+
+    /// Payload fields with those of sensitive names redacted
+    pub fn payload(&self, payload: &HashMap<String, Value>) -> Value {
+        Value::Object(self.members(payload.iter()))
+    }
+
+    fn members<'a>(
+        &self,
+        members: impl Iterator<Item = (&'a String, &'a Value)>,
+    ) -> Map<String, Value> {
+        members
+            .map(|(name, value)| {
+                let value = if self.is_sensitive(name) {
+                    REDACTED.into()
+                } else {
+                    self.value(value)
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// A query string with the values of sensitive parameters redacted
+    pub fn query(&self, query: &str) -> String {
+        query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if self.is_sensitive(&decoded(name)) => {
+                    format!("{}={}", name, REDACTED)
+                }
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+/// A query parameter name, percent-decoded
+fn decoded(name: &str) -> String {
+    let uri = format!("/?{}=", name);
+    uri.parse()
+        .ok()
+        .and_then(|uri| Query::<Vec<(String, String)>>::try_from_uri(&uri).ok())
+        .and_then(|Query(pairs)| pairs.into_iter().next())
+        .map_or_else(|| name.to_string(), |(name, _)| name)
+}
+
+/// What the access log layer needs of the configuration
+#[derive(Clone)]
+pub struct AccessLog {
+    format: AccessLogFormat,
+    redactor: Redactor,
+    security: Option<SecurityConfig>,
+    trusted_proxies: usize,
+}
+
+impl AccessLog {
+    pub fn new(
+        config: &AccessLogConfig,
+        security: Option<SecurityConfig>,
+        trusted_proxies: usize,
+    ) -> Self {
+        Self {
+            format: config.format,
+            redactor: Redactor::new(config),
+            security,
+            trusted_proxies,
+        }
+    }
+}
+
+/// One answered request
+struct Entry {
+    method: String,
+    path: String,
+    query: Option<String>,
+    version: String,
+    status: u16,
+    latency_ms: f64,
+    bytes: Option<u64>,
+    user: Option<String>,
+    client_ip: Option<String>,
+    request_id: Option<String>,
+    referer: Option<String>,
+    user_agent: Option<String>,
+}
+
+impl Entry {
+    fn combined(&self) -> String {
+        let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        let target = match &self.query {
+            Some(query) => format!("{}?{}", self.path, query),
+            None => self.path.clone(),
+        };
+        format!(
+            "{} - {} [{}] \"{} {} {}\" {} {} \"{}\" \"{}\" {:.1}ms {}",
+            or_dash(&self.client_ip),
+            or_dash(&self.user),
+            Utc::now().format("%d/%b/%Y:%H:%M:%S %z"),
+            self.method,
+            target,
+            self.version,
+            self.status,
+            self.bytes
+                .map_or_else(|| "-".to_string(), |bytes| bytes.to_string()),
+            or_dash(&self.referer),
+            or_dash(&self.user_agent),
+            self.latency_ms,
+            or_dash(&self.request_id),
+        )
+    }
+
+    fn json(&self) -> Value {
+        json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "method": self.method,
+            "path": self.path,
+            "query": self.query,
+            "status": self.status,
+            "latency_ms": self.latency_ms,
+            "bytes": self.bytes,
+            "user": self.user,
+            "client_ip": self.client_ip,
+            "request_id": self.request_id,
+            "referer": self.referer,
+            "user_agent": self.user_agent,
+        })
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: impl header::AsHeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Log every request once it is answered
+pub async fn log(State(log): State<Arc<AccessLog>>, request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let uri = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| request.uri().clone(), |uri| uri.0.clone());
+    let headers = request.headers();
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let metadata = crate::audit::RequestMetadata::from_headers(headers, peer, log.trusted_proxies);
+    let user = AuthContext::from_headers(headers, log.security.as_ref())
+        .ok()
+        .and_then(|auth| auth.user_id);
+    let mut entry = Entry {
+        method: request.method().to_string(),
+        path: uri.path().to_string(),
+        query: uri.query().map(|query| log.redactor.query(query)),
+        version: format!("{:?}", request.version()),
+        status: 0,
+        latency_ms: 0.0,
+        bytes: None,
+        user,
+        client_ip: metadata.client_ip,
+        request_id: None,
+        referer: header_value(headers, header::REFERER),
+        user_agent: metadata.user_agent,
+    };
+
+    let response = next.run(request).await;
+
+    entry.status = response.status().as_u16();
+    entry.latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    entry.bytes = header_value(response.headers(), header::CONTENT_LENGTH)
+        .and_then(|length| length.parse().ok());
+    entry.request_id = header_value(response.headers(), crate::request_id::HEADER);
+    match log.format {
+        AccessLogFormat::Combined => info!(target: "access", "{}", entry.combined()),
+        AccessLogFormat::Json => info!(target: "access", "{}", entry.json()),
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redaction() {
+        let redactor = Redactor::new(&AccessLogConfig {
+            redact: vec!["SSN".to_string()],
+            ..Default::default()
+        });
+        assert!(redactor.is_sensitive("refresh_token"));
+        assert!(redactor.is_sensitive("ssn"));
+        assert!(!redactor.is_sensitive("author_id"));
+
+        assert_eq!(
+            redactor.query("q=ada&api_key=abc&page%5Bsize%5D=5&access%5Ftoken=xyz"),
+            "q=ada&api_key=[REDACTED]&page%5Bsize%5D=5&access%5Ftoken=[REDACTED]"
+        );
+        let payload = HashMap::from([
+            ("name".to_string(), json!("Ada")),
+            ("password".to_string(), json!("hunter2")),
+            (
+                "profile".to_string(),
+                json!({"ssn": "123", "city": "London"}),
+            ),
+        ]);
+        assert_eq!(
+            redactor.payload(&payload),
+            json!({
+                "name": "Ada",
+                "password": REDACTED,
+                "profile": {"ssn": REDACTED, "city": "London"},
+            })
+        );
+
+        let entry = Entry {
+            method: "GET".to_string(),
+            path: "/api/backoffices".to_string(),
+            query: None,
+            version: "HTTP/1.1".to_string(),
+            status: 200,
+            latency_ms: 1.5,
+            bytes: Some(42),
+            user: Some("ada".to_string()),
+            client_ip: Some("10.0.0.1".to_string()),
+            request_id: Some("req-1".to_string()),
+            referer: None,
+            user_agent: Some("curl/8".to_string()),
+        };
+        let line = entry.combined();
+        assert!(line.starts_with("10.0.0.1 - ada ["));
+        assert!(
+            line.ends_with("\"GET /api/backoffices HTTP/1.1\" 200 42 \"-\" \"curl/8\" 1.5ms req-1")
+        );
+        assert_eq!(entry.json()["status"], 200);
+    }
+}
@@ -0,0 +1,122 @@
+//! Virus scanning of uploaded files
+//!
+//! With `antivirus` configured, files uploaded to File and Image fields are
+//! streamed to a ClamAV daemon with its `INSTREAM` command before they are
+//! stored. Flagged files are rejected, and so are files that could not be
+//! scanned: only fields with `skip_virus_scan: true` are stored unscanned.
+
+use crate::config::AntivirusConfig;
+use anyhow::{anyhow, bail, Result};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+/// Outcome of a scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Clean,
+    /// Name of the signature the file matched, e.g. `Eicar-Test-Signature`
+    Infected(String),
+}
+
+/// Stream content to clamd and read its verdict
+pub async fn scan(config: &AntivirusConfig, content: &[u8]) -> Result<Verdict> {
+    let timeout = Duration::from_secs(config.timeout_secs);
+    tokio::time::timeout(timeout, instream(config, content))
+        .await
+        .map_err(|_| anyhow!("Virus scan timed out after {}s", config.timeout_secs))?
+}
+
+async fn instream(config: &AntivirusConfig, content: &[u8]) -> Result<Verdict> {
+    let mut stream = TcpStream::connect(&config.address)
+        .await
+        .map_err(|e| anyhow!("Failed to connect to clamd at {}: {}", config.address, e))?;
+
+    debug!(address = %config.address, size = content.len(), "Streaming file to clamd");
+    stream.write_all(b"zINSTREAM\0").await?;
+    // Each chunk is prefixed with its length; an empty one ends the stream
+    for chunk in content.chunks(config.chunk_bytes.max(1)) {
+        stream
+            .write_all(&(chunk.len() as u32).to_be_bytes())
+            .await?;
+        stream.write_all(chunk).await?;
+    }
+    stream.write_all(&0u32.to_be_bytes()).await?;
+    stream.flush().await?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    parse_reply(&String::from_utf8_lossy(&reply))
+}
+
+/// Verdict of a reply such as `stream: OK` or `stream: Eicar-Test-Signature FOUND`
+fn parse_reply(reply: &str) -> Result<Verdict> {
+    let reply = reply.trim_end_matches(['\0', '\n']).trim();
+    let result = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+    if result == "OK" {
+        return Ok(Verdict::Clean);
+    }
+    if let Some(signature) = result.strip_suffix("FOUND") {
+        return Ok(Verdict::Infected(signature.trim().to_string()));
+    }
+    bail!("clamd could not scan the file: {}", reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// clamd stand-in flagging content containing `EICAR`
+    async fn clamd() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut command = [0u8; 10];
+                socket.read_exact(&mut command).await.unwrap();
+                assert_eq!(&command, b"zINSTREAM\0");
+                let mut content = Vec::new();
+                loop {
+                    let length = socket.read_u32().await.unwrap() as usize;
+                    if length == 0 {
+                        break;
+                    }
+                    let mut chunk = vec![0u8; length];
+                    socket.read_exact(&mut chunk).await.unwrap();
+                    content.extend(chunk);
+                }
+                let reply: &[u8] = if content.windows(5).any(|w| w == b"EICAR") {
+                    b"stream: Eicar-Test-Signature FOUND\0"
+                } else {
+                    b"stream: OK\0"
+                };
+                socket.write_all(reply).await.unwrap();
+            }
+        });
+        address
+    }
+
+    #[tokio::test]
+    async fn test_scan() {
+        let config = AntivirusConfig {
+            address: clamd().await,
+            timeout_secs: 5,
+            chunk_bytes: 4,
+        };
+        assert_eq!(scan(&config, b"hello world").await.unwrap(), Verdict::Clean);
+        assert_eq!(
+            scan(&config, b"an EICAR test file").await.unwrap(),
+            Verdict::Infected("Eicar-Test-Signature".to_string())
+        );
+
+        assert!(parse_reply("INSTREAM size limit exceeded. ERROR\0").is_err());
+        let unreachable = AntivirusConfig {
+            address: "127.0.0.1:1".to_string(),
+            ..config
+        };
+        assert!(scan(&unreachable, b"hello").await.is_err());
+    }
+}
@@ -1,12 +1,29 @@
-use crate::config::AuditConfig;
-use chrono::{DateTime, Utc};
+use crate::config::{AuditConfig, AuditMaintenanceConfig, AuditStorageConfig, DataSourceConfig};
+use crate::data_source::{DataSourceRegistry, PaginationParams};
+use crate::server::AppState;
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Query},
+    http::{
+        header::{ACCEPT_LANGUAGE, USER_AGENT},
+        request::Parts,
+        HeaderMap,
+    },
+};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::fs::{create_dir_all, OpenOptions};
-use std::io::Write;
+use std::convert::Infallible;
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
-use tracing::{debug, error, info};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{debug, error, info, warn};
 
 /// Audit log entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,7 +40,7 @@ pub struct AuditLogEntry {
     pub metadata: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AuditOperation {
     Create,
@@ -32,6 +49,17 @@ pub enum AuditOperation {
     Read,
 }
 
+impl AuditOperation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditOperation::Create => "create",
+            AuditOperation::Update => "update",
+            AuditOperation::Delete => "delete",
+            AuditOperation::Read => "read",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FieldChange {
     pub field: String,
@@ -39,14 +67,160 @@ pub struct FieldChange {
     pub new_value: Option<Value>,
 }
 
-/// Audit logger that writes to JSON files
+/// Request an audited change came from, recorded in the entry metadata
+#[derive(Debug, Clone, Default)]
+pub struct RequestMetadata {
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// `X-Request-Id` of the request, generated when the client sends none
+    pub request_id: Option<String>,
+    /// Locales the client prefers for messages, from `?locale=` or
+    /// `Accept-Language`; not recorded in entries
+    pub locale: Option<String>,
+    /// Set by the server, such as the entry a restore re-applies
+    pub extra: HashMap<String, String>,
+}
+
+impl RequestMetadata {
+    /// Read the metadata of a request from its headers and peer address
+    ///
+    /// Behind `trusted_proxies` proxies, the client IP is the
+    /// `X-Forwarded-For` entry that many hops from the right; entries further
+    /// left could have been forged by the client.
+    pub fn from_headers(headers: &HeaderMap, peer: Option<IpAddr>, trusted_proxies: usize) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        let forwarded: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty())
+            .collect();
+        let client_ip = (trusted_proxies > 0)
+            .then(|| forwarded.len().saturating_sub(trusted_proxies))
+            .and_then(|i| forwarded.get(i))
+            .map(|ip| ip.to_string())
+            .or_else(|| peer.map(|ip| ip.to_string()));
+
+        Self {
+            client_ip,
+            user_agent: header(USER_AGENT.as_str()),
+            request_id: header("x-request-id"),
+            locale: header(ACCEPT_LANGUAGE.as_str()),
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Add the request metadata and the action that made the change
+    pub fn attach(&self, entry: &mut AuditLogEntry, action_id: &str) {
+        let fields = [
+            ("client_ip", self.client_ip.as_deref()),
+            ("user_agent", self.user_agent.as_deref()),
+            ("request_id", self.request_id.as_deref()),
+            ("action_id", Some(action_id)),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                entry.metadata.insert(key.to_string(), value.to_string());
+            }
+        }
+        entry.metadata.extend(self.extra.clone());
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for RequestMetadata {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+        let mut metadata =
+            Self::from_headers(&parts.headers, peer, state.config.server.trusted_proxies);
+        if let Ok(Query(query)) = Query::<HashMap<String, String>>::try_from_uri(&parts.uri) {
+            if let Some(locale) = query.get("locale") {
+                metadata.locale = Some(locale.clone());
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+/// Where an audit logger writes its entries
+enum AuditStorage {
+    /// Daily JSONL files in a directory, continued in numbered parts once
+    /// `max_file_bytes` is reached
+    File {
+        log_dir: PathBuf,
+        max_file_bytes: Option<u64>,
+    },
+    /// A database table, Elasticsearch index or S3 bucket
+    DataSource {
+        config: Box<DataSourceConfig>,
+        table: String,
+        registry: Arc<DataSourceRegistry>,
+    },
+}
+
+/// Name of a log file: `audit-<date>[.<part>].jsonl[.gz]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LogFile {
+    day: NaiveDate,
+    part: u32,
+    compressed: bool,
+}
+
+impl LogFile {
+    fn parse(name: &str) -> Option<Self> {
+        let (name, compressed) = match name.strip_suffix(".gz") {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        let stem = name.strip_prefix("audit-")?.strip_suffix(".jsonl")?;
+        let (day, part) = match stem.split_once('.') {
+            Some((day, part)) => (day, part.parse().ok()?),
+            None => (stem, 0),
+        };
+        Some(Self {
+            day: NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()?,
+            part,
+            compressed,
+        })
+    }
+
+    /// Uncompressed file name of a day's part
+    fn name(day: NaiveDate, part: u32) -> String {
+        match part {
+            0 => format!("audit-{}.jsonl", day),
+            part => format!("audit-{}.{}.jsonl", day, part),
+        }
+    }
+}
+
+/// Audit logger writing to JSON files or a data source
 pub struct AuditLogger {
-    log_dir: PathBuf,
+    storage: AuditStorage,
     enabled: bool,
+    /// Entries as they are logged, for push subscribers
+    events: broadcast::Sender<AuditLogEntry>,
 }
 
+/// Logged entries a lagging subscriber may fall behind by
+const EVENT_CAPACITY: usize = 256;
+
 impl AuditLogger {
-    /// Create a new audit logger
+    /// Create a new audit logger writing daily files to `log_dir`
     pub fn new(log_dir: impl Into<PathBuf>) -> Self {
         let log_dir = log_dir.into();
 
@@ -60,21 +234,97 @@ impl AuditLogger {
         }
 
         Self {
-            log_dir,
+            storage: AuditStorage::File {
+                log_dir,
+                max_file_bytes: None,
+            },
             enabled: true,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Start a new part of the day's log file once it reaches `max` bytes
+    pub fn with_max_file_bytes(mut self, max: Option<u64>) -> Self {
+        if let AuditStorage::File { max_file_bytes, .. } = &mut self.storage {
+            *max_file_bytes = max;
+        }
+        self
+    }
+
+    /// Create the audit logger of an `audit_storage` block
+    ///
+    /// Data source sinks are created through `registry` on every write, so
+    /// they share its pools, circuit breakers and health monitoring.
+    pub fn from_config(
+        storage: Option<&AuditStorageConfig>,
+        registry: Arc<DataSourceRegistry>,
+    ) -> Self {
+        match storage {
+            None => Self::new("logs/audit"),
+            Some(AuditStorageConfig::File { dir }) => Self::new(dir),
+            Some(AuditStorageConfig::DataSource { data_source, table }) => Self {
+                storage: AuditStorage::DataSource {
+                    config: data_source.clone(),
+                    table: table.clone(),
+                    registry,
+                },
+                enabled: true,
+                events: broadcast::channel(EVENT_CAPACITY).0,
+            },
         }
     }
 
     /// Log an audit entry
-    pub fn log(&self, entry: AuditLogEntry) -> anyhow::Result<()> {
+    pub async fn log(&self, entry: AuditLogEntry) -> anyhow::Result<()> {
         if !self.enabled {
             debug!("Audit logging disabled, skipping");
             return Ok(());
         }
 
-        // Create a log file for today
-        let date = Utc::now().format("%Y-%m-%d").to_string();
-        let log_file = self.log_dir.join(format!("audit-{}.jsonl", date));
+        match &self.storage {
+            AuditStorage::File {
+                log_dir,
+                max_file_bytes,
+            } => Self::write_file(log_dir, *max_file_bytes, &entry)?,
+            AuditStorage::DataSource {
+                config,
+                table,
+                registry,
+            } => Self::write_data_source(config, table, registry, &entry).await?,
+        }
+
+        info!(
+            id = %entry.id,
+            operation = ?entry.operation,
+            "Audit entry logged successfully"
+        );
+        // Nobody may be listening
+        let _ = self.events.send(entry);
+
+        Ok(())
+    }
+
+    /// Receive entries as they are logged
+    pub fn subscribe(&self) -> broadcast::Receiver<AuditLogEntry> {
+        self.events.subscribe()
+    }
+
+    fn write_file(
+        log_dir: &std::path::Path,
+        max_file_bytes: Option<u64>,
+        entry: &AuditLogEntry,
+    ) -> anyhow::Result<()> {
+        // Today's first part that is neither full nor compressed
+        let today = Utc::now().date_naive();
+        let log_file = (0..)
+            .map(|part| log_dir.join(LogFile::name(today, part)))
+            .find(|path| {
+                let full = max_file_bytes.is_some_and(|max| {
+                    std::fs::metadata(path).is_ok_and(|metadata| metadata.len() >= max)
+                });
+                !full && !path.with_extension("jsonl.gz").exists()
+            })
+            .expect("log file parts are unbounded");
 
         debug!(
             file = ?log_file,
@@ -91,21 +341,73 @@ impl AuditLogger {
             .map_err(|e| anyhow::anyhow!("Failed to open audit log file: {}", e))?;
 
         // Write as JSON line
-        let json = serde_json::to_string(&entry)
+        let json = serde_json::to_string(entry)
             .map_err(|e| anyhow::anyhow!("Failed to serialize audit entry: {}", e))?;
 
         writeln!(file, "{}", json)
             .map_err(|e| anyhow::anyhow!("Failed to write audit entry: {}", e))?;
 
         file.flush()
-            .map_err(|e| anyhow::anyhow!("Failed to flush audit log: {}", e))?;
+            .map_err(|e| anyhow::anyhow!("Failed to flush audit log: {}", e))
+    }
 
-        info!(
-            id = %entry.id,
+    /// Insert a row, index a document keyed by the entry id, or store a
+    /// `<date>/<id>.json` object, depending on the data source
+    async fn write_data_source(
+        config: &DataSourceConfig,
+        table: &str,
+        registry: &DataSourceRegistry,
+        entry: &AuditLogEntry,
+    ) -> anyhow::Result<()> {
+        let Value::Object(document) = serde_json::to_value(entry)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize audit entry: {}", e))?
+        else {
+            unreachable!("audit entries serialize to objects");
+        };
+        let data: HashMap<String, Value> = document.into_iter().collect();
+
+        let target = match config {
+            DataSourceConfig::Database { .. } => {
+                let mut columns: Vec<&String> = data.keys().collect();
+                columns.sort();
+                format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    table,
+                    columns
+                        .iter()
+                        .map(|c| c.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    columns
+                        .iter()
+                        .map(|c| format!("{{{{{}}}}}", c))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            DataSourceConfig::Elasticsearch { .. } => entry.id.clone(),
+            DataSourceConfig::S3 { .. } => {
+                format!("{}/{}.json", entry.timestamp.format("%Y-%m-%d"), entry.id)
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "audit_storage supports database, elasticsearch and s3 data sources"
+                ))
+            }
+        };
+
+        debug!(
+            target = %target,
             operation = ?entry.operation,
-            "Audit entry logged successfully"
+            section = %entry.section_id,
+            "Writing audit log entry to data source"
         );
-
+        registry
+            .create(config)
+            .await?
+            .execute_mutation(&target, &data)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to write audit entry: {}", e))?;
         Ok(())
     }
 
@@ -138,15 +440,16 @@ impl AuditLogger {
     }
 
     /// Create an audit entry for an update operation
-    #[allow(dead_code)]
+    ///
+    /// Without the old values, every new value is recorded as a change.
     pub fn update_entry(
         section_id: String,
         record_id: String,
-        old_data: &HashMap<String, Value>,
+        old_data: Option<&HashMap<String, Value>>,
         new_data: &HashMap<String, Value>,
         user_id: Option<String>,
     ) -> AuditLogEntry {
-        let changes = compute_changes(old_data, new_data);
+        let changes = compute_changes(old_data.unwrap_or(&HashMap::new()), new_data);
 
         AuditLogEntry {
             id: uuid::Uuid::new_v4().to_string(),
@@ -155,13 +458,43 @@ impl AuditLogger {
             section_id,
             record_id: Some(record_id),
             user_id,
-            old_values: Some(old_data.clone()),
+            old_values: old_data.cloned(),
             new_values: Some(new_data.clone()),
             changes,
             metadata: HashMap::new(),
         }
     }
 
+    /// Create an audit entry for an update of every record matching a
+    /// filter, with the filter and the number of records updated in its
+    /// metadata
+    pub fn bulk_update_entry(
+        section_id: String,
+        filter: &HashMap<String, Value>,
+        new_data: &HashMap<String, Value>,
+        affected: u64,
+        user_id: Option<String>,
+    ) -> AuditLogEntry {
+        let filter: serde_json::Map<String, Value> =
+            filter.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+
+        AuditLogEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            operation: AuditOperation::Update,
+            section_id,
+            record_id: None,
+            user_id,
+            old_values: None,
+            new_values: Some(new_data.clone()),
+            changes: compute_changes(&HashMap::new(), new_data),
+            metadata: HashMap::from([
+                ("filter".to_string(), Value::Object(filter).to_string()),
+                ("affected_count".to_string(), affected.to_string()),
+            ]),
+        }
+    }
+
     /// Create an audit entry for a delete operation
     pub fn delete_entry(
         section_id: String,
@@ -207,42 +540,66 @@ impl AuditLogger {
         }
     }
 
+    /// Delete entries older than the retention and compress log files that
+    /// are no longer written to
+    pub async fn run_maintenance(&self, config: &AuditMaintenanceConfig) -> anyhow::Result<()> {
+        if let Some(retention_days) = config.retention_days {
+            self.cleanup_old_logs(retention_days).await?;
+        }
+        if let (true, AuditStorage::File { log_dir, .. }) = (config.compress, &self.storage) {
+            Self::compress_closed_files(log_dir)?;
+        }
+        Ok(())
+    }
+
     /// Clean up old audit logs based on retention policy
-    #[allow(dead_code)]
-    pub fn cleanup_old_logs(&self, retention_days: u32) -> anyhow::Result<()> {
+    ///
+    /// Log files and database rows are deleted. Elasticsearch indices and S3
+    /// buckets are left to their own lifecycle policies.
+    pub async fn cleanup_old_logs(&self, retention_days: u32) -> anyhow::Result<()> {
         info!(
             retention_days = retention_days,
             "Starting audit log cleanup"
         );
 
-        let cutoff_date = Utc::now() - chrono::Duration::days(retention_days as i64);
-        let cutoff_str = cutoff_date.format("%Y-%m-%d").to_string();
+        let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
 
-        let entries = std::fs::read_dir(&self.log_dir)
-            .map_err(|e| anyhow::anyhow!("Failed to read audit log directory: {}", e))?;
+        let log_dir = match &self.storage {
+            AuditStorage::File { log_dir, .. } => log_dir,
+            AuditStorage::DataSource {
+                config,
+                table,
+                registry,
+            } => {
+                if !matches!(config.as_ref(), DataSourceConfig::Database { .. }) {
+                    debug!("Audit entries are not stored in a database, skipping cleanup");
+                    return Ok(());
+                }
+                // Timestamps are stored as RFC 3339 text, which sorts chronologically
+                let params = HashMap::from([(
+                    "cutoff".to_string(),
+                    Value::String(cutoff.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+                )]);
+                let deleted = registry
+                    .create(config)
+                    .await?
+                    .execute_mutation(
+                        &format!("DELETE FROM {} WHERE timestamp < {{{{cutoff}}}}", table),
+                        &params,
+                    )
+                    .await?;
+                info!(deleted = %deleted, "Audit log cleanup completed");
+                return Ok(());
+            }
+        };
 
         let mut deleted_count = 0;
-        for entry in entries {
-            let entry =
-                entry.map_err(|e| anyhow::anyhow!("Failed to read directory entry: {}", e))?;
-            let path = entry.path();
-
-            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                if file_name.starts_with("audit-") && file_name.ends_with(".jsonl") {
-                    // Extract date from filename
-                    if let Some(date_str) = file_name
-                        .strip_prefix("audit-")
-                        .and_then(|s| s.strip_suffix(".jsonl"))
-                    {
-                        if date_str < cutoff_str.as_str() {
-                            debug!(file = %file_name, "Deleting old audit log");
-                            std::fs::remove_file(&path).map_err(|e| {
-                                anyhow::anyhow!("Failed to delete audit log: {}", e)
-                            })?;
-                            deleted_count += 1;
-                        }
-                    }
-                }
+        for (path, file) in Self::log_files(log_dir)? {
+            if file.day < cutoff.date_naive() {
+                debug!(file = ?path, "Deleting old audit log");
+                std::fs::remove_file(&path)
+                    .map_err(|e| anyhow::anyhow!("Failed to delete audit log: {}", e))?;
+                deleted_count += 1;
             }
         }
 
@@ -250,10 +607,479 @@ impl AuditLogger {
 
         Ok(())
     }
+
+    /// Gzip the files of past days and every part of today but the last
+    fn compress_closed_files(log_dir: &std::path::Path) -> anyhow::Result<()> {
+        let files = Self::log_files(log_dir)?;
+        let today = Utc::now().date_naive();
+        let current_part = files
+            .iter()
+            .filter(|(_, file)| file.day == today)
+            .map(|(_, file)| file.part)
+            .max();
+
+        for (path, file) in &files {
+            let closed = file.day < today || current_part.is_some_and(|part| file.part < part);
+            if file.compressed || !closed {
+                continue;
+            }
+            // Written under a name reads skip, so a crash leaves no partial file
+            let tmp = path.with_extension("jsonl.gz.tmp");
+            let mut encoder = GzEncoder::new(
+                File::create(&tmp)
+                    .map_err(|e| anyhow::anyhow!("Failed to create compressed audit log: {}", e))?,
+                flate2::Compression::default(),
+            );
+            std::io::copy(&mut File::open(path)?, &mut encoder)
+                .and_then(|_| encoder.finish())
+                .map_err(|e| anyhow::anyhow!("Failed to compress audit log: {}", e))?;
+            std::fs::rename(&tmp, path.with_extension("jsonl.gz"))?;
+            std::fs::remove_file(path)?;
+            debug!(file = ?path, "Compressed audit log");
+        }
+        Ok(())
+    }
+
+    /// Log files of a directory with their parsed names
+    fn log_files(log_dir: &std::path::Path) -> anyhow::Result<Vec<(PathBuf, LogFile)>> {
+        let files = match std::fs::read_dir(log_dir) {
+            Ok(files) => files,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(anyhow::anyhow!("Failed to read audit log directory: {}", e)),
+        };
+
+        let mut log_files = Vec::new();
+        for file in files {
+            let path = file
+                .map_err(|e| anyhow::anyhow!("Failed to read directory entry: {}", e))?
+                .path();
+            if let Some(file) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(LogFile::parse)
+            {
+                log_files.push((path, file));
+            }
+        }
+        Ok(log_files)
+    }
+}
+
+/// Entries a search reads at most from backends filtered in memory
+pub(crate) const MAX_SCANNED_ENTRIES: usize = 10_000;
+
+/// Which audit entries a search returns
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    /// Entries of these sections only
+    pub sections: Vec<String>,
+    pub section_id: Option<String>,
+    pub operation: Option<AuditOperation>,
+    pub record_id: Option<String>,
+    pub user_id: Option<String>,
+    /// Inclusive lower bound of the entry timestamp
+    pub from: Option<DateTime<Utc>>,
+    /// Inclusive upper bound of the entry timestamp
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditLogEntry) -> bool {
+        self.sections.contains(&entry.section_id)
+            && self
+                .section_id
+                .as_ref()
+                .is_none_or(|id| *id == entry.section_id)
+            && self.operation.is_none_or(|op| op == entry.operation)
+            && self
+                .record_id
+                .as_ref()
+                .is_none_or(|id| entry.record_id.as_ref() == Some(id))
+            && self
+                .user_id
+                .as_ref()
+                .is_none_or(|id| entry.user_id.as_ref() == Some(id))
+            && self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp <= to)
+    }
+
+    /// Whether entries of a day can match the date range
+    fn covers_day(&self, day: NaiveDate) -> bool {
+        self.from.is_none_or(|from| day >= from.date_naive())
+            && self.to.is_none_or(|to| day <= to.date_naive())
+    }
+}
+
+/// Field audit search results are sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditSortField {
+    #[default]
+    Timestamp,
+    SectionId,
+    Operation,
+    RecordId,
+    UserId,
+}
+
+impl AuditSortField {
+    fn column(self) -> &'static str {
+        match self {
+            AuditSortField::Timestamp => "timestamp",
+            AuditSortField::SectionId => "section_id",
+            AuditSortField::Operation => "operation",
+            AuditSortField::RecordId => "record_id",
+            AuditSortField::UserId => "user_id",
+        }
+    }
+
+    fn compare(self, a: &AuditLogEntry, b: &AuditLogEntry) -> std::cmp::Ordering {
+        match self {
+            AuditSortField::Timestamp => a.timestamp.cmp(&b.timestamp),
+            AuditSortField::SectionId => a.section_id.cmp(&b.section_id),
+            AuditSortField::Operation => a.operation.as_str().cmp(b.operation.as_str()),
+            AuditSortField::RecordId => a.record_id.cmp(&b.record_id),
+            AuditSortField::UserId => a.user_id.cmp(&b.user_id),
+        }
+    }
+}
+
+/// One page of audit search results
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditPage {
+    pub entries: Vec<AuditLogEntry>,
+    /// Entries matching the filter across all pages
+    pub total: usize,
+}
+
+/// A change in the timeline of a record
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEvent {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub operation: AuditOperation,
+    pub user_id: Option<String>,
+    pub changes: Vec<FieldChange>,
+    /// Values of the record after the change, as far as its audit entries
+    /// tell; `null` once deleted
+    pub values: Option<HashMap<String, Value>>,
+    pub metadata: HashMap<String, String>,
+}
+
+/// Replay the audit entries of one record, oldest first, into its timeline
+pub fn record_history(entries: Vec<AuditLogEntry>) -> Vec<HistoryEvent> {
+    let mut values: Option<HashMap<String, Value>> = None;
+    entries
+        .into_iter()
+        .map(|entry| {
+            match entry.operation {
+                AuditOperation::Create => values = entry.new_values.clone(),
+                AuditOperation::Update => {
+                    let mut updated = values
+                        .take()
+                        .or_else(|| entry.old_values.clone())
+                        .unwrap_or_default();
+                    updated.extend(entry.new_values.clone().unwrap_or_default());
+                    values = Some(updated);
+                }
+                AuditOperation::Delete => values = None,
+                AuditOperation::Read => {}
+            }
+            HistoryEvent {
+                id: entry.id,
+                timestamp: entry.timestamp,
+                operation: entry.operation,
+                user_id: entry.user_id,
+                changes: entry.changes,
+                values: values.clone(),
+                metadata: entry.metadata,
+            }
+        })
+        .collect()
+}
+
+impl AuditLogger {
+    /// Search the configured backend for entries matching `filter`
+    ///
+    /// Database backends filter, sort and paginate in SQL. Files, Elasticsearch
+    /// and S3 are filtered in memory, reading at most 10,000 entries.
+    pub async fn search(
+        &self,
+        filter: &AuditFilter,
+        sort: AuditSortField,
+        descending: bool,
+        pagination: &PaginationParams,
+    ) -> anyhow::Result<AuditPage> {
+        let entries = match &self.storage {
+            AuditStorage::File { log_dir, .. } => Self::read_files(log_dir, filter)?,
+            AuditStorage::DataSource {
+                config,
+                table,
+                registry,
+            } => match config.as_ref() {
+                DataSourceConfig::Database { .. } => {
+                    return Self::search_database(
+                        config, table, registry, filter, sort, descending, pagination,
+                    )
+                    .await
+                }
+                DataSourceConfig::Elasticsearch { .. } => {
+                    Self::read_elasticsearch(config, registry, filter).await?
+                }
+                _ => Self::read_objects(config, registry, filter).await?,
+            },
+        };
+
+        let mut entries: Vec<AuditLogEntry> =
+            entries.into_iter().filter(|e| filter.matches(e)).collect();
+        entries.sort_by(|a, b| {
+            let ordering = sort.compare(a, b);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        let total = entries.len();
+        Ok(AuditPage {
+            entries: entries
+                .into_iter()
+                .skip(pagination.offset)
+                .take(pagination.page_size)
+                .collect(),
+            total,
+        })
+    }
+
+    /// Entries of the daily files within the filter's date range
+    fn read_files(
+        log_dir: &std::path::Path,
+        filter: &AuditFilter,
+    ) -> anyhow::Result<Vec<AuditLogEntry>> {
+        let mut entries = Vec::new();
+        for (path, file) in Self::log_files(log_dir)? {
+            if !filter.covers_day(file.day) {
+                continue;
+            }
+
+            let mut content = String::new();
+            let read = if file.compressed {
+                File::open(&path).and_then(|f| GzDecoder::new(f).read_to_string(&mut content))
+            } else {
+                File::open(&path).and_then(|mut f| f.read_to_string(&mut content))
+            };
+            read.map_err(|e| anyhow::anyhow!("Failed to read audit log file: {}", e))?;
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<AuditLogEntry>(line) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => warn!(file = ?path, error = %e, "Skipping unreadable audit entry"),
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn search_database(
+        config: &DataSourceConfig,
+        table: &str,
+        registry: &DataSourceRegistry,
+        filter: &AuditFilter,
+        sort: AuditSortField,
+        descending: bool,
+        pagination: &PaginationParams,
+    ) -> anyhow::Result<AuditPage> {
+        if filter.sections.is_empty() {
+            return Ok(AuditPage {
+                entries: Vec::new(),
+                total: 0,
+            });
+        }
+
+        let mut params: HashMap<String, Value> = HashMap::new();
+        let sections: Vec<String> = filter
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(i, section)| {
+                params.insert(format!("section_{}", i), Value::String(section.clone()));
+                format!("{{{{section_{}}}}}", i)
+            })
+            .collect();
+        let mut conditions = vec![format!("section_id IN ({})", sections.join(", "))];
+        let mut condition = |param: &str, column: &str, operator: &str, value: String| {
+            conditions.push(format!("{} {} {{{{{}}}}}", column, operator, param));
+            params.insert(param.to_string(), Value::String(value));
+        };
+        if let Some(section_id) = &filter.section_id {
+            condition("section_id", "section_id", "=", section_id.clone());
+        }
+        if let Some(operation) = filter.operation {
+            condition(
+                "operation",
+                "operation",
+                "=",
+                operation.as_str().to_string(),
+            );
+        }
+        if let Some(record_id) = &filter.record_id {
+            condition("record_id", "record_id", "=", record_id.clone());
+        }
+        if let Some(user_id) = &filter.user_id {
+            condition("user_id", "user_id", "=", user_id.clone());
+        }
+        // Timestamps are stored as RFC 3339 text, which sorts chronologically
+        if let Some(from) = filter.from {
+            let from = from.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+            condition("from", "timestamp", ">=", from);
+        }
+        if let Some(to) = filter.to {
+            let to = to.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+            condition("to", "timestamp", "<=", to);
+        }
+        let conditions = conditions.join(" AND ");
+
+        let data_source = registry.create(config).await?;
+        let count = data_source
+            .execute_query(
+                &format!(
+                    "SELECT COUNT(*) AS total FROM {} WHERE {}",
+                    table, conditions
+                ),
+                Some(&params),
+            )
+            .await?;
+        let total = count
+            .first()
+            .and_then(|row| row.get("total"))
+            .and_then(|total| total.as_u64())
+            .unwrap_or(0) as usize;
+
+        let query = format!(
+            "SELECT * FROM {} WHERE {} ORDER BY {} {}, id",
+            table,
+            conditions,
+            sort.column(),
+            if descending { "DESC" } else { "ASC" }
+        );
+        debug!(query = %query, "Searching audit entries");
+        let entries = data_source
+            .execute_query_paginated(&query, Some(&params), Some(pagination))
+            .await?
+            .into_iter()
+            .filter_map(|row| match entry_from_row(row) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!(error = %e, "Skipping unreadable audit entry");
+                    None
+                }
+            })
+            .collect();
+
+        Ok(AuditPage { entries, total })
+    }
+
+    async fn read_elasticsearch(
+        config: &DataSourceConfig,
+        registry: &DataSourceRegistry,
+        filter: &AuditFilter,
+    ) -> anyhow::Result<Vec<AuditLogEntry>> {
+        let mut range = serde_json::Map::new();
+        if let Some(from) = filter.from {
+            range.insert("gte".to_string(), Value::String(from.to_rfc3339()));
+        }
+        if let Some(to) = filter.to {
+            range.insert("lte".to_string(), Value::String(to.to_rfc3339()));
+        }
+        let mut clauses = vec![serde_json::json!({"terms": {"section_id": filter.sections}})];
+        if !range.is_empty() {
+            clauses.push(serde_json::json!({"range": {"timestamp": range}}));
+        }
+        let query = serde_json::json!({
+            "query": {"bool": {"filter": clauses}},
+            "size": MAX_SCANNED_ENTRIES,
+        });
+
+        let rows = registry
+            .create(config)
+            .await?
+            .execute_query(&query.to_string(), None)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|mut row| {
+                row.remove("_id");
+                entry_from_row(row).ok()
+            })
+            .collect())
+    }
+
+    /// Entries stored as `<date>/<id>.json` objects, by day
+    async fn read_objects(
+        config: &DataSourceConfig,
+        registry: &DataSourceRegistry,
+        filter: &AuditFilter,
+    ) -> anyhow::Result<Vec<AuditLogEntry>> {
+        let data_source = registry.create(config).await?;
+        let key = |row: &HashMap<String, Value>| {
+            row.get("key")
+                .and_then(|k| k.as_str())
+                .map(|k| k.to_string())
+        };
+
+        let mut entries = Vec::new();
+        for folder in data_source.execute_query("", None).await? {
+            let Some(folder) = key(&folder) else {
+                continue;
+            };
+            let covered = NaiveDate::parse_from_str(folder.trim_end_matches('/'), "%Y-%m-%d")
+                .is_ok_and(|day| filter.covers_day(day));
+            if !covered {
+                continue;
+            }
+            for object in data_source.execute_query(&folder, None).await? {
+                if entries.len() >= MAX_SCANNED_ENTRIES {
+                    warn!("Audit search stopped after {} entries", MAX_SCANNED_ENTRIES);
+                    return Ok(entries);
+                }
+                let Some(object) = key(&object).filter(|k| k.ends_with(".json")) else {
+                    continue;
+                };
+                for row in data_source.execute_query(&object, None).await? {
+                    match entry_from_row(row) {
+                        Ok(entry) => entries.push(entry),
+                        Err(e) => {
+                            warn!(key = %object, error = %e, "Skipping unreadable audit entry")
+                        }
+                    }
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Parse an audit entry stored as a row, whose nested values may be JSON text
+fn entry_from_row(mut row: HashMap<String, Value>) -> anyhow::Result<AuditLogEntry> {
+    for (column, empty) in [
+        ("old_values", Value::Null),
+        ("new_values", Value::Null),
+        ("changes", Value::Array(Vec::new())),
+        ("metadata", Value::Object(serde_json::Map::new())),
+    ] {
+        let value = match row.remove(column) {
+            Some(Value::String(json)) => serde_json::from_str(&json)?,
+            Some(Value::Null) | None => empty,
+            Some(value) => value,
+        };
+        row.insert(column.to_string(), value);
+    }
+    Ok(serde_json::from_value(Value::Object(
+        row.into_iter().collect(),
+    ))?)
 }
 
 /// Compute changes between old and new data
-#[allow(dead_code)]
 fn compute_changes(
     old_data: &HashMap<String, Value>,
     new_data: &HashMap<String, Value>,
@@ -323,4 +1149,166 @@ mod tests {
         assert!(matches!(entry.operation, AuditOperation::Create));
         assert_eq!(entry.changes.len(), 1);
     }
+
+    #[test]
+    fn test_request_metadata() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "6.6.6.6, 1.2.3.4".parse().unwrap());
+        headers.append("x-forwarded-for", "10.0.0.1".parse().unwrap());
+        headers.insert(USER_AGENT, "curl/8.0".parse().unwrap());
+        headers.insert("x-request-id", "req-1".parse().unwrap());
+        let peer = Some("10.0.0.2".parse().unwrap());
+
+        // Without trusted proxies the header is ignored
+        let request = RequestMetadata::from_headers(&headers, peer, 0);
+        assert_eq!(request.client_ip.as_deref(), Some("10.0.0.2"));
+        let request = RequestMetadata::from_headers(&headers, peer, 2);
+        assert_eq!(request.client_ip.as_deref(), Some("1.2.3.4"));
+        assert_eq!(
+            RequestMetadata::from_headers(&headers, peer, 5)
+                .client_ip
+                .as_deref(),
+            Some("6.6.6.6")
+        );
+
+        let mut entry = AuditLogger::delete_entry("users".to_string(), "1".to_string(), None, None);
+        request.attach(&mut entry, "delete");
+        assert_eq!(entry.metadata["client_ip"], "1.2.3.4");
+        assert_eq!(entry.metadata["user_agent"], "curl/8.0");
+        assert_eq!(entry.metadata["request_id"], "req-1");
+        assert_eq!(entry.metadata["action_id"], "delete");
+    }
+
+    #[tokio::test]
+    async fn test_file_rotation_and_retention() {
+        let dir = std::env::temp_dir().join(format!("pmp-audit-{}", uuid::Uuid::new_v4()));
+        let logger = AuditLogger::new(&dir).with_max_file_bytes(Some(1));
+        let entry = || AuditLogger::delete_entry("users".to_string(), "1".to_string(), None, None);
+        for day in [
+            Utc::now() - chrono::Duration::days(1),
+            Utc::now() - chrono::Duration::days(400),
+        ] {
+            let mut old = entry();
+            old.timestamp = day;
+            let name = LogFile::name(day.date_naive(), 0);
+            std::fs::write(dir.join(name), serde_json::to_string(&old).unwrap()).unwrap();
+        }
+        logger.log(entry()).await.unwrap();
+        logger.log(entry()).await.unwrap();
+
+        let config = AuditMaintenanceConfig {
+            retention_days: Some(30),
+            compress: true,
+            ..Default::default()
+        };
+        logger.run_maintenance(&config).await.unwrap();
+
+        let mut files: Vec<LogFile> = AuditLogger::log_files(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|(_, file)| file)
+            .collect();
+        files.sort_by_key(|file| (file.day, file.part));
+        let today = Utc::now().date_naive();
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| (file.day == today, file.part, file.compressed))
+                .collect::<Vec<_>>(),
+            [(false, 0, true), (true, 0, true), (true, 1, false)]
+        );
+
+        let filter = AuditFilter {
+            sections: vec!["users".to_string()],
+            ..Default::default()
+        };
+        let page = logger
+            .search(
+                &filter,
+                AuditSortField::Timestamp,
+                true,
+                &PaginationParams::new(1, 10),
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.total, 3);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_data_source_storage() {
+        let dir = std::env::temp_dir().join(format!("pmp-audit-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let storage: AuditStorageConfig = serde_yaml::from_str(&format!(
+            r#"
+type: data_source
+table: audit_entries
+data_source:
+  type: database
+  connection_string: "sqlite://{}?mode=rwc"
+  db_type: sqlite
+"#,
+            dir.join("audit.db").display()
+        ))
+        .unwrap();
+        storage.validate().unwrap();
+
+        let registry = Arc::new(DataSourceRegistry::new());
+        let AuditStorageConfig::DataSource { data_source, .. } = &storage else {
+            unreachable!();
+        };
+        let db = registry.create(data_source).await.unwrap();
+        db.execute_mutation(
+            "CREATE TABLE audit_entries (id TEXT PRIMARY KEY, timestamp TEXT, operation TEXT, \
+             section_id TEXT, record_id TEXT, user_id TEXT, old_values TEXT, new_values TEXT, \
+             changes TEXT, metadata TEXT)",
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let logger = AuditLogger::from_config(Some(&storage), registry);
+        let data = HashMap::from([("name".to_string(), Value::String("Test".to_string()))]);
+        logger
+            .log(AuditLogger::create_entry(
+                "users".to_string(),
+                Some("123".to_string()),
+                &data,
+                Some("admin".to_string()),
+            ))
+            .await
+            .unwrap();
+
+        let rows = db
+            .execute_query(
+                "SELECT operation, user_id, new_values FROM audit_entries",
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["operation"], "create");
+        assert_eq!(rows[0]["user_id"], "admin");
+        assert_eq!(rows[0]["new_values"], r#"{"name":"Test"}"#);
+
+        let filter = AuditFilter {
+            sections: vec!["users".to_string()],
+            user_id: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let page = logger
+            .search(
+                &filter,
+                AuditSortField::Timestamp,
+                true,
+                &PaginationParams::new(1, 10),
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.entries[0].new_values.as_ref().unwrap()["name"], "Test");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,200 @@
+//! Caller identity extracted from the request's bearer token
+//!
+//! When security is enabled, `Authorization: Bearer <jwt>` is verified with the
+//! configured `jwt_secret` (HS256). Requests without a token are anonymous and
+//! hold no scopes. When security is disabled, every scope check passes.
+
+use crate::config::SecurityConfig;
+use crate::server::AppState;
+use anyhow::{anyhow, Result};
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Authenticated (or anonymous) caller of a request
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    /// `sub` claim of the token
+    pub user_id: Option<String>,
+    pub scopes: HashSet<String>,
+    /// All token claims, `null` for anonymous callers
+    pub claims: Value,
+    enforced: bool,
+}
+
+impl AuthContext {
+    /// Caller without a token
+    pub fn anonymous(enforced: bool) -> Self {
+        Self {
+            user_id: None,
+            scopes: HashSet::new(),
+            claims: Value::Null,
+            enforced,
+        }
+    }
+
+    /// Build the context from request headers
+    pub fn from_headers(headers: &HeaderMap, security: Option<&SecurityConfig>) -> Result<Self> {
+        let security = match security {
+            Some(security) if security.enabled => security,
+            _ => return Ok(Self::anonymous(false)),
+        };
+
+        let token = match headers
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+        {
+            Some(token) => token.trim(),
+            None => return Ok(Self::anonymous(true)),
+        };
+
+        let secret = security
+            .jwt_secret
+            .as_deref()
+            .ok_or_else(|| anyhow!("Security is enabled but no jwt_secret is configured"))?;
+
+        let claims = decode::<Value>(
+            token,
+            &DecodingKey::from_secret(secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .map_err(|e| anyhow!("Invalid token: {}", e))?
+        .claims;
+
+        Ok(Self::from_claims(claims))
+    }
+
+    /// Build an enforced context from already verified token claims
+    pub fn from_claims(claims: Value) -> Self {
+        let mut scopes = HashSet::new();
+        if let Some(scope) = claims.get("scope").and_then(|s| s.as_str()) {
+            scopes.extend(scope.split_whitespace().map(|s| s.to_string()));
+        }
+        for key in ["scopes", "scp"] {
+            if let Some(list) = claims.get(key).and_then(|s| s.as_array()) {
+                scopes.extend(
+                    list.iter()
+                        .filter_map(|s| s.as_str())
+                        .map(|s| s.to_string()),
+                );
+            }
+        }
+
+        let user_id = claims
+            .get("sub")
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string());
+
+        debug!(user_id = ?user_id, scopes = scopes.len(), "Authenticated request");
+
+        Self {
+            user_id,
+            scopes,
+            claims,
+            enforced: true,
+        }
+    }
+
+    /// Whether access checks apply to this caller (security is enabled)
+    pub fn is_enforced(&self) -> bool {
+        self.enforced
+    }
+
+    /// Whether the caller holds `scope`
+    pub fn has_scope(&self, scope: &str) -> bool {
+        !self.enforced || self.scopes.contains(scope)
+    }
+
+    /// Whether the caller holds every scope in `scopes`
+    pub fn has_all_scopes(&self, scopes: &[String]) -> bool {
+        scopes.iter().all(|s| self.has_scope(s))
+    }
+
+    /// Whether the caller holds at least one scope in `scopes`
+    pub fn has_any_scope(&self, scopes: &[String]) -> bool {
+        scopes.iter().any(|s| self.has_scope(s))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for AuthContext {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        AuthContext::from_headers(&parts.headers, state.config.security.as_ref()).map_err(|e| {
+            warn!(error = %e, "Rejected request with invalid credentials");
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    fn security() -> SecurityConfig {
+        SecurityConfig {
+            enabled: true,
+            jwt_secret: Some("secret".to_string()),
+            unauthorized_field_writes: Default::default(),
+            admin_scope: "admin".to_string(),
+        }
+    }
+
+    fn bearer(claims: Value, secret: &str) -> HeaderMap {
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_token_scopes_and_subject() {
+        let headers = bearer(
+            serde_json::json!({"sub": "u1", "scope": "read write", "exp": 4102444800u64}),
+            "secret",
+        );
+        let auth = AuthContext::from_headers(&headers, Some(&security())).unwrap();
+
+        assert_eq!(auth.user_id.as_deref(), Some("u1"));
+        assert!(auth.has_scope("read"));
+        assert!(!auth.has_scope("admin"));
+    }
+
+    #[test]
+    fn test_invalid_signature_is_rejected() {
+        let headers = bearer(serde_json::json!({"exp": 4102444800u64}), "other");
+        assert!(AuthContext::from_headers(&headers, Some(&security())).is_err());
+    }
+
+    #[test]
+    fn test_disabled_security_grants_all_scopes() {
+        let auth = AuthContext::from_headers(&HeaderMap::new(), None).unwrap();
+        assert!(auth.has_scope("admin"));
+
+        let auth = AuthContext::from_headers(&HeaderMap::new(), Some(&security())).unwrap();
+        assert!(!auth.has_scope("admin"));
+    }
+}
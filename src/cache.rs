@@ -0,0 +1,477 @@
+//! Query result caching
+//!
+//! List and view actions with a `cache` block or a `cache_ttl_secs` keep
+//! their data source rows for that TTL, keyed by the final query, request
+//! params and, for lists paginated by the data source, the page. Rows are cached before plugins, row filters, permissions and
+//! masking run, so every caller still gets their own view of them. Mutations
+//! and deletes drop every entry of their section, and a single action's
+//! entries can be dropped on demand.
+//!
+//! The memory backend is per process. The Redis backend stores entries in a
+//! Redis data source and invalidates by bumping a per-section generation
+//! number, so every instance sharing the Redis sees the invalidation.
+
+#[cfg(feature = "redis-datasource")]
+use crate::config::DataSourceConfig;
+use crate::config::{ActionCacheConfig, ActionConfig, BackofficeConfig, CacheBackend};
+use crate::data_source::{Page, PaginationParams};
+use anyhow::Result;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+type Rows = Vec<HashMap<String, Value>>;
+
+/// Memory entries beyond which expired ones are pruned on insert
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Cache of data source rows, shared by all requests
+#[derive(Default)]
+pub struct QueryCache {
+    entries: Mutex<HashMap<String, (Instant, Page)>>,
+}
+
+impl QueryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return cached rows for the action's query, or run `fetch` and cache
+    /// its rows when the action has caching enabled
+    ///
+    /// Cache backend failures are logged and fall through to `fetch`.
+    pub async fn fetch<F, Fut>(
+        &self,
+        backoffice: &BackofficeConfig,
+        section_id: &str,
+        action: &ActionConfig,
+        query: &str,
+        params: &HashMap<String, Value>,
+        fetch: F,
+    ) -> Result<Rows>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Rows>>,
+    {
+        let key = fingerprint(query, params);
+        let page = self
+            .cached(backoffice, section_id, action, key, || async {
+                Ok(Page {
+                    rows: fetch().await?,
+                    ..Default::default()
+                })
+            })
+            .await?;
+        Ok(page.rows)
+    }
+
+    /// Like [`QueryCache::fetch`], for one page of the action's query
+    #[allow(clippy::too_many_arguments)]
+    pub async fn fetch_page<F, Fut>(
+        &self,
+        backoffice: &BackofficeConfig,
+        section_id: &str,
+        action: &ActionConfig,
+        query: &str,
+        params: &HashMap<String, Value>,
+        pagination: &PaginationParams,
+        fetch: F,
+    ) -> Result<Page>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Page>>,
+    {
+        let key = format!(
+            "{}:{}:{}",
+            fingerprint(query, params),
+            pagination.page,
+            pagination.page_size
+        );
+        self.cached(backoffice, section_id, action, key, fetch)
+            .await
+    }
+
+    async fn cached<F, Fut>(
+        &self,
+        backoffice: &BackofficeConfig,
+        section_id: &str,
+        action: &ActionConfig,
+        fingerprint: String,
+        fetch: F,
+    ) -> Result<Page>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Page>>,
+    {
+        let Some(cache) = action.cache_config() else {
+            return fetch().await;
+        };
+        let ttl = Duration::from_secs(cache.ttl_secs);
+        let key = format!(
+            "{}:{}:{}:{}",
+            backoffice.id, section_id, action.id, fingerprint
+        );
+
+        match cache.backend {
+            CacheBackend::Memory => {
+                if let Some(page) = self.memory_get(&key) {
+                    debug!(key = %key, "Query cache hit");
+                    return Ok(page);
+                }
+                let page = fetch().await?;
+                self.memory_put(key, ttl, page.clone());
+                Ok(page)
+            }
+            CacheBackend::Redis => {
+                let redis = RedisCache::new(backoffice, &cache, section_id);
+                match redis.get(&key).await {
+                    Ok(Some(page)) => {
+                        debug!(key = %key, "Query cache hit");
+                        return Ok(page);
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!(error = %e, "Redis query cache read failed"),
+                }
+                let page = fetch().await?;
+                if let Err(e) = redis.put(&key, ttl, &page).await {
+                    warn!(error = %e, "Redis query cache write failed");
+                }
+                Ok(page)
+            }
+        }
+    }
+
+    /// Drop every cached result of a section
+    pub async fn invalidate_section(&self, backoffice: &BackofficeConfig, section_id: &str) {
+        let prefix = format!("{}:{}:", backoffice.id, section_id);
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(&prefix));
+
+        let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+            return;
+        };
+        // Bump the generation once per Redis data source
+        let mut invalidated: Vec<Option<String>> = Vec::new();
+        for action in &section.actions {
+            let Some(cache) = action
+                .cache_config()
+                .filter(|c| c.backend == CacheBackend::Redis)
+            else {
+                continue;
+            };
+            if invalidated.contains(&cache.data_source) {
+                continue;
+            }
+            if let Err(e) = RedisCache::new(backoffice, &cache, section_id)
+                .invalidate()
+                .await
+            {
+                warn!(error = %e, section = %section_id, "Redis query cache invalidation failed");
+            }
+            invalidated.push(cache.data_source);
+        }
+        debug!(section = %section_id, "Invalidated query cache");
+    }
+
+    /// Drop every cached result of an action
+    ///
+    /// Redis entries are versioned per section, so for the redis backend
+    /// this drops the entries of the action's whole section.
+    pub async fn invalidate_action(
+        &self,
+        backoffice: &BackofficeConfig,
+        section_id: &str,
+        action: &ActionConfig,
+    ) -> Result<()> {
+        let prefix = format!("{}:{}:{}:", backoffice.id, section_id, action.id);
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(&prefix));
+
+        if let Some(cache) = action
+            .cache_config()
+            .filter(|c| c.backend == CacheBackend::Redis)
+        {
+            RedisCache::new(backoffice, &cache, section_id)
+                .invalidate()
+                .await?;
+        }
+        debug!(section = %section_id, action = %action.id, "Invalidated action cache");
+        Ok(())
+    }
+
+    fn memory_get(&self, key: &str) -> Option<Page> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((expires, page)) if *expires > Instant::now() => Some(page.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn memory_put(&self, key: String, ttl: Duration, page: Page) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= PRUNE_THRESHOLD {
+            let now = Instant::now();
+            entries.retain(|_, (expires, _)| *expires > now);
+        }
+        entries.insert(key, (Instant::now() + ttl, page));
+    }
+}
+
+/// Stable hash of a query and its params, shared by every instance
+fn fingerprint(query: &str, params: &HashMap<String, Value>) -> String {
+    let params: BTreeMap<_, _> = params.iter().collect();
+    let mut hasher = Sha256::new();
+    hasher.update(query.as_bytes());
+    hasher.update([0]);
+    hasher.update(serde_json::to_vec(&params).unwrap_or_default());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Cache entries stored in a Redis data source
+struct RedisCache {
+    #[cfg(feature = "redis-datasource")]
+    connection_string: Option<String>,
+    /// Key holding the section's generation number
+    #[cfg_attr(not(feature = "redis-datasource"), allow(dead_code))]
+    generation_key: String,
+}
+
+impl RedisCache {
+    fn new(backoffice: &BackofficeConfig, cache: &ActionCacheConfig, section_id: &str) -> Self {
+        #[cfg(feature = "redis-datasource")]
+        let connection_string = cache
+            .data_source
+            .as_ref()
+            .and_then(|name| backoffice.data_sources.get(name))
+            .and_then(|config| match config {
+                DataSourceConfig::Redis {
+                    connection_string, ..
+                } => Some(connection_string.clone()),
+                _ => None,
+            });
+        #[cfg(not(feature = "redis-datasource"))]
+        let _ = cache;
+
+        Self {
+            #[cfg(feature = "redis-datasource")]
+            connection_string,
+            generation_key: format!("pmp:cache:{}:{}:generation", backoffice.id, section_id),
+        }
+    }
+}
+
+#[cfg(feature = "redis-datasource")]
+impl RedisCache {
+    async fn connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        let connection_string = self.connection_string.as_deref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "The redis cache backend needs `data_source` naming a Redis data source"
+            )
+        })?;
+        let client = redis::Client::open(connection_string)?;
+        Ok(client.get_multiplexed_async_connection().await?)
+    }
+
+    async fn versioned_key(
+        &self,
+        con: &mut redis::aio::MultiplexedConnection,
+        key: &str,
+    ) -> Result<String> {
+        use redis::AsyncCommands;
+
+        let generation: Option<u64> = con.get(&self.generation_key).await?;
+        Ok(format!("pmp:cache:{}:{}", generation.unwrap_or(0), key))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Page>> {
+        use redis::AsyncCommands;
+
+        let mut con = self.connection().await?;
+        let key = self.versioned_key(&mut con, key).await?;
+        let cached: Option<String> = con.get(&key).await?;
+        Ok(cached.map(|json| serde_json::from_str(&json)).transpose()?)
+    }
+
+    async fn put(&self, key: &str, ttl: Duration, page: &Page) -> Result<()> {
+        use redis::AsyncCommands;
+
+        let mut con = self.connection().await?;
+        let key = self.versioned_key(&mut con, key).await?;
+        con.set_ex::<_, _, ()>(&key, serde_json::to_string(page)?, ttl.as_secs().max(1))
+            .await?;
+        Ok(())
+    }
+
+    async fn invalidate(&self) -> Result<()> {
+        use redis::AsyncCommands;
+
+        let mut con = self.connection().await?;
+        con.incr::<_, _, ()>(&self.generation_key, 1).await?;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "redis-datasource"))]
+impl RedisCache {
+    async fn get(&self, _key: &str) -> Result<Option<Page>> {
+        Err(anyhow::anyhow!(
+            "The redis cache backend requires the redis-datasource feature"
+        ))
+    }
+
+    async fn put(&self, _key: &str, _ttl: Duration, _page: &Page) -> Result<()> {
+        self.get("").await.map(|_| ())
+    }
+
+    async fn invalidate(&self) -> Result<()> {
+        self.get("").await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_memory_cache_hits_and_invalidates() {
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: shop
+name: Shop
+data_sources: {}
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        query: SELECT * FROM orders
+        required_scopes: []
+        cache: { ttl_secs: 60 }
+        fields: []
+"#,
+        )
+        .unwrap();
+        let action = &backoffice.sections[0].actions[0];
+        let cache = QueryCache::new();
+        let calls = AtomicUsize::new(0);
+        let fetch = |params: HashMap<String, Value>| {
+            let (cache, calls) = (&cache, &calls);
+            let backoffice = &backoffice;
+            async move {
+                cache
+                    .fetch(backoffice, "orders", action, "SELECT", &params, || async {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        Ok(vec![HashMap::from([("id".to_string(), json!(1))])])
+                    })
+                    .await
+                    .unwrap()
+            }
+        };
+        let params = |status: &str| HashMap::from([("status".to_string(), json!(status))]);
+
+        assert_eq!(fetch(params("open")).await.len(), 1);
+        fetch(params("open")).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Different params are a different entry
+        fetch(params("closed")).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        cache.invalidate_section(&backoffice, "orders").await;
+        fetch(params("open")).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_action_ttl_and_invalidation() {
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: shop
+name: Shop
+data_sources: {}
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        query: SELECT * FROM orders
+        required_scopes: []
+        cache: { ttl_secs: 60 }
+        cache_ttl_secs: 5
+        fields: []
+      - id: revenue
+        name: Revenue
+        type: view
+        data_source: db
+        query: SELECT SUM(total) AS total FROM orders
+        required_scopes: []
+        cache_ttl_secs: 3600
+        fields: []
+"#,
+        )
+        .unwrap();
+        let actions = &backoffice.sections[0].actions;
+        assert_eq!(actions[0].cache_config().unwrap().ttl_secs, 5);
+        assert_eq!(
+            actions[1].cache_config().unwrap().backend,
+            CacheBackend::Memory
+        );
+
+        let cache = QueryCache::new();
+        let calls = AtomicUsize::new(0);
+        let fetch = |action: &ActionConfig| {
+            let (cache, calls, backoffice) = (&cache, &calls, &backoffice);
+            let action = action.clone();
+            async move {
+                cache
+                    .fetch(
+                        backoffice,
+                        "orders",
+                        &action,
+                        "SELECT",
+                        &HashMap::new(),
+                        || async {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            Ok(vec![])
+                        },
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+
+        fetch(&actions[0]).await;
+        fetch(&actions[1]).await;
+        fetch(&actions[1]).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Only the invalidated action's entries are dropped
+        cache
+            .invalidate_action(&backoffice, "orders", &actions[1])
+            .await
+            .unwrap();
+        fetch(&actions[0]).await;
+        fetch(&actions[1]).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,325 @@
+//! Data source circuit breakers
+//!
+//! Every data source created by the registry shares a breaker with the other
+//! instances of the same configuration. After `failure_threshold` consecutive
+//! outages the circuit opens and calls fail fast with [`CircuitOpenError`]
+//! (served as 503) instead of waiting for timeouts. Once `open_secs` pass,
+//! up to `half_open_probes` calls are let through: a success closes the
+//! circuit, a failure opens it again.
+//!
+//! Only outages count as failures. Errors reported by a reachable server,
+//! such as SQL errors or JSON-RPC error objects, leave the circuit closed.
+
+use crate::config::{CircuitBreakerConfig, DataSourceConfig};
+use crate::data_source::{
+    self, DataSource, JsonRpcError, Mutation, Page, PaginationParams, PresignMethod, PresignedUrl,
+    RowStream,
+};
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Returned while a data source's circuit is open
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Data source unavailable after repeated failures; retry in {}s", .retry_after.as_secs())]
+pub struct CircuitOpenError {
+    pub retry_after: Duration,
+}
+
+/// Breakers of every data source configuration seen so far
+pub struct CircuitBreakers {
+    config: CircuitBreakerConfig,
+    breakers: Mutex<HashMap<String, Arc<Breaker>>>,
+}
+
+impl CircuitBreakers {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The breaker shared by every data source created from `config`
+    pub(crate) fn breaker(&self, config: &DataSourceConfig) -> Arc<Breaker> {
+        let key = data_source::config_key(config);
+        self.breakers
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(Breaker::new(
+                    self.config.failure_threshold,
+                    Duration::from_secs(self.config.open_secs),
+                    self.config.half_open_probes,
+                ))
+            })
+            .clone()
+    }
+
+    /// `closed`, `open` or `half_open`
+    pub(crate) fn circuit_state(&self, config: &DataSourceConfig) -> &'static str {
+        let key = data_source::config_key(config);
+        match self.breakers.lock().unwrap().get(&key) {
+            Some(breaker) => breaker.circuit_state(),
+            None => "closed",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// Half-open probes in flight
+    probes: u32,
+}
+
+/// Failure tracking of one data source
+pub(crate) struct Breaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    half_open_probes: u32,
+    state: Mutex<BreakerState>,
+}
+
+/// Permission to make one call; a half-open probe slot is released on drop
+pub(crate) struct Permit<'a> {
+    breaker: &'a Breaker,
+    probe: bool,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            let mut state = self.breaker.state.lock().unwrap();
+            state.probes = state.probes.saturating_sub(1);
+        }
+    }
+}
+
+impl Breaker {
+    fn new(failure_threshold: u32, open_duration: Duration, half_open_probes: u32) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            half_open_probes: half_open_probes.max(1),
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    fn circuit_state(&self) -> &'static str {
+        match self.state.lock().unwrap().opened_at {
+            None => "closed",
+            Some(opened_at) if opened_at.elapsed() < self.open_duration => "open",
+            Some(_) => "half_open",
+        }
+    }
+
+    /// Admit a call, or fail fast while the circuit is open
+    pub(crate) fn acquire(&self) -> Result<Permit<'_>, CircuitOpenError> {
+        let mut state = self.state.lock().unwrap();
+        let Some(opened_at) = state.opened_at else {
+            return Ok(Permit {
+                breaker: self,
+                probe: false,
+            });
+        };
+
+        let elapsed = opened_at.elapsed();
+        if elapsed < self.open_duration || state.probes >= self.half_open_probes {
+            let remaining = self.open_duration.saturating_sub(elapsed);
+            return Err(CircuitOpenError {
+                retry_after: Duration::from_secs(remaining.as_secs_f64().ceil().max(1.0) as u64),
+            });
+        }
+        state.probes += 1;
+        Ok(Permit {
+            breaker: self,
+            probe: true,
+        })
+    }
+
+    /// Record the outcome of an admitted call
+    pub(crate) fn record(&self, permit: Permit<'_>, outage: bool) {
+        let mut state = self.state.lock().unwrap();
+        if !outage {
+            if permit.probe {
+                info!("Data source recovered, closing circuit");
+            }
+            state.consecutive_failures = 0;
+            state.opened_at = None;
+            return;
+        }
+
+        state.consecutive_failures += 1;
+        if permit.probe || state.consecutive_failures >= self.failure_threshold {
+            if state.opened_at.is_none() || permit.probe {
+                warn!(
+                    failures = state.consecutive_failures,
+                    open_secs = self.open_duration.as_secs(),
+                    "Data source failing, opening circuit"
+                );
+            }
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Run a data source call through the breaker
+    async fn call<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        let permit = self.acquire()?;
+        let result = call.await;
+        self.record(permit, result.as_ref().is_err_and(is_outage));
+        result
+    }
+}
+
+/// Whether an error means the data source could not be reached
+pub(crate) fn is_outage(e: &anyhow::Error) -> bool {
+    if e.is::<JsonRpcError>() || e.is::<CircuitOpenError>() {
+        return false;
+    }
+    if let Some(e) = e.downcast_ref::<sqlx::Error>() {
+        return matches!(
+            e,
+            sqlx::Error::Io(_)
+                | sqlx::Error::Tls(_)
+                | sqlx::Error::PoolTimedOut
+                | sqlx::Error::PoolClosed
+                | sqlx::Error::WorkerCrashed
+        );
+    }
+    true
+}
+
+/// A data source whose calls go through its breaker
+pub(crate) struct CircuitBreakerDataSource {
+    pub(crate) inner: Box<dyn DataSource>,
+    pub(crate) breaker: Arc<Breaker>,
+}
+
+#[async_trait::async_trait]
+impl DataSource for CircuitBreakerDataSource {
+    async fn execute_query(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.breaker
+            .call(self.inner.execute_query(query, params))
+            .await
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.breaker
+            .call(
+                self.inner
+                    .execute_query_paginated(query, params, pagination),
+            )
+            .await
+    }
+
+    async fn execute_page(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: &PaginationParams,
+    ) -> Result<Page> {
+        self.breaker
+            .call(self.inner.execute_page(query, params, pagination))
+            .await
+    }
+
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        self.breaker
+            .call(self.inner.execute_mutation(query, data))
+            .await
+    }
+
+    async fn execute_delete(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        self.breaker
+            .call(self.inner.execute_delete(query, data))
+            .await
+    }
+
+    async fn subscribe(&self, query: &str) -> Result<RowStream> {
+        // Unsupported subscriptions are configuration errors, not outages;
+        // creating the source already went through the breaker
+        self.breaker.acquire()?;
+        self.inner.subscribe(query).await
+    }
+
+    async fn presign(
+        &self,
+        key: &str,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedUrl> {
+        // Signing happens locally, without calling the data source
+        self.inner.presign(key, method, expires_in).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.inner.health_check().await
+    }
+
+    fn supports_transactions(&self) -> bool {
+        self.inner.supports_transactions()
+    }
+
+    async fn execute_transaction(&self, mutations: &[Mutation]) -> Result<Vec<Value>> {
+        self.breaker
+            .call(self.inner.execute_transaction(mutations))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[tokio::test]
+    async fn test_breaker_opens_and_recovers() {
+        let breaker = Breaker::new(2, Duration::from_millis(50), 1);
+        let outage = || async { Err::<(), _>(anyhow!("connection refused")) };
+
+        assert!(breaker.call(outage()).await.is_err());
+        // A server-reported error is not an outage and resets the count
+        let rpc = JsonRpcError {
+            code: -32000,
+            message: "bad params".to_string(),
+            data: None,
+        };
+        assert!(breaker
+            .call(async { Err::<(), _>(rpc.into()) })
+            .await
+            .is_err());
+        assert!(breaker.acquire().is_ok());
+
+        assert!(breaker.call(outage()).await.is_err());
+        assert!(breaker.call(outage()).await.is_err());
+        let err = breaker.call(async { Ok(()) }).await.unwrap_err();
+        assert!(err.is::<CircuitOpenError>());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        // Only one half-open probe at a time
+        let probe = breaker.acquire().unwrap();
+        assert!(breaker.acquire().is_err());
+        breaker.record(probe, true);
+        assert!(breaker.acquire().is_err());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(breaker.call(async { Ok(()) }).await.is_ok());
+        assert!(breaker.acquire().is_ok());
+    }
+}
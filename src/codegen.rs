@@ -0,0 +1,340 @@
+//! TypeScript client generation
+//!
+//! `pmp-backoffice-generator codegen ts --out FILE` writes an interface per
+//! section's records, the request and response types of every action and a
+//! thin `fetch` client with a typed method per action, from the same field
+//! schemas as the OpenAPI export.
+
+use crate::config::{ActionType, BackofficeConfig, FieldConfig};
+use crate::openapi::{field_schema, record_fields, type_name};
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Types and client shared by every backoffice
+const PRELUDE: &str = r#"// Generated by `pmp-backoffice-generator codegen ts`. Do not edit.
+
+export type QueryParams = Record<string, string | number | boolean | undefined>;
+
+export interface Pagination {
+  page: number;
+  page_size: number;
+  total_items: number | null;
+  total_pages: number | null;
+  next_cursor?: string;
+}
+
+export interface ListResponse<T> {
+  data: T[];
+  pagination: Pagination | null;
+}
+
+export interface RowsResponse<T> {
+  data: T[];
+}
+
+export interface ValidationError {
+  field: string;
+  message: string;
+  code?: string;
+  params?: Record<string, unknown>;
+}
+
+export interface MutationResponse {
+  success: boolean;
+  data?: Record<string, unknown>;
+  warnings?: ValidationError[];
+  nested?: Record<string, number>;
+  steps?: Record<string, unknown[]>;
+}
+
+export class ApiError extends Error {
+  constructor(
+    public readonly status: number,
+    public readonly body: unknown,
+  ) {
+    super(`Request failed with status ${status}`);
+  }
+}
+
+export interface ClientOptions {
+  /** Server URL, e.g. `http://localhost:3000` */
+  baseUrl: string;
+  /** Bearer token sent with every request */
+  token?: string;
+  fetch?: typeof fetch;
+}
+
+async function request<T>(
+  options: ClientOptions,
+  method: string,
+  path: string,
+  params?: object,
+  body?: unknown,
+): Promise<T> {
+  const url = new URL(path, options.baseUrl);
+  for (const [name, value] of Object.entries(params ?? {})) {
+    if (value !== undefined && value !== null) {
+      url.searchParams.set(name, String(value));
+    }
+  }
+  const headers: Record<string, string> = { Accept: "application/json" };
+  if (body !== undefined) {
+    headers["Content-Type"] = "application/json";
+  }
+  if (options.token) {
+    headers["Authorization"] = `Bearer ${options.token}`;
+  }
+  const response = await (options.fetch ?? fetch)(url, {
+    method,
+    headers,
+    body: body === undefined ? undefined : JSON.stringify(body),
+  });
+  const text = await response.text();
+  const json = text ? JSON.parse(text) : undefined;
+  if (!response.ok) {
+    throw new ApiError(response.status, json);
+  }
+  return json as T;
+}
+"#;
+
+/// TypeScript type of a JSON schema
+fn ts_type(schema: &Value) -> String {
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.is_empty() {
+            return values
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(" | ");
+        }
+    }
+    match schema["type"].as_str() {
+        Some("integer") | Some("number") => "number".to_string(),
+        Some("boolean") => "boolean".to_string(),
+        Some("string") => "string".to_string(),
+        Some("array") => {
+            let items = ts_type(&schema["items"]);
+            if items.contains(' ') {
+                format!("({})[]", items)
+            } else {
+                format!("{}[]", items)
+            }
+        }
+        Some("object") => match schema["properties"].as_object() {
+            Some(properties) if !properties.is_empty() => {
+                let members: Vec<String> = properties
+                    .iter()
+                    .map(|(name, schema)| format!("{}: {}", property(name), ts_type(schema)))
+                    .collect();
+                format!("{{ {} }}", members.join("; "))
+            }
+            _ => "Record<string, unknown>".to_string(),
+        },
+        _ => "unknown".to_string(),
+    }
+}
+
+/// A property name, quoted unless it is an identifier
+fn property(name: &str) -> String {
+    let identifier = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if identifier {
+        name.to_string()
+    } else {
+        Value::from(name).to_string()
+    }
+}
+
+/// A client member name for an id, e.g. `orderItems` for `order_items`
+fn member(id: &str) -> String {
+    let name = type_name(id);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_digit() => format!("_{}", name),
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => "_".to_string(),
+    }
+}
+
+/// An interface with a member per field; only required fields are mandatory,
+/// and only editable ones are part of inputs
+fn interface(out: &mut String, name: &str, doc: &str, fields: &[&FieldConfig], input: bool) {
+    let _ = writeln!(out, "\n/** {} */\nexport interface {} {{", doc, name);
+    for field in fields {
+        if input && !field.editable {
+            continue;
+        }
+        let _ = writeln!(out, "  /** {} */", field.name);
+        let ty = ts_type(&field_schema(field));
+        if field.required {
+            let _ = writeln!(out, "  {}: {};", property(&field.id), ty);
+        } else if ty == "unknown" {
+            let _ = writeln!(out, "  {}?: unknown;", property(&field.id));
+        } else {
+            let _ = writeln!(out, "  {}?: {} | null;", property(&field.id), ty);
+        }
+    }
+    out.push_str("}\n");
+}
+
+/// TypeScript types and client of the backoffices
+pub fn typescript(backoffices: &[BackofficeConfig]) -> String {
+    let mut types = String::new();
+    let mut client = String::from(
+        "\nexport class BackofficeClient {\n  constructor(private readonly options: ClientOptions) {}\n",
+    );
+
+    for backoffice in backoffices {
+        let _ = writeln!(client, "\n  /** {} */", backoffice.name);
+        let _ = writeln!(client, "  readonly {} = {{", member(&backoffice.id));
+        for section in &backoffice.sections {
+            let prefix = format!("{}{}", type_name(&backoffice.id), type_name(&section.id));
+            let records = record_fields(&section.actions);
+            let record = if records.is_empty() {
+                "Record<string, unknown>".to_string()
+            } else {
+                interface(
+                    &mut types,
+                    &prefix,
+                    &format!("{} / {} record", backoffice.name, section.name),
+                    &records,
+                    false,
+                );
+                prefix.clone()
+            };
+
+            let _ = writeln!(client, "    {}: {{", member(&section.id));
+            for action in &section.actions {
+                let name = format!("{}{}", prefix, type_name(&action.id));
+                let path = format!(
+                    "/api/backoffices/{}/sections/{}/actions/{}",
+                    backoffice.id, section.id, action.id
+                );
+                let doc = format!("{} / {}: {}", backoffice.name, section.name, action.name);
+                let (response, method) = match &action.action_type {
+                    ActionType::List { .. } => (format!("ListResponse<{}>", record), "GET"),
+                    ActionType::View { .. } | ActionType::Custom { .. } => {
+                        (format!("RowsResponse<{}>", record), "GET")
+                    }
+                    ActionType::Summary { .. } | ActionType::Report { .. } => {
+                        ("RowsResponse<Record<string, unknown>>".to_string(), "GET")
+                    }
+                    ActionType::Form { fields, .. } | ActionType::Pipeline { fields, .. } => {
+                        let fields: Vec<&FieldConfig> = fields.iter().collect();
+                        interface(
+                            &mut types,
+                            &format!("{}Input", name),
+                            &format!("Input of {}", doc),
+                            &fields,
+                            true,
+                        );
+                        ("MutationResponse".to_string(), "POST")
+                    }
+                };
+                let _ = writeln!(types, "\nexport type {}Response = {};", name, response);
+
+                let call = if method == "POST" {
+                    format!(
+                        "(input: {0}Input): Promise<{0}Response> =>\n        request(this.options, \"POST\", \"{1}\", undefined, input)",
+                        name, path
+                    )
+                } else {
+                    let params = match &action.action_type {
+                        ActionType::Report { parameters, .. } if !parameters.is_empty() => {
+                            let parameters: Vec<&FieldConfig> = parameters.iter().collect();
+                            interface(
+                                &mut types,
+                                &format!("{}Params", name),
+                                &format!("Parameters of {}", doc),
+                                &parameters,
+                                true,
+                            );
+                            format!("params: {}Params", name)
+                        }
+                        _ => "params?: QueryParams".to_string(),
+                    };
+                    format!(
+                        "({}): Promise<{}Response> =>\n        request(this.options, \"GET\", \"{}\", params)",
+                        params, name, path
+                    )
+                };
+                let _ = writeln!(client, "      /** {} */", action.name);
+                let _ = writeln!(client, "      {}: {},", member(&action.id), call);
+            }
+            client.push_str("    },\n");
+        }
+        client.push_str("  };\n");
+    }
+    client.push_str("}\n");
+
+    format!("{}{}{}", PRELUDE, types, client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typescript() {
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: shop
+name: Shop
+data_sources: {}
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        required_scopes: []
+        fields:
+          - { id: id, name: Id, field_type: number, required: true }
+          - { id: status, name: Status, field_type: select, config: { options: [{ value: open, label: Open }, { value: paid, label: Paid }] } }
+          - { id: tags, name: Tags, field_type: tags }
+          - { id: ship-to, name: Ship to, field_type: geolocation }
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        required_scopes: []
+        fields:
+          - { id: sku, name: SKU, field_type: text, required: true }
+          - { id: id, name: Id, field_type: number, editable: false }
+      - id: sales
+        name: Sales
+        type: report
+        data_source: db
+        required_scopes: []
+        parameters:
+          - { id: from, name: From, field_type: date, required: true }
+"#,
+        )
+        .unwrap();
+        let ts = typescript(&[backoffice]);
+        assert_eq!(member("field-showcase"), "fieldShowcase");
+        assert_eq!(member("2fa"), "_2fa");
+
+        assert!(ts.contains(
+            "export interface ShopOrders {\n  /** Id */\n  id: number;\n  /** Status */\n  status?: \"open\" | \"paid\" | null;\n  /** Tags */\n  tags?: string[] | null;\n  /** Ship to */\n  \"ship-to\"?: { lat: number; lng: number } | null;\n}"
+        ));
+        assert!(ts.contains("export type ShopOrdersListResponse = ListResponse<ShopOrders>;"));
+        assert!(ts
+            .contains("export interface ShopOrdersCreateInput {\n  /** SKU */\n  sku: string;\n}"));
+        assert!(ts.contains("export type ShopOrdersCreateResponse = MutationResponse;"));
+        assert!(ts.contains(
+            "export interface ShopOrdersSalesParams {\n  /** From */\n  from: string;\n}"
+        ));
+        assert!(ts.contains("readonly shop = {\n    orders: {\n      /** List */\n      list: (params?: QueryParams): Promise<ShopOrdersListResponse> =>\n        request(this.options, \"GET\", \"/api/backoffices/shop/sections/orders/actions/list\", params),"));
+        assert!(ts.contains("create: (input: ShopOrdersCreateInput): Promise<ShopOrdersCreateResponse> =>\n        request(this.options, \"POST\", \"/api/backoffices/shop/sections/orders/actions/create\", undefined, input),"));
+        assert!(ts.contains("sales: (params: ShopOrdersSalesParams)"));
+    }
+}
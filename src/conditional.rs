@@ -0,0 +1,178 @@
+//! Conditional GET
+//!
+//! Successful action responses carry an `ETag` hashed from the body and,
+//! when the section declares `updated_at_field`, a `Last-Modified` taken from
+//! the newest row. Requests repeating a validator in `If-None-Match` or
+//! `If-Modified-Since` get an empty `304 Not Modified`, which saves bandwidth
+//! for polling frontends.
+
+use axum::body::{to_bytes, Body};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::{debug, error};
+
+/// Add validators to a response, or replace it with `304 Not Modified` when
+/// the request's validators still match
+pub async fn respond(
+    request_headers: &HeaderMap,
+    response: Response,
+    updated_at_field: Option<&str>,
+) -> Response {
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!(error = %e, "Failed to buffer response body");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let etag = entity_tag(&bytes);
+    let last_modified = updated_at_field.and_then(|field| {
+        let body: Value = serde_json::from_slice(&bytes).ok()?;
+        last_modified(&body, field)
+    });
+
+    let not_modified = match header_str(request_headers, header::IF_NONE_MATCH) {
+        Some(if_none_match) => etag_matches(if_none_match, &etag),
+        None => match (
+            header_str(request_headers, header::IF_MODIFIED_SINCE)
+                .and_then(|since| DateTime::parse_from_rfc2822(since).ok()),
+            last_modified,
+        ) {
+            // HTTP dates have whole seconds
+            (Some(since), Some(modified)) => modified.timestamp() <= since.timestamp(),
+            _ => false,
+        },
+    };
+
+    let mut validators = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        validators.insert(header::ETAG, value);
+    }
+    if let Some(modified) = last_modified {
+        let http_date = modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        if let Ok(value) = HeaderValue::from_str(&http_date) {
+            validators.insert(header::LAST_MODIFIED, value);
+        }
+    }
+
+    if not_modified {
+        debug!(etag = %etag, "Response not modified");
+        return (StatusCode::NOT_MODIFIED, validators).into_response();
+    }
+
+    parts.headers.extend(validators);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Strong entity tag of a response body
+fn entity_tag(body: &[u8]) -> String {
+    let digest = format!("{:x}", Sha256::digest(body));
+    format!("\"{}\"", &digest[..32])
+}
+
+/// Whether an `If-None-Match` list contains the tag, compared weakly
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// The newest `field` value among the response's rows
+fn last_modified(body: &Value, field: &str) -> Option<DateTime<Utc>> {
+    body.get("data")?
+        .as_array()?
+        .iter()
+        .filter_map(|row| parse_timestamp(row.get(field)?))
+        .max()
+}
+
+/// Parse RFC 3339 or zone-less timestamps (taken as UTC), or Unix times in
+/// seconds or milliseconds
+fn parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .map(|t| t.with_timezone(&Utc))
+            .ok()
+            .or_else(|| {
+                ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+                    .iter()
+                    .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+                    .map(|t| t.and_utc())
+            }),
+        Value::Number(n) => {
+            let n = n.as_i64()?;
+            if n.abs() < 100_000_000_000 {
+                Utc.timestamp_opt(n, 0).single()
+            } else {
+                Utc.timestamp_millis_opt(n).single()
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ok_response() -> Response {
+        axum::Json(json!({"data": [
+            {"id": 1, "updated_at": "2024-03-01T10:00:00Z"},
+            {"id": 2, "updated_at": "2024-03-02 08:30:00"},
+        ]}))
+        .into_response()
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get() {
+        let response = respond(&HeaderMap::new(), ok_response(), Some("updated_at")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG].clone();
+        assert_eq!(
+            response.headers()[header::LAST_MODIFIED],
+            "Sat, 02 Mar 2024 08:30:00 GMT"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag.clone());
+        let response = respond(&headers, ok_response(), Some("updated_at")).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"stale\""));
+        let response = respond(&headers, ok_response(), None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Sat, 02 Mar 2024 08:30:00 GMT"),
+        );
+        let response = respond(&headers, ok_response(), Some("updated_at")).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Fri, 01 Mar 2024 00:00:00 GMT"),
+        );
+        let response = respond(&headers, ok_response(), Some("updated_at")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
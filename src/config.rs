@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 use walkdir::WalkDir;
 
@@ -10,18 +10,509 @@ use walkdir::WalkDir;
 pub struct AppConfig {
     pub server: ServerConfig,
     pub security: Option<SecurityConfig>,
+    /// Where audit entries are written; daily files under `logs/audit` by default
+    #[serde(default)]
+    pub audit_storage: Option<AuditStorageConfig>,
+    /// Retention and rotation of audit entries
+    #[serde(default)]
+    pub audit: AuditMaintenanceConfig,
+    /// Validation messages by locale
+    #[serde(default)]
+    pub i18n: I18nConfig,
+    /// Virus scanning of files uploaded to File and Image fields
+    #[serde(default)]
+    pub antivirus: Option<AntivirusConfig>,
+    /// Real-time events over the `/api/ws` WebSocket
+    #[serde(default)]
+    pub push: PushConfig,
+    /// gRPC API on its own port; not served when unset
+    #[serde(default)]
+    pub grpc: Option<GrpcConfig>,
+    /// Version history and rollback of backoffice config files
+    #[serde(default)]
+    pub config_history: ConfigHistoryConfig,
+    /// Remote store backoffice configs are pulled from, instead of
+    /// `config/backoffices`
+    #[serde(default)]
+    pub config_store: Option<ConfigStoreConfig>,
+    /// Flags that sections and actions name in `enabled_when`
+    #[serde(default)]
+    pub feature_flags: FeatureFlagsConfig,
+}
+
+/// Where the gRPC API listens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    /// Defaults to `server.host`
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default = "default_grpc_port")]
+    pub port: u16,
+}
+
+fn default_grpc_port() -> u16 {
+    50051
+}
+
+/// Where versions of backoffice config files are kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigHistoryConfig {
+    /// Off by default, as versions hold data source credentials
+    #[serde(default)]
+    pub enabled: bool,
+    /// Directory of the history files, one per config file
+    #[serde(default = "default_config_history_path")]
+    pub path: String,
+    /// Versions kept per config file; the oldest are dropped
+    #[serde(default = "default_config_history_max_versions")]
+    pub max_versions: usize,
+    /// Scope needed to read the history and roll back
+    #[serde(default = "default_config_history_scope")]
+    pub scope: String,
+}
+
+impl Default for ConfigHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_config_history_path(),
+            max_versions: default_config_history_max_versions(),
+            scope: default_config_history_scope(),
+        }
+    }
+}
+
+fn default_config_history_path() -> String {
+    "config/history".to_string()
+}
+
+fn default_config_history_max_versions() -> usize {
+    50
+}
+
+fn default_config_history_scope() -> String {
+    "admin".to_string()
+}
+
+/// Remote store of backoffice configs, mirrored to a local directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigStoreConfig {
+    #[serde(flatten)]
+    pub source: ConfigSource,
+    /// Local copy of the configs, loaded when the store is unreachable
+    #[serde(default = "default_config_store_cache_dir")]
+    pub cache_dir: String,
+    /// Check the store for changes this often; not polled when unset
+    #[serde(default)]
+    pub poll_interval_secs: Option<u64>,
+    /// Stop the server when the configs change, for its supervisor to
+    /// restart it with them; otherwise they apply from the next start
+    #[serde(default)]
+    pub exit_on_change: bool,
+}
+
+fn default_config_store_cache_dir() -> String {
+    "config/remote".to_string()
+}
+
+/// Where remote backoffice configs are kept; only `.yaml` and `.yml`
+/// objects, files or keys are loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ConfigSource {
+    /// Objects under a prefix of an S3 bucket
+    S3 {
+        bucket: String,
+        region: String,
+        #[serde(default)]
+        prefix: Option<String>,
+        /// Static credentials; without them the AWS environment is used
+        #[serde(default)]
+        access_key: Option<String>,
+        #[serde(default)]
+        secret_key: Option<String>,
+        /// Custom endpoint for S3-compatible stores such as MinIO
+        #[serde(default)]
+        endpoint: Option<String>,
+    },
+    /// Files of a git repository, cloned with the `git` command
+    Git {
+        url: String,
+        /// Defaults to the remote's default branch
+        #[serde(default)]
+        branch: Option<String>,
+        /// Directory of the configs within the repository
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default = "default_git_checkout_dir")]
+        checkout_dir: String,
+    },
+    /// Keys under a prefix of the Consul KV store
+    Consul {
+        /// e.g. `http://127.0.0.1:8500`
+        address: String,
+        prefix: String,
+        #[serde(default)]
+        token: Option<String>,
+    },
+    /// Keys under a prefix of etcd, through its v3 JSON gateway
+    Etcd {
+        /// e.g. `http://127.0.0.1:2379`
+        address: String,
+        prefix: String,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+    },
+}
+
+fn default_git_checkout_dir() -> String {
+    "config/git".to_string()
+}
+
+/// Feature flags, by name, and where their values come from
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeatureFlagsConfig {
+    #[serde(default)]
+    pub flags: HashMap<String, FeatureFlag>,
+    /// Unleash server answering the `unleash` flags
+    #[serde(default)]
+    pub unleash: Option<UnleashConfig>,
+}
+
+/// Value of a feature flag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FeatureFlag {
+    Static(bool),
+    /// An environment variable such as `true`, `1`, `yes` or `on`; `default`
+    /// when unset
+    Env {
+        env: String,
+        #[serde(default)]
+        default: bool,
+    },
+    /// An Unleash toggle; `default` until Unleash has answered
+    Unleash {
+        unleash: String,
+        #[serde(default)]
+        default: bool,
+    },
+}
+
+/// Unleash server of feature toggles, polled through its client API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnleashConfig {
+    /// API URL, e.g. `https://unleash.example.com/api`
+    pub url: String,
+    /// Client API token
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default = "default_unleash_app_name")]
+    pub app_name: String,
+    #[serde(default = "default_unleash_refresh_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+fn default_unleash_app_name() -> String {
+    "pmp-backoffice".to_string()
+}
+
+fn default_unleash_refresh_secs() -> u64 {
+    15
+}
+
+/// Who may subscribe to what on the push WebSocket
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PushConfig {
+    /// Scopes needed to receive the outcomes of background tasks
+    #[serde(default)]
+    pub task_scopes: Vec<String>,
+}
+
+/// A ClamAV daemon scanning uploads over its TCP socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AntivirusConfig {
+    /// `host:port` of clamd, e.g. `127.0.0.1:3310`
+    pub address: String,
+    #[serde(default = "default_scan_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Size of the chunks files are streamed to clamd in
+    #[serde(default = "default_scan_chunk_bytes")]
+    pub chunk_bytes: usize,
+}
+
+fn default_scan_timeout_secs() -> u64 {
+    30
+}
+
+fn default_scan_chunk_bytes() -> usize {
+    64 * 1024
+}
+
+/// Message templates of non-English deployments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct I18nConfig {
+    /// Locale of requests asking for none the messages are available in
+    pub default_locale: String,
+    /// Templates by locale and message code, e.g. `es: {min_length: ...}`,
+    /// overriding the built-in English ones
+    pub messages: HashMap<String, HashMap<String, String>>,
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self {
+            default_locale: "en".to_string(),
+            messages: HashMap::new(),
+        }
+    }
+}
+
+/// Background retention and rotation of the audit storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditMaintenanceConfig {
+    /// Entries older than this many days are deleted; `null` keeps them
+    pub retention_days: Option<u32>,
+    /// Size beyond which a day's log file is continued in a new part
+    pub max_file_bytes: Option<u64>,
+    /// Gzip log files once nothing is written to them anymore
+    pub compress: bool,
+    /// Seconds between maintenance runs; `null` disables them
+    pub interval_secs: Option<u64>,
+}
+
+impl Default for AuditMaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: None,
+            max_file_bytes: None,
+            compress: false,
+            interval_secs: Some(24 * 60 * 60),
+        }
+    }
+}
+
+/// Audit log sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditStorageConfig {
+    /// Daily JSONL files, local to each replica
+    File {
+        #[serde(default = "default_audit_dir")]
+        dir: String,
+    },
+    /// A database table, Elasticsearch index or S3 bucket shared by replicas
+    DataSource {
+        data_source: Box<DataSourceConfig>,
+        /// Table entries are inserted into, for database data sources
+        #[serde(default = "default_audit_table")]
+        table: String,
+    },
+}
+
+fn default_audit_dir() -> String {
+    "logs/audit".to_string()
+}
+
+fn default_audit_table() -> String {
+    "audit_log".to_string()
+}
+
+impl AuditStorageConfig {
+    /// Reject data sources entries cannot be written to
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            AuditStorageConfig::File { .. } => Ok(()),
+            AuditStorageConfig::DataSource { data_source, .. } => match data_source.as_ref() {
+                DataSourceConfig::Database { .. }
+                | DataSourceConfig::Elasticsearch { .. }
+                | DataSourceConfig::S3 { .. } => Ok(()),
+                _ => Err(anyhow::anyhow!(
+                    "audit_storage supports database, elasticsearch and s3 data sources"
+                )),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    #[serde(default)]
+    pub health_checks: HealthCheckConfig,
+    /// Reverse proxies in front of the server, whose `X-Forwarded-For`
+    /// entries are trusted for the client IP; 0 ignores the header
+    #[serde(default)]
+    pub trusted_proxies: usize,
+    #[serde(default)]
+    pub access_log: AccessLogConfig,
+}
+
+/// One log line per request, written to the `access` tracing target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessLogConfig {
+    pub enabled: bool,
+    pub format: AccessLogFormat,
+    /// Names of query parameters and payload fields to redact besides the
+    /// built-in ones, such as `password` and `token`
+    pub redact: Vec<String>,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            format: AccessLogFormat::Combined,
+            redact: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    /// Apache combined log format, followed by latency and request id
+    #[default]
+    Combined,
+    /// A JSON object per request
+    Json,
+}
+
+/// Periodic data source health checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthCheckConfig {
+    /// Seconds between checks; `null` disables them
+    pub interval_secs: Option<u64>,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: Some(30),
+        }
+    }
+}
+
+/// Fail fast on data sources that keep failing instead of waiting for timeouts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    pub enabled: bool,
+    /// Consecutive failed calls that open the circuit
+    pub failure_threshold: u32,
+    /// Seconds calls fail fast before the data source is probed again
+    pub open_secs: u64,
+    /// Calls let through at once to probe a data source
+    pub half_open_probes: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failure_threshold: 5,
+            open_secs: 30,
+            half_open_probes: 1,
+        }
+    }
+}
+
+/// Guards against oversized payloads and slow requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Largest accepted request body in bytes; larger ones get 413
+    pub max_body_bytes: usize,
+    /// Requests taking longer get 408; `null` disables the timeout
+    pub request_timeout_secs: Option<u64>,
+    /// Requests handled at once, beyond which requests wait their turn
+    pub max_concurrent_requests: Option<usize>,
+    /// Timeout of HTTP calls made by API, GraphQL and Elasticsearch sources
+    pub data_source_timeout_secs: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 2 * 1024 * 1024,
+            request_timeout_secs: Some(60),
+            max_concurrent_requests: None,
+            data_source_timeout_secs: 30,
+        }
+    }
+}
+
+/// Response compression, negotiated through `Accept-Encoding`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+    /// Encodings offered to clients
+    pub algorithms: Vec<CompressionAlgorithm>,
+    /// Responses smaller than this many bytes are sent uncompressed
+    pub min_size: u16,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            algorithms: vec![
+                CompressionAlgorithm::Gzip,
+                CompressionAlgorithm::Br,
+                CompressionAlgorithm::Zstd,
+            ],
+            min_size: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    Gzip,
+    Br,
+    Zstd,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityConfig {
     pub enabled: bool,
     pub jwt_secret: Option<String>,
+    /// What to do with mutation fields the caller lacks scopes to write
+    #[serde(default)]
+    pub unauthorized_field_writes: FieldWritePolicy,
+    /// Scope needed for administrative endpoints such as schema scaffolding
+    #[serde(default = "default_admin_scope")]
+    pub admin_scope: String,
+}
+
+fn default_admin_scope() -> String {
+    "admin".to_string()
+}
+
+/// Handling of fields a caller is not allowed to write
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldWritePolicy {
+    /// Fail the mutation with 403
+    #[default]
+    Reject,
+    /// Silently remove the fields from the payload
+    Drop,
 }
 
 /// Backoffice configuration
@@ -34,6 +525,351 @@ pub struct BackofficeConfig {
     pub sections: Vec<SectionConfig>,
     #[serde(default)]
     pub relationships: Vec<RelationshipConfig>,
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    #[serde(default)]
+    pub validation_functions: HashMap<String, ValidationFunctionConfig>,
+    #[serde(default)]
+    pub script_hooks: Vec<ScriptHookConfig>,
+    #[serde(default)]
+    pub dashboard: Option<DashboardConfig>,
+    /// Reporting currency that currency amounts are converted to
+    #[serde(default)]
+    pub currency: Option<CurrencyConfig>,
+    /// Response envelope versions and format served for the backoffice
+    #[serde(default)]
+    pub api: ApiVersionConfig,
+}
+
+impl BackofficeConfig {
+    /// Check the layouts of form and view actions against their fields
+    pub fn validate_layouts(&self) -> Result<()> {
+        for section in &self.sections {
+            for action in &section.actions {
+                if let ActionType::Form {
+                    fields,
+                    layout: Some(layout),
+                    ..
+                }
+                | ActionType::View {
+                    fields,
+                    layout: Some(layout),
+                } = &action.action_type
+                {
+                    layout.validate(fields).with_context(|| {
+                        format!("Action '{}' of section '{}'", action.id, section.id)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the states and transitions of state fields
+    pub fn validate_state_fields(&self) -> Result<()> {
+        for section in &self.sections {
+            for action in &section.actions {
+                for field in action.action_type.fields() {
+                    if let FieldType::State { config } = &field.field_type {
+                        config.validate().with_context(|| {
+                            format!(
+                                "Field '{}' of action '{}' of section '{}'",
+                                field.id, action.id, section.id
+                            )
+                        })?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the API versions are ones the server has
+    pub fn validate_api_versions(&self) -> Result<()> {
+        let api = &self.api;
+        for version in api.versions.iter().chain(&api.deprecated) {
+            if !crate::versioning::VERSIONS.contains(version) {
+                return Err(anyhow::anyhow!("Unknown API version {}", version));
+            }
+        }
+        if !api.versions.contains(&api.version) {
+            return Err(anyhow::anyhow!(
+                "API version {} is pinned but not in api.versions",
+                api.version
+            ));
+        }
+        Ok(())
+    }
+
+    /// Compile the expressions of `expression` validation rules
+    pub fn validate_expressions(&self) -> Result<()> {
+        for section in &self.sections {
+            for action in &section.actions {
+                for field in action.action_type.fields() {
+                    for rule in &field.validations {
+                        if let ValidationType::Expression { expr } = &rule.rule_type {
+                            crate::expression::compile(expr).with_context(|| {
+                                format!(
+                                    "Field '{}' of action '{}' of section '{}'",
+                                    field.id, action.id, section.id
+                                )
+                            })?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `unique_in` rules are on actions of database data sources,
+    /// which are the only ones they can query
+    pub fn validate_unique_rules(&self) -> Result<()> {
+        for section in &self.sections {
+            for action in &section.actions {
+                let unique = action.action_type.fields().iter().any(|field| {
+                    field
+                        .validations
+                        .iter()
+                        .any(|rule| matches!(rule.rule_type, ValidationType::UniqueIn { .. }))
+                });
+                let database = matches!(
+                    self.data_sources.get(&action.data_source),
+                    None | Some(DataSourceConfig::Database { .. })
+                );
+                if unique && !database {
+                    return Err(anyhow::anyhow!(
+                        "Action '{}' of section '{}' has unique_in rules but its data source '{}' is not a database",
+                        action.id,
+                        section.id,
+                        action.data_source
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Response envelope versions of a backoffice's routes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiVersionConfig {
+    /// Envelope of the unversioned `/api/backoffices/...` routes
+    pub version: u8,
+    /// Versions served under `/api/vN/backoffices/...`
+    pub versions: Vec<u8>,
+    /// Versions still served but answered with a `Deprecation` header
+    pub deprecated: Vec<u8>,
+    /// Document format of requests that don't ask for one in `Accept`
+    pub format: ResponseFormat,
+}
+
+/// Document format of section action responses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Bodies of the requested envelope version
+    #[default]
+    Native,
+    /// JSON:API documents, as `Accept: application/vnd.api+json` asks for
+    JsonApi,
+}
+
+impl Default for ApiVersionConfig {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            versions: crate::versioning::VERSIONS.to_vec(),
+            deprecated: Vec::new(),
+            format: ResponseFormat::Native,
+        }
+    }
+}
+
+/// Reporting currency of a backoffice and the exchange rates to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyConfig {
+    pub reporting_currency: String,
+    /// Defaults to the currency's usual symbol
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default = "default_decimal_places")]
+    pub decimal_places: usize,
+    /// Locale whose digit grouping amounts are written with, e.g. `de-DE`
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Units of each currency worth one unit of the reporting currency
+    #[serde(default)]
+    pub rates: HashMap<String, f64>,
+    /// Source of current rates, which take precedence over `rates`
+    #[serde(default)]
+    pub provider: Option<ExchangeRateProviderConfig>,
+}
+
+fn default_decimal_places() -> usize {
+    2
+}
+
+/// A query returning exchange rates, either as rows with a currency and a
+/// rate or as one row with a `rates` object (e.g. `{"EUR": 0.92}`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRateProviderConfig {
+    pub data_source: String,
+    pub query: String,
+    #[serde(default = "default_currency_column")]
+    pub currency_field: String,
+    #[serde(default = "default_rate_column")]
+    pub rate_field: String,
+    /// Seconds fetched rates are reused for
+    #[serde(default = "default_rates_ttl_secs")]
+    pub cache_ttl_secs: u64,
+}
+
+fn default_currency_column() -> String {
+    "currency".to_string()
+}
+
+fn default_rate_column() -> String {
+    "rate".to_string()
+}
+
+fn default_rates_ttl_secs() -> u64 {
+    3600
+}
+
+/// Dashboard of a backoffice: widgets showing the results of its actions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DashboardConfig {
+    #[serde(default)]
+    pub widgets: Vec<WidgetConfig>,
+}
+
+/// A dashboard widget, showing the result of a (typically summary) action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WidgetConfig {
+    pub id: String,
+    pub title: String,
+    pub section: String,
+    pub action: String,
+    #[serde(default)]
+    pub chart: ChartType,
+    /// Seconds between refreshes in the UI; never refreshed when unset
+    #[serde(default)]
+    pub refresh_secs: Option<u64>,
+    #[serde(default)]
+    pub layout: WidgetLayout,
+    /// Query parameters sent to the action
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartType {
+    #[default]
+    Bar,
+    Line,
+    Area,
+    Pie,
+    /// The first value of the first series, shown large
+    Number,
+    Table,
+}
+
+/// Placement of a widget on a 12-column grid; unset positions flow in order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WidgetLayout {
+    #[serde(default)]
+    pub x: Option<u32>,
+    #[serde(default)]
+    pub y: Option<u32>,
+    #[serde(default = "default_widget_width")]
+    pub width: u32,
+    #[serde(default = "default_widget_height")]
+    pub height: u32,
+}
+
+fn default_widget_width() -> u32 {
+    6
+}
+
+fn default_widget_height() -> u32 {
+    1
+}
+
+impl Default for WidgetLayout {
+    fn default() -> Self {
+        Self {
+            x: None,
+            y: None,
+            width: default_widget_width(),
+            height: default_widget_height(),
+        }
+    }
+}
+
+/// Named validation function referenced by `custom_function` rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ValidationFunctionConfig {
+    /// Rhai script evaluated with `value`, `data` and `field` in scope
+    Rhai { script: String },
+    /// Export of a configured WASM plugin
+    Wasm { plugin: String, export: String },
+}
+
+/// WASM plugin configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfig {
+    pub id: String,
+    pub path: String,
+    #[serde(default)]
+    pub hooks: Vec<PluginHook>,
+    /// Sections the hooks apply to (all sections when empty)
+    #[serde(default)]
+    pub sections: Vec<String>,
+    /// Maximum fuel (instruction budget) per plugin call
+    pub fuel: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginHook {
+    TransformRow,
+    BeforeMutation,
+}
+
+/// Lifecycle hook implemented by a Rhai script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptHookConfig {
+    pub event: HookEvent,
+    pub script: String,
+    /// Sections the hook applies to (all sections when empty)
+    #[serde(default)]
+    pub sections: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    BeforeQuery,
+    AfterQuery,
+    BeforeMutation,
+    AfterMutation,
+    OnDelete,
+}
+
+/// When a data source is constructed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InitMode {
+    /// On first use
+    #[default]
+    Lazy,
+    /// At startup; a failure marks the backoffice degraded instead of
+    /// aborting boot
+    Eager,
 }
 
 /// Data source configuration
@@ -42,37 +878,62 @@ pub struct BackofficeConfig {
 pub enum DataSourceConfig {
     #[serde(rename = "database")]
     Database {
+        /// Primary connection, used for mutations
         connection_string: String,
         db_type: DatabaseType,
+        /// Read replicas queried round-robin, falling back to the primary
+        #[serde(default)]
+        read_replicas: Vec<String>,
+        /// SQL migrations applied to the primary
+        #[serde(default)]
+        migrations: Option<MigrationsConfig>,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "api")]
     Api {
         base_url: String,
         headers: Option<HashMap<String, String>>,
         auth: Option<ApiAuthConfig>,
+        /// How pages are requested; without it `page`, `page_size`, `limit`
+        /// and `offset` are all sent
+        pagination: Option<ApiPaginationConfig>,
+        /// Where items, totals and cursors live in responses
+        response: Option<Box<ApiResponseMapping>>,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "graphql")]
     GraphQL {
         endpoint: String,
         headers: Option<HashMap<String, String>>,
         auth: Option<ApiAuthConfig>,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "mongodb")]
     MongoDB {
         connection_string: String,
         database: String,
         collection: String,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "redis")]
     Redis {
         connection_string: String,
         key_prefix: Option<String>,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "elasticsearch")]
     Elasticsearch {
         nodes: Vec<String>,
         index: String,
         auth: Option<ApiAuthConfig>,
+        tls: Option<TlsConfig>,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "grpc")]
     Grpc {
@@ -80,39 +941,165 @@ pub enum DataSourceConfig {
         proto_file: String,
         service_name: String,
         tls_enabled: bool,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "kafka")]
     Kafka {
         brokers: Vec<String>,
         topic: String,
         group_id: String,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "s3")]
     S3 {
         bucket: String,
         region: String,
+        /// Static credentials; without them the AWS environment is used
         access_key: Option<String>,
         secret_key: Option<String>,
         prefix: Option<String>,
+        /// Custom endpoint for S3-compatible stores such as MinIO or
+        /// LocalStack; requests then use path-style addressing
+        endpoint: Option<String>,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "firebase")]
     Firebase {
         project_id: String,
         collection: String,
         credentials_path: Option<String>,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "supabase")]
     Supabase {
         url: String,
         api_key: String,
         table: String,
+        #[serde(default)]
+        init: InitMode,
+    },
+    #[serde(rename = "couchdb")]
+    CouchDb {
+        url: String,
+        database: String,
+        username: Option<String>,
+        password: Option<String>,
+        #[serde(default)]
+        init: InitMode,
+    },
+    #[serde(rename = "influxdb")]
+    InfluxDb {
+        url: String,
+        org: String,
+        bucket: String,
+        token: String,
+        /// Write precision: ns (default), us, ms or s
+        precision: Option<String>,
+        #[serde(default)]
+        init: InitMode,
+    },
+    #[serde(rename = "prometheus")]
+    Prometheus {
+        url: String,
+        headers: Option<HashMap<String, String>>,
+        #[serde(default)]
+        init: InitMode,
+    },
+    #[serde(rename = "file")]
+    File {
+        /// File path, or a glob for read-only sources
+        path: String,
+        /// Inferred from the file extension when omitted
+        format: Option<FileFormat>,
+        #[serde(default)]
+        write_mode: FileWriteMode,
+        #[serde(default)]
+        init: InitMode,
+    },
+    #[serde(rename = "airtable")]
+    Airtable {
+        base_id: String,
+        table: String,
+        api_key: String,
+        #[serde(default)]
+        init: InitMode,
+    },
+    #[serde(rename = "odata")]
+    OData {
+        service_url: String,
+        entity_set: String,
+        /// Entity key property, defaults to `id`
+        key_field: Option<String>,
+        headers: Option<HashMap<String, String>>,
+        #[serde(default)]
+        init: InitMode,
+    },
+    #[serde(rename = "jsonrpc")]
+    JsonRpc {
+        endpoint: String,
+        headers: Option<HashMap<String, String>>,
+        #[serde(default)]
+        init: InitMode,
     },
     #[serde(rename = "websocket")]
     WebSocket {
         url: String,
         reconnect: bool,
         heartbeat_interval: Option<u32>,
+        #[serde(default)]
+        init: InitMode,
     },
+    #[serde(rename = "wasm")]
+    Wasm {
+        path: String,
+        #[serde(default)]
+        config: serde_json::Value,
+        fuel: Option<u64>,
+        #[serde(default)]
+        init: InitMode,
+    },
+    /// Data source built by a factory registered in the `DataSourceRegistry`
+    #[serde(rename = "custom")]
+    Custom {
+        factory: String,
+        #[serde(default)]
+        config: serde_json::Value,
+        #[serde(default)]
+        init: InitMode,
+    },
+}
+
+impl DataSourceConfig {
+    /// When the data source is constructed
+    pub fn init(&self) -> InitMode {
+        match self {
+            Self::Database { init, .. }
+            | Self::Api { init, .. }
+            | Self::GraphQL { init, .. }
+            | Self::MongoDB { init, .. }
+            | Self::Redis { init, .. }
+            | Self::Elasticsearch { init, .. }
+            | Self::Grpc { init, .. }
+            | Self::Kafka { init, .. }
+            | Self::S3 { init, .. }
+            | Self::Firebase { init, .. }
+            | Self::Supabase { init, .. }
+            | Self::CouchDb { init, .. }
+            | Self::InfluxDb { init, .. }
+            | Self::Prometheus { init, .. }
+            | Self::File { init, .. }
+            | Self::Airtable { init, .. }
+            | Self::OData { init, .. }
+            | Self::JsonRpc { init, .. }
+            | Self::WebSocket { init, .. }
+            | Self::Wasm { init, .. }
+            | Self::Custom { init, .. } => *init,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -123,12 +1110,143 @@ pub enum DatabaseType {
     Sqlite,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ApiAuthConfig {
-    pub auth_type: String,
-    pub token: Option<String>,
-    pub username: Option<String>,
-    pub password: Option<String>,
+/// Format of a file data source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    Csv,
+    /// A JSON array of objects
+    Json,
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// How a file data source applies mutations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileWriteMode {
+    /// Every mutation adds a new record
+    #[default]
+    Append,
+    /// Mutations replace the record with the same `id`, or add one
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiAuthConfig {
+    pub auth_type: String,
+    pub token: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// OAuth2 token endpoint (`oauth2` client-credentials auth)
+    pub token_url: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+/// Pagination strategy of an API data source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPaginationConfig {
+    pub style: ApiPaginationStyle,
+    /// Page number parameter (`page` style)
+    #[serde(default = "default_page_param")]
+    pub page_param: String,
+    /// Page size parameter; `page_size` for the `page` style and `limit` otherwise
+    pub size_param: Option<String>,
+    /// Offset parameter (`offset` style)
+    #[serde(default = "default_offset_param")]
+    pub offset_param: String,
+    /// Cursor parameter (`cursor` style)
+    #[serde(default = "default_cursor_param")]
+    pub cursor_param: String,
+    /// Dotted path of the next page cursor in the response (`cursor` style)
+    #[serde(default = "default_next_cursor_field")]
+    pub next_cursor_field: String,
+    /// Dotted path of the total item count in the response
+    pub total_field: Option<String>,
+}
+
+impl ApiPaginationConfig {
+    pub fn size_param(&self) -> &str {
+        match (&self.size_param, &self.style) {
+            (Some(param), _) => param,
+            (None, ApiPaginationStyle::Page) => "page_size",
+            (None, _) => "limit",
+        }
+    }
+}
+
+fn default_page_param() -> String {
+    "page".to_string()
+}
+
+fn default_offset_param() -> String {
+    "offset".to_string()
+}
+
+fn default_cursor_param() -> String {
+    "cursor".to_string()
+}
+
+fn default_next_cursor_field() -> String {
+    "next_cursor".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiPaginationStyle {
+    /// `?page=N&page_size=M`
+    Page,
+    /// `?offset=N&limit=M`
+    Offset,
+    /// `?cursor=<next cursor from the previous response>&limit=M`
+    Cursor,
+    /// Follow the `rel="next"` URL of the `Link` response header
+    LinkHeader,
+}
+
+/// Extraction of items and page metadata from API responses
+///
+/// Paths are JSONPath-like: `$.result.items`, `.meta.total` or
+/// `data[0].entries` all work.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiResponseMapping {
+    /// Path of the items array; without it a top-level array or `data`
+    /// array is used
+    pub items: Option<String>,
+    /// Path of the total item count; takes precedence over
+    /// `pagination.total_field`
+    pub total: Option<String>,
+    /// Path of the next page cursor; takes precedence over
+    /// `pagination.next_cursor_field`
+    pub next_cursor: Option<String>,
+    /// Output field name -> path inside each item
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+}
+
+/// Directory of sqlx migrations for a database data source
+///
+/// Files are named `<version>_<description>.sql` (or `.up.sql`/`.down.sql`
+/// pairs), as with `sqlx migrate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationsConfig {
+    pub path: String,
+    /// Apply pending migrations when the server starts
+    #[serde(default)]
+    pub run_on_startup: bool,
+}
+
+/// TLS options for HTTPS data sources
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// PEM file with an extra CA certificate to trust
+    pub ca_cert: Option<String>,
+    /// Accept invalid or self-signed certificates (development only)
+    #[serde(default)]
+    pub skip_verify: bool,
 }
 
 /// Relationship configuration between tables/sections
@@ -141,8 +1259,12 @@ pub struct RelationshipConfig {
     pub from_field: String,
     pub to_section: String,
     pub to_field: String,
+    /// Shorthand for `on_delete: cascade`
     #[serde(default)]
     pub cascade_delete: bool,
+    /// What deleting a `to_section` record does to the records referencing it
+    #[serde(default)]
+    pub on_delete: Option<OnDelete>,
     #[serde(default)]
     pub display_in_form: bool,
     #[serde(default)]
@@ -150,6 +1272,26 @@ pub struct RelationshipConfig {
     pub display_fields: Option<Vec<String>>,
 }
 
+impl RelationshipConfig {
+    /// The delete policy, if any; `cascade_delete: true` means `cascade`
+    pub fn on_delete(&self) -> Option<OnDelete> {
+        self.on_delete
+            .or(self.cascade_delete.then_some(OnDelete::Cascade))
+    }
+}
+
+/// Delete policy of a relationship
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDelete {
+    /// Delete the referencing records too
+    Cascade,
+    /// Refuse to delete a record that is still referenced
+    Restrict,
+    /// Clear `from_field` on the referencing records
+    SetNull,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RelationshipType {
@@ -172,6 +1314,117 @@ pub struct SectionConfig {
     pub actions: Vec<ActionConfig>,
     #[serde(default)]
     pub audit: Option<AuditConfig>,
+    /// Row-level security filters applied to every query and mutation
+    #[serde(default)]
+    pub row_filters: Vec<RowFilterConfig>,
+    /// Table (or MongoDB/Elasticsearch collection) behind the section, used
+    /// by `generated` actions; defaults to the section id
+    #[serde(default, alias = "collection")]
+    pub table: Option<String>,
+    /// Fixture files (YAML, JSON, JSONL or CSV) inserted by `seed`
+    #[serde(default)]
+    pub seeds: Vec<String>,
+    /// Row field holding each record's last change, sent as `Last-Modified`
+    #[serde(default)]
+    pub updated_at_field: Option<String>,
+    /// Full-text search backend answering the `q` parameter of its lists
+    #[serde(default)]
+    pub search: Option<SearchConfig>,
+    /// Enables copying records with the duplicate endpoint
+    #[serde(default)]
+    pub duplicate: Option<DuplicateConfig>,
+    /// Feature flag the section is only served under
+    #[serde(default)]
+    pub enabled_when: Option<String>,
+}
+
+/// How records of a section are copied
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DuplicateConfig {
+    /// Form action inserting the copies; the section's first create form by
+    /// default
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Fields left out of copies, such as unique keys, so they take their
+    /// defaults
+    #[serde(default)]
+    pub strip: Vec<String>,
+    /// Fields given new values in copies
+    #[serde(default)]
+    pub regenerate: HashMap<String, RegeneratedValue>,
+}
+
+/// New value of a field in a copied record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegeneratedValue {
+    /// The current time, in RFC 3339
+    Now,
+    /// A random UUID
+    Uuid,
+    /// The original text followed by ` (copy)`
+    CopySuffix,
+}
+
+/// Full-text search of a section's records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchConfig {
+    #[serde(flatten)]
+    pub backend: SearchBackend,
+    /// Fields returned as highlighted snippets and searched by Elasticsearch
+    /// and the `LIKE` fallback; the list's `searchable_fields` otherwise
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// Most hits asked from the backend
+    #[serde(default = "default_search_limit")]
+    pub limit: usize,
+}
+
+fn default_search_limit() -> usize {
+    1000
+}
+
+/// Where full-text searches run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum SearchBackend {
+    /// A `tsvector` column of the section's PostgreSQL table
+    Postgres {
+        column: String,
+        /// Text search configuration, e.g. `english` or `simple`
+        #[serde(default = "default_search_language")]
+        language: String,
+        /// Data source holding the table; defaults to the list action's
+        #[serde(default)]
+        data_source: Option<String>,
+    },
+    /// An Elasticsearch data source indexing the section's records
+    Elasticsearch { data_source: String },
+    /// A Meilisearch index of the section's records
+    Meilisearch {
+        url: String,
+        index: String,
+        #[serde(default)]
+        api_key: Option<String>,
+        #[serde(default = "default_search_primary_key")]
+        primary_key: String,
+    },
+}
+
+fn default_search_language() -> String {
+    "english".to_string()
+}
+
+fn default_search_primary_key() -> String {
+    "id".to_string()
+}
+
+/// Restricts a section's records to those whose `field` equals a token claim
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowFilterConfig {
+    pub field: String,
+    /// Claim path in the caller's token, e.g. `tenant_id` or `org.id`
+    pub claim: String,
 }
 
 /// Audit trail configuration
@@ -209,7 +1462,80 @@ pub struct ActionConfig {
     pub data_source: String,
     pub query: Option<String>,
     pub endpoint: Option<String>,
+    /// HTTP method for API sources (e.g. `PATCH`); `endpoint` may then be a
+    /// path template such as `/users/{id}`
+    #[serde(default)]
+    pub method: Option<String>,
+    /// Generate the query from the section's table and the action's fields
+    /// instead of using `query`
+    #[serde(default)]
+    pub generated: bool,
+    /// Cache results of list and view actions
+    #[serde(default)]
+    pub cache: Option<ActionCacheConfig>,
+    /// TTL overriding `cache.ttl_secs`, or caching in memory on its own
+    #[serde(default)]
+    pub cache_ttl_secs: Option<u64>,
     pub required_scopes: Vec<String>,
+    /// Feature flag the action is only served under
+    #[serde(default)]
+    pub enabled_when: Option<String>,
+}
+
+/// Result caching for a list or view action
+///
+/// Entries expire after `ttl_secs` and are dropped by any mutation or delete
+/// in the same section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionCacheConfig {
+    pub ttl_secs: u64,
+    #[serde(default)]
+    pub backend: CacheBackend,
+    /// Redis data source that stores entries for the `redis` backend
+    pub data_source: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheBackend {
+    /// Per-process memory
+    #[default]
+    Memory,
+    /// Shared between instances through a Redis data source
+    Redis,
+}
+
+impl ActionConfig {
+    /// How the action's results are cached, if they are
+    pub fn cache_config(&self) -> Option<ActionCacheConfig> {
+        match (&self.cache, self.cache_ttl_secs) {
+            (Some(cache), Some(ttl_secs)) => Some(ActionCacheConfig {
+                ttl_secs,
+                ..cache.clone()
+            }),
+            (Some(cache), None) => Some(cache.clone()),
+            (None, Some(ttl_secs)) => Some(ActionCacheConfig {
+                ttl_secs,
+                backend: CacheBackend::Memory,
+                data_source: None,
+            }),
+            (None, None) => None,
+        }
+    }
+
+    /// The query or endpoint sent to the data source, prefixed with the
+    /// configured HTTP method (e.g. `PATCH /users/{id}`)
+    pub fn target(&self) -> String {
+        let target = self
+            .query
+            .as_deref()
+            .or(self.endpoint.as_deref())
+            .unwrap_or("");
+        match &self.method {
+            Some(method) => format!("{} {}", method.to_uppercase(), target),
+            None => target.to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -224,13 +1550,58 @@ pub enum ActionType {
         fields: Vec<FieldConfig>,
         #[serde(default)]
         config: FormActionConfig,
+        #[serde(default)]
+        layout: Option<LayoutConfig>,
     },
     View {
         fields: Vec<FieldConfig>,
+        #[serde(default)]
+        layout: Option<LayoutConfig>,
     },
     Custom {
         fields: Vec<FieldConfig>,
     },
+    /// Aggregates of the action's rows, grouped by some of their fields
+    Summary {
+        /// Labels and permissions of the group and aggregate columns
+        #[serde(default)]
+        fields: Vec<FieldConfig>,
+        config: SummaryActionConfig,
+    },
+    /// A read-only query run with parameters entered in a generated form
+    Report {
+        /// Labels and permissions of the report's columns
+        #[serde(default)]
+        fields: Vec<FieldConfig>,
+        /// Parameters of the query, validated like form fields
+        #[serde(default)]
+        parameters: Vec<FieldConfig>,
+        #[serde(default)]
+        config: ReportActionConfig,
+    },
+    /// Steps run in order on submit, each able to use the outputs of the
+    /// steps before it
+    Pipeline {
+        /// Inputs of the pipeline, entered in a form
+        #[serde(default)]
+        fields: Vec<FieldConfig>,
+        steps: Vec<PipelineStep>,
+    },
+}
+
+impl ActionType {
+    /// Fields of the action, whatever its type
+    pub fn fields(&self) -> &[FieldConfig] {
+        match self {
+            ActionType::List { fields, .. }
+            | ActionType::Form { fields, .. }
+            | ActionType::View { fields, .. }
+            | ActionType::Custom { fields }
+            | ActionType::Summary { fields, .. }
+            | ActionType::Report { fields, .. }
+            | ActionType::Pipeline { fields, .. } => fields,
+        }
+    }
 }
 
 /// Configuration specific to list actions
@@ -244,6 +1615,9 @@ pub struct ListActionConfig {
     pub filters: Vec<FilterConfig>,
     #[serde(default)]
     pub sortable_fields: Vec<String>,
+    /// Fields matched by the `q` search parameter
+    #[serde(default)]
+    pub searchable_fields: Vec<String>,
     #[serde(default)]
     pub default_sort_field: Option<String>,
     #[serde(default)]
@@ -261,12 +1635,147 @@ impl Default for ListActionConfig {
             enable_pagination: false,
             filters: Vec::new(),
             sortable_fields: Vec::new(),
+            searchable_fields: Vec::new(),
             default_sort_field: None,
             default_sort_order: SortOrder::Ascending,
         }
     }
 }
 
+/// Configuration specific to summary actions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryActionConfig {
+    /// Fields whose distinct values form the groups; none summarizes all rows
+    #[serde(default)]
+    pub group_by: Vec<String>,
+    pub aggregates: Vec<AggregateConfig>,
+    /// Most groups returned
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl SummaryActionConfig {
+    /// Columns the summarized rows must have
+    pub fn columns(&self) -> impl Iterator<Item = &str> {
+        self.group_by
+            .iter()
+            .map(String::as_str)
+            .chain(self.aggregates.iter().filter_map(|a| a.field.as_deref()))
+    }
+}
+
+/// Configuration specific to report actions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportActionConfig {
+    /// Formats the report can be downloaded in
+    #[serde(default = "default_export_formats")]
+    pub export_formats: Vec<FileFormat>,
+    /// Most rows returned
+    #[serde(default)]
+    pub max_rows: Option<usize>,
+}
+
+fn default_export_formats() -> Vec<FileFormat> {
+    vec![FileFormat::Csv, FileFormat::Json, FileFormat::Jsonl]
+}
+
+impl Default for ReportActionConfig {
+    fn default() -> Self {
+        Self {
+            export_formats: default_export_formats(),
+            max_rows: None,
+        }
+    }
+}
+
+/// One step of a pipeline action
+///
+/// Strings in `params` and `map` are templates: `{{input.<field>}}`,
+/// `{{steps.<step>}}` (e.g. `{{steps.fetch.0.id}}`) and, for steps running
+/// per row, `{{row.<field>}}`. A template that is a single `{{...}}` keeps
+/// the referenced value's type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: PipelineStepKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PipelineStepKind {
+    /// Rows read from a data source
+    Query {
+        /// Defaults to the action's data source
+        #[serde(default)]
+        data_source: Option<String>,
+        query: String,
+        #[serde(default)]
+        params: HashMap<String, serde_json::Value>,
+    },
+    /// One row per row of an earlier step, built from `map`
+    Transform {
+        from: String,
+        map: HashMap<String, serde_json::Value>,
+    },
+    /// A write, run once or once per row of the `for_each` step
+    Mutation {
+        /// Defaults to the action's data source
+        #[serde(default)]
+        data_source: Option<String>,
+        query: String,
+        #[serde(default)]
+        params: HashMap<String, serde_json::Value>,
+        #[serde(default)]
+        for_each: Option<String>,
+    },
+}
+
+/// One aggregate of a summary action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateConfig {
+    pub function: AggregateFunction,
+    /// Aggregated field; `count` without one counts rows
+    #[serde(default)]
+    pub field: Option<String>,
+    /// Result column; defaults to `<function>_<field>`, or `count`
+    #[serde(default)]
+    pub alias: Option<String>,
+}
+
+impl AggregateConfig {
+    /// Name of the aggregate's column in summary rows
+    pub fn name(&self) -> String {
+        match (&self.alias, &self.field) {
+            (Some(alias), _) => alias.clone(),
+            (None, Some(field)) => format!("{}_{}", self.function.as_str(), field),
+            (None, None) => self.function.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+        }
+    }
+}
+
 /// Filter configuration for list actions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterConfig {
@@ -308,6 +1817,9 @@ pub struct FormActionConfig {
     pub redirect_on_success: Option<String>,
     #[serde(default)]
     pub show_success_message: bool,
+    /// How files imported through the form are read
+    #[serde(default)]
+    pub import: ImportConfig,
 }
 
 impl Default for FormActionConfig {
@@ -318,6 +1830,95 @@ impl Default for FormActionConfig {
             form_mode: FormMode::Create,
             redirect_on_success: None,
             show_success_message: true,
+            import: ImportConfig::default(),
+        }
+    }
+}
+
+/// How the fields of a form or view action are laid out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutConfig {
+    #[serde(default)]
+    pub style: LayoutStyle,
+    pub groups: Vec<FieldGroupConfig>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutStyle {
+    /// Groups one below the other
+    #[default]
+    Groups,
+    /// One tab per group
+    Tabs,
+}
+
+/// Fields shown together, as a group or a tab
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldGroupConfig {
+    pub id: String,
+    pub label: String,
+    /// Ids of the group's fields, in display order
+    pub fields: Vec<String>,
+    /// Position among the groups; groups without one keep their place
+    /// after those with one
+    #[serde(default)]
+    pub order: Option<i32>,
+    #[serde(default)]
+    pub collapsible: bool,
+}
+
+impl LayoutConfig {
+    /// Fail on duplicate group ids and on fields that are unknown or in
+    /// more than one group
+    pub fn validate(&self, fields: &[FieldConfig]) -> Result<()> {
+        let mut group_ids = Vec::new();
+        let mut grouped = Vec::new();
+        for group in &self.groups {
+            if group_ids.contains(&&group.id) {
+                anyhow::bail!("Layout group '{}' is declared twice", group.id);
+            }
+            group_ids.push(&group.id);
+            for field in &group.fields {
+                if !fields.iter().any(|f| &f.id == field) {
+                    anyhow::bail!(
+                        "Layout group '{}' refers to unknown field '{}'",
+                        group.id,
+                        field
+                    );
+                }
+                if grouped.contains(&field) {
+                    anyhow::bail!("Field '{}' is in more than one layout group", field);
+                }
+                grouped.push(field);
+            }
+        }
+        Ok(())
+    }
+
+    /// The layout for some of its fields, such as those a caller can read:
+    /// groups sorted by `order`, without other fields and without groups
+    /// left empty
+    pub fn for_fields(&self, fields: &[FieldConfig]) -> LayoutConfig {
+        let mut groups: Vec<FieldGroupConfig> = self
+            .groups
+            .iter()
+            .map(|group| FieldGroupConfig {
+                fields: group
+                    .fields
+                    .iter()
+                    .filter(|id| fields.iter().any(|f| &f.id == *id))
+                    .cloned()
+                    .collect(),
+                ..group.clone()
+            })
+            .filter(|group| !group.fields.is_empty())
+            .collect();
+        // Stable, so groups with the same order keep their declared order
+        groups.sort_by_key(|group| (group.order.is_none(), group.order));
+        LayoutConfig {
+            style: self.style,
+            groups,
         }
     }
 }
@@ -351,6 +1952,26 @@ pub struct FieldConfig {
     #[serde(default)]
     pub validations: Vec<ValidationRule>,
     pub relationship_id: Option<String>,
+    /// Redact the value in API responses and audit logs
+    #[serde(default = "default_false")]
+    pub sensitive: bool,
+    /// Mask pattern for sensitive values, e.g. `****{last4}`
+    pub mask: Option<String>,
+    /// Scopes allowed to see the sensitive value unmasked
+    #[serde(default)]
+    pub unmask_scopes: Vec<String>,
+    /// Scopes needed to read (and write) the field
+    #[serde(default)]
+    pub required_scopes: Vec<String>,
+    /// Additional scopes needed to write the field
+    #[serde(default)]
+    pub write_scopes: Vec<String>,
+    /// Show the field only when the condition holds
+    #[serde(default)]
+    pub visible_when: Option<ValidationCondition>,
+    /// Require the field when the condition holds
+    #[serde(default)]
+    pub required_when: Option<ValidationCondition>,
 }
 
 fn default_false() -> bool {
@@ -524,6 +2145,9 @@ pub enum FieldType {
         #[serde(default)]
         config: ColorPaletteFieldConfig,
     },
+    State {
+        config: StateFieldConfig,
+    },
 }
 
 /// Text field configuration
@@ -601,6 +2225,92 @@ pub struct SelectOption {
     pub label: String,
 }
 
+/// How imported files map to a form's fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportConfig {
+    /// Format of imported files, unless the request names one
+    #[serde(default = "default_import_format")]
+    pub format: FileFormat,
+    /// Field ids of file columns; columns named like a field need no entry
+    #[serde(default)]
+    pub columns: HashMap<String, String>,
+    /// Delimiter of CSV files
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// `strftime` formats of date and date-time values, by field id
+    #[serde(default)]
+    pub date_formats: HashMap<String, String>,
+    /// Rows skipped before the CSV header, or records skipped in JSON files
+    #[serde(default)]
+    pub skip_rows: usize,
+}
+
+fn default_import_format() -> FileFormat {
+    FileFormat::Csv
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            format: default_import_format(),
+            columns: HashMap::new(),
+            delimiter: default_delimiter(),
+            date_formats: HashMap::new(),
+            skip_rows: 0,
+        }
+    }
+}
+
+/// State machine field configuration: the states a record can be in and the
+/// transitions allowed between them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateFieldConfig {
+    pub states: Vec<SelectOption>,
+    #[serde(default)]
+    pub transitions: Vec<StateTransition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub from: String,
+    pub to: String,
+    /// Button text of the transition; the target state's label by default
+    pub label: Option<String>,
+    /// Scopes needed to make the transition
+    #[serde(default)]
+    pub required_scopes: Vec<String>,
+}
+
+impl StateFieldConfig {
+    /// Fail on duplicate states and on transitions between unknown states
+    pub fn validate(&self) -> Result<()> {
+        let mut states = Vec::new();
+        for state in &self.states {
+            if states.contains(&&state.value) {
+                anyhow::bail!("State '{}' is declared twice", state.value);
+            }
+            states.push(&state.value);
+        }
+        for transition in &self.transitions {
+            for state in [&transition.from, &transition.to] {
+                if !states.contains(&state) {
+                    anyhow::bail!(
+                        "Transition from '{}' to '{}' refers to unknown state '{}'",
+                        transition.from,
+                        transition.to,
+                        state
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// TextArea field configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextAreaFieldConfig {
@@ -634,6 +2344,11 @@ pub struct EmailFieldConfig {
     pub allow_multiple: bool,
     pub domain_whitelist: Option<Vec<String>>,
     pub domain_blacklist: Option<Vec<String>>,
+    /// Check that the domains of addresses accept mail (MX or address records)
+    #[serde(default)]
+    pub verify_mx: bool,
+    pub mx_timeout_ms: Option<u64>,
+    pub mx_cache_ttl_secs: Option<u64>,
 }
 
 impl Default for EmailFieldConfig {
@@ -645,6 +2360,9 @@ impl Default for EmailFieldConfig {
             allow_multiple: false,
             domain_whitelist: None,
             domain_blacklist: None,
+            verify_mx: false,
+            mx_timeout_ms: None,
+            mx_cache_ttl_secs: None,
         }
     }
 }
@@ -656,6 +2374,12 @@ pub struct FileFieldConfig {
     pub max_size_mb: Option<f64>,
     #[serde(default)]
     pub multiple: bool,
+    /// Object storage data source files uploaded through the API go to
+    #[serde(default)]
+    pub storage: Option<String>,
+    /// Store uploads without a virus scan even when `antivirus` is configured
+    #[serde(default)]
+    pub skip_virus_scan: bool,
 }
 
 /// URL field configuration
@@ -695,6 +2419,9 @@ pub struct CurrencyFieldConfig {
     pub max: Option<f64>,
     pub allow_negative: bool,
     pub decimal_places: usize,
+    /// Locale whose digit grouping amounts are written with, e.g. `de-DE`
+    #[serde(default)]
+    pub locale: Option<String>,
 }
 
 impl Default for CurrencyFieldConfig {
@@ -706,6 +2433,7 @@ impl Default for CurrencyFieldConfig {
             max: None,
             allow_negative: false,
             decimal_places: 2,
+            locale: None,
         }
     }
 }
@@ -850,6 +2578,12 @@ pub struct ImageFieldConfig {
     pub allow_resize: bool,
     #[serde(default)]
     pub multiple: bool,
+    /// Object storage data source images uploaded through the API go to
+    #[serde(default)]
+    pub storage: Option<String>,
+    /// Store uploads without a virus scan even when `antivirus` is configured
+    #[serde(default)]
+    pub skip_virus_scan: bool,
 }
 
 impl Default for ImageFieldConfig {
@@ -871,6 +2605,8 @@ impl Default for ImageFieldConfig {
             allow_crop: false,
             allow_resize: false,
             multiple: false,
+            storage: None,
+            skip_virus_scan: false,
         }
     }
 }
@@ -1253,6 +2989,9 @@ pub struct SignatureFieldConfig {
     pub pen_color: String,
     pub background_color: String,
     pub line_width: u8,
+    /// Object storage data source signatures are rendered to as PNGs
+    #[serde(default)]
+    pub storage: Option<String>,
 }
 
 impl Default for SignatureFieldConfig {
@@ -1263,6 +3002,7 @@ impl Default for SignatureFieldConfig {
             pen_color: "#000000".to_string(),
             background_color: "#FFFFFF".to_string(),
             line_width: 2,
+            storage: None,
         }
     }
 }
@@ -1408,6 +3148,18 @@ pub struct ValidationRule {
     pub rule_type: ValidationType,
     pub message: Option<String>,
     pub condition: Option<ValidationCondition>,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+/// Whether a failed rule blocks the mutation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Error,
+    /// Reported, and only blocks mutations until the client accepts it
+    Warning,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1447,7 +3199,14 @@ pub enum ValidationType {
     MatchField {
         field: String,
     },
-    CreditCard,
+    CreditCard {
+        /// Brands accepted, any by default
+        #[serde(default)]
+        allowed_brands: Vec<CardBrand>,
+        /// Store the number with all but its last four digits masked
+        #[serde(default = "default_true")]
+        mask: bool,
+    },
     Ipv4,
     Ipv6,
     Uuid,
@@ -1494,6 +3253,84 @@ pub enum ValidationType {
         min: f64,
         max: f64,
     },
+    /// Validate by calling another data source (e.g. an external API)
+    ///
+    /// `{value}` in `query`/`endpoint` is replaced with the field value.
+    Remote {
+        data_source: String,
+        query: Option<String>,
+        endpoint: Option<String>,
+        #[serde(default)]
+        expect: RemoteExpectation,
+        /// Result field checked by the `truthy` expectation
+        field: Option<String>,
+        timeout_ms: Option<u64>,
+        cache_ttl_secs: Option<u64>,
+    },
+    /// A Rhai expression over the payload that must be true, e.g.
+    /// `discount <= price * 0.5`
+    Expression {
+        expr: String,
+    },
+    /// Look for personal data in free text
+    NoPii {
+        /// Kinds of personal data to look for, all of them by default
+        #[serde(default)]
+        kinds: Vec<PiiKind>,
+        #[serde(default)]
+        mode: PiiMode,
+    },
+}
+
+/// What a remote validation call must return for the value to be valid
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteExpectation {
+    /// At least one row is returned
+    #[default]
+    Exists,
+    /// No rows are returned
+    Empty,
+    /// The first row's `field` is truthy
+    Truthy,
+}
+
+/// Card brands, told apart by the leading digits of the number (IIN)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardBrand {
+    Visa,
+    Mastercard,
+    Amex,
+    Discover,
+    DinersClub,
+    Jcb,
+    UnionPay,
+    Maestro,
+}
+
+/// Kinds of personal data `no_pii` rules look for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    /// Card numbers passing the Luhn check
+    CreditCard,
+    Ssn,
+    Phone,
+}
+
+/// What a `no_pii` rule does with the personal data it finds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiMode {
+    /// Fail validation
+    #[default]
+    Reject,
+    /// Accept the value, with a warning in the mutation's response
+    Flag,
+    /// Accept the value, replacing the personal data in responses
+    Scrub,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1531,6 +3368,9 @@ pub async fn load_app_config<P: AsRef<Path>>(path: P) -> Result<AppConfig> {
 
     let config: AppConfig =
         serde_yaml::from_str(&content).context("Failed to parse app config YAML")?;
+    if let Some(storage) = &config.audit_storage {
+        storage.validate()?;
+    }
 
     debug!(
         host = %config.server.host,
@@ -1546,43 +3386,16 @@ pub async fn load_backoffices<P: AsRef<Path>>(dir: P) -> Result<Vec<BackofficeCo
     let mut backoffices = Vec::new();
     let dir_path = dir.as_ref();
 
-    debug!(path = ?dir_path, "Scanning directory for backoffice configs");
-
-    let yaml_files: Vec<_> = WalkDir::new(dir_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.path().extension().and_then(|s| s.to_str()) == Some("yaml")
-                || e.path().extension().and_then(|s| s.to_str()) == Some("yml")
-        })
-        .collect();
-
-    info!(count = yaml_files.len(), "Found YAML config files");
-
-    for entry in yaml_files {
-        let file_path = entry.path();
+    for file_path in backoffice_files(dir_path) {
         debug!(path = ?file_path, "Reading backoffice config");
 
-        let content = tokio::fs::read_to_string(file_path)
+        let content = tokio::fs::read_to_string(&file_path)
             .await
             .context(format!("Failed to read backoffice config: {:?}", file_path))?;
 
         debug!(path = ?file_path, size = content.len(), "Config file read");
 
-        let config: BackofficeConfig = serde_yaml::from_str(&content).context(format!(
-            "Failed to parse backoffice config: {:?}",
-            file_path
-        ))?;
-
-        info!(
-            file = ?file_path,
-            id = %config.id,
-            name = %config.name,
-            sections = config.sections.len(),
-            "Parsed backoffice config"
-        );
-
-        backoffices.push(config);
+        backoffices.push(parse_backoffice(&content, &file_path)?);
     }
 
     if backoffices.is_empty() {
@@ -1591,3 +3404,46 @@ pub async fn load_backoffices<P: AsRef<Path>>(dir: P) -> Result<Vec<BackofficeCo
 
     Ok(backoffices)
 }
+
+/// The YAML files under a backoffice config directory
+pub fn backoffice_files(dir: &Path) -> Vec<PathBuf> {
+    debug!(path = ?dir, "Scanning directory for backoffice configs");
+
+    let yaml_files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.path().extension().and_then(|s| s.to_str()) == Some("yaml")
+                || e.path().extension().and_then(|s| s.to_str()) == Some("yml")
+        })
+        .map(|e| e.into_path())
+        .collect();
+
+    info!(count = yaml_files.len(), "Found YAML config files");
+    yaml_files
+}
+
+/// Parse and validate the content of a backoffice config file
+pub fn parse_backoffice(content: &str, file_path: &Path) -> Result<BackofficeConfig> {
+    let config: BackofficeConfig = serde_yaml::from_str(content).context(format!(
+        "Failed to parse backoffice config: {:?}",
+        file_path
+    ))?;
+    config
+        .validate_layouts()
+        .and_then(|_| config.validate_state_fields())
+        .and_then(|_| config.validate_expressions())
+        .and_then(|_| config.validate_unique_rules())
+        .and_then(|_| config.validate_api_versions())
+        .context(format!("Invalid backoffice config: {:?}", file_path))?;
+
+    info!(
+        file = ?file_path,
+        id = %config.id,
+        name = %config.name,
+        sections = config.sections.len(),
+        "Parsed backoffice config"
+    );
+
+    Ok(config)
+}
@@ -0,0 +1,390 @@
+//! Backoffice config versions
+//!
+//! With `config_history.enabled`, every time a backoffice config file is
+//! loaded with content that differs from its last version, a new version is
+//! kept under
+//! `config_history.path`, with who made it, when, and its diff. When a file
+//! no longer parses or validates, its last good version is served instead of
+//! failing the whole server, and any version can be restored to the file
+//! with `POST /api/admin/backoffices/:id/rollback?version=` by callers
+//! authenticated with security enabled.
+//!
+//! Backoffices are loaded at startup, so a restored file is served from the
+//! next start.
+
+use crate::config::{self, BackofficeConfig, ConfigHistoryConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+/// Author of the versions loaded from disk
+pub const DISK: &str = "disk";
+
+/// Serializes reading and writing history files
+static LOCK: Mutex<()> = Mutex::const_new(());
+
+/// One version of a backoffice config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigVersion {
+    pub version: u64,
+    /// Id of the backoffice the file declares
+    pub backoffice: String,
+    /// `disk` for versions loaded from the file, else the user who rolled back
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+    /// What made the version, e.g. `loaded` or `rollback to version 3`
+    pub reason: String,
+    /// Changes from the previous version
+    pub diff: String,
+    pub content: String,
+}
+
+/// The versions of one config file, oldest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    pub file: PathBuf,
+    pub versions: Vec<ConfigVersion>,
+}
+
+/// Version store of backoffice config files
+#[derive(Debug, Clone)]
+pub struct ConfigHistory {
+    config: ConfigHistoryConfig,
+}
+
+impl ConfigHistory {
+    pub fn new(config: &ConfigHistoryConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Scope needed to read the history and roll back
+    pub fn scope(&self) -> &str {
+        &self.config.scope
+    }
+
+    /// Load the backoffice configs in `dir`, recording changed files and
+    /// falling back to the last good version of invalid ones
+    pub async fn load<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<BackofficeConfig>> {
+        if !self.config.enabled {
+            return config::load_backoffices(dir).await;
+        }
+
+        let mut backoffices = Vec::new();
+        for file in config::backoffice_files(dir.as_ref()) {
+            let content = tokio::fs::read_to_string(&file)
+                .await
+                .context(format!("Failed to read backoffice config: {:?}", file))?;
+
+            let backoffice = match config::parse_backoffice(&content, &file) {
+                Ok(backoffice) => {
+                    if let Err(e) = self
+                        .record(&file, &backoffice.id, &content, DISK, "loaded")
+                        .await
+                    {
+                        warn!(file = ?file, error = %e, "Failed to record backoffice config version");
+                    }
+                    backoffice
+                }
+                Err(e) => {
+                    let last = self
+                        .read(&file)
+                        .await
+                        .ok()
+                        .and_then(|mut history| history.versions.pop());
+                    let Some(last) = last else {
+                        return Err(e);
+                    };
+                    error!(
+                        file = ?file,
+                        version = last.version,
+                        error = %format!("{:#}", e),
+                        "Invalid backoffice config, serving its last good version"
+                    );
+                    config::parse_backoffice(&last.content, &file)?
+                }
+            };
+            backoffices.push(backoffice);
+        }
+
+        if backoffices.is_empty() {
+            warn!(directory = ?dir.as_ref(), "No backoffice configurations found in directory");
+        }
+
+        Ok(backoffices)
+    }
+
+    /// Keep `content` as the new version of `file`, unless it is the last one
+    pub async fn record(
+        &self,
+        file: &Path,
+        backoffice: &str,
+        content: &str,
+        author: &str,
+        reason: &str,
+    ) -> Result<Option<ConfigVersion>> {
+        let _lock = LOCK.lock().await;
+        let mut history = self.read(file).await?;
+        if history
+            .versions
+            .last()
+            .is_some_and(|last| last.content == content)
+        {
+            return Ok(None);
+        }
+        self.append(&mut history, backoffice, content, author, reason)
+            .await
+            .map(Some)
+    }
+
+    /// History of the file declaring `backoffice`
+    pub async fn find(&self, backoffice: &str) -> Result<Option<History>> {
+        let mut entries = match tokio::fs::read_dir(&self.config.path).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let content = tokio::fs::read_to_string(entry.path()).await?;
+            let Ok(history) = serde_json::from_str::<History>(&content) else {
+                continue;
+            };
+            if history
+                .versions
+                .last()
+                .is_some_and(|last| last.backoffice == backoffice)
+            {
+                return Ok(Some(history));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Write `version` back to the config file, as a new version by `author`
+    ///
+    /// `None` when it already is the file's last version.
+    pub async fn restore(
+        &self,
+        file: &Path,
+        version: &ConfigVersion,
+        author: &str,
+    ) -> Result<Option<ConfigVersion>> {
+        let _lock = LOCK.lock().await;
+        let mut history = self.read(file).await?;
+        if history
+            .versions
+            .last()
+            .is_some_and(|last| last.content == version.content)
+        {
+            return Ok(None);
+        }
+        config::parse_backoffice(&version.content, file)?;
+
+        let temporary = file.with_extension("rollback");
+        tokio::fs::write(&temporary, &version.content).await?;
+        tokio::fs::rename(&temporary, file)
+            .await
+            .context(format!("Failed to restore backoffice config: {:?}", file))?;
+
+        let restored = self
+            .append(
+                &mut history,
+                &version.backoffice,
+                &version.content,
+                author,
+                &format!("rollback to version {}", version.version),
+            )
+            .await?;
+        info!(
+            file = ?file,
+            backoffice = %version.backoffice,
+            version = version.version,
+            author = %author,
+            "Backoffice config rolled back"
+        );
+        Ok(Some(restored))
+    }
+
+    async fn append(
+        &self,
+        history: &mut History,
+        backoffice: &str,
+        content: &str,
+        author: &str,
+        reason: &str,
+    ) -> Result<ConfigVersion> {
+        let last = history.versions.last();
+        let version = ConfigVersion {
+            version: last.map_or(1, |last| last.version + 1),
+            backoffice: backoffice.to_string(),
+            author: author.to_string(),
+            created_at: Utc::now(),
+            reason: reason.to_string(),
+            diff: diff(last.map_or("", |last| &last.content), content),
+            content: content.to_string(),
+        };
+        history.versions.push(version.clone());
+        let excess = history
+            .versions
+            .len()
+            .saturating_sub(self.config.max_versions.max(1));
+        history.versions.drain(..excess);
+
+        tokio::fs::create_dir_all(&self.config.path).await?;
+        tokio::fs::write(
+            self.path(&history.file),
+            serde_json::to_vec_pretty(&history)?,
+        )
+        .await?;
+        Ok(version)
+    }
+
+    async fn read(&self, file: &Path) -> Result<History> {
+        match tokio::fs::read_to_string(self.path(file)).await {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(History {
+                file: file.to_path_buf(),
+                versions: Vec::new(),
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// History file of a config file, named after its path
+    fn path(&self, file: &Path) -> PathBuf {
+        let name: String = file
+            .to_string_lossy()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+                _ => '_',
+            })
+            .collect();
+        Path::new(&self.config.path).join(format!("{}.json", name))
+    }
+}
+
+/// Line diff of two versions: a `@@ -line +line @@` header before each run
+/// of changes, then the removed lines as `-` and the added ones as `+`
+pub fn diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // common[i][j]: length of the longest common lines of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut in_change = false;
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            in_change = false;
+            continue;
+        }
+        if !in_change {
+            lines.push(format!("@@ -{} +{} @@", i + 1, j + 1));
+            in_change = true;
+        }
+        if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(format!("-{}", old[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "id: shop\nname: Shop\ndata_sources: {}\nsections: []\n";
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff("a\nb\nc", "a\nb\nc"), "");
+        assert_eq!(diff("", "a\nb"), "@@ -1 +1 @@\n+a\n+b");
+        assert_eq!(
+            diff("a\nb\nc\nd", "a\nB\nc\nd\ne"),
+            "@@ -2 +2 @@\n-b\n+B\n@@ -5 +5 @@\n+e"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_versions_and_rollback() {
+        let dir = std::env::temp_dir().join(format!("pmp-history-{}", uuid::Uuid::new_v4()));
+        let backoffices = dir.join("backoffices");
+        std::fs::create_dir_all(&backoffices).unwrap();
+        let file = backoffices.join("shop.yaml");
+        let history = ConfigHistory::new(&ConfigHistoryConfig {
+            enabled: true,
+            path: dir.join("history").to_string_lossy().into_owned(),
+            max_versions: 2,
+            ..Default::default()
+        });
+
+        std::fs::write(&file, VALID).unwrap();
+        assert_eq!(history.load(&backoffices).await.unwrap()[0].name, "Shop");
+        history.load(&backoffices).await.unwrap();
+        let renamed = VALID.replace("name: Shop", "name: Store");
+        std::fs::write(&file, &renamed).unwrap();
+        assert_eq!(history.load(&backoffices).await.unwrap()[0].name, "Store");
+
+        let found = history.find("shop").await.unwrap().unwrap();
+        assert_eq!(found.file, file);
+        let versions: Vec<u64> = found.versions.iter().map(|v| v.version).collect();
+        assert_eq!(versions, [1, 2]);
+        assert_eq!(found.versions[1].author, DISK);
+        assert_eq!(
+            found.versions[1].diff,
+            "@@ -2 +2 @@\n-name: Shop\n+name: Store"
+        );
+
+        // A broken file is served as its last good version
+        std::fs::write(&file, "id: shop\nname: [").unwrap();
+        assert_eq!(history.load(&backoffices).await.unwrap()[0].name, "Store");
+
+        let restored = history
+            .restore(&file, &found.versions[0], "ada")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(restored.version, 3);
+        assert_eq!(restored.author, "ada");
+        assert_eq!(restored.reason, "rollback to version 1");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), VALID);
+        assert!(history
+            .restore(&file, &found.versions[0], "ada")
+            .await
+            .unwrap()
+            .is_none());
+
+        let found = history.find("shop").await.unwrap().unwrap();
+        let versions: Vec<u64> = found.versions.iter().map(|v| v.version).collect();
+        assert_eq!(versions, [2, 3]);
+        assert!(history.find("other").await.unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
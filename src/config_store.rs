@@ -0,0 +1,591 @@
+//! Remote backoffice config stores
+//!
+//! With `config_store` set, backoffice configs are pulled from an S3 prefix,
+//! a git repository, or Consul or etcd keys instead of `config/backoffices`.
+//! They are mirrored to `cache_dir`, which is what gets loaded, so an
+//! instance still starts with the last configs it saw when the store is
+//! unreachable. Configs that don't parse are refused, keeping the last good
+//! copy. With `poll_interval_secs` the store is checked for changes, which
+//! apply from the next start: nothing is reloaded while the server runs, and
+//! `exit_on_change` stops it so its supervisor restarts it with them.
+
+use crate::config::{self, ConfigSource, ConfigStoreConfig};
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+/// Configs by their name relative to the store's prefix, e.g. `crm/users.yaml`
+pub type Documents = BTreeMap<String, String>;
+
+/// A remote config store and its local copy
+pub struct ConfigStore {
+    config: ConfigStoreConfig,
+    client: reqwest::Client,
+}
+
+impl ConfigStore {
+    pub fn new(config: &ConfigStoreConfig) -> Self {
+        Self {
+            config: config.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Directory the configs are mirrored to and loaded from
+    pub fn dir(&self) -> &Path {
+        Path::new(&self.config.cache_dir)
+    }
+
+    /// Pull the configs from the store, mirroring them to [`dir`](Self::dir)
+    ///
+    /// Returns whether the local copy changed. The copy is left as it is
+    /// when any config in the store fails to parse.
+    pub async fn sync(&self) -> Result<bool> {
+        let documents = self.fetch().await?;
+        for (name, content) in &documents {
+            config::parse_backoffice(content, Path::new(name))
+                .context(format!("Refusing invalid config from the store: {}", name))?;
+        }
+        let dir = self.dir();
+        tokio::fs::create_dir_all(dir).await?;
+
+        let mut changed = false;
+        for (name, path) in local_files(dir) {
+            if !documents.contains_key(&name) {
+                info!(config = %name, "Removing backoffice config deleted from the store");
+                tokio::fs::remove_file(path).await?;
+                changed = true;
+            }
+        }
+        for (name, content) in &documents {
+            let path = dir.join(name);
+            if tokio::fs::read_to_string(&path).await.ok().as_deref() == Some(content.as_str()) {
+                continue;
+            }
+            info!(config = %name, "Writing backoffice config from the store");
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&path, content).await?;
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    /// Poll the store while the server runs, for changes that apply on restart
+    ///
+    /// Changes are only mirrored to the local copy, as backoffices are loaded
+    /// at startup. Returns once the configs changed when `exit_on_change` is
+    /// set, for the caller to stop the server, and never otherwise.
+    pub async fn poll_for_restart(&self) {
+        let Some(secs) = self.config.poll_interval_secs.filter(|secs| *secs > 0) else {
+            return std::future::pending().await;
+        };
+        let interval = Duration::from_secs(secs);
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            match self.sync().await {
+                Ok(false) => debug!("Backoffice configs unchanged in the store"),
+                Ok(true) if self.config.exit_on_change => {
+                    warn!("Backoffice configs changed in the store, stopping to apply them");
+                    return;
+                }
+                Ok(true) => {
+                    warn!("Backoffice configs changed in the store, restart to apply them")
+                }
+                Err(e) => warn!(error = %e, "Failed to poll the backoffice config store"),
+            }
+        }
+    }
+
+    /// The configs currently in the store
+    pub async fn fetch(&self) -> Result<Documents> {
+        match &self.config.source {
+            ConfigSource::S3 {
+                bucket,
+                region,
+                prefix,
+                access_key,
+                secret_key,
+                endpoint,
+            } => {
+                let credentials = crate::data_source::s3_credentials(
+                    access_key.as_deref(),
+                    secret_key.as_deref(),
+                )?;
+                fetch_s3(
+                    bucket,
+                    region,
+                    prefix.as_deref().unwrap_or(""),
+                    credentials,
+                    endpoint.as_deref(),
+                )
+                .await
+            }
+            ConfigSource::Git {
+                url,
+                branch,
+                path,
+                checkout_dir,
+            } => {
+                fetch_git(
+                    url,
+                    branch.as_deref(),
+                    path.as_deref(),
+                    Path::new(checkout_dir),
+                )
+                .await
+            }
+            ConfigSource::Consul {
+                address,
+                prefix,
+                token,
+            } => self.fetch_consul(address, prefix, token.as_deref()).await,
+            ConfigSource::Etcd {
+                address,
+                prefix,
+                username,
+                password,
+            } => {
+                self.fetch_etcd(address, prefix, username.as_deref(), password.as_deref())
+                    .await
+            }
+        }
+    }
+
+    async fn fetch_consul(
+        &self,
+        address: &str,
+        prefix: &str,
+        token: Option<&str>,
+    ) -> Result<Documents> {
+        let prefix = prefix.trim_start_matches('/');
+        let mut request = self
+            .client
+            .get(format!(
+                "{}/v1/kv/{}",
+                address.trim_end_matches('/'),
+                prefix
+            ))
+            .query(&[("recurse", "true")]);
+        if let Some(token) = token {
+            request = request.header("X-Consul-Token", token);
+        }
+        let response = request.send().await?;
+        // Consul answers 404 when no key has the prefix
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Documents::new());
+        }
+        let entries: Vec<Value> = response.error_for_status()?.json().await?;
+
+        let mut documents = Documents::new();
+        for entry in entries {
+            let (Some(key), Some(value)) = (entry["Key"].as_str(), entry["Value"].as_str()) else {
+                continue;
+            };
+            let Some(name) = document_name(key.strip_prefix(prefix).unwrap_or(key)) else {
+                continue;
+            };
+            documents.insert(name, decode(value)?);
+        }
+        Ok(documents)
+    }
+
+    async fn fetch_etcd(
+        &self,
+        address: &str,
+        prefix: &str,
+        username: Option<&str>,
+        password: Option<&str>,
+    ) -> Result<Documents> {
+        let address = address.trim_end_matches('/');
+        let base64 = base64::engine::general_purpose::STANDARD;
+
+        let mut request = self.client.post(format!("{}/v3/kv/range", address));
+        if let (Some(username), Some(password)) = (username, password) {
+            let auth: Value = self
+                .client
+                .post(format!("{}/v3/auth/authenticate", address))
+                .json(&json!({"name": username, "password": password}))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let token = auth["token"]
+                .as_str()
+                .ok_or_else(|| anyhow!("etcd authentication returned no token"))?;
+            request = request.header("Authorization", token);
+        }
+        let range: Value = request
+            .json(&json!({
+                "key": base64.encode(prefix),
+                "range_end": base64.encode(prefix_end(prefix.as_bytes())),
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let mut documents = Documents::new();
+        for kv in range["kvs"].as_array().into_iter().flatten() {
+            let (Some(key), Some(value)) = (kv["key"].as_str(), kv["value"].as_str()) else {
+                continue;
+            };
+            let key = decode(key)?;
+            let Some(name) = document_name(key.strip_prefix(prefix).unwrap_or(&key)) else {
+                continue;
+            };
+            documents.insert(name, decode(value)?);
+        }
+        Ok(documents)
+    }
+}
+
+#[cfg(feature = "s3-datasource")]
+async fn fetch_s3(
+    bucket: &str,
+    region: &str,
+    prefix: &str,
+    credentials: Option<(String, String)>,
+    endpoint: Option<&str>,
+) -> Result<Documents> {
+    let client = crate::data_source::s3_client(region.to_string(), credentials, endpoint).await;
+    let mut documents = Documents::new();
+    let mut token = None;
+    loop {
+        let page = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .set_continuation_token(token)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to list S3 configs: {}", e))?;
+        for object in page.contents() {
+            let Some(key) = object.key() else {
+                continue;
+            };
+            let Some(name) = document_name(key.strip_prefix(prefix).unwrap_or(key)) else {
+                continue;
+            };
+            let body = client
+                .get_object()
+                .bucket(bucket)
+                .key(key)
+                .send()
+                .await
+                .map_err(|e| anyhow!("Failed to get S3 config '{}': {}", key, e))?
+                .body
+                .collect()
+                .await?
+                .into_bytes();
+            documents.insert(name, String::from_utf8(body.to_vec())?);
+        }
+        token = page.next_continuation_token().map(str::to_string);
+        if token.is_none() {
+            break;
+        }
+    }
+    Ok(documents)
+}
+
+#[cfg(not(feature = "s3-datasource"))]
+async fn fetch_s3(
+    _bucket: &str,
+    _region: &str,
+    _prefix: &str,
+    _credentials: Option<(String, String)>,
+    _endpoint: Option<&str>,
+) -> Result<Documents> {
+    Err(anyhow!(
+        "S3 config stores need the s3-datasource feature enabled"
+    ))
+}
+
+/// Clone the repository, or update its checkout, and read its configs
+async fn fetch_git(
+    url: &str,
+    branch: Option<&str>,
+    path: Option<&str>,
+    checkout: &Path,
+) -> Result<Documents> {
+    if checkout.join(".git").exists() {
+        git(&[
+            "-C",
+            &checkout.to_string_lossy(),
+            "fetch",
+            "--depth",
+            "1",
+            "--",
+            "origin",
+            branch.unwrap_or("HEAD"),
+        ])
+        .await?;
+        git(&[
+            "-C",
+            &checkout.to_string_lossy(),
+            "reset",
+            "--hard",
+            "FETCH_HEAD",
+        ])
+        .await?;
+    } else {
+        let mut args = vec!["clone", "--depth", "1"];
+        if let Some(branch) = branch {
+            args.extend(["--branch", branch]);
+        }
+        let checkout = checkout.to_string_lossy();
+        args.extend(["--", url, &checkout]);
+        git(&args).await?;
+    }
+
+    let root = checkout.join(path.unwrap_or(""));
+    let mut documents = Documents::new();
+    for (name, file) in local_files(&root) {
+        let content = tokio::fs::read_to_string(&file)
+            .await
+            .context(format!("Failed to read git config: {:?}", file))?;
+        documents.insert(name, content);
+    }
+    Ok(documents)
+}
+
+async fn git(args: &[&str]) -> Result<()> {
+    debug!(args = ?args, "Running git");
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// The YAML files under `dir`, by their path relative to it
+fn local_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let relative = e.path().strip_prefix(dir).ok()?.to_string_lossy();
+            let name = document_name(&relative.replace('\\', "/"))?;
+            Some((name, e.into_path()))
+        })
+        .collect()
+}
+
+/// Name of a config relative to the store's prefix, for YAML keys that stay
+/// within the local copy
+fn document_name(key: &str) -> Option<String> {
+    let name = key.trim_start_matches('/');
+    let is_yaml = name.ends_with(".yaml") || name.ends_with(".yml");
+    let is_contained = Path::new(name)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    (is_yaml && is_contained).then(|| name.to_string())
+}
+
+fn decode(value: &str) -> Result<String> {
+    let bytes = base64::engine::general_purpose::STANDARD.decode(value)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// End of the etcd range of the keys starting with `prefix`
+fn prefix_end(prefix: &[u8]) -> Vec<u8> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < 0xff {
+            end.push(last + 1);
+            return end;
+        }
+    }
+    // Every key
+    vec![0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, routing::post, Json, Router};
+
+    const USERS: &str = "id: users\nname: Users\ndata_sources: {}\nsections: []\n";
+
+    fn store(source: ConfigSource, dir: &Path) -> ConfigStore {
+        ConfigStore::new(&ConfigStoreConfig {
+            source,
+            cache_dir: dir.join("cache").to_string_lossy().into_owned(),
+            poll_interval_secs: None,
+            exit_on_change: false,
+        })
+    }
+
+    #[test]
+    fn test_document_name() {
+        assert_eq!(
+            document_name("/crm/users.yaml").as_deref(),
+            Some("crm/users.yaml")
+        );
+        assert_eq!(document_name("users.yml").as_deref(), Some("users.yml"));
+        assert_eq!(document_name("README.md"), None);
+        assert_eq!(document_name("../etc/users.yaml"), None);
+        assert_eq!(prefix_end(b"backoffices/"), b"backoffices0");
+        assert_eq!(prefix_end(&[b'a', 0xff]), b"b");
+    }
+
+    #[tokio::test]
+    async fn test_consul_and_etcd() {
+        let base64 = base64::engine::general_purpose::STANDARD;
+        let consul = json!([
+            {"Key": "backoffices/", "Value": null},
+            {"Key": "backoffices/users.yaml", "Value": base64.encode(USERS)},
+            {"Key": "backoffices/notes.txt", "Value": base64.encode("skip")},
+        ]);
+        let etcd = json!({"kvs": [
+            {"key": base64.encode("/backoffices/crm/users.yaml"), "value": base64.encode(USERS)},
+        ]});
+        let app = Router::new()
+            .route(
+                "/v1/kv/backoffices/",
+                get(move || {
+                    let consul = consul.clone();
+                    async move { Json(consul) }
+                }),
+            )
+            .route(
+                "/v3/kv/range",
+                post(move |Json(body): Json<Value>| {
+                    let etcd = etcd.clone();
+                    async move {
+                        assert_eq!(body["key"], base64.encode("/backoffices/"));
+                        Json(etcd)
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dir = std::env::temp_dir().join(format!("pmp-store-{}", uuid::Uuid::new_v4()));
+        let consul = store(
+            ConfigSource::Consul {
+                address: address.clone(),
+                prefix: "backoffices/".to_string(),
+                token: Some("secret".to_string()),
+            },
+            &dir,
+        );
+        assert_eq!(
+            consul.fetch().await.unwrap(),
+            Documents::from([("users.yaml".to_string(), USERS.to_string())])
+        );
+
+        // The local copy mirrors the store
+        std::fs::create_dir_all(consul.dir()).unwrap();
+        std::fs::write(consul.dir().join("stale.yaml"), USERS).unwrap();
+        assert!(consul.sync().await.unwrap());
+        assert!(!consul.sync().await.unwrap());
+        assert!(!consul.dir().join("stale.yaml").exists());
+        assert_eq!(
+            std::fs::read_to_string(consul.dir().join("users.yaml")).unwrap(),
+            USERS
+        );
+
+        let etcd = store(
+            ConfigSource::Etcd {
+                address,
+                prefix: "/backoffices/".to_string(),
+                username: None,
+                password: None,
+            },
+            &dir,
+        );
+        assert_eq!(
+            etcd.fetch().await.unwrap(),
+            Documents::from([("crm/users.yaml".to_string(), USERS.to_string())])
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_git() {
+        let dir = std::env::temp_dir().join(format!("pmp-store-{}", uuid::Uuid::new_v4()));
+        let repo = dir.join("repo");
+        std::fs::create_dir_all(repo.join("backoffices")).unwrap();
+        std::fs::write(repo.join("backoffices/users.yaml"), USERS).unwrap();
+        std::fs::write(repo.join("README.md"), "configs").unwrap();
+        let repo_path = repo.to_string_lossy().into_owned();
+        let commit = |message: &'static str| {
+            let repo_path = repo_path.clone();
+            async move {
+                git(&["-C", &repo_path, "add", "-A"]).await.unwrap();
+                git(&[
+                    "-C",
+                    &repo_path,
+                    "-c",
+                    "user.name=test",
+                    "-c",
+                    "user.email=test@example.com",
+                    "commit",
+                    "-qm",
+                    message,
+                ])
+                .await
+                .unwrap();
+            }
+        };
+        git(&["init", "-q", &repo_path]).await.unwrap();
+        commit("Add users").await;
+
+        let store = store(
+            ConfigSource::Git {
+                url: format!("file://{}", repo_path),
+                branch: None,
+                path: Some("backoffices".to_string()),
+                checkout_dir: dir.join("checkout").to_string_lossy().into_owned(),
+            },
+            &dir,
+        );
+        assert!(store.sync().await.unwrap());
+        assert!(store.dir().join("users.yaml").exists());
+
+        std::fs::write(
+            repo.join("backoffices/users.yaml"),
+            USERS.replace("Users", "People"),
+        )
+        .unwrap();
+        commit("Rename users").await;
+        assert!(store.sync().await.unwrap());
+        assert!(std::fs::read_to_string(store.dir().join("users.yaml"))
+            .unwrap()
+            .contains("People"));
+
+        // A config that doesn't parse leaves the local copy alone
+        std::fs::write(repo.join("backoffices/users.yaml"), "id: [").unwrap();
+        commit("Break users").await;
+        assert!(store.sync().await.is_err());
+        assert!(std::fs::read_to_string(store.dir().join("users.yaml"))
+            .unwrap()
+            .contains("People"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
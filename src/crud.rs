@@ -0,0 +1,663 @@
+//! Generated CRUD queries
+//!
+//! Actions marked `generated: true` need no hand-written query. Their
+//! statement is built from the section's `table` (or `collection`, falling
+//! back to the section id) and the action's fields: SQL for database sources,
+//! with identifiers quoted for the backend and values bound through `{{name}}`
+//! placeholders, and the equivalent JSON query for MongoDB and Elasticsearch.
+
+use crate::config::{
+    ActionConfig, ActionType, DataSourceConfig, DatabaseType, FieldConfig, FieldType,
+    SectionConfig, SortOrder,
+};
+use crate::row_filters::{self, RowCondition};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// What a generated query does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrudOperation {
+    List,
+    View,
+    Create,
+    Update,
+    Delete,
+}
+
+impl CrudOperation {
+    /// The read an action performs, or for forms, a create or an update
+    /// depending on whether the submitted data carries an `id`
+    pub fn for_action(action: &ActionConfig, data: Option<&HashMap<String, Value>>) -> Self {
+        match action.action_type {
+            ActionType::List { .. } | ActionType::Summary { .. } | ActionType::Report { .. } => {
+                CrudOperation::List
+            }
+            ActionType::View { .. } | ActionType::Custom { .. } => CrudOperation::View,
+            ActionType::Pipeline { .. } => CrudOperation::Create,
+            ActionType::Form { .. } => {
+                if data
+                    .and_then(|d| d.get("id"))
+                    .is_some_and(|id| !id.is_null())
+                {
+                    CrudOperation::Update
+                } else {
+                    CrudOperation::Create
+                }
+            }
+        }
+    }
+}
+
+/// The query to send for an action: the configured query or endpoint, or
+/// the generated one when the action is `generated`
+///
+/// `values` are the request params for reads and the submitted data for
+/// writes; SQL only references them through placeholders.
+pub fn action_target(
+    section: &SectionConfig,
+    action: &ActionConfig,
+    config: &DataSourceConfig,
+    operation: CrudOperation,
+    values: &HashMap<String, Value>,
+) -> Result<String> {
+    if !action.generated {
+        return Ok(action.target());
+    }
+
+    let table = section.table.as_deref().unwrap_or(&section.id);
+    match config {
+        DataSourceConfig::Database { db_type, .. } => {
+            sql_query(section, action, table, db_type, operation, values)
+        }
+        DataSourceConfig::MongoDB { .. } => mongo_query(operation, values),
+        DataSourceConfig::Elasticsearch { .. } => elasticsearch_query(action, operation, values),
+        _ => Err(anyhow!(
+            "Generated queries are only supported for database, MongoDB and Elasticsearch data sources"
+        )),
+    }
+}
+
+/// The query updating only the fields present in `data`
+///
+/// Hand-written SQL updates usually set every column of the form, which
+/// would overwrite the other columns with NULL, so database updates are
+/// always generated. Other data sources get the action's own target.
+pub fn partial_update_target(
+    section: &SectionConfig,
+    action: &ActionConfig,
+    config: &DataSourceConfig,
+    data: &HashMap<String, Value>,
+) -> Result<String> {
+    match config {
+        DataSourceConfig::Database { db_type, .. } => {
+            let table = section.table.as_deref().unwrap_or(&section.id);
+            sql_query(section, action, table, db_type, CrudOperation::Update, data)
+        }
+        _ => action_target(section, action, config, CrudOperation::Update, data),
+    }
+}
+
+/// Statement of a bulk update on a database table
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkUpdate {
+    pub update: String,
+    /// The data, the filter values as `where_<field>` and the row filter
+    /// values as `row_filter_<n>`
+    pub params: HashMap<String, Value>,
+}
+
+/// A single `UPDATE ... WHERE` setting the columns present in `data` on
+/// every record matching the filter
+///
+/// Filter values match with `=`, and nulls with `IS NULL`. Records must
+/// also match the caller's row-level security conditions.
+pub fn bulk_update(
+    section: &SectionConfig,
+    action: &ActionConfig,
+    db_type: &DatabaseType,
+    data: &HashMap<String, Value>,
+    filter: &HashMap<String, Value>,
+    row_conditions: &[RowCondition],
+) -> Result<BulkUpdate> {
+    let table = quote_identifier(section.table.as_deref().unwrap_or(&section.id), db_type);
+    let field = |name: &str| action_fields(action).iter().find(|f| f.id == name);
+
+    let columns = write_columns(section, action, data);
+    if columns.is_empty() {
+        return Err(anyhow!("No fields to update"));
+    }
+    let assignments = columns
+        .iter()
+        .map(|c| {
+            Ok(format!(
+                "{} = {}",
+                quote_identifier(c, db_type),
+                placeholder(c, field(c), db_type)?
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut params = data.clone();
+    let mut names: Vec<&String> = filter.keys().collect();
+    names.sort();
+    let mut conditions = Vec::new();
+    for name in names {
+        let column = quote_identifier(name, db_type);
+        match &filter[name] {
+            Value::Null => conditions.push(format!("{} IS NULL", column)),
+            value => {
+                let param = format!("where_{}", name);
+                conditions.push(format!(
+                    "{} = {}",
+                    column,
+                    placeholder(&param, field(name), db_type)?
+                ));
+                params.insert(param, value.clone());
+            }
+        }
+    }
+    if !row_conditions.is_empty() {
+        conditions.push(row_filters::sql_params(
+            row_conditions,
+            db_type,
+            &mut params,
+        ));
+    }
+    let condition = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    Ok(BulkUpdate {
+        update: format!(
+            "UPDATE {} SET {}{}",
+            table,
+            assignments.join(", "),
+            condition
+        ),
+        params,
+    })
+}
+
+/// `SELECT *` of the record matching `{{id}}`, for database data sources
+pub fn record_query(section: &SectionConfig, config: &DataSourceConfig) -> Option<String> {
+    let DataSourceConfig::Database { db_type, .. } = config else {
+        return None;
+    };
+    let table = section.table.as_deref().unwrap_or(&section.id);
+    Some(format!(
+        "SELECT * FROM {} WHERE {}",
+        quote_identifier(table, db_type),
+        id_condition(db_type)
+    ))
+}
+
+fn action_fields(action: &ActionConfig) -> &[FieldConfig] {
+    action.action_type.fields()
+}
+
+/// Columns read by list and view actions; `id` is always included so rows
+/// can be linked to their view, edit and delete actions
+///
+/// Summary actions read the columns they group and aggregate instead of
+/// their fields, which describe the summary rows.
+fn read_columns(action: &ActionConfig) -> Vec<&str> {
+    let mut columns = vec!["id"];
+    let names: Vec<&str> = match &action.action_type {
+        ActionType::Summary { config, .. } => config.columns().collect(),
+        action_type => action_type.fields().iter().map(|f| f.id.as_str()).collect(),
+    };
+    for name in names {
+        if !columns.contains(&name) {
+            columns.push(name);
+        }
+    }
+    columns
+}
+
+/// Columns written by a form: its fields, then row filter fields pinned by
+/// row-level security, limited to those present in the data
+fn write_columns<'a>(
+    section: &'a SectionConfig,
+    action: &'a ActionConfig,
+    data: &HashMap<String, Value>,
+) -> Vec<&'a str> {
+    let mut columns: Vec<&str> = Vec::new();
+    let names = action_fields(action)
+        .iter()
+        .map(|f| f.id.as_str())
+        .chain(section.row_filters.iter().map(|f| f.field.as_str()));
+    for name in names {
+        if name != "id" && data.contains_key(name) && !columns.contains(&name) {
+            columns.push(name);
+        }
+    }
+    columns
+}
+
+/// Quote an identifier, keeping schema-qualified names like `app.users`
+pub(crate) fn quote_identifier(name: &str, db_type: &DatabaseType) -> String {
+    let quote = match db_type {
+        DatabaseType::MySQL => '`',
+        DatabaseType::Postgres | DatabaseType::Sqlite => '"',
+    };
+    name.split('.')
+        .map(|part| {
+            let escaped = part.replace(quote, &format!("{0}{0}", quote));
+            format!("{0}{1}{0}", quote, escaped)
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// A `{{name}}` placeholder for a column's value
+///
+/// Postgres binds strings as text, so date and time fields are cast to
+/// their column types.
+fn placeholder(name: &str, field: Option<&FieldConfig>, db_type: &DatabaseType) -> Result<String> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(anyhow!(
+            "Field '{}' cannot be used in a generated query; use a hand-written query instead",
+            name
+        ));
+    }
+
+    let value = format!("{{{{{}}}}}", name);
+    let cast = match (db_type, field.map(|f| &f.field_type)) {
+        (DatabaseType::Postgres, Some(FieldType::Date { .. })) => Some("DATE"),
+        (DatabaseType::Postgres, Some(FieldType::DateTime { .. })) => Some("TIMESTAMP"),
+        (DatabaseType::Postgres, Some(FieldType::Time { .. })) => Some("TIME"),
+        _ => None,
+    };
+    Ok(match cast {
+        Some(cast) => format!("CAST({} AS {})", value, cast),
+        None => value,
+    })
+}
+
+/// `WHERE` clause matching the record's id
+///
+/// The id binds as the column's type, so integer, UUID and text keys all
+/// compare on the column as is and can use its index.
+fn id_condition(db_type: &DatabaseType) -> String {
+    format!("{} = {{{{id}}}}", quote_identifier("id", db_type))
+}
+
+fn sql_query(
+    section: &SectionConfig,
+    action: &ActionConfig,
+    table: &str,
+    db_type: &DatabaseType,
+    operation: CrudOperation,
+    data: &HashMap<String, Value>,
+) -> Result<String> {
+    let table = quote_identifier(table, db_type);
+    let field = |name: &str| action_fields(action).iter().find(|f| f.id == name);
+
+    match operation {
+        CrudOperation::List | CrudOperation::View => {
+            let columns = read_columns(action)
+                .iter()
+                .map(|c| quote_identifier(c, db_type))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut query = format!("SELECT {} FROM {}", columns, table);
+
+            if operation == CrudOperation::View {
+                query.push_str(&format!(" WHERE {}", id_condition(db_type)));
+            } else if let ActionType::List { config, .. } = &action.action_type {
+                if let Some(sort_field) = &config.default_sort_field {
+                    let order = match config.default_sort_order {
+                        SortOrder::Ascending => "ASC",
+                        SortOrder::Descending => "DESC",
+                    };
+                    query.push_str(&format!(
+                        " ORDER BY {} {}",
+                        quote_identifier(sort_field, db_type),
+                        order
+                    ));
+                }
+            }
+            Ok(query)
+        }
+        CrudOperation::Create => {
+            let columns = write_columns(section, action, data);
+            if columns.is_empty() {
+                return Err(anyhow!("No fields to insert"));
+            }
+            let values = columns
+                .iter()
+                .map(|c| placeholder(c, field(c), db_type))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                columns
+                    .iter()
+                    .map(|c| quote_identifier(c, db_type))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                values.join(", ")
+            ))
+        }
+        CrudOperation::Update => {
+            let columns = write_columns(section, action, data);
+            if columns.is_empty() {
+                return Err(anyhow!("No fields to update"));
+            }
+            let assignments = columns
+                .iter()
+                .map(|c| {
+                    Ok(format!(
+                        "{} = {}",
+                        quote_identifier(c, db_type),
+                        placeholder(c, field(c), db_type)?
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(format!(
+                "UPDATE {} SET {} WHERE {}",
+                table,
+                assignments.join(", "),
+                id_condition(db_type)
+            ))
+        }
+        CrudOperation::Delete => Ok(format!(
+            "DELETE FROM {} WHERE {}",
+            table,
+            id_condition(db_type)
+        )),
+    }
+}
+
+fn record_id(values: &HashMap<String, Value>) -> Result<&Value> {
+    values
+        .get("id")
+        .filter(|id| !id.is_null())
+        .ok_or_else(|| anyhow!("Missing 'id' parameter"))
+}
+
+/// MongoDB filters; the collection is the one configured on the data source
+fn mongo_query(operation: CrudOperation, values: &HashMap<String, Value>) -> Result<String> {
+    match operation {
+        CrudOperation::List => Ok("{}".to_string()),
+        CrudOperation::View => {
+            let id = record_id(values)?;
+            // 24 hex digit ids are ObjectIds
+            let id = match id.as_str() {
+                Some(hex) if hex.len() == 24 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                    json!({ "$oid": hex })
+                }
+                _ => id.clone(),
+            };
+            Ok(json!({ "_id": id }).to_string())
+        }
+        CrudOperation::Create => Ok(String::new()),
+        CrudOperation::Update | CrudOperation::Delete => Err(anyhow!(
+            "MongoDB data sources only support generated list, view and create actions"
+        )),
+    }
+}
+
+/// Elasticsearch search bodies, or the document id for writes
+fn elasticsearch_query(
+    action: &ActionConfig,
+    operation: CrudOperation,
+    values: &HashMap<String, Value>,
+) -> Result<String> {
+    let source = read_columns(action);
+    match operation {
+        CrudOperation::List => Ok(json!({
+            "query": { "match_all": {} },
+            "_source": source,
+        })
+        .to_string()),
+        CrudOperation::View => Ok(json!({
+            "query": { "ids": { "values": [record_id(values)?] } },
+            "_source": source,
+        })
+        .to_string()),
+        CrudOperation::Create => Ok(String::new()),
+        CrudOperation::Update => Ok(match record_id(values)? {
+            Value::String(id) => id.clone(),
+            id => id.to_string(),
+        }),
+        CrudOperation::Delete => Err(anyhow!(
+            "Elasticsearch data sources do not support generated delete actions"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(yaml: &str) -> SectionConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn database(db_type: DatabaseType) -> DataSourceConfig {
+        DataSourceConfig::Database {
+            connection_string: "sqlite::memory:".to_string(),
+            db_type,
+            read_replicas: vec![],
+            migrations: None,
+            init: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_generated_sql() {
+        let section = section(
+            r#"
+id: users
+name: Users
+table: app.users
+row_filters:
+  - field: tenant_id
+    claim: tenant_id
+actions:
+  - id: list
+    name: List
+    type: list
+    data_source: db
+    generated: true
+    required_scopes: []
+    fields:
+      - { id: name, name: Name, field_type: text }
+      - { id: born, name: Born, field_type: date }
+    config:
+      default_sort_field: name
+      default_sort_order: descending
+  - id: edit
+    name: Edit
+    type: form
+    data_source: db
+    generated: true
+    required_scopes: []
+    fields:
+      - { id: name, name: Name, field_type: text }
+      - { id: born, name: Born, field_type: date }
+"#,
+        );
+        let (list, form) = (&section.actions[0], &section.actions[1]);
+        let postgres = database(DatabaseType::Postgres);
+        let mysql = database(DatabaseType::MySQL);
+        let none = HashMap::new();
+
+        assert_eq!(
+            action_target(&section, list, &postgres, CrudOperation::List, &none).unwrap(),
+            r#"SELECT "id", "name", "born" FROM "app"."users" ORDER BY "name" DESC"#
+        );
+        assert_eq!(
+            action_target(&section, list, &mysql, CrudOperation::View, &none).unwrap(),
+            "SELECT `id`, `name`, `born` FROM `app`.`users` WHERE `id` = {{id}}"
+        );
+
+        let data = HashMap::from([
+            ("name".to_string(), json!("Ann")),
+            ("born".to_string(), json!("1990-01-01")),
+            ("tenant_id".to_string(), json!("t1")),
+            ("ignored".to_string(), json!(true)),
+        ]);
+        assert_eq!(
+            CrudOperation::for_action(form, Some(&data)),
+            CrudOperation::Create
+        );
+        assert_eq!(
+            action_target(&section, form, &postgres, CrudOperation::Create, &data).unwrap(),
+            r#"INSERT INTO "app"."users" ("name", "born", "tenant_id") VALUES ({{name}}, CAST({{born}} AS DATE), {{tenant_id}})"#
+        );
+
+        let mut data = data;
+        data.insert("id".to_string(), json!(7));
+        assert_eq!(
+            CrudOperation::for_action(form, Some(&data)),
+            CrudOperation::Update
+        );
+        assert_eq!(
+            action_target(&section, form, &mysql, CrudOperation::Update, &data).unwrap(),
+            "UPDATE `app`.`users` SET `name` = {{name}}, `born` = {{born}}, `tenant_id` = {{tenant_id}} WHERE `id` = {{id}}"
+        );
+        assert_eq!(
+            action_target(&section, form, &postgres, CrudOperation::Delete, &data).unwrap(),
+            r#"DELETE FROM "app"."users" WHERE "id" = {{id}}"#
+        );
+
+        // Record lookups (row filter and uniqueness checks, audit snapshots)
+        // read the section's table too, falling back to its id
+        assert_eq!(
+            record_query(&section, &mysql).unwrap(),
+            "SELECT * FROM `app`.`users` WHERE `id` = {{id}}"
+        );
+        let untabled = SectionConfig {
+            table: None,
+            ..section.clone()
+        };
+        assert_eq!(
+            record_query(&untabled, &postgres).unwrap(),
+            r#"SELECT * FROM "users" WHERE "id" = {{id}}"#
+        );
+        let api: DataSourceConfig =
+            serde_yaml::from_str("{ type: api, base_url: http://localhost }").unwrap();
+        assert!(record_query(&section, &api).is_none());
+    }
+
+    #[test]
+    fn test_bulk_update() {
+        let section = section(
+            r#"
+id: orders
+name: Orders
+actions:
+  - id: edit
+    name: Edit
+    type: form
+    data_source: db
+    required_scopes: []
+    fields:
+      - { id: status, name: Status, field_type: text }
+      - { id: shipped_on, name: Shipped, field_type: date }
+"#,
+        );
+        let data = HashMap::from([("status".to_string(), json!("shipped"))]);
+        let filter = HashMap::from([
+            ("status".to_string(), json!("paid")),
+            ("shipped_on".to_string(), Value::Null),
+        ]);
+
+        let bulk = bulk_update(
+            &section,
+            &section.actions[0],
+            &DatabaseType::Postgres,
+            &data,
+            &filter,
+            &[RowCondition {
+                field: "tenant_id".to_string(),
+                value: json!("t1"),
+            }],
+        )
+        .unwrap();
+        let condition = r#" WHERE "shipped_on" IS NULL AND "status" = {{where_status}} AND "tenant_id" = {{row_filter_0}}"#;
+        assert_eq!(
+            bulk.update,
+            format!(
+                r#"UPDATE "orders" SET "status" = {{{{status}}}}{}"#,
+                condition
+            )
+        );
+        assert_eq!(bulk.params["where_status"], json!("paid"));
+        assert_eq!(bulk.params["status"], json!("shipped"));
+        assert_eq!(bulk.params["row_filter_0"], json!("t1"));
+
+        let none = HashMap::new();
+        assert!(bulk_update(
+            &section,
+            &section.actions[0],
+            &DatabaseType::Sqlite,
+            &none,
+            &filter,
+            &[]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_generated_document_queries() {
+        let section = section(
+            r#"
+id: products
+name: Products
+actions:
+  - id: view
+    name: View
+    type: view
+    data_source: search
+    generated: true
+    required_scopes: []
+    fields:
+      - { id: title, name: Title, field_type: text }
+"#,
+        );
+        let view = &section.actions[0];
+        let params = HashMap::from([("id".to_string(), json!("abc"))]);
+
+        let elasticsearch = DataSourceConfig::Elasticsearch {
+            nodes: vec!["http://localhost:9200".to_string()],
+            index: "products".to_string(),
+            auth: None,
+            tls: None,
+            init: Default::default(),
+        };
+        let query: Value = serde_json::from_str(
+            &action_target(&section, view, &elasticsearch, CrudOperation::View, &params).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            query,
+            json!({"query": {"ids": {"values": ["abc"]}}, "_source": ["id", "title"]})
+        );
+        assert!(action_target(
+            &section,
+            view,
+            &elasticsearch,
+            CrudOperation::View,
+            &HashMap::new()
+        )
+        .is_err());
+
+        let api = DataSourceConfig::Api {
+            base_url: "http://localhost".to_string(),
+            headers: None,
+            auth: None,
+            pagination: None,
+            response: None,
+            init: Default::default(),
+        };
+        assert!(action_target(&section, view, &api, CrudOperation::View, &params).is_err());
+    }
+}
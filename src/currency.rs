@@ -0,0 +1,331 @@
+//! Currency amounts
+//!
+//! Rows of list, view and summary actions carry their currency fields
+//! written out under `_formatted`, e.g. `{"total": "1.234,50 €"}`, with the
+//! field's symbol, decimal places and locale. Backoffices with a
+//! `currency.reporting_currency` also get each amount converted to it under
+//! `_converted`, using configured rates or those of a provider query.
+
+use crate::config::{CurrencyConfig, CurrencyFieldConfig, FieldConfig, FieldType};
+use crate::data_source::DataSource;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Symbols of common currencies, used when a field sets none
+const SYMBOLS: &[(&str, &str)] = &[
+    ("USD", "$"),
+    ("EUR", "€"),
+    ("GBP", "£"),
+    ("JPY", "¥"),
+    ("CNY", "¥"),
+    ("INR", "₹"),
+    ("KRW", "₩"),
+    ("BRL", "R$"),
+    ("CAD", "CA$"),
+    ("AUD", "A$"),
+    ("MXN", "MX$"),
+];
+
+/// Write an amount with the symbol, decimal places and digit grouping of a
+/// currency field, e.g. `$1,234.50` or `1.234,50 €` for `de-DE`
+pub fn format(amount: f64, config: &CurrencyFieldConfig) -> String {
+    let (group, decimal, symbol_after) = separators(config.locale.as_deref().unwrap_or("en"));
+    let symbol = config
+        .symbol
+        .clone()
+        .or_else(|| {
+            SYMBOLS
+                .iter()
+                .find(|(code, _)| code.eq_ignore_ascii_case(&config.currency_code))
+                .map(|(_, symbol)| symbol.to_string())
+        })
+        .unwrap_or_else(|| config.currency_code.clone());
+
+    let digits = format!("{:.*}", config.decimal_places, amount.abs());
+    let (whole, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+    let mut number = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            number.push_str(group);
+        }
+        number.push(digit);
+    }
+    if !fraction.is_empty() {
+        number.push(decimal);
+        number.push_str(fraction);
+    }
+
+    // Zero amounts rounded from negatives aren't written as `-0.00`
+    let sign = if amount < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        "-"
+    } else {
+        ""
+    };
+    if symbol_after {
+        format!("{}{} {}", sign, number, symbol)
+    } else if symbol.chars().all(char::is_alphabetic) {
+        format!("{}{} {}", sign, symbol, number)
+    } else {
+        format!("{}{}{}", sign, symbol, number)
+    }
+}
+
+/// Group and decimal separators of a locale, and whether the symbol
+/// follows the amount
+fn separators(locale: &str) -> (&'static str, char, bool) {
+    let language = locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match language.as_str() {
+        "de" | "es" | "it" | "pt" | "da" | "tr" | "id" => (".", ',', true),
+        "nl" => (".", ',', false),
+        "fr" | "pl" | "ru" | "sv" | "nb" | "no" | "fi" | "cs" | "uk" => ("\u{a0}", ',', true),
+        _ => (",", '.', false),
+    }
+}
+
+/// Exchange rates to a backoffice's reporting currency
+pub struct Conversion<'a> {
+    config: &'a CurrencyConfig,
+    rates: HashMap<String, f64>,
+}
+
+impl Conversion<'_> {
+    /// An amount in the reporting currency, when the rate of its currency is known
+    pub fn convert(&self, amount: f64, currency: &str) -> Option<f64> {
+        if currency.eq_ignore_ascii_case(&self.config.reporting_currency) {
+            return Some(amount);
+        }
+        self.rates
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(currency))
+            .map(|(_, rate)| *rate)
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| amount / rate)
+    }
+
+    fn format(&self, amount: f64) -> String {
+        format(
+            amount,
+            &CurrencyFieldConfig {
+                currency_code: self.config.reporting_currency.clone(),
+                symbol: self.config.symbol.clone(),
+                decimal_places: self.config.decimal_places,
+                locale: self.config.locale.clone(),
+                ..Default::default()
+            },
+        )
+    }
+}
+
+/// Rates fetched from a provider, and when
+type FetchedRates = (Instant, HashMap<String, f64>);
+
+/// Rates last fetched from a provider, keyed by backoffice
+fn provider_cache() -> &'static Mutex<HashMap<String, FetchedRates>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, FetchedRates>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// The rates of a backoffice: its configured ones, overridden by those of
+/// its provider
+///
+/// Fetched rates are reused for the provider's `cache_ttl_secs`. When a
+/// fetch fails the last fetched rates are kept, so a provider outage only
+/// makes rates stale.
+pub async fn conversion<'a>(
+    config: &'a CurrencyConfig,
+    backoffice_id: &str,
+    provider: Option<&dyn DataSource>,
+) -> Conversion<'a> {
+    let mut rates = config.rates.clone();
+    let (Some(provider_config), Some(provider)) = (&config.provider, provider) else {
+        return Conversion { config, rates };
+    };
+
+    let cached = provider_cache().lock().unwrap().get(backoffice_id).cloned();
+    let fresh = cached.as_ref().is_some_and(|(fetched_at, _)| {
+        fetched_at.elapsed() < Duration::from_secs(provider_config.cache_ttl_secs)
+    });
+    let fetched = if fresh {
+        cached.map(|(_, rates)| rates)
+    } else {
+        debug!(backoffice = %backoffice_id, "Fetching exchange rates");
+        match provider.execute_query(&provider_config.query, None).await {
+            Ok(rows) => {
+                let fetched = provider_rates(
+                    &rows,
+                    &provider_config.currency_field,
+                    &provider_config.rate_field,
+                );
+                provider_cache()
+                    .lock()
+                    .unwrap()
+                    .insert(backoffice_id.to_string(), (Instant::now(), fetched.clone()));
+                Some(fetched)
+            }
+            Err(e) => {
+                warn!(backoffice = %backoffice_id, error = %e, "Fetching exchange rates failed");
+                cached.map(|(_, rates)| rates)
+            }
+        }
+    };
+    rates.extend(fetched.unwrap_or_default());
+    Conversion { config, rates }
+}
+
+/// Rates out of provider rows with a currency and a rate each, or out of
+/// the `rates` object of the first row
+fn provider_rates(
+    rows: &[HashMap<String, Value>],
+    currency_field: &str,
+    rate_field: &str,
+) -> HashMap<String, f64> {
+    if let Some(Value::Object(rates)) = rows.first().and_then(|row| row.get("rates")) {
+        return rates
+            .iter()
+            .filter_map(|(code, rate)| Some((code.clone(), number(rate)?)))
+            .collect();
+    }
+    rows.iter()
+        .filter_map(|row| {
+            let code = row.get(currency_field)?.as_str()?;
+            Some((code.to_string(), number(row.get(rate_field)?)?))
+        })
+        .collect()
+}
+
+/// Numbers, including decimals returned as text (e.g. Postgres `NUMERIC`)
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whether any field is a currency field
+pub fn has_currency(fields: &[FieldConfig]) -> bool {
+    fields
+        .iter()
+        .any(|f| matches!(f.field_type, FieldType::Currency { .. }))
+}
+
+/// Add the formatted amounts of currency fields to rows under `_formatted`,
+/// and with a conversion, the amounts in the reporting currency under
+/// `_converted`
+///
+/// Values that aren't numbers, such as masked ones, are left out.
+pub fn annotate_rows(
+    rows: &mut [HashMap<String, Value>],
+    fields: &[FieldConfig],
+    conversion: Option<&Conversion>,
+) {
+    let currencies: Vec<(&str, &CurrencyFieldConfig)> = fields
+        .iter()
+        .filter_map(|f| match &f.field_type {
+            FieldType::Currency { config } => Some((f.id.as_str(), config)),
+            _ => None,
+        })
+        .collect();
+    if currencies.is_empty() {
+        return;
+    }
+
+    for row in rows.iter_mut() {
+        let mut formatted = Map::new();
+        let mut converted = Map::new();
+        for (id, config) in &currencies {
+            let Some(amount) = row.get(*id).and_then(number) else {
+                continue;
+            };
+            formatted.insert(id.to_string(), json!(format(amount, config)));
+            let Some(conversion) = conversion else {
+                continue;
+            };
+            if let Some(amount) = conversion.convert(amount, &config.currency_code) {
+                converted.insert(
+                    id.to_string(),
+                    json!({
+                        "amount": amount,
+                        "currency": conversion.config.reporting_currency,
+                        "formatted": conversion.format(amount),
+                    }),
+                );
+            }
+        }
+        if !formatted.is_empty() {
+            row.insert("_formatted".to_string(), Value::Object(formatted));
+        }
+        if !converted.is_empty() {
+            row.insert("_converted".to_string(), Value::Object(converted));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn currency(yaml: &str) -> CurrencyFieldConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_format() {
+        let usd = CurrencyFieldConfig::default();
+        assert_eq!(format(1234.5, &usd), "$1,234.50");
+        assert_eq!(format(-0.001, &usd), "$0.00");
+        assert_eq!(format(-1234567.0, &usd), "-$1,234,567.00");
+
+        let eur = currency(
+            "{currency_code: EUR, allow_negative: false, decimal_places: 2, locale: de-DE}",
+        );
+        assert_eq!(format(1234.5, &eur), "1.234,50 €");
+
+        let chf = currency("{currency_code: CHF, allow_negative: false, decimal_places: 0}");
+        assert_eq!(format(999.6, &chf), "CHF 1,000");
+    }
+
+    #[tokio::test]
+    async fn test_annotate_rows() {
+        let fields: Vec<FieldConfig> = serde_yaml::from_str(
+            r#"
+- id: total
+  name: Total
+  field_type: currency
+  config: {currency_code: EUR, allow_negative: false, decimal_places: 2}
+- { id: name, name: Name, field_type: text }
+"#,
+        )
+        .unwrap();
+        let config: CurrencyConfig =
+            serde_yaml::from_str("{reporting_currency: USD, rates: {EUR: 0.8}}").unwrap();
+        let conversion = conversion(&config, "shop", None).await;
+
+        let mut rows = vec![
+            HashMap::from([("total".to_string(), json!(100))]),
+            HashMap::from([("total".to_string(), json!("****"))]),
+        ];
+        annotate_rows(&mut rows, &fields, Some(&conversion));
+        assert_eq!(rows[0]["_formatted"], json!({"total": "€100.00"}));
+        assert_eq!(
+            rows[0]["_converted"],
+            json!({"total": {"amount": 125.0, "currency": "USD", "formatted": "$125.00"}})
+        );
+        assert!(!rows[1].contains_key("_formatted"));
+
+        // Rows of a provider
+        let rows = vec![HashMap::from([(
+            "rates".to_string(),
+            json!({"EUR": 0.9, "GBP": "0.75"}),
+        )])];
+        let rates = provider_rates(&rows, "currency", "rate");
+        assert_eq!(rates["GBP"], 0.75);
+    }
+}
@@ -1,14 +1,22 @@
-use crate::config::{DataSourceConfig, DatabaseType};
+pub use crate::circuit_breaker::CircuitOpenError;
+use crate::circuit_breaker::{self, CircuitBreakerDataSource, CircuitBreakers};
+use crate::config::{
+    ApiAuthConfig, ApiPaginationConfig, ApiPaginationStyle, ApiResponseMapping,
+    CircuitBreakerConfig, DataSourceConfig, DatabaseType, FileFormat, FileWriteMode, TlsConfig,
+};
+use crate::health::{DataSourceHealth, HealthMonitor, MonitoredDataSource};
 use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use sqlx::{
-    any::{AnyPoolOptions, AnyRow},
-    AnyPool, Column, Row, TypeInfo,
-};
-use std::collections::HashMap;
+use sqlx::{Column, Row, TypeInfo, ValueRef};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
+/// Largest page size requests may ask for
+pub const MAX_PAGE_SIZE: usize = 1000;
+
 /// Pagination parameters for data source queries
 #[derive(Debug, Clone)]
 pub struct PaginationParams {
@@ -18,8 +26,10 @@ pub struct PaginationParams {
 }
 
 impl PaginationParams {
+    /// Pages count from 1; page 0 is read as the first page
     pub fn new(page: usize, page_size: usize) -> Self {
-        let offset = (page - 1) * page_size;
+        let page = page.max(1);
+        let offset = (page - 1).saturating_mul(page_size);
         Self {
             page,
             page_size,
@@ -28,6 +38,32 @@ impl PaginationParams {
     }
 }
 
+/// One page of a query's rows
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Page {
+    pub rows: Vec<HashMap<String, Value>>,
+    /// Total item count, when the source reports it
+    pub total: Option<u64>,
+    /// Cursor of the next page, for sources paginated by cursor
+    pub next_cursor: Option<String>,
+}
+
+impl Page {
+    /// A page sliced from every row of a query
+    pub fn slice(rows: Vec<HashMap<String, Value>>, pagination: &PaginationParams) -> Self {
+        let total = rows.len() as u64;
+        Self {
+            rows: rows
+                .into_iter()
+                .skip(pagination.offset)
+                .take(pagination.page_size)
+                .collect(),
+            total: Some(total),
+            next_cursor: None,
+        }
+    }
+}
+
 /// Data source trait for executing queries
 #[async_trait::async_trait]
 pub trait DataSource: Send + Sync {
@@ -45,121 +81,876 @@ pub trait DataSource: Send + Sync {
         pagination: Option<&PaginationParams>,
     ) -> Result<Vec<HashMap<String, Value>>>;
 
+    /// Fetch one page of a query with the total item count
+    ///
+    /// Sources that page natively fetch only the page; the others read every
+    /// row and slice the page from them.
+    async fn execute_page(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: &PaginationParams,
+    ) -> Result<Page> {
+        let rows = self.execute_query(query, params).await?;
+        Ok(Page::slice(rows, pagination))
+    }
+
     async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value>;
+
+    /// Delete the record whose id is `data["id"]`, for the delete endpoint
+    ///
+    /// `query` is the action's delete statement or endpoint, which sources
+    /// mutated by statements run like any mutation. Document and file
+    /// sources delete by id instead.
+    async fn execute_delete(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        self.execute_mutation(query, data).await
+    }
+
+    /// Subscribe to rows pushed by the source as they happen
+    ///
+    /// Sources without a live feed return an error.
+    async fn subscribe(&self, _query: &str) -> Result<RowStream> {
+        Err(anyhow!(
+            "This data source does not support live subscriptions"
+        ))
+    }
+
+    /// Issue a URL that lets a browser read or write an object directly
+    ///
+    /// Sources without object storage return an error.
+    async fn presign(
+        &self,
+        _key: &str,
+        _method: PresignMethod,
+        _expires_in: std::time::Duration,
+    ) -> Result<PresignedUrl> {
+        Err(anyhow!("This data source does not issue presigned URLs"))
+    }
+
+    /// Verify the source is reachable, for periodic health checks
+    ///
+    /// Sources without a cheaper check count as healthy once created.
+    async fn health_check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether [`DataSource::execute_transaction`] is supported
+    fn supports_transactions(&self) -> bool {
+        false
+    }
+
+    /// Run mutations atomically: either all of them apply or none do
+    async fn execute_transaction(&self, _mutations: &[Mutation]) -> Result<Vec<Value>> {
+        Err(anyhow!("This data source does not support transactions"))
+    }
+}
+
+/// Shared instances are used like owned ones
+#[async_trait::async_trait]
+impl<T: DataSource + ?Sized> DataSource for Arc<T> {
+    async fn execute_query(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        (**self).execute_query(query, params).await
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        (**self)
+            .execute_query_paginated(query, params, pagination)
+            .await
+    }
+
+    async fn execute_page(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: &PaginationParams,
+    ) -> Result<Page> {
+        (**self).execute_page(query, params, pagination).await
+    }
+
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        (**self).execute_mutation(query, data).await
+    }
+
+    async fn execute_delete(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        (**self).execute_delete(query, data).await
+    }
+
+    async fn subscribe(&self, query: &str) -> Result<RowStream> {
+        (**self).subscribe(query).await
+    }
+
+    async fn presign(
+        &self,
+        key: &str,
+        method: PresignMethod,
+        expires_in: std::time::Duration,
+    ) -> Result<PresignedUrl> {
+        (**self).presign(key, method, expires_in).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        (**self).health_check().await
+    }
+
+    fn supports_transactions(&self) -> bool {
+        (**self).supports_transactions()
+    }
+
+    async fn execute_transaction(&self, mutations: &[Mutation]) -> Result<Vec<Value>> {
+        (**self).execute_transaction(mutations).await
+    }
+}
+
+/// One mutation of a transaction
+#[derive(Debug, Clone, Default)]
+pub struct Mutation {
+    pub query: String,
+    pub data: HashMap<String, Value>,
+    /// Data keys set to the id generated by an earlier insert of the same
+    /// transaction, given by its index
+    pub generated_ids: Vec<(String, usize)>,
+    /// Rows the mutation must affect; any other count rolls the transaction
+    /// back with [`UnexpectedRowCount`]
+    pub expected_rows: Option<u64>,
+}
+
+/// Returned when a transaction's mutation affects other than its
+/// [`Mutation::expected_rows`]
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Expected {expected} records to match, found {affected}")]
+pub struct UnexpectedRowCount {
+    pub expected: u64,
+    pub affected: u64,
+}
+
+/// Rows pushed by a live data source subscription
+pub type RowStream = futures_util::stream::BoxStream<'static, Result<HashMap<String, Value>>>;
+
+/// Operation a presigned URL grants
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresignMethod {
+    /// Download the object
+    #[default]
+    Get,
+    /// Upload the object
+    Put,
+}
+
+/// A presigned request the browser sends as-is
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PresignedUrl {
+    pub url: String,
+    pub method: String,
+    /// Headers the request must carry for the signature to match
+    pub headers: HashMap<String, String>,
+    pub expires_in: u64,
 }
 
 /// Database data source with connection pooling
 pub struct DatabaseDataSource {
-    pool: Arc<AnyPool>,
+    pool: DatabasePool,
+    /// Read replicas; queries fall back to `pool` when they fail
+    replicas: Vec<DatabasePool>,
     db_type: DatabaseType,
 }
 
+/// Connection pool of the configured backend
+///
+/// Native pools are used instead of `sqlx::Any`, which rejects columns of
+/// types it cannot represent (UUID, NUMERIC, timestamps, arrays, ...).
+#[derive(Clone)]
+enum DatabasePool {
+    Postgres(sqlx::PgPool, ParamTypes),
+    MySql(sqlx::MySqlPool),
+    Sqlite(sqlx::SqlitePool),
+}
+
+/// Types Postgres infers for the parameters of each statement, by SQL
+type ParamTypes = Arc<std::sync::Mutex<HashMap<String, Arc<Vec<String>>>>>;
+
+/// Statements whose parameter types are remembered per pool
+const MAX_PARAM_TYPES: usize = 1024;
+
+/// Run the same code against whichever backend pool is in use
+macro_rules! with_pool {
+    ($pool:expr, $p:ident => $body:expr) => {
+        match $pool {
+            DatabasePool::Postgres($p, _) => $body,
+            DatabasePool::MySql($p) => $body,
+            DatabasePool::Sqlite($p) => $body,
+        }
+    };
+}
+
+impl DatabasePool {
+    /// Connect to a database; lazy pools only connect on first use
+    async fn connect(connection_string: &str, db_type: &DatabaseType, lazy: bool) -> Result<Self> {
+        macro_rules! connect {
+            ($options:ty, $variant:expr) => {{
+                let options = <$options>::new().max_connections(5);
+                let pool = if lazy {
+                    options.connect_lazy(connection_string)
+                } else {
+                    options.connect(connection_string).await
+                };
+                pool.map($variant)
+            }};
+        }
+
+        match db_type {
+            DatabaseType::Postgres => connect!(sqlx::postgres::PgPoolOptions, |pool| {
+                DatabasePool::Postgres(pool, ParamTypes::default())
+            }),
+            DatabaseType::MySQL => connect!(sqlx::mysql::MySqlPoolOptions, DatabasePool::MySql),
+            DatabaseType::Sqlite => connect!(sqlx::sqlite::SqlitePoolOptions, DatabasePool::Sqlite),
+        }
+        .map_err(|e| anyhow!("Failed to connect to database: {}", e))
+    }
+
+    /// Rewrite named placeholders into this backend's positional form
+    fn prepare(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> (String, Vec<Value>) {
+        let numbered = matches!(self, DatabasePool::Postgres(..));
+        expand_named_params(query, params, numbered)
+    }
+
+    /// Types Postgres infers for the parameters of a statement, such as
+    /// `INT8` for `$1` in `"id" = $1` on a bigint column
+    ///
+    /// Statements are described once per pool. Other backends, and
+    /// statements that can't be described, report no types.
+    async fn param_types(&self, sql: &str) -> Arc<Vec<String>> {
+        let DatabasePool::Postgres(pool, cache) = self else {
+            return Arc::default();
+        };
+        if let Some(types) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(sql) {
+            return types.clone();
+        }
+
+        let types: Arc<Vec<String>> = match sqlx::Executor::describe(pool, sql).await {
+            Ok(described) => match described.parameters() {
+                Some(sqlx::Either::Left(types)) => {
+                    Arc::new(types.iter().map(|t| t.name().to_string()).collect())
+                }
+                _ => Arc::default(),
+            },
+            Err(e) => {
+                debug!(error = %e, "Could not describe statement parameters");
+                return Arc::default();
+            }
+        };
+        let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.len() >= MAX_PARAM_TYPES {
+            cache.clear();
+        }
+        cache.insert(sql.to_string(), types.clone());
+        types
+    }
+
+    async fn fetch_all(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let (sql, values) = self.prepare(query, params);
+        let types = self.param_types(&sql).await;
+        let rows = match self {
+            DatabasePool::Postgres(pool, _) => bind_sql_params(sqlx::query(&sql), &values, &types)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| row_to_map(row, pg_value))
+                .collect(),
+            DatabasePool::MySql(pool) => bind_sql_params(sqlx::query(&sql), &values, &types)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| row_to_map(row, mysql_value))
+                .collect(),
+            DatabasePool::Sqlite(pool) => bind_sql_params(sqlx::query(&sql), &values, &types)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| row_to_map(row, sqlite_value))
+                .collect(),
+        };
+        Ok(rows)
+    }
+
+    async fn execute(&self, query: &str, params: &HashMap<String, Value>) -> Result<u64> {
+        let (sql, values) = self.prepare(query, Some(params));
+        let types = self.param_types(&sql).await;
+        Ok(
+            with_pool!(self, pool => bind_sql_params(sqlx::query(&sql), &values, &types)
+            .execute(pool)
+            .await?
+            .rows_affected()),
+        )
+    }
+
+    /// Run mutations in one transaction, returning the rows each affected
+    ///
+    /// Ids generated by inserts that later mutations refer to are read back
+    /// right after the insert, on the transaction's connection.
+    async fn execute_transaction(&self, mutations: &[Mutation]) -> Result<Vec<u64>> {
+        let referenced: HashSet<usize> = mutations
+            .iter()
+            .flat_map(|m| m.generated_ids.iter().map(|(_, index)| *index))
+            .collect();
+        let last_insert_id = match self {
+            DatabasePool::Postgres(..) => "SELECT lastval()",
+            DatabasePool::MySql(_) => "SELECT CAST(LAST_INSERT_ID() AS SIGNED)",
+            DatabasePool::Sqlite(_) => "SELECT last_insert_rowid()",
+        };
+
+        with_pool!(self, pool => {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(|e| with_message(e.into(), "Failed to start transaction"))?;
+
+            let mut results = Vec::with_capacity(mutations.len());
+            let mut generated: HashMap<usize, i64> = HashMap::new();
+            for (index, mutation) in mutations.iter().enumerate() {
+                debug!(query = %mutation.query, "Executing mutation in transaction");
+                let mut data = mutation.data.clone();
+                for (key, source) in &mutation.generated_ids {
+                    let id = generated.get(source).ok_or_else(|| {
+                        anyhow!("Mutation {} refers to the id of mutation {}, which runs later", index, source)
+                    })?;
+                    data.insert(key.clone(), Value::from(*id));
+                }
+
+                let (sql, values) = self.prepare(&mutation.query, Some(&data));
+                let types = self.param_types(&sql).await;
+                let result = bind_sql_params(sqlx::query(&sql), &values, &types)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| {
+                        with_message(
+                            e.into(),
+                            "Mutation execution failed, transaction rolled back",
+                        )
+                    })?;
+                if let Some(expected) = mutation.expected_rows {
+                    if result.rows_affected() != expected {
+                        return Err(UnexpectedRowCount {
+                            expected,
+                            affected: result.rows_affected(),
+                        }
+                        .into());
+                    }
+                }
+                results.push(result.rows_affected());
+
+                if referenced.contains(&index) {
+                    let id: i64 = sqlx::query_scalar(last_insert_id)
+                        .fetch_one(&mut *tx)
+                        .await
+                        .map_err(|e| with_message(e.into(), "Failed to read generated id"))?;
+                    generated.insert(index, id);
+                }
+            }
+
+            tx.commit()
+                .await
+                .map_err(|e| with_message(e.into(), "Failed to commit transaction"))?;
+            Ok(results)
+        })
+    }
+}
+
+/// Rewrite `{{name}}` and `:name` placeholders into positional ones (`$1`
+/// when `numbered`, `?` otherwise), returning the values to bind in order
+///
+/// Placeholders inside string literals, quoted identifiers and comments are
+/// left alone, as are Postgres `::type` casts. Names missing from `params`
+/// bind as NULL so optional filters can be written as
+/// `({{status}} IS NULL OR status = {{status}})`.
+fn expand_named_params(
+    query: &str,
+    params: Option<&HashMap<String, Value>>,
+    numbered: bool,
+) -> (String, Vec<Value>) {
+    let is_name_start = |c: char| c.is_ascii_alphabetic() || c == '_';
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut sql = String::with_capacity(query.len());
+    let mut values = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    let mut placeholder = |name: &str, sql: &mut String| {
+        let value = params
+            .and_then(|params| params.get(name))
+            .cloned()
+            .unwrap_or(Value::Null);
+        if !numbered {
+            values.push(value);
+            sql.push('?');
+            return;
+        }
+        let position = match names.iter().position(|n| *n == name) {
+            Some(position) => position,
+            None => {
+                names.push(name.to_string());
+                values.push(value);
+                names.len() - 1
+            }
+        };
+        sql.push_str(&format!("${}", position + 1));
+    };
+
+    let mut rest = query;
+    while let Some(c) = rest.chars().next() {
+        // Literals, quoted identifiers and comments are copied verbatim
+        let verbatim_end = match c {
+            '\'' | '"' | '`' => Some(rest[1..].find(c).map_or(rest.len(), |end| end + 2)),
+            '-' if rest.starts_with("--") => Some(rest.find('\n').unwrap_or(rest.len())),
+            '/' if rest.starts_with("/*") => {
+                Some(rest.find("*/").map_or(rest.len(), |end| end + 2))
+            }
+            _ => None,
+        };
+        if let Some(end) = verbatim_end {
+            sql.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        if rest.starts_with("{{") {
+            if let Some(end) = rest.find("}}") {
+                let name = rest[2..end].trim();
+                if name.starts_with(is_name_start) && name.chars().all(is_name) {
+                    placeholder(name, &mut sql);
+                    rest = &rest[end + 2..];
+                    continue;
+                }
+            }
+        }
+
+        if rest.starts_with("::") {
+            sql.push_str("::");
+            rest = &rest[2..];
+            continue;
+        }
+
+        if c == ':' && rest[1..].starts_with(is_name_start) {
+            let end = rest[1..]
+                .find(|c: char| !is_name(c))
+                .map_or(rest.len(), |end| end + 1);
+            placeholder(&rest[1..end], &mut sql);
+            rest = &rest[end..];
+            continue;
+        }
+
+        sql.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    (sql, values)
+}
+
+/// Bind JSON values to a query with the closest native type
+///
+/// Where the parameter's type is known (see `DatabasePool::param_types`),
+/// values are converted to it, so a string id binds as a number or UUID
+/// and compares with the column as is. Values that don't convert bind
+/// unchanged.
+fn bind_sql_params<'q, DB>(
+    query: sqlx::query::Query<'q, DB, <DB as sqlx::database::HasArguments<'q>>::Arguments>,
+    values: &[Value],
+    types: &[String],
+) -> sqlx::query::Query<'q, DB, <DB as sqlx::database::HasArguments<'q>>::Arguments>
+where
+    DB: sqlx::Database,
+    bool: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    f64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    String: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<String>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<bool>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<i64>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<f64>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<sqlx::types::Uuid>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+{
+    values
+        .iter()
+        .enumerate()
+        .fold(query, |query, (index, value)| {
+            let ty = types.get(index).map(String::as_str).unwrap_or_default();
+            if ty == "UUID" {
+                match value {
+                    Value::Null => return query.bind(None::<sqlx::types::Uuid>),
+                    Value::String(s) => {
+                        if let Ok(uuid) = sqlx::types::Uuid::parse_str(s.trim()) {
+                            return query.bind(Some(uuid));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            match param_value(value, ty) {
+                Value::Null => match ty {
+                    "INT2" | "INT4" | "INT8" => query.bind(None::<i64>),
+                    "FLOAT4" | "FLOAT8" => query.bind(None::<f64>),
+                    "BOOL" => query.bind(None::<bool>),
+                    _ => query.bind(None::<String>),
+                },
+                Value::Bool(b) => query.bind(b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => query.bind(i),
+                    None => query.bind(n.as_f64().unwrap_or_default()),
+                },
+                Value::String(s) => query.bind(s),
+                // Arrays and objects are bound as JSON text
+                other => query.bind(other.to_string()),
+            }
+        })
+}
+
+/// A value converted to the type of its parameter, e.g. `"42"` to `42` for
+/// an `INT8` parameter
+fn param_value(value: &Value, ty: &str) -> Value {
+    let converted = match (ty, value) {
+        ("INT2" | "INT4" | "INT8", Value::String(s)) => {
+            s.trim().parse::<i64>().ok().map(Value::from)
+        }
+        ("FLOAT4" | "FLOAT8", Value::String(s)) => s.trim().parse::<f64>().ok().map(Value::from),
+        ("BOOL", Value::String(s)) => match s.trim() {
+            "true" | "1" => Some(Value::Bool(true)),
+            "false" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("TEXT" | "VARCHAR" | "BPCHAR" | "NAME", Value::Number(n)) => {
+            Some(Value::String(n.to_string()))
+        }
+        ("TEXT" | "VARCHAR" | "BPCHAR" | "NAME", Value::Bool(b)) => {
+            Some(Value::String(b.to_string()))
+        }
+        _ => None,
+    };
+    converted.unwrap_or_else(|| value.clone())
+}
+
+/// Round-robin position over read replicas, shared by all data sources
+static NEXT_REPLICA: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
 impl DatabaseDataSource {
     pub async fn new(connection_string: String, db_type: DatabaseType) -> Result<Self> {
+        Self::with_replicas(connection_string, &[], db_type).await
+    }
+
+    /// Connect to a primary for mutations and read replicas for queries
+    ///
+    /// Replicas connect lazily, so one that is down only costs a fallback to
+    /// the primary.
+    pub async fn with_replicas(
+        connection_string: String,
+        read_replicas: &[String],
+        db_type: DatabaseType,
+    ) -> Result<Self> {
         tracing::info!(
             db_type = ?db_type,
+            replicas = read_replicas.len(),
             "Connecting to database"
         );
 
-        let pool = AnyPoolOptions::new()
-            .max_connections(5)
-            .connect(&connection_string)
-            .await
-            .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
+        let pool = DatabasePool::connect(&connection_string, &db_type, false).await?;
+
+        let mut replicas = Vec::with_capacity(read_replicas.len());
+        for replica in read_replicas {
+            replicas.push(
+                DatabasePool::connect(replica, &db_type, true)
+                    .await
+                    .map_err(|e| anyhow!("Invalid read replica connection string: {}", e))?,
+            );
+        }
 
         tracing::info!("Database connection established");
 
         Ok(Self {
-            pool: Arc::new(pool),
+            pool,
+            replicas,
             db_type,
         })
     }
 
-    /// Convert a database row to a HashMap
-    fn row_to_map(row: &AnyRow) -> Result<HashMap<String, Value>> {
-        let mut map = HashMap::new();
+    /// Run a query on the next read replica, or on the primary when there
+    /// are no replicas or the replica fails
+    async fn fetch_all(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        if !self.replicas.is_empty() {
+            let index = NEXT_REPLICA.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+                % self.replicas.len();
+            match self.replicas[index].fetch_all(query, params).await {
+                Ok(rows) => return Ok(rows),
+                Err(e) => tracing::warn!(
+                    replica = index,
+                    error = %e,
+                    "Read replica query failed, falling back to the primary"
+                ),
+            }
+        }
+
+        self.pool
+            .fetch_all(query, params)
+            .await
+            .map_err(|e| with_message(e, "Query execution failed"))
+    }
+}
 
-        for (i, column) in row.columns().iter().enumerate() {
-            let column_name = column.name().to_string();
-            let type_info = column.type_info();
+/// Prefix an error's message while keeping the original error (e.g. the
+/// `sqlx::Error`) downcastable for outage detection
+fn with_message(e: anyhow::Error, message: &str) -> anyhow::Error {
+    let message = format!("{}: {}", message, e);
+    e.context(message)
+}
 
-            // Try to extract the value based on the type
-            let value = if type_info.is_null() {
+/// Convert a database row to a HashMap, decoding each column with `decode`
+fn row_to_map<R: Row>(
+    row: &R,
+    decode: fn(&R, usize, &str) -> Option<Value>,
+) -> HashMap<String, Value>
+where
+    usize: sqlx::ColumnIndex<R>,
+{
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let type_name = column.type_info().name();
+            let is_null = row.try_get_raw(i).map_or(true, |v| v.is_null());
+            let value = if is_null {
                 Value::Null
             } else {
-                let type_name = type_info.name();
-                match type_name {
-                    "TEXT" | "VARCHAR" | "CHAR" | "STRING" | "BPCHAR" => {
-                        if let Ok(val) = row.try_get::<String, _>(i) {
-                            Value::String(val)
-                        } else {
-                            Value::Null
-                        }
-                    }
-                    "INTEGER" | "INT" | "SMALLINT" | "BIGINT" | "INT4" | "INT2" | "INT8" => {
-                        if let Ok(val) = row.try_get::<i64, _>(i) {
-                            Value::Number(serde_json::Number::from(val))
-                        } else if let Ok(val) = row.try_get::<i32, _>(i) {
-                            Value::Number(serde_json::Number::from(val))
-                        } else {
-                            Value::Null
-                        }
-                    }
-                    "REAL" | "FLOAT" | "DOUBLE" | "NUMERIC" | "DECIMAL" | "FLOAT4" | "FLOAT8" => {
-                        if let Ok(val) = row.try_get::<f64, _>(i) {
-                            if let Some(num) = serde_json::Number::from_f64(val) {
-                                Value::Number(num)
-                            } else {
-                                Value::Null
-                            }
-                        } else {
-                            Value::Null
-                        }
-                    }
-                    "BOOLEAN" | "BOOL" => {
-                        if let Ok(val) = row.try_get::<bool, _>(i) {
-                            Value::Bool(val)
-                        } else {
-                            Value::Null
-                        }
-                    }
-                    "JSON" | "JSONB" => {
-                        if let Ok(val) = row.try_get::<String, _>(i) {
-                            serde_json::from_str(&val).unwrap_or(Value::Null)
-                        } else {
-                            Value::Null
-                        }
-                    }
-                    "TIMESTAMP" | "TIMESTAMPTZ" | "DATETIME" | "DATE" | "TIME" => {
-                        if let Ok(val) = row.try_get::<String, _>(i) {
-                            Value::String(val)
-                        } else {
-                            Value::Null
-                        }
-                    }
-                    _ => {
-                        // Try string as fallback
-                        if let Ok(val) = row.try_get::<String, _>(i) {
-                            Value::String(val)
-                        } else {
-                            tracing::warn!(
-                                column = %column_name,
-                                type_name = %type_name,
-                                "Unknown column type, using null"
-                            );
-                            Value::Null
-                        }
-                    }
-                }
+                decode(row, i, type_name).unwrap_or_else(|| {
+                    warn!(
+                        column = %column.name(),
+                        type_name = %type_name,
+                        "Unsupported column type, using null"
+                    );
+                    Value::Null
+                })
             };
+            (column.name().to_string(), value)
+        })
+        .collect()
+}
+
+/// Decode column `i` as `T`, or `None` when the types do not match
+fn column<'r, R, T>(row: &'r R, i: usize) -> Option<T>
+where
+    R: Row,
+    T: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+    usize: sqlx::ColumnIndex<R>,
+{
+    row.try_get(i).ok()
+}
+
+/// Decode column `i` as `T` without checking the column type, for values
+/// sent as text such as MySQL decimals or Postgres enums
+fn column_unchecked<'r, R, T>(row: &'r R, i: usize) -> Option<T>
+where
+    R: Row,
+    T: sqlx::Decode<'r, R::Database>,
+    usize: sqlx::ColumnIndex<R>,
+{
+    row.try_get_unchecked(i).ok()
+}
+
+/// Timestamps without a zone, in ISO 8601 form
+fn naive_datetime_json(value: chrono::NaiveDateTime) -> Value {
+    json!(value.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+}
+
+/// Timestamps with a zone, in RFC 3339 form
+fn utc_datetime_json(value: chrono::DateTime<chrono::Utc>) -> Value {
+    json!(value.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+}
+
+/// Binary values, base64 encoded
+fn bytes_json(value: Vec<u8>) -> Value {
+    use base64::Engine;
+
+    json!(base64::engine::general_purpose::STANDARD.encode(value))
+}
+
+fn pg_value(row: &sqlx::postgres::PgRow, i: usize, type_name: &str) -> Option<Value> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+    match type_name {
+        "BOOL" => column::<_, bool>(row, i).map(Value::Bool),
+        "INT2" => column::<_, i16>(row, i).map(|v| json!(v)),
+        "INT4" => column::<_, i32>(row, i).map(|v| json!(v)),
+        "INT8" => column::<_, i64>(row, i).map(|v| json!(v)),
+        "OID" => column::<_, sqlx::postgres::types::Oid>(row, i).map(|v| json!(v.0)),
+        "FLOAT4" => column::<_, f32>(row, i).map(|v| json!(v)),
+        "FLOAT8" => column::<_, f64>(row, i).map(|v| json!(v)),
+        // Exact decimal strings; converting to f64 would lose precision
+        "NUMERIC" => column_unchecked::<_, &[u8]>(row, i)
+            .and_then(pg_numeric)
+            .map(Value::String),
+        "UUID" => column::<_, uuid::Uuid>(row, i).map(|v| json!(v.to_string())),
+        "JSON" | "JSONB" => column::<_, Value>(row, i),
+        "DATE" => column::<_, NaiveDate>(row, i).map(|v| json!(v.to_string())),
+        "TIME" => column::<_, NaiveTime>(row, i).map(|v| json!(v.to_string())),
+        "TIMESTAMP" => column::<_, NaiveDateTime>(row, i).map(naive_datetime_json),
+        "TIMESTAMPTZ" => column::<_, DateTime<Utc>>(row, i).map(utc_datetime_json),
+        "BYTEA" => column::<_, Vec<u8>>(row, i).map(bytes_json),
+        "BOOL[]" => column::<_, Vec<Option<bool>>>(row, i).map(|v| json!(v)),
+        "INT2[]" => column::<_, Vec<Option<i16>>>(row, i).map(|v| json!(v)),
+        "INT4[]" => column::<_, Vec<Option<i32>>>(row, i).map(|v| json!(v)),
+        "INT8[]" => column::<_, Vec<Option<i64>>>(row, i).map(|v| json!(v)),
+        "FLOAT4[]" => column::<_, Vec<Option<f32>>>(row, i).map(|v| json!(v)),
+        "FLOAT8[]" => column::<_, Vec<Option<f64>>>(row, i).map(|v| json!(v)),
+        "TEXT[]" | "VARCHAR[]" | "BPCHAR[]" | "NAME[]" => {
+            column::<_, Vec<Option<String>>>(row, i).map(|v| json!(v))
+        }
+        "UUID[]" => column::<_, Vec<Option<uuid::Uuid>>>(row, i).map(|v| {
+            json!(v
+                .iter()
+                .map(|u| u.map(|u| u.to_string()))
+                .collect::<Vec<_>>())
+        }),
+        "DATE[]" => column::<_, Vec<Option<NaiveDate>>>(row, i).map(|v| {
+            json!(v
+                .iter()
+                .map(|d| d.map(|d| d.to_string()))
+                .collect::<Vec<_>>())
+        }),
+        "TIMESTAMP[]" => column::<_, Vec<Option<NaiveDateTime>>>(row, i).map(|v| {
+            Value::Array(
+                v.into_iter()
+                    .map(|t| t.map_or(Value::Null, naive_datetime_json))
+                    .collect(),
+            )
+        }),
+        "TIMESTAMPTZ[]" => column::<_, Vec<Option<DateTime<Utc>>>>(row, i).map(|v| {
+            Value::Array(
+                v.into_iter()
+                    .map(|t| t.map_or(Value::Null, utc_datetime_json))
+                    .collect(),
+            )
+        }),
+        "JSON[]" | "JSONB[]" => column::<_, Vec<Value>>(row, i).map(Value::Array),
+        // Text-like types (VARCHAR, CITEXT, enums, ...) arrive as UTF-8
+        _ => column_unchecked::<_, String>(row, i).map(Value::String),
+    }
+}
+
+/// Decimal text of a Postgres binary NUMERIC value
+///
+/// The value is a sign, a decimal scale and base-10000 digits, the first of
+/// which is multiplied by 10000^weight.
+fn pg_numeric(bytes: &[u8]) -> Option<String> {
+    let word = |at: usize| Some(i16::from_be_bytes([*bytes.get(at)?, *bytes.get(at + 1)?]));
+    let ndigits = word(0)? as usize;
+    let weight = word(2)? as i32;
+    let sign = word(4)? as u16;
+    let scale = word(6)? as u16 as usize;
+    if sign == 0xC000 {
+        return Some("NaN".to_string());
+    }
 
-            map.insert(column_name, value);
+    let digits = (0..ndigits)
+        .map(|d| word(8 + 2 * d))
+        .collect::<Option<Vec<i16>>>()?;
+    let digit = |index: i32| {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| digits.get(index).copied())
+            .unwrap_or(0)
+    };
+
+    let mut text = if sign == 0x4000 {
+        "-".to_string()
+    } else {
+        String::new()
+    };
+    if weight < 0 {
+        text.push('0');
+    } else {
+        text.push_str(&digit(0).to_string());
+        for index in 1..=weight {
+            text.push_str(&format!("{:04}", digit(index)));
         }
+    }
+
+    if scale > 0 {
+        let mut fraction = String::new();
+        let mut index = weight + 1;
+        while fraction.len() < scale {
+            fraction.push_str(&format!("{:04}", digit(index)));
+            index += 1;
+        }
+        fraction.truncate(scale);
+        text.push('.');
+        text.push_str(&fraction);
+    }
 
-        Ok(map)
+    Some(text)
+}
+
+fn mysql_value(row: &sqlx::mysql::MySqlRow, i: usize, type_name: &str) -> Option<Value> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+    match type_name {
+        "BOOLEAN" => column::<_, bool>(row, i).map(Value::Bool),
+        name if name.ends_with("UNSIGNED") => column::<_, u64>(row, i).map(|v| json!(v)),
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" => {
+            column::<_, i64>(row, i).map(|v| json!(v))
+        }
+        "FLOAT" => column::<_, f32>(row, i).map(|v| json!(v)),
+        "DOUBLE" => column::<_, f64>(row, i).map(|v| json!(v)),
+        // Decimals are sent as text; keep them exact
+        "DECIMAL" => column_unchecked::<_, String>(row, i).map(Value::String),
+        "DATE" => column::<_, NaiveDate>(row, i).map(|v| json!(v.to_string())),
+        "TIME" => column::<_, NaiveTime>(row, i).map(|v| json!(v.to_string())),
+        "DATETIME" => column::<_, NaiveDateTime>(row, i).map(naive_datetime_json),
+        "TIMESTAMP" => column::<_, DateTime<Utc>>(row, i).map(utc_datetime_json),
+        "JSON" => column::<_, Value>(row, i),
+        "BINARY" | "VARBINARY" | "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BIT"
+        | "GEOMETRY" => column_unchecked::<_, Vec<u8>>(row, i).map(bytes_json),
+        _ => column_unchecked::<_, String>(row, i).map(Value::String),
     }
 }
 
+fn sqlite_value(row: &sqlx::sqlite::SqliteRow, i: usize, type_name: &str) -> Option<Value> {
+    let value = match type_name {
+        "BOOLEAN" => column::<_, bool>(row, i).map(Value::Bool),
+        "INTEGER" => column::<_, i64>(row, i).map(|v| json!(v)),
+        "REAL" => column::<_, f64>(row, i).map(|v| json!(v)),
+        "BLOB" => column::<_, Vec<u8>>(row, i).map(bytes_json),
+        _ => None,
+    };
+
+    // Declared types are only hints in SQLite, so fall back on the type of
+    // the stored value (e.g. NUMERIC, DATETIME or untyped columns)
+    value.or_else(|| {
+        let stored = row.try_get_raw(i).ok()?.type_info().name().to_string();
+        match stored.as_str() {
+            "INTEGER" => column_unchecked::<_, i64>(row, i).map(|v| json!(v)),
+            "REAL" => column_unchecked::<_, f64>(row, i).map(|v| json!(v)),
+            "BLOB" => column_unchecked::<_, Vec<u8>>(row, i).map(bytes_json),
+            _ => column_unchecked::<_, String>(row, i).map(Value::String),
+        }
+    })
+}
 #[async_trait::async_trait]
 impl DataSource for DatabaseDataSource {
     async fn execute_query(
@@ -170,6 +961,10 @@ impl DataSource for DatabaseDataSource {
         self.execute_query_paginated(query, params, None).await
     }
 
+    async fn health_check(&self) -> Result<()> {
+        self.pool.fetch_all("SELECT 1", None).await.map(|_| ())
+    }
+
     async fn execute_query_paginated(
         &self,
         query: &str,
@@ -188,46 +983,66 @@ impl DataSource for DatabaseDataSource {
             query.to_string()
         };
 
+        // Parameter values can be personal data; only their names are logged
         tracing::info!(
             query = %final_query,
-            params = ?params,
+            params = ?params.map(|params| params.keys().collect::<Vec<_>>()),
             db_type = ?self.db_type,
             pagination = ?pagination,
             "Executing database query"
         );
 
-        // Execute query without parameter binding for now
-        // A full implementation would need to bind parameters properly using sqlx::query!
-        let rows = sqlx::query(&final_query)
-            .fetch_all(&*self.pool)
-            .await
-            .map_err(|e| anyhow!("Query execution failed: {}", e))?;
-
-        let mut results = Vec::new();
-        for row in &rows {
-            results.push(Self::row_to_map(row)?);
-        }
+        // Named placeholders are bound from params, never interpolated
+        let results = self.fetch_all(&final_query, params).await?;
 
         tracing::info!(row_count = results.len(), "Query executed successfully");
 
         Ok(results)
     }
 
-    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
-        tracing::info!(
-            query = %query,
-            data = ?data,
-            db_type = ?self.db_type,
+    /// The page with `LIMIT` and `OFFSET`, and its total from a `COUNT(*)`
+    /// of the query
+    async fn execute_page(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: &PaginationParams,
+    ) -> Result<Page> {
+        let query = query.trim().trim_end_matches(';');
+        let count = format!("SELECT COUNT(*) AS count FROM ({}) AS counted", query);
+        let total = self
+            .fetch_all(&count, params)
+            .await?
+            .first()
+            .and_then(|row| match row.get("count") {
+                Some(Value::String(s)) => s.parse().ok(),
+                Some(other) => other.as_u64(),
+                None => None,
+            });
+        let rows = self
+            .execute_query_paginated(query, params, Some(pagination))
+            .await?;
+        Ok(Page {
+            rows,
+            total,
+            next_cursor: None,
+        })
+    }
+
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        tracing::info!(
+            query = %query,
+            fields = ?data.keys().collect::<Vec<_>>(),
+            db_type = ?self.db_type,
             "Executing database mutation"
         );
 
         // Execute the mutation
-        let result = sqlx::query(query)
-            .execute(&*self.pool)
+        let rows_affected = self
+            .pool
+            .execute(query, data)
             .await
-            .map_err(|e| anyhow!("Mutation execution failed: {}", e))?;
-
-        let rows_affected = result.rows_affected();
+            .map_err(|e| with_message(e, "Mutation execution failed"))?;
 
         tracing::info!(
             rows_affected = rows_affected,
@@ -236,6 +1051,194 @@ impl DataSource for DatabaseDataSource {
 
         Ok(Value::Number(serde_json::Number::from(rows_affected)))
     }
+
+    fn supports_transactions(&self) -> bool {
+        true
+    }
+
+    /// Returns the rows affected by each mutation; the transaction rolls
+    /// back when any of them fails or affects other than its expected rows
+    async fn execute_transaction(&self, mutations: &[Mutation]) -> Result<Vec<Value>> {
+        tracing::info!(
+            mutations = mutations.len(),
+            db_type = ?self.db_type,
+            "Executing database transaction"
+        );
+
+        let results = self.pool.execute_transaction(mutations).await?;
+
+        tracing::info!(mutations = mutations.len(), "Transaction committed");
+        Ok(results
+            .into_iter()
+            .map(|rows_affected| Value::Number(rows_affected.into()))
+            .collect())
+    }
+}
+
+/// Credentials sent with every request of an HTTP data source
+#[derive(Debug, Clone, PartialEq)]
+enum HttpAuth {
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    /// Encoded API key, sent as `Authorization: ApiKey <key>`
+    ApiKey(String),
+    Bearer(String),
+    /// Bearer tokens fetched with the OAuth2 client-credentials grant
+    OAuth2 {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        scopes: Vec<String>,
+    },
+}
+
+/// OAuth2 access tokens per token endpoint, client and scopes, shared across
+/// data source instances
+fn oauth2_tokens() -> &'static std::sync::Mutex<HashMap<String, CachedToken>> {
+    static TOKENS: std::sync::OnceLock<std::sync::Mutex<HashMap<String, CachedToken>>> =
+        std::sync::OnceLock::new();
+    TOKENS.get_or_init(Default::default)
+}
+
+impl HttpAuth {
+    /// Resolve the auth config; `auth_type` is `basic`, `api_key`, `bearer` or `oauth2`
+    fn from_config(auth: &ApiAuthConfig) -> Result<Self> {
+        let required = |value: &Option<String>, name: &str| {
+            value
+                .clone()
+                .ok_or_else(|| anyhow!("'{}' auth requires {}", auth.auth_type, name))
+        };
+
+        match auth.auth_type.to_lowercase().as_str() {
+            "basic" => Ok(Self::Basic {
+                username: required(&auth.username, "a username")?,
+                password: auth.password.clone(),
+            }),
+            "api_key" | "apikey" => Ok(Self::ApiKey(required(&auth.token, "a token")?)),
+            "bearer" => Ok(Self::Bearer(required(&auth.token, "a token")?)),
+            "oauth2" | "client_credentials" => Ok(Self::OAuth2 {
+                token_url: required(&auth.token_url, "a token_url")?,
+                client_id: required(&auth.client_id, "a client_id")?,
+                client_secret: required(&auth.client_secret, "a client_secret")?,
+                scopes: auth.scopes.clone(),
+            }),
+            other => Err(anyhow!(
+                "Unsupported auth type '{}' (expected basic, api_key, bearer or oauth2)",
+                other
+            )),
+        }
+    }
+
+    async fn apply(
+        &self,
+        client: &reqwest::Client,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::RequestBuilder> {
+        Ok(match self {
+            Self::Basic { username, password } => request.basic_auth(username, password.as_ref()),
+            Self::ApiKey(key) => {
+                request.header(reqwest::header::AUTHORIZATION, format!("ApiKey {}", key))
+            }
+            Self::Bearer(token) => request.bearer_auth(token),
+            Self::OAuth2 { .. } => request.bearer_auth(self.oauth2_token(client).await?),
+        })
+    }
+
+    fn token_cache_key(&self) -> Option<String> {
+        match self {
+            Self::OAuth2 {
+                token_url,
+                client_id,
+                scopes,
+                ..
+            } => Some(format!("{}|{}|{}", token_url, client_id, scopes.join(" "))),
+            _ => None,
+        }
+    }
+
+    /// Forget a cached OAuth2 token the server rejected, so the next request
+    /// fetches a new one
+    fn invalidate(&self) {
+        if let Some(key) = self.token_cache_key() {
+            oauth2_tokens().lock().unwrap().remove(&key);
+        }
+    }
+
+    /// Client-credentials access token, cached until shortly before expiry
+    async fn oauth2_token(&self, client: &reqwest::Client) -> Result<String> {
+        use std::time::{Duration, Instant};
+
+        let Self::OAuth2 {
+            token_url,
+            client_id,
+            client_secret,
+            scopes,
+        } = self
+        else {
+            return Err(anyhow!("Not an OAuth2 auth config"));
+        };
+        let cache_key = self.token_cache_key().unwrap_or_default();
+
+        if let Some((token, expires_at)) = oauth2_tokens().lock().unwrap().get(&cache_key) {
+            if *expires_at > Instant::now() {
+                return Ok(token.clone());
+            }
+        }
+
+        debug!(token_url = %token_url, client_id = %client_id, "Requesting OAuth2 access token");
+
+        let mut form = vec![
+            ("grant_type", "client_credentials".to_string()),
+            ("client_id", client_id.clone()),
+            ("client_secret", client_secret.clone()),
+        ];
+        if !scopes.is_empty() {
+            form.push(("scope", scopes.join(" ")));
+        }
+
+        let response = client
+            .post(token_url)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| anyhow!("OAuth2 token request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!(
+                "OAuth2 token request failed {}: {}",
+                status,
+                error_text
+            ));
+        }
+
+        let body: Value = response.json().await?;
+        let token = body["access_token"]
+            .as_str()
+            .ok_or_else(|| anyhow!("OAuth2 token response has no access_token"))?
+            .to_string();
+        let expires_in = body["expires_in"].as_u64().unwrap_or(3600);
+        let expires_at = Instant::now()
+            + Duration::from_secs(expires_in.saturating_sub(TOKEN_REFRESH_MARGIN_SECS));
+
+        oauth2_tokens()
+            .lock()
+            .unwrap()
+            .insert(cache_key, (token.clone(), expires_at));
+
+        Ok(token)
+    }
+}
+
+/// HTTP client whose requests give up after `timeout`
+fn http_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new())
 }
 
 /// API data source
@@ -243,30 +1246,101 @@ pub struct ApiDataSource {
     base_url: String,
     client: reqwest::Client,
     headers: HashMap<String, String>,
-    timeout_secs: u64,
     max_retries: u32,
+    auth: Option<HttpAuth>,
+    pagination: Option<ApiPaginationConfig>,
+    response: ApiResponseMapping,
 }
 
-impl ApiDataSource {
-    pub fn new(base_url: String, headers: Option<HashMap<String, String>>) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .unwrap_or_else(|_| reqwest::Client::new());
+/// Parsed body of a successful API response and its `Link: rel="next"` URL
+struct ApiResponse {
+    body: Value,
+    next_link: Option<String>,
+}
 
-        Self {
+impl ApiDataSource {
+    pub fn new(
+        base_url: String,
+        headers: Option<HashMap<String, String>>,
+        auth: Option<&ApiAuthConfig>,
+        pagination: Option<ApiPaginationConfig>,
+        response: Option<ApiResponseMapping>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        Ok(Self {
             base_url,
-            client,
+            client: http_client(timeout),
             headers: headers.unwrap_or_default(),
-            timeout_secs: 30,
             max_retries: 3,
+            auth: auth.map(HttpAuth::from_config).transpose()?,
+            pagination,
+            response: response.unwrap_or_default(),
+        })
+    }
+
+    /// Rows of a response body, located and renamed by the response mapping
+    fn rows(&self, body: Value) -> Result<Vec<HashMap<String, Value>>> {
+        let rows = match &self.response.items {
+            Some(path) => match json_path(&body, path) {
+                Some(items) => api_rows(items.clone())?,
+                None => Vec::new(),
+            },
+            None => api_rows(body)?,
+        };
+
+        if self.response.fields.is_empty() {
+            return Ok(rows);
         }
+
+        Ok(rows
+            .into_iter()
+            .map(|mut row| {
+                let item = Value::Object(row.clone().into_iter().collect());
+                for (name, path) in &self.response.fields {
+                    // Plain top-level keys are renamed rather than copied
+                    row.remove(path);
+                    if let Some(value) = json_path(&item, path) {
+                        row.insert(name.clone(), value.clone());
+                    }
+                }
+                row
+            })
+            .collect())
     }
 
-    async fn execute_with_retry<F, Fut>(&self, operation: F) -> Result<Value>
+    /// Add the configured headers and credentials to a request
+    async fn prepare(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::RequestBuilder> {
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        request = crate::request_id::forward(request);
+        match &self.auth {
+            Some(auth) => auth.apply(&self.client, request).await,
+            None => Ok(request),
+        }
+    }
+
+    /// Fail on error statuses, dropping a rejected OAuth2 token so the retry
+    /// fetches a fresh one
+    fn check_status(&self, status: reqwest::StatusCode, what: &str) -> Result<()> {
+        if status.is_success() {
+            return Ok(());
+        }
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            if let Some(auth) = &self.auth {
+                auth.invalidate();
+            }
+        }
+        Err(anyhow!("{} returned error status: {}", what, status))
+    }
+
+    async fn execute_with_retry<F, Fut, T>(&self, operation: F) -> Result<T>
     where
         F: Fn() -> Fut,
-        Fut: std::future::Future<Output = Result<Value>>,
+        Fut: std::future::Future<Output = Result<T>>,
     {
         let mut last_error = None;
 
@@ -297,6 +1371,167 @@ impl ApiDataSource {
 
         Err(last_error.unwrap_or_else(|| anyhow!("API request failed after all retries")))
     }
+
+    /// Send a request with query parameters and an optional JSON body,
+    /// retrying failures
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        query: &[(String, String)],
+        body: Option<&Value>,
+    ) -> Result<ApiResponse> {
+        self.execute_with_retry(|| async {
+            let mut request = self.client.request(method.clone(), url).query(query);
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let response = self
+                .prepare(request)
+                .await?
+                .send()
+                .await
+                .map_err(|e| anyhow!("API request failed: {}", e))?;
+
+            self.check_status(response.status(), "API")?;
+
+            let next_link = response
+                .headers()
+                .get(reqwest::header::LINK)
+                .and_then(|h| h.to_str().ok())
+                .and_then(link_next)
+                .and_then(|next| response.url().join(&next).ok())
+                .map(|next| next.to_string());
+
+            // Empty bodies (e.g. `204 No Content`) become null
+            let text = response
+                .text()
+                .await
+                .map_err(|e| anyhow!("Failed to read API response: {}", e))?;
+            let body = if text.trim().is_empty() {
+                Value::Null
+            } else {
+                serde_json::from_str(&text)
+                    .map_err(|e| anyhow!("Failed to parse API response: {}", e))?
+            };
+
+            Ok(ApiResponse { body, next_link })
+        })
+        .await
+    }
+
+    /// GET a URL with query parameters, retrying failures
+    async fn get(&self, url: &str, query: &[(String, String)]) -> Result<ApiResponse> {
+        self.send(reqwest::Method::GET, url, query, None).await
+    }
+
+    /// Fetch one page with the configured pagination strategy
+    ///
+    /// Cursor and link-header pages remember the cursor of each visited page,
+    /// so only jumping ahead walks the pages in between.
+    pub async fn fetch_page(
+        &self,
+        endpoint: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: &PaginationParams,
+    ) -> Result<Page> {
+        let config = self
+            .pagination
+            .as_ref()
+            .ok_or_else(|| anyhow!("API data source has no pagination strategy configured"))?;
+        let endpoint = api_endpoint(&self.base_url, endpoint, params)?;
+        let url = endpoint.url;
+        let mut query = api_query_params(params, &endpoint.used);
+        query.push((
+            config.size_param().to_string(),
+            pagination.page_size.to_string(),
+        ));
+
+        let total_field = self.response.total.as_ref().or(config.total_field.as_ref());
+        let next_cursor_field = self
+            .response
+            .next_cursor
+            .as_ref()
+            .unwrap_or(&config.next_cursor_field);
+        let total = std::sync::Mutex::new(None);
+        let next_cursor = std::sync::Mutex::new(None);
+        let page_total = |body: &Value| {
+            if let Some(field) = total_field {
+                *total.lock().unwrap() = json_path(body, field).and_then(|v| match v {
+                    Value::String(s) => s.parse().ok(),
+                    other => other.as_u64(),
+                });
+            }
+        };
+
+        debug!(
+            url = %url,
+            style = ?config.style,
+            page = pagination.page,
+            "Executing paginated API query"
+        );
+
+        let rows = match config.style {
+            ApiPaginationStyle::Page | ApiPaginationStyle::Offset => {
+                if config.style == ApiPaginationStyle::Page {
+                    query.push((config.page_param.clone(), pagination.page.to_string()));
+                } else {
+                    query.push((config.offset_param.clone(), pagination.offset.to_string()));
+                }
+                let response = self.get(&url, &query).await?;
+                page_total(&response.body);
+                self.rows(response.body)?
+            }
+            ApiPaginationStyle::Cursor => {
+                let cache_key = format!("{}|{:?}|{:?}", url, config.style, query);
+                fetch_cursor_page(cache_key, pagination, |cursor| {
+                    let mut query = query.clone();
+                    if let Some(cursor) = cursor {
+                        query.push((config.cursor_param.clone(), cursor));
+                    }
+                    let (url, page_total, next_cursor) = (&url, &page_total, &next_cursor);
+                    async move {
+                        let response = self.get(url, &query).await?;
+                        page_total(&response.body);
+                        let next =
+                            json_path(&response.body, next_cursor_field).and_then(|v| match v {
+                                Value::String(s) if !s.is_empty() => Some(s.clone()),
+                                Value::Number(n) => Some(n.to_string()),
+                                _ => None,
+                            });
+                        next_cursor.lock().unwrap().clone_from(&next);
+                        Ok((self.rows(response.body)?, next))
+                    }
+                })
+                .await?
+            }
+            ApiPaginationStyle::LinkHeader => {
+                let cache_key = format!("{}|{:?}|{:?}", url, config.style, query);
+                fetch_cursor_page(cache_key, pagination, |next_url| {
+                    let (url, query, page_total, next_cursor) =
+                        (&url, &query, &page_total, &next_cursor);
+                    async move {
+                        // Next links already carry every query parameter
+                        let response = match &next_url {
+                            Some(next_url) => self.get(next_url, &[]).await?,
+                            None => self.get(url, query).await?,
+                        };
+                        page_total(&response.body);
+                        next_cursor.lock().unwrap().clone_from(&response.next_link);
+                        Ok((self.rows(response.body)?, response.next_link))
+                    }
+                })
+                .await?
+            }
+        };
+
+        Ok(Page {
+            rows,
+            total: total.into_inner().unwrap(),
+            next_cursor: next_cursor.into_inner().unwrap(),
+        })
+    }
 }
 
 #[async_trait::async_trait]
@@ -309,165 +1544,236 @@ impl DataSource for ApiDataSource {
         self.execute_query_paginated(endpoint, params, None).await
     }
 
+    /// Pages with the configured strategy; without one the API may ignore
+    /// the pagination parameters, so every row is read
+    async fn execute_page(
+        &self,
+        endpoint: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: &PaginationParams,
+    ) -> Result<Page> {
+        match &self.pagination {
+            Some(_) => self.fetch_page(endpoint, params, pagination).await,
+            None => Ok(Page::slice(
+                self.execute_query(endpoint, params).await?,
+                pagination,
+            )),
+        }
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        // Any answer short of a server error means the API is reachable
+        let response = self.client.get(&self.base_url).send().await?;
+        if response.status().is_server_error() {
+            return Err(anyhow!("API returned {}", response.status()));
+        }
+        Ok(())
+    }
+
     async fn execute_query_paginated(
         &self,
         endpoint: &str,
         params: Option<&HashMap<String, Value>>,
         pagination: Option<&PaginationParams>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        let url = format!("{}/{}", self.base_url, endpoint);
+        if let (Some(_), Some(p)) = (&self.pagination, pagination) {
+            return Ok(self.fetch_page(endpoint, params, p).await?.rows);
+        }
+
+        let endpoint = api_endpoint(&self.base_url, endpoint, params)?;
+        let method = endpoint.method.unwrap_or(reqwest::Method::GET);
 
         debug!(
-            url = %url,
+            method = %method,
+            url = %endpoint.url,
             pagination = ?pagination,
             "Executing API query"
         );
 
-        // Clone necessary data for the retry closure
-        let url_clone = url.clone();
-        let headers = self.headers.clone();
-        let params_clone = params.cloned();
-        let pagination_clone = pagination.cloned();
-        let client = self.client.clone();
-
-        let result = self
-            .execute_with_retry(|| {
-                let url = url_clone.clone();
-                let headers = headers.clone();
-                let params = params_clone.clone();
-                let pagination = pagination_clone.clone();
-                let client = client.clone();
-
-                async move {
-                    let mut request = client.get(&url);
-
-                    // Add headers
-                    for (key, value) in &headers {
-                        request = request.header(key, value);
-                    }
-
-                    // Add query parameters
-                    if let Some(params) = params.as_ref() {
-                        for (key, value) in params.iter() {
-                            if let Some(s) = value.as_str() {
-                                request = request.query(&[(key, s)]);
-                            } else {
-                                request = request.query(&[(key, value.to_string())]);
-                            }
-                        }
-                    }
-
-                    // Add pagination parameters
-                    if let Some(p) = pagination.as_ref() {
-                        request = request
-                            .query(&[("page", p.page.to_string())])
-                            .query(&[("page_size", p.page_size.to_string())])
-                            .query(&[("limit", p.page_size.to_string())])
-                            .query(&[("offset", p.offset.to_string())]);
-                    }
-
-                    let response = request
-                        .send()
-                        .await
-                        .map_err(|e| anyhow!("API request failed: {}", e))?;
-
-                    if !response.status().is_success() {
-                        return Err(anyhow!("API returned error status: {}", response.status()));
-                    }
-
-                    let data: Value = response
-                        .json()
-                        .await
-                        .map_err(|e| anyhow!("Failed to parse API response: {}", e))?;
-
-                    Ok(data)
-                }
-            })
-            .await?;
+        let mut query = api_query_params(params, &endpoint.used);
 
-        // Try to convert the response to a list of objects
-        match result {
-            Value::Array(arr) => {
-                let mut result_vec = Vec::new();
-                for item in arr {
-                    if let Value::Object(obj) = item {
-                        let map: HashMap<String, Value> = obj.into_iter().collect();
-                        result_vec.push(map);
-                    }
-                }
-                Ok(result_vec)
-            }
-            Value::Object(obj) => {
-                // Check if it's a paginated response with a data field
-                if let Some(Value::Array(arr)) = obj.get("data") {
-                    let mut result_vec = Vec::new();
-                    for item in arr {
-                        if let Value::Object(item_obj) = item {
-                            result_vec.push(item_obj.clone().into_iter().collect());
-                        }
-                    }
-                    Ok(result_vec)
-                } else {
-                    let map: HashMap<String, Value> = obj.into_iter().collect();
-                    Ok(vec![map])
-                }
-            }
-            _ => Err(anyhow!("Unexpected API response format")),
+        // Without a configured strategy, send every common pagination parameter
+        if let Some(p) = pagination {
+            query.extend([
+                ("page".to_string(), p.page.to_string()),
+                ("page_size".to_string(), p.page_size.to_string()),
+                ("limit".to_string(), p.page_size.to_string()),
+                ("offset".to_string(), p.offset.to_string()),
+            ]);
         }
+
+        self.rows(self.send(method, &endpoint.url, &query, None).await?.body)
     }
 
+    /// `endpoint` may name the method and a path template, e.g.
+    /// `PATCH /users/{id}`; the method defaults to POST and placeholders are
+    /// filled from `data`. GET, HEAD and DELETE requests carry no body.
     async fn execute_mutation(
         &self,
         endpoint: &str,
         data: &HashMap<String, Value>,
     ) -> Result<Value> {
-        let url = format!("{}/{}", self.base_url, endpoint);
+        let endpoint = api_endpoint(&self.base_url, endpoint, Some(data))?;
+        let method = endpoint.method.unwrap_or(reqwest::Method::POST);
 
-        debug!(url = %url, "Executing API mutation");
+        debug!(method = %method, url = %endpoint.url, "Executing API mutation");
 
-        // Clone necessary data for the retry closure
-        let url_clone = url.clone();
-        let headers = self.headers.clone();
-        let data_clone = data.clone();
-        let client = self.client.clone();
+        let body = match method {
+            reqwest::Method::GET | reqwest::Method::HEAD | reqwest::Method::DELETE => None,
+            _ => Some(json!(data)),
+        };
 
-        self.execute_with_retry(|| {
-            let url = url_clone.clone();
-            let headers = headers.clone();
-            let data = data_clone.clone();
-            let client = client.clone();
+        self.send(method, &endpoint.url, &[], body.as_ref())
+            .await
+            .map(|response| response.body)
+            .map_err(|e| anyhow!("API mutation failed: {}", e))
+    }
+}
 
-            async move {
-                let mut request = client.post(&url);
+/// Method and URL of an endpoint such as `PATCH /users/{id}`
+#[derive(Debug, PartialEq)]
+struct ApiEndpoint {
+    method: Option<reqwest::Method>,
+    url: String,
+    /// Parameters substituted into the path
+    used: Vec<String>,
+}
 
-                // Add headers
-                for (key, value) in &headers {
-                    request = request.header(key, value);
-                }
+/// Resolve an optional leading HTTP method and fill `{name}` path
+/// placeholders with URL-encoded values
+fn api_endpoint(
+    base_url: &str,
+    endpoint: &str,
+    values: Option<&HashMap<String, Value>>,
+) -> Result<ApiEndpoint> {
+    const METHODS: [&str; 7] = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+    let endpoint = endpoint.trim();
+    let (method, path) = match endpoint.split_once(char::is_whitespace) {
+        Some((method, path)) if METHODS.contains(&method.to_uppercase().as_str()) => (
+            Some(reqwest::Method::from_bytes(
+                method.to_uppercase().as_bytes(),
+            )?),
+            path.trim(),
+        ),
+        _ => (None, endpoint),
+    };
+
+    let mut resolved = String::new();
+    let mut used = Vec::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|i| start + i)
+            .ok_or_else(|| anyhow!("Unclosed placeholder in endpoint '{}'", endpoint))?;
+        let name = &rest[start + 1..end];
+        let value = values
+            .and_then(|v| v.get(name))
+            .filter(|v| !v.is_null())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Missing value for '{{{}}}' in endpoint '{}'",
+                    name,
+                    endpoint
+                )
+            })?;
+
+        resolved.push_str(&rest[..start]);
+        resolved.push_str(&url_encode(&value_as_text(value)));
+        used.push(name.to_string());
+        rest = &rest[end + 1..];
+    }
+    resolved.push_str(rest);
+
+    Ok(ApiEndpoint {
+        method,
+        url: format!(
+            "{}/{}",
+            base_url.trim_end_matches('/'),
+            resolved.trim_start_matches('/')
+        ),
+        used,
+    })
+}
 
-                let response = request
-                    .json(&data)
-                    .send()
-                    .await
-                    .map_err(|e| anyhow!("API mutation request failed: {}", e))?;
+/// Request parameters as query string pairs, skipping those already
+/// substituted into the path
+fn api_query_params(
+    params: Option<&HashMap<String, Value>>,
+    used: &[String],
+) -> Vec<(String, String)> {
+    params
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| !used.contains(key))
+        .map(|(key, value)| match value.as_str() {
+            Some(s) => (key.clone(), s.to_string()),
+            None => (key.clone(), value.to_string()),
+        })
+        .collect()
+}
 
-                if !response.status().is_success() {
-                    return Err(anyhow!(
-                        "API mutation returned error status: {}",
-                        response.status()
-                    ));
-                }
+/// Rows of an API response: a top-level array, a `data` array, or a single object
+fn api_rows(response: Value) -> Result<Vec<HashMap<String, Value>>> {
+    match response {
+        Value::Array(arr) => Ok(arr
+            .into_iter()
+            .filter_map(|item| match item {
+                Value::Object(obj) => Some(obj.into_iter().collect()),
+                _ => None,
+            })
+            .collect()),
+        Value::Object(mut obj) => match obj.remove("data") {
+            Some(Value::Array(arr)) => api_rows(Value::Array(arr)),
+            Some(data) => {
+                obj.insert("data".to_string(), data);
+                Ok(vec![obj.into_iter().collect()])
+            }
+            None => Ok(vec![obj.into_iter().collect()]),
+        },
+        _ => Err(anyhow!("Unexpected API response format")),
+    }
+}
 
-                let result: Value = response
-                    .json()
-                    .await
-                    .map_err(|e| anyhow!("Failed to parse API mutation response: {}", e))?;
+/// Look up a path in a JSON value, e.g. `meta.next_cursor`, `$.items[0].id`
+/// or `.data.0`
+fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    path.split(['.', '['])
+        .map(|segment| segment.trim_end_matches(']'))
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |value, key| match value {
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => value.get(key),
+        })
+        .filter(|v| !v.is_null())
+}
 
-                Ok(result)
-            }
+/// Target of the `rel="next"` entry of a `Link` header
+fn link_next(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let target = parts.next()?.trim();
+        let is_next = parts.any(|param| {
+            param
+                .trim()
+                .strip_prefix("rel=")
+                .map(|rel| {
+                    rel.trim_matches('"')
+                        .split_whitespace()
+                        .any(|r| r == "next")
+                })
+                .unwrap_or(false)
+        });
+        is_next.then(|| {
+            target
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string()
         })
-        .await
-    }
+    })
 }
 
 /// GraphQL data source
@@ -476,21 +1782,23 @@ pub struct GraphQLDataSource {
     client: reqwest::Client,
     headers: HashMap<String, String>,
     max_retries: u32,
+    auth: Option<HttpAuth>,
 }
 
 impl GraphQLDataSource {
-    pub fn new(endpoint: String, headers: Option<HashMap<String, String>>) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .unwrap_or_else(|_| reqwest::Client::new());
-
-        Self {
+    pub fn new(
+        endpoint: String,
+        headers: Option<HashMap<String, String>>,
+        auth: Option<&ApiAuthConfig>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        Ok(Self {
             endpoint,
-            client,
+            client: http_client(timeout),
             headers: headers.unwrap_or_default(),
             max_retries: 3,
-        }
+            auth: auth.map(HttpAuth::from_config).transpose()?,
+        })
     }
 
     async fn execute_with_retry<F, Fut>(&self, operation: F) -> Result<Value>
@@ -539,12 +1847,25 @@ impl DataSource for GraphQLDataSource {
         self.execute_query_paginated(query, params, None).await
     }
 
-    async fn execute_query_paginated(
-        &self,
-        query: &str,
-        params: Option<&HashMap<String, Value>>,
-        pagination: Option<&PaginationParams>,
-    ) -> Result<Vec<HashMap<String, Value>>> {
+    async fn health_check(&self) -> Result<()> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(&json!({ "query": "{ __typename }" }))
+            .send()
+            .await?;
+        if response.status().is_server_error() {
+            return Err(anyhow!("GraphQL endpoint returned {}", response.status()));
+        }
+        Ok(())
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
         debug!(
             endpoint = %self.endpoint,
             pagination = ?pagination,
@@ -566,6 +1887,7 @@ impl DataSource for GraphQLDataSource {
         let query_str = query.to_string();
         let variables_clone = variables.clone();
         let client = self.client.clone();
+        let auth = self.auth.as_ref();
 
         let data = self
             .execute_with_retry(|| {
@@ -590,6 +1912,10 @@ impl DataSource for GraphQLDataSource {
                     for (key, value) in &headers {
                         request = request.header(key, value);
                     }
+                    request = crate::request_id::forward(request);
+                    if let Some(auth) = auth {
+                        request = auth.apply(&client, request).await?;
+                    }
 
                     let response = request
                         .json(&request_body)
@@ -598,6 +1924,11 @@ impl DataSource for GraphQLDataSource {
                         .map_err(|e| anyhow!("GraphQL request failed: {}", e))?;
 
                     if !response.status().is_success() {
+                        if let (Some(auth), reqwest::StatusCode::UNAUTHORIZED) =
+                            (auth, response.status())
+                        {
+                            auth.invalidate();
+                        }
                         return Err(anyhow!(
                             "GraphQL returned error status: {}",
                             response.status()
@@ -682,6 +2013,7 @@ impl DataSource for GraphQLDataSource {
         let mutation_str = mutation.to_string();
         let variables_clone = variables.clone();
         let client = self.client.clone();
+        let auth = self.auth.as_ref();
 
         self.execute_with_retry(|| {
             let endpoint = endpoint.clone();
@@ -700,6 +2032,10 @@ impl DataSource for GraphQLDataSource {
                 for (key, value) in &headers {
                     request = request.header(key, value);
                 }
+                request = crate::request_id::forward(request);
+                if let Some(auth) = auth {
+                    request = auth.apply(&client, request).await?;
+                }
 
                 let response = request
                     .json(&request_body)
@@ -708,6 +2044,11 @@ impl DataSource for GraphQLDataSource {
                     .map_err(|e| anyhow!("GraphQL mutation request failed: {}", e))?;
 
                 if !response.status().is_success() {
+                    if let (Some(auth), reqwest::StatusCode::UNAUTHORIZED) =
+                        (auth, response.status())
+                    {
+                        auth.invalidate();
+                    }
                     return Err(anyhow!(
                         "GraphQL mutation returned error status: {}",
                         response.status()
@@ -813,6 +2154,26 @@ impl DataSource for MongoDBDataSource {
         let db = self.client.database(&self.database_name);
         let collection = db.collection::<Document>(&self.collection_name);
 
+        // A JSON array is an aggregation pipeline; anything else is a find filter
+        if let Some(pipeline) = mongo_pipeline(query, pagination)? {
+            let mut cursor = collection
+                .aggregate(pipeline, None)
+                .await
+                .map_err(|e| anyhow!("MongoDB aggregate failed: {}", e))?;
+
+            let mut results = Vec::new();
+            while let Some(doc) = cursor
+                .try_next()
+                .await
+                .map_err(|e| anyhow!("Failed to read MongoDB cursor: {}", e))?
+            {
+                results.push(mongo_document_to_row(doc));
+            }
+
+            tracing::info!(count = results.len(), "MongoDB aggregation completed");
+            return Ok(results);
+        }
+
         // Parse query as BSON document (expected to be JSON)
         let filter: Document = if query.is_empty() || query == "{}" {
             doc! {}
@@ -842,14 +2203,7 @@ impl DataSource for MongoDBDataSource {
             .await
             .map_err(|e| anyhow!("Failed to read MongoDB cursor: {}", e))?
         {
-            // Convert BSON document to JSON
-            let json_str = serde_json::to_string(&doc)
-                .map_err(|e| anyhow!("Failed to serialize BSON to JSON: {}", e))?;
-            let json_value: Value = serde_json::from_str(&json_str)?;
-
-            if let Value::Object(obj) = json_value {
-                results.push(obj.into_iter().collect());
-            }
+            results.push(mongo_document_to_row(doc));
         }
 
         tracing::info!(count = results.len(), "MongoDB query completed");
@@ -884,6 +2238,56 @@ impl DataSource for MongoDBDataSource {
     }
 }
 
+/// Parse an aggregation pipeline (JSON array of stages), appending `$skip`/`$limit`
+/// for pagination. Returns `None` when the query is a plain find filter.
+#[cfg(feature = "mongodb-datasource")]
+fn mongo_pipeline(
+    query: &str,
+    pagination: Option<&PaginationParams>,
+) -> Result<Option<Vec<mongodb::bson::Document>>> {
+    use mongodb::bson::{doc, Document};
+
+    if !query.trim_start().starts_with('[') {
+        return Ok(None);
+    }
+
+    let mut pipeline: Vec<Document> = serde_json::from_str(query)
+        .map_err(|e| anyhow!("Invalid MongoDB aggregation pipeline JSON: {}", e))?;
+
+    if let Some(p) = pagination {
+        pipeline.push(doc! { "$skip": p.offset as i64 });
+        pipeline.push(doc! { "$limit": p.page_size as i64 });
+    }
+
+    Ok(Some(pipeline))
+}
+
+#[cfg(feature = "mongodb-datasource")]
+fn mongo_document_to_row(doc: mongodb::bson::Document) -> HashMap<String, Value> {
+    doc.into_iter().map(|(k, v)| (k, bson_to_json(v))).collect()
+}
+
+/// Convert BSON to plain JSON: ObjectIds become hex strings and dates RFC 3339
+/// strings instead of extended JSON wrappers like `{"$oid": ...}`.
+#[cfg(feature = "mongodb-datasource")]
+fn bson_to_json(value: mongodb::bson::Bson) -> Value {
+    use mongodb::bson::Bson;
+
+    match value {
+        Bson::ObjectId(oid) => Value::String(oid.to_hex()),
+        Bson::DateTime(dt) => dt
+            .try_to_rfc3339_string()
+            .map(Value::String)
+            .unwrap_or_else(|_| json!(dt.timestamp_millis())),
+        Bson::Decimal128(d) => Value::String(d.to_string()),
+        Bson::Document(doc) => {
+            Value::Object(doc.into_iter().map(|(k, v)| (k, bson_to_json(v))).collect())
+        }
+        Bson::Array(items) => Value::Array(items.into_iter().map(bson_to_json).collect()),
+        other => other.into_relaxed_extjson(),
+    }
+}
+
 // Stub implementation when feature is disabled
 #[cfg(not(feature = "mongodb-datasource"))]
 pub struct MongoDBDataSource {
@@ -974,6 +2378,432 @@ impl RedisDataSource {
             key.to_string()
         }
     }
+
+    fn unprefixed_key<'a>(&self, key: &'a str) -> &'a str {
+        strip_key_prefix(self.key_prefix.as_deref(), key)
+    }
+
+    /// List the keys matching a `SCAN` pattern, one row per key with its parsed
+    /// value merged in. Without pagination every matching key is returned.
+    async fn scan_rows(
+        &self,
+        pattern: &str,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let mut con = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow!("Failed to get Redis connection: {}", e))?;
+
+        let pattern = self.prefixed_key(pattern);
+        let rows = match pagination {
+            Some(p) => {
+                let cache_key = format!(
+                    "{}|{}|{}",
+                    self.client.get_connection_info().addr,
+                    pattern,
+                    p.page_size
+                );
+                fetch_cursor_page(cache_key, p, |token| {
+                    let mut con = con.clone();
+                    let pattern = pattern.clone();
+                    async move {
+                        let (keys, next) = redis_scan_page(token, p.page_size, |cursor| {
+                            let mut con = con.clone();
+                            let pattern = pattern.clone();
+                            async move { redis_scan(&mut con, &pattern, cursor, p.page_size).await }
+                        })
+                        .await?;
+                        Ok((self.key_rows(&mut con, keys).await?, next))
+                    }
+                })
+                .await?
+            }
+            None => {
+                let mut keys = Vec::new();
+                let mut cursor = 0;
+                loop {
+                    let (next, batch) = redis_scan(&mut con, &pattern, cursor, 100).await?;
+                    keys.extend(batch);
+                    if next == 0 {
+                        break;
+                    }
+                    cursor = next;
+                }
+                self.key_rows(&mut con, keys).await?
+            }
+        };
+
+        tracing::info!(count = rows.len(), "Redis scan completed");
+        Ok(rows)
+    }
+
+    async fn key_rows(
+        &self,
+        con: &mut redis::aio::MultiplexedConnection,
+        keys: Vec<String>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // MGET returns nil for keys holding non-string values
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(con)
+            .await
+            .map_err(|e| anyhow!("Redis MGET failed: {}", e))?;
+
+        Ok(keys
+            .iter()
+            .zip(values)
+            .map(|(key, value)| redis_key_row(self.unprefixed_key(key), value))
+            .collect())
+    }
+
+    /// Read a hash (one row of fields), a list (one row per element, with its
+    /// `index`) or a sorted set (one row per member, with its `score`)
+    async fn structure_rows(
+        &self,
+        query: RedisQuery<'_>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let mut con = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow!("Failed to get Redis connection: {}", e))?;
+
+        let (offset, count) = pagination
+            .map(|p| (p.offset as isize, p.page_size as isize))
+            .unwrap_or((0, -1));
+
+        let rows = match query {
+            RedisQuery::Key(_) => return Err(anyhow!("Not a Redis data structure query")),
+            // Messages aren't stored; they are only delivered to live subscribers
+            RedisQuery::Channel(_) => Vec::new(),
+            RedisQuery::Hash(key) => {
+                let fields: HashMap<String, String> = redis::cmd("HGETALL")
+                    .arg(self.prefixed_key(key))
+                    .query_async(&mut con)
+                    .await
+                    .map_err(|e| anyhow!("Redis HGETALL failed: {}", e))?;
+                if fields.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![fields
+                        .into_iter()
+                        .map(|(field, value)| (field, redis_json(&value)))
+                        .collect()]
+                }
+            }
+            RedisQuery::List(key) => {
+                let stop = if count < 0 { -1 } else { offset + count - 1 };
+                let items: Vec<String> = redis::cmd("LRANGE")
+                    .arg(self.prefixed_key(key))
+                    .arg(offset)
+                    .arg(stop)
+                    .query_async(&mut con)
+                    .await
+                    .map_err(|e| anyhow!("Redis LRANGE failed: {}", e))?;
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let mut row = redis_value_row(item);
+                        row.insert("index".to_string(), json!(offset as usize + i));
+                        row
+                    })
+                    .collect()
+            }
+            RedisQuery::SortedSet { key, min, max } => {
+                let members: Vec<(String, f64)> = redis::cmd("ZRANGEBYSCORE")
+                    .arg(self.prefixed_key(key))
+                    .arg(min)
+                    .arg(max)
+                    .arg("WITHSCORES")
+                    .arg("LIMIT")
+                    .arg(offset)
+                    .arg(count)
+                    .query_async(&mut con)
+                    .await
+                    .map_err(|e| anyhow!("Redis ZRANGEBYSCORE failed: {}", e))?;
+                members
+                    .into_iter()
+                    .map(|(member, score)| {
+                        let mut row = match redis_json(&member) {
+                            Value::Object(obj) => obj.into_iter().collect(),
+                            other => HashMap::from([("member".to_string(), other)]),
+                        };
+                        row.insert("score".to_string(), json!(score));
+                        row
+                    })
+                    .collect()
+            }
+        };
+
+        tracing::info!(count = rows.len(), "Redis query completed");
+        Ok(rows)
+    }
+
+    /// `HSET` the fields of `data`, `RPUSH` or `PUBLISH` it as JSON, or `ZADD`
+    /// its `member` (or the remaining fields as JSON) with its `score`
+    async fn mutate_structure(
+        &self,
+        query: RedisQuery<'_>,
+        data: &HashMap<String, Value>,
+    ) -> Result<Value> {
+        let mut con = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow!("Failed to get Redis connection: {}", e))?;
+
+        let result: i64 = match query {
+            RedisQuery::Key(_) => return Err(anyhow!("Not a Redis data structure query")),
+            RedisQuery::Hash(key) => {
+                if data.is_empty() {
+                    return Err(anyhow!("Redis HSET requires at least one field"));
+                }
+                let mut cmd = redis::cmd("HSET");
+                cmd.arg(self.prefixed_key(key));
+                for (field, value) in data {
+                    cmd.arg(field).arg(redis_string(value));
+                }
+                cmd.query_async(&mut con)
+                    .await
+                    .map_err(|e| anyhow!("Redis HSET failed: {}", e))?
+            }
+            RedisQuery::List(key) => redis::cmd("RPUSH")
+                .arg(self.prefixed_key(key))
+                .arg(serde_json::to_string(data)?)
+                .query_async(&mut con)
+                .await
+                .map_err(|e| anyhow!("Redis RPUSH failed: {}", e))?,
+            RedisQuery::Channel(channel) => redis::cmd("PUBLISH")
+                .arg(self.prefixed_key(channel))
+                .arg(serde_json::to_string(data)?)
+                .query_async(&mut con)
+                .await
+                .map_err(|e| anyhow!("Redis PUBLISH failed: {}", e))?,
+            RedisQuery::SortedSet { key, .. } => {
+                let (score, member) = redis_sorted_set_entry(data)?;
+                redis::cmd("ZADD")
+                    .arg(self.prefixed_key(key))
+                    .arg(score)
+                    .arg(member)
+                    .query_async(&mut con)
+                    .await
+                    .map_err(|e| anyhow!("Redis ZADD failed: {}", e))?
+            }
+        };
+
+        tracing::info!(result = result, "Redis mutation completed");
+        Ok(json!(result))
+    }
+}
+
+/// Split mutation data into a sorted set score and member
+#[cfg(feature = "redis-datasource")]
+fn redis_sorted_set_entry(data: &HashMap<String, Value>) -> Result<(f64, String)> {
+    let score = data
+        .get("score")
+        .and_then(|v| match v {
+            Value::String(s) => s.parse().ok(),
+            other => other.as_f64(),
+        })
+        .ok_or_else(|| anyhow!("Redis ZADD requires a numeric 'score'"))?;
+
+    let member = match data.get("member") {
+        Some(member) => redis_string(member),
+        None => {
+            let rest: serde_json::Map<String, Value> = data
+                .iter()
+                .filter(|(k, _)| k.as_str() != "score")
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            if rest.is_empty() {
+                return Err(anyhow!("Redis ZADD requires a 'member'"));
+            }
+            Value::Object(rest).to_string()
+        }
+    };
+
+    Ok((score, member))
+}
+
+/// Remove the `<prefix>:` namespace from a key or channel name
+#[cfg(feature = "redis-datasource")]
+fn strip_key_prefix<'a>(prefix: Option<&str>, key: &'a str) -> &'a str {
+    prefix
+        .and_then(|prefix| key.strip_prefix(prefix))
+        .and_then(|key| key.strip_prefix(':'))
+        .unwrap_or(key)
+}
+
+/// Whether a Redis query is a `SCAN` glob pattern rather than a single key
+#[cfg(feature = "redis-datasource")]
+fn is_redis_pattern(key: &str) -> bool {
+    key.contains(['*', '?', '['])
+}
+
+#[cfg(feature = "redis-datasource")]
+async fn redis_scan(
+    con: &mut redis::aio::MultiplexedConnection,
+    pattern: &str,
+    cursor: u64,
+    count: usize,
+) -> Result<(u64, Vec<String>)> {
+    redis::cmd("SCAN")
+        .arg(cursor)
+        .arg("MATCH")
+        .arg(pattern)
+        .arg("COUNT")
+        .arg(count)
+        .query_async(con)
+        .await
+        .map_err(|e| anyhow!("Redis SCAN failed: {}", e))
+}
+
+/// Collect one page of `SCAN` keys
+///
+/// SCAN batches don't line up with pages, so a page token is
+/// `<cursor>:<skip>`: the SCAN cursor the page starts from and how many keys of
+/// that batch belong to the previous page. `scan` runs one SCAN call from a
+/// cursor and returns the next cursor and the batch.
+#[cfg(feature = "redis-datasource")]
+async fn redis_scan_page<F, Fut>(
+    token: Option<String>,
+    page_size: usize,
+    mut scan: F,
+) -> Result<(Vec<String>, Option<String>)>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: std::future::Future<Output = Result<(u64, Vec<String>)>>,
+{
+    let (mut cursor, mut skip) = match token {
+        Some(token) => {
+            let (cursor, skip) = token
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid Redis page token '{}'", token))?;
+            (
+                cursor
+                    .parse::<u64>()
+                    .map_err(|_| anyhow!("Invalid Redis page token '{}'", token))?,
+                skip.parse::<usize>()
+                    .map_err(|_| anyhow!("Invalid Redis page token '{}'", token))?,
+            )
+        }
+        None => (0, 0),
+    };
+
+    let mut keys = Vec::new();
+    loop {
+        let (next, batch) = scan(cursor).await?;
+        let available = batch.len().saturating_sub(skip);
+        let wanted = page_size - keys.len();
+
+        if available > wanted {
+            keys.extend(batch.into_iter().skip(skip).take(wanted));
+            return Ok((keys, Some(format!("{}:{}", cursor, skip + wanted))));
+        }
+
+        keys.extend(batch.into_iter().skip(skip));
+        skip = 0;
+        if next == 0 {
+            return Ok((keys, None));
+        }
+        cursor = next;
+        if keys.len() == page_size {
+            return Ok((keys, Some(format!("{}:0", cursor))));
+        }
+    }
+}
+
+/// Build a row for a scanned key; missing or non-string values become a null `value`
+#[cfg(feature = "redis-datasource")]
+fn redis_key_row(key: &str, value: Option<String>) -> HashMap<String, Value> {
+    let mut row = value
+        .as_deref()
+        .map(redis_value_row)
+        .unwrap_or_else(|| HashMap::from([("value".to_string(), Value::Null)]));
+    row.insert("key".to_string(), Value::String(key.to_string()));
+    row
+}
+
+/// JSON objects are merged into the row, anything else goes under `value`
+#[cfg(feature = "redis-datasource")]
+fn redis_value_row(value: &str) -> HashMap<String, Value> {
+    match redis_json(value) {
+        Value::Object(obj) => obj.into_iter().collect(),
+        other => HashMap::from([("value".to_string(), other)]),
+    }
+}
+
+/// Parse a stored Redis string as JSON, keeping it as a string otherwise
+#[cfg(feature = "redis-datasource")]
+fn redis_json(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+/// Store a value as a Redis string: strings as-is, anything else as JSON
+#[cfg(feature = "redis-datasource")]
+fn redis_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// What a Redis query or mutation targets
+///
+/// A bare key (or `SCAN` pattern) reads and writes JSON strings. A leading
+/// command selects a data structure: `HGETALL`/`HSET <key>` for hashes,
+/// `LRANGE`/`RPUSH <key>` for lists and `ZRANGEBYSCORE`/`ZADD <key> [min max]`
+/// for sorted sets. `SUBSCRIBE`/`PUBLISH <channel>` targets a pub/sub channel.
+#[cfg(feature = "redis-datasource")]
+#[derive(Debug, PartialEq)]
+enum RedisQuery<'a> {
+    Key(&'a str),
+    Hash(&'a str),
+    List(&'a str),
+    SortedSet {
+        key: &'a str,
+        min: &'a str,
+        max: &'a str,
+    },
+    Channel(&'a str),
+}
+
+#[cfg(feature = "redis-datasource")]
+fn parse_redis_query(query: &str) -> Result<RedisQuery<'_>> {
+    let mut parts = query.split_whitespace();
+    let Some(command) = parts.next() else {
+        return Ok(RedisQuery::Key(query));
+    };
+
+    let structure = match command.to_uppercase().as_str() {
+        "HGETALL" | "HSET" => "hash",
+        "LRANGE" | "RPUSH" => "list",
+        "ZRANGEBYSCORE" | "ZADD" => "zset",
+        "SUBSCRIBE" | "PUBLISH" => "channel",
+        _ => return Ok(RedisQuery::Key(query.trim())),
+    };
+    let key = parts
+        .next()
+        .ok_or_else(|| anyhow!("Redis {} query requires a key", command))?;
+
+    Ok(match structure {
+        "hash" => RedisQuery::Hash(key),
+        "list" => RedisQuery::List(key),
+        "channel" => RedisQuery::Channel(key),
+        _ => RedisQuery::SortedSet {
+            key,
+            min: parts.next().unwrap_or("-inf"),
+            max: parts.next().unwrap_or("+inf"),
+        },
+    })
 }
 
 #[cfg(feature = "redis-datasource")]
@@ -986,6 +2816,19 @@ impl DataSource for RedisDataSource {
     ) -> Result<Vec<HashMap<String, Value>>> {
         use redis::AsyncCommands;
 
+        let key = match parse_redis_query(key)? {
+            RedisQuery::Key(key) => key,
+            structure => {
+                tracing::info!(query = ?structure, "Executing Redis query");
+                return self.structure_rows(structure, None).await;
+            }
+        };
+
+        if is_redis_pattern(key) {
+            tracing::info!(pattern = %key, "Executing Redis scan");
+            return self.scan_rows(key, None).await;
+        }
+
         tracing::info!(key = %key, "Executing Redis query");
 
         let mut con = self
@@ -1031,16 +2874,37 @@ impl DataSource for RedisDataSource {
         &self,
         key: &str,
         params: Option<&HashMap<String, Value>>,
-        _pagination: Option<&PaginationParams>,
+        pagination: Option<&PaginationParams>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        // Redis is a key-value store, pagination doesn't apply
-        // Just call the regular execute_query
-        self.execute_query(key, params).await
+        // Pagination applies to key patterns, lists and sorted sets; a single
+        // key or hash is one lookup
+        let Some(p) = pagination else {
+            return self.execute_query(key, params).await;
+        };
+        match parse_redis_query(key)? {
+            RedisQuery::Key(key) if is_redis_pattern(key) => {
+                tracing::info!(pattern = %key, page = p.page, "Executing Redis scan");
+                self.scan_rows(key, Some(p)).await
+            }
+            RedisQuery::Key(key) => self.execute_query(key, params).await,
+            structure => {
+                tracing::info!(query = ?structure, page = p.page, "Executing Redis query");
+                self.structure_rows(structure, Some(p)).await
+            }
+        }
     }
 
     async fn execute_mutation(&self, key: &str, data: &HashMap<String, Value>) -> Result<Value> {
         use redis::AsyncCommands;
 
+        let key = match parse_redis_query(key)? {
+            RedisQuery::Key(key) => key,
+            structure => {
+                tracing::info!(query = ?structure, "Executing Redis mutation");
+                return self.mutate_structure(structure, data).await;
+            }
+        };
+
         tracing::info!(key = %key, "Executing Redis mutation");
 
         let mut con = self
@@ -1064,6 +2928,49 @@ impl DataSource for RedisDataSource {
 
         Ok(Value::String(result))
     }
+
+    /// `SUBSCRIBE <channel>` streams each published message as a row with its
+    /// `channel`; glob channels use `PSUBSCRIBE`
+    async fn subscribe(&self, query: &str) -> Result<RowStream> {
+        use futures_util::StreamExt;
+
+        let RedisQuery::Channel(channel) = parse_redis_query(query)? else {
+            return Err(anyhow!(
+                "Redis subscriptions require a 'SUBSCRIBE <channel>' query"
+            ));
+        };
+        let channel = self.prefixed_key(channel);
+
+        let mut pubsub = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|e| anyhow!("Failed to get Redis connection: {}", e))?
+            .into_pubsub();
+        if is_redis_pattern(&channel) {
+            pubsub.psubscribe(&channel).await
+        } else {
+            pubsub.subscribe(&channel).await
+        }
+        .map_err(|e| anyhow!("Redis SUBSCRIBE failed: {}", e))?;
+
+        tracing::info!(channel = %channel, "Subscribed to Redis channel");
+
+        let prefix = self.key_prefix.clone();
+        Ok(pubsub
+            .into_on_message()
+            .map(move |msg| {
+                let payload: String = msg
+                    .get_payload()
+                    .map_err(|e| anyhow!("Invalid Redis message payload: {}", e))?;
+                let channel = strip_key_prefix(prefix.as_deref(), msg.get_channel_name());
+
+                let mut row = redis_value_row(&payload);
+                row.insert("channel".to_string(), Value::String(channel.to_string()));
+                Ok(row)
+            })
+            .boxed())
+    }
 }
 
 // Stub implementation when feature is disabled
@@ -1111,20 +3018,43 @@ pub struct ElasticsearchDataSource {
     nodes: Vec<String>,
     index: String,
     client: reqwest::Client,
+    auth: Option<HttpAuth>,
 }
 
 impl ElasticsearchDataSource {
-    pub fn new(nodes: Vec<String>, index: String) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
+    pub fn new(
+        nodes: Vec<String>,
+        index: String,
+        auth: Option<&ApiAuthConfig>,
+        tls: Option<&TlsConfig>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let auth = auth.map(HttpAuth::from_config).transpose()?;
+
+        let mut builder = reqwest::Client::builder().timeout(timeout);
+        if let Some(tls) = tls {
+            if let Some(path) = &tls.ca_cert {
+                let pem = std::fs::read(path)
+                    .map_err(|e| anyhow!("Failed to read CA certificate {}: {}", path, e))?;
+                let cert = reqwest::Certificate::from_pem(&pem)
+                    .map_err(|e| anyhow!("Invalid CA certificate {}: {}", path, e))?;
+                builder = builder.add_root_certificate(cert);
+            }
+            if tls.skip_verify {
+                warn!("Elasticsearch TLS certificate verification is disabled");
+                builder = builder.danger_accept_invalid_certs(true);
+            }
+        }
+        let client = builder
             .build()
-            .unwrap_or_else(|_| reqwest::Client::new());
+            .map_err(|e| anyhow!("Failed to build Elasticsearch client: {}", e))?;
 
-        Self {
+        Ok(Self {
             nodes,
             index,
             client,
-        }
+            auth,
+        })
     }
 
     fn get_node_url(&self) -> &str {
@@ -1133,6 +3063,14 @@ impl ElasticsearchDataSource {
             .map(|s| s.as_str())
             .unwrap_or("http://localhost:9200")
     }
+
+    async fn request(&self, method: reqwest::Method, url: &str) -> Result<reqwest::RequestBuilder> {
+        let request = self.client.request(method, url);
+        match &self.auth {
+            Some(auth) => auth.apply(&self.client, request).await,
+            None => Ok(request),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -1145,6 +3083,15 @@ impl DataSource for ElasticsearchDataSource {
         self.execute_query_paginated(query, params, None).await
     }
 
+    async fn health_check(&self) -> Result<()> {
+        self.request(reqwest::Method::GET, self.get_node_url())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
     async fn execute_query_paginated(
         &self,
         query: &str,
@@ -1181,8 +3128,8 @@ impl DataSource for ElasticsearchDataSource {
         }
 
         let response = self
-            .client
-            .post(&search_url)
+            .request(reqwest::Method::POST, &search_url)
+            .await?
             .header("Content-Type", "application/json")
             .json(&query_obj)
             .send()
@@ -1206,22 +3153,29 @@ impl DataSource for ElasticsearchDataSource {
 
         // Extract hits from Elasticsearch response
         let mut results = Vec::new();
-        if let Some(hits_wrapper) = data.get("hits").and_then(|h| h.get("hits")) {
-            if let Value::Array(hits) = hits_wrapper {
-                for hit in hits {
-                    if let Some(source) = hit.get("_source") {
-                        if let Value::Object(obj) = source {
-                            let mut map: HashMap<String, Value> = obj.clone().into_iter().collect();
-                            // Also include _id if available
-                            if let Some(id) = hit.get("_id") {
-                                map.insert("_id".to_string(), id.clone());
-                            }
-                            results.push(map);
-                        }
+        if let Some(Value::Array(hits)) = data.get("hits").and_then(|h| h.get("hits")) {
+            for hit in hits {
+                if let Some(Value::Object(obj)) = hit.get("_source") {
+                    let mut map: HashMap<String, Value> = obj.clone().into_iter().collect();
+                    // Also include _id if available
+                    if let Some(id) = hit.get("_id") {
+                        map.insert("_id".to_string(), id.clone());
+                    }
+                    // And snippets, when the query asks for highlighting
+                    if let Some(highlight) = hit.get("highlight") {
+                        map.insert("_highlight".to_string(), highlight.clone());
                     }
+                    results.push(map);
                 }
             }
         }
+        // Aggregations come back as a row of their own
+        if let Some(aggregations) = data.get("aggregations") {
+            results.push(HashMap::from([(
+                "_aggregations".to_string(),
+                aggregations.clone(),
+            )]));
+        }
 
         info!(count = results.len(), "Elasticsearch query completed");
         Ok(results)
@@ -1240,7 +3194,6 @@ impl DataSource for ElasticsearchDataSource {
         debug!(url = %index_url, "Executing Elasticsearch mutation");
 
         let response = self
-            .client
             .request(
                 if doc_id.is_empty() {
                     reqwest::Method::POST
@@ -1249,6 +3202,7 @@ impl DataSource for ElasticsearchDataSource {
                 },
                 &index_url,
             )
+            .await?
             .header("Content-Type", "application/json")
             .json(&data)
             .send()
@@ -1276,64 +3230,326 @@ impl DataSource for ElasticsearchDataSource {
 }
 
 /// gRPC data source
+///
+/// `proto_file` must be a compiled descriptor set
+/// (`protoc --include_imports --descriptor_set_out=service.pb service.proto`).
+/// The action's query/endpoint names the unary method to call on `service_name`;
+/// params are converted to the request message using the protobuf JSON mapping.
+#[cfg(feature = "grpc-datasource")]
 pub struct GrpcDataSource {
-    #[allow(dead_code)]
     endpoint: String,
-    #[allow(dead_code)]
-    proto_file: String,
-    #[allow(dead_code)]
-    service_name: String,
-    #[allow(dead_code)]
-    tls_enabled: bool,
+    service: prost_reflect::ServiceDescriptor,
+    channel: tonic::transport::Channel,
 }
 
+#[cfg(feature = "grpc-datasource")]
 impl GrpcDataSource {
-    pub fn new(
+    pub async fn new(
         endpoint: String,
         proto_file: String,
         service_name: String,
         tls_enabled: bool,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        use prost_reflect::DescriptorPool;
+        use tonic::transport::{ClientTlsConfig, Endpoint};
+
+        info!(
+            endpoint = %endpoint,
+            proto_file = %proto_file,
+            service = %service_name,
+            tls_enabled = tls_enabled,
+            "Initializing gRPC data source"
+        );
+
+        if proto_file.ends_with(".proto") {
+            return Err(anyhow!(
+                "gRPC proto_file must be a compiled descriptor set, not '{}'. Generate one with: protoc --include_imports --descriptor_set_out=service.pb {}",
+                proto_file,
+                proto_file
+            ));
+        }
+
+        let bytes = tokio::fs::read(&proto_file)
+            .await
+            .map_err(|e| anyhow!("Failed to read gRPC descriptor set '{}': {}", proto_file, e))?;
+        let pool = DescriptorPool::decode(bytes.as_slice())
+            .map_err(|e| anyhow!("Invalid gRPC descriptor set '{}': {}", proto_file, e))?;
+        let service = pool.get_service_by_name(&service_name).ok_or_else(|| {
+            anyhow!(
+                "gRPC service '{}' not found in '{}'",
+                service_name,
+                proto_file
+            )
+        })?;
+
+        let uri = if endpoint.contains("://") {
+            endpoint.clone()
+        } else if tls_enabled {
+            format!("https://{}", endpoint)
+        } else {
+            format!("http://{}", endpoint)
+        };
+
+        let mut channel = Endpoint::from_shared(uri)
+            .map_err(|e| anyhow!("Invalid gRPC endpoint '{}': {}", endpoint, e))?;
+        if tls_enabled {
+            channel = channel
+                .tls_config(ClientTlsConfig::new().with_native_roots())
+                .map_err(|e| anyhow!("Failed to configure gRPC TLS: {}", e))?;
+        }
+
+        Ok(Self {
             endpoint,
-            proto_file,
-            service_name,
-            tls_enabled,
+            service,
+            channel: channel.connect_lazy(),
+        })
+    }
+
+    /// Call a unary method with a JSON request, returning the JSON response
+    async fn call(&self, method_name: &str, request: Value) -> Result<Value> {
+        use prost_reflect::{DeserializeOptions, DynamicMessage, SerializeOptions};
+        use tonic::codegen::http::uri::PathAndQuery;
+
+        let method = self
+            .service
+            .methods()
+            .find(|m| m.name() == method_name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "gRPC method '{}' not found on service '{}'",
+                    method_name,
+                    self.service.full_name()
+                )
+            })?;
+
+        if method.is_client_streaming() || method.is_server_streaming() {
+            return Err(anyhow!(
+                "gRPC method '{}' is streaming; only unary methods are supported",
+                method.full_name()
+            ));
         }
+
+        // Params may carry extra keys (e.g. pagination) the request doesn't define
+        let message = DynamicMessage::deserialize_with_options(
+            method.input(),
+            request,
+            &DeserializeOptions::new().deny_unknown_fields(false),
+        )
+        .map_err(|e| anyhow!("Invalid request for gRPC method '{}': {}", method_name, e))?;
+
+        let path =
+            PathAndQuery::try_from(format!("/{}/{}", self.service.full_name(), method.name()))
+                .map_err(|e| anyhow!("Invalid gRPC method path: {}", e))?;
+
+        debug!(endpoint = %self.endpoint, method = %method.full_name(), "Calling gRPC method");
+
+        let mut client = tonic::client::Grpc::new(self.channel.clone());
+        client
+            .ready()
+            .await
+            .map_err(|e| anyhow!("gRPC endpoint '{}' is unavailable: {}", self.endpoint, e))?;
+
+        let response = client
+            .unary(
+                tonic::Request::new(message),
+                path,
+                DynamicCodec(method.output()),
+            )
+            .await
+            .map_err(|status| {
+                error!(
+                    method = %method.full_name(),
+                    code = ?status.code(),
+                    error = %status.message(),
+                    "gRPC call failed"
+                );
+                anyhow!(
+                    "gRPC call '{}' failed: {} ({:?})",
+                    method.full_name(),
+                    status.message(),
+                    status.code()
+                )
+            })?;
+
+        let options = SerializeOptions::new()
+            .use_proto_field_name(true)
+            .stringify_64_bit_integers(false);
+        Ok(response
+            .into_inner()
+            .serialize_with_options(serde_json::value::Serializer, &options)?)
     }
 }
 
-#[async_trait::async_trait]
-impl DataSource for GrpcDataSource {
-    async fn execute_query(
-        &self,
-        method: &str,
-        _params: Option<&HashMap<String, Value>>,
-    ) -> Result<Vec<HashMap<String, Value>>> {
-        tracing::warn!("gRPC query execution not yet fully implemented: {}", method);
-        Ok(vec![])
+/// Encodes dynamic messages and decodes those of a descriptor: a method's
+/// output for the data source, its input for the gRPC API
+#[cfg(any(feature = "grpc-datasource", feature = "grpc"))]
+#[derive(Clone)]
+pub(crate) struct DynamicCodec(pub(crate) prost_reflect::MessageDescriptor);
+
+#[cfg(any(feature = "grpc-datasource", feature = "grpc"))]
+impl tonic::codec::Codec for DynamicCodec {
+    type Encode = prost_reflect::DynamicMessage;
+    type Decode = prost_reflect::DynamicMessage;
+    type Encoder = DynamicCodec;
+    type Decoder = DynamicCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.clone()
     }
 
-    async fn execute_query_paginated(
-        &self,
+    fn decoder(&mut self) -> Self::Decoder {
+        self.clone()
+    }
+}
+
+#[cfg(any(feature = "grpc-datasource", feature = "grpc"))]
+impl tonic::codec::Encoder for DynamicCodec {
+    type Item = prost_reflect::DynamicMessage;
+    type Error = tonic::Status;
+
+    fn encode(
+        &mut self,
+        item: Self::Item,
+        dst: &mut tonic::codec::EncodeBuf<'_>,
+    ) -> Result<(), Self::Error> {
+        use prost::Message;
+
+        item.encode(dst)
+            .map_err(|e| tonic::Status::internal(e.to_string()))
+    }
+}
+
+#[cfg(any(feature = "grpc-datasource", feature = "grpc"))]
+impl tonic::codec::Decoder for DynamicCodec {
+    type Item = prost_reflect::DynamicMessage;
+    type Error = tonic::Status;
+
+    fn decode(
+        &mut self,
+        src: &mut tonic::codec::DecodeBuf<'_>,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        prost_reflect::DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|e| tonic::Status::internal(e.to_string()))
+    }
+}
+
+/// Map a structured response (gRPC message, JSON-RPC result) to rows
+///
+/// A response with exactly one list-of-objects field (e.g. `users`) yields
+/// its elements; any other response is a single row.
+fn response_rows(response: Value) -> Vec<HashMap<String, Value>> {
+    let obj = match response {
+        Value::Object(obj) if !obj.is_empty() => obj,
+        _ => return Vec::new(),
+    };
+
+    let lists: Vec<&Vec<Value>> = obj
+        .values()
+        .filter_map(|v| v.as_array())
+        .filter(|items| items.iter().all(|item| item.is_object()))
+        .collect();
+
+    if let [items] = lists.as_slice() {
+        return items
+            .iter()
+            .filter_map(|item| item.as_object())
+            .map(|item| item.clone().into_iter().collect())
+            .collect();
+    }
+
+    vec![obj.into_iter().collect()]
+}
+
+#[cfg(feature = "grpc-datasource")]
+#[async_trait::async_trait]
+impl DataSource for GrpcDataSource {
+    async fn execute_query(
+        &self,
+        method: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let request = json!(params.cloned().unwrap_or_default());
+        let response = self.call(method, request).await?;
+        let rows = response_rows(response);
+
+        info!(method = %method, row_count = rows.len(), "gRPC query completed");
+        Ok(rows)
+    }
+
+    async fn execute_query_paginated(
+        &self,
         method: &str,
         params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let mut params = params.cloned().unwrap_or_default();
+        if let Some(p) = pagination {
+            // Fields the request message doesn't define are ignored
+            params.insert("page".to_string(), json!(p.page));
+            params.insert("page_size".to_string(), json!(p.page_size));
+            params.insert("offset".to_string(), json!(p.offset));
+            params.insert("limit".to_string(), json!(p.page_size));
+        }
+
+        self.execute_query(method, Some(&params)).await
+    }
+
+    async fn execute_mutation(&self, method: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let result = self.call(method, json!(data)).await?;
+
+        info!(method = %method, "gRPC mutation completed");
+        Ok(result)
+    }
+}
+
+// Stub when feature is disabled
+#[cfg(not(feature = "grpc-datasource"))]
+pub struct GrpcDataSource {
+    #[allow(dead_code)]
+    endpoint: String,
+}
+
+#[cfg(not(feature = "grpc-datasource"))]
+impl GrpcDataSource {
+    pub async fn new(
+        _endpoint: String,
+        _proto_file: String,
+        _service_name: String,
+        _tls_enabled: bool,
+    ) -> Result<Self> {
+        Err(anyhow!(
+            "gRPC support not enabled. Enable the 'grpc-datasource' feature in Cargo.toml"
+        ))
+    }
+}
+
+#[cfg(not(feature = "grpc-datasource"))]
+#[async_trait::async_trait]
+impl DataSource for GrpcDataSource {
+    async fn execute_query(
+        &self,
+        _method: &str,
+        _params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        Err(anyhow!("gRPC support not enabled"))
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        _method: &str,
+        _params: Option<&HashMap<String, Value>>,
         _pagination: Option<&PaginationParams>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        // Call non-paginated version for stub
-        self.execute_query(method, params).await
+        Err(anyhow!("gRPC support not enabled"))
     }
 
     async fn execute_mutation(
         &self,
-        method: &str,
+        _method: &str,
         _data: &HashMap<String, Value>,
     ) -> Result<Value> {
-        tracing::warn!(
-            "gRPC mutation execution not yet fully implemented: {}",
-            method
-        );
-        Ok(Value::Bool(true))
+        Err(anyhow!("gRPC support not enabled"))
     }
 }
 
@@ -1401,23 +3617,25 @@ pub struct S3DataSource {
 
 #[cfg(feature = "s3-datasource")]
 impl S3DataSource {
-    pub async fn new(bucket: String, region: String, prefix: Option<String>) -> Result<Self> {
-        use aws_config::BehaviorVersion;
-
+    /// `credentials` are an access key and secret key; without them the
+    /// ambient AWS environment (variables, profiles, instance roles) is used
+    pub async fn new(
+        bucket: String,
+        region: String,
+        prefix: Option<String>,
+        credentials: Option<(String, String)>,
+        endpoint: Option<String>,
+    ) -> Result<Self> {
         info!(
             bucket = %bucket,
             region = %region,
             prefix = ?prefix,
+            endpoint = ?endpoint,
+            static_credentials = credentials.is_some(),
             "Initializing S3 data source"
         );
 
-        // Load AWS configuration
-        let config = aws_config::defaults(BehaviorVersion::latest())
-            .region(aws_config::Region::new(region))
-            .load()
-            .await;
-
-        let client = aws_sdk_s3::Client::new(&config);
+        let client = s3_client(region, credentials, endpoint.as_deref()).await;
 
         // Verify bucket access by attempting to list objects
         match client
@@ -1454,6 +3672,134 @@ impl S3DataSource {
             key.to_string()
         }
     }
+
+    /// List one page of the objects and folders directly under `key`
+    ///
+    /// Returns rows with the object `key` (relative to the data source prefix,
+    /// so it can be queried back), `size`, `last_modified`, `etag` and
+    /// `is_folder`, plus the continuation token of the next page.
+    async fn list_page(
+        &self,
+        key: &str,
+        token: Option<String>,
+        max_keys: Option<usize>,
+    ) -> Result<(Vec<HashMap<String, Value>>, Option<String>)> {
+        use aws_sdk_s3::primitives::DateTimeFormat;
+
+        let full_prefix = self.full_key(key);
+        let relative = |full: &str| match &self.prefix {
+            Some(prefix) => full
+                .strip_prefix(prefix.as_str())
+                .map(|k| k.trim_start_matches('/'))
+                .unwrap_or(full)
+                .to_string(),
+            None => full.to_string(),
+        };
+
+        debug!(
+            bucket = %self.bucket,
+            prefix = %full_prefix,
+            token = ?token,
+            "Listing S3 objects"
+        );
+
+        let output = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(&full_prefix)
+            .delimiter("/")
+            .set_continuation_token(token)
+            .set_max_keys(max_keys.map(|n| n.min(i32::MAX as usize) as i32))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to list S3 objects: {}", e))?;
+
+        let folders = output.common_prefixes().iter().filter_map(|p| {
+            Some(HashMap::from([
+                ("key".to_string(), json!(relative(p.prefix()?))),
+                ("size".to_string(), Value::Null),
+                ("last_modified".to_string(), Value::Null),
+                ("etag".to_string(), Value::Null),
+                ("is_folder".to_string(), json!(true)),
+            ]))
+        });
+        let objects = output.contents().iter().filter_map(|object| {
+            Some(HashMap::from([
+                ("key".to_string(), json!(relative(object.key()?))),
+                ("size".to_string(), json!(object.size())),
+                (
+                    "last_modified".to_string(),
+                    json!(object
+                        .last_modified()
+                        .and_then(|t| t.fmt(DateTimeFormat::DateTime).ok())),
+                ),
+                (
+                    "etag".to_string(),
+                    json!(object.e_tag().map(|e| e.trim_matches('"'))),
+                ),
+                ("is_folder".to_string(), json!(false)),
+            ]))
+        });
+        let rows = folders.chain(objects).collect();
+
+        Ok((rows, output.next_continuation_token().map(str::to_string)))
+    }
+}
+
+/// S3 client of a region, with static `credentials` (access key and secret
+/// key) or those of the AWS environment
+#[cfg(feature = "s3-datasource")]
+pub(crate) async fn s3_client(
+    region: String,
+    credentials: Option<(String, String)>,
+    endpoint: Option<&str>,
+) -> aws_sdk_s3::Client {
+    use aws_config::BehaviorVersion;
+
+    // Load AWS configuration
+    let mut loader =
+        aws_config::defaults(BehaviorVersion::latest()).region(aws_config::Region::new(region));
+    if let Some((access_key, secret_key)) = credentials {
+        loader = loader.credentials_provider(aws_sdk_s3::config::Credentials::new(
+            access_key,
+            secret_key,
+            None,
+            None,
+            "backoffice-config",
+        ));
+    }
+    let config = loader.load().await;
+
+    // S3-compatible stores rarely support virtual-hosted bucket addressing
+    let mut s3_config = aws_sdk_s3::config::Builder::from(&config);
+    if let Some(endpoint) = endpoint {
+        s3_config = s3_config.endpoint_url(endpoint).force_path_style(true);
+    }
+    aws_sdk_s3::Client::from_conf(s3_config.build())
+}
+
+/// Static S3 credentials from the configured access key and secret key
+pub(crate) fn s3_credentials(
+    access_key: Option<&str>,
+    secret_key: Option<&str>,
+) -> Result<Option<(String, String)>> {
+    match (access_key, secret_key) {
+        (Some(access_key), Some(secret_key)) => {
+            Ok(Some((access_key.to_string(), secret_key.to_string())))
+        }
+        (None, None) => Ok(None),
+        _ => Err(anyhow!(
+            "S3 needs both access_key and secret_key, or neither"
+        )),
+    }
+}
+
+/// Keys ending in `/` (or empty keys) list the objects under them instead of
+/// fetching a single object
+#[cfg(feature = "s3-datasource")]
+fn is_s3_listing(key: &str) -> bool {
+    key.is_empty() || key.ends_with('/')
 }
 
 #[cfg(feature = "s3-datasource")]
@@ -1464,6 +3810,19 @@ impl DataSource for S3DataSource {
         key: &str,
         _params: Option<&HashMap<String, Value>>,
     ) -> Result<Vec<HashMap<String, Value>>> {
+        if is_s3_listing(key) {
+            let mut rows = Vec::new();
+            let mut token = None;
+            loop {
+                let (page, next) = self.list_page(key, token, None).await?;
+                rows.extend(page);
+                match next {
+                    Some(next) => token = Some(next),
+                    None => return Ok(rows),
+                }
+            }
+        }
+
         let full_key = self.full_key(key);
 
         debug!(
@@ -1532,11 +3891,20 @@ impl DataSource for S3DataSource {
         &self,
         key: &str,
         params: Option<&HashMap<String, Value>>,
-        _pagination: Option<&PaginationParams>,
+        pagination: Option<&PaginationParams>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        // S3 is object storage, pagination doesn't apply to individual objects
-        // Just call the regular execute_query
-        self.execute_query(key, params).await
+        // Listings page with continuation tokens; single objects are not paginated
+        match pagination {
+            Some(p) if is_s3_listing(key) => {
+                let cache_key =
+                    format!("s3|{}|{}|{}", self.bucket, self.full_key(key), p.page_size);
+                fetch_cursor_page(cache_key, p, |token| {
+                    self.list_page(key, token, Some(p.page_size))
+                })
+                .await
+            }
+            _ => self.execute_query(key, params).await,
+        }
     }
 
     async fn execute_mutation(&self, key: &str, data: &HashMap<String, Value>) -> Result<Value> {
@@ -1584,6 +3952,55 @@ impl DataSource for S3DataSource {
             }
         }
     }
+
+    async fn presign(
+        &self,
+        key: &str,
+        method: PresignMethod,
+        expires_in: std::time::Duration,
+    ) -> Result<PresignedUrl> {
+        use aws_sdk_s3::presigning::PresigningConfig;
+
+        let full_key = self.full_key(key);
+        let config = PresigningConfig::expires_in(expires_in)
+            .map_err(|e| anyhow!("Invalid presigned URL expiry: {}", e))?;
+
+        debug!(
+            bucket = %self.bucket,
+            key = %full_key,
+            method = ?method,
+            "Presigning S3 request"
+        );
+
+        let request = match method {
+            PresignMethod::Get => self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(&full_key)
+                .presigned(config)
+                .await
+                .map_err(|e| anyhow!("Failed to presign S3 download: {}", e))?,
+            PresignMethod::Put => self
+                .client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(&full_key)
+                .presigned(config)
+                .await
+                .map_err(|e| anyhow!("Failed to presign S3 upload: {}", e))?,
+        };
+
+        Ok(PresignedUrl {
+            url: request.uri().to_string(),
+            method: request.method().to_string(),
+            headers: request
+                .headers()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            expires_in: expires_in.as_secs(),
+        })
+    }
 }
 
 // Stub when feature is disabled
@@ -1595,7 +4012,13 @@ pub struct S3DataSource {
 
 #[cfg(not(feature = "s3-datasource"))]
 impl S3DataSource {
-    pub async fn new(bucket: String, _region: String, _prefix: Option<String>) -> Result<Self> {
+    pub async fn new(
+        bucket: String,
+        _region: String,
+        _prefix: Option<String>,
+        _credentials: Option<(String, String)>,
+        _endpoint: Option<String>,
+    ) -> Result<Self> {
         Err(anyhow!(
             "S3 support not enabled. Enable the 's3-datasource' feature in Cargo.toml"
         ))
@@ -1627,21 +4050,384 @@ impl DataSource for S3DataSource {
     }
 }
 
-/// Firebase data source
+/// Firebase (Cloud Firestore) data source
+///
+/// Talks to the Firestore REST API. Requests are authorized with an OAuth token
+/// obtained by signing a JWT with the `credentials_path` service account (or
+/// `GOOGLE_APPLICATION_CREDENTIALS`). When `FIRESTORE_EMULATOR_HOST` is set,
+/// requests go to the emulator without authentication.
 pub struct FirebaseDataSource {
-    #[allow(dead_code)]
-    project_id: String,
-    #[allow(dead_code)]
+    client: reqwest::Client,
+    base_url: String,
     collection: String,
+    service_account: Option<FirestoreServiceAccount>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct FirestoreServiceAccount {
+    client_email: String,
+    private_key: String,
+    #[serde(default = "default_google_token_uri")]
+    token_uri: String,
+}
+
+fn default_google_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+/// OAuth scope granting access to Firestore
+const FIRESTORE_SCOPE: &str = "https://www.googleapis.com/auth/datastore";
+
+/// Refresh cached tokens this long before they expire
+const TOKEN_REFRESH_MARGIN_SECS: u64 = 60;
+
+/// Cached access token and when it should be refreshed
+type CachedToken = (String, std::time::Instant);
+
+/// Access tokens per service account, shared across data source instances
+fn firestore_tokens() -> &'static std::sync::Mutex<HashMap<String, CachedToken>> {
+    static TOKENS: std::sync::OnceLock<std::sync::Mutex<HashMap<String, CachedToken>>> =
+        std::sync::OnceLock::new();
+    TOKENS.get_or_init(Default::default)
 }
 
 impl FirebaseDataSource {
-    pub fn new(project_id: String, collection: String) -> Self {
-        Self {
-            project_id,
+    pub async fn new(
+        project_id: String,
+        collection: String,
+        credentials_path: Option<String>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        info!(
+            project_id = %project_id,
+            collection = %collection,
+            "Initializing Firebase data source"
+        );
+
+        let emulator_host = std::env::var("FIRESTORE_EMULATOR_HOST").ok();
+        let base_url = format!(
+            "{}/v1/projects/{}/databases/(default)/documents",
+            emulator_host
+                .as_ref()
+                .map(|host| format!("http://{}", host))
+                .unwrap_or_else(|| "https://firestore.googleapis.com".to_string()),
+            project_id
+        );
+
+        let credentials_path =
+            credentials_path.or_else(|| std::env::var("GOOGLE_APPLICATION_CREDENTIALS").ok());
+        let service_account = match (&emulator_host, credentials_path) {
+            (Some(host), _) => {
+                info!(host = %host, "Using Firestore emulator");
+                None
+            }
+            (None, Some(path)) => {
+                let contents = tokio::fs::read_to_string(&path).await.map_err(|e| {
+                    anyhow!("Failed to read Firebase credentials '{}': {}", path, e)
+                })?;
+                let account: FirestoreServiceAccount = serde_json::from_str(&contents)
+                    .map_err(|e| anyhow!("Invalid Firebase service account '{}': {}", path, e))?;
+                Some(account)
+            }
+            (None, None) => {
+                warn!("No Firebase credentials configured, sending unauthenticated requests");
+                None
+            }
+        };
+
+        Ok(Self {
+            client: http_client(timeout),
+            base_url,
             collection,
+            service_account,
+        })
+    }
+
+    /// Bearer token for the service account, cached until shortly before expiry
+    async fn access_token(&self) -> Result<Option<String>> {
+        use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+        use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+        let Some(account) = &self.service_account else {
+            return Ok(None);
+        };
+
+        if let Some((token, expires_at)) = firestore_tokens()
+            .lock()
+            .unwrap()
+            .get(&account.client_email)
+        {
+            if *expires_at > Instant::now() {
+                return Ok(Some(token.clone()));
+            }
+        }
+
+        debug!(client_email = %account.client_email, "Requesting Firestore access token");
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let claims = json!({
+            "iss": account.client_email,
+            "scope": FIRESTORE_SCOPE,
+            "aud": account.token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        });
+        let key = EncodingKey::from_rsa_pem(account.private_key.as_bytes())
+            .map_err(|e| anyhow!("Invalid Firebase service account private key: {}", e))?;
+        let assertion = encode(&Header::new(Algorithm::RS256), &claims, &key)?;
+
+        let response = self
+            .client
+            .post(&account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| anyhow!("Firebase token request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!(
+                "Firebase token request failed {}: {}",
+                status,
+                error_text
+            ));
+        }
+
+        let body: Value = response.json().await?;
+        let token = body["access_token"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Firebase token response has no access_token"))?
+            .to_string();
+        let expires_in = body["expires_in"].as_u64().unwrap_or(3600);
+        let expires_at = Instant::now()
+            + Duration::from_secs(expires_in.saturating_sub(TOKEN_REFRESH_MARGIN_SECS));
+
+        firestore_tokens()
+            .lock()
+            .unwrap()
+            .insert(account.client_email.clone(), (token.clone(), expires_at));
+
+        Ok(Some(token))
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let request = match self.access_token().await? {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Firestore request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            error!(status = %status, error = %error_text, "Firestore returned an error");
+            return Err(anyhow!(
+                "Firestore returned error {}: {}",
+                status,
+                error_text
+            ));
+        }
+
+        let text = response.text().await?;
+        if text.trim().is_empty() {
+            return Ok(json!({}));
+        }
+        serde_json::from_str(&text)
+            .map_err(|e| anyhow!("Failed to parse Firestore response: {}", e))
+    }
+}
+
+/// Build a Firestore `structuredQuery` from an action query
+///
+/// The action query is JSON: `{"collection", "where": [{"field", "op", "value"}],
+/// "orderBy", "limit", "select"}`. A `where` value of `"{name}"` is replaced
+/// with the `name` request parameter.
+fn firestore_structured_query(
+    query: &str,
+    default_collection: &str,
+    params: Option<&HashMap<String, Value>>,
+    pagination: Option<&PaginationParams>,
+) -> Result<Value> {
+    let spec: Value = if query.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(query).map_err(|e| anyhow!("Invalid Firestore query JSON: {}", e))?
+    };
+
+    let collection = spec["collection"].as_str().unwrap_or(default_collection);
+    let mut structured = json!({ "from": [{ "collectionId": collection }] });
+
+    let mut filters = Vec::new();
+    for filter in spec["where"].as_array().into_iter().flatten() {
+        let field = filter["field"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Firestore filter is missing 'field'"))?;
+        let op = firestore_operator(filter["op"].as_str().unwrap_or("=="))?;
+
+        let mut value = filter["value"].clone();
+        bind_params(&mut value, params)?;
+
+        filters.push(json!({
+            "fieldFilter": {
+                "field": { "fieldPath": field },
+                "op": op,
+                "value": to_firestore_value(&value),
+            }
+        }));
+    }
+    match filters.len() {
+        0 => {}
+        1 => structured["where"] = filters.remove(0),
+        _ => {
+            structured["where"] = json!({ "compositeFilter": { "op": "AND", "filters": filters } })
+        }
+    }
+
+    let order_by: Vec<Value> = match &spec["orderBy"] {
+        Value::String(field) => vec![json!({ "field": { "fieldPath": field } })],
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(field) => json!({ "field": { "fieldPath": field } }),
+                other => {
+                    let direction = match other["direction"].as_str() {
+                        Some(d) if d.eq_ignore_ascii_case("desc") => "DESCENDING",
+                        _ => "ASCENDING",
+                    };
+                    json!({
+                        "field": { "fieldPath": other["field"] },
+                        "direction": direction,
+                    })
+                }
+            })
+            .collect(),
+        _ => vec![],
+    };
+    if !order_by.is_empty() {
+        structured["orderBy"] = Value::Array(order_by);
+    }
+
+    if let Some(fields) = spec["select"].as_array() {
+        let fields: Vec<Value> = fields.iter().map(|f| json!({ "fieldPath": f })).collect();
+        structured["select"] = json!({ "fields": fields });
+    }
+
+    match pagination {
+        Some(p) => {
+            structured["limit"] = json!(p.page_size);
+            structured["offset"] = json!(p.offset);
+        }
+        None => {
+            if let Some(limit) = spec["limit"].as_u64() {
+                structured["limit"] = json!(limit);
+            }
         }
     }
+
+    Ok(structured)
+}
+
+fn firestore_operator(op: &str) -> Result<&'static str> {
+    Ok(match op {
+        "==" | "EQUAL" => "EQUAL",
+        "!=" | "NOT_EQUAL" => "NOT_EQUAL",
+        "<" | "LESS_THAN" => "LESS_THAN",
+        "<=" | "LESS_THAN_OR_EQUAL" => "LESS_THAN_OR_EQUAL",
+        ">" | "GREATER_THAN" => "GREATER_THAN",
+        ">=" | "GREATER_THAN_OR_EQUAL" => "GREATER_THAN_OR_EQUAL",
+        "array-contains" | "ARRAY_CONTAINS" => "ARRAY_CONTAINS",
+        "array-contains-any" | "ARRAY_CONTAINS_ANY" => "ARRAY_CONTAINS_ANY",
+        "in" | "IN" => "IN",
+        "not-in" | "NOT_IN" => "NOT_IN",
+        other => return Err(anyhow!("Unsupported Firestore operator: {}", other)),
+    })
+}
+
+/// Convert JSON to a Firestore typed value
+fn to_firestore_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "nullValue": null }),
+        Value::Bool(b) => json!({ "booleanValue": b }),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "integerValue": n.to_string() }),
+        Value::Number(n) => json!({ "doubleValue": n }),
+        Value::String(s) => json!({ "stringValue": s }),
+        Value::Array(items) => json!({
+            "arrayValue": { "values": items.iter().map(to_firestore_value).collect::<Vec<_>>() }
+        }),
+        Value::Object(obj) => json!({ "mapValue": { "fields": to_firestore_fields(obj) } }),
+    }
+}
+
+fn to_firestore_fields<'a>(fields: impl IntoIterator<Item = (&'a String, &'a Value)>) -> Value {
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(k, v)| (k.clone(), to_firestore_value(v)))
+            .collect(),
+    )
+}
+
+/// Convert a Firestore typed value to JSON
+fn from_firestore_value(value: &Value) -> Value {
+    let Some((kind, inner)) = value.as_object().and_then(|obj| obj.iter().next()) else {
+        return Value::Null;
+    };
+
+    match kind.as_str() {
+        "integerValue" => inner
+            .as_str()
+            .and_then(|s| s.parse::<i64>().ok())
+            .map(Value::from)
+            .unwrap_or_else(|| inner.clone()),
+        "arrayValue" => Value::Array(
+            inner["values"]
+                .as_array()
+                .map(|items| items.iter().map(from_firestore_value).collect())
+                .unwrap_or_default(),
+        ),
+        "mapValue" => Value::Object(
+            inner["fields"]
+                .as_object()
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .map(|(k, v)| (k.clone(), from_firestore_value(v)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        // nullValue, booleanValue, doubleValue, stringValue, timestampValue,
+        // bytesValue, referenceValue and geoPointValue map directly
+        _ => inner.clone(),
+    }
+}
+
+/// Convert a Firestore document to a row; the document id becomes `id`
+fn firestore_document_to_row(document: &Value) -> HashMap<String, Value> {
+    let mut row: HashMap<String, Value> = document["fields"]
+        .as_object()
+        .map(|fields| {
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), from_firestore_value(v)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Some(id) = document["name"].as_str().and_then(|n| n.rsplit('/').next()) {
+        row.entry("id".to_string())
+            .or_insert_with(|| Value::String(id.to_string()));
+    }
+
+    row
 }
 
 #[async_trait::async_trait]
@@ -1649,35 +4435,120 @@ impl DataSource for FirebaseDataSource {
     async fn execute_query(
         &self,
         query: &str,
-        _params: Option<&HashMap<String, Value>>,
+        params: Option<&HashMap<String, Value>>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        tracing::warn!(
-            "Firebase query execution not yet fully implemented: {}",
-            query
-        );
-        Ok(vec![])
+        self.execute_query_paginated(query, params, None).await
     }
 
     async fn execute_query_paginated(
         &self,
         query: &str,
         params: Option<&HashMap<String, Value>>,
-        _pagination: Option<&PaginationParams>,
+        pagination: Option<&PaginationParams>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        // Call non-paginated version for stub
-        self.execute_query(query, params).await
-    }
+        let structured = firestore_structured_query(query, &self.collection, params, pagination)?;
 
-    async fn execute_mutation(
-        &self,
-        doc_id: &str,
-        _data: &HashMap<String, Value>,
-    ) -> Result<Value> {
-        tracing::warn!(
-            "Firebase mutation execution not yet fully implemented: {}",
-            doc_id
+        debug!(
+            structured_query = %structured,
+            pagination = ?pagination,
+            "Executing Firestore query"
         );
-        Ok(Value::Bool(true))
+
+        let response = self
+            .send(
+                self.client
+                    .post(format!("{}:runQuery", self.base_url))
+                    .json(&json!({ "structuredQuery": structured })),
+            )
+            .await?;
+
+        let rows: Vec<HashMap<String, Value>> = response
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|result| result.get("document").is_some())
+            .map(|result| firestore_document_to_row(&result["document"]))
+            .collect();
+
+        info!(count = rows.len(), "Firestore query completed");
+        Ok(rows)
+    }
+
+    /// `query` selects the operation: `create` (default), `update` or `delete`
+    ///
+    /// The document id is taken from `data.id`; it is optional for `create`.
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let operation = query.trim().to_lowercase();
+        if operation == "delete" {
+            return self.execute_delete(query, data).await;
+        }
+        let doc_id = data.get("id").map(|id| match id {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        });
+        let fields = to_firestore_fields(data.iter().filter(|(k, _)| k.as_str() != "id"));
+        let collection_url = format!("{}/{}", self.base_url, self.collection);
+
+        debug!(
+            collection = %self.collection,
+            operation = %operation,
+            doc_id = ?doc_id,
+            "Executing Firestore mutation"
+        );
+
+        let result = if operation.starts_with("update") {
+            let doc_id = doc_id.ok_or_else(|| anyhow!("Firestore update requires an id"))?;
+            let mut mask: Vec<(&str, &str)> = data
+                .keys()
+                .filter(|k| k.as_str() != "id")
+                .map(|k| ("updateMask.fieldPaths", k.as_str()))
+                .collect();
+            mask.push(("currentDocument.exists", "true"));
+            let document = self
+                .send(
+                    self.client
+                        .patch(format!("{}/{}", collection_url, doc_id))
+                        .query(&mask)
+                        .json(&json!({ "fields": fields })),
+                )
+                .await?;
+            Value::Object(firestore_document_to_row(&document).into_iter().collect())
+        } else if operation.is_empty() || operation.starts_with("create") {
+            let mut request = self
+                .client
+                .post(&collection_url)
+                .json(&json!({ "fields": fields }));
+            if let Some(doc_id) = &doc_id {
+                request = request.query(&[("documentId", doc_id)]);
+            }
+            let document = self.send(request).await?;
+            Value::Object(firestore_document_to_row(&document).into_iter().collect())
+        } else {
+            return Err(anyhow!(
+                "Unsupported Firestore mutation '{}'; use create, update or delete",
+                query
+            ));
+        };
+
+        info!(collection = %self.collection, operation = %operation, "Firestore mutation completed");
+        Ok(result)
+    }
+
+    /// Deletes the document `data.id`; the statement is not used
+    async fn execute_delete(&self, _query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let doc_id = match data.get("id") {
+            Some(Value::String(s)) => s.clone(),
+            Some(other) if !other.is_null() => other.to_string(),
+            _ => return Err(anyhow!("Firestore delete requires an id")),
+        };
+        self.send(
+            self.client
+                .delete(format!("{}/{}/{}", self.base_url, self.collection, doc_id)),
+        )
+        .await?;
+
+        info!(collection = %self.collection, doc_id = %doc_id, "Firestore document deleted");
+        Ok(json!({ "id": doc_id, "deleted": true }))
     }
 }
 
@@ -1690,14 +4561,80 @@ pub struct SupabaseDataSource {
 }
 
 impl SupabaseDataSource {
-    pub fn new(url: String, api_key: String, table: String) -> Self {
-        let client = reqwest::Client::new();
+    pub fn new(url: String, api_key: String, table: String, timeout: Duration) -> Self {
         Self {
             url,
             api_key,
             table,
-            client,
+            client: http_client(timeout),
+        }
+    }
+
+    /// Request to a PostgREST path such as the table or `rpc/<fn>`, with the
+    /// API key attached
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}/rest/v1/{}", self.url, path))
+            .header("apikey", &self.api_key)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+    }
+
+    /// Send a mutation request, returning its JSON body (null when empty)
+    async fn send_mutation(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Supabase mutation request failed: {}", e))?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+
+        if !status.is_success() {
+            return Err(anyhow!("Supabase mutation failed {}: {}", status, text));
         }
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&text)
+            .map_err(|e| anyhow!("Failed to parse Supabase mutation response: {}", e))
+    }
+}
+
+/// PostgREST row filters for an update or delete
+///
+/// Explicit filters (`status=eq.draft&owner=eq.{owner}`) are filled from the
+/// payload; without them the row is matched by `data.id`.
+fn supabase_filters(filters: &str, data: &HashMap<String, Value>) -> Result<Vec<(String, String)>> {
+    let explicit: Vec<(String, String)> = filters
+        .split('&')
+        .filter_map(|filter| filter.split_once('='))
+        .map(|(column, condition)| {
+            let condition = match condition
+                .rsplit_once('.')
+                .and_then(|(op, value)| Some((op, value.strip_prefix('{')?.strip_suffix('}')?)))
+            {
+                Some((op, name)) => {
+                    let value = data
+                        .get(name)
+                        .ok_or_else(|| anyhow!("Missing value for '{{{}}}'", name))?;
+                    format!("{}.{}", op, value_as_text(value))
+                }
+                None => condition.to_string(),
+            };
+            Ok((column.trim().to_string(), condition))
+        })
+        .collect::<Result<_>>()?;
+    if !explicit.is_empty() {
+        return Ok(explicit);
+    }
+
+    match data.get("id").filter(|id| !id.is_null()) {
+        Some(id) => Ok(vec![(
+            "id".to_string(),
+            format!("eq.{}", value_as_text(id)),
+        )]),
+        None => Err(anyhow!(
+            "Supabase updates and deletes need filters or an id"
+        )),
     }
 }
 
@@ -1727,10 +4664,7 @@ impl DataSource for SupabaseDataSource {
         );
 
         let mut request = self
-            .client
-            .get(&url)
-            .header("apikey", &self.api_key)
-            .header("Authorization", format!("Bearer {}", self.api_key))
+            .request(reqwest::Method::GET, &self.table)
             .header("Prefer", "return=representation");
 
         // Add filters from query string (Supabase PostgREST format)
@@ -1797,387 +4731,4340 @@ impl DataSource for SupabaseDataSource {
         }
     }
 
-    async fn execute_mutation(
-        &self,
-        _doc_id: &str,
-        data: &HashMap<String, Value>,
-    ) -> Result<Value> {
-        let url = format!("{}/rest/v1/{}", self.url, self.table);
+    /// `query` selects the operation: `create` (default), `upsert`,
+    /// `update [filters]`, `delete [filters]` or `rpc <function>`
+    ///
+    /// Updates and deletes match rows by the PostgREST filters given after
+    /// the operation, or by `data.id`. RPC calls pass `data` as the function
+    /// arguments.
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let query = query.trim();
+        let (operation, rest) = query.split_once(char::is_whitespace).unwrap_or((query, ""));
+        let operation = operation.to_lowercase();
+        let rest = rest.trim();
 
-        let request = self
-            .client
-            .post(&url)
-            .header("apikey", &self.api_key)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&data);
+        debug!(
+            table = %self.table,
+            operation = %operation,
+            "Executing Supabase mutation"
+        );
 
-        let response = request.send().await?;
-        let result: Value = response.json().await?;
+        let result = match operation.as_str() {
+            "" | "create" | "insert" => {
+                self.send_mutation(
+                    self.request(reqwest::Method::POST, &self.table)
+                        .header("Prefer", "return=representation")
+                        .json(data),
+                )
+                .await?
+            }
+            "upsert" => {
+                self.send_mutation(
+                    self.request(reqwest::Method::POST, &self.table)
+                        .header(
+                            "Prefer",
+                            "resolution=merge-duplicates,return=representation",
+                        )
+                        .json(data),
+                )
+                .await?
+            }
+            "update" => {
+                let filters = supabase_filters(rest, data)?;
+                let changes: HashMap<&String, &Value> =
+                    data.iter().filter(|(k, _)| k.as_str() != "id").collect();
+                self.send_mutation(
+                    self.request(reqwest::Method::PATCH, &self.table)
+                        .query(&filters)
+                        .header("Prefer", "return=representation")
+                        .json(&changes),
+                )
+                .await?
+            }
+            "delete" => {
+                let filters = supabase_filters(rest, data)?;
+                self.send_mutation(
+                    self.request(reqwest::Method::DELETE, &self.table)
+                        .query(&filters)
+                        .header("Prefer", "return=representation"),
+                )
+                .await?
+            }
+            "rpc" if !rest.is_empty() => {
+                self.send_mutation(
+                    self.request(reqwest::Method::POST, &format!("rpc/{}", rest))
+                        .json(data),
+                )
+                .await?
+            }
+            _ => return Err(anyhow!("Unsupported Supabase mutation: {}", query)),
+        };
 
+        info!(table = %self.table, operation = %operation, "Supabase mutation completed");
         Ok(result)
     }
+
+    /// Deletes the rows matching `data.id`; the statement is not used
+    async fn execute_delete(&self, _query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        self.execute_mutation("delete", data).await
+    }
 }
 
-/// WebSocket data source for real-time communication
-#[cfg(feature = "websocket-datasource")]
-pub struct WebSocketDataSource {
-    url: String,
-    #[allow(dead_code)]
-    reconnect: bool,
-    #[allow(dead_code)]
-    heartbeat_interval: Option<u32>,
+/// CouchDB data source
+///
+/// Queries are Mango `_find` requests: the action query is either a selector
+/// or a full `_find` body (`selector`, `sort`, `fields`, ...).
+pub struct CouchDbDataSource {
+    client: reqwest::Client,
+    database_url: String,
+    username: Option<String>,
+    password: Option<String>,
 }
 
-#[cfg(feature = "websocket-datasource")]
-impl WebSocketDataSource {
-    pub async fn new(
+/// Retries for saves that lose a `_rev` race without a caller supplied `_rev`
+const COUCHDB_CONFLICT_RETRIES: usize = 3;
+
+impl CouchDbDataSource {
+    pub fn new(
         url: String,
-        reconnect: bool,
-        heartbeat_interval: Option<u32>,
-    ) -> Result<Self> {
-        use tokio_tungstenite::connect_async;
+        database: String,
+        username: Option<String>,
+        password: Option<String>,
+        timeout: Duration,
+    ) -> Self {
+        info!(url = %url, database = %database, "Initializing CouchDB data source");
 
-        info!(
-            url = %url,
-            reconnect = reconnect,
-            heartbeat_interval = ?heartbeat_interval,
-            "Initializing WebSocket data source"
-        );
+        Self {
+            client: http_client(timeout),
+            database_url: format!("{}/{}", url.trim_end_matches('/'), database),
+            username,
+            password,
+        }
+    }
 
-        // Test connection
-        match connect_async(&url).await {
-            Ok((ws_stream, _)) => {
-                info!(url = %url, "Successfully connected to WebSocket");
-                drop(ws_stream); // Close test connection
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = if path.is_empty() {
+            self.database_url.clone()
+        } else {
+            format!("{}/{}", self.database_url, path)
+        };
+        let request = self.client.request(method, url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_ref()),
+            None => request,
+        }
+    }
+
+    /// Send a request, returning the status and JSON body
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<(reqwest::StatusCode, Value)> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("CouchDB request failed: {}", e))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        Ok((status, body))
+    }
+
+    /// Run one `_find` request, returning the documents and the next bookmark
+    async fn find(&self, body: &Value) -> Result<(Vec<Value>, Option<String>)> {
+        let (status, response) = self
+            .send(self.request(reqwest::Method::POST, "_find").json(body))
+            .await?;
+
+        if !status.is_success() {
+            return Err(anyhow!(
+                "CouchDB _find failed {}: {}",
+                status,
+                couchdb_reason(&response)
+            ));
+        }
+        if let Some(warning) = response["warning"].as_str() {
+            debug!(warning = %warning, "CouchDB _find warning");
+        }
+
+        let docs = response["docs"].as_array().cloned().unwrap_or_default();
+        let bookmark = response["bookmark"]
+            .as_str()
+            .filter(|b| !b.is_empty() && *b != "nil")
+            .map(|b| b.to_string());
+        Ok((docs, bookmark))
+    }
+
+    /// Current revision of a document, if it exists
+    async fn current_rev(&self, id: &str) -> Result<Option<String>> {
+        let (status, doc) = self
+            .send(self.request(reqwest::Method::GET, &couchdb_doc_path(id)))
+            .await?;
+        match status {
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => Ok(doc["_rev"].as_str().map(|r| r.to_string())),
+            s => Err(anyhow!(
+                "CouchDB lookup of '{}' failed {}: {}",
+                id,
+                s,
+                couchdb_reason(&doc)
+            )),
+        }
+    }
+
+    async fn save(&self, mut doc: serde_json::Map<String, Value>) -> Result<Value> {
+        let Some(id) = doc
+            .get("_id")
+            .and_then(|id| id.as_str())
+            .map(|s| s.to_string())
+        else {
+            // No id: let CouchDB generate one
+            let (status, result) = self
+                .send(self.request(reqwest::Method::POST, "").json(&doc))
+                .await?;
+            if !status.is_success() {
+                return Err(anyhow!(
+                    "CouchDB insert failed {}: {}",
+                    status,
+                    couchdb_reason(&result)
+                ));
             }
-            Err(e) => {
-                error!(
-                    url = %url,
-                    error = %e,
-                    "Failed to connect to WebSocket"
-                );
-                return Err(anyhow!("Failed to connect to WebSocket: {}", e));
+            return Ok(result);
+        };
+
+        // A caller supplied `_rev` means optimistic concurrency: conflicts are errors
+        let caller_rev = doc.contains_key("_rev");
+        let mut attempts = 0;
+        loop {
+            if !caller_rev {
+                match self.current_rev(&id).await? {
+                    Some(rev) => doc.insert("_rev".to_string(), Value::String(rev)),
+                    None => doc.remove("_rev"),
+                };
+            }
+
+            let (status, result) = self
+                .send(
+                    self.request(reqwest::Method::PUT, &couchdb_doc_path(&id))
+                        .json(&doc),
+                )
+                .await?;
+
+            if status.is_success() {
+                return Ok(result);
+            }
+            if status == reqwest::StatusCode::CONFLICT {
+                if caller_rev {
+                    return Err(anyhow!(
+                        "Document '{}' was modified by someone else; reload it and try again",
+                        id
+                    ));
+                }
+                attempts += 1;
+                if attempts < COUCHDB_CONFLICT_RETRIES {
+                    warn!(id = %id, attempt = attempts, "CouchDB update conflict, retrying");
+                    continue;
+                }
             }
+            return Err(anyhow!(
+                "CouchDB update of '{}' failed {}: {}",
+                id,
+                status,
+                couchdb_reason(&result)
+            ));
         }
+    }
 
-        Ok(Self {
-            url,
-            reconnect,
-            heartbeat_interval,
-        })
+    async fn delete(&self, id: &str, rev: Option<String>) -> Result<Value> {
+        let rev = match rev {
+            Some(rev) => rev,
+            None => self
+                .current_rev(id)
+                .await?
+                .ok_or_else(|| anyhow!("CouchDB document '{}' not found", id))?,
+        };
+
+        let (status, result) = self
+            .send(
+                self.request(reqwest::Method::DELETE, &couchdb_doc_path(id))
+                    .query(&[("rev", rev)]),
+            )
+            .await?;
+
+        match status {
+            s if s.is_success() => Ok(result),
+            reqwest::StatusCode::CONFLICT => Err(anyhow!(
+                "Document '{}' was modified by someone else; reload it and try again",
+                id
+            )),
+            s => Err(anyhow!(
+                "CouchDB delete of '{}' failed {}: {}",
+                id,
+                s,
+                couchdb_reason(&result)
+            )),
+        }
     }
+}
 
-    async fn connect(
-        &self,
-    ) -> Result<
-        tokio_tungstenite::WebSocketStream<
-            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-        >,
-    > {
-        use tokio_tungstenite::connect_async;
+fn couchdb_doc_path(id: &str) -> String {
+    url_encode(id)
+}
 
-        debug!(url = %self.url, "Connecting to WebSocket");
+fn couchdb_reason(body: &Value) -> String {
+    body["reason"]
+        .as_str()
+        .or_else(|| body["error"].as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| body.to_string())
+}
 
-        let (ws_stream, _) = connect_async(&self.url)
-            .await
-            .map_err(|e| anyhow!("WebSocket connection failed: {}", e))?;
+/// Build a `_find` body from an action query
+fn couchdb_find_body(query: &str, params: Option<&HashMap<String, Value>>) -> Result<Value> {
+    let mut spec: Value = if query.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(query).map_err(|e| anyhow!("Invalid CouchDB query JSON: {}", e))?
+    };
+    bind_params(&mut spec, params)?;
+
+    match spec {
+        Value::Object(obj) if obj.contains_key("selector") => Ok(Value::Object(obj)),
+        selector @ Value::Object(_) => Ok(json!({ "selector": selector })),
+        _ => Err(anyhow!("CouchDB query must be a JSON object")),
+    }
+}
 
-        Ok(ws_stream)
+/// Convert a CouchDB document to a row; `_id` is also exposed as `id`
+fn couchdb_doc_to_row(doc: Value) -> Option<HashMap<String, Value>> {
+    let Value::Object(obj) = doc else {
+        return None;
+    };
+    let mut row: HashMap<String, Value> = obj.into_iter().collect();
+    if let Some(id) = row.get("_id").cloned() {
+        row.entry("id".to_string()).or_insert(id);
     }
+    Some(row)
 }
 
-#[cfg(feature = "websocket-datasource")]
 #[async_trait::async_trait]
-impl DataSource for WebSocketDataSource {
+impl DataSource for CouchDbDataSource {
     async fn execute_query(
         &self,
-        message: &str,
+        query: &str,
         params: Option<&HashMap<String, Value>>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        use futures_util::{SinkExt, StreamExt};
-        use tokio_tungstenite::tungstenite::Message;
+        self.execute_query_paginated(query, params, None).await
+    }
 
-        let mut ws_stream = self.connect().await?;
+    /// Pages are walked with bookmarks
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let mut body = couchdb_find_body(query, params)?;
 
-        // Prepare the query message
-        let query_data = if let Some(params) = params {
-            let mut data = params.clone();
-            data.insert("query".to_string(), Value::String(message.to_string()));
-            serde_json::to_string(&data)?
-        } else {
-            message.to_string()
+        debug!(
+            database = %self.database_url,
+            body = %body,
+            pagination = ?pagination,
+            "Executing CouchDB query"
+        );
+
+        let Some(p) = pagination else {
+            let (docs, _) = self.find(&body).await?;
+            let rows: Vec<_> = docs.into_iter().filter_map(couchdb_doc_to_row).collect();
+            info!(count = rows.len(), "CouchDB query completed");
+            return Ok(rows);
         };
 
-        debug!(message_length = query_data.len(), "Sending WebSocket query");
+        body["limit"] = json!(p.page_size);
+        let cache_key = format!("{}|{}", self.database_url, body);
+        let rows = fetch_cursor_page(cache_key, p, |bookmark| {
+            let mut body = body.clone();
+            if let Some(bookmark) = bookmark {
+                body["bookmark"] = json!(bookmark);
+            }
+            async move {
+                let (docs, next) = self.find(&body).await?;
+                let rows = docs.into_iter().filter_map(couchdb_doc_to_row).collect();
+                Ok((rows, next))
+            }
+        })
+        .await?;
 
-        // Send the query
-        ws_stream
-            .send(Message::Text(query_data))
-            .await
-            .map_err(|e| anyhow!("Failed to send WebSocket message: {}", e))?;
+        info!(count = rows.len(), page = p.page, "CouchDB query completed");
+        Ok(rows)
+    }
 
-        // Wait for response
-        match ws_stream.next().await {
-            Some(Ok(Message::Text(text))) => {
-                debug!(response_length = text.len(), "Received WebSocket response");
+    /// `query` set to `delete` deletes the document `id`; anything else saves
+    /// `data`, creating or updating the document `id`
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        if query.trim().eq_ignore_ascii_case("delete") {
+            return self.execute_delete(query, data).await;
+        }
 
-                // Try to parse as JSON
-                match serde_json::from_str::<Value>(&text) {
-                    Ok(Value::Array(arr)) => {
-                        let mut result = Vec::new();
-                        for item in arr {
-                            if let Value::Object(obj) = item {
-                                result.push(obj.into_iter().collect());
-                            }
-                        }
-                        Ok(result)
-                    }
-                    Ok(Value::Object(obj)) => Ok(vec![obj.into_iter().collect()]),
-                    _ => {
-                        // If not JSON, return as text
-                        let mut map = HashMap::new();
-                        map.insert("response".to_string(), Value::String(text));
-                        Ok(vec![map])
-                    }
-                }
-            }
-            Some(Ok(Message::Binary(bytes))) => {
-                // Handle binary response
-                let text = String::from_utf8_lossy(&bytes).to_string();
-                let mut map = HashMap::new();
-                map.insert("response".to_string(), Value::String(text));
-                Ok(vec![map])
-            }
-            Some(Ok(Message::Close(_))) => {
-                warn!("WebSocket connection closed by server");
-                Err(anyhow!("WebSocket connection closed by server"))
-            }
-            Some(Err(e)) => {
-                error!(error = %e, "WebSocket error");
-                Err(anyhow!("WebSocket error: {}", e))
-            }
-            None => {
-                warn!("WebSocket stream ended unexpectedly");
-                Err(anyhow!("WebSocket stream ended unexpectedly"))
+        let doc = couchdb_doc(data);
+        debug!(database = %self.database_url, id = ?doc.get("_id"), "Executing CouchDB mutation");
+        let result = self.save(doc).await?;
+
+        info!(result = %result, "CouchDB mutation completed");
+        Ok(result)
+    }
+
+    /// Deletes the document `id` at `_rev`, or at its latest revision; the
+    /// statement is not used
+    async fn execute_delete(&self, _query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let doc = couchdb_doc(data);
+        let id = doc
+            .get("_id")
+            .and_then(|id| id.as_str())
+            .ok_or_else(|| anyhow!("CouchDB delete requires an id"))?
+            .to_string();
+        let rev = doc
+            .get("_rev")
+            .and_then(|r| r.as_str())
+            .map(|r| r.to_string());
+
+        debug!(database = %self.database_url, id = %id, "Deleting CouchDB document");
+        let result = self.delete(&id, rev).await?;
+
+        info!(result = %result, "CouchDB document deleted");
+        Ok(result)
+    }
+}
+
+/// Mutation data as a CouchDB document, with `id` stored as `_id`
+fn couchdb_doc(data: &HashMap<String, Value>) -> serde_json::Map<String, Value> {
+    let mut doc: serde_json::Map<String, Value> =
+        data.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    if let Some(id) = doc.remove("id") {
+        let id = match id {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        doc.entry("_id".to_string()).or_insert(Value::String(id));
+    }
+    doc
+}
+
+/// InfluxDB v2 data source
+///
+/// Queries are Flux scripts; mutations write points with line protocol.
+pub struct InfluxDbDataSource {
+    client: reqwest::Client,
+    url: String,
+    org: String,
+    bucket: String,
+    token: String,
+    precision: String,
+}
+
+impl InfluxDbDataSource {
+    pub fn new(
+        url: String,
+        org: String,
+        bucket: String,
+        token: String,
+        precision: Option<String>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let precision = precision.unwrap_or_else(|| "ns".to_string());
+        if !matches!(precision.as_str(), "ns" | "us" | "ms" | "s") {
+            return Err(anyhow!(
+                "Invalid InfluxDB precision '{}', expected ns, us, ms or s",
+                precision
+            ));
+        }
+
+        info!(
+            url = %url,
+            org = %org,
+            bucket = %bucket,
+            precision = %precision,
+            "Initializing InfluxDB data source"
+        );
+
+        Ok(Self {
+            client: http_client(timeout),
+            url: url.trim_end_matches('/').to_string(),
+            org,
+            bucket,
+            token,
+            precision,
+        })
+    }
+}
+
+/// Replace `{name}` placeholders in a Flux script with Flux literals
+///
+/// `{bucket}` defaults to the configured bucket.
+fn bind_flux_params(query: &str, bucket: &str, params: Option<&HashMap<String, Value>>) -> String {
+    let mut flux = query.replace("{bucket}", &flux_literal(&Value::from(bucket)));
+    if let Some(params) = params {
+        for (name, value) in params {
+            flux = flux.replace(&format!("{{{}}}", name), &flux_literal(value));
+        }
+    }
+    flux
+}
+
+fn flux_literal(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+        Value::Null => "\"\"".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Split CSV text into records, honouring quoted fields
+pub(crate) fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
             }
-            _ => {
-                warn!("Unexpected WebSocket message type");
-                Ok(vec![])
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
             }
+            c => field.push(c),
         }
     }
-
-    async fn execute_query_paginated(
-        &self,
-        message: &str,
-        params: Option<&HashMap<String, Value>>,
-        _pagination: Option<&PaginationParams>,
-    ) -> Result<Vec<HashMap<String, Value>>> {
-        // WebSocket is real-time bidirectional, pagination doesn't typically apply
-        // Just call the regular execute_query
-        self.execute_query(message, params).await
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
     }
 
-    async fn execute_mutation(
-        &self,
-        message: &str,
-        data: &HashMap<String, Value>,
-    ) -> Result<Value> {
-        use futures_util::{SinkExt, StreamExt};
-        use tokio_tungstenite::tungstenite::Message;
+    records
+}
 
-        let mut ws_stream = self.connect().await?;
+/// Convert an annotated CSV query response into rows
+///
+/// The annotation column and the `result`/`table` bookkeeping columns are
+/// dropped; cells are typed using the `#datatype` annotation.
+fn influx_csv_to_rows(text: &str) -> Result<Vec<HashMap<String, Value>>> {
+    let mut rows = Vec::new();
+    let mut datatypes: Vec<String> = Vec::new();
+    let mut defaults: Vec<String> = Vec::new();
+    let mut header: Option<Vec<String>> = None;
+
+    for record in parse_csv(text, ',') {
+        if record.iter().all(|cell| cell.is_empty()) {
+            // A blank line ends the current table
+            datatypes.clear();
+            defaults.clear();
+            header = None;
+            continue;
+        }
 
-        // Prepare the mutation message
-        let mut mutation_data = data.clone();
-        mutation_data.insert("mutation".to_string(), Value::String(message.to_string()));
-        let json = serde_json::to_string(&mutation_data)?;
+        match record[0].as_str() {
+            "#datatype" => {
+                datatypes = record;
+                header = None;
+                continue;
+            }
+            "#default" => {
+                defaults = record;
+                continue;
+            }
+            first if first.starts_with('#') => continue,
+            _ => {}
+        }
 
-        debug!(message_length = json.len(), "Sending WebSocket mutation");
+        let Some(columns) = &header else {
+            header = Some(record);
+            continue;
+        };
 
-        // Send the mutation
-        ws_stream
-            .send(Message::Text(json))
-            .await
-            .map_err(|e| anyhow!("Failed to send WebSocket message: {}", e))?;
+        // Errors after the response started streaming come back as a table
+        if columns.iter().any(|c| c == "error") && columns.iter().any(|c| c == "reference") {
+            let index = columns.iter().position(|c| c == "error").unwrap_or(0);
+            return Err(anyhow!(
+                "InfluxDB query failed: {}",
+                record.get(index).cloned().unwrap_or_default()
+            ));
+        }
 
-        // Wait for response
-        match ws_stream.next().await {
-            Some(Ok(Message::Text(text))) => {
-                info!(
-                    response_length = text.len(),
-                    "Received WebSocket mutation response"
-                );
+        let mut row = HashMap::new();
+        for (i, column) in columns.iter().enumerate() {
+            if column.is_empty() || column == "result" || column == "table" {
+                continue;
+            }
+            let cell = match record.get(i).map(|c| c.as_str()) {
+                Some("") | None => defaults.get(i).map(|d| d.as_str()).unwrap_or(""),
+                Some(cell) => cell,
+            };
+            let datatype = datatypes.get(i).map(|d| d.as_str()).unwrap_or("string");
+            row.insert(column.clone(), influx_cell(cell, datatype));
+        }
+        rows.push(row);
+    }
 
-                // Try to parse as JSON
-                match serde_json::from_str::<Value>(&text) {
-                    Ok(value) => Ok(value),
-                    Err(_) => Ok(Value::String(text)),
+    Ok(rows)
+}
+
+fn influx_cell(cell: &str, datatype: &str) -> Value {
+    if cell.is_empty() && datatype != "string" {
+        return Value::Null;
+    }
+    match datatype {
+        "long" => cell.parse::<i64>().map(Value::from).unwrap_or(Value::Null),
+        "unsignedLong" => cell.parse::<u64>().map(Value::from).unwrap_or(Value::Null),
+        "double" => cell
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        "boolean" => Value::Bool(cell == "true"),
+        _ => Value::String(cell.to_string()),
+    }
+}
+
+/// Build a line protocol point from mutation data
+///
+/// `tags` and `fields` objects are used as-is when present. Otherwise string
+/// values become tags and numbers and booleans become fields. `time` (or
+/// `_time`) sets the timestamp, as an RFC 3339 string or a number in
+/// `precision` units.
+fn influx_line_protocol(
+    measurement: &str,
+    data: &HashMap<String, Value>,
+    precision: &str,
+) -> Result<String> {
+    let mut tags: Vec<(String, String)> = Vec::new();
+    let mut fields: Vec<(String, String)> = Vec::new();
+    let mut timestamp = None;
+
+    let mut entries: Vec<(&String, &Value)> = data.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (key, value) in entries {
+        match (key.as_str(), value) {
+            ("time" | "_time", value) => timestamp = Some(influx_timestamp(value, precision)?),
+            ("measurement" | "_measurement", _) => {}
+            ("tags", Value::Object(obj)) => {
+                for (k, v) in obj {
+                    tags.push((k.clone(), value_as_text(v)));
                 }
             }
-            Some(Ok(Message::Binary(bytes))) => {
-                let text = String::from_utf8_lossy(&bytes).to_string();
-                Ok(Value::String(text))
-            }
-            Some(Ok(Message::Close(_))) => {
-                warn!("WebSocket connection closed by server");
-                Err(anyhow!("WebSocket connection closed by server"))
+            ("fields", Value::Object(obj)) => {
+                for (k, v) in obj {
+                    if let Some(field) = influx_field_value(v) {
+                        fields.push((k.clone(), field));
+                    }
+                }
             }
-            Some(Err(e)) => {
-                error!(error = %e, "WebSocket error");
-                Err(anyhow!("WebSocket error: {}", e))
+            (_, Value::String(s)) if !data.contains_key("tags") => {
+                tags.push((key.clone(), s.clone()))
             }
-            None => {
-                warn!("WebSocket stream ended unexpectedly");
-                Err(anyhow!("WebSocket stream ended unexpectedly"))
+            (_, value) if !data.contains_key("fields") => {
+                if let Some(field) = influx_field_value(value) {
+                    fields.push((key.clone(), field));
+                }
             }
-            _ => Ok(json!({"success": true})),
+            _ => {}
         }
     }
+
+    if fields.is_empty() {
+        return Err(anyhow!(
+            "InfluxDB points need at least one numeric or boolean field"
+        ));
+    }
+
+    let measurement = data
+        .get("measurement")
+        .or_else(|| data.get("_measurement"))
+        .and_then(|m| m.as_str())
+        .unwrap_or(measurement);
+    if measurement.is_empty() {
+        return Err(anyhow!("InfluxDB writes need a measurement"));
+    }
+
+    let escape = |s: &str| {
+        s.replace('\\', "\\\\")
+            .replace(',', "\\,")
+            .replace('=', "\\=")
+            .replace(' ', "\\ ")
+    };
+
+    let mut line = measurement.replace(',', "\\,").replace(' ', "\\ ");
+    for (key, value) in tags.iter().filter(|(_, v)| !v.is_empty()) {
+        line.push_str(&format!(",{}={}", escape(key), escape(value)));
+    }
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}={}", escape(key), value))
+        .collect();
+    line.push(' ');
+    line.push_str(&fields.join(","));
+    if let Some(timestamp) = timestamp {
+        line.push_str(&format!(" {}", timestamp));
+    }
+
+    Ok(line)
 }
 
-// Stub when feature is disabled
-#[cfg(not(feature = "websocket-datasource"))]
-pub struct WebSocketDataSource {
-    #[allow(dead_code)]
-    url: String,
+fn influx_field_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) if n.is_i64() => Some(format!("{}i", n)),
+        Value::Number(n) if n.is_u64() => Some(format!("{}u", n)),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(format!(
+            "\"{}\"",
+            s.replace('\\', "\\\\").replace('"', "\\\"")
+        )),
+        _ => None,
+    }
 }
 
-#[cfg(not(feature = "websocket-datasource"))]
-impl WebSocketDataSource {
-    pub async fn new(
-        url: String,
-        _reconnect: bool,
-        _heartbeat_interval: Option<u32>,
-    ) -> Result<Self> {
-        Err(anyhow!(
-            "WebSocket support not enabled. Enable the 'websocket-datasource' feature in Cargo.toml"
-        ))
+fn influx_timestamp(value: &Value, precision: &str) -> Result<i64> {
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| anyhow!("Invalid InfluxDB timestamp: {}", n)),
+        Value::String(s) => {
+            let time = chrono::DateTime::parse_from_rfc3339(s)
+                .map_err(|e| anyhow!("Invalid InfluxDB timestamp '{}': {}", s, e))?;
+            Ok(match precision {
+                "s" => time.timestamp(),
+                "ms" => time.timestamp_millis(),
+                "us" => time.timestamp_micros(),
+                _ => time
+                    .timestamp_nanos_opt()
+                    .ok_or_else(|| anyhow!("InfluxDB timestamp out of range: {}", s))?,
+            })
+        }
+        other => Err(anyhow!("Invalid InfluxDB timestamp: {}", other)),
+    }
+}
+
+pub(crate) fn value_as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
     }
 }
 
-#[cfg(not(feature = "websocket-datasource"))]
 #[async_trait::async_trait]
-impl DataSource for WebSocketDataSource {
+impl DataSource for InfluxDbDataSource {
     async fn execute_query(
         &self,
-        _message: &str,
-        _params: Option<&HashMap<String, Value>>,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        Err(anyhow!("WebSocket support not enabled"))
+        self.execute_query_paginated(query, params, None).await
     }
 
     async fn execute_query_paginated(
         &self,
-        _message: &str,
-        _params: Option<&HashMap<String, Value>>,
-        _pagination: Option<&PaginationParams>,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
     ) -> Result<Vec<HashMap<String, Value>>> {
-        Err(anyhow!("WebSocket support not enabled"))
+        let mut flux = bind_flux_params(query, &self.bucket, params);
+        if let Some(p) = pagination {
+            flux.push_str(&format!(
+                "\n  |> limit(n: {}, offset: {})",
+                p.page_size, p.offset
+            ));
+        }
+
+        debug!(flux = %flux, pagination = ?pagination, "Executing InfluxDB query");
+
+        let response = self
+            .client
+            .post(format!("{}/api/v2/query", self.url))
+            .query(&[("org", &self.org)])
+            .header("Authorization", format!("Token {}", self.token))
+            .header("Accept", "application/csv")
+            .json(&json!({
+                "query": flux,
+                "type": "flux",
+                "dialect": {
+                    "header": true,
+                    "annotations": ["datatype", "group", "default"],
+                },
+            }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("InfluxDB query request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("InfluxDB query failed {}: {}", status, error_text));
+        }
+
+        let rows = influx_csv_to_rows(&response.text().await?)?;
+
+        info!(count = rows.len(), "InfluxDB query completed");
+        Ok(rows)
     }
 
-    async fn execute_mutation(
-        &self,
-        _message: &str,
-        _data: &HashMap<String, Value>,
-    ) -> Result<Value> {
-        Err(anyhow!("WebSocket support not enabled"))
+    /// Writes one point to `measurement`, given by `query`
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let line = influx_line_protocol(query.trim(), data, &self.precision)?;
+        debug!(line = %line, "Writing InfluxDB point");
+
+        let response = self
+            .client
+            .post(format!("{}/api/v2/write", self.url))
+            .query(&[
+                ("org", self.org.as_str()),
+                ("bucket", self.bucket.as_str()),
+                ("precision", self.precision.as_str()),
+            ])
+            .header("Authorization", format!("Token {}", self.token))
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(line.clone())
+            .send()
+            .await
+            .map_err(|e| anyhow!("InfluxDB write request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("InfluxDB write failed {}: {}", status, error_text));
+        }
+
+        info!(bucket = %self.bucket, "InfluxDB write completed");
+        Ok(json!({ "success": true, "line": line }))
+    }
+
+    async fn execute_delete(&self, _query: &str, _data: &HashMap<String, Value>) -> Result<Value> {
+        Err(anyhow!(
+            "InfluxDB data sources do not support deleting individual records"
+        ))
     }
 }
 
-/// Factory to create data sources
-pub async fn create_data_source(config: &DataSourceConfig) -> Result<Box<dyn DataSource>> {
-    match config {
-        DataSourceConfig::Database {
-            connection_string,
-            db_type,
-        } => Ok(Box::new(
-            DatabaseDataSource::new(connection_string.clone(), db_type.clone()).await?,
-        )),
-        DataSourceConfig::Api {
-            base_url, headers, ..
-        } => Ok(Box::new(ApiDataSource::new(
-            base_url.clone(),
-            headers.clone(),
-        ))),
-        DataSourceConfig::GraphQL {
-            endpoint, headers, ..
-        } => Ok(Box::new(GraphQLDataSource::new(
-            endpoint.clone(),
-            headers.clone(),
-        ))),
-        DataSourceConfig::MongoDB {
-            connection_string,
-            database,
-            collection,
-        } => Ok(Box::new(
-            MongoDBDataSource::new(
-                connection_string.clone(),
-                database.clone(),
-                collection.clone(),
-            )
-            .await?,
-        )),
-        DataSourceConfig::Redis {
-            connection_string,
-            key_prefix,
-        } => Ok(Box::new(
-            RedisDataSource::new(connection_string.clone(), key_prefix.clone()).await?,
-        )),
-        DataSourceConfig::Elasticsearch { nodes, index, .. } => Ok(Box::new(
-            ElasticsearchDataSource::new(nodes.clone(), index.clone()),
-        )),
-        DataSourceConfig::Grpc {
-            endpoint,
-            proto_file,
-            service_name,
-            tls_enabled,
-        } => Ok(Box::new(GrpcDataSource::new(
-            endpoint.clone(),
-            proto_file.clone(),
-            service_name.clone(),
-            *tls_enabled,
-        ))),
-        DataSourceConfig::Kafka {
-            brokers,
-            topic,
-            group_id,
-        } => Ok(Box::new(KafkaDataSource::new(
-            brokers.clone(),
-            topic.clone(),
-            group_id.clone(),
-        ))),
-        DataSourceConfig::S3 {
-            bucket,
-            region,
-            prefix,
-            ..
-        } => Ok(Box::new(
-            S3DataSource::new(bucket.clone(), region.clone(), prefix.clone()).await?,
-        )),
-        DataSourceConfig::Firebase {
-            project_id,
-            collection,
-            ..
-        } => Ok(Box::new(FirebaseDataSource::new(
-            project_id.clone(),
-            collection.clone(),
-        ))),
-        DataSourceConfig::Supabase {
-            url,
+/// Prometheus data source (read-only)
+///
+/// The action query is a PromQL expression. With a `start` parameter it runs
+/// as a range query (`end` defaults to now, `step` to 1/250th of the range),
+/// otherwise as an instant query at `time` (default now).
+pub struct PrometheusDataSource {
+    client: reqwest::Client,
+    url: String,
+    headers: HashMap<String, String>,
+}
+
+/// Target number of samples per series when no `step` is given
+const PROMETHEUS_DEFAULT_POINTS: f64 = 250.0;
+
+impl PrometheusDataSource {
+    pub fn new(url: String, headers: Option<HashMap<String, String>>, timeout: Duration) -> Self {
+        info!(url = %url, "Initializing Prometheus data source");
+
+        Self {
+            client: http_client(timeout),
+            url: url.trim_end_matches('/').to_string(),
+            headers: headers.unwrap_or_default(),
+        }
+    }
+}
+
+/// Replace `{name}` placeholders with escaped parameter values
+///
+/// Meant for label values, e.g. `up{job="{job}"}`.
+fn bind_promql_params(query: &str, params: Option<&HashMap<String, Value>>) -> String {
+    let mut promql = query.to_string();
+    if let Some(params) = params {
+        for (name, value) in params {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            promql = promql.replace(
+                &format!("{{{}}}", name),
+                &value.replace('\\', "\\\\").replace('"', "\\\""),
+            );
+        }
+    }
+    promql
+}
+
+/// Parse a Prometheus time parameter (unix seconds or RFC 3339) to seconds
+fn prometheus_time(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok().or_else(|| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|t| t.timestamp_millis() as f64 / 1000.0)
+        }),
+        _ => None,
+    }
+}
+
+/// Query string parameters for `/api/v1/query` or `/api/v1/query_range`
+fn prometheus_request(
+    promql: &str,
+    params: Option<&HashMap<String, Value>>,
+    now: f64,
+) -> Result<(&'static str, Vec<(&'static str, String)>)> {
+    let param = |name: &str| params.and_then(|p| p.get(name)).filter(|v| !v.is_null());
+    let time = |name: &str| -> Result<Option<f64>> {
+        param(name)
+            .map(|v| {
+                prometheus_time(v).ok_or_else(|| anyhow!("Invalid Prometheus {}: {}", name, v))
+            })
+            .transpose()
+    };
+
+    let mut query = vec![("query", promql.to_string())];
+
+    let Some(start) = time("start")? else {
+        if let Some(at) = time("time")? {
+            query.push(("time", at.to_string()));
+        }
+        return Ok(("query", query));
+    };
+
+    let end = time("end")?.unwrap_or(now);
+    if end <= start {
+        return Err(anyhow!("Prometheus range end must be after start"));
+    }
+    let step = match param("step") {
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => ((end - start) / PROMETHEUS_DEFAULT_POINTS)
+            .ceil()
+            .max(1.0)
+            .to_string(),
+    };
+
+    query.push(("start", start.to_string()));
+    query.push(("end", end.to_string()));
+    query.push(("step", step));
+    Ok(("query_range", query))
+}
+
+/// Flatten a Prometheus query result into one row per sample
+///
+/// Rows hold the series labels (`__name__` as `metric`), `timestamp` (RFC 3339)
+/// and `value`.
+fn prometheus_rows(data: &Value) -> Vec<HashMap<String, Value>> {
+    let sample_row = |labels: Option<&serde_json::Map<String, Value>>, sample: &Value| {
+        let mut row: HashMap<String, Value> = labels
+            .map(|labels| {
+                labels
+                    .iter()
+                    .map(|(k, v)| {
+                        let key = if k == "__name__" {
+                            "metric"
+                        } else {
+                            k.as_str()
+                        };
+                        (key.to_string(), v.clone())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let timestamp = sample[0].as_f64().and_then(|ts| {
+            chrono::DateTime::from_timestamp_millis((ts * 1000.0) as i64)
+                .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        });
+        row.insert("timestamp".to_string(), json!(timestamp));
+
+        let value = match sample[1].as_str() {
+            Some(s) => s
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .unwrap_or_else(|| Value::String(s.to_string())),
+            None => sample[1].clone(),
+        };
+        row.insert("value".to_string(), value);
+        row
+    };
+
+    let result = &data["result"];
+    match data["resultType"].as_str() {
+        Some("vector") => result
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|series| sample_row(series["metric"].as_object(), &series["value"]))
+            .collect(),
+        Some("matrix") => result
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|series| {
+                series["values"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(move |sample| sample_row(series["metric"].as_object(), sample))
+            })
+            .collect(),
+        Some("scalar") | Some("string") => vec![sample_row(None, result)],
+        _ => Vec::new(),
+    }
+}
+
+#[async_trait::async_trait]
+impl DataSource for PrometheusDataSource {
+    async fn execute_query(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.execute_query_paginated(query, params, None).await
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let promql = bind_promql_params(query, params);
+        let now = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+        let (endpoint, query_params) = prometheus_request(&promql, params, now)?;
+        let url = format!("{}/api/v1/{}", self.url, endpoint);
+
+        debug!(url = %url, promql = %promql, "Executing Prometheus query");
+
+        let mut request = self.client.get(&url).query(&query_params);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Prometheus request failed: {}", e))?;
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse Prometheus response ({}): {}", status, e))?;
+
+        if body["status"] != "success" {
+            return Err(anyhow!(
+                "Prometheus query failed: {}",
+                body["error"].as_str().unwrap_or("unknown error")
+            ));
+        }
+        if let Some(warnings) = body["warnings"].as_array() {
+            warn!(warnings = ?warnings, "Prometheus query returned warnings");
+        }
+
+        let mut rows = prometheus_rows(&body["data"]);
+
+        // Prometheus has no pagination, so page through the samples in memory
+        if let Some(p) = pagination {
+            rows = rows.into_iter().skip(p.offset).take(p.page_size).collect();
+        }
+
+        info!(count = rows.len(), "Prometheus query completed");
+        Ok(rows)
+    }
+
+    async fn execute_mutation(
+        &self,
+        _query: &str,
+        _data: &HashMap<String, Value>,
+    ) -> Result<Value> {
+        Err(anyhow!("Prometheus data sources are read-only"))
+    }
+}
+
+/// Local file data source (CSV, JSON array or JSONL)
+///
+/// `path` may be a glob (`*`, `?`, `**`) when the source is only read; all
+/// matching files are concatenated in path order. Filtering and pagination
+/// happen in memory.
+pub struct FileDataSource {
+    path: String,
+    format: Option<FileFormat>,
+    write_mode: FileWriteMode,
+}
+
+/// Serializes writes so concurrent mutations don't lose records
+fn file_write_lock() -> &'static tokio::sync::Mutex<()> {
+    static LOCK: std::sync::OnceLock<tokio::sync::Mutex<()>> = std::sync::OnceLock::new();
+    LOCK.get_or_init(Default::default)
+}
+
+impl FileDataSource {
+    pub fn new(path: String, format: Option<FileFormat>, write_mode: FileWriteMode) -> Self {
+        info!(path = %path, format = ?format, write_mode = ?write_mode, "Initializing file data source");
+
+        Self {
+            path,
+            format,
+            write_mode,
+        }
+    }
+
+    /// Writes `data` as [`DataSource::execute_mutation`] does, or removes the
+    /// record `id` when `delete` is set
+    async fn write(&self, data: &HashMap<String, Value>, delete: bool) -> Result<Value> {
+        if self.path.contains(['*', '?']) {
+            return Err(anyhow!(
+                "File data source '{}' is a glob and cannot be written",
+                self.path
+            ));
+        }
+
+        let path = std::path::PathBuf::from(&self.path);
+        let format = self.format_of(&path)?;
+        let _guard = file_write_lock().lock().await;
+
+        let id = record_id(data);
+
+        let result = if delete || self.write_mode == FileWriteMode::Overwrite {
+            let mut contents = self.read_file(&path).await?;
+            let position = id.as_ref().and_then(|id| {
+                contents
+                    .rows
+                    .iter()
+                    .position(|row| record_id(row).as_ref() == Some(id))
+            });
+
+            let result = match (delete, position) {
+                (true, Some(index)) => {
+                    contents.rows.remove(index);
+                    json!({ "deleted": id })
+                }
+                (true, None) => return Err(anyhow!("Record {:?} not found", id)),
+                (false, Some(index)) => {
+                    contents.rows[index].extend(data.clone());
+                    json!({ "updated": id })
+                }
+                (false, None) => {
+                    contents.rows.push(data.clone());
+                    json!({ "inserted": id })
+                }
+            };
+
+            if contents.headers.is_empty() && format == FileFormat::Csv {
+                let mut headers: Vec<String> = data.keys().cloned().collect();
+                headers.sort();
+                contents.headers = headers;
+            }
+
+            tokio::fs::write(&path, render_file(&contents, &format)?)
+                .await
+                .map_err(|e| anyhow!("Failed to write '{}': {}", path.display(), e))?;
+            result
+        } else {
+            let line = match format {
+                FileFormat::Jsonl => json!(data).to_string() + "\n",
+                FileFormat::Csv => {
+                    let existing = self.read_file(&path).await?;
+                    if existing.headers.is_empty() {
+                        let mut headers: Vec<String> = data.keys().cloned().collect();
+                        headers.sort();
+                        format!("{}{}", csv_line(&headers), csv_row(&headers, data))
+                    } else {
+                        csv_row(&existing.headers, data)
+                    }
+                }
+                FileFormat::Json => {
+                    // A JSON array can't be appended to in place
+                    let mut contents = self.read_file(&path).await?;
+                    contents.rows.push(data.clone());
+                    tokio::fs::write(&path, render_file(&contents, &format)?)
+                        .await
+                        .map_err(|e| anyhow!("Failed to write '{}': {}", path.display(), e))?;
+                    String::new()
+                }
+            };
+
+            if !line.is_empty() {
+                use tokio::io::AsyncWriteExt;
+
+                let mut file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .await
+                    .map_err(|e| anyhow!("Failed to open '{}': {}", path.display(), e))?;
+                file.write_all(line.as_bytes()).await?;
+            }
+            json!({ "inserted": id })
+        };
+
+        info!(path = %self.path, result = %result, "File mutation completed");
+        Ok(result)
+    }
+
+    fn format_of(&self, path: &std::path::Path) -> Result<FileFormat> {
+        if let Some(format) = &self.format {
+            return Ok(format.clone());
+        }
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Ok(FileFormat::Csv),
+            Some("json") => Ok(FileFormat::Json),
+            Some("jsonl") | Some("ndjson") => Ok(FileFormat::Jsonl),
+            _ => Err(anyhow!(
+                "Cannot infer the format of '{}'; set `format` to csv, json or jsonl",
+                path.display()
+            )),
+        }
+    }
+
+    /// Files matching the configured path, in path order
+    fn files(&self) -> Vec<std::path::PathBuf> {
+        if !self.path.contains(['*', '?']) {
+            return vec![std::path::PathBuf::from(&self.path)];
+        }
+
+        // Walk from the last directory before the first wildcard
+        let wildcard = self.path.find(['*', '?']).unwrap_or(0);
+        let root = match self.path[..wildcard].rfind('/') {
+            Some(i) => &self.path[..i.max(1)],
+            None => ".",
+        };
+
+        let mut files: Vec<_> = walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .filter(|p| {
+                let path = p.to_string_lossy();
+                let path = path.strip_prefix("./").unwrap_or(&path);
+                glob_match(self.path.trim_start_matches("./"), path)
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    async fn read_file(&self, path: &std::path::Path) -> Result<FileContents> {
+        let text = match tokio::fs::read_to_string(path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(anyhow!("Failed to read '{}': {}", path.display(), e)),
+        };
+        parse_file(&text, &self.format_of(path)?)
+            .map_err(|e| anyhow!("Failed to parse '{}': {}", path.display(), e))
+    }
+}
+
+/// Parsed file: CSV headers (with type annotations) and rows
+struct FileContents {
+    headers: Vec<String>,
+    rows: Vec<HashMap<String, Value>>,
+}
+
+fn parse_file(text: &str, format: &FileFormat) -> Result<FileContents> {
+    let to_row = |value: Value| match value {
+        Value::Object(obj) => Ok(obj.into_iter().collect()),
+        other => Err(anyhow!("Expected an object, found {}", other)),
+    };
+
+    match format {
+        FileFormat::Json if text.trim().is_empty() => Ok(FileContents {
+            headers: Vec::new(),
+            rows: Vec::new(),
+        }),
+        FileFormat::Json => match serde_json::from_str(text)? {
+            Value::Array(items) => Ok(FileContents {
+                headers: Vec::new(),
+                rows: items.into_iter().map(to_row).collect::<Result<_>>()?,
+            }),
+            _ => Err(anyhow!("Expected a JSON array")),
+        },
+        FileFormat::Jsonl => Ok(FileContents {
+            headers: Vec::new(),
+            rows: text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| to_row(serde_json::from_str(line)?))
+                .collect::<Result<_>>()?,
+        }),
+        FileFormat::Csv => {
+            let mut records = parse_csv(text, ',').into_iter();
+            let headers = records.next().unwrap_or_default();
+            let rows = records
+                .filter(|record| record.iter().any(|cell| !cell.is_empty()))
+                .map(|record| {
+                    headers
+                        .iter()
+                        .enumerate()
+                        .map(|(i, header)| {
+                            let (name, kind) = csv_header(header);
+                            let cell = record.get(i).map(|c| c.as_str()).unwrap_or("");
+                            (name.to_string(), csv_cell(cell, kind))
+                        })
+                        .collect()
+                })
+                .collect();
+            Ok(FileContents { headers, rows })
+        }
+    }
+}
+
+/// Parse the rows of a JSON, JSONL or CSV document, as the file data
+/// source reads them
+pub(crate) fn parse_rows(text: &str, format: &FileFormat) -> Result<Vec<HashMap<String, Value>>> {
+    Ok(parse_file(text, format)?.rows)
+}
+
+/// Split a CSV header into its name and optional type, e.g. `age:int`
+fn csv_header(header: &str) -> (&str, Option<&str>) {
+    match header.split_once(':') {
+        Some((name, kind)) => (name.trim(), Some(kind.trim())),
+        None => (header.trim(), None),
+    }
+}
+
+/// Type a CSV cell by its header type, or infer it when untyped
+fn csv_cell(cell: &str, kind: Option<&str>) -> Value {
+    if cell.is_empty() {
+        return Value::Null;
+    }
+    let number = |cell: &str| {
+        cell.parse::<i64>().map(Value::from).ok().or_else(|| {
+            cell.parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+        })
+    };
+    match kind {
+        Some("string") => Value::String(cell.to_string()),
+        Some("int") | Some("float") | Some("number") => {
+            number(cell).unwrap_or_else(|| Value::String(cell.to_string()))
+        }
+        Some("bool") => Value::Bool(cell.eq_ignore_ascii_case("true") || cell == "1"),
+        Some("json") => {
+            serde_json::from_str(cell).unwrap_or_else(|_| Value::String(cell.to_string()))
+        }
+        _ => match cell {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => number(cell).unwrap_or_else(|| Value::String(cell.to_string())),
+        },
+    }
+}
+
+fn render_file(contents: &FileContents, format: &FileFormat) -> Result<String> {
+    let to_object = |row: &HashMap<String, Value>| -> Value {
+        Value::Object(row.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+    };
+
+    match format {
+        FileFormat::Json => Ok(serde_json::to_string_pretty(&Value::Array(
+            contents.rows.iter().map(to_object).collect(),
+        ))?),
+        FileFormat::Jsonl => Ok(contents
+            .rows
+            .iter()
+            .map(|row| to_object(row).to_string() + "\n")
+            .collect()),
+        FileFormat::Csv => {
+            let mut out = csv_line(&contents.headers);
+            for row in &contents.rows {
+                out.push_str(&csv_row(&contents.headers, row));
+            }
+            Ok(out)
+        }
+    }
+}
+
+fn csv_row(headers: &[String], row: &HashMap<String, Value>) -> String {
+    let cells: Vec<String> = headers
+        .iter()
+        .map(|header| {
+            row.get(csv_header(header).0)
+                .map(value_as_text)
+                .unwrap_or_default()
+        })
+        .collect();
+    csv_line(&cells)
+}
+
+pub(crate) fn csv_line(cells: &[String]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect();
+    cells.join(",") + "\n"
+}
+
+/// Match a path against a glob with `*`, `?` and `**` (any number of directories)
+fn glob_match(pattern: &str, path: &str) -> bool {
+    fn segment_match(pattern: &[char], text: &[char]) -> bool {
+        match (pattern.first(), text.first()) {
+            (None, None) => true,
+            (Some('*'), _) => {
+                segment_match(&pattern[1..], text)
+                    || (!text.is_empty() && segment_match(pattern, &text[1..]))
+            }
+            (Some('?'), Some(_)) => segment_match(&pattern[1..], &text[1..]),
+            (Some(p), Some(t)) if p == t => segment_match(&pattern[1..], &text[1..]),
+            _ => false,
+        }
+    }
+
+    fn parts_match(pattern: &[&str], path: &[&str]) -> bool {
+        match (pattern.first(), path.first()) {
+            (None, None) => true,
+            (Some(&"**"), _) => {
+                parts_match(&pattern[1..], path)
+                    || (!path.is_empty() && parts_match(pattern, &path[1..]))
+            }
+            (Some(p), Some(t)) => {
+                let p: Vec<char> = p.chars().collect();
+                let t: Vec<char> = t.chars().collect();
+                segment_match(&p, &t) && parts_match(&pattern[1..], &path[1..])
+            }
+            _ => false,
+        }
+    }
+
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    parts_match(&pattern, &path)
+}
+
+/// Whether a row matches every filter, comparing values loosely
+fn row_matches(row: &HashMap<String, Value>, filters: &serde_json::Map<String, Value>) -> bool {
+    filters.iter().all(|(field, expected)| {
+        row.get(field).is_some_and(|actual| {
+            actual == expected || value_as_text(actual) == value_as_text(expected)
+        })
+    })
+}
+
+fn record_id(data: &HashMap<String, Value>) -> Option<String> {
+    data.get("id").filter(|id| !id.is_null()).map(value_as_text)
+}
+
+#[async_trait::async_trait]
+impl DataSource for FileDataSource {
+    async fn execute_query(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.execute_query_paginated(query, params, None).await
+    }
+
+    /// The query is an optional JSON object of equality filters; `"{name}"`
+    /// values are replaced with request parameters
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let mut filters = if query.trim().is_empty() {
+            Value::Object(Default::default())
+        } else {
+            serde_json::from_str(query).map_err(|e| anyhow!("Invalid file query JSON: {}", e))?
+        };
+        bind_params(&mut filters, params)?;
+        let Value::Object(filters) = filters else {
+            return Err(anyhow!("File query must be a JSON object of filters"));
+        };
+
+        let files = self.files();
+        debug!(path = %self.path, files = files.len(), "Reading file data source");
+
+        let mut rows = Vec::new();
+        for file in &files {
+            rows.extend(
+                self.read_file(file)
+                    .await?
+                    .rows
+                    .into_iter()
+                    .filter(|row| row_matches(row, &filters)),
+            );
+        }
+
+        if let Some(p) = pagination {
+            rows = rows.into_iter().skip(p.offset).take(p.page_size).collect();
+        }
+
+        info!(count = rows.len(), "File query completed");
+        Ok(rows)
+    }
+
+    /// Appends `data` as a new record, or with `write_mode: overwrite` replaces
+    /// the record with the same `id`. A query of `delete` removes the record
+    /// `id`.
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        self.write(data, query.trim().eq_ignore_ascii_case("delete"))
+            .await
+    }
+
+    /// Removes the record `id`; the statement is not used
+    async fn execute_delete(&self, _query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        self.write(data, true).await
+    }
+}
+
+/// Airtable data source
+///
+/// The action query is a JSON object with optional `filters` (field -> value,
+/// combined into a `filterByFormula`), `filterByFormula`, `sort`, `view`,
+/// `fields` and `maxRecords`.
+pub struct AirtableDataSource {
+    client: reqwest::Client,
+    table_url: String,
+    api_key: String,
+}
+
+/// Largest page Airtable returns
+const AIRTABLE_MAX_PAGE_SIZE: usize = 100;
+
+impl AirtableDataSource {
+    pub fn new(base_id: String, table: String, api_key: String, timeout: Duration) -> Self {
+        info!(base_id = %base_id, table = %table, "Initializing Airtable data source");
+
+        Self {
+            client: http_client(timeout),
+            table_url: format!(
+                "https://api.airtable.com/v0/{}/{}",
+                base_id,
+                url_encode(&table)
+            ),
             api_key,
-            table,
-        } => Ok(Box::new(SupabaseDataSource::new(
-            url.clone(),
-            api_key.clone(),
-            table.clone(),
-        ))),
-        DataSourceConfig::WebSocket {
-            url,
-            reconnect,
-            heartbeat_interval,
-        } => Ok(Box::new(
-            WebSocketDataSource::new(url.clone(), *reconnect, *heartbeat_interval).await?,
-        )),
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let response = request
+            .bearer_auth(&self.api_key)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Airtable request failed: {}", e))?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = body["error"]["message"]
+                .as_str()
+                .or_else(|| body["error"].as_str())
+                .unwrap_or("unknown error");
+            return Err(anyhow!("Airtable returned error {}: {}", status, message));
+        }
+        Ok(body)
+    }
+
+    /// List one page of records, returning the rows and the next offset token
+    async fn list(
+        &self,
+        query: &[(String, String)],
+        page_size: usize,
+        offset: Option<String>,
+    ) -> Result<(Vec<HashMap<String, Value>>, Option<String>)> {
+        let mut request = self
+            .client
+            .get(&self.table_url)
+            .query(query)
+            .query(&[("pageSize", page_size.min(AIRTABLE_MAX_PAGE_SIZE))]);
+        if let Some(offset) = &offset {
+            request = request.query(&[("offset", offset)]);
+        }
+
+        let body = self.send(request).await?;
+        let rows = body["records"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(airtable_record_to_row)
+            .collect();
+        let next = body["offset"].as_str().map(|s| s.to_string());
+        Ok((rows, next))
+    }
+}
+
+/// Render a JSON value as an Airtable formula literal
+fn airtable_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "BLANK()".to_string(),
+        Value::Bool(true) => "TRUE()".to_string(),
+        Value::Bool(false) => "FALSE()".to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+        other => format!(
+            "\"{}\"",
+            other.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+        ),
+    }
+}
+
+/// Query string parameters for listing records
+fn airtable_list_query(
+    query: &str,
+    params: Option<&HashMap<String, Value>>,
+) -> Result<Vec<(String, String)>> {
+    let mut spec: Value = if query.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(query).map_err(|e| anyhow!("Invalid Airtable query JSON: {}", e))?
+    };
+    if !spec.is_object() {
+        return Err(anyhow!("Airtable query must be a JSON object"));
+    }
+    // Only `filters` values are bound; formulas use `{Field}` for field names
+    bind_params(&mut spec["filters"], params)?;
+
+    let mut conditions: Vec<String> = spec["filterByFormula"]
+        .as_str()
+        .filter(|f| !f.trim().is_empty())
+        .map(|f| vec![f.to_string()])
+        .unwrap_or_default();
+    if let Some(filters) = spec["filters"].as_object() {
+        let mut filters: Vec<_> = filters.iter().collect();
+        filters.sort_by(|a, b| a.0.cmp(b.0));
+        for (field, value) in filters {
+            conditions.push(format!("{{{}}} = {}", field, airtable_literal(value)));
+        }
+    }
+
+    let mut out = Vec::new();
+    match conditions.len() {
+        0 => {}
+        1 => out.push(("filterByFormula".to_string(), conditions.remove(0))),
+        _ => out.push((
+            "filterByFormula".to_string(),
+            format!("AND({})", conditions.join(", ")),
+        )),
+    }
+
+    for (i, sort) in spec["sort"].as_array().into_iter().flatten().enumerate() {
+        let (field, direction) = match sort {
+            Value::String(field) => (field.as_str(), "asc"),
+            other => (
+                other["field"].as_str().unwrap_or_default(),
+                other["direction"].as_str().unwrap_or("asc"),
+            ),
+        };
+        out.push((format!("sort[{}][field]", i), field.to_string()));
+        out.push((format!("sort[{}][direction]", i), direction.to_lowercase()));
+    }
+    for field in spec["fields"].as_array().into_iter().flatten() {
+        if let Some(field) = field.as_str() {
+            out.push(("fields[]".to_string(), field.to_string()));
+        }
+    }
+    if let Some(view) = spec["view"].as_str() {
+        out.push(("view".to_string(), view.to_string()));
+    }
+    if let Some(max) = spec["maxRecords"].as_u64() {
+        out.push(("maxRecords".to_string(), max.to_string()));
+    }
+
+    Ok(out)
+}
+
+/// Convert an Airtable record to a row of its fields plus `id` and `createdTime`
+fn airtable_record_to_row(record: &Value) -> HashMap<String, Value> {
+    let mut row: HashMap<String, Value> = record["fields"]
+        .as_object()
+        .map(|fields| fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+    row.insert("id".to_string(), record["id"].clone());
+    if let Some(created) = record.get("createdTime") {
+        row.insert("createdTime".to_string(), created.clone());
+    }
+    row
+}
+
+#[async_trait::async_trait]
+impl DataSource for AirtableDataSource {
+    async fn execute_query(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.execute_query_paginated(query, params, None).await
+    }
+
+    /// Pages are walked with Airtable offset tokens
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let list_query = airtable_list_query(query, params)?;
+
+        debug!(
+            url = %self.table_url,
+            query = ?list_query,
+            pagination = ?pagination,
+            "Executing Airtable query"
+        );
+
+        let rows = match pagination {
+            Some(p) => {
+                let cache_key = format!("{}|{:?}|{}", self.table_url, list_query, p.page_size);
+                fetch_cursor_page(cache_key, p, |offset| {
+                    self.list(&list_query, p.page_size, offset)
+                })
+                .await?
+            }
+            None => {
+                let mut rows = Vec::new();
+                let mut offset = None;
+                loop {
+                    let (page, next) = self
+                        .list(&list_query, AIRTABLE_MAX_PAGE_SIZE, offset)
+                        .await?;
+                    rows.extend(page);
+                    match next {
+                        Some(next) => offset = Some(next),
+                        None => break,
+                    }
+                }
+                rows
+            }
+        };
+
+        info!(count = rows.len(), "Airtable query completed");
+        Ok(rows)
+    }
+
+    /// A query of `delete` deletes the record `id`; otherwise the record `id`
+    /// is updated, or a record is created when there is no `id`
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        if query.trim().eq_ignore_ascii_case("delete") {
+            return self.execute_delete(query, data).await;
+        }
+
+        let id = data
+            .get("id")
+            .and_then(|id| id.as_str())
+            .filter(|id| !id.is_empty());
+        let fields: serde_json::Map<String, Value> = data
+            .iter()
+            .filter(|(k, _)| !matches!(k.as_str(), "id" | "createdTime"))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        debug!(url = %self.table_url, id = ?id, "Executing Airtable mutation");
+
+        let body = json!({ "fields": fields, "typecast": true });
+        let record = match id {
+            Some(id) => {
+                self.send(
+                    self.client
+                        .patch(format!("{}/{}", self.table_url, id))
+                        .json(&body),
+                )
+                .await?
+            }
+            None => {
+                self.send(self.client.post(&self.table_url).json(&body))
+                    .await?
+            }
+        };
+        let result = Value::Object(airtable_record_to_row(&record).into_iter().collect());
+
+        info!(url = %self.table_url, "Airtable mutation completed");
+        Ok(result)
+    }
+
+    /// Deletes the record `id`; the statement is not used
+    async fn execute_delete(&self, _query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let id = data
+            .get("id")
+            .and_then(|id| id.as_str())
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| anyhow!("Airtable delete requires an id"))?;
+        let result = self
+            .send(self.client.delete(format!("{}/{}", self.table_url, id)))
+            .await?;
+
+        info!(url = %self.table_url, id = %id, "Airtable record deleted");
+        Ok(result)
+    }
+}
+
+/// OData v4 data source
+///
+/// The action query is a JSON object with optional `filters` (field -> value
+/// equality), `filter` (a raw `$filter`), `orderBy`, `select` and `expand`.
+/// Mutations are POST/PATCH/DELETE on the entity set.
+pub struct ODataDataSource {
+    client: reqwest::Client,
+    entity_set_url: String,
+    key_field: String,
+    headers: HashMap<String, String>,
+}
+
+impl ODataDataSource {
+    pub fn new(
+        service_url: String,
+        entity_set: String,
+        key_field: Option<String>,
+        headers: Option<HashMap<String, String>>,
+        timeout: Duration,
+    ) -> Self {
+        info!(service_url = %service_url, entity_set = %entity_set, "Initializing OData data source");
+
+        Self {
+            client: http_client(timeout),
+            entity_set_url: format!("{}/{}", service_url.trim_end_matches('/'), entity_set),
+            key_field: key_field.unwrap_or_else(|| "id".to_string()),
+            headers: headers.unwrap_or_default(),
+        }
+    }
+
+    async fn send(&self, mut request: reqwest::RequestBuilder) -> Result<Value> {
+        request = request
+            .header("Accept", "application/json")
+            .header("OData-Version", "4.0");
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("OData request failed: {}", e))?;
+
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        let body: Value = if text.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&text).unwrap_or(Value::String(text))
+        };
+
+        if !status.is_success() {
+            let message = body["error"]["message"]
+                .as_str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| body.to_string());
+            return Err(anyhow!(
+                "OData service returned error {}: {}",
+                status,
+                message
+            ));
+        }
+        Ok(body)
+    }
+
+    fn entity_url(&self, key: &Value) -> String {
+        format!("{}({})", self.entity_set_url, odata_key(key))
+    }
+}
+
+/// Render a JSON value as an OData literal
+fn odata_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+        other => format!("'{}'", other.to_string().replace('\'', "''")),
+    }
+}
+
+/// Render an entity key; numeric strings and GUIDs are left unquoted
+fn odata_key(key: &Value) -> String {
+    match key {
+        Value::String(s) if uuid::Uuid::parse_str(s).is_ok() => s.clone(),
+        Value::String(s) if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) => s.clone(),
+        other => url_encode(&odata_literal(other)),
+    }
+}
+
+/// System query options for listing entities
+fn odata_list_query(
+    query: &str,
+    params: Option<&HashMap<String, Value>>,
+    pagination: Option<&PaginationParams>,
+) -> Result<Vec<(&'static str, String)>> {
+    let mut spec: Value = if query.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(query).map_err(|e| anyhow!("Invalid OData query JSON: {}", e))?
+    };
+    if !spec.is_object() {
+        return Err(anyhow!("OData query must be a JSON object"));
+    }
+    bind_params(&mut spec["filters"], params)?;
+
+    let mut conditions: Vec<String> = spec["filter"]
+        .as_str()
+        .filter(|f| !f.trim().is_empty())
+        .map(|f| vec![format!("({})", f)])
+        .unwrap_or_default();
+    if let Some(filters) = spec["filters"].as_object() {
+        let mut filters: Vec<_> = filters.iter().collect();
+        filters.sort_by(|a, b| a.0.cmp(b.0));
+        for (field, value) in filters {
+            conditions.push(format!("{} eq {}", field, odata_literal(value)));
+        }
+    }
+
+    let list = |value: &Value| -> Option<String> {
+        match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Array(items) => Some(
+                items
+                    .iter()
+                    .filter_map(|i| i.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            _ => None,
+        }
+    };
+
+    let mut out = Vec::new();
+    if !conditions.is_empty() {
+        out.push(("$filter", conditions.join(" and ")));
+    }
+    if let Some(order_by) = list(&spec["orderBy"]) {
+        out.push(("$orderby", order_by));
+    }
+    if let Some(select) = list(&spec["select"]) {
+        out.push(("$select", select));
+    }
+    if let Some(expand) = list(&spec["expand"]) {
+        out.push(("$expand", expand));
+    }
+    if let Some(p) = pagination {
+        out.push(("$top", p.page_size.to_string()));
+        out.push(("$skip", p.offset.to_string()));
+    }
+
+    Ok(out)
+}
+
+/// Drop OData annotations (`@odata.etag`, ...) from an entity
+fn odata_entity_to_row(entity: &Value) -> HashMap<String, Value> {
+    entity
+        .as_object()
+        .map(|obj| {
+            obj.iter()
+                .filter(|(k, _)| !k.contains('@'))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[async_trait::async_trait]
+impl DataSource for ODataDataSource {
+    async fn execute_query(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.execute_query_paginated(query, params, None).await
+    }
+
+    /// Pages map to `$top`/`$skip`; without pagination `@odata.nextLink` is
+    /// followed until every entity is loaded
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let options = odata_list_query(query, params, pagination)?;
+
+        debug!(
+            url = %self.entity_set_url,
+            options = ?options,
+            "Executing OData query"
+        );
+
+        let mut rows = Vec::new();
+        let mut body = self
+            .send(self.client.get(&self.entity_set_url).query(&options))
+            .await?;
+        loop {
+            match body["value"].as_array() {
+                Some(entities) => rows.extend(entities.iter().map(odata_entity_to_row)),
+                None => {
+                    rows.push(odata_entity_to_row(&body));
+                    break;
+                }
+            }
+
+            match body["@odata.nextLink"].as_str() {
+                Some(next) if pagination.is_none() => {
+                    let next = next.to_string();
+                    body = self.send(self.client.get(next)).await?;
+                }
+                _ => break,
+            }
+        }
+
+        info!(count = rows.len(), "OData query completed");
+        Ok(rows)
+    }
+
+    /// A query of `delete` deletes the entity whose key is in `data`;
+    /// otherwise the entity is updated (PATCH), or created (POST) when `data`
+    /// has no key
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        if query.trim().eq_ignore_ascii_case("delete") {
+            return self.execute_delete(query, data).await;
+        }
+
+        let key = data.get(&self.key_field).filter(|k| !k.is_null()).cloned();
+
+        debug!(url = %self.entity_set_url, key = ?key, "Executing OData mutation");
+
+        let request = match &key {
+            Some(key) => {
+                let mut changes = data.clone();
+                changes.remove(&self.key_field);
+                self.client.patch(self.entity_url(key)).json(&changes)
+            }
+            None => self.client.post(&self.entity_set_url).json(data),
+        };
+        let entity = self
+            .send(request.header("Prefer", "return=representation"))
+            .await?;
+        let result = match entity {
+            Value::Null => json!({ "success": true }),
+            entity => Value::Object(odata_entity_to_row(&entity).into_iter().collect()),
+        };
+
+        info!(url = %self.entity_set_url, "OData mutation completed");
+        Ok(result)
+    }
+
+    /// Deletes the entity whose key is in `data`, falling back to `id` as the
+    /// delete endpoint always passes it; the statement is not used
+    async fn execute_delete(&self, _query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let key = data
+            .get(&self.key_field)
+            .or_else(|| data.get("id"))
+            .filter(|k| !k.is_null())
+            .ok_or_else(|| anyhow!("OData delete requires '{}'", self.key_field))?;
+        self.send(self.client.delete(self.entity_url(key))).await?;
+
+        info!(url = %self.entity_set_url, key = %key, "OData entity deleted");
+        Ok(json!({ "deleted": key }))
+    }
+}
+
+/// Error object returned by a JSON-RPC server
+#[derive(Debug, Clone, PartialEq, serde::Serialize, thiserror::Error)]
+#[error("JSON-RPC error {code}: {message}")]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// JSON-RPC 2.0 data source
+///
+/// The action query is the method name, or a JSON array of calls (method
+/// names or `{"method", "params"}` objects) sent as one batch request.
+pub struct JsonRpcDataSource {
+    client: reqwest::Client,
+    endpoint: String,
+    headers: HashMap<String, String>,
+}
+
+/// A single call of a (possibly batched) request
+#[derive(Debug, Clone, PartialEq)]
+struct JsonRpcCall {
+    method: String,
+    params: Value,
+}
+
+impl JsonRpcDataSource {
+    pub fn new(
+        endpoint: String,
+        headers: Option<HashMap<String, String>>,
+        timeout: Duration,
+    ) -> Self {
+        info!(endpoint = %endpoint, "Initializing JSON-RPC data source");
+
+        Self {
+            client: http_client(timeout),
+            endpoint,
+            headers: headers.unwrap_or_default(),
+        }
+    }
+
+    /// Send the calls, returning their results in order
+    ///
+    /// The first error object in the response is returned as a `JsonRpcError`.
+    async fn call(&self, calls: &[JsonRpcCall]) -> Result<Vec<Value>> {
+        static NEXT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+        let first_id = NEXT_ID.fetch_add(calls.len() as u64, std::sync::atomic::Ordering::Relaxed);
+        let requests: Vec<Value> = calls
+            .iter()
+            .enumerate()
+            .map(|(i, call)| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": call.method,
+                    "params": call.params,
+                    "id": first_id + i as u64,
+                })
+            })
+            .collect();
+        let body = match requests.as_slice() {
+            [single] => single.clone(),
+            _ => Value::Array(requests),
+        };
+
+        debug!(endpoint = %self.endpoint, calls = calls.len(), "Sending JSON-RPC request");
+
+        let mut request = self.client.post(&self.endpoint).json(&body);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = crate::request_id::forward(request)
+            .send()
+            .await
+            .map_err(|e| anyhow!("JSON-RPC request failed: {}", e))?;
+
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        let body: Value = serde_json::from_str(&text).map_err(|_| {
+            anyhow!(
+                "JSON-RPC endpoint returned {} with an invalid response: {}",
+                status,
+                text
+            )
+        })?;
+
+        let responses = match body {
+            Value::Array(responses) => responses,
+            single => vec![single],
+        };
+
+        // Batch responses may come back in any order
+        (0..calls.len())
+            .map(|i| {
+                let id = first_id + i as u64;
+                let response = responses
+                    .iter()
+                    .find(|r| r["id"].as_u64() == Some(id))
+                    .or_else(|| {
+                        responses
+                            .iter()
+                            .find(|r| r["id"].is_null() && r.get("error").is_some())
+                    })
+                    .ok_or_else(|| {
+                        anyhow!("JSON-RPC response is missing the result of call {}", id)
+                    })?;
+
+                if let Some(error) = response.get("error").filter(|e| !e.is_null()) {
+                    return Err(JsonRpcError {
+                        code: error["code"].as_i64().unwrap_or_default(),
+                        message: error["message"]
+                            .as_str()
+                            .unwrap_or("Unknown error")
+                            .to_string(),
+                        data: error.get("data").cloned(),
+                    }
+                    .into());
+                }
+                Ok(response["result"].clone())
+            })
+            .collect()
+    }
+}
+
+/// Parse an action query into calls, giving each the request params
+fn json_rpc_calls(query: &str, params: Value) -> Result<Vec<JsonRpcCall>> {
+    let query = query.trim();
+    if !query.starts_with('[') {
+        if query.is_empty() {
+            return Err(anyhow!("JSON-RPC actions need a method name"));
+        }
+        return Ok(vec![JsonRpcCall {
+            method: query.to_string(),
+            params,
+        }]);
+    }
+
+    let calls: Vec<Value> =
+        serde_json::from_str(query).map_err(|e| anyhow!("Invalid JSON-RPC batch: {}", e))?;
+    calls
+        .into_iter()
+        .map(|call| match call {
+            Value::String(method) => Ok(JsonRpcCall {
+                method,
+                params: params.clone(),
+            }),
+            Value::Object(obj) => {
+                let method = obj
+                    .get("method")
+                    .and_then(|m| m.as_str())
+                    .ok_or_else(|| anyhow!("JSON-RPC batch call is missing 'method'"))?;
+                // Calls with their own params merge the request params over them
+                let params = match (obj.get("params"), &params) {
+                    (Some(Value::Object(own)), Value::Object(request)) => {
+                        let mut merged = own.clone();
+                        merged.extend(request.clone());
+                        Value::Object(merged)
+                    }
+                    (Some(own), _) => own.clone(),
+                    (None, _) => params.clone(),
+                };
+                Ok(JsonRpcCall {
+                    method: method.to_string(),
+                    params,
+                })
+            }
+            other => Err(anyhow!("Invalid JSON-RPC batch call: {}", other)),
+        })
+        .collect()
+}
+
+/// Map a JSON-RPC result to rows
+fn json_rpc_result_rows(result: Value) -> Vec<HashMap<String, Value>> {
+    match result {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::Object(obj) => obj.into_iter().collect(),
+                other => HashMap::from([("result".to_string(), other)]),
+            })
+            .collect(),
+        Value::Object(_) => response_rows(result),
+        Value::Null => Vec::new(),
+        other => vec![HashMap::from([("result".to_string(), other)])],
+    }
+}
+
+#[async_trait::async_trait]
+impl DataSource for JsonRpcDataSource {
+    async fn execute_query(
+        &self,
+        method: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.execute_query_paginated(method, params, None).await
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        method: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        let calls = json_rpc_calls(method, json!(params.cloned().unwrap_or_default()))?;
+        let mut rows: Vec<HashMap<String, Value>> = self
+            .call(&calls)
+            .await?
+            .into_iter()
+            .flat_map(json_rpc_result_rows)
+            .collect();
+
+        // RPC methods have no standard pagination, so page in memory
+        if let Some(p) = pagination {
+            rows = rows.into_iter().skip(p.offset).take(p.page_size).collect();
+        }
+
+        info!(count = rows.len(), "JSON-RPC query completed");
+        Ok(rows)
+    }
+
+    /// Returns the method result, or an array of results for a batch
+    async fn execute_mutation(&self, method: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        let calls = json_rpc_calls(method, json!(data))?;
+        let mut results = self.call(&calls).await?;
+
+        info!(endpoint = %self.endpoint, calls = calls.len(), "JSON-RPC mutation completed");
+        Ok(if results.len() == 1 {
+            results.remove(0)
+        } else {
+            Value::Array(results)
+        })
+    }
+}
+
+/// WebSocket data source for real-time communication
+#[cfg(feature = "websocket-datasource")]
+pub struct WebSocketDataSource {
+    url: String,
+    #[allow(dead_code)]
+    reconnect: bool,
+    #[allow(dead_code)]
+    heartbeat_interval: Option<u32>,
+}
+
+#[cfg(feature = "websocket-datasource")]
+impl WebSocketDataSource {
+    pub async fn new(
+        url: String,
+        reconnect: bool,
+        heartbeat_interval: Option<u32>,
+    ) -> Result<Self> {
+        use tokio_tungstenite::connect_async;
+
+        info!(
+            url = %url,
+            reconnect = reconnect,
+            heartbeat_interval = ?heartbeat_interval,
+            "Initializing WebSocket data source"
+        );
+
+        // Test connection
+        match connect_async(&url).await {
+            Ok((ws_stream, _)) => {
+                info!(url = %url, "Successfully connected to WebSocket");
+                drop(ws_stream); // Close test connection
+            }
+            Err(e) => {
+                error!(
+                    url = %url,
+                    error = %e,
+                    "Failed to connect to WebSocket"
+                );
+                return Err(anyhow!("Failed to connect to WebSocket: {}", e));
+            }
+        }
+
+        Ok(Self {
+            url,
+            reconnect,
+            heartbeat_interval,
+        })
+    }
+
+    async fn connect(
+        &self,
+    ) -> Result<
+        tokio_tungstenite::WebSocketStream<
+            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+        >,
+    > {
+        use tokio_tungstenite::connect_async;
+
+        debug!(url = %self.url, "Connecting to WebSocket");
+
+        let (ws_stream, _) = connect_async(&self.url)
+            .await
+            .map_err(|e| anyhow!("WebSocket connection failed: {}", e))?;
+
+        Ok(ws_stream)
+    }
+}
+
+#[cfg(feature = "websocket-datasource")]
+#[async_trait::async_trait]
+impl DataSource for WebSocketDataSource {
+    async fn execute_query(
+        &self,
+        message: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut ws_stream = self.connect().await?;
+
+        // Prepare the query message
+        let query_data = if let Some(params) = params {
+            let mut data = params.clone();
+            data.insert("query".to_string(), Value::String(message.to_string()));
+            serde_json::to_string(&data)?
+        } else {
+            message.to_string()
+        };
+
+        debug!(message_length = query_data.len(), "Sending WebSocket query");
+
+        // Send the query
+        ws_stream
+            .send(Message::Text(query_data))
+            .await
+            .map_err(|e| anyhow!("Failed to send WebSocket message: {}", e))?;
+
+        // Wait for response
+        match ws_stream.next().await {
+            Some(Ok(Message::Text(text))) => {
+                debug!(response_length = text.len(), "Received WebSocket response");
+
+                // Try to parse as JSON
+                match serde_json::from_str::<Value>(&text) {
+                    Ok(Value::Array(arr)) => {
+                        let mut result = Vec::new();
+                        for item in arr {
+                            if let Value::Object(obj) = item {
+                                result.push(obj.into_iter().collect());
+                            }
+                        }
+                        Ok(result)
+                    }
+                    Ok(Value::Object(obj)) => Ok(vec![obj.into_iter().collect()]),
+                    _ => {
+                        // If not JSON, return as text
+                        let mut map = HashMap::new();
+                        map.insert("response".to_string(), Value::String(text));
+                        Ok(vec![map])
+                    }
+                }
+            }
+            Some(Ok(Message::Binary(bytes))) => {
+                // Handle binary response
+                let text = String::from_utf8_lossy(&bytes).to_string();
+                let mut map = HashMap::new();
+                map.insert("response".to_string(), Value::String(text));
+                Ok(vec![map])
+            }
+            Some(Ok(Message::Close(_))) => {
+                warn!("WebSocket connection closed by server");
+                Err(anyhow!("WebSocket connection closed by server"))
+            }
+            Some(Err(e)) => {
+                error!(error = %e, "WebSocket error");
+                Err(anyhow!("WebSocket error: {}", e))
+            }
+            None => {
+                warn!("WebSocket stream ended unexpectedly");
+                Err(anyhow!("WebSocket stream ended unexpectedly"))
+            }
+            _ => {
+                warn!("Unexpected WebSocket message type");
+                Ok(vec![])
+            }
+        }
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        message: &str,
+        params: Option<&HashMap<String, Value>>,
+        _pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        // WebSocket is real-time bidirectional, pagination doesn't typically apply
+        // Just call the regular execute_query
+        self.execute_query(message, params).await
+    }
+
+    async fn execute_mutation(
+        &self,
+        message: &str,
+        data: &HashMap<String, Value>,
+    ) -> Result<Value> {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut ws_stream = self.connect().await?;
+
+        // Prepare the mutation message
+        let mut mutation_data = data.clone();
+        mutation_data.insert("mutation".to_string(), Value::String(message.to_string()));
+        let json = serde_json::to_string(&mutation_data)?;
+
+        debug!(message_length = json.len(), "Sending WebSocket mutation");
+
+        // Send the mutation
+        ws_stream
+            .send(Message::Text(json))
+            .await
+            .map_err(|e| anyhow!("Failed to send WebSocket message: {}", e))?;
+
+        // Wait for response
+        match ws_stream.next().await {
+            Some(Ok(Message::Text(text))) => {
+                info!(
+                    response_length = text.len(),
+                    "Received WebSocket mutation response"
+                );
+
+                // Try to parse as JSON
+                match serde_json::from_str::<Value>(&text) {
+                    Ok(value) => Ok(value),
+                    Err(_) => Ok(Value::String(text)),
+                }
+            }
+            Some(Ok(Message::Binary(bytes))) => {
+                let text = String::from_utf8_lossy(&bytes).to_string();
+                Ok(Value::String(text))
+            }
+            Some(Ok(Message::Close(_))) => {
+                warn!("WebSocket connection closed by server");
+                Err(anyhow!("WebSocket connection closed by server"))
+            }
+            Some(Err(e)) => {
+                error!(error = %e, "WebSocket error");
+                Err(anyhow!("WebSocket error: {}", e))
+            }
+            None => {
+                warn!("WebSocket stream ended unexpectedly");
+                Err(anyhow!("WebSocket stream ended unexpectedly"))
+            }
+            _ => Ok(json!({"success": true})),
+        }
+    }
+}
+
+// Stub when feature is disabled
+#[cfg(not(feature = "websocket-datasource"))]
+pub struct WebSocketDataSource {
+    #[allow(dead_code)]
+    url: String,
+}
+
+#[cfg(not(feature = "websocket-datasource"))]
+impl WebSocketDataSource {
+    pub async fn new(
+        url: String,
+        _reconnect: bool,
+        _heartbeat_interval: Option<u32>,
+    ) -> Result<Self> {
+        Err(anyhow!(
+            "WebSocket support not enabled. Enable the 'websocket-datasource' feature in Cargo.toml"
+        ))
+    }
+}
+
+#[cfg(not(feature = "websocket-datasource"))]
+#[async_trait::async_trait]
+impl DataSource for WebSocketDataSource {
+    async fn execute_query(
+        &self,
+        _message: &str,
+        _params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        Err(anyhow!("WebSocket support not enabled"))
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        _message: &str,
+        _params: Option<&HashMap<String, Value>>,
+        _pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        Err(anyhow!("WebSocket support not enabled"))
+    }
+
+    async fn execute_mutation(
+        &self,
+        _message: &str,
+        _data: &HashMap<String, Value>,
+    ) -> Result<Value> {
+        Err(anyhow!("WebSocket support not enabled"))
+    }
+}
+
+/// Data source backed by a WASM plugin exporting `query` and `mutate`
+pub struct WasmDataSource {
+    path: String,
+    config: Value,
+    fuel: u64,
+}
+
+impl WasmDataSource {
+    pub fn new(path: String, config: Value, fuel: Option<u64>) -> Self {
+        Self {
+            path,
+            config,
+            fuel: fuel.unwrap_or(crate::plugins::DEFAULT_FUEL),
+        }
+    }
+
+    fn rows_from_value(value: Value) -> Result<Vec<HashMap<String, Value>>> {
+        match value {
+            Value::Array(arr) => Ok(arr
+                .into_iter()
+                .filter_map(|item| match item {
+                    Value::Object(obj) => Some(obj.into_iter().collect()),
+                    _ => None,
+                })
+                .collect()),
+            Value::Object(obj) => Ok(vec![obj.into_iter().collect()]),
+            Value::Null => Ok(vec![]),
+            _ => Err(anyhow!("Unexpected WASM plugin query result format")),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DataSource for WasmDataSource {
+    async fn execute_query(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.execute_query_paginated(query, params, None).await
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        debug!(plugin = %self.path, pagination = ?pagination, "Executing WASM plugin query");
+
+        let input = json!({
+            "query": query,
+            "params": params,
+            "pagination": pagination.map(|p| json!({
+                "page": p.page,
+                "page_size": p.page_size,
+                "offset": p.offset,
+            })),
+            "config": self.config,
+        });
+
+        let output = crate::plugins::call_export(&self.path, "query", input, self.fuel).await?;
+        Self::rows_from_value(output)
+    }
+
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        debug!(plugin = %self.path, "Executing WASM plugin mutation");
+
+        let input = json!({
+            "query": query,
+            "data": data,
+            "config": self.config,
+        });
+
+        crate::plugins::call_export(&self.path, "mutate", input, self.fuel).await
+    }
+}
+
+/// Render a JSON value as a SQL literal
+pub fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+        other => format!("'{}'", other.to_string().replace('\'', "''")),
+    }
+}
+
+/// Percent-encode a value for use in a URL path or query string
+pub fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Cursors (bookmarks, offset tokens) that start each page after the first,
+/// keyed by data source and query
+fn page_cursors() -> &'static std::sync::Mutex<HashMap<String, Vec<String>>> {
+    static CURSORS: std::sync::OnceLock<std::sync::Mutex<HashMap<String, Vec<String>>>> =
+        std::sync::OnceLock::new();
+    CURSORS.get_or_init(Default::default)
+}
+
+/// Upper bound on distinct queries whose page cursors are remembered
+const PAGE_CURSOR_CACHE_SIZE: usize = 1000;
+
+/// Fetch one page of a cursor-paginated source
+///
+/// `fetch` loads the page starting at a cursor (`None` for the first page) and
+/// returns its rows and the cursor of the next page. Cursors of pages already
+/// visited are remembered, so moving to the next page is a single request;
+/// jumping ahead walks the pages in between.
+async fn fetch_cursor_page<F, Fut>(
+    cache_key: String,
+    pagination: &PaginationParams,
+    mut fetch: F,
+) -> Result<Vec<HashMap<String, Value>>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: std::future::Future<Output = Result<(Vec<HashMap<String, Value>>, Option<String>)>>,
+{
+    let known = page_cursors()
+        .lock()
+        .unwrap()
+        .get(&cache_key)
+        .cloned()
+        .unwrap_or_default();
+
+    // known[i] starts page i + 2
+    let target = pagination.page.max(1);
+    let mut page = (known.len() + 1).min(target);
+    let mut cursor = page.checked_sub(2).map(|i| known[i].clone());
+    let mut cursors = known;
+
+    loop {
+        let (rows, next) = fetch(cursor).await?;
+        if let Some(next) = &next {
+            if cursors.len() < page {
+                cursors.push(next.clone());
+            }
+        }
+
+        if page == target || rows.len() < pagination.page_size || next.is_none() {
+            let mut cache = page_cursors().lock().unwrap();
+            if cache.len() >= PAGE_CURSOR_CACHE_SIZE && !cache.contains_key(&cache_key) {
+                cache.clear();
+            }
+            cache.insert(cache_key, cursors);
+
+            return Ok(if page == target { rows } else { Vec::new() });
+        }
+
+        cursor = next;
+        page += 1;
+    }
+}
+
+/// Replace `"{name}"` strings in a JSON query with request parameters
+fn bind_params(value: &mut Value, params: Option<&HashMap<String, Value>>) -> Result<()> {
+    match value {
+        Value::String(s) => {
+            if let Some(name) = s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                *value = params
+                    .and_then(|p| p.get(name))
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Missing query parameter '{}'", name))?;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                bind_params(item, params)?;
+            }
+        }
+        Value::Object(obj) => {
+            for item in obj.values_mut() {
+                bind_params(item, params)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Identity of a data source configuration, shared by its instances
+pub(crate) fn config_key(config: &DataSourceConfig) -> String {
+    serde_json::to_string(config).unwrap_or_default()
+}
+
+/// Timeout of HTTP calls made by data sources unless configured otherwise
+pub const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Factory to create data sources
+pub async fn create_data_source(config: &DataSourceConfig) -> Result<Box<dyn DataSource>> {
+    create_data_source_with_timeout(config, DEFAULT_HTTP_TIMEOUT).await
+}
+
+/// Create a data source whose HTTP calls give up after `http_timeout`
+pub async fn create_data_source_with_timeout(
+    config: &DataSourceConfig,
+    http_timeout: Duration,
+) -> Result<Box<dyn DataSource>> {
+    match config {
+        DataSourceConfig::Database {
+            connection_string,
+            db_type,
+            read_replicas,
+            ..
+        } => Ok(Box::new(
+            DatabaseDataSource::with_replicas(
+                connection_string.clone(),
+                read_replicas,
+                db_type.clone(),
+            )
+            .await?,
+        )),
+        DataSourceConfig::Api {
+            base_url,
+            headers,
+            auth,
+            pagination,
+            response,
+            ..
+        } => Ok(Box::new(ApiDataSource::new(
+            base_url.clone(),
+            headers.clone(),
+            auth.as_ref(),
+            pagination.clone(),
+            response.as_deref().cloned(),
+            http_timeout,
+        )?)),
+        DataSourceConfig::GraphQL {
+            endpoint,
+            headers,
+            auth,
+            ..
+        } => Ok(Box::new(GraphQLDataSource::new(
+            endpoint.clone(),
+            headers.clone(),
+            auth.as_ref(),
+            http_timeout,
+        )?)),
+        DataSourceConfig::MongoDB {
+            connection_string,
+            database,
+            collection,
+            ..
+        } => Ok(Box::new(
+            MongoDBDataSource::new(
+                connection_string.clone(),
+                database.clone(),
+                collection.clone(),
+            )
+            .await?,
+        )),
+        DataSourceConfig::Redis {
+            connection_string,
+            key_prefix,
+            ..
+        } => Ok(Box::new(
+            RedisDataSource::new(connection_string.clone(), key_prefix.clone()).await?,
+        )),
+        DataSourceConfig::Elasticsearch {
+            nodes,
+            index,
+            auth,
+            tls,
+            ..
+        } => Ok(Box::new(ElasticsearchDataSource::new(
+            nodes.clone(),
+            index.clone(),
+            auth.as_ref(),
+            tls.as_ref(),
+            http_timeout,
+        )?)),
+        DataSourceConfig::Grpc {
+            endpoint,
+            proto_file,
+            service_name,
+            tls_enabled,
+            ..
+        } => Ok(Box::new(
+            GrpcDataSource::new(
+                endpoint.clone(),
+                proto_file.clone(),
+                service_name.clone(),
+                *tls_enabled,
+            )
+            .await?,
+        )),
+        DataSourceConfig::Kafka {
+            brokers,
+            topic,
+            group_id,
+            ..
+        } => Ok(Box::new(KafkaDataSource::new(
+            brokers.clone(),
+            topic.clone(),
+            group_id.clone(),
+        ))),
+        DataSourceConfig::S3 {
+            bucket,
+            region,
+            access_key,
+            secret_key,
+            prefix,
+            endpoint,
+            ..
+        } => Ok(Box::new(
+            S3DataSource::new(
+                bucket.clone(),
+                region.clone(),
+                prefix.clone(),
+                s3_credentials(access_key.as_deref(), secret_key.as_deref())?,
+                endpoint.clone(),
+            )
+            .await?,
+        )),
+        DataSourceConfig::Firebase {
+            project_id,
+            collection,
+            credentials_path,
+            ..
+        } => Ok(Box::new(
+            FirebaseDataSource::new(
+                project_id.clone(),
+                collection.clone(),
+                credentials_path.clone(),
+                http_timeout,
+            )
+            .await?,
+        )),
+        DataSourceConfig::Supabase {
+            url,
+            api_key,
+            table,
+            ..
+        } => Ok(Box::new(SupabaseDataSource::new(
+            url.clone(),
+            api_key.clone(),
+            table.clone(),
+            http_timeout,
+        ))),
+        DataSourceConfig::CouchDb {
+            url,
+            database,
+            username,
+            password,
+            ..
+        } => Ok(Box::new(CouchDbDataSource::new(
+            url.clone(),
+            database.clone(),
+            username.clone(),
+            password.clone(),
+            http_timeout,
+        ))),
+        DataSourceConfig::InfluxDb {
+            url,
+            org,
+            bucket,
+            token,
+            precision,
+            ..
+        } => Ok(Box::new(InfluxDbDataSource::new(
+            url.clone(),
+            org.clone(),
+            bucket.clone(),
+            token.clone(),
+            precision.clone(),
+            http_timeout,
+        )?)),
+        DataSourceConfig::Prometheus { url, headers, .. } => Ok(Box::new(
+            PrometheusDataSource::new(url.clone(), headers.clone(), http_timeout),
+        )),
+        DataSourceConfig::File {
+            path,
+            format,
+            write_mode,
+            ..
+        } => Ok(Box::new(FileDataSource::new(
+            path.clone(),
+            format.clone(),
+            write_mode.clone(),
+        ))),
+        DataSourceConfig::Airtable {
+            base_id,
+            table,
+            api_key,
+            ..
+        } => Ok(Box::new(AirtableDataSource::new(
+            base_id.clone(),
+            table.clone(),
+            api_key.clone(),
+            http_timeout,
+        ))),
+        DataSourceConfig::OData {
+            service_url,
+            entity_set,
+            key_field,
+            headers,
+            ..
+        } => Ok(Box::new(ODataDataSource::new(
+            service_url.clone(),
+            entity_set.clone(),
+            key_field.clone(),
+            headers.clone(),
+            http_timeout,
+        ))),
+        DataSourceConfig::JsonRpc {
+            endpoint, headers, ..
+        } => Ok(Box::new(JsonRpcDataSource::new(
+            endpoint.clone(),
+            headers.clone(),
+            http_timeout,
+        ))),
+        DataSourceConfig::WebSocket {
+            url,
+            reconnect,
+            heartbeat_interval,
+            ..
+        } => Ok(Box::new(
+            WebSocketDataSource::new(url.clone(), *reconnect, *heartbeat_interval).await?,
+        )),
+        DataSourceConfig::Wasm {
+            path, config, fuel, ..
+        } => Ok(Box::new(WasmDataSource::new(
+            path.clone(),
+            config.clone(),
+            *fuel,
+        ))),
+        DataSourceConfig::Custom { factory, .. } => Err(anyhow!(
+            "Custom data source factory '{}' requires a DataSourceRegistry",
+            factory
+        )),
+    }
+}
+
+/// Builds custom data sources from their configuration
+#[async_trait::async_trait]
+pub trait DataSourceFactory: Send + Sync {
+    async fn create(&self, config: &Value) -> Result<Box<dyn DataSource>>;
+}
+
+#[async_trait::async_trait]
+impl<F> DataSourceFactory for F
+where
+    F: Fn(&Value) -> Result<Box<dyn DataSource>> + Send + Sync,
+{
+    async fn create(&self, config: &Value) -> Result<Box<dyn DataSource>> {
+        self(config)
+    }
+}
+
+/// Creates data sources, including `type: custom` ones from registered factories
+#[derive(Default, Clone)]
+pub struct DataSourceRegistry {
+    factories: HashMap<String, Arc<dyn DataSourceFactory>>,
+    http_timeout: Option<Duration>,
+    /// Client with the HTTP timeout, for calls made next to data sources
+    client: Option<reqwest::Client>,
+    circuit_breakers: Option<Arc<CircuitBreakers>>,
+    health: Arc<HealthMonitor>,
+    /// Constructed data sources, shared by every request
+    instances: Arc<std::sync::Mutex<HashMap<String, Arc<dyn DataSource>>>>,
+}
+
+impl DataSourceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a factory for `type: custom` data sources with `factory: <name>`
+    pub fn register(&mut self, name: impl Into<String>, factory: impl DataSourceFactory + 'static) {
+        self.factories.insert(name.into(), Arc::new(factory));
+    }
+
+    /// Timeout of HTTP calls made by API, GraphQL and Elasticsearch sources
+    pub fn set_http_timeout(&mut self, timeout: Duration) {
+        self.http_timeout = Some(timeout);
+        self.client = Some(http_client(timeout));
+    }
+
+    /// HTTP client giving up after the HTTP timeout, e.g. for requests to
+    /// presigned URLs
+    pub fn http_client(&self) -> reqwest::Client {
+        self.client
+            .clone()
+            .unwrap_or_else(|| http_client(DEFAULT_HTTP_TIMEOUT))
+    }
+
+    /// Wrap created data sources in circuit breakers, one per configuration
+    pub fn set_circuit_breaker(&mut self, config: CircuitBreakerConfig) {
+        self.circuit_breakers = config
+            .enabled
+            .then(|| Arc::new(CircuitBreakers::new(config)));
+    }
+
+    /// Create a data source from its configuration
+    ///
+    /// With circuit breakers enabled, creation fails fast with
+    /// [`CircuitOpenError`] while the data source is considered down.
+    pub async fn create(&self, config: &DataSourceConfig) -> Result<Box<dyn DataSource>> {
+        let Some(breakers) = &self.circuit_breakers else {
+            return self.create_monitored(config).await;
+        };
+        let breaker = breakers.breaker(config);
+        let permit = breaker.acquire()?;
+        match self.create_monitored(config).await {
+            Ok(inner) => {
+                drop(permit);
+                Ok(Box::new(CircuitBreakerDataSource { inner, breaker }))
+            }
+            Err(e) => {
+                breaker.record(permit, circuit_breaker::is_outage(&e));
+                Err(e)
+            }
+        }
+    }
+
+    /// Create a data source whose calls are recorded by the health monitor
+    async fn create_monitored(&self, config: &DataSourceConfig) -> Result<Box<dyn DataSource>> {
+        let key = config_key(config);
+        let started = std::time::Instant::now();
+        match self.create_unguarded(config).await {
+            Ok(inner) => Ok(Box::new(MonitoredDataSource {
+                inner,
+                monitor: self.health.clone(),
+                key,
+            })),
+            Err(e) => {
+                self.health.record(&key, started.elapsed(), Err(&e));
+                Err(e)
+            }
+        }
+    }
+
+    /// Probe a data source and record the outcome, bypassing its breaker
+    ///
+    /// A fresh instance is constructed so sources that only verify
+    /// connectivity when created are verified again.
+    pub async fn check_health(&self, config: &DataSourceConfig) {
+        let started = std::time::Instant::now();
+        let result = match self.construct(config).await {
+            Ok(data_source) => {
+                let result = data_source.health_check().await;
+                if result.is_ok() {
+                    self.instances
+                        .lock()
+                        .unwrap()
+                        .entry(config_key(config))
+                        .or_insert_with(|| Arc::from(data_source));
+                }
+                result
+            }
+            Err(e) => Err(e),
+        };
+        self.health.record_check(
+            &config_key(config),
+            started.elapsed(),
+            result.as_ref().map(|_| ()),
+        );
+    }
+
+    /// Health of a data source as seen by calls and checks so far
+    pub fn health(&self, config: &DataSourceConfig) -> DataSourceHealth {
+        let mut report = self.health.report(&config_key(config));
+        report.circuit = self
+            .circuit_breakers
+            .as_ref()
+            .map(|breakers| breakers.circuit_state(config));
+        report
+    }
+
+    /// The shared instance of a configuration, constructed on first use
+    async fn create_unguarded(&self, config: &DataSourceConfig) -> Result<Box<dyn DataSource>> {
+        let key = config_key(config);
+        if let Some(instance) = self.instances.lock().unwrap().get(&key).cloned() {
+            return Ok(Box::new(instance));
+        }
+        let instance: Arc<dyn DataSource> = Arc::from(self.construct(config).await?);
+        self.instances.lock().unwrap().insert(key, instance.clone());
+        Ok(Box::new(instance))
+    }
+
+    async fn construct(&self, config: &DataSourceConfig) -> Result<Box<dyn DataSource>> {
+        match config {
+            DataSourceConfig::Custom {
+                factory, config, ..
+            } => {
+                let builder = self
+                    .factories
+                    .get(factory)
+                    .ok_or_else(|| anyhow!("Unknown custom data source factory: {}", factory))?;
+                debug!(factory = %factory, "Creating custom data source");
+                builder.create(config).await
+            }
+            other => {
+                create_data_source_with_timeout(
+                    other,
+                    self.http_timeout.unwrap_or(DEFAULT_HTTP_TIMEOUT),
+                )
+                .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticDataSource(Vec<HashMap<String, Value>>);
+
+    #[async_trait::async_trait]
+    impl DataSource for StaticDataSource {
+        async fn execute_query(
+            &self,
+            _query: &str,
+            _params: Option<&HashMap<String, Value>>,
+        ) -> Result<Vec<HashMap<String, Value>>> {
+            Ok(self.0.clone())
+        }
+
+        async fn execute_query_paginated(
+            &self,
+            query: &str,
+            params: Option<&HashMap<String, Value>>,
+            _pagination: Option<&PaginationParams>,
+        ) -> Result<Vec<HashMap<String, Value>>> {
+            self.execute_query(query, params).await
+        }
+
+        async fn execute_mutation(
+            &self,
+            _query: &str,
+            _data: &HashMap<String, Value>,
+        ) -> Result<Value> {
+            Ok(Value::Null)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_custom_factory() {
+        let mut registry = DataSourceRegistry::new();
+        registry.register("static", |config: &Value| {
+            let row = HashMap::from([("greeting".to_string(), config["greeting"].clone())]);
+            Ok(Box::new(StaticDataSource(vec![row])) as Box<dyn DataSource>)
+        });
+
+        let config = DataSourceConfig::Custom {
+            factory: "static".to_string(),
+            config: json!({"greeting": "hello"}),
+            init: Default::default(),
+        };
+        let source = registry.create(&config).await.unwrap();
+        let rows = source.execute_query("", None).await.unwrap();
+        assert_eq!(rows[0]["greeting"], "hello");
+
+        let unknown = DataSourceConfig::Custom {
+            factory: "missing".to_string(),
+            config: Value::Null,
+            init: Default::default(),
+        };
+        assert!(registry.create(&unknown).await.is_err());
+    }
+
+    #[test]
+    fn test_response_rows() {
+        let rows = response_rows(json!({
+            "users": [{"id": 1}, {"id": 2}],
+            "next_page_token": "abc"
+        }));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["id"], 2);
+
+        let rows = response_rows(json!({"id": 1, "tags": ["a", "b"]}));
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["tags"], json!(["a", "b"]));
+
+        assert!(response_rows(json!({})).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_database_transaction_rolls_back() {
+        let path = std::env::temp_dir().join(format!("pmp-tx-{}.db", uuid::Uuid::new_v4()));
+        let db = DatabaseDataSource::new(
+            format!("sqlite://{}?mode=rwc", path.display()),
+            DatabaseType::Sqlite,
+        )
+        .await
+        .unwrap();
+        assert!(db.supports_transactions());
+        let mutation = |query: &str| Mutation {
+            query: query.to_string(),
+            ..Default::default()
+        };
+
+        db.execute_transaction(&[
+            mutation("CREATE TABLE items (id TEXT PRIMARY KEY)"),
+            mutation("INSERT INTO items VALUES ('1'), ('2')"),
+        ])
+        .await
+        .unwrap();
+
+        // The second statement fails, so the first delete is undone
+        assert!(db
+            .execute_transaction(&[
+                mutation("DELETE FROM items WHERE id = '1'"),
+                mutation("DELETE FROM missing_table"),
+            ])
+            .await
+            .is_err());
+        let rows = db
+            .execute_query("SELECT id FROM items", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+
+        // So is a mutation affecting other than its expected rows
+        let error = db
+            .execute_transaction(&[Mutation {
+                expected_rows: Some(1),
+                ..mutation("DELETE FROM items")
+            }])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<UnexpectedRowCount>().unwrap().affected,
+            2
+        );
+        let rows = db
+            .execute_query("SELECT id FROM items", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let results = db
+            .execute_transaction(&[mutation("DELETE FROM items")])
+            .await
+            .unwrap();
+        assert_eq!(results, vec![json!(2)]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_database_column_types() {
+        let path = std::env::temp_dir().join(format!("pmp-types-{}.db", uuid::Uuid::new_v4()));
+        let db = DatabaseDataSource::new(
+            format!("sqlite://{}?mode=rwc", path.display()),
+            DatabaseType::Sqlite,
+        )
+        .await
+        .unwrap();
+        let data = HashMap::new();
+        db.execute_mutation(
+            "CREATE TABLE items (id INTEGER, active BOOLEAN, price NUMERIC, \
+             created DATETIME, payload BLOB, note TEXT)",
+            &data,
+        )
+        .await
+        .unwrap();
+        db.execute_mutation(
+            "INSERT INTO items VALUES \
+             (1, 1, 9.5, '2024-01-02T03:04:05', X'68656C6C6F', NULL)",
+            &data,
+        )
+        .await
+        .unwrap();
+
+        let rows = db.execute_query("SELECT * FROM items", None).await.unwrap();
+        assert_eq!(rows[0]["id"], json!(1));
+        assert_eq!(rows[0]["active"], json!(true));
+        assert_eq!(rows[0]["price"], json!(9.5));
+        assert_eq!(rows[0]["created"], json!("2024-01-02T03:04:05"));
+        assert_eq!(rows[0]["payload"], json!("aGVsbG8="));
+        assert_eq!(rows[0]["note"], Value::Null);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_expand_named_params() {
+        let params = HashMap::from([
+            ("status".to_string(), json!("active")),
+            ("limit".to_string(), json!(10)),
+        ]);
+
+        let (sql, values) = expand_named_params(
+            "SELECT id::text, ':skip' FROM t WHERE status = {{ status }} \
+             AND (:status IS NULL OR kind = :kind) -- :comment\nLIMIT :limit",
+            Some(&params),
+            true,
+        );
+        assert_eq!(
+            sql,
+            "SELECT id::text, ':skip' FROM t WHERE status = $1 \
+             AND ($1 IS NULL OR kind = $2) -- :comment\nLIMIT $3"
+        );
+        assert_eq!(values, vec![json!("active"), Value::Null, json!(10)]);
+
+        let (sql, values) =
+            expand_named_params("WHERE a = :status OR b = {{status}}", Some(&params), false);
+        assert_eq!(sql, "WHERE a = ? OR b = ?");
+        assert_eq!(values, vec![json!("active"), json!("active")]);
+    }
+
+    #[test]
+    fn test_param_value() {
+        assert_eq!(param_value(&json!("42"), "INT8"), json!(42));
+        assert_eq!(param_value(&json!("4.5"), "FLOAT8"), json!(4.5));
+        assert_eq!(param_value(&json!("1"), "BOOL"), json!(true));
+        assert_eq!(param_value(&json!(7), "TEXT"), json!("7"));
+        // Values that don't convert bind unchanged
+        assert_eq!(param_value(&json!("abc"), "INT4"), json!("abc"));
+        assert_eq!(param_value(&json!("42"), ""), json!("42"));
+    }
+
+    #[tokio::test]
+    async fn test_registry_health() {
+        let path = std::env::temp_dir().join(format!("pmp-health-{}.db", uuid::Uuid::new_v4()));
+        let config = |mode: &str| DataSourceConfig::Database {
+            connection_string: format!("sqlite://{}?mode={}", path.display(), mode),
+            db_type: DatabaseType::Sqlite,
+            read_replicas: vec![],
+            migrations: None,
+            init: Default::default(),
+        };
+        let registry = DataSourceRegistry::new();
+
+        registry.check_health(&config("rwc")).await;
+        let health = registry.health(&config("rwc"));
+        assert_eq!(health.state, crate::health::HealthState::Healthy);
+        assert!(health.last_checked_at.is_some());
+
+        // SQL errors are reported but the database is still reachable
+        let source = registry.create(&config("rwc")).await.unwrap();
+        assert!(source
+            .execute_query("SELECT * FROM missing", None)
+            .await
+            .is_err());
+        let health = registry.health(&config("rwc"));
+        assert_eq!(health.state, crate::health::HealthState::Healthy);
+        assert_eq!((health.calls, health.errors), (2, 1));
+        assert!(health.last_error.is_some());
+
+        let missing =
+            std::env::temp_dir().join(format!("pmp-missing-{}/x.db", uuid::Uuid::new_v4()));
+        let unreachable = DataSourceConfig::Database {
+            connection_string: format!("sqlite://{}?mode=ro", missing.display()),
+            db_type: DatabaseType::Sqlite,
+            read_replicas: vec![],
+            migrations: None,
+            init: Default::default(),
+        };
+        registry.check_health(&unreachable).await;
+        assert_eq!(
+            registry.health(&unreachable).state,
+            crate::health::HealthState::Unhealthy
+        );
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_database_binds_named_params() {
+        let path = std::env::temp_dir().join(format!("pmp-params-{}.db", uuid::Uuid::new_v4()));
+        let db = DatabaseDataSource::new(
+            format!("sqlite://{}?mode=rwc", path.display()),
+            DatabaseType::Sqlite,
+        )
+        .await
+        .unwrap();
+        db.execute_mutation(
+            "CREATE TABLE users (name TEXT, age INTEGER)",
+            &HashMap::new(),
+        )
+        .await
+        .unwrap();
+        for (name, age) in [("alice", 30), ("bob", 40)] {
+            let data = HashMap::from([
+                ("name".to_string(), json!(name)),
+                ("age".to_string(), json!(age)),
+            ]);
+            db.execute_mutation("INSERT INTO users VALUES ({{name}}, :age)", &data)
+                .await
+                .unwrap();
+        }
+
+        let query = "SELECT name FROM users WHERE ({{name}} IS NULL OR name = {{name}})";
+        let params = HashMap::from([("name".to_string(), json!("alice"))]);
+        let rows = db.execute_query(query, Some(&params)).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["name"], json!("alice"));
+
+        // Values are bound, never spliced into the SQL
+        let params = HashMap::from([("name".to_string(), json!("x' OR '1'='1"))]);
+        assert!(db
+            .execute_query(query, Some(&params))
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Missing params bind as NULL
+        assert_eq!(db.execute_query(query, None).await.unwrap().len(), 2);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_pg_numeric() {
+        // ndigits, weight, sign, dscale, then base-10000 digits
+        let numeric = |header: [i16; 4], digits: &[i16]| {
+            header
+                .iter()
+                .chain(digits)
+                .flat_map(|word| word.to_be_bytes())
+                .collect::<Vec<u8>>()
+        };
+
+        // 12345678.90
+        assert_eq!(
+            pg_numeric(&numeric([3, 1, 0, 2], &[1234, 5678, 9000])).as_deref(),
+            Some("12345678.90")
+        );
+        // -0.0012
+        assert_eq!(
+            pg_numeric(&numeric([1, -1, 0x4000, 4], &[12])).as_deref(),
+            Some("-0.0012")
+        );
+        // 10000
+        assert_eq!(
+            pg_numeric(&numeric([1, 1, 0, 0], &[1])).as_deref(),
+            Some("10000")
+        );
+        assert_eq!(
+            pg_numeric(&numeric([0, 0, 0xC000u16 as i16, 0], &[])).as_deref(),
+            Some("NaN")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_database_read_replicas() {
+        let dir = std::env::temp_dir().join(format!("pmp-replicas-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let url = |name: &str| format!("sqlite://{}?mode=rwc", dir.join(name).display());
+
+        for (name, label) in [("primary.db", "primary"), ("replica.db", "replica")] {
+            let db = DatabaseDataSource::new(url(name), DatabaseType::Sqlite)
+                .await
+                .unwrap();
+            db.execute_mutation("CREATE TABLE items (label TEXT)", &HashMap::new())
+                .await
+                .unwrap();
+            db.execute_mutation(
+                &format!("INSERT INTO items VALUES ('{}')", label),
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        }
+
+        // Queries go to the replica, mutations to the primary
+        let db = DatabaseDataSource::with_replicas(
+            url("primary.db"),
+            &[url("replica.db")],
+            DatabaseType::Sqlite,
+        )
+        .await
+        .unwrap();
+        let rows = db
+            .execute_query("SELECT label FROM items", None)
+            .await
+            .unwrap();
+        assert_eq!(rows[0]["label"], "replica");
+        db.execute_mutation("DELETE FROM items", &HashMap::new())
+            .await
+            .unwrap();
+        let rows = db
+            .execute_query("SELECT label FROM items", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+
+        // A replica that cannot be reached falls back to the primary
+        let missing = format!("sqlite://{}?mode=ro", dir.join("missing.db").display());
+        let db =
+            DatabaseDataSource::with_replicas(url("primary.db"), &[missing], DatabaseType::Sqlite)
+                .await
+                .unwrap();
+        let rows = db
+            .execute_query("SELECT label FROM items", None)
+            .await
+            .unwrap();
+        assert!(rows.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_supabase_filters() {
+        let data = HashMap::from([
+            ("id".to_string(), json!(7)),
+            ("owner".to_string(), json!("ada")),
+        ]);
+
+        assert_eq!(
+            supabase_filters("", &data).unwrap(),
+            vec![("id".to_string(), "eq.7".to_string())]
+        );
+        assert_eq!(
+            supabase_filters("status=eq.draft&owner=eq.{owner}", &data).unwrap(),
+            vec![
+                ("status".to_string(), "eq.draft".to_string()),
+                ("owner".to_string(), "eq.ada".to_string()),
+            ]
+        );
+        assert!(supabase_filters("owner=eq.{missing}", &data).is_err());
+        assert!(supabase_filters("", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_s3_credentials() {
+        assert_eq!(s3_credentials(None, None).unwrap(), None);
+        assert_eq!(
+            s3_credentials(Some("AKIA"), Some("secret")).unwrap(),
+            Some(("AKIA".to_string(), "secret".to_string()))
+        );
+        assert!(s3_credentials(Some("AKIA"), None).is_err());
+    }
+
+    #[test]
+    fn test_api_pagination_helpers() {
+        assert_eq!(PaginationParams::new(0, 10).offset, 0);
+        assert_eq!(PaginationParams::new(3, 10).offset, 20);
+        assert_eq!(PaginationParams::new(usize::MAX, 1000).offset, usize::MAX);
+
+        let body = json!({"meta": {"next": "abc", "total": 42}, "data": [{"id": 1}, "x"]});
+        assert_eq!(json_path(&body, "meta.next"), Some(&json!("abc")));
+        assert_eq!(json_path(&body, "meta.missing"), None);
+        assert_eq!(api_rows(body).unwrap().len(), 1);
+        assert_eq!(
+            api_rows(json!({"data": {"id": 1}})).unwrap()[0]["data"]["id"],
+            1
+        );
+
+        assert_eq!(
+            link_next(
+                r#"<https://api.example.com/items?page=1>; rel="prev", <https://api.example.com/items?page=3>; rel="next""#
+            )
+            .as_deref(),
+            Some("https://api.example.com/items?page=3")
+        );
+        assert_eq!(
+            link_next(r#"<https://api.example.com/items>; rel="last""#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_api_response_mapping() {
+        let body = json!({
+            "result": {"items": [{"ID": 1, "attrs": {"name": "Ada"}, "kind": "user"}]},
+            "meta": {"count": [7]}
+        });
+        assert_eq!(json_path(&body, "$.meta.count[0]"), Some(&json!(7)));
+        assert_eq!(json_path(&body, ".result.items.0.ID"), Some(&json!(1)));
+
+        let mapping = ApiResponseMapping {
+            items: Some("$.result.items".to_string()),
+            fields: HashMap::from([
+                ("id".to_string(), "ID".to_string()),
+                ("name".to_string(), "attrs.name".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let source = ApiDataSource::new(
+            "https://api.example.com".to_string(),
+            None,
+            None,
+            None,
+            Some(mapping),
+            DEFAULT_HTTP_TIMEOUT,
+        )
+        .unwrap();
+
+        let rows = source.rows(body).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["id"], 1);
+        assert_eq!(rows[0]["name"], "Ada");
+        assert_eq!(rows[0]["kind"], "user");
+        assert!(!rows[0].contains_key("ID"));
+
+        assert!(source.rows(json!({"other": []})).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_api_endpoint_templates() {
+        let values = HashMap::from([
+            ("id".to_string(), json!(42)),
+            ("name".to_string(), json!("a b")),
+        ]);
+
+        let endpoint = api_endpoint(
+            "https://api.example.com/",
+            "patch /users/{id}",
+            Some(&values),
+        )
+        .unwrap();
+        assert_eq!(endpoint.method, Some(reqwest::Method::PATCH));
+        assert_eq!(endpoint.url, "https://api.example.com/users/42");
+        assert_eq!(endpoint.used, vec!["id".to_string()]);
+        assert_eq!(
+            api_query_params(Some(&values), &endpoint.used),
+            vec![("name".to_string(), "a b".to_string())]
+        );
+
+        let endpoint =
+            api_endpoint("https://api.example.com", "search/{name}", Some(&values)).unwrap();
+        assert_eq!(endpoint.method, None);
+        assert_eq!(endpoint.url, "https://api.example.com/search/a%20b");
+
+        assert!(api_endpoint(
+            "https://api.example.com",
+            "DELETE /users/{uid}",
+            Some(&values)
+        )
+        .is_err());
+        assert!(api_endpoint("https://api.example.com", "/users/{id", Some(&values)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_api_cursor_and_link_pagination() {
+        use axum::{extract::Query, http::header, routing::get, Json, Router};
+
+        // Items 0..5; cursors and `page` links both point at the next start index
+        async fn items(
+            Query(query): Query<HashMap<String, String>>,
+        ) -> ([(header::HeaderName, String); 1], Json<Value>) {
+            let start: usize = query
+                .get("cursor")
+                .or(query.get("page"))
+                .map_or(0, |c| c.parse().unwrap());
+            let limit: usize = query["limit"].parse().unwrap();
+            let end = (start + limit).min(5);
+            let next = (end < 5).then(|| end.to_string());
+            let link = match &next {
+                Some(next) => format!("</items?page={}&limit={}>; rel=\"next\"", next, limit),
+                None => String::new(),
+            };
+            let data: Vec<Value> = (start..end).map(|i| json!({"id": i})).collect();
+            (
+                [(header::LINK, link)],
+                Json(json!({"data": data, "meta": {"next": next, "total": 5}})),
+            )
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/items", get(items)))
+                .await
+                .unwrap()
+        });
+
+        for style in [ApiPaginationStyle::Cursor, ApiPaginationStyle::LinkHeader] {
+            let config: ApiPaginationConfig = serde_json::from_value(json!({
+                "style": style,
+                "next_cursor_field": "meta.next",
+                "total_field": "meta.total",
+            }))
+            .unwrap();
+            let source = ApiDataSource::new(
+                base_url.clone(),
+                None,
+                None,
+                Some(config),
+                None,
+                DEFAULT_HTTP_TIMEOUT,
+            )
+            .unwrap();
+
+            let page = source
+                .fetch_page("items", None, &PaginationParams::new(3, 2))
+                .await
+                .unwrap();
+            let ids: Vec<&Value> = page.rows.iter().map(|row| &row["id"]).collect();
+            assert_eq!(ids, vec![&json!(4)], "{:?}", style);
+            assert_eq!(page.total, Some(5));
+        }
+    }
+
+    #[cfg(feature = "mongodb-datasource")]
+    #[test]
+    fn test_mongo_pipeline_and_bson_conversion() {
+        use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime};
+
+        assert!(mongo_pipeline(r#"{"status": "active"}"#, None)
+            .unwrap()
+            .is_none());
+
+        let pagination = PaginationParams::new(3, 10);
+        let pipeline = mongo_pipeline(
+            r#"[{"$match": {"status": "active"}}, {"$sort": {"created": -1}}]"#,
+            Some(&pagination),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(pipeline.len(), 4);
+        assert_eq!(pipeline[2], doc! { "$skip": 20_i64 });
+        assert_eq!(pipeline[3], doc! { "$limit": 10_i64 });
+        assert!(mongo_pipeline("[{", None).is_err());
+
+        let oid = ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap();
+        let row = mongo_document_to_row(doc! {
+            "_id": oid,
+            "created": DateTime::from_millis(0),
+            "tags": [Bson::ObjectId(oid)],
+            "total": 12.5,
+        });
+        assert_eq!(row["_id"], "507f1f77bcf86cd799439011");
+        assert_eq!(row["created"], "1970-01-01T00:00:00Z");
+        assert_eq!(row["tags"], json!(["507f1f77bcf86cd799439011"]));
+        assert_eq!(row["total"], 12.5);
+    }
+
+    #[cfg(feature = "redis-datasource")]
+    #[tokio::test]
+    async fn test_redis_scan_pages() {
+        // Ten keys served in batches of three; the cursor is the batch start
+        let scan = |cursor: u64| async move {
+            let keys: Vec<String> = (cursor..(cursor + 3).min(10))
+                .map(|i| format!("user:{}", i))
+                .collect();
+            let next = if cursor + 3 >= 10 { 0 } else { cursor + 3 };
+            Ok((next, keys))
+        };
+
+        let (page, token) = redis_scan_page(None, 4, scan).await.unwrap();
+        assert_eq!(page, vec!["user:0", "user:1", "user:2", "user:3"]);
+        assert_eq!(token.as_deref(), Some("3:1"));
+
+        let (page, token) = redis_scan_page(token, 4, scan).await.unwrap();
+        assert_eq!(page, vec!["user:4", "user:5", "user:6", "user:7"]);
+        assert_eq!(token.as_deref(), Some("6:2"));
+
+        let (page, token) = redis_scan_page(token, 4, scan).await.unwrap();
+        assert_eq!(page, vec!["user:8", "user:9"]);
+        assert!(token.is_none());
+
+        let (page, token) = redis_scan_page(None, 3, scan).await.unwrap();
+        assert_eq!(page.len(), 3);
+        assert_eq!(token.as_deref(), Some("3:0"));
+
+        assert!(redis_scan_page(Some("bogus".to_string()), 4, scan)
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "redis-datasource")]
+    #[test]
+    fn test_redis_key_rows() {
+        assert!(is_redis_pattern("user:*"));
+        assert!(!is_redis_pattern("user:42"));
+
+        let row = redis_key_row("user:1", Some(r#"{"name": "Ada"}"#.to_string()));
+        assert_eq!(row["key"], "user:1");
+        assert_eq!(row["name"], "Ada");
+
+        let row = redis_key_row("counter", Some("plain".to_string()));
+        assert_eq!(row["value"], "plain");
+        assert_eq!(redis_key_row("list", None)["value"], Value::Null);
+    }
+
+    #[cfg(feature = "redis-datasource")]
+    #[test]
+    fn test_redis_structure_queries() {
+        assert_eq!(
+            parse_redis_query("user:1").unwrap(),
+            RedisQuery::Key("user:1")
+        );
+        assert_eq!(
+            parse_redis_query("HGETALL user:1").unwrap(),
+            RedisQuery::Hash("user:1")
+        );
+        assert_eq!(
+            parse_redis_query("rpush jobs").unwrap(),
+            RedisQuery::List("jobs")
+        );
+        assert_eq!(
+            parse_redis_query("ZRANGEBYSCORE scores 10 (20").unwrap(),
+            RedisQuery::SortedSet {
+                key: "scores",
+                min: "10",
+                max: "(20"
+            }
+        );
+        assert_eq!(
+            parse_redis_query("ZADD scores").unwrap(),
+            RedisQuery::SortedSet {
+                key: "scores",
+                min: "-inf",
+                max: "+inf"
+            }
+        );
+        assert!(parse_redis_query("LRANGE").is_err());
+        assert_eq!(
+            parse_redis_query("SUBSCRIBE events:*").unwrap(),
+            RedisQuery::Channel("events:*")
+        );
+
+        let data = HashMap::from([
+            ("score".to_string(), json!(42)),
+            ("member".to_string(), json!("ada")),
+        ]);
+        assert_eq!(
+            redis_sorted_set_entry(&data).unwrap(),
+            (42.0, "ada".to_string())
+        );
+        let data = HashMap::from([
+            ("score".to_string(), json!("1.5")),
+            ("name".to_string(), json!("Ada")),
+        ]);
+        assert_eq!(
+            redis_sorted_set_entry(&data).unwrap(),
+            (1.5, r#"{"name":"Ada"}"#.to_string())
+        );
+        assert!(redis_sorted_set_entry(&HashMap::new()).is_err());
+    }
+
+    #[cfg(feature = "grpc-datasource")]
+    #[tokio::test]
+    async fn test_grpc_requires_descriptor_set() {
+        let err = GrpcDataSource::new(
+            "localhost:50051".to_string(),
+            "service.proto".to_string(),
+            "users.UserService".to_string(),
+            false,
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("--descriptor_set_out"));
+    }
+
+    #[test]
+    fn test_firestore_value_conversion() {
+        let value =
+            json!({"name": "Ada", "age": 36, "score": 1.5, "tags": ["a"], "meta": {"ok": true}});
+        let firestore = to_firestore_value(&value);
+        assert_eq!(
+            firestore["mapValue"]["fields"]["age"],
+            json!({"integerValue": "36"})
+        );
+        assert_eq!(from_firestore_value(&firestore), value);
+
+        let row = firestore_document_to_row(&json!({
+            "name": "projects/p/databases/(default)/documents/users/u1",
+            "fields": {"created": {"timestampValue": "2024-01-01T00:00:00Z"}}
+        }));
+        assert_eq!(row["id"], "u1");
+        assert_eq!(row["created"], "2024-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_firestore_structured_query() {
+        let query = r#"{
+            "where": [
+                {"field": "status", "op": "==", "value": "{status}"},
+                {"field": "age", "op": ">=", "value": 18}
+            ],
+            "orderBy": [{"field": "createdAt", "direction": "desc"}],
+            "limit": 100
+        }"#;
+        let params = HashMap::from([("status".to_string(), json!("active"))]);
+        let pagination = PaginationParams::new(2, 20);
+
+        let structured =
+            firestore_structured_query(query, "users", Some(&params), Some(&pagination)).unwrap();
+        assert_eq!(structured["from"][0]["collectionId"], "users");
+        let filters = &structured["where"]["compositeFilter"]["filters"];
+        assert_eq!(
+            filters[0]["fieldFilter"]["value"],
+            json!({"stringValue": "active"})
+        );
+        assert_eq!(filters[1]["fieldFilter"]["op"], "GREATER_THAN_OR_EQUAL");
+        assert_eq!(structured["orderBy"][0]["direction"], "DESCENDING");
+        assert_eq!(structured["limit"], 20);
+        assert_eq!(structured["offset"], 20);
+
+        assert!(firestore_structured_query(query, "users", None, None).is_err());
+    }
+
+    #[test]
+    fn test_couchdb_find_body() {
+        let params = HashMap::from([("status".to_string(), json!("active"))]);
+
+        let body = couchdb_find_body(r#"{"status": "{status}"}"#, Some(&params)).unwrap();
+        assert_eq!(body, json!({"selector": {"status": "active"}}));
+
+        let body = couchdb_find_body(
+            r#"{"selector": {"age": {"$gt": 18}}, "sort": [{"age": "desc"}]}"#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(body["sort"][0]["age"], "desc");
+
+        assert_eq!(
+            couchdb_find_body("", None).unwrap(),
+            json!({"selector": {}})
+        );
+        assert!(couchdb_find_body(r#"{"status": "{status}"}"#, None).is_err());
+
+        let row = couchdb_doc_to_row(json!({"_id": "u1", "_rev": "1-a", "name": "Ada"})).unwrap();
+        assert_eq!(row["id"], "u1");
+        assert_eq!(row["_rev"], "1-a");
+    }
+
+    #[test]
+    fn test_influx_annotated_csv() {
+        let csv = "#datatype,string,long,dateTime:RFC3339,double,string\r\n\
+#group,false,false,false,false,true\r\n\
+#default,_result,,,,\r\n\
+,result,table,_time,_value,host\r\n\
+,,0,2024-01-01T00:00:00Z,1.5,\"web,1\"\r\n\
+,,0,2024-01-01T00:01:00Z,,web2\r\n\
+\r\n";
+        let rows = influx_csv_to_rows(csv).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["_value"], json!(1.5));
+        assert_eq!(rows[0]["host"], "web,1");
+        assert_eq!(rows[1]["_value"], Value::Null);
+        assert!(!rows[0].contains_key("table"));
+
+        let error = "#datatype,string,string\r\n#group,true,true\r\n#default,,\r\n,error,reference\r\n,bad query,897\r\n";
+        assert!(influx_csv_to_rows(error).is_err());
+    }
+
+    #[test]
+    fn test_influx_line_protocol() {
+        let data = HashMap::from([
+            ("host".to_string(), json!("web 1")),
+            ("usage".to_string(), json!(0.5)),
+            ("cores".to_string(), json!(8)),
+            ("time".to_string(), json!("2024-01-01T00:00:00Z")),
+        ]);
+        assert_eq!(
+            influx_line_protocol("cpu", &data, "s").unwrap(),
+            "cpu,host=web\\ 1 cores=8i,usage=0.5 1704067200"
+        );
+
+        let only_tags = HashMap::from([("host".to_string(), json!("web1"))]);
+        assert!(influx_line_protocol("cpu", &only_tags, "ns").is_err());
+    }
+
+    #[test]
+    fn test_prometheus_request_and_rows() {
+        let params = HashMap::from([
+            ("job".to_string(), json!("api\"")),
+            ("start".to_string(), json!("2024-01-01T00:00:00Z")),
+            ("end".to_string(), json!(1704070800)),
+        ]);
+        let promql = bind_promql_params(r#"up{job="{job}"}"#, Some(&params));
+        assert_eq!(promql, r#"up{job="api\""}"#);
+
+        let (endpoint, query) = prometheus_request(&promql, Some(&params), 0.0).unwrap();
+        assert_eq!(endpoint, "query_range");
+        assert!(query.contains(&("step", "15".to_string())));
+
+        let (endpoint, _) = prometheus_request("up", None, 0.0).unwrap();
+        assert_eq!(endpoint, "query");
+
+        let rows = prometheus_rows(&json!({
+            "resultType": "matrix",
+            "result": [{
+                "metric": {"__name__": "up", "job": "api"},
+                "values": [[1704067200, "1"], [1704067215, "0"]]
+            }]
+        }));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["metric"], "up");
+        assert_eq!(rows[0]["timestamp"], "2024-01-01T00:00:00.000Z");
+        assert_eq!(rows[1]["value"], json!(0.0));
+    }
+
+    #[tokio::test]
+    async fn test_file_data_source() {
+        let dir = std::env::temp_dir().join(format!("pmp-files-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("users.csv");
+        std::fs::write(
+            &csv,
+            "id,name,age:int,active\n1,Ada,36,true\n2,\"Grace, H\",45,false\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("more.jsonl"), "{\"id\": 3, \"name\": \"Linus\"}\n").unwrap();
+
+        let source = FileDataSource::new(
+            csv.to_string_lossy().to_string(),
+            None,
+            FileWriteMode::Overwrite,
+        );
+        let rows = source.execute_query("", None).await.unwrap();
+        assert_eq!(rows[1]["name"], "Grace, H");
+        assert_eq!(rows[1]["age"], 45);
+        assert_eq!(rows[1]["active"], false);
+
+        let params = HashMap::from([("name".to_string(), json!("Ada"))]);
+        let rows = source
+            .execute_query(r#"{"name": "{name}"}"#, Some(&params))
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+
+        let update = HashMap::from([
+            ("id".to_string(), json!("1")),
+            ("age".to_string(), json!(37)),
+        ]);
+        source.execute_mutation("", &update).await.unwrap();
+        source
+            .execute_delete(
+                "DELETE FROM users WHERE id = {{id}}",
+                &HashMap::from([("id".to_string(), json!("2"))]),
+            )
+            .await
+            .unwrap();
+        let text = std::fs::read_to_string(&csv).unwrap();
+        assert_eq!(text, "id,name,age:int,active\n1,Ada,37,true\n");
+
+        let glob = FileDataSource::new(
+            format!("{}/*.*", dir.to_string_lossy()),
+            None,
+            FileWriteMode::Append,
+        );
+        assert_eq!(glob.execute_query("", None).await.unwrap().len(), 2);
+        assert!(glob.execute_mutation("", &update).await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_airtable_list_query() {
+        let params = HashMap::from([("status".to_string(), json!("Active \"now\""))]);
+        let query = airtable_list_query(
+            r#"{
+                "filterByFormula": "{Age} > 18",
+                "filters": {"Status": "{status}"},
+                "sort": [{"field": "Name", "direction": "DESC"}],
+                "fields": ["Name"]
+            }"#,
+            Some(&params),
+        )
+        .unwrap();
+
+        assert_eq!(
+            query[0],
+            (
+                "filterByFormula".to_string(),
+                r#"AND({Age} > 18, {Status} = "Active \"now\"")"#.to_string()
+            )
+        );
+        assert!(query.contains(&("sort[0][direction]".to_string(), "desc".to_string())));
+        assert!(query.contains(&("fields[]".to_string(), "Name".to_string())));
+
+        let row = airtable_record_to_row(&json!({
+            "id": "rec1",
+            "createdTime": "2024-01-01T00:00:00.000Z",
+            "fields": {"Name": "Ada"}
+        }));
+        assert_eq!(row["id"], "rec1");
+        assert_eq!(row["Name"], "Ada");
+    }
+
+    #[test]
+    fn test_odata_list_query() {
+        let params = HashMap::from([("city".to_string(), json!("O'Brien"))]);
+        let pagination = PaginationParams::new(3, 25);
+        let options = odata_list_query(
+            r#"{
+                "filter": "Age gt 18",
+                "filters": {"City": "{city}", "Active": true},
+                "orderBy": ["Name desc", "Id"],
+                "select": "Id,Name"
+            }"#,
+            Some(&params),
+            Some(&pagination),
+        )
+        .unwrap();
+
+        assert_eq!(
+            options[0],
+            (
+                "$filter",
+                "(Age gt 18) and Active eq true and City eq 'O''Brien'".to_string()
+            )
+        );
+        assert!(options.contains(&("$orderby", "Name desc,Id".to_string())));
+        assert!(options.contains(&("$top", "25".to_string())));
+        assert!(options.contains(&("$skip", "50".to_string())));
+
+        assert_eq!(odata_key(&json!(42)), "42");
+        assert_eq!(odata_key(&json!("ALFKI")), "%27ALFKI%27");
+
+        let row = odata_entity_to_row(&json!({"@odata.etag": "W/1", "Id": 1}));
+        assert_eq!(row.len(), 1);
+    }
+
+    #[test]
+    fn test_json_rpc_calls() {
+        let params = json!({"status": "active"});
+        assert_eq!(
+            json_rpc_calls("users.list", params.clone()).unwrap(),
+            vec![JsonRpcCall {
+                method: "users.list".to_string(),
+                params: params.clone(),
+            }]
+        );
+
+        let calls = json_rpc_calls(
+            r#"["users.list", {"method": "users.count", "params": {"limit": 5}}]"#,
+            params,
+        )
+        .unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].params, json!({"limit": 5, "status": "active"}));
+
+        let rows = json_rpc_result_rows(json!([{"id": 1}, 2]));
+        assert_eq!(rows[1]["result"], 2);
+        assert_eq!(json_rpc_result_rows(json!(42))[0]["result"], 42);
+    }
+
+    #[test]
+    fn test_elasticsearch_auth() {
+        let config = |auth_type: &str, token: Option<&str>, username: Option<&str>| ApiAuthConfig {
+            auth_type: auth_type.to_string(),
+            token: token.map(|s| s.to_string()),
+            username: username.map(|s| s.to_string()),
+            password: Some("secret".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            HttpAuth::from_config(&config("basic", None, Some("elastic"))).unwrap(),
+            HttpAuth::Basic {
+                username: "elastic".to_string(),
+                password: Some("secret".to_string()),
+            }
+        );
+        assert_eq!(
+            HttpAuth::from_config(&config("api_key", Some("a2V5"), None)).unwrap(),
+            HttpAuth::ApiKey("a2V5".to_string())
+        );
+        assert!(HttpAuth::from_config(&config("bearer", None, None)).is_err());
+        assert!(HttpAuth::from_config(&config("kerberos", None, None)).is_err());
+
+        let tls = TlsConfig {
+            ca_cert: Some("/nonexistent/ca.pem".to_string()),
+            skip_verify: false,
+        };
+        assert!(ElasticsearchDataSource::new(
+            vec![],
+            "logs".to_string(),
+            None,
+            Some(&tls),
+            DEFAULT_HTTP_TIMEOUT
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_api_oauth2_client_credentials() {
+        use axum::{
+            extract::State,
+            http::{header, HeaderMap, StatusCode},
+            routing::{get, post},
+            Json, Router,
+        };
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let token_requests = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/token",
+                post(
+                    |State(count): State<Arc<AtomicUsize>>, body: String| async move {
+                        assert!(body.contains("grant_type=client_credentials"));
+                        assert!(body.contains("scope=read+write"));
+                        count.fetch_add(1, Ordering::SeqCst);
+                        Json(json!({"access_token": "tok-1", "expires_in": 3600}))
+                    },
+                ),
+            )
+            .route(
+                "/items",
+                get(|headers: HeaderMap| async move {
+                    match headers.get(header::AUTHORIZATION) {
+                        Some(auth) if auth == "Bearer tok-1" => Ok(Json(json!([{"id": 1}]))),
+                        _ => Err(StatusCode::UNAUTHORIZED),
+                    }
+                }),
+            )
+            .with_state(token_requests.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let auth = ApiAuthConfig {
+            auth_type: "oauth2".to_string(),
+            token_url: Some(format!("{}/token", base_url)),
+            client_id: Some("backoffice".to_string()),
+            client_secret: Some("s3cret".to_string()),
+            scopes: vec!["read".to_string(), "write".to_string()],
+            ..Default::default()
+        };
+        let source = ApiDataSource::new(
+            base_url.clone(),
+            None,
+            Some(&auth),
+            None,
+            None,
+            DEFAULT_HTTP_TIMEOUT,
+        )
+        .unwrap();
+
+        for _ in 0..2 {
+            let rows = source.execute_query("items", None).await.unwrap();
+            assert_eq!(rows[0]["id"], 1);
+        }
+        assert_eq!(token_requests.load(Ordering::SeqCst), 1);
+
+        let missing_secret = ApiAuthConfig {
+            client_secret: None,
+            ..auth
+        };
+        assert!(HttpAuth::from_config(&missing_secret).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_http_sources_time_out() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Accepts connections and never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                open.push(socket);
+            }
+        });
+
+        let sources = [
+            (
+                json!({"type": "supabase", "url": url, "api_key": "k", "table": "t"}),
+                "select=*",
+            ),
+            (
+                json!({"type": "couchdb", "url": url, "database": "d"}),
+                "{}",
+            ),
+            (
+                json!({"type": "influxdb", "url": url, "org": "o", "bucket": "b", "token": "t"}),
+                "from(bucket: \"b\")",
+            ),
+            (json!({"type": "prometheus", "url": url}), "up"),
+            (
+                json!({"type": "odata", "service_url": url, "entity_set": "People"}),
+                "",
+            ),
+            (json!({"type": "jsonrpc", "endpoint": url}), "list_items"),
+        ];
+        for (config, query) in sources {
+            let config: DataSourceConfig = serde_json::from_value(config).unwrap();
+            let source = create_data_source_with_timeout(&config, Duration::from_millis(200))
+                .await
+                .unwrap();
+            let before = connections.load(Ordering::SeqCst);
+            let result =
+                tokio::time::timeout(Duration::from_secs(10), source.execute_query(query, None))
+                    .await
+                    .unwrap_or_else(|_| panic!("{:?} did not time out", config));
+            assert!(result.is_err(), "{:?}", config);
+            assert!(connections.load(Ordering::SeqCst) > before, "{:?}", config);
+        }
     }
 }
@@ -0,0 +1,158 @@
+//! Email addresses
+//!
+//! Email fields enforce their `domain_whitelist` and `domain_blacklist`,
+//! and with `verify_mx` check that the domain of every address accepts
+//! mail: it must have MX records, or at least an address record to fall
+//! back to. Lookups are cached, and lookups that fail or time out let the
+//! address through, so a DNS outage doesn't block submissions.
+
+use crate::config::EmailFieldConfig;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+use trust_dns_resolver::error::{ResolveError, ResolveErrorKind};
+use trust_dns_resolver::TokioAsyncResolver;
+
+/// Default time an MX lookup may take
+pub const DEFAULT_MX_TIMEOUT_MS: u64 = 3000;
+
+/// Default time MX lookup results are reused for
+pub const DEFAULT_MX_CACHE_TTL_SECS: u64 = 3600;
+
+/// The addresses of a value: each of a comma separated list when the
+/// field allows several
+pub fn addresses<'a>(value: &'a str, config: &EmailFieldConfig) -> Vec<&'a str> {
+    if config.allow_multiple {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .collect()
+    } else {
+        vec![value.trim()]
+    }
+}
+
+/// Lowercased domain of an address
+pub fn domain(address: &str) -> Option<String> {
+    address
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.trim_end_matches('.').to_lowercase())
+        .filter(|domain| !domain.is_empty())
+}
+
+/// Whether a domain is, or is a subdomain of, a list entry such as
+/// `example.com` (also written `@example.com`)
+fn listed(domain: &str, list: &[String]) -> bool {
+    list.iter().any(|entry| {
+        let entry = entry.trim().trim_start_matches('@').to_lowercase();
+        domain == entry || domain.ends_with(&format!(".{}", entry))
+    })
+}
+
+/// Whether the field's domain lists let a domain through
+pub fn domain_allowed(domain: &str, config: &EmailFieldConfig) -> bool {
+    config
+        .domain_whitelist
+        .as_ref()
+        .is_none_or(|list| listed(domain, list))
+        && !config
+            .domain_blacklist
+            .as_ref()
+            .is_some_and(|list| listed(domain, list))
+}
+
+/// Whether domains accept mail, keyed by domain, and when that was looked up
+fn mx_cache() -> &'static Mutex<HashMap<String, (Instant, bool)>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (Instant, bool)>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// Whether a domain accepts mail, false only when DNS says it has neither
+/// MX nor address records
+pub async fn accepts_mail(domain: &str, config: &EmailFieldConfig) -> bool {
+    let ttl = Duration::from_secs(
+        config
+            .mx_cache_ttl_secs
+            .unwrap_or(DEFAULT_MX_CACHE_TTL_SECS),
+    );
+    if let Some((looked_up_at, accepts)) = mx_cache().lock().unwrap().get(domain) {
+        if looked_up_at.elapsed() < ttl {
+            return *accepts;
+        }
+    }
+
+    let timeout = Duration::from_millis(config.mx_timeout_ms.unwrap_or(DEFAULT_MX_TIMEOUT_MS));
+    match tokio::time::timeout(timeout, lookup(domain)).await {
+        Ok(Ok(accepts)) => {
+            debug!(domain = %domain, accepts, "Looked up mail servers");
+            mx_cache()
+                .lock()
+                .unwrap()
+                .insert(domain.to_string(), (Instant::now(), accepts));
+            accepts
+        }
+        Ok(Err(e)) => {
+            warn!(domain = %domain, error = %e, "Mail server lookup failed");
+            true
+        }
+        Err(_) => {
+            warn!(domain = %domain, "Mail server lookup timed out");
+            true
+        }
+    }
+}
+
+async fn lookup(domain: &str) -> Result<bool, ResolveError> {
+    let resolver = TokioAsyncResolver::tokio_from_system_conf()?;
+    // A fully qualified name isn't searched for under local domains
+    let name = format!("{}.", domain);
+    match resolver.mx_lookup(name.as_str()).await {
+        Ok(mx) if mx.iter().next().is_some() => return Ok(true),
+        Ok(_) => {}
+        Err(e) if no_records(&e) => {}
+        Err(e) => return Err(e),
+    }
+    // Without MX records mail goes to the domain's own address
+    match resolver.lookup_ip(name.as_str()).await {
+        Ok(ips) => Ok(ips.iter().next().is_some()),
+        Err(e) if no_records(&e) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn no_records(e: &ResolveError) -> bool {
+    matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_domains() {
+        let config = EmailFieldConfig {
+            allow_multiple: true,
+            domain_whitelist: Some(vec!["example.com".to_string(), "@corp.io".to_string()]),
+            domain_blacklist: Some(vec!["old.example.com".to_string()]),
+            verify_mx: true,
+            ..Default::default()
+        };
+        let domains: Vec<_> = addresses("ada@Example.com, bob@eu.corp.io,", &config)
+            .into_iter()
+            .filter_map(domain)
+            .collect();
+        assert_eq!(domains, ["example.com", "eu.corp.io"]);
+        assert!(domains.iter().all(|d| domain_allowed(d, &config)));
+        assert!(!domain_allowed("old.example.com", &config));
+        assert!(!domain_allowed("notexample.com", &config));
+
+        // Cached answers are reused without a lookup
+        mx_cache()
+            .lock()
+            .unwrap()
+            .insert("no-mail.example.com".to_string(), (Instant::now(), false));
+        assert!(!accepts_mail("no-mail.example.com", &config).await);
+    }
+}
@@ -0,0 +1,137 @@
+//! Expression validation rules
+//!
+//! `expression` rules check a Rhai expression over the whole payload, for
+//! business rules the other validation types can't express, such as
+//! `discount <= price * 0.5` or `end_date > start_date + days(1)`. Every
+//! field of the payload is a variable, along with `value` (the value of
+//! the field the rule is on) and `data` (the whole payload). Dates and
+//! datetimes are seconds since the Unix epoch, so they can be compared
+//! and shifted with `days(n)`, `hours(n)` and `minutes(n)`.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Maximum number of Rhai operations an expression may run
+const MAX_OPERATIONS: u64 = 10_000;
+
+fn engine() -> &'static rhai::Engine {
+    static ENGINE: OnceLock<rhai::Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine
+            .register_fn("days", |n: i64| n * 86_400)
+            .register_fn("hours", |n: i64| n * 3_600)
+            .register_fn("minutes", |n: i64| n * 60)
+            .register_fn("now", || Utc::now().timestamp())
+            .register_fn("today", || {
+                Utc::now()
+                    .date_naive()
+                    .and_hms_opt(0, 0, 0)
+                    .map_or(0, |midnight| midnight.and_utc().timestamp())
+            });
+        engine
+    })
+}
+
+/// Compiled expressions, keyed by their source
+fn compiled() -> &'static Mutex<HashMap<String, Arc<rhai::AST>>> {
+    static COMPILED: OnceLock<Mutex<HashMap<String, Arc<rhai::AST>>>> = OnceLock::new();
+    COMPILED.get_or_init(Default::default)
+}
+
+/// Compile an expression, reusing earlier compilations
+pub fn compile(expr: &str) -> Result<Arc<rhai::AST>> {
+    if let Some(ast) = compiled().lock().unwrap().get(expr) {
+        return Ok(ast.clone());
+    }
+    let ast = Arc::new(
+        engine()
+            .compile_expression(expr)
+            .map_err(|e| anyhow!("Invalid expression '{}': {}", expr, e))?,
+    );
+    compiled()
+        .lock()
+        .unwrap()
+        .insert(expr.to_string(), ast.clone());
+    Ok(ast)
+}
+
+/// Seconds since the epoch of a date (`2024-05-01`) or datetime (RFC 3339,
+/// or without an offset for UTC)
+fn timestamp(text: &str) -> Option<i64> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.timestamp());
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(datetime.and_utc().timestamp());
+        }
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc().timestamp())
+}
+
+fn to_dynamic(value: &Value) -> Result<rhai::Dynamic> {
+    match value {
+        Value::String(text) => Ok(match timestamp(text) {
+            Some(seconds) => seconds.into(),
+            None => text.clone().into(),
+        }),
+        value => Ok(rhai::serde::to_dynamic(value)?),
+    }
+}
+
+/// Evaluate an expression over a payload; `value` is the value of the
+/// field the rule is on
+pub fn evaluate(expr: &str, value: &Value, data: &HashMap<String, Value>) -> Result<bool> {
+    let ast = compile(expr)?;
+    let mut scope = rhai::Scope::new();
+    for (id, field_value) in data {
+        scope.push_dynamic(id.as_str(), to_dynamic(field_value)?);
+    }
+    scope.push_dynamic("value", to_dynamic(value)?);
+    scope.push_dynamic("data", rhai::serde::to_dynamic(data)?);
+
+    engine()
+        .eval_ast_with_scope::<bool>(&mut scope, &ast)
+        .map_err(|e| anyhow!("Expression '{}' failed: {}", expr, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn data(pairs: Value) -> HashMap<String, Value> {
+        serde_json::from_value(pairs).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let order = data(json!({"price": 100, "discount": 30.5}));
+        assert!(evaluate("discount <= price * 0.5", &json!(30.5), &order).unwrap());
+        let order = data(json!({"price": 100, "discount": 60}));
+        assert!(!evaluate("discount <= price * 0.5", &json!(60), &order).unwrap());
+        assert!(evaluate("value == data.discount", &json!(60), &order).unwrap());
+
+        let booking = data(json!({"start_date": "2024-05-01", "end_date": "2024-05-03"}));
+        assert!(evaluate("end_date > start_date + days(1)", &json!(null), &booking).unwrap());
+        let booking = data(json!({
+            "start_date": "2024-05-01T22:00:00Z",
+            "end_date": "2024-05-02T08:00:00+02:00"
+        }));
+        assert!(!evaluate("end_date > start_date + days(1)", &json!(null), &booking).unwrap());
+        assert!(evaluate("end_date - start_date == hours(8)", &json!(null), &booking).unwrap());
+
+        // Missing fields and non-boolean results are errors
+        assert!(evaluate("total > 0", &json!(null), &order).is_err());
+        assert!(evaluate("price * 2", &json!(null), &order).is_err());
+        assert!(compile("let x = 1; x").is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! Feature flags
+//!
+//! Sections and actions with `enabled_when: <flag>` are only served while
+//! the flag is on: otherwise they are left out of the backoffice listings,
+//! GraphQL schemas and gRPC metadata, and their routes answer 404. Flags
+//! are static booleans, environment variables read at startup, or Unleash
+//! toggles refreshed every `refresh_interval_secs`. Flags that aren't
+//! declared are off.
+
+use crate::config::{BackofficeConfig, FeatureFlag, FeatureFlagsConfig, UnleashConfig};
+use crate::server::AppState;
+use anyhow::Result;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+/// Current values of the configured feature flags
+#[derive(Debug, Default)]
+pub struct FeatureFlags {
+    /// Static and environment flags
+    fixed: HashMap<String, bool>,
+    /// Unleash flags, by flag name: toggle name and current value
+    toggles: RwLock<HashMap<String, (String, bool)>>,
+    unleash: Option<UnleashConfig>,
+}
+
+impl FeatureFlags {
+    pub fn new(config: &FeatureFlagsConfig) -> Self {
+        let mut fixed = HashMap::new();
+        let mut toggles = HashMap::new();
+        for (name, flag) in &config.flags {
+            match flag {
+                FeatureFlag::Static(value) => {
+                    fixed.insert(name.clone(), *value);
+                }
+                FeatureFlag::Env { env, default } => {
+                    let value = std::env::var(env)
+                        .ok()
+                        .and_then(|value| parse_bool(&value))
+                        .unwrap_or(*default);
+                    fixed.insert(name.clone(), value);
+                }
+                FeatureFlag::Unleash { unleash, default } => {
+                    toggles.insert(name.clone(), (unleash.clone(), *default));
+                }
+            }
+        }
+        Self {
+            fixed,
+            toggles: RwLock::new(toggles),
+            unleash: config.unleash.clone(),
+        }
+    }
+
+    /// Whether a flag is declared
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.fixed.contains_key(name) || self.toggles.read().unwrap().contains_key(name)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        if let Some(value) = self.fixed.get(name) {
+            return *value;
+        }
+        self.toggles
+            .read()
+            .unwrap()
+            .get(name)
+            .is_some_and(|(_, value)| *value)
+    }
+
+    /// Whether an item with `enabled_when` is served
+    pub fn allows(&self, enabled_when: Option<&str>) -> bool {
+        enabled_when.is_none_or(|flag| self.is_enabled(flag))
+    }
+
+    /// A backoffice without its disabled sections and actions
+    pub fn backoffice(&self, backoffice: &BackofficeConfig) -> BackofficeConfig {
+        let mut backoffice = backoffice.clone();
+        backoffice
+            .sections
+            .retain(|section| self.allows(section.enabled_when.as_deref()));
+        for section in &mut backoffice.sections {
+            section
+                .actions
+                .retain(|action| self.allows(action.enabled_when.as_deref()));
+        }
+        backoffice
+    }
+
+    /// The not found error of a disabled section, or of a disabled action
+    /// when `action_id` is given
+    pub fn disabled(
+        &self,
+        backoffice: &BackofficeConfig,
+        section_id: &str,
+        action_id: Option<&str>,
+    ) -> Option<&'static str> {
+        let section = backoffice.sections.iter().find(|s| s.id == section_id)?;
+        if !self.allows(section.enabled_when.as_deref()) {
+            return Some("Section not found");
+        }
+        let action = section
+            .actions
+            .iter()
+            .find(|a| Some(a.id.as_str()) == action_id)?;
+        (!self.allows(action.enabled_when.as_deref())).then_some("Action not found")
+    }
+
+    /// Whether some flags are Unleash toggles
+    pub fn polls_unleash(&self) -> bool {
+        self.unleash.is_some() && !self.toggles.read().unwrap().is_empty()
+    }
+
+    /// Fetch the Unleash toggles of the flags
+    pub async fn refresh(&self, client: &reqwest::Client) -> Result<()> {
+        let Some(unleash) = &self.unleash else {
+            return Ok(());
+        };
+        let mut request = client
+            .get(format!(
+                "{}/client/features",
+                unleash.url.trim_end_matches('/')
+            ))
+            .header("UNLEASH-APPNAME", &unleash.app_name);
+        if let Some(token) = &unleash.token {
+            request = request.header("Authorization", token);
+        }
+        let response: Value = request.send().await?.error_for_status()?.json().await?;
+        let enabled: HashMap<&str, bool> = response["features"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|feature| {
+                Some((
+                    feature["name"].as_str()?,
+                    feature["enabled"].as_bool().unwrap_or(false),
+                ))
+            })
+            .collect();
+
+        let mut toggles = self.toggles.write().unwrap();
+        for (name, (toggle, value)) in toggles.iter_mut() {
+            // Toggles missing from Unleash are off
+            let current = enabled.get(toggle.as_str()).copied().unwrap_or(false);
+            if current != *value {
+                debug!(flag = %name, toggle = %toggle, enabled = current, "Feature flag changed");
+            }
+            *value = current;
+        }
+        Ok(())
+    }
+}
+
+/// `true`, `1`, `yes` and `on` or `false`, `0`, `no` and `off`
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Section and action ids of a section route
+fn section_route(path: &str) -> Option<(&str, &str, Option<&str>)> {
+    let segments: Vec<&str> = path.strip_prefix("/api/backoffices/")?.split('/').collect();
+    match segments[..] {
+        [backoffice, "sections", section, "actions", action, ..] => {
+            Some((backoffice, section, Some(action)))
+        }
+        [backoffice, "sections", section, ..] => Some((backoffice, section, None)),
+        _ => None,
+    }
+}
+
+/// Answer the routes of disabled sections and actions with 404
+pub async fn gate(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let disabled = section_route(request.uri().path()).and_then(|(backoffice, section, action)| {
+        let backoffice = state.backoffice(backoffice)?;
+        state.feature_flags.disabled(backoffice, section, action)
+    });
+    match disabled {
+        Some(error) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": error })),
+        )
+            .into_response(),
+        None => next.run(request).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_flags() {
+        std::env::set_var("PMP_TEST_FLAG_BETA", "on");
+        let config: FeatureFlagsConfig = serde_yaml::from_str(
+            r#"
+flags:
+  reports: true
+  legacy: false
+  beta: { env: PMP_TEST_FLAG_BETA }
+  unset: { env: PMP_TEST_FLAG_UNSET, default: true }
+  checkout: { unleash: checkout-v2 }
+  exports: { unleash: exports, default: true }
+"#,
+        )
+        .unwrap();
+        let flags = FeatureFlags::new(&config);
+        assert!(flags.is_enabled("reports"));
+        assert!(!flags.is_enabled("legacy"));
+        assert!(flags.is_enabled("beta"));
+        assert!(flags.is_enabled("unset"));
+        assert!(!flags.is_enabled("checkout"));
+        assert!(flags.is_enabled("exports"));
+        assert!(!flags.is_enabled("undeclared"));
+        assert!(!flags.is_declared("undeclared"));
+        assert!(flags.allows(None));
+
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: shop
+name: Shop
+data_sources: {}
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - { id: list, name: List, type: custom, fields: [], data_source: db, required_scopes: [] }
+      - { id: export, name: Export, type: custom, fields: [], data_source: db, required_scopes: [], enabled_when: legacy }
+  - id: reports
+    name: Reports
+    enabled_when: checkout
+    actions: []
+"#,
+        )
+        .unwrap();
+        let visible = flags.backoffice(&backoffice);
+        assert_eq!(visible.sections.len(), 1);
+        assert_eq!(visible.sections[0].actions.len(), 1);
+        assert_eq!(
+            flags.disabled(&backoffice, "reports", None),
+            Some("Section not found")
+        );
+        assert_eq!(
+            flags.disabled(&backoffice, "orders", Some("export")),
+            Some("Action not found")
+        );
+        assert_eq!(flags.disabled(&backoffice, "orders", Some("list")), None);
+        assert_eq!(flags.disabled(&backoffice, "orders", None), None);
+
+        assert_eq!(
+            section_route("/api/backoffices/shop/sections/orders/actions/list/stream"),
+            Some(("shop", "orders", Some("list")))
+        );
+        assert_eq!(
+            section_route("/api/backoffices/shop/sections/orders/records/1/history"),
+            Some(("shop", "orders", None))
+        );
+        assert_eq!(section_route("/api/backoffices/shop"), None);
+
+        let app = axum::Router::new().route(
+            "/api/client/features",
+            axum::routing::get(|| async {
+                Json(serde_json::json!({"features": [
+                    {"name": "checkout-v2", "enabled": true},
+                    {"name": "unrelated", "enabled": true},
+                ]}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let flags = FeatureFlags::new(&FeatureFlagsConfig {
+            unleash: Some(UnleashConfig {
+                url,
+                token: Some("client-token".to_string()),
+                app_name: "test".to_string(),
+                refresh_interval_secs: 15,
+            }),
+            ..config
+        });
+        assert!(flags.polls_unleash());
+        flags.refresh(&reqwest::Client::new()).await.unwrap();
+        assert!(flags.is_enabled("checkout"));
+        // Toggles missing from Unleash are off
+        assert!(!flags.is_enabled("exports"));
+        assert_eq!(flags.disabled(&backoffice, "reports", None), None);
+    }
+}
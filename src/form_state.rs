@@ -0,0 +1,123 @@
+//! Conditional form state
+//!
+//! Fields can be shown with `visible_when` and made mandatory with
+//! `required_when`, conditions on the values of other fields. The server
+//! evaluates them the same way for validation and for the form's evaluate
+//! endpoint, so conditional forms behave the same in the browser and in
+//! the API: hidden fields aren't validated, and fields required by a
+//! condition must be filled in like `required` ones.
+
+use crate::auth::AuthContext;
+use crate::config::FieldConfig;
+use crate::permissions;
+use crate::validation::evaluate_condition;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// State of a field for the values entered so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FieldState {
+    pub visible: bool,
+    pub required: bool,
+    pub disabled: bool,
+}
+
+/// Whether a field is shown
+pub fn visible(field: &FieldConfig, data: &HashMap<String, Value>) -> bool {
+    field.visible
+        && field
+            .visible_when
+            .as_ref()
+            .is_none_or(|condition| evaluate_condition(data, condition))
+}
+
+/// Whether a field must be filled in; hidden fields never need to be
+pub fn required(field: &FieldConfig, data: &HashMap<String, Value>) -> bool {
+    visible(field, data)
+        && (field.required
+            || field
+                .required_when
+                .as_ref()
+                .is_some_and(|condition| evaluate_condition(data, condition)))
+}
+
+/// State of every field of a form, keyed by field id
+///
+/// Fields the caller may not read are left out, and those they may not
+/// write are disabled.
+pub fn evaluate(
+    fields: &[FieldConfig],
+    data: &HashMap<String, Value>,
+    auth: &AuthContext,
+) -> BTreeMap<String, FieldState> {
+    fields
+        .iter()
+        .filter(|field| permissions::can_read(field, auth))
+        .map(|field| {
+            let state = FieldState {
+                visible: visible(field, data),
+                required: required(field, data),
+                disabled: !field.editable || !permissions::can_write(field, auth),
+            };
+            (field.id.clone(), state)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_evaluate() {
+        let fields: Vec<FieldConfig> = serde_yaml::from_str(
+            r#"
+- id: kind
+  name: Kind
+  field_type: text
+- id: company
+  name: Company
+  field_type: text
+  visible_when: { field: kind, operator: equals, value: business }
+  required_when: { field: kind, operator: equals, value: business }
+- id: vat
+  name: VAT number
+  field_type: text
+  required: true
+  visible_when: { field: kind, operator: equals, value: business }
+- id: notes
+  name: Notes
+  field_type: text
+  editable: false
+- id: salary
+  name: Salary
+  field_type: number
+  required_scopes: [hr]
+"#,
+        )
+        .unwrap();
+        let auth = AuthContext::from_claims(json!({"scope": "staff"}));
+
+        let personal = HashMap::from([("kind".to_string(), json!("personal"))]);
+        let form = evaluate(&fields, &personal, &auth);
+        assert_eq!(form.len(), 4);
+        assert!(!form["company"].visible && !form["company"].required);
+        // Hidden fields aren't required even when marked so
+        assert!(!form["vat"].required);
+        assert!(form["notes"].visible && form["notes"].disabled);
+
+        let business = HashMap::from([("kind".to_string(), json!("business"))]);
+        let form = evaluate(&fields, &business, &auth);
+        assert_eq!(
+            form["company"],
+            FieldState {
+                visible: true,
+                required: true,
+                disabled: false
+            }
+        );
+        assert!(form["vat"].required);
+    }
+}
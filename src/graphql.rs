@@ -0,0 +1,1136 @@
+//! GraphQL API
+//!
+//! Every backoffice gets a GraphQL schema generated from its sections,
+//! served at `POST /api/graphql/:backoffice_id` with GraphiQL on `GET`.
+//! List, view and custom actions become query fields returning a type per
+//! section, summaries and reports return JSON, and form and pipeline
+//! actions become mutation fields taking an input of their fields. The
+//! relationships of a section are fields of its type.
+//!
+//! Resolvers run the code of the REST endpoints, so scopes, hooks,
+//! validation, row filters, field permissions, masking and auditing all
+//! apply, and failed calls carry the REST response body (such as its
+//! `validation_errors`) in their error `extensions`.
+//!
+//! A relationship is loaded once for all the records a field returned, and
+//! queries are limited in depth, size and relationship nesting.
+
+use crate::audit::RequestMetadata;
+use crate::auth::AuthContext;
+use crate::config::{
+    ActionType, BackofficeConfig, FieldConfig, FieldType, RelationshipType, SectionConfig,
+};
+use crate::feature_flags::FeatureFlags;
+use crate::relationships::lookup_key;
+use crate::server::{self, AppState};
+use async_graphql::dynamic::{
+    Field, FieldFuture, FieldValue, InputObject, InputValue, Object, ResolverContext, Scalar,
+    Schema, SchemaError, TypeRef,
+};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Error, ErrorExtensions, Value as GraphQLValue};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Json, Response},
+};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use tracing::{debug, error};
+
+/// Scalar of values without a more specific GraphQL type
+const JSON: &str = "JSON";
+
+/// Result of every mutation field
+const MUTATION_RESULT: &str = "MutationResult";
+
+/// Deepest selection a query may make, leaving room for the introspection
+/// query of GraphiQL
+const MAX_DEPTH: usize = 16;
+
+/// Most fields a query may select
+const MAX_COMPLEXITY: usize = 1000;
+
+/// Relationships a query may follow from the records of a query field, so
+/// cyclic relationships can't be nested up to `MAX_DEPTH`
+const MAX_RELATIONSHIP_DEPTH: usize = 3;
+
+/// Who a GraphQL request is executed for, available to every resolver
+pub struct Caller {
+    pub state: Arc<AppState>,
+    pub auth: AuthContext,
+    pub request: RequestMetadata,
+}
+
+/// A page of records of a list action
+struct Page {
+    records: Vec<Record>,
+    pagination: Value,
+}
+
+/// A record returned by a field, with the records returned along with it
+struct Record {
+    row: Value,
+    batch: Arc<Batch>,
+}
+
+/// Records returned by one field, whose relationships are loaded together
+struct Batch {
+    ids: Vec<Value>,
+    /// Relationships followed to reach the records
+    depth: usize,
+    /// Loaded relationships by id
+    related: Mutex<HashMap<String, Arc<OnceCell<Related>>>>,
+}
+
+/// A relationship loaded for the records of a batch
+struct Related {
+    /// Related records by the key of the record id
+    records: HashMap<String, Vec<Value>>,
+    page_size: usize,
+    /// All the related records, whose own relationships are loaded together
+    batch: Arc<Batch>,
+}
+
+impl Batch {
+    fn new<'a>(rows: impl IntoIterator<Item = &'a Value>, depth: usize) -> Arc<Self> {
+        let mut seen = HashSet::new();
+        let ids = rows
+            .into_iter()
+            .filter_map(|row| row.get("id"))
+            .filter(|id| lookup_key(id).is_some_and(|key| seen.insert(key)))
+            .cloned()
+            .collect();
+        Arc::new(Batch {
+            ids,
+            depth,
+            related: Mutex::default(),
+        })
+    }
+
+    /// Records of rows returned by a query field
+    fn records(rows: Vec<Value>) -> Vec<Record> {
+        let batch = Self::new(&rows, 0);
+        rows.into_iter()
+            .map(|row| Record {
+                row,
+                batch: batch.clone(),
+            })
+            .collect()
+    }
+
+    /// The relationship of the batch's records, loaded by whichever record
+    /// asks first
+    fn related(&self, relationship_id: &str) -> Arc<OnceCell<Related>> {
+        let mut related = self.related.lock().unwrap_or_else(|e| e.into_inner());
+        related
+            .entry(relationship_id.to_string())
+            .or_default()
+            .clone()
+    }
+}
+
+/// A GraphQL name for an id: letters, digits and underscores, not starting
+/// with a digit
+fn field_name(id: &str) -> String {
+    let name: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+/// A GraphQL type name for an id, e.g. `OrderItems` for `order_items`
+fn type_name(id: &str) -> String {
+    let name: String = id
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    field_name(&name)
+}
+
+/// GraphQL type of a field's values
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Float,
+    Boolean,
+    String,
+    Json,
+}
+
+impl Kind {
+    fn of(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Number { .. }
+            | FieldType::Currency { .. }
+            | FieldType::Range { .. }
+            | FieldType::Rating { .. }
+            | FieldType::Percentage { .. }
+            | FieldType::Slider { .. } => Kind::Float,
+            FieldType::Boolean { .. } => Kind::Boolean,
+            FieldType::Json { .. }
+            | FieldType::Tags { .. }
+            | FieldType::MultiCheckbox { .. }
+            | FieldType::Geolocation { .. }
+            | FieldType::DateTimeRange { .. }
+            | FieldType::ColorPalette { .. }
+            | FieldType::File { .. }
+            | FieldType::Image { .. }
+            | FieldType::Video { .. }
+            | FieldType::Audio { .. } => Kind::Json,
+            _ => Kind::String,
+        }
+    }
+
+    fn type_name(self) -> &'static str {
+        match self {
+            Kind::Float => TypeRef::FLOAT,
+            Kind::Boolean => TypeRef::BOOLEAN,
+            Kind::String => TypeRef::STRING,
+            Kind::Json => JSON,
+        }
+    }
+
+    /// A stored value as this type; values that can't be converted, such as
+    /// text in a number column, are null
+    fn output(self, value: &Value) -> Option<GraphQLValue> {
+        match (self, value) {
+            (_, Value::Null) => None,
+            (Kind::Float, Value::Number(n)) => Some(GraphQLValue::Number(n.clone())),
+            (Kind::Float, Value::String(s)) => s.trim().parse::<f64>().ok().map(GraphQLValue::from),
+            (Kind::Boolean, Value::Bool(b)) => Some(GraphQLValue::Boolean(*b)),
+            // SQLite and MySQL store booleans as integers
+            (Kind::Boolean, Value::Number(n)) => n.as_i64().map(|n| GraphQLValue::Boolean(n != 0)),
+            (Kind::Boolean, Value::String(s)) => match s.as_str() {
+                "true" | "1" => Some(GraphQLValue::Boolean(true)),
+                "false" | "0" => Some(GraphQLValue::Boolean(false)),
+                _ => None,
+            },
+            (Kind::String, Value::String(s)) => Some(GraphQLValue::String(s.clone())),
+            (Kind::String, value) => Some(GraphQLValue::String(value.to_string())),
+            (Kind::Json, value) => GraphQLValue::from_json(value.clone()).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Fields of the records a section's queries return: those of its list,
+/// view and custom actions
+fn record_fields(section: &SectionConfig) -> Vec<&FieldConfig> {
+    let mut seen = HashSet::new();
+    section
+        .actions
+        .iter()
+        .filter(|a| {
+            matches!(
+                a.action_type,
+                ActionType::List { .. } | ActionType::View { .. } | ActionType::Custom { .. }
+            )
+        })
+        .flat_map(|a| a.action_type.fields())
+        .filter(|f| seen.insert(f.id.as_str()))
+        .collect()
+}
+
+/// The JSON body of a REST response, or an error with the body in its
+/// extensions
+async fn json_body(response: Response) -> async_graphql::Result<Value> {
+    let (status, body) = server::response_json(response).await;
+    if status.is_success() {
+        return Ok(body);
+    }
+
+    let message = body
+        .get("error")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| status.to_string());
+    Err(Error::new(message).extend_with(|_, extensions| {
+        extensions.set("status", status.as_u16());
+        if let Value::Object(body) = &body {
+            for (key, value) in body.iter().filter(|(key, _)| *key != "error") {
+                if let Ok(value) = GraphQLValue::from_json(value.clone()) {
+                    extensions.set(key, value);
+                }
+            }
+        }
+    }))
+}
+
+fn json_value(value: Value) -> async_graphql::Result<FieldValue<'static>> {
+    Ok(FieldValue::value(GraphQLValue::from_json(value)?))
+}
+
+/// Query parameters of a query field: its arguments, with `params` spread
+fn query_params(ctx: &ResolverContext<'_>) -> async_graphql::Result<HashMap<String, String>> {
+    let mut params = HashMap::new();
+    if let Some(extra) = ctx.args.get("params") {
+        if let Value::Object(extra) = extra.as_value().clone().into_json()? {
+            for (name, value) in extra {
+                let value = match value {
+                    Value::String(s) => s,
+                    value => value.to_string(),
+                };
+                params.insert(name, value);
+            }
+        }
+    }
+    for (name, value) in ctx.args.iter() {
+        let value = match value.as_value() {
+            GraphQLValue::String(s) => s.clone(),
+            GraphQLValue::Number(n) => n.to_string(),
+            _ => continue,
+        };
+        let name = match name.as_str() {
+            "search" => crate::search::SEARCH_PARAM,
+            name => name,
+        };
+        params.insert(name.to_string(), value);
+    }
+    Ok(params)
+}
+
+fn params_argument() -> InputValue {
+    InputValue::new("params", TypeRef::named(JSON))
+        .description("Further query parameters, such as filters")
+}
+
+/// Ids of an action, as the REST handlers take them
+type ActionIds = (String, String, String);
+
+fn list_field(name: String, ids: ActionIds, record_type: Option<&String>) -> Field {
+    let typed = record_type.is_some();
+    let ty = match record_type {
+        Some(record_type) => TypeRef::named_nn(format!("{}Page", record_type)),
+        None => TypeRef::named(JSON),
+    };
+    Field::new(name, ty, move |ctx| {
+        let ids = ids.clone();
+        FieldFuture::new(async move {
+            let caller = ctx.data::<Caller>()?;
+            let params = query_params(&ctx)?;
+            let response =
+                server::run_query(caller.state.clone(), ids, caller.auth.clone(), params).await;
+            let mut body = json_body(response).await?;
+            if !typed {
+                return json_value(body["data"].take()).map(Some);
+            }
+            let rows = match body["data"].take() {
+                Value::Array(rows) => rows,
+                _ => Vec::new(),
+            };
+            let pagination = match body["pagination"].take() {
+                Value::Null => serde_json::json!({
+                    "page": 1,
+                    "page_size": rows.len(),
+                    "total_items": rows.len(),
+                    "total_pages": 1,
+                }),
+                pagination => pagination,
+            };
+            Ok(Some(FieldValue::owned_any(Page {
+                records: Batch::records(rows),
+                pagination,
+            })))
+        })
+    })
+    .argument(InputValue::new("page", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("page_size", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("search", TypeRef::named(TypeRef::STRING)))
+    .argument(InputValue::new("sort_by", TypeRef::named(TypeRef::STRING)))
+    .argument(InputValue::new(
+        "sort_order",
+        TypeRef::named(TypeRef::STRING),
+    ))
+    .argument(params_argument())
+}
+
+/// View, custom, summary and report queries, returning their rows
+fn rows_field(name: String, ids: ActionIds, record_type: Option<&String>, single: bool) -> Field {
+    let typed = record_type.is_some();
+    let ty = match (record_type, single) {
+        (Some(record_type), true) => TypeRef::named(record_type),
+        (Some(record_type), false) => TypeRef::named_nn_list_nn(record_type),
+        (None, _) => TypeRef::named(JSON),
+    };
+    let field = Field::new(name, ty, move |ctx| {
+        let ids = ids.clone();
+        FieldFuture::new(async move {
+            let caller = ctx.data::<Caller>()?;
+            let params = query_params(&ctx)?;
+            let response =
+                server::run_query(caller.state.clone(), ids, caller.auth.clone(), params).await;
+            let data = json_body(response).await?["data"].take();
+            if !typed {
+                return json_value(data).map(Some);
+            }
+            let records = match data {
+                Value::Array(rows) => Batch::records(rows),
+                _ => Vec::new(),
+            };
+            if single {
+                return Ok(records.into_iter().next().map(FieldValue::owned_any));
+            }
+            Ok(Some(FieldValue::list(
+                records.into_iter().map(FieldValue::owned_any),
+            )))
+        })
+    })
+    .argument(params_argument());
+    if single {
+        field.argument(InputValue::new("id", TypeRef::named_nn(TypeRef::ID)))
+    } else {
+        field
+    }
+}
+
+/// The object type of a section's records, with its relationships
+fn record_type(
+    backoffice: &BackofficeConfig,
+    section: &SectionConfig,
+    name: &str,
+    record_types: &HashMap<&str, String>,
+) -> Object {
+    let mut object = Object::new(name);
+    let mut taken = HashSet::new();
+    for field in record_fields(section) {
+        let kind = Kind::of(&field.field_type);
+        let id = field.id.clone();
+        taken.insert(field_name(&field.id));
+        object = object.field(
+            Field::new(
+                field_name(&field.id),
+                TypeRef::named(kind.type_name()),
+                move |ctx| {
+                    let value = ctx
+                        .parent_value
+                        .downcast_ref::<Record>()
+                        .and_then(|record| record.row.get(&id))
+                        .and_then(|value| kind.output(value));
+                    FieldFuture::from_value(value)
+                },
+            )
+            .description(field.name.clone()),
+        );
+    }
+
+    for relationship in backoffice
+        .relationships
+        .iter()
+        .filter(|r| r.from_section == section.id)
+    {
+        let name = field_name(&relationship.id);
+        if !taken.insert(name.clone()) {
+            debug!(relationship = %relationship.id, "GraphQL relationship field shadowed by a data field");
+            continue;
+        }
+        let single = matches!(
+            relationship.relationship_type,
+            RelationshipType::ManyToOne | RelationshipType::OneToOne
+        );
+        let target = record_types.get(relationship.to_section.as_str());
+        let ty = match (target, single) {
+            (Some(target), true) => TypeRef::named(target),
+            (Some(target), false) => TypeRef::named_nn_list(target),
+            (None, _) => TypeRef::named(JSON),
+        };
+        let typed = target.is_some();
+        let ids = (
+            backoffice.id.clone(),
+            section.id.clone(),
+            relationship.id.clone(),
+        );
+        object = object.field(
+            Field::new(name, ty, move |ctx| {
+                let ids = ids.clone();
+                FieldFuture::new(async move {
+                    let record = ctx.parent_value.try_downcast_ref::<Record>()?;
+                    let Some(key) = record.row.get("id").and_then(lookup_key) else {
+                        return Ok(None);
+                    };
+                    if record.batch.depth >= MAX_RELATIONSHIP_DEPTH {
+                        return Err(Error::new(format!(
+                            "Relationships can be nested at most {} levels deep",
+                            MAX_RELATIONSHIP_DEPTH
+                        )));
+                    }
+                    let caller = ctx.data::<Caller>()?;
+                    let batch = &record.batch;
+                    let cell = batch.related(&ids.2);
+                    let related = cell
+                        .get_or_try_init(|| async {
+                            let loaded = server::run_related_batch(
+                                &caller.state,
+                                (&ids.0, &ids.1, &ids.2),
+                                &batch.ids,
+                                &caller.auth,
+                            )
+                            .await;
+                            match loaded {
+                                Ok(loaded) => Ok(Related {
+                                    batch: Batch::new(
+                                        loaded.records.values().flatten(),
+                                        batch.depth + 1,
+                                    ),
+                                    records: loaded.records,
+                                    page_size: loaded.page_size,
+                                }),
+                                Err(response) => Err(json_body(response)
+                                    .await
+                                    .err()
+                                    .unwrap_or_else(|| Error::new("Related records not loaded"))),
+                            }
+                        })
+                        .await?;
+
+                    let argument = |name: &str| {
+                        ctx.args
+                            .get(name)
+                            .and_then(|v| v.u64().ok())
+                            .map(|n| n as usize)
+                    };
+                    let page = argument("page").unwrap_or(1).max(1);
+                    let page_size = argument("page_size")
+                        .unwrap_or(related.page_size)
+                        .clamp(1, crate::data_source::MAX_PAGE_SIZE);
+                    let rows = related
+                        .records
+                        .get(&key)
+                        .into_iter()
+                        .flatten()
+                        .skip((page - 1).saturating_mul(page_size))
+                        .take(page_size)
+                        .cloned();
+                    if !typed {
+                        let rows: Vec<Value> = rows.collect();
+                        return match single {
+                            true => rows.into_iter().next().map(json_value).transpose(),
+                            false => json_value(Value::Array(rows)).map(Some),
+                        };
+                    }
+                    let mut records = rows.map(|row| {
+                        FieldValue::owned_any(Record {
+                            row,
+                            batch: related.batch.clone(),
+                        })
+                    });
+                    match single {
+                        true => Ok(records.next()),
+                        false => Ok(Some(FieldValue::list(records))),
+                    }
+                })
+            })
+            .description(relationship.name.clone())
+            .argument(InputValue::new("page", TypeRef::named(TypeRef::INT)))
+            .argument(InputValue::new("page_size", TypeRef::named(TypeRef::INT))),
+        );
+    }
+    object
+}
+
+/// The page type of a section's list queries
+fn page_type(record_type: &str) -> Object {
+    let pagination = |name: &'static str| {
+        Field::new(name, TypeRef::named(TypeRef::INT), move |ctx| {
+            let value = ctx
+                .parent_value
+                .downcast_ref::<Page>()
+                .and_then(|page| page.pagination.get(name))
+                .and_then(Value::as_i64)
+                .map(GraphQLValue::from);
+            FieldFuture::from_value(value)
+        })
+    };
+    Object::new(format!("{}Page", record_type))
+        .field(Field::new(
+            "items",
+            TypeRef::named_nn_list_nn(record_type),
+            |ctx| {
+                FieldFuture::new(async move {
+                    let page = ctx.parent_value.try_downcast_ref::<Page>()?;
+                    Ok(Some(FieldValue::list(
+                        page.records
+                            .iter()
+                            .map(|record| FieldValue::borrowed_any(record)),
+                    )))
+                })
+            },
+        ))
+        .field(pagination("page"))
+        .field(pagination("page_size"))
+        .field(pagination("total_items"))
+        .field(pagination("total_pages"))
+}
+
+/// The input type of a form or pipeline, and the field ids of its GraphQL
+/// field names
+fn input_type(name: &str, fields: &[FieldConfig]) -> (InputObject, HashMap<String, String>) {
+    let mut input = InputObject::new(name);
+    let mut ids = HashMap::new();
+    for field in fields {
+        let kind = Kind::of(&field.field_type);
+        let field_name = field_name(&field.id);
+        input = input.field(
+            InputValue::new(&field_name, TypeRef::named(kind.type_name()))
+                .description(field.name.clone()),
+        );
+        ids.insert(field_name, field.id.clone());
+    }
+    // Updates name the record they change
+    if !ids.contains_key("id") {
+        input = input.field(InputValue::new("id", TypeRef::named(TypeRef::ID)));
+        ids.insert("id".to_string(), "id".to_string());
+    }
+    (input, ids)
+}
+
+fn mutation_field(
+    name: String,
+    ids: ActionIds,
+    input: &str,
+    fields: HashMap<String, String>,
+) -> Field {
+    let fields = Arc::new(fields);
+    Field::new(name, TypeRef::named_nn(MUTATION_RESULT), move |ctx| {
+        let ids = ids.clone();
+        let fields = fields.clone();
+        FieldFuture::new(async move {
+            let caller = ctx.data::<Caller>()?;
+            let mut data = HashMap::new();
+            if let Value::Object(input) =
+                ctx.args.try_get("input")?.as_value().clone().into_json()?
+            {
+                for (name, value) in input {
+                    let id = fields.get(&name).cloned().unwrap_or(name);
+                    data.insert(id, value);
+                }
+            }
+            let accept_warnings = match ctx.args.get("accept_warnings") {
+                Some(accept) => accept.boolean()?,
+                None => false,
+            };
+            let response = server::run_mutation(
+                caller.state.clone(),
+                ids,
+                caller.auth.clone(),
+                caller.request.clone(),
+                data,
+                accept_warnings,
+            )
+            .await;
+            Ok(Some(FieldValue::owned_any(json_body(response).await?)))
+        })
+    })
+    .argument(InputValue::new("input", TypeRef::named_nn(input)))
+    .argument(
+        InputValue::new("accept_warnings", TypeRef::named(TypeRef::BOOLEAN))
+            .description("Save even though rules with `severity: warning` failed"),
+    )
+}
+
+fn delete_field(name: String, ids: ActionIds) -> Field {
+    Field::new(name, TypeRef::named_nn(MUTATION_RESULT), move |ctx| {
+        let ids = ids.clone();
+        FieldFuture::new(async move {
+            let caller = ctx.data::<Caller>()?;
+            let record_id = ctx.args.try_get("id")?.string()?.to_string();
+            let response = server::run_delete(
+                caller.state.clone(),
+                ids,
+                caller.auth.clone(),
+                caller.request.clone(),
+                record_id,
+            )
+            .await;
+            Ok(Some(FieldValue::owned_any(json_body(response).await?)))
+        })
+    })
+    .argument(InputValue::new("id", TypeRef::named_nn(TypeRef::ID)))
+}
+
+/// The outcome of a mutation, read from the REST response body
+fn mutation_result_type() -> Object {
+    let body_field = |name: &'static str, ty: &'static str| {
+        Field::new(name, TypeRef::named(ty), move |ctx| {
+            let value = ctx
+                .parent_value
+                .downcast_ref::<Value>()
+                .and_then(|body| body.get(name))
+                .filter(|value| !value.is_null())
+                .and_then(|value| GraphQLValue::from_json(value.clone()).ok());
+            FieldFuture::from_value(value)
+        })
+    };
+    Object::new(MUTATION_RESULT)
+        .field(body_field("success", TypeRef::BOOLEAN))
+        .field(body_field("data", JSON))
+        .field(body_field("message", TypeRef::STRING))
+        .field(body_field("warnings", JSON))
+}
+
+/// The GraphQL schema of a backoffice
+pub fn schema(backoffice: &BackofficeConfig) -> Result<Schema, SchemaError> {
+    let record_types: HashMap<&str, String> = backoffice
+        .sections
+        .iter()
+        .filter(|s| !record_fields(s).is_empty())
+        .map(|s| (s.id.as_str(), type_name(&s.id)))
+        .collect();
+
+    let mut query = Object::new("Query");
+    let mut mutation = Object::new("Mutation");
+    let mut has_query = false;
+    let mut has_mutation = false;
+    let mut objects = vec![mutation_result_type()];
+    let mut inputs = Vec::new();
+
+    for section in &backoffice.sections {
+        let record_type_name = record_types.get(section.id.as_str());
+        if let Some(name) = record_type_name {
+            objects.push(record_type(backoffice, section, name, &record_types));
+            objects.push(page_type(name));
+        }
+
+        for action in &section.actions {
+            let name = format!("{}_{}", field_name(&section.id), field_name(&action.id));
+            let ids = (backoffice.id.clone(), section.id.clone(), action.id.clone());
+            match &action.action_type {
+                ActionType::List { .. } => {
+                    query = query.field(list_field(name, ids, record_type_name));
+                    has_query = true;
+                }
+                ActionType::View { .. } => {
+                    query = query.field(rows_field(name, ids, record_type_name, true));
+                    has_query = true;
+                }
+                ActionType::Custom { .. } => {
+                    query = query.field(rows_field(name, ids, record_type_name, false));
+                    has_query = true;
+                }
+                ActionType::Summary { .. } | ActionType::Report { .. } => {
+                    query = query.field(rows_field(name, ids, None, false));
+                    has_query = true;
+                }
+                ActionType::Form { fields, .. } | ActionType::Pipeline { fields, .. } => {
+                    let input_name =
+                        format!("{}{}Input", type_name(&section.id), type_name(&action.id));
+                    let (input, field_ids) = input_type(&input_name, fields);
+                    inputs.push(input);
+                    mutation = mutation.field(mutation_field(name, ids, &input_name, field_ids));
+                    has_mutation = true;
+                }
+            }
+        }
+
+        if let Some(form) = section
+            .actions
+            .iter()
+            .find(|a| matches!(a.action_type, ActionType::Form { .. }))
+        {
+            let ids = (backoffice.id.clone(), section.id.clone(), form.id.clone());
+            mutation = mutation.field(delete_field(
+                format!("delete_{}", field_name(&section.id)),
+                ids,
+            ));
+        }
+    }
+
+    // A schema needs at least one query field
+    if !has_query {
+        let name = backoffice.name.clone();
+        query = query.field(Field::new(
+            "backoffice",
+            TypeRef::named_nn(TypeRef::STRING),
+            move |_| FieldFuture::from_value(Some(GraphQLValue::from(name.clone()))),
+        ));
+    }
+
+    let mut builder = Schema::build("Query", has_mutation.then_some("Mutation"), None)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .register(Scalar::new(JSON).description("Any JSON value"))
+        .register(query);
+    if has_mutation {
+        builder = builder.register(mutation);
+    }
+    for object in objects {
+        builder = builder.register(object);
+    }
+    for input in inputs {
+        builder = builder.register(input);
+    }
+    builder.finish()
+}
+
+/// GraphQL schemas of the backoffices, built on first use and again when the
+/// feature flags change which of their sections and actions are served
+#[derive(Default)]
+pub struct Schemas(Mutex<HashMap<String, (Vec<bool>, Schema)>>);
+
+impl Schemas {
+    /// The schema of a backoffice under the current feature flags
+    fn get(
+        &self,
+        flags: &FeatureFlags,
+        backoffice: &BackofficeConfig,
+    ) -> Result<Schema, SchemaError> {
+        let served: Vec<bool> = backoffice
+            .sections
+            .iter()
+            .flat_map(|section| {
+                std::iter::once(&section.enabled_when)
+                    .chain(section.actions.iter().map(|a| &a.enabled_when))
+            })
+            .map(|enabled_when| flags.allows(enabled_when.as_deref()))
+            .collect();
+
+        let mut schemas = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached, schema)) = schemas.get(&backoffice.id) {
+            if *cached == served {
+                return Ok(schema.clone());
+            }
+        }
+        let schema = schema(&flags.backoffice(backoffice))?;
+        schemas.insert(backoffice.id.clone(), (served, schema.clone()));
+        Ok(schema)
+    }
+}
+
+/// Execute a GraphQL request against a backoffice's schema
+pub async fn graphql_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+    request: RequestMetadata,
+    Json(query): Json<async_graphql::Request>,
+) -> Response {
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Backoffice not found"})),
+        )
+            .into_response();
+    };
+    let schema = match state.graphql_schemas.get(&state.feature_flags, backoffice) {
+        Ok(schema) => schema,
+        Err(e) => {
+            error!(backoffice = %backoffice_id, error = %e, "Failed to build GraphQL schema");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Invalid GraphQL schema: {}", e)})),
+            )
+                .into_response();
+        }
+    };
+    let caller = Caller {
+        state: state.clone(),
+        auth,
+        request,
+    };
+    Json(schema.execute(query.data(caller)).await).into_response()
+}
+
+/// GraphiQL, querying a backoffice's schema
+pub async fn graphiql_handler(Path(backoffice_id): Path<String>) -> Html<String> {
+    Html(
+        GraphiQLSource::build()
+            .endpoint(&format!("/api/graphql/{}", backoffice_id))
+            .finish(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLogger;
+    use crate::server::RouterBuilder;
+    use crate::test_support::sqlite_backoffice;
+    use serde_json::json;
+
+    #[test]
+    fn test_names_and_kinds() {
+        assert_eq!(field_name("order-items"), "order_items");
+        assert_eq!(field_name("2fa"), "_2fa");
+        assert_eq!(type_name("order_items"), "OrderItems");
+
+        assert_eq!(
+            Kind::Float.output(&json!("12.5")),
+            Some(GraphQLValue::from(12.5))
+        );
+        assert_eq!(Kind::Float.output(&json!("n/a")), None);
+        assert_eq!(
+            Kind::Boolean.output(&json!(1)),
+            Some(GraphQLValue::Boolean(true))
+        );
+        assert_eq!(
+            Kind::String.output(&json!(42)),
+            Some(GraphQLValue::from("42"))
+        );
+        assert_eq!(Kind::Json.output(&json!(null)), None);
+    }
+
+    #[tokio::test]
+    async fn test_relationships_and_limits() {
+        let dir = std::env::temp_dir().join(format!("pmp-graphql-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let backoffice: BackofficeConfig = serde_yaml::from_str(&format!(
+            r#"
+id: blog
+name: Blog
+data_sources:
+  db:
+    type: database
+    connection_string: "sqlite://{}?mode=rwc"
+    db_type: sqlite
+relationships:
+  - id: posts
+    name: Posts
+    relationship_type: onetomany
+    from_section: users
+    from_field: id
+    to_section: posts
+    to_field: user_id
+  - id: author
+    name: Author
+    relationship_type: manytoone
+    from_section: posts
+    from_field: user_id
+    to_section: users
+    to_field: id
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - {{ id: name, name: Name, field_type: text }}
+  - id: posts
+    name: Posts
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - {{ id: title, name: Title, field_type: text }}
+          - {{ id: user_id, name: Author, field_type: text }}
+"#,
+            dir.join("blog.db").display()
+        ))
+        .unwrap();
+
+        let config =
+            serde_yaml::from_str("server: { host: 127.0.0.1, port: 0 }\nsecurity: null").unwrap();
+        let state = RouterBuilder::new(config, vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .state();
+        let db = state
+            .data_sources
+            .create(&state.backoffices[0].data_sources["db"])
+            .await
+            .unwrap();
+        for statement in [
+            "CREATE TABLE users (id TEXT PRIMARY KEY, name TEXT)",
+            "CREATE TABLE posts (id TEXT PRIMARY KEY, title TEXT, user_id TEXT)",
+            "INSERT INTO users VALUES ('u1', 'Ann'), ('u2', 'Bob')",
+            "INSERT INTO posts VALUES ('p1', 'Rust tips', 'u1'), ('p2', 'Cooking', 'u1'), ('p3', 'Other', 'u2')",
+        ] {
+            db.execute_mutation(statement, &HashMap::new())
+                .await
+                .unwrap();
+        }
+
+        let execute = |query: String| {
+            let state = state.clone();
+            async move {
+                let response = graphql_handler(
+                    State(state),
+                    Path("blog".to_string()),
+                    AuthContext::anonymous(false),
+                    RequestMetadata::default(),
+                    Json(async_graphql::Request::new(query)),
+                )
+                .await;
+                server::response_json(response).await.1
+            }
+        };
+
+        let body = execute(
+            "{ users_list { items { name posts(page_size: 1, page: 2) { title author { name } } } } }"
+                .to_string(),
+        )
+        .await;
+        assert_eq!(body["errors"], Value::Null, "{}", body);
+        let users = &body["data"]["users_list"]["items"];
+        assert_eq!(users[0]["name"], "Ann");
+        assert_eq!(
+            users[0]["posts"],
+            json!([{"title": "Cooking", "author": {"name": "Ann"}}])
+        );
+        assert_eq!(users[1]["posts"], json!([]));
+
+        // Cyclic relationships only nest so deep
+        let body = execute(
+            "{ users_list { items { posts { author { posts { author { name } } } } } } }"
+                .to_string(),
+        )
+        .await;
+        assert!(body["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("nested at most 3 levels"));
+
+        let too_deep = format!(
+            "{{ __schema {{ types {{ {} name {} }} }} }}",
+            "fields { type { ofType { ".repeat(5),
+            "} } } ".repeat(5)
+        );
+        let body = execute(too_deep).await;
+        assert!(body["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("too deep"));
+
+        // The introspection query of GraphiQL still fits
+        let introspection = format!(
+            "{{ __schema {{ types {{ fields {{ args {{ type {{ {} name {} }} }} }} }} }} }}",
+            "ofType { ".repeat(9),
+            "} ".repeat(9)
+        );
+        let body = execute(introspection).await;
+        assert_eq!(body["errors"], Value::Null, "{}", body);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_graphql() {
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: blog
+name: Blog
+data_sources:
+relationships:
+  - id: author
+    name: Author
+    relationship_type: manytoone
+    from_section: posts
+    from_field: author_id
+    to_section: users
+    to_field: id
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: id, name: ID, field_type: number }
+          - { id: name, name: Name, field_type: text }
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text, required: true }
+  - id: posts
+    name: Posts
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: id, name: ID, field_type: number }
+          - { id: title, name: Title, field_type: text }
+          - { id: author_id, name: Author, field_type: number }
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: title, name: Title, field_type: text, required: true }
+          - { id: author_id, name: Author, field_type: text }
+"#,
+            &[
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+                "CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT, author_id INTEGER)",
+            ],
+        )
+        .await;
+
+        let graphql = |query: &str| {
+            let state = state.clone();
+            let request: async_graphql::Request = async_graphql::Request::new(query);
+            async move {
+                let response = crate::graphql::graphql_handler(
+                    State(state),
+                    Path("blog".to_string()),
+                    AuthContext::anonymous(false),
+                    RequestMetadata::default(),
+                    Json(request),
+                )
+                .await;
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<Value>(&bytes).unwrap()
+            }
+        };
+
+        let created =
+            graphql(r#"mutation { users_create(input: { name: "Ada" }) { success } }"#).await;
+        assert_eq!(created["data"]["users_create"]["success"], true);
+        let created = graphql(
+            r#"mutation { posts_create(input: { title: "Hello", author_id: "1" }) { success } }"#,
+        )
+        .await;
+        assert_eq!(created["data"]["posts_create"]["success"], true);
+
+        // Validation failures carry the REST body in their extensions
+        let invalid = graphql(r#"mutation { users_create(input: {}) { success } }"#).await;
+        let error = &invalid["errors"][0];
+        assert_eq!(error["extensions"]["status"], 400);
+        assert_eq!(error["extensions"]["validation_errors"][0]["field"], "name");
+
+        let posts = graphql(
+            "{ posts_list(page_size: 10) { total_items items { title author_id author { name } } } }",
+        )
+        .await;
+        assert!(posts["errors"].is_null(), "{}", posts);
+        let page = &posts["data"]["posts_list"];
+        assert_eq!(page["total_items"], 1);
+        assert_eq!(page["items"][0]["title"], "Hello");
+        assert_eq!(page["items"][0]["author_id"], 1.0);
+        assert_eq!(page["items"][0]["author"]["name"], "Ada");
+    }
+}
@@ -0,0 +1,477 @@
+//! gRPC API
+//!
+//! With `grpc` configured, the `BackofficeService` of `proto/backoffice.proto`
+//! is served on its own port, for internal tools and CLIs. Messages are
+//! handled dynamically from the proto's descriptor set, compiled by the build
+//! script, and mapped to and from the JSON the REST handlers take and return,
+//! so calls go through the same scopes, hooks, validation and auditing.
+//! Failed calls carry the REST error body as JSON in their status details.
+
+use crate::audit::RequestMetadata;
+use crate::auth::AuthContext;
+use crate::config::{ActionConfig, FieldConfig, RelationshipType};
+use crate::data_source::DynamicCodec;
+use crate::request_id;
+use crate::server::{self, AppState};
+use anyhow::{anyhow, Result};
+use axum::http::StatusCode;
+use axum::response::Response;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor, MethodDescriptor,
+    SerializeOptions, ServiceDescriptor,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, OnceLock};
+use tonic::codegen::{http, BoxFuture, Bytes, Context, Poll, Service};
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::server::{NamedService, UnaryService};
+use tonic::{Code, Status};
+use tracing::{debug, error, info};
+
+/// Descriptor set of `proto/backoffice.proto`
+const DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/backoffice_descriptor.bin"));
+
+const SERVICE_NAME: &str = "pmp.backoffice.v1.BackofficeService";
+
+pub(crate) fn service() -> &'static ServiceDescriptor {
+    static SERVICE: OnceLock<ServiceDescriptor> = OnceLock::new();
+    SERVICE.get_or_init(|| {
+        DescriptorPool::decode(DESCRIPTOR_SET)
+            .expect("the build script writes a valid descriptor set")
+            .get_service_by_name(SERVICE_NAME)
+            .expect("proto/backoffice.proto defines the service")
+    })
+}
+
+/// The backoffice gRPC service, over the application state the HTTP
+/// server uses
+#[derive(Clone)]
+pub struct BackofficeService {
+    state: Arc<AppState>,
+}
+
+impl BackofficeService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+}
+
+impl NamedService for BackofficeService {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+impl Service<http::Request<tonic::body::Body>> for BackofficeService {
+    type Response = http::Response<tonic::body::Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<tonic::body::Body>) -> Self::Future {
+        let state = self.state.clone();
+        Box::pin(async move {
+            let name = request.uri().path().rsplit('/').next().unwrap_or_default();
+            let Some(method) = service().methods().find(|m| m.name() == name) else {
+                let status =
+                    Status::unimplemented(format!("Unknown method {}", request.uri().path()));
+                return Ok(status.into_http());
+            };
+            let mut grpc = tonic::server::Grpc::new(DynamicCodec(method.input()));
+            Ok(grpc.unary(Call { state, method }, request).await)
+        })
+    }
+}
+
+/// One call of a method
+struct Call {
+    state: Arc<AppState>,
+    method: MethodDescriptor,
+}
+
+impl UnaryService<DynamicMessage> for Call {
+    type Response = DynamicMessage;
+    type Future = BoxFuture<tonic::Response<DynamicMessage>, Status>;
+
+    fn call(&mut self, mut request: tonic::Request<DynamicMessage>) -> Self::Future {
+        let state = self.state.clone();
+        let method = self.method.clone();
+        Box::pin(async move {
+            // Calls have a request id like HTTP requests, echoed in the
+            // response metadata
+            let id = request
+                .metadata()
+                .get(request_id::HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map_or_else(|| uuid::Uuid::new_v4().to_string(), str::to_string);
+            let value: MetadataValue<Ascii> = id
+                .parse()
+                .map_err(|_| Status::invalid_argument("Invalid x-request-id"))?;
+            request
+                .metadata_mut()
+                .insert(request_id::HEADER, value.clone());
+
+            let output = request_id::scope(id, async {
+                debug!(method = %method.full_name(), "Handling gRPC call");
+                handle(state, &method, request).await
+            })
+            .await?;
+            let mut response = from_json(method.output(), output).map(tonic::Response::new)?;
+            response.metadata_mut().insert(request_id::HEADER, value);
+            Ok(response)
+        })
+    }
+}
+
+async fn handle(
+    state: Arc<AppState>,
+    method: &MethodDescriptor,
+    request: tonic::Request<DynamicMessage>,
+) -> Result<Value, Status> {
+    let headers = request.metadata().clone().into_headers();
+    let auth = AuthContext::from_headers(&headers, state.config.security.as_ref())
+        .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    let peer = request.remote_addr().map(|addr| addr.ip());
+    let metadata =
+        RequestMetadata::from_headers(&headers, peer, state.config.server.trusted_proxies);
+
+    let input = to_json(request.into_inner())?;
+    let text = |key: &str| input[key].as_str().unwrap_or_default().to_string();
+    let ids = (text("backoffice_id"), text("section_id"), text("action_id"));
+    let disabled = state.backoffice(&ids.0).and_then(|backoffice| {
+        state
+            .feature_flags
+            .disabled(backoffice, &ids.1, Some(&ids.2))
+    });
+    if let Some(error) = disabled {
+        return Err(Status::not_found(error));
+    }
+    match method.name() {
+        "ListBackoffices" => Ok(list_backoffices(&state)),
+        "ExecuteQuery" => {
+            let params: HashMap<String, String> = match &input["params"] {
+                Value::Object(params) => params
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = value
+                            .as_str()
+                            .map_or_else(|| value.to_string(), str::to_string);
+                        (name.clone(), value)
+                    })
+                    .collect(),
+                _ => HashMap::new(),
+            };
+            let response = server::run_query(state, ids, auth, params).await;
+            let mut body = body(response).await?;
+            let rows = match body["data"].take() {
+                Value::Array(rows) => rows,
+                Value::Null => Vec::new(),
+                row => vec![row],
+            };
+            Ok(json!({"rows": rows, "pagination": body["pagination"].take()}))
+        }
+        "ExecuteMutation" => {
+            let data: HashMap<String, Value> = match &input["data"] {
+                Value::Object(data) => data.clone().into_iter().collect(),
+                _ => HashMap::new(),
+            };
+            let accept_warnings = input["accept_warnings"].as_bool().unwrap_or(false);
+            let response =
+                server::run_mutation(state, ids, auth, metadata, data, accept_warnings).await;
+            let mut body = body(response).await?;
+            // Pipelines return the outputs of their steps
+            if body.get("data").is_none() {
+                body["data"] = body["steps"].take();
+            }
+            Ok(body)
+        }
+        name => Err(Status::unimplemented(format!("Unknown method {}", name))),
+    }
+}
+
+/// The JSON body of a REST response, or a status with its code and the body
+/// as details
+async fn body(response: Response) -> Result<Value, Status> {
+    let (status, body) = server::response_json(response).await;
+    if status.is_success() {
+        return Ok(body);
+    }
+
+    let code = match status {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::CONFLICT => Code::Aborted,
+        StatusCode::PRECONDITION_FAILED => Code::FailedPrecondition,
+        StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::NOT_IMPLEMENTED => Code::Unimplemented,
+        StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+        _ => Code::Internal,
+    };
+    let message = body
+        .get("error")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| status.to_string());
+    Err(Status::with_details(
+        code,
+        message,
+        Bytes::from(body.to_string()),
+    ))
+}
+
+fn to_json(message: DynamicMessage) -> Result<Value, Status> {
+    let options = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .stringify_64_bit_integers(false);
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .map_err(|e| Status::invalid_argument(e.to_string()))
+}
+
+fn from_json(descriptor: MessageDescriptor, value: Value) -> Result<DynamicMessage, Status> {
+    // REST bodies carry more than the messages define, e.g. `nested`
+    DynamicMessage::deserialize_with_options(
+        descriptor,
+        value,
+        &DeserializeOptions::new().deny_unknown_fields(false),
+    )
+    .map_err(|e| {
+        error!(error = %e, "Failed to encode gRPC response");
+        Status::internal(format!("Failed to encode response: {}", e))
+    })
+}
+
+/// Tag of an externally tagged enum value, e.g. the `type` of an action
+fn tag<T: serde::Serialize>(value: &T, key: &str) -> Value {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.get(key).cloned())
+        .unwrap_or_default()
+}
+
+fn field_json(field: &FieldConfig) -> Value {
+    json!({
+        "id": field.id,
+        "name": field.name,
+        "field_type": tag(field, "field_type"),
+        "required": field.required,
+        "editable": field.editable,
+        "config": serde_json::to_value(field).unwrap_or_default(),
+    })
+}
+
+fn action_json(action: &ActionConfig) -> Value {
+    json!({
+        "id": action.id,
+        "name": action.name,
+        "type": tag(action, "type"),
+        "required_scopes": action.required_scopes,
+        "fields": action.action_type.fields().iter().map(field_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Metadata of the configured backoffices; data sources, hooks and plugins
+/// stay private to the server
+fn list_backoffices(state: &AppState) -> Value {
+    let backoffices: Vec<Value> = state
+        .backoffices
+        .iter()
+        .map(|backoffice| state.feature_flags.backoffice(backoffice))
+        .map(|backoffice| {
+            let sections: Vec<Value> = backoffice
+                .sections
+                .iter()
+                .map(|section| {
+                    json!({
+                        "id": section.id,
+                        "name": section.name,
+                        "actions": section.actions.iter().map(action_json).collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+            let relationships: Vec<Value> = backoffice
+                .relationships
+                .iter()
+                .map(|relationship| {
+                    let relationship_type = match relationship.relationship_type {
+                        RelationshipType::OneToOne => "onetoone",
+                        RelationshipType::OneToMany => "onetomany",
+                        RelationshipType::ManyToOne => "manytoone",
+                        RelationshipType::ManyToMany { .. } => "manytomany",
+                    };
+                    json!({
+                        "id": relationship.id,
+                        "name": relationship.name,
+                        "relationship_type": relationship_type,
+                        "from_section": relationship.from_section,
+                        "from_field": relationship.from_field,
+                        "to_section": relationship.to_section,
+                        "to_field": relationship.to_field,
+                    })
+                })
+                .collect();
+            json!({
+                "id": backoffice.id,
+                "name": backoffice.name,
+                "description": backoffice.description,
+                "sections": sections,
+                "relationships": relationships,
+            })
+        })
+        .collect();
+    json!({ "backoffices": backoffices })
+}
+
+/// Serve the gRPC API on `addr` until the server fails
+pub async fn serve(state: Arc<AppState>, addr: String) -> Result<()> {
+    let socket_addr = tokio::net::lookup_host(&addr)
+        .await
+        .map_err(|e| anyhow!("Invalid gRPC address '{}': {}", addr, e))?
+        .next()
+        .ok_or_else(|| anyhow!("gRPC address '{}' resolves to nothing", addr))?;
+    info!(address = %socket_addr, "gRPC API listening");
+    tonic::transport::Server::builder()
+        .add_service(BackofficeService::new(state))
+        .serve(socket_addr)
+        .await
+        .map_err(|e| anyhow!("gRPC server failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sqlite_backoffice;
+
+    #[tokio::test]
+    async fn test_grpc() {
+        use crate::data_source::DynamicCodec;
+        use prost_reflect::{DynamicMessage, SerializeOptions};
+        use tonic::codegen::http::uri::PathAndQuery;
+
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: crm
+name: CRM
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        config: { enable_pagination: true }
+        fields:
+          - { id: name, name: Name, field_type: text }
+          - { id: age, name: Age, field_type: number }
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text, required: true }
+          - { id: age, name: Age, field_type: number }
+"#,
+            &["CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age INTEGER)"],
+        )
+        .await;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(crate::grpc::BackofficeService::new(state))
+                .serve_with_incoming(tonic::transport::server::TcpIncoming::from(listener)),
+        );
+        let channel = tonic::transport::Endpoint::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect_lazy();
+
+        let call = |name: &str, request: Value| {
+            let method = crate::grpc::service()
+                .methods()
+                .find(|m| m.name() == name)
+                .unwrap();
+            let channel = channel.clone();
+            async move {
+                let message = DynamicMessage::deserialize(method.input(), request).unwrap();
+                let path = PathAndQuery::try_from(format!(
+                    "/{}/{}",
+                    crate::grpc::service().full_name(),
+                    method.name()
+                ))
+                .unwrap();
+                let mut client = tonic::client::Grpc::new(channel);
+                client.ready().await.unwrap();
+                client
+                    .unary(
+                        tonic::Request::new(message),
+                        path,
+                        DynamicCodec(method.output()),
+                    )
+                    .await
+                    .map(|response| {
+                        response
+                            .into_inner()
+                            .serialize_with_options(
+                                serde_json::value::Serializer,
+                                &SerializeOptions::new()
+                                    .use_proto_field_name(true)
+                                    .stringify_64_bit_integers(false),
+                            )
+                            .unwrap()
+                    })
+            }
+        };
+
+        let listed = call("ListBackoffices", serde_json::json!({}))
+            .await
+            .unwrap();
+        let users = &listed["backoffices"][0]["sections"][0];
+        assert_eq!(users["actions"][1]["type"], "form");
+        assert_eq!(users["actions"][1]["fields"][0]["field_type"], "text");
+        assert_eq!(users["actions"][1]["fields"][0]["required"], true);
+
+        let ids = serde_json::json!({"backoffice_id": "crm", "section_id": "users"});
+        let mutation = |data: Value| {
+            let mut request = ids.clone();
+            request["action_id"] = "create".into();
+            request["data"] = data;
+            request
+        };
+        let created = call(
+            "ExecuteMutation",
+            mutation(serde_json::json!({"name": "Ada", "age": 36})),
+        )
+        .await
+        .unwrap();
+        assert_eq!(created["success"], true);
+
+        // Failures keep the REST status and body
+        let status = call("ExecuteMutation", mutation(serde_json::json!({"age": 1})))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        let details: Value = serde_json::from_slice(status.details()).unwrap();
+        assert_eq!(details["validation_errors"][0]["field"], "name");
+
+        let mut query = ids.clone();
+        query["action_id"] = "list".into();
+        query["params"] = serde_json::json!({"page_size": "10"});
+        let listed = call("ExecuteQuery", query).await.unwrap();
+        assert_eq!(listed["rows"][0]["name"], "Ada");
+        assert_eq!(listed["rows"][0]["age"], 36.0);
+        assert_eq!(listed["pagination"]["total_items"], 1);
+    }
+}
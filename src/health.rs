@@ -0,0 +1,287 @@
+//! Data source health monitoring
+//!
+//! Every call made through a registry-created data source is timed and its
+//! outcome recorded per data source configuration. Periodic health checks
+//! probe each configured source even when nobody uses it, and the status
+//! endpoint reports the state, last error, latency percentiles and number of
+//! recoveries of every data source of a backoffice.
+
+use crate::circuit_breaker::is_outage;
+use crate::data_source::{
+    DataSource, Mutation, Page, PaginationParams, PresignMethod, PresignedUrl, RowStream,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Latency samples kept per data source
+const LATENCY_SAMPLES: usize = 256;
+
+/// Whether a data source is reachable, judging by its latest call or check
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthState {
+    /// No call or check has completed yet
+    #[default]
+    Unknown,
+    Healthy,
+    Unhealthy,
+}
+
+/// Latency percentiles of recent calls, in milliseconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencyPercentiles {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+/// Health report of one data source
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DataSourceHealth {
+    pub state: HealthState,
+    /// Circuit breaker state, when breakers are enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<&'static str>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub latency_ms: Option<LatencyPercentiles>,
+    pub calls: u64,
+    pub errors: u64,
+    /// Times the source recovered after being unhealthy
+    pub reconnects: u64,
+}
+
+#[derive(Debug, Default)]
+struct SourceHealth {
+    report: DataSourceHealth,
+    latencies: VecDeque<Duration>,
+}
+
+/// Health of every data source configuration seen so far
+#[derive(Default)]
+pub struct HealthMonitor {
+    sources: Mutex<HashMap<String, SourceHealth>>,
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome of a call or health check
+    pub(crate) fn record(&self, key: &str, latency: Duration, result: Result<(), &anyhow::Error>) {
+        let mut sources = self.sources.lock().unwrap();
+        let source = sources.entry(key.to_string()).or_default();
+        let report = &mut source.report;
+        report.calls += 1;
+
+        if source.latencies.len() == LATENCY_SAMPLES {
+            source.latencies.pop_front();
+        }
+        source.latencies.push_back(latency);
+
+        match result {
+            Ok(()) => {
+                if report.state == HealthState::Unhealthy {
+                    report.reconnects += 1;
+                }
+                report.state = HealthState::Healthy;
+            }
+            Err(e) => {
+                report.errors += 1;
+                report.last_error = Some(e.to_string());
+                report.last_error_at = Some(Utc::now());
+                // Errors reported by a reachable source don't make it unhealthy
+                if is_outage(e) {
+                    report.state = HealthState::Unhealthy;
+                } else if report.state == HealthState::Unknown {
+                    report.state = HealthState::Healthy;
+                }
+            }
+        }
+    }
+
+    /// Record a periodic health check
+    pub(crate) fn record_check(
+        &self,
+        key: &str,
+        latency: Duration,
+        result: Result<(), &anyhow::Error>,
+    ) {
+        self.record(key, latency, result);
+        if let Some(source) = self.sources.lock().unwrap().get_mut(key) {
+            source.report.last_checked_at = Some(Utc::now());
+        }
+    }
+
+    /// Current report of a data source
+    pub(crate) fn report(&self, key: &str) -> DataSourceHealth {
+        let sources = self.sources.lock().unwrap();
+        let Some(source) = sources.get(key) else {
+            return DataSourceHealth::default();
+        };
+
+        let mut report = source.report.clone();
+        let mut latencies: Vec<Duration> = source.latencies.iter().copied().collect();
+        latencies.sort();
+        report.latency_ms = (!latencies.is_empty()).then(|| LatencyPercentiles {
+            p50: percentile(&latencies, 50.0),
+            p95: percentile(&latencies, 95.0),
+            p99: percentile(&latencies, 99.0),
+        });
+        report
+    }
+
+    /// Time a call and record its outcome
+    pub(crate) async fn observe<T>(
+        &self,
+        key: &str,
+        call: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let started = Instant::now();
+        let result = call.await;
+        self.record(key, started.elapsed(), result.as_ref().map(|_| ()));
+        result
+    }
+}
+
+/// Nearest-rank percentile of sorted samples, in milliseconds
+fn percentile(sorted: &[Duration], percent: f64) -> f64 {
+    let rank = ((percent / 100.0) * sorted.len() as f64).ceil() as usize;
+    let sample = sorted[rank.clamp(1, sorted.len()) - 1];
+    (sample.as_secs_f64() * 1000.0 * 100.0).round() / 100.0
+}
+
+/// A data source whose calls are timed by the health monitor
+pub(crate) struct MonitoredDataSource {
+    pub(crate) inner: Box<dyn DataSource>,
+    pub(crate) monitor: Arc<HealthMonitor>,
+    pub(crate) key: String,
+}
+
+#[async_trait::async_trait]
+impl DataSource for MonitoredDataSource {
+    async fn execute_query(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.monitor
+            .observe(&self.key, self.inner.execute_query(query, params))
+            .await
+    }
+
+    async fn execute_query_paginated(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: Option<&PaginationParams>,
+    ) -> Result<Vec<HashMap<String, Value>>> {
+        self.monitor
+            .observe(
+                &self.key,
+                self.inner
+                    .execute_query_paginated(query, params, pagination),
+            )
+            .await
+    }
+
+    async fn execute_page(
+        &self,
+        query: &str,
+        params: Option<&HashMap<String, Value>>,
+        pagination: &PaginationParams,
+    ) -> Result<Page> {
+        self.monitor
+            .observe(
+                &self.key,
+                self.inner.execute_page(query, params, pagination),
+            )
+            .await
+    }
+
+    async fn execute_mutation(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        self.monitor
+            .observe(&self.key, self.inner.execute_mutation(query, data))
+            .await
+    }
+
+    async fn execute_delete(&self, query: &str, data: &HashMap<String, Value>) -> Result<Value> {
+        self.monitor
+            .observe(&self.key, self.inner.execute_delete(query, data))
+            .await
+    }
+
+    async fn subscribe(&self, query: &str) -> Result<RowStream> {
+        self.inner.subscribe(query).await
+    }
+
+    async fn presign(
+        &self,
+        key: &str,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedUrl> {
+        self.inner.presign(key, method, expires_in).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.inner.health_check().await
+    }
+
+    fn supports_transactions(&self) -> bool {
+        self.inner.supports_transactions()
+    }
+
+    async fn execute_transaction(&self, mutations: &[Mutation]) -> Result<Vec<Value>> {
+        self.monitor
+            .observe(&self.key, self.inner.execute_transaction(mutations))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_health_reports() {
+        let monitor = HealthMonitor::new();
+        assert_eq!(monitor.report("db").state, HealthState::Unknown);
+
+        for ms in 1..=100 {
+            monitor.record("db", Duration::from_millis(ms), Ok(()));
+        }
+        let report = monitor.report("db");
+        assert_eq!(report.state, HealthState::Healthy);
+        assert_eq!(
+            report.latency_ms,
+            Some(LatencyPercentiles {
+                p50: 50.0,
+                p95: 95.0,
+                p99: 99.0,
+            })
+        );
+
+        let refused = anyhow!("connection refused");
+        monitor.record_check("db", Duration::from_millis(1), Err(&refused));
+        let report = monitor.report("db");
+        assert_eq!(report.state, HealthState::Unhealthy);
+        assert_eq!(report.last_error.as_deref(), Some("connection refused"));
+        assert!(report.last_checked_at.is_some());
+
+        monitor.record("db", Duration::from_millis(1), Ok(()));
+        let report = monitor.report("db");
+        assert_eq!(report.state, HealthState::Healthy);
+        assert_eq!(report.reconnects, 1);
+        assert_eq!((report.calls, report.errors), (102, 1));
+    }
+}
@@ -0,0 +1,364 @@
+//! Lifecycle hooks around queries, mutations and deletes
+//!
+//! Hooks are either native Rust implementations of [`Hooks`] registered on the
+//! `RouterBuilder`, or Rhai scripts declared under a backoffice's
+//! `script_hooks`. Native hooks run first, in registration order.
+
+use crate::auth::AuthContext;
+use crate::config::{ActionConfig, BackofficeConfig, HookEvent, ScriptHookConfig, SectionConfig};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, warn};
+
+/// Maximum number of Rhai operations a hook script may run
+const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+
+/// What a handler is operating on when a hook runs
+pub struct HookContext<'a> {
+    pub backoffice: &'a BackofficeConfig,
+    pub section: &'a SectionConfig,
+    pub action: &'a ActionConfig,
+    pub auth: &'a AuthContext,
+}
+
+/// Whether the operation should go ahead
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookOutcome {
+    Continue,
+    /// Reject the operation with the given message
+    Veto(String),
+}
+
+/// Lifecycle hooks; every method defaults to a no-op
+#[async_trait]
+pub trait Hooks: Send + Sync {
+    /// Runs before a List/View/Custom query; may rewrite the query parameters
+    async fn before_query(
+        &self,
+        _ctx: &HookContext<'_>,
+        _params: &mut HashMap<String, Value>,
+    ) -> Result<HookOutcome> {
+        Ok(HookOutcome::Continue)
+    }
+
+    /// Runs on query results before they are returned
+    async fn after_query(
+        &self,
+        _ctx: &HookContext<'_>,
+        _rows: &mut Vec<HashMap<String, Value>>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs before a mutation is validated and executed; may rewrite the payload
+    async fn before_mutation(
+        &self,
+        _ctx: &HookContext<'_>,
+        _data: &mut HashMap<String, Value>,
+    ) -> Result<HookOutcome> {
+        Ok(HookOutcome::Continue)
+    }
+
+    /// Runs after a mutation succeeded
+    async fn after_mutation(
+        &self,
+        _ctx: &HookContext<'_>,
+        _data: &HashMap<String, Value>,
+        _result: &Value,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs before a record (and its cascades) is deleted
+    async fn on_delete(&self, _ctx: &HookContext<'_>, _record_id: &str) -> Result<HookOutcome> {
+        Ok(HookOutcome::Continue)
+    }
+}
+
+/// Run the `before_query` hooks
+pub async fn before_query(
+    hooks: &[Arc<dyn Hooks>],
+    ctx: &HookContext<'_>,
+    params: &mut HashMap<String, Value>,
+) -> Result<HookOutcome> {
+    for hook in hooks {
+        if let HookOutcome::Veto(message) = hook.before_query(ctx, params).await? {
+            return Ok(HookOutcome::Veto(message));
+        }
+    }
+
+    for script in scripts_for(ctx, HookEvent::BeforeQuery) {
+        let output = run_script(script, ctx, "params", to_json(params))?;
+        if let Some(veto) = veto_from(&output) {
+            return Ok(HookOutcome::Veto(veto));
+        }
+        if let Value::Object(new_params) = output {
+            *params = new_params.into_iter().collect();
+        }
+    }
+
+    Ok(HookOutcome::Continue)
+}
+
+/// Run the `after_query` hooks
+pub async fn after_query(
+    hooks: &[Arc<dyn Hooks>],
+    ctx: &HookContext<'_>,
+    rows: &mut Vec<HashMap<String, Value>>,
+) -> Result<()> {
+    for hook in hooks {
+        hook.after_query(ctx, rows).await?;
+    }
+
+    for script in scripts_for(ctx, HookEvent::AfterQuery) {
+        let input = Value::Array(rows.iter().map(to_json).collect());
+        match run_script(script, ctx, "rows", input)? {
+            Value::Null => {}
+            Value::Array(new_rows) => {
+                *rows = new_rows
+                    .into_iter()
+                    .filter_map(|row| match row {
+                        Value::Object(obj) => Some(obj.into_iter().collect()),
+                        _ => None,
+                    })
+                    .collect();
+            }
+            other => return Err(anyhow!("after_query script returned {}", other)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the `before_mutation` hooks
+pub async fn before_mutation(
+    hooks: &[Arc<dyn Hooks>],
+    ctx: &HookContext<'_>,
+    data: &mut HashMap<String, Value>,
+) -> Result<HookOutcome> {
+    for hook in hooks {
+        if let HookOutcome::Veto(message) = hook.before_mutation(ctx, data).await? {
+            return Ok(HookOutcome::Veto(message));
+        }
+    }
+
+    for script in scripts_for(ctx, HookEvent::BeforeMutation) {
+        let output = run_script(script, ctx, "data", to_json(data))?;
+        if let Some(veto) = veto_from(&output) {
+            return Ok(HookOutcome::Veto(veto));
+        }
+        if let Value::Object(new_data) = output {
+            *data = new_data.into_iter().collect();
+        }
+    }
+
+    Ok(HookOutcome::Continue)
+}
+
+/// Run the `after_mutation` hooks; failures are logged, not returned
+pub async fn after_mutation(
+    hooks: &[Arc<dyn Hooks>],
+    ctx: &HookContext<'_>,
+    data: &HashMap<String, Value>,
+    result: &Value,
+) {
+    for hook in hooks {
+        if let Err(e) = hook.after_mutation(ctx, data, result).await {
+            warn!(error = %e, "after_mutation hook failed");
+        }
+    }
+
+    for script in scripts_for(ctx, HookEvent::AfterMutation) {
+        let input = serde_json::json!({ "data": data, "result": result });
+        if let Err(e) = run_script(script, ctx, "mutation", input) {
+            warn!(error = %e, "after_mutation script failed");
+        }
+    }
+}
+
+/// Run the `on_delete` hooks
+pub async fn on_delete(
+    hooks: &[Arc<dyn Hooks>],
+    ctx: &HookContext<'_>,
+    record_id: &str,
+) -> Result<HookOutcome> {
+    for hook in hooks {
+        if let HookOutcome::Veto(message) = hook.on_delete(ctx, record_id).await? {
+            return Ok(HookOutcome::Veto(message));
+        }
+    }
+
+    for script in scripts_for(ctx, HookEvent::OnDelete) {
+        let output = run_script(script, ctx, "record_id", Value::from(record_id))?;
+        if let Some(veto) = veto_from(&output) {
+            return Ok(HookOutcome::Veto(veto));
+        }
+    }
+
+    Ok(HookOutcome::Continue)
+}
+
+fn scripts_for<'a>(
+    ctx: &'a HookContext<'_>,
+    event: HookEvent,
+) -> impl Iterator<Item = &'a ScriptHookConfig> {
+    let section_id = ctx.section.id.as_str();
+    ctx.backoffice.script_hooks.iter().filter(move |h| {
+        h.event == event && (h.sections.is_empty() || h.sections.iter().any(|s| s == section_id))
+    })
+}
+
+fn engine() -> &'static rhai::Engine {
+    static ENGINE: OnceLock<rhai::Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+        engine
+    })
+}
+
+/// Evaluate a hook script with `context` and the event's input in scope
+fn run_script(
+    hook: &ScriptHookConfig,
+    ctx: &HookContext<'_>,
+    input_name: &'static str,
+    input: Value,
+) -> Result<Value> {
+    debug!(event = ?hook.event, section = %ctx.section.id, "Running script hook");
+
+    let context = serde_json::json!({
+        "backoffice_id": ctx.backoffice.id,
+        "section_id": ctx.section.id,
+        "action_id": ctx.action.id,
+        "user_id": ctx.auth.user_id,
+        "scopes": ctx.auth.scopes,
+    });
+
+    let mut scope = rhai::Scope::new();
+    scope.push_dynamic("context", rhai::serde::to_dynamic(&context)?);
+    scope.push_dynamic(input_name, rhai::serde::to_dynamic(&input)?);
+
+    let result = engine()
+        .eval_with_scope::<rhai::Dynamic>(&mut scope, &hook.script)
+        .map_err(|e| anyhow!("{:?} hook script failed: {}", hook.event, e))?;
+
+    Ok(rhai::serde::from_dynamic(&result)?)
+}
+
+/// Scripts veto by returning a message or `false`
+fn veto_from(output: &Value) -> Option<String> {
+    match output {
+        Value::String(message) => Some(message.clone()),
+        Value::Bool(false) => Some("Operation rejected".to_string()),
+        _ => None,
+    }
+}
+
+fn to_json(map: &HashMap<String, Value>) -> Value {
+    Value::Object(map.clone().into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ActionType, SectionConfig};
+
+    fn backoffice(script_hooks: Vec<ScriptHookConfig>) -> BackofficeConfig {
+        BackofficeConfig {
+            id: "shop".to_string(),
+            name: "Shop".to_string(),
+            description: None,
+            data_sources: HashMap::new(),
+            relationships: vec![],
+            plugins: vec![],
+            validation_functions: HashMap::new(),
+            script_hooks,
+            dashboard: None,
+            currency: None,
+            api: Default::default(),
+            sections: vec![SectionConfig {
+                id: "orders".to_string(),
+                name: "Orders".to_string(),
+                icon: None,
+                actions: vec![ActionConfig {
+                    id: "create".to_string(),
+                    name: "Create".to_string(),
+                    action_type: ActionType::Custom { fields: vec![] },
+                    data_source: "db".to_string(),
+                    query: None,
+                    endpoint: None,
+                    method: None,
+                    generated: false,
+                    cache: None,
+                    cache_ttl_secs: None,
+                    enabled_when: None,
+                    required_scopes: vec![],
+                }],
+                audit: None,
+                row_filters: vec![],
+                table: None,
+                seeds: vec![],
+                updated_at_field: None,
+                search: None,
+                duplicate: None,
+                enabled_when: None,
+            }],
+        }
+    }
+
+    struct Stamp;
+
+    #[async_trait]
+    impl Hooks for Stamp {
+        async fn before_mutation(
+            &self,
+            ctx: &HookContext<'_>,
+            data: &mut HashMap<String, Value>,
+        ) -> Result<HookOutcome> {
+            data.insert(
+                "created_by".to_string(),
+                Value::from(ctx.auth.user_id.clone()),
+            );
+            Ok(HookOutcome::Continue)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_native_and_script_hooks() {
+        let backoffice = backoffice(vec![ScriptHookConfig {
+            event: HookEvent::BeforeMutation,
+            script: r#"
+                if data.total < 0 { return "Total cannot be negative"; }
+                data.reviewed_in = context.section_id;
+                data
+            "#
+            .to_string(),
+            sections: vec![],
+        }]);
+        let section = &backoffice.sections[0];
+        let auth = AuthContext::from_claims(serde_json::json!({"sub": "ana"}));
+        let ctx = HookContext {
+            backoffice: &backoffice,
+            section,
+            action: &section.actions[0],
+            auth: &auth,
+        };
+        let hooks: Vec<Arc<dyn Hooks>> = vec![Arc::new(Stamp)];
+
+        let mut data = HashMap::from([("total".to_string(), Value::from(10))]);
+        let outcome = before_mutation(&hooks, &ctx, &mut data).await.unwrap();
+        assert_eq!(outcome, HookOutcome::Continue);
+        assert_eq!(data["created_by"], "ana");
+        assert_eq!(data["reviewed_in"], "orders");
+
+        let mut data = HashMap::from([("total".to_string(), Value::from(-1))]);
+        let outcome = before_mutation(&hooks, &ctx, &mut data).await.unwrap();
+        assert_eq!(
+            outcome,
+            HookOutcome::Veto("Total cannot be negative".to_string())
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! Localized messages
+//!
+//! Validation errors carry a `code` and `params` besides their message, e.g.
+//! `{"code": "min_length", "params": {"field": "Name", "min": 5}}`, so
+//! frontends can present their own. Messages are rendered from templates
+//! such as `{field} must be at least {min} characters`: the built-in English
+//! ones, or those of `i18n.messages` for the locale a request asks for with
+//! `?locale=` or `Accept-Language`.
+
+use crate::config::I18nConfig;
+use crate::validation::ValidationError;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// English templates of the built-in message codes
+const BUILT_IN: &[(&str, &str)] = &[
+    ("required", "{field} is required"),
+    ("min_length", "{field} must be at least {min} characters"),
+    ("max_length", "{field} must be at most {max} characters"),
+    ("pattern", "{field} does not match the required pattern"),
+    ("min", "{field} must be at least {min}"),
+    ("max", "{field} must be at most {max}"),
+    ("between", "{field} must be between {min} and {max}"),
+    ("email", "{field} must be a valid email address"),
+    ("url", "{field} must be a valid URL"),
+    ("email_domain", "{field} can't use addresses at {domain}"),
+    ("email_mx", "{domain} doesn't accept email"),
+    ("phone", "{field} must be a valid phone number"),
+    ("phone_extension", "{field} must not have an extension"),
+    ("custom_function", "{field} is invalid"),
+    ("expression", "{field} is invalid"),
+    ("remote", "{field} is invalid"),
+    (
+        "depends_on",
+        "{field} depends on {other} having a specific value",
+    ),
+    ("match_field", "{field} must match {other}"),
+    ("unique_in", "{field} must be unique"),
+    (
+        "unique_together",
+        "{field} must be unique together with {fields}",
+    ),
+    ("credit_card", "{field} must be a valid credit card number"),
+    ("ipv4", "{field} must be a valid IPv4 address"),
+    ("ipv6", "{field} must be a valid IPv6 address"),
+    ("uuid", "{field} must be a valid UUID"),
+    ("date_range", "Start date must be before end date"),
+    (
+        "password_min_length",
+        "Password must be at least {min} characters",
+    ),
+    (
+        "password_uppercase",
+        "Password must contain at least one uppercase letter",
+    ),
+    (
+        "password_lowercase",
+        "Password must contain at least one lowercase letter",
+    ),
+    (
+        "password_number",
+        "Password must contain at least one number",
+    ),
+    (
+        "password_special",
+        "Password must contain at least one special character",
+    ),
+    (
+        "alpha_numeric",
+        "{field} must contain only alphanumeric characters",
+    ),
+    ("luhn", "{field} failed Luhn check"),
+    (
+        "card_brand",
+        "{field}: {brand} cards are not accepted ({allowed})",
+    ),
+    ("mac_address", "{field} must be a valid MAC address"),
+    ("isbn", "{field} must be a valid ISBN"),
+    ("iban", "{field} must be a valid IBAN"),
+    ("ssn", "{field} must be a valid SSN (XXX-XX-XXXX)"),
+    (
+        "postal_code",
+        "{field} must be a valid {country} postal code",
+    ),
+    ("base64", "{field} must be valid Base64"),
+    ("json", "{field} must be valid JSON"),
+    ("hex", "{field} must be valid hexadecimal"),
+    ("ascii", "{field} must contain only ASCII characters"),
+    ("not_empty", "{field} must not be empty"),
+    ("future", "{field} must be a future date"),
+    ("past", "{field} must be a past date"),
+    ("pii", "{field} must not contain personal data ({kinds})"),
+    ("min_age", "Must be at least {years} years old"),
+    ("max_age", "Must be at most {years} years old"),
+    ("invalid_value", "{field} is not a valid value"),
+    (
+        "date_format",
+        "'{value}' does not match the date format '{format}'",
+    ),
+    ("write_forbidden", "Insufficient scopes to write field"),
+    ("related_id", "Must be the id of a related record"),
+    ("object", "Must be an object"),
+    ("initial_state", "Records can't start in state '{state}'"),
+];
+
+/// The English template of a built-in message code
+pub fn built_in(code: &str) -> Option<&'static str> {
+    BUILT_IN
+        .iter()
+        .find(|(built_in, _)| *built_in == code)
+        .map(|(_, template)| *template)
+}
+
+/// Message parameters from name and value pairs
+pub fn params<const N: usize>(pairs: [(&str, Value); N]) -> HashMap<String, Value> {
+    pairs
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+/// A number parameter, whole numbers without a fractional part
+pub fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::from(n as i64)
+    } else {
+        Value::from(n)
+    }
+}
+
+/// Replace the `{name}` placeholders of a template with its parameters
+///
+/// Unknown placeholders are kept as they are.
+pub fn render(template: &str, params: &HashMap<String, Value>) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        let Some(end) = placeholder.find('}') else {
+            break;
+        };
+        match params.get(&placeholder[1..end]) {
+            Some(Value::String(text)) => message.push_str(text),
+            Some(Value::Array(items)) => message.push_str(
+                &items
+                    .iter()
+                    .map(|item| match item {
+                        Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Some(value) => message.push_str(&value.to_string()),
+            None => message.push_str(&placeholder[..=end]),
+        }
+        rest = &placeholder[end + 1..];
+    }
+    message.push_str(rest);
+    message
+}
+
+/// Locales of a `?locale=` value or `Accept-Language` header, best first
+fn preferences(preference: &str) -> Vec<&str> {
+    let mut locales: Vec<(&str, f32)> = preference
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let locale = parts.next()?.trim();
+            let quality = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            (!locale.is_empty() && locale != "*" && quality > 0.0).then_some((locale, quality))
+        })
+        .collect();
+    locales.sort_by(|a, b| b.1.total_cmp(&a.1));
+    locales.into_iter().map(|(locale, _)| locale).collect()
+}
+
+/// The configured locale best matching a request's preference
+///
+/// `es-AR` matches `es-AR` first and `es` otherwise; requests matching no
+/// locale get the default one, if it has messages.
+pub fn negotiate<'a>(config: &'a I18nConfig, preference: Option<&str>) -> Option<&'a str> {
+    let find = |wanted: &str| {
+        config
+            .messages
+            .keys()
+            .find(|locale| locale.eq_ignore_ascii_case(wanted))
+            .map(String::as_str)
+    };
+    preference
+        .map(preferences)
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|locale| {
+            find(locale).or_else(|| {
+                locale
+                    .split_once(['-', '_'])
+                    .and_then(|(language, _)| find(language))
+            })
+        })
+        .or_else(|| find(&config.default_locale))
+}
+
+/// Render the messages of errors in the locale a request prefers
+///
+/// Errors whose rule sets its own message, or whose code the locale has no
+/// template for, keep their message.
+pub fn localize<'a>(
+    errors: impl IntoIterator<Item = &'a mut ValidationError>,
+    config: &I18nConfig,
+    preference: Option<&str>,
+) {
+    let Some(templates) = negotiate(config, preference).and_then(|l| config.messages.get(l)) else {
+        return;
+    };
+    for error in errors.into_iter().filter(|e| e.localizable()) {
+        if let Some(template) = templates.get(&error.code) {
+            error.message = render(template, &error.params);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let params = params([
+            ("field", Value::from("Name")),
+            ("min", number(5.0)),
+            ("fields", serde_json::json!(["tenant", "slug"])),
+        ]);
+        assert_eq!(
+            render(built_in("min_length").unwrap(), &params),
+            "Name must be at least 5 characters"
+        );
+        assert_eq!(
+            render("{field}: {fields} {missing}", &params),
+            "Name: tenant, slug {missing}"
+        );
+    }
+
+    #[test]
+    fn test_localize() {
+        let config: I18nConfig = serde_yaml::from_str(
+            r#"
+default_locale: en
+messages:
+  es:
+    min_length: "{field} debe tener al menos {min} caracteres"
+  en:
+    required: "Please fill in {field}"
+"#,
+        )
+        .unwrap();
+        let errors = || {
+            vec![
+                ValidationError::new(
+                    "name",
+                    "min_length",
+                    params([("field", "Name".into()), ("min", 5.into())]),
+                ),
+                ValidationError::new("email", "required", params([("field", "Email".into())])),
+            ]
+        };
+
+        let mut spanish = errors();
+        localize(&mut spanish, &config, Some("es-AR,es;q=0.9,en;q=0.8"));
+        assert_eq!(spanish[0].message, "Name debe tener al menos 5 caracteres");
+        // Codes the locale has no template for keep the English message
+        assert_eq!(spanish[1].message, "Email is required");
+
+        let mut other = errors();
+        localize(&mut other, &config, Some("fr"));
+        assert_eq!(other[1].message, "Please fill in Email");
+
+        let mut custom = vec![errors().remove(0).with_message("Too short")];
+        localize(&mut custom, &config, Some("es"));
+        assert_eq!(custom[0].message, "Too short");
+    }
+}
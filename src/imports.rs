@@ -0,0 +1,268 @@
+//! File imports
+//!
+//! `POST .../actions/:action_id/import` reads a CSV, JSON or JSONL file and
+//! creates one record per row through a form action, with the same
+//! validation as a submitted form. The form's `import` config maps file
+//! columns to fields and sets the CSV delimiter, the formats dates are
+//! written in and the rows to skip. Files import all or nothing, and
+//! `dry_run=true` only returns the error report, so messy files can be
+//! fixed until they import cleanly.
+
+use crate::config::{FieldConfig, FieldType, FileFormat, ImportConfig};
+use crate::data_source;
+use crate::i18n::params;
+use crate::reports;
+use crate::validation::ValidationError;
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A problem with one row of an imported file
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportError {
+    /// 1-based position of the row among the file's records
+    pub row: usize,
+    #[serde(flatten)]
+    pub error: ValidationError,
+}
+
+/// Rows of an imported file, keyed by field id
+#[derive(Debug, Default)]
+pub struct ImportedRows {
+    /// Each row's position in the file and its values
+    pub rows: Vec<(usize, HashMap<String, Value>)>,
+    /// Values that could not be read as their field's type
+    pub errors: Vec<ImportError>,
+    /// File columns that map to no field
+    pub ignored_columns: Vec<String>,
+}
+
+impl ImportedRows {
+    fn ignore(&mut self, column: &str) {
+        if !self.ignored_columns.iter().any(|c| c == column) {
+            self.ignored_columns.push(column.to_string());
+        }
+    }
+}
+
+/// Read the rows of a file, keeping the columns that map to a field
+///
+/// CSV cells are typed by their field's type and empty ones left out, so
+/// required fields still fail validation; JSON values are kept as they
+/// are. Dates with a configured format are rewritten in ISO 8601.
+pub fn read(
+    text: &str,
+    format: &FileFormat,
+    config: &ImportConfig,
+    fields: &[FieldConfig],
+) -> Result<ImportedRows> {
+    let field_for = |column: &str| {
+        let id = config
+            .columns
+            .get(column)
+            .map(String::as_str)
+            .unwrap_or(column);
+        fields.iter().find(|f| f.id == id)
+    };
+    let mut imported = ImportedRows::default();
+
+    match format {
+        FileFormat::Csv => {
+            let mut records = data_source::parse_csv(text, config.delimiter)
+                .into_iter()
+                .skip(config.skip_rows);
+            let header: Vec<String> = records
+                .next()
+                .ok_or_else(|| anyhow!("The file has no header row"))?
+                .iter()
+                .map(|column| column.trim().to_string())
+                .collect();
+            let columns: Vec<Option<&FieldConfig>> = header
+                .iter()
+                .map(|column| {
+                    let field = field_for(column);
+                    if field.is_none() {
+                        imported.ignore(column);
+                    }
+                    field
+                })
+                .collect();
+
+            let records = records.filter(|record| record.iter().any(|c| !c.trim().is_empty()));
+            for (index, record) in records.enumerate() {
+                let mut row = HashMap::new();
+                for (cell, field) in record.iter().zip(&columns) {
+                    let (cell, Some(field)) = (cell.trim(), field) else {
+                        continue;
+                    };
+                    if cell.is_empty() {
+                        continue;
+                    }
+                    match read_text(cell, field, config) {
+                        Ok(value) => {
+                            row.insert(field.id.clone(), value);
+                        }
+                        Err(error) => imported.errors.push(ImportError {
+                            row: index + 1,
+                            error: *error,
+                        }),
+                    }
+                }
+                imported.rows.push((index + 1, row));
+            }
+        }
+        FileFormat::Json | FileFormat::Jsonl => {
+            let records = data_source::parse_rows(text, format)?
+                .into_iter()
+                .skip(config.skip_rows);
+            for (index, record) in records.enumerate() {
+                let mut row = HashMap::new();
+                for (column, value) in record {
+                    let Some(field) = field_for(&column) else {
+                        imported.ignore(&column);
+                        continue;
+                    };
+                    let value = match (&value, config.date_formats.get(&field.id)) {
+                        (Value::String(text), Some(date_format)) => {
+                            read_date(text, date_format, field)
+                        }
+                        _ => Ok(value),
+                    };
+                    match value {
+                        Ok(value) => {
+                            row.insert(field.id.clone(), value);
+                        }
+                        Err(error) => imported.errors.push(ImportError {
+                            row: index + 1,
+                            error: *error,
+                        }),
+                    }
+                }
+                imported.rows.push((index + 1, row));
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Type the text of a CSV cell by its field
+fn read_text(
+    text: &str,
+    field: &FieldConfig,
+    config: &ImportConfig,
+) -> Result<Value, Box<ValidationError>> {
+    if let Some(date_format) = config.date_formats.get(&field.id) {
+        return read_date(text, date_format, field);
+    }
+    reports::parse_text(text, &field.field_type).ok_or_else(|| {
+        Box::new(ValidationError::new(
+            &field.id,
+            "invalid_value",
+            params([("field", field.name.clone().into()), ("value", text.into())]),
+        ))
+    })
+}
+
+/// Rewrite a date in `strftime` format as an ISO 8601 date, or date-time
+/// for date-time fields
+fn read_date(
+    text: &str,
+    date_format: &str,
+    field: &FieldConfig,
+) -> Result<Value, Box<ValidationError>> {
+    let invalid = || {
+        Box::new(ValidationError::new(
+            &field.id,
+            "date_format",
+            params([
+                ("field", field.name.clone().into()),
+                ("value", text.into()),
+                ("format", date_format.into()),
+            ]),
+        ))
+    };
+    let date = match &field.field_type {
+        FieldType::DateTime { .. } => NaiveDateTime::parse_from_str(text, date_format)
+            .or_else(|_| {
+                NaiveDate::parse_from_str(text, date_format)
+                    .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            })
+            .map_err(|_| invalid())?
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string(),
+        _ => NaiveDate::parse_from_str(text, date_format)
+            .map_err(|_| invalid())?
+            .format("%Y-%m-%d")
+            .to_string(),
+    };
+    Ok(Value::String(date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Vec<FieldConfig> {
+        serde_yaml::from_str(
+            r#"
+- { id: name, name: Name, field_type: text }
+- { id: age, name: Age, field_type: number }
+- { id: born, name: Born, field_type: date }
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_read_csv_with_mapping() {
+        let config: ImportConfig = serde_yaml::from_str(
+            r#"
+columns: { "Full name": name }
+delimiter: ";"
+date_formats: { born: "%d/%m/%Y" }
+skip_rows: 1
+"#,
+        )
+        .unwrap();
+        let text = "exported by crm\nFull name;age;born;notes\nAda;36;10/12/1815;x\n;;;\nBob;old;31/02/2000;\n";
+
+        let imported = read(text, &FileFormat::Csv, &config, &fields()).unwrap();
+        assert_eq!(imported.ignored_columns, vec!["notes".to_string()]);
+        assert_eq!(imported.rows.len(), 2);
+        let (row, ada) = &imported.rows[0];
+        assert_eq!(*row, 1);
+        assert_eq!(ada["name"], serde_json::json!("Ada"));
+        assert_eq!(ada["age"], serde_json::json!(36));
+        assert_eq!(ada["born"], serde_json::json!("1815-12-10"));
+
+        // Blank lines are skipped, and unreadable values reported by row
+        let fields: Vec<_> = imported
+            .errors
+            .iter()
+            .map(|e| e.error.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["age", "born"]);
+        assert!(imported.errors.iter().all(|e| e.row == 2));
+        assert!(!imported.rows[1].1.contains_key("age"));
+    }
+
+    #[test]
+    fn test_read_json_with_mapping() {
+        let config: ImportConfig = serde_yaml::from_str(
+            r#"
+columns: { fullName: name }
+date_formats: { born: "%m/%d/%Y" }
+"#,
+        )
+        .unwrap();
+        let text = r#"{"fullName": "Ada", "born": "12/10/1815"}"#;
+
+        let imported = read(text, &FileFormat::Jsonl, &config, &fields()).unwrap();
+        assert!(imported.errors.is_empty());
+        assert_eq!(imported.rows[0].1["name"], serde_json::json!("Ada"));
+        assert_eq!(imported.rows[0].1["born"], serde_json::json!("1815-12-10"));
+    }
+}
@@ -0,0 +1,579 @@
+//! Referential integrity audit
+//!
+//! Scans the relationships of a backoffice for orphaned rows, whose foreign
+//! key names a record that does not exist, and for dangling junction entries
+//! of many-to-many relationships. When both sides of a relationship share a
+//! data source, the database finds them with an anti-join; otherwise the
+//! referenced keys are loaded and rows compared in memory. Only sections
+//! backed by database data sources can be audited.
+//!
+//! With a fix mode, orphaned foreign keys are set to NULL or their rows are
+//! deleted, and dangling junction entries are deleted in both modes. Deleting
+//! orphans does not cascade: rows referencing them show up in the next audit.
+
+use crate::config::{BackofficeConfig, DataSourceConfig, RelationshipConfig, RelationshipType};
+use crate::data_source::{DataSource, DataSourceRegistry};
+use crate::relationships::{lookup_key, placeholders, section_table};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info, warn};
+
+/// Offending rows listed per issue; `count` has the full number
+const MAX_LISTED_ROWS: usize = 100;
+
+/// Rows changed per remediation statement
+const FIX_BATCH_SIZE: usize = 500;
+
+/// How integrity issues are remediated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityFix {
+    /// Set orphaned foreign keys to NULL
+    #[serde(alias = "set-null")]
+    SetNull,
+    /// Delete rows with orphaned foreign keys
+    Delete,
+}
+
+impl std::str::FromStr for IntegrityFix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "set-null" | "set_null" => Ok(IntegrityFix::SetNull),
+            "delete" => Ok(IntegrityFix::Delete),
+            _ => Err(anyhow!(
+                "Unknown fix '{}'; expected 'set-null' or 'delete'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Rows whose foreign key has no target record
+    OrphanedRows,
+    /// Junction entries missing either side
+    DanglingJunctionEntries,
+}
+
+/// Rows of one table breaking one relationship
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityIssue {
+    pub relationship_id: String,
+    pub kind: IssueKind,
+    pub table: String,
+    /// Foreign key column, or the junction's two columns
+    pub field: String,
+    /// `table.column` the field should reference
+    pub references: String,
+    pub count: usize,
+    /// Ids of orphaned rows, or `[from, to]` pairs of junction entries
+    pub rows: Vec<Value>,
+    /// Rows set to NULL or deleted by the fix
+    pub fixed: usize,
+}
+
+/// Outcome of auditing one backoffice
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IntegrityReport {
+    /// Foreign keys and junction tables checked
+    pub checked: usize,
+    pub issues: Vec<IntegrityIssue>,
+    /// Relationships that could not be checked, with the reason
+    pub skipped: Vec<String>,
+}
+
+/// A column referencing another table's column
+struct ForeignKey<'a> {
+    relationship: &'a RelationshipConfig,
+    section: &'a str,
+    field: &'a str,
+    target_section: &'a str,
+    target_field: &'a str,
+}
+
+/// Foreign keys of a relationship, seen from the referencing side
+///
+/// A ManyToOne and the matching OneToMany describe the same column and map
+/// to the same foreign key.
+fn foreign_key(relationship: &RelationshipConfig) -> Option<ForeignKey<'_>> {
+    let (section, field, target_section, target_field) = match relationship.relationship_type {
+        RelationshipType::ManyToOne | RelationshipType::OneToOne => (
+            &relationship.from_section,
+            &relationship.from_field,
+            &relationship.to_section,
+            &relationship.to_field,
+        ),
+        RelationshipType::OneToMany => (
+            &relationship.to_section,
+            &relationship.to_field,
+            &relationship.from_section,
+            &relationship.from_field,
+        ),
+        RelationshipType::ManyToMany { .. } => return None,
+    };
+    Some(ForeignKey {
+        relationship,
+        section,
+        field,
+        target_section,
+        target_field,
+    })
+}
+
+/// Name of the database data source backing a section
+fn section_source<'a>(backoffice: &'a BackofficeConfig, section_id: &str) -> Result<&'a str> {
+    let section = backoffice
+        .sections
+        .iter()
+        .find(|s| s.id == section_id)
+        .ok_or_else(|| anyhow!("Section not found: {}", section_id))?;
+    let action = section
+        .actions
+        .first()
+        .ok_or_else(|| anyhow!("Section '{}' has no actions", section_id))?;
+    match backoffice.data_sources.get(&action.data_source) {
+        Some(DataSourceConfig::Database { .. }) => Ok(&action.data_source),
+        Some(_) => Err(anyhow!(
+            "Section '{}' is not backed by a database data source",
+            section_id
+        )),
+        None => Err(anyhow!("Data source not found: {}", action.data_source)),
+    }
+}
+
+/// Data sources created on first use
+struct Sources<'a> {
+    backoffice: &'a BackofficeConfig,
+    registry: &'a DataSourceRegistry,
+    created: HashMap<String, Box<dyn DataSource>>,
+}
+
+impl Sources<'_> {
+    async fn get(&mut self, name: &str) -> Result<&dyn DataSource> {
+        if !self.created.contains_key(name) {
+            let config = self
+                .backoffice
+                .data_sources
+                .get(name)
+                .ok_or_else(|| anyhow!("Data source not found: {}", name))?;
+            let data_source = self
+                .registry
+                .create(config)
+                .await
+                .map_err(|e| anyhow!("Failed to create data source '{}': {}", name, e))?;
+            self.created.insert(name.to_string(), data_source);
+        }
+        Ok(self.created[name].as_ref())
+    }
+}
+
+/// Lookup keys of every value of a column
+async fn column_keys(
+    sources: &mut Sources<'_>,
+    section_id: &str,
+    field: &str,
+) -> Result<HashSet<String>> {
+    let backoffice = sources.backoffice;
+    let source = sources.get(section_source(backoffice, section_id)?).await?;
+    let query = format!(
+        "SELECT {} FROM {} WHERE {} IS NOT NULL",
+        field,
+        section_table(backoffice, section_id),
+        field
+    );
+    debug!(query = %query, "Loading referenced keys");
+    Ok(source
+        .execute_query(&query, None)
+        .await?
+        .iter()
+        .filter_map(|row| row.get(field).and_then(lookup_key))
+        .collect())
+}
+
+/// Audit the relationships of a backoffice, remediating issues with `fix`
+///
+/// Relationships whose sections are not backed by databases are reported
+/// as skipped; an error is only returned when a query or fix fails.
+pub async fn audit_backoffice(
+    backoffice: &BackofficeConfig,
+    registry: &DataSourceRegistry,
+    fix: Option<IntegrityFix>,
+) -> Result<IntegrityReport> {
+    let mut sources = Sources {
+        backoffice,
+        registry,
+        created: HashMap::new(),
+    };
+    let mut report = IntegrityReport::default();
+    let mut seen: HashSet<(&str, &str)> = HashSet::new();
+
+    for relationship in &backoffice.relationships {
+        let result = match foreign_key(relationship) {
+            Some(fk) => {
+                if !seen.insert((fk.section, fk.field)) {
+                    continue;
+                }
+                audit_foreign_key(&mut sources, &fk, fix).await
+            }
+            None => audit_junction(&mut sources, relationship, fix).await,
+        };
+        match result {
+            Ok(issue) => {
+                report.checked += 1;
+                report.issues.extend(issue);
+            }
+            Err(e) if e.is::<Skipped>() => {
+                warn!(relationship = %relationship.id, reason = %e, "Skipping integrity check");
+                report.skipped.push(format!("{}: {}", relationship.id, e));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    info!(
+        backoffice = %backoffice.id,
+        checked = report.checked,
+        issues = report.issues.len(),
+        "Audited referential integrity"
+    );
+    Ok(report)
+}
+
+/// A relationship that cannot be audited, as opposed to a failing query
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct Skipped(String);
+
+fn skipped(e: anyhow::Error) -> anyhow::Error {
+    Skipped(e.to_string()).into()
+}
+
+async fn audit_foreign_key(
+    sources: &mut Sources<'_>,
+    fk: &ForeignKey<'_>,
+    fix: Option<IntegrityFix>,
+) -> Result<Option<IntegrityIssue>> {
+    let backoffice = sources.backoffice;
+    let source_name = section_source(backoffice, fk.section).map_err(skipped)?;
+    let target_source = section_source(backoffice, fk.target_section).map_err(skipped)?;
+
+    let table = section_table(backoffice, fk.section);
+    let target_table = section_table(backoffice, fk.target_section);
+    let orphans: Vec<Value> = if target_source == source_name {
+        let query = format!(
+            "SELECT c.id AS id FROM {} c LEFT JOIN {} t ON t.{} = c.{} \
+             WHERE c.{} IS NOT NULL AND t.{} IS NULL",
+            table, target_table, fk.target_field, fk.field, fk.field, fk.target_field
+        );
+        debug!(query = %query, "Finding orphaned rows");
+        sources
+            .get(source_name)
+            .await?
+            .execute_query(&query, None)
+            .await?
+            .into_iter()
+            .filter_map(|mut row| row.remove("id"))
+            .collect()
+    } else {
+        let targets = column_keys(sources, fk.target_section, fk.target_field).await?;
+        let query = format!(
+            "SELECT id, {} FROM {} WHERE {} IS NOT NULL",
+            fk.field, table, fk.field
+        );
+        debug!(query = %query, "Loading foreign keys");
+        sources
+            .get(source_name)
+            .await?
+            .execute_query(&query, None)
+            .await?
+            .into_iter()
+            .filter(|row| {
+                row.get(fk.field)
+                    .and_then(lookup_key)
+                    .is_some_and(|key| !targets.contains(&key))
+            })
+            .filter_map(|mut row| row.remove("id"))
+            .collect()
+    };
+    if orphans.is_empty() {
+        return Ok(None);
+    }
+
+    let mut fixed = 0;
+    if let Some(fix) = fix {
+        let source = sources.get(source_name).await?;
+        for batch in orphans.chunks(FIX_BATCH_SIZE) {
+            let mut params = HashMap::new();
+            let ids = placeholders("id", batch, &mut params);
+            let statement = match fix {
+                IntegrityFix::SetNull => format!(
+                    "UPDATE {} SET {} = NULL WHERE id IN ({})",
+                    table, fk.field, ids
+                ),
+                IntegrityFix::Delete => format!("DELETE FROM {} WHERE id IN ({})", table, ids),
+            };
+            source.execute_mutation(&statement, &params).await?;
+            fixed += batch.len();
+        }
+        info!(table = %table, field = %fk.field, fixed = fixed, "Fixed orphaned rows");
+    }
+
+    Ok(Some(IntegrityIssue {
+        relationship_id: fk.relationship.id.clone(),
+        kind: IssueKind::OrphanedRows,
+        table: table.to_string(),
+        field: fk.field.to_string(),
+        references: format!("{}.{}", target_table, fk.target_field),
+        count: orphans.len(),
+        rows: orphans.into_iter().take(MAX_LISTED_ROWS).collect(),
+        fixed,
+    }))
+}
+
+async fn audit_junction(
+    sources: &mut Sources<'_>,
+    relationship: &RelationshipConfig,
+    fix: Option<IntegrityFix>,
+) -> Result<Option<IntegrityIssue>> {
+    let RelationshipType::ManyToMany {
+        junction_table,
+        from_junction_field,
+        to_junction_field,
+    } = &relationship.relationship_type
+    else {
+        unreachable!("only ManyToMany relationships have junction tables");
+    };
+    let backoffice = sources.backoffice;
+    let source_name = section_source(backoffice, &relationship.from_section).map_err(skipped)?;
+    let to_source = section_source(backoffice, &relationship.to_section).map_err(skipped)?;
+
+    let entry = |mut row: HashMap<String, Value>| {
+        let from = row.remove(from_junction_field).unwrap_or(Value::Null);
+        let to = row.remove(to_junction_field).unwrap_or(Value::Null);
+        (from, to)
+    };
+    let dangling: Vec<(Value, Value)> = if to_source == source_name {
+        // NOT EXISTS rather than joins, so referenced columns holding
+        // duplicates don't repeat entries
+        let query = format!(
+            "SELECT j.{fj} AS {fj}, j.{tj} AS {tj} FROM {} j \
+             WHERE NOT EXISTS (SELECT 1 FROM {} f WHERE f.{} = j.{fj}) \
+             OR NOT EXISTS (SELECT 1 FROM {} t WHERE t.{} = j.{tj})",
+            junction_table,
+            section_table(backoffice, &relationship.from_section),
+            relationship.from_field,
+            section_table(backoffice, &relationship.to_section),
+            relationship.to_field,
+            fj = from_junction_field,
+            tj = to_junction_field,
+        );
+        debug!(query = %query, "Finding dangling junction entries");
+        sources
+            .get(source_name)
+            .await?
+            .execute_query(&query, None)
+            .await?
+            .into_iter()
+            .map(entry)
+            .collect()
+    } else {
+        let from_keys = column_keys(
+            sources,
+            &relationship.from_section,
+            &relationship.from_field,
+        )
+        .await?;
+        let to_keys =
+            column_keys(sources, &relationship.to_section, &relationship.to_field).await?;
+        let query = format!(
+            "SELECT {}, {} FROM {}",
+            from_junction_field, to_junction_field, junction_table
+        );
+        debug!(query = %query, "Loading junction entries");
+        let exists = |value: &Value, keys: &HashSet<String>| {
+            lookup_key(value).is_some_and(|key| keys.contains(&key))
+        };
+        sources
+            .get(source_name)
+            .await?
+            .execute_query(&query, None)
+            .await?
+            .into_iter()
+            .map(entry)
+            .filter(|(from, to)| !exists(from, &from_keys) || !exists(to, &to_keys))
+            .collect()
+    };
+    if dangling.is_empty() {
+        return Ok(None);
+    }
+
+    let mut fixed = 0;
+    if fix.is_some() {
+        let source = sources.get(source_name).await?;
+        let condition = |field: &str, name: &str, value: &Value, params: &mut HashMap<_, _>| {
+            if value.is_null() {
+                return format!("{} IS NULL", field);
+            }
+            params.insert(name.to_string(), value.clone());
+            format!("{} = {{{{{}}}}}", field, name)
+        };
+        for (from, to) in &dangling {
+            let mut params = HashMap::new();
+            let statement = format!(
+                "DELETE FROM {} WHERE {} AND {}",
+                junction_table,
+                condition(from_junction_field, "from", from, &mut params),
+                condition(to_junction_field, "to", to, &mut params)
+            );
+            source.execute_mutation(&statement, &params).await?;
+            fixed += 1;
+        }
+        info!(table = %junction_table, fixed = fixed, "Deleted dangling junction entries");
+    }
+
+    Ok(Some(IntegrityIssue {
+        relationship_id: relationship.id.clone(),
+        kind: IssueKind::DanglingJunctionEntries,
+        table: junction_table.clone(),
+        field: format!("{}, {}", from_junction_field, to_junction_field),
+        references: format!(
+            "{}.{}, {}.{}",
+            section_table(backoffice, &relationship.from_section),
+            relationship.from_field,
+            section_table(backoffice, &relationship.to_section),
+            relationship.to_field
+        ),
+        count: dangling.len(),
+        rows: dangling
+            .into_iter()
+            .take(MAX_LISTED_ROWS)
+            .map(|(from, to)| Value::Array(vec![from, to]))
+            .collect(),
+        fixed,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_audit_backoffice() {
+        let dir = std::env::temp_dir().join(format!("pmp-integrity-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let backoffice: BackofficeConfig = serde_yaml::from_str(&format!(
+            r#"
+id: blog
+name: Blog
+data_sources:
+  db:
+    type: database
+    connection_string: "sqlite://{}?mode=rwc"
+    db_type: sqlite
+relationships:
+  - id: author
+    name: Author
+    relationship_type: manytoone
+    from_section: posts
+    from_field: user_id
+    to_section: users
+    to_field: id
+  - id: user_posts
+    name: Posts
+    relationship_type: onetomany
+    from_section: users
+    from_field: id
+    to_section: posts
+    to_field: user_id
+  - id: tags
+    name: Tags
+    relationship_type: !manytomany
+      junction_table: post_tags
+      from_junction_field: post_id
+      to_junction_field: tag_id
+    from_section: posts
+    from_field: id
+    to_section: tags
+    to_field: id
+sections:
+  - id: users
+    name: Users
+    actions: &actions
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields: []
+  - id: posts
+    name: Posts
+    actions: *actions
+  - id: tags
+    name: Tags
+    actions: *actions
+"#,
+            dir.join("blog.db").display()
+        ))
+        .unwrap();
+
+        let registry = DataSourceRegistry::new();
+        let db = registry
+            .create(&backoffice.data_sources["db"])
+            .await
+            .unwrap();
+        for statement in [
+            "CREATE TABLE users (id TEXT PRIMARY KEY)",
+            "CREATE TABLE posts (id TEXT PRIMARY KEY, user_id TEXT)",
+            "CREATE TABLE tags (id TEXT PRIMARY KEY)",
+            "CREATE TABLE post_tags (post_id TEXT, tag_id TEXT)",
+            "INSERT INTO users VALUES ('u1')",
+            "INSERT INTO posts VALUES ('p1', 'u1'), ('p2', 'gone'), ('p3', NULL), ('it''s', 'gone')",
+            "INSERT INTO tags VALUES ('t1')",
+            "INSERT INTO post_tags VALUES ('p1', 't1'), ('p1', 'gone'), ('gone', 't1')",
+        ] {
+            db.execute_mutation(statement, &HashMap::new())
+                .await
+                .unwrap();
+        }
+
+        let report = audit_backoffice(&backoffice, &registry, None)
+            .await
+            .unwrap();
+        // The ManyToOne and OneToMany share the posts.user_id column
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.issues.len(), 2);
+        assert_eq!(report.issues[0].kind, IssueKind::OrphanedRows);
+        let mut orphans = report.issues[0].rows.clone();
+        orphans.sort_by_key(|id| id.to_string());
+        assert_eq!(
+            orphans,
+            vec![serde_json::json!("it's"), serde_json::json!("p2")]
+        );
+        assert_eq!(report.issues[1].kind, IssueKind::DanglingJunctionEntries);
+        assert_eq!(report.issues[1].count, 2);
+
+        let report = audit_backoffice(&backoffice, &registry, Some(IntegrityFix::SetNull))
+            .await
+            .unwrap();
+        assert_eq!(report.issues[0].fixed, 2);
+        assert_eq!(report.issues[1].fixed, 2);
+        let report = audit_backoffice(&backoffice, &registry, None)
+            .await
+            .unwrap();
+        assert!(report.issues.is_empty());
+        let rows = db
+            .execute_query("SELECT id FROM posts WHERE user_id IS NULL", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,848 @@
+//! JSON:API documents
+//!
+//! Section action routes answer with [JSON:API](https://jsonapi.org)
+//! documents when `Accept` names `application/vnd.api+json`, or when the
+//! backoffice sets `api.format: json_api` and the request doesn't ask for
+//! `application/json`:
+//!
+//! - List and custom actions answer with a collection of resources, view
+//!   actions with one (or null). Members starting with `_`, such as
+//!   `_formatted`, become the resource's `meta`.
+//! - Relationships leaving the section are resource relationships, with a
+//!   `related` link and, when `to_field` is the target's `id`, their linkage.
+//! - `include` embeds relationships like `expand` does, into `included`, and
+//!   `page[number]`/`page[size]` paginate with `links` to the other pages.
+//! - Request bodies of type `application/vnd.api+json` are resource objects
+//!   whose attributes, id and relationship linkage make up the payload.
+//! - Mutations answer with their result as top-level `meta`, and errors as
+//!   `errors` with the codes of [`crate::problem`].
+
+use crate::config::{ActionType, BackofficeConfig, RelationshipConfig, RelationshipType};
+use crate::config::{ResponseFormat, SectionConfig};
+use crate::relationships::lookup_key;
+use crate::server::AppState;
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::warn;
+
+pub const CONTENT_TYPE: &str = "application/vnd.api+json";
+
+/// Backoffice, section and action ids of a section action route
+fn action_route(path: &str) -> Option<(&str, &str, &str)> {
+    let segments: Vec<&str> = path.strip_prefix("/api/backoffices/")?.split('/').collect();
+    match segments[..] {
+        [backoffice, "sections", section, "actions", action] => Some((backoffice, section, action)),
+        _ => None,
+    }
+}
+
+/// Whether a request is answered with JSON:API documents
+fn negotiated(headers: &HeaderMap, format: ResponseFormat) -> bool {
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    accept.contains(CONTENT_TYPE)
+        || (format == ResponseFormat::JsonApi && !accept.contains("application/json"))
+}
+
+fn has_content_type(headers: &HeaderMap, content_type: &str) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with(content_type))
+}
+
+/// Answer section action routes with JSON:API documents when negotiated
+pub async fn documents(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let Some((backoffice_id, section_id, action_id)) = action_route(&path) else {
+        return next.run(request).await;
+    };
+    let Some(backoffice) = state.backoffice(backoffice_id) else {
+        return next.run(request).await;
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return next.run(request).await;
+    };
+    let wanted = negotiated(request.headers(), backoffice.api.format);
+    let has_document = has_content_type(request.headers(), CONTENT_TYPE);
+    if !wanted && !has_document {
+        return next.run(request).await;
+    }
+
+    let original = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| request.uri().clone(), |uri| uri.0.clone());
+    if let Some(uri) = handler_uri(request.uri()) {
+        *request.uri_mut() = uri;
+    }
+    if has_document {
+        let limit = state.config.server.limits.max_body_bytes;
+        let (mut parts, body) = request.into_parts();
+        let document = match axum::body::to_bytes(body, limit).await {
+            Ok(bytes) => serde_json::from_slice::<Value>(&bytes).map_err(|e| {
+                let error = format!("Invalid JSON:API document: {}", e);
+                (StatusCode::BAD_REQUEST, json!({ "error": error }))
+            }),
+            Err(_) => Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                json!({"error": "Request body is too large"}),
+            )),
+        };
+        let payload =
+            document.and_then(|document| payload(&document, section, &backoffice.relationships));
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err((status, body)) => return document_response(status, errors(status, body)),
+        };
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts.headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        request = Request::from_parts(parts, Body::from(payload.to_string()));
+    }
+    let method = request.method().clone();
+
+    let response = next.run(request).await;
+    if !wanted {
+        return response;
+    }
+    let status = response.status();
+    let is_json = has_content_type(response.headers(), "application/json");
+    let is_error = status.is_client_error() || status.is_server_error();
+    if !(is_error || status.is_success() && is_json) {
+        return response;
+    }
+    let Some(action) = section.actions.iter().find(|a| a.id == action_id) else {
+        return response;
+    };
+    let single = match action.action_type {
+        ActionType::List { .. } | ActionType::Custom { .. } => false,
+        ActionType::View { .. } => true,
+        _ if method != Method::GET || is_error => false,
+        // Summaries, reports and the like aren't resources
+        _ => return response,
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) if is_json => serde_json::from_slice(&bytes).unwrap_or_default(),
+        Ok(bytes) => Value::String(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) => {
+            warn!(error = %e, "Failed to read response body for its JSON:API document");
+            Value::Null
+        }
+    };
+    let document = if is_error {
+        errors(status, body)
+    } else if method == Method::GET {
+        let links = Links::new(&original);
+        query_document(body, single, section, backoffice, &links)
+    } else {
+        mutation_document(body)
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE));
+    Response::from_parts(parts, Body::from(document.to_string()))
+}
+
+fn document_response(status: StatusCode, document: Value) -> Response {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, CONTENT_TYPE)
+        .body(Body::from(document.to_string()))
+        .unwrap_or_default()
+}
+
+/// Query parameters of a request, decoded
+fn query_pairs(uri: &Uri) -> Vec<(String, String)> {
+    Query::<Vec<(String, String)>>::try_from_uri(uri)
+        .map(|Query(pairs)| pairs)
+        .unwrap_or_default()
+}
+
+/// The request's URI with JSON:API parameters renamed to those the handlers
+/// take, if it has any
+fn handler_uri(uri: &Uri) -> Option<Uri> {
+    let mut renamed = false;
+    let pairs: Vec<(String, String)> = query_pairs(uri)
+        .into_iter()
+        .map(|(name, value)| {
+            let handler_name = match name.as_str() {
+                "include" => "expand",
+                "page[number]" => "page",
+                "page[size]" => "page_size",
+                _ => return (name, value),
+            };
+            renamed = true;
+            (handler_name.to_string(), value)
+        })
+        .collect();
+    if !renamed {
+        return None;
+    }
+    let query = serde_urlencoded::to_string(&pairs).ok()?;
+    format!("{}?{}", uri.path(), query).parse().ok()
+}
+
+/// The payload of a JSON:API request document, or the error to answer with
+fn payload(
+    document: &Value,
+    section: &SectionConfig,
+    relationships: &[RelationshipConfig],
+) -> Result<Value, (StatusCode, Value)> {
+    let invalid = |error: &str| (StatusCode::BAD_REQUEST, json!({ "error": error }));
+    let Some(resource) = document.get("data").and_then(Value::as_object) else {
+        return Err(invalid(
+            "JSON:API documents need a resource object in `data`",
+        ));
+    };
+    if let Some(resource_type) = resource.get("type").and_then(Value::as_str) {
+        if resource_type != section.id {
+            let error = format!(
+                "Resources of type '{}' can't be sent to section '{}'",
+                resource_type, section.id
+            );
+            return Err((StatusCode::CONFLICT, json!({ "error": error })));
+        }
+    }
+
+    let mut payload = match resource.get("attributes") {
+        Some(Value::Object(attributes)) => attributes.clone(),
+        None | Some(Value::Null) => Map::new(),
+        Some(_) => return Err(invalid("`attributes` must be an object")),
+    };
+    if let Some(id) = resource.get("id") {
+        payload.insert("id".to_string(), id.clone());
+    }
+    let linkage = resource.get("relationships").and_then(Value::as_object);
+    for relationship in leaving(relationships, &section.id) {
+        let Some(data) = linkage
+            .and_then(|linkage| linkage.get(&relationship.id))
+            .and_then(|relationship| relationship.get("data"))
+        else {
+            continue;
+        };
+        if relationship.to_field != "id" {
+            return Err(invalid(&format!(
+                "Relationship '{}' can't be set with resource linkage",
+                relationship.id
+            )));
+        }
+        let id = |identifier: &Value| identifier.get("id").cloned().unwrap_or(Value::Null);
+        let value = match data {
+            Value::Array(identifiers) => identifiers.iter().map(id).collect(),
+            identifier => id(identifier),
+        };
+        payload.insert(relationship.from_field.clone(), value);
+    }
+    if let Some(accept) = document.pointer("/meta/accept_warnings") {
+        payload.insert("accept_warnings".to_string(), accept.clone());
+    }
+    Ok(Value::Object(payload))
+}
+
+/// Relationships leaving a section
+fn leaving<'a>(
+    relationships: &'a [RelationshipConfig],
+    section_id: &'a str,
+) -> impl Iterator<Item = &'a RelationshipConfig> {
+    relationships
+        .iter()
+        .filter(move |relationship| relationship.from_section == section_id)
+}
+
+fn is_to_one(relationship: &RelationshipConfig) -> bool {
+    matches!(
+        relationship.relationship_type,
+        RelationshipType::ManyToOne | RelationshipType::OneToOne
+    )
+}
+
+/// Links of a query document, from the URI the client requested
+struct Links {
+    uri: Uri,
+    /// Path of the section, e.g. `/api/v1/backoffices/crm/sections/users`
+    section: String,
+}
+
+impl Links {
+    fn new(uri: &Uri) -> Self {
+        let section = uri
+            .path()
+            .rsplit_once("/actions/")
+            .map_or(uri.path(), |(section, _)| section)
+            .to_string();
+        Self {
+            uri: uri.clone(),
+            section,
+        }
+    }
+
+    fn related(&self, id: &str, relationship: &str) -> String {
+        format!(
+            "{}/records/{}/relationships/{}",
+            self.section, id, relationship
+        )
+    }
+
+    /// The requested URI for another page
+    fn page(&self, number: u64, size: u64) -> String {
+        let mut pairs: Vec<(String, String)> = query_pairs(&self.uri)
+            .into_iter()
+            .filter(|(name, _)| {
+                !matches!(
+                    name.as_str(),
+                    "page" | "page_size" | "page[number]" | "page[size]"
+                )
+            })
+            .collect();
+        pairs.push(("page[number]".to_string(), number.to_string()));
+        pairs.push(("page[size]".to_string(), size.to_string()));
+        let query = serde_urlencoded::to_string(&pairs).unwrap_or_default();
+        format!("{}?{}", self.uri.path(), query)
+    }
+}
+
+/// The JSON:API document of a query action's body
+fn query_document(
+    body: Value,
+    single: bool,
+    section: &SectionConfig,
+    backoffice: &BackofficeConfig,
+    links: &Links,
+) -> Value {
+    let mut meta = match body {
+        Value::Object(body) => body,
+        _ => Map::new(),
+    };
+    let rows = match meta.remove("data") {
+        Some(Value::Array(rows)) => rows,
+        Some(Value::Null) | None => Vec::new(),
+        Some(row) => vec![row],
+    };
+    let relationships: Vec<&RelationshipConfig> =
+        leaving(&backoffice.relationships, &section.id).collect();
+
+    let mut included = Included::default();
+    let resources: Vec<Value> = rows
+        .into_iter()
+        .map(|row| resource(&section.id, row, &relationships, links, &mut included))
+        .collect();
+    let data = if single {
+        resources.into_iter().next().unwrap_or(Value::Null)
+    } else {
+        Value::Array(resources)
+    };
+
+    let mut document_links = Map::new();
+    document_links.insert("self".to_string(), links.uri.to_string().into());
+    if let Some(pagination) = meta.get("pagination") {
+        let number = |key: &str| pagination[key].as_u64().unwrap_or_default();
+        let (page, size, pages) = (number("page"), number("page_size"), number("total_pages"));
+        document_links.insert("first".to_string(), links.page(1, size).into());
+        document_links.insert("last".to_string(), links.page(pages.max(1), size).into());
+        let prev = (page > 1).then(|| links.page(page - 1, size));
+        document_links.insert("prev".to_string(), prev.into());
+        let next = (page < pages).then(|| links.page(page + 1, size));
+        document_links.insert("next".to_string(), next.into());
+    }
+
+    let mut document = json!({
+        "jsonapi": {"version": "1.1"},
+        "data": data,
+        "links": document_links,
+    });
+    if !included.resources.is_empty() {
+        document["included"] = Value::Array(included.resources);
+    }
+    if !meta.is_empty() {
+        document["meta"] = Value::Object(meta);
+    }
+    document
+}
+
+/// Related resources of a document, each once
+#[derive(Default)]
+struct Included {
+    seen: HashSet<(String, String)>,
+    resources: Vec<Value>,
+}
+
+impl Included {
+    /// The identifier of a related record, adding the record to `included`
+    fn add(&mut self, resource_type: &str, record: Value, to_field: &str) -> Value {
+        let Value::Object(mut record) = record else {
+            return Value::Null;
+        };
+        let Some(id) = record
+            .get("id")
+            .or_else(|| record.get(to_field))
+            .and_then(lookup_key)
+        else {
+            return Value::Null;
+        };
+        let identifier = json!({"type": resource_type, "id": id});
+        if self.seen.insert((resource_type.to_string(), id)) {
+            record.remove("id");
+            record.retain(|name, _| !name.starts_with('_'));
+            let mut resource = identifier.clone();
+            resource["attributes"] = Value::Object(record);
+            self.resources.push(resource);
+        }
+        identifier
+    }
+}
+
+/// The resource object of a row
+fn resource(
+    resource_type: &str,
+    row: Value,
+    relationships: &[&RelationshipConfig],
+    links: &Links,
+    included: &mut Included,
+) -> Value {
+    let Value::Object(row) = row else {
+        return Value::Null;
+    };
+    let id = row.get("id").and_then(lookup_key);
+    let mut attributes = Map::new();
+    let mut meta = Map::new();
+    let mut expanded = Map::new();
+    for (name, value) in row {
+        match name.strip_prefix('_') {
+            Some("expanded") => {
+                if let Value::Object(value) = value {
+                    expanded = value;
+                }
+            }
+            Some(annotation) => {
+                meta.insert(annotation.to_string(), value);
+            }
+            None if name == "id" => {}
+            None => {
+                attributes.insert(name, value);
+            }
+        }
+    }
+
+    let mut resource_relationships = Map::new();
+    for relationship in relationships {
+        let target = relationship.to_section.as_str();
+        let linkage = match expanded.remove(&relationship.id) {
+            Some(Value::Array(records)) => Some(Value::Array(
+                records
+                    .into_iter()
+                    .map(|record| included.add(target, record, &relationship.to_field))
+                    .collect(),
+            )),
+            Some(record) => Some(included.add(target, record, &relationship.to_field)),
+            None if relationship.to_field == "id" => {
+                let value = if relationship.from_field == "id" {
+                    id.clone().map(Value::from)
+                } else {
+                    attributes.get(&relationship.from_field).cloned()
+                };
+                let identifier = |value: &Value| {
+                    lookup_key(value).map_or(Value::Null, |id| json!({"type": target, "id": id}))
+                };
+                Some(match value {
+                    Some(Value::Array(values)) => {
+                        Value::Array(values.iter().map(identifier).collect())
+                    }
+                    Some(value) if is_to_one(relationship) => identifier(&value),
+                    Some(value) => Value::Array(vec![identifier(&value)]),
+                    None if is_to_one(relationship) => Value::Null,
+                    None => Value::Array(Vec::new()),
+                })
+            }
+            None => None,
+        };
+
+        let mut entry = Map::new();
+        if let Some(id) = &id {
+            let related = links.related(id, &relationship.id);
+            entry.insert("links".to_string(), json!({ "related": related }));
+        }
+        if let Some(linkage) = linkage {
+            entry.insert("data".to_string(), linkage);
+        }
+        if !entry.is_empty() {
+            resource_relationships.insert(relationship.id.clone(), Value::Object(entry));
+        }
+    }
+
+    let mut resource = json!({"type": resource_type, "attributes": attributes});
+    if let Some(id) = id {
+        resource["id"] = id.into();
+    }
+    if !resource_relationships.is_empty() {
+        resource["relationships"] = Value::Object(resource_relationships);
+    }
+    if !meta.is_empty() {
+        resource["meta"] = Value::Object(meta);
+    }
+    resource
+}
+
+/// Mutation results are the document's `meta`
+fn mutation_document(body: Value) -> Value {
+    let meta = match body {
+        Value::Object(mut body) => {
+            body.remove("success");
+            body
+        }
+        body => Map::from_iter([("data".to_string(), body)]),
+    };
+    json!({"jsonapi": {"version": "1.1"}, "meta": meta})
+}
+
+/// The `errors` document of an error body; validation errors point at the
+/// attribute they are about
+fn errors(status: StatusCode, body: Value) -> Value {
+    let mut members = match body {
+        Value::Object(members) => members,
+        Value::String(text) if !text.trim().is_empty() => {
+            Map::from_iter([("error".to_string(), Value::String(text))])
+        }
+        _ => Map::new(),
+    };
+    let code = crate::problem::code_for(status, &members);
+    members.remove("code");
+    let detail = members.remove("error");
+    let status_text = status.as_u16().to_string();
+
+    let validation_errors = match members.remove("validation_errors") {
+        Some(Value::Array(errors)) if !errors.is_empty() => errors,
+        _ => Vec::new(),
+    };
+    let errors: Vec<Value> = if validation_errors.is_empty() {
+        let mut error = json!({"status": status_text, "code": code.code, "title": code.title});
+        if let Some(detail) = detail {
+            error["detail"] = detail;
+        }
+        vec![error]
+    } else {
+        validation_errors
+            .into_iter()
+            .map(|error| {
+                let field = error["field"].as_str().unwrap_or_default();
+                json!({
+                    "status": status_text,
+                    "code": error.get("code").cloned().unwrap_or(code.code.into()),
+                    "title": code.title,
+                    "detail": error["message"],
+                    "source": {"pointer": format!("/data/attributes/{}", field)},
+                })
+            })
+            .collect()
+    };
+
+    if let Some(id) = crate::request_id::current() {
+        members.insert("request_id".to_string(), id.into());
+    }
+    let mut document = json!({"jsonapi": {"version": "1.1"}, "errors": errors});
+    if !members.is_empty() {
+        document["meta"] = Value::Object(members);
+    }
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::RouterBuilder;
+    use crate::test_support::sqlite_backoffice;
+
+    fn backoffice() -> BackofficeConfig {
+        serde_yaml::from_str(
+            r#"
+id: blog
+name: Blog
+data_sources: {}
+sections:
+  - id: posts
+    name: Posts
+    actions: []
+relationships:
+  - id: author
+    name: Author
+    relationship_type: manytoone
+    from_section: posts
+    from_field: author_id
+    to_section: users
+    to_field: id
+  - id: comments
+    name: Comments
+    relationship_type: onetomany
+    from_section: posts
+    from_field: id
+    to_section: comments
+    to_field: post_id
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_query_document() {
+        let backoffice = backoffice();
+        let uri: Uri = "/api/v1/backoffices/blog/sections/posts/actions/list?page%5Bnumber%5D=2&page%5Bsize%5D=1&include=author"
+            .parse()
+            .unwrap();
+        let body = json!({
+            "data": [
+                {"id": 7, "title": "Hello", "author_id": "1", "_formatted": {"views": "1,000"},
+                 "_expanded": {"author": {"id": 1, "name": "ada"}}},
+                {"id": 8, "title": "Again", "author_id": null},
+            ],
+            "pagination": {"page": 2, "page_size": 1, "total_items": 3, "total_pages": 3},
+        });
+        let document = query_document(
+            body,
+            false,
+            &backoffice.sections[0],
+            &backoffice,
+            &Links::new(&uri),
+        );
+
+        let post = &document["data"][0];
+        assert_eq!(post["type"], "posts");
+        assert_eq!(post["id"], "7");
+        assert_eq!(
+            post["attributes"],
+            json!({"title": "Hello", "author_id": "1"})
+        );
+        assert_eq!(post["meta"], json!({"formatted": {"views": "1,000"}}));
+        assert_eq!(
+            post["relationships"]["author"],
+            json!({
+                "links": {"related": "/api/v1/backoffices/blog/sections/posts/records/7/relationships/author"},
+                "data": {"type": "users", "id": "1"},
+            })
+        );
+        // Linkage of relationships to other fields than `id` needs a lookup
+        assert!(post["relationships"]["comments"].get("data").is_none());
+        assert_eq!(
+            document["data"][1]["relationships"]["author"]["data"],
+            Value::Null
+        );
+        assert_eq!(
+            document["included"],
+            json!([{"type": "users", "id": "1", "attributes": {"name": "ada"}}])
+        );
+
+        assert_eq!(document["meta"]["pagination"]["total_items"], 3);
+        let links = &document["links"];
+        assert_eq!(
+            links["next"],
+            "/api/v1/backoffices/blog/sections/posts/actions/list?include=author&page%5Bnumber%5D=3&page%5Bsize%5D=1"
+        );
+        assert_eq!(
+            links["first"],
+            "/api/v1/backoffices/blog/sections/posts/actions/list?include=author&page%5Bnumber%5D=1&page%5Bsize%5D=1"
+        );
+    }
+
+    #[test]
+    fn test_payload() {
+        let backoffice = backoffice();
+        let section = &backoffice.sections[0];
+        let document = json!({
+            "data": {
+                "type": "posts",
+                "id": "7",
+                "attributes": {"title": "Hello"},
+                "relationships": {"author": {"data": {"type": "users", "id": "1"}}},
+            },
+            "meta": {"accept_warnings": true},
+        });
+        assert_eq!(
+            payload(&document, section, &backoffice.relationships).unwrap(),
+            json!({"title": "Hello", "id": "7", "author_id": "1", "accept_warnings": true})
+        );
+
+        let other = json!({"data": {"type": "users", "attributes": {}}});
+        let (status, _) = payload(&other, section, &backoffice.relationships).unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        assert_eq!(
+            handler_uri(&"/x?include=author&page%5Bsize%5D=5".parse().unwrap()).unwrap(),
+            "/x?expand=author&page_size=5"
+        );
+        assert!(handler_uri(&"/x?q=ada".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        let invalid = json!({
+            "error": "Validation failed",
+            "validation_errors": [{"field": "title", "code": "required", "message": "Title is required"}],
+        });
+        assert_eq!(
+            errors(StatusCode::BAD_REQUEST, invalid)["errors"],
+            json!([{
+                "status": "400",
+                "code": "required",
+                "title": "Validation failed",
+                "detail": "Title is required",
+                "source": {"pointer": "/data/attributes/title"},
+            }])
+        );
+
+        let missing = errors(StatusCode::NOT_FOUND, json!("Backoffice not found"));
+        assert_eq!(missing["errors"][0]["code"], "not_found");
+        assert_eq!(missing["errors"][0]["detail"], "Backoffice not found");
+    }
+
+    #[tokio::test]
+    async fn test_json_api_documents() {
+        use tower::ServiceExt;
+
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: blog
+name: Blog
+api: { format: json_api }
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+  - id: posts
+    name: Posts
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        config: { enable_pagination: true, page_size: 10 }
+        fields:
+          - { id: title, name: Title, field_type: text }
+          - { id: author_id, name: Author, field_type: text }
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: title, name: Title, field_type: text, required: true }
+          - { id: author_id, name: Author, field_type: text }
+relationships:
+  - id: author
+    name: Author
+    relationship_type: manytoone
+    from_section: posts
+    from_field: author_id
+    to_section: users
+    to_field: id
+"#,
+            &[
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+                "CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT, author_id TEXT)",
+                "INSERT INTO users (name) VALUES ('ada')",
+            ],
+        )
+        .await;
+        let router = RouterBuilder::new(state.config.clone(), state.backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .build();
+        let send = |request: Request<axum::body::Body>| {
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let content_type = response.headers()["content-type"].clone();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let body: Value = serde_json::from_slice(&bytes).unwrap();
+                (status, content_type, body)
+            }
+        };
+        let posts = "/api/backoffices/blog/sections/posts/actions";
+
+        let create = |document: Value| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("{}/create", posts))
+                .header("content-type", crate::json_api::CONTENT_TYPE)
+                .body(axum::body::Body::from(document.to_string()))
+                .unwrap()
+        };
+        let (status, content_type, body) = send(create(serde_json::json!({
+            "data": {
+                "type": "posts",
+                "attributes": {"title": "Hello"},
+                "relationships": {"author": {"data": {"type": "users", "id": "1"}}}
+            }
+        })))
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(content_type, crate::json_api::CONTENT_TYPE);
+        assert!(body["meta"].get("data").is_some());
+
+        let (status, _, body) = send(create(serde_json::json!({
+            "data": {"type": "posts", "attributes": {}}
+        })))
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["errors"][0]["source"]["pointer"],
+            "/data/attributes/title"
+        );
+
+        // The backoffice answers with JSON:API unless asked for plain JSON
+        let list = |accept: &str| {
+            Request::builder()
+                .uri(format!("{}/list?include=author&page[size]=1", posts))
+                .header("accept", accept)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let (status, content_type, body) = send(list("*/*")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, crate::json_api::CONTENT_TYPE);
+        let post = &body["data"][0];
+        assert_eq!(post["type"], "posts");
+        assert_eq!(post["attributes"]["title"], "Hello");
+        assert_eq!(
+            post["relationships"]["author"]["data"],
+            serde_json::json!({"type": "users", "id": "1"})
+        );
+        assert_eq!(body["included"][0]["attributes"]["name"], "ada");
+        assert_eq!(body["meta"]["pagination"]["page_size"], 1);
+        assert_eq!(body["links"]["next"], Value::Null);
+
+        let (_, content_type, body) = send(list("application/json")).await;
+        assert_eq!(content_type, "application/json");
+        assert_eq!(body["data"][0]["title"], "Hello");
+    }
+}
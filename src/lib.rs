@@ -1,12 +1,61 @@
 // Library exports for testing and potential reuse
 
+pub mod access_log;
+pub mod antivirus;
 pub mod audit;
+pub mod auth;
+pub mod cache;
+pub mod card;
+pub mod circuit_breaker;
+pub mod codegen;
+pub mod conditional;
 pub mod config;
+pub mod config_history;
+pub mod config_store;
+pub mod crud;
+pub mod currency;
 pub mod data_source;
+pub mod duplicate;
+pub mod email;
+pub mod expression;
+pub mod feature_flags;
+pub mod form_state;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod health;
+pub mod hooks;
+pub mod i18n;
+pub mod imports;
+pub mod integrity;
+pub mod json_api;
+pub mod masking;
+pub mod migrations;
+pub mod openapi;
+pub mod permissions;
+pub mod phone;
+pub mod pii;
+pub mod pipeline;
+pub mod plugins;
+pub mod problem;
+pub mod push;
 pub mod relationships;
+pub mod reports;
+pub mod request_id;
+pub mod row_filters;
+pub mod scaffold;
+pub mod search;
+pub mod seeds;
 pub mod server;
+pub mod signature;
+pub mod state_machine;
+pub mod summary;
+#[cfg(test)]
+mod test_support;
 pub mod validation;
+pub mod versioning;
 
 // Re-export commonly used types
 pub use config::{AppConfig, BackofficeConfig};
-pub use server::AppState;
+pub use server::{build_router, AppState, RouterBuilder};
@@ -1,11 +1,12 @@
-mod audit;
-mod config;
-mod data_source;
-mod relationships;
-mod server;
-mod validation;
-
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use pmp_backoffice_generator::config::{AppConfig, BackofficeConfig, DataSourceConfig};
+use pmp_backoffice_generator::config_history::ConfigHistory;
+use pmp_backoffice_generator::config_store::ConfigStore;
+use pmp_backoffice_generator::data_source::DataSourceRegistry;
+use pmp_backoffice_generator::integrity::{self, IntegrityFix};
+use pmp_backoffice_generator::{codegen, config, migrations, openapi, seeds, server};
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
 
@@ -43,9 +44,23 @@ async fn main() -> Result<()> {
         }
     };
 
-    // Load backoffice configurations
-    info!("Loading backoffice configurations from config/backoffices...");
-    let backoffices = match config::load_backoffices("config/backoffices").await {
+    // Pull backoffice configurations from the remote store, if any
+    let store = app_config.config_store.as_ref().map(ConfigStore::new);
+    let config_dir = match &store {
+        Some(store) => {
+            info!(cache_dir = ?store.dir(), "Pulling backoffice configurations from the config store...");
+            if let Err(e) = store.sync().await {
+                warn!(error = %e, "Failed to pull backoffice configurations, loading the local copy");
+            }
+            store.dir().to_path_buf()
+        }
+        None => PathBuf::from("config/backoffices"),
+    };
+
+    // Load backoffice configurations, keeping their versions
+    info!(directory = ?config_dir, "Loading backoffice configurations...");
+    let history = ConfigHistory::new(&app_config.config_history);
+    let backoffices = match history.load(&config_dir).await {
         Ok(configs) => {
             info!(
                 count = configs.len(),
@@ -89,6 +104,55 @@ async fn main() -> Result<()> {
         warn!("No backoffice configurations found! The application will start but have no backends available.");
     }
 
+    // `migrate` applies every configured migration, `migrate status` lists
+    // them, `seed` inserts section fixtures, `integrity [--fix MODE]` audits
+    // relationships, `export-openapi --out FILE` and `export-postman --out
+    // FILE` write the API of the configs and `codegen ts --out FILE` a
+    // TypeScript client of it, instead of starting the server
+    let mut args = std::env::args().skip(1);
+    if let Some(command) = args.next() {
+        return match (command.as_str(), args.next().as_deref()) {
+            ("migrate", None) => migrations::run_all(&backoffices, false).await,
+            ("migrate", Some("status")) => print_migration_status(&backoffices).await,
+            ("seed", None) => seed(&app_config, &backoffices).await,
+            ("integrity", None) => audit_integrity(&app_config, &backoffices, None).await,
+            ("integrity", Some("--fix")) => {
+                let fix = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--fix needs 'set-null' or 'delete'"))?
+                    .parse()?;
+                audit_integrity(&app_config, &backoffices, Some(fix)).await
+            }
+            ("export-openapi" | "export-postman", Some("--out")) => {
+                let out = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--out needs a file"))?;
+                export(&command, &app_config, &backoffices, &out).await
+            }
+            ("codegen", Some("ts")) => {
+                let out = match (args.next().as_deref(), args.next()) {
+                    (Some("--out"), Some(out)) => out,
+                    _ => return Err(anyhow!("codegen ts needs --out FILE")),
+                };
+                tokio::fs::write(&out, codegen::typescript(&backoffices))
+                    .await
+                    .map_err(|e| anyhow!("Failed to write {}: {}", out, e))?;
+                info!(file = %out, backoffices = backoffices.len(), "Generated TypeScript client");
+                Ok(())
+            }
+            _ => Err(anyhow!(
+                "Unknown command '{}'; expected 'migrate', 'migrate status', 'seed', 'integrity [--fix set-null|delete]', 'export-openapi --out FILE', 'export-postman --out FILE' or 'codegen ts --out FILE'",
+                command
+            )),
+        };
+    }
+
+    info!("Applying startup migrations...");
+    if let Err(e) = migrations::run_all(&backoffices, true).await {
+        error!(error = %e, "Failed to apply migrations");
+        return Err(e);
+    }
+
     // Start web server
     info!("Starting web server...");
     let bind_addr = format!("{}:{}", app_config.server.host, app_config.server.port);
@@ -98,7 +162,17 @@ async fn main() -> Result<()> {
         "Starting HTTP server"
     );
 
-    match server::start_server(app_config, backoffices).await {
+    let poll = async {
+        match &store {
+            Some(store) => store.poll_for_restart().await,
+            None => std::future::pending().await,
+        }
+    };
+    let served = tokio::select! {
+        served = server::start_server(app_config, backoffices) => served,
+        _ = poll => Ok(()),
+    };
+    match served {
         Ok(_) => {
             info!("Server shutdown gracefully");
             Ok(())
@@ -109,3 +183,141 @@ async fn main() -> Result<()> {
         }
     }
 }
+
+/// Print every configured migration and whether it has been applied
+async fn print_migration_status(backoffices: &[BackofficeConfig]) -> Result<()> {
+    for backoffice in backoffices {
+        let mut names: Vec<_> = backoffice.data_sources.keys().collect();
+        names.sort();
+        for name in names {
+            let config = &backoffice.data_sources[name];
+            if !matches!(
+                config,
+                DataSourceConfig::Database {
+                    migrations: Some(_),
+                    ..
+                }
+            ) {
+                continue;
+            }
+
+            println!("{} / {}", backoffice.id, name);
+            for migration in migrations::status(config).await? {
+                println!(
+                    "  {:>14}  {:<7}  {}",
+                    migration.version,
+                    if migration.applied {
+                        "applied"
+                    } else {
+                        "pending"
+                    },
+                    migration.description
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write the OpenAPI spec (YAML, or JSON for `.json` files) or the Postman
+/// collection of the backoffices to `out`
+async fn export(
+    command: &str,
+    app_config: &AppConfig,
+    backoffices: &[BackofficeConfig],
+    out: &str,
+) -> Result<()> {
+    let content = if command == "export-postman" {
+        let base_url = format!("http://localhost:{}", app_config.server.port);
+        serde_json::to_string_pretty(&openapi::postman(backoffices, &base_url))?
+    } else {
+        let spec = openapi::spec(backoffices)?;
+        if out.ends_with(".json") {
+            serde_json::to_string_pretty(&spec)?
+        } else {
+            serde_yaml::to_string(&spec)?
+        }
+    };
+    tokio::fs::write(out, content)
+        .await
+        .map_err(|e| anyhow!("Failed to write {}: {}", out, e))?;
+    info!(file = %out, backoffices = backoffices.len(), "Exported {}", command.trim_start_matches("export-"));
+    Ok(())
+}
+
+/// Insert the seed fixtures of every backoffice, failing if any record fails
+async fn seed(app_config: &AppConfig, backoffices: &[BackofficeConfig]) -> Result<()> {
+    let mut registry = DataSourceRegistry::new();
+    registry.set_http_timeout(Duration::from_secs(
+        app_config.server.limits.data_source_timeout_secs,
+    ));
+    let mut failed = 0;
+    for backoffice in backoffices {
+        for report in seeds::seed_backoffice(backoffice, &registry).await? {
+            println!(
+                "{} / {}: {} inserted, {} failed",
+                backoffice.id,
+                report.section_id,
+                report.inserted,
+                report.failed.len()
+            );
+            for failure in &report.failed {
+                println!(
+                    "  {} record {}: {}",
+                    failure.file,
+                    failure.record,
+                    failure.errors.join("; ")
+                );
+            }
+            failed += report.failed.len();
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("{} seed records failed", failed));
+    }
+    Ok(())
+}
+
+/// Report relationship integrity issues of every backoffice, remediating them
+/// with `fix`; fails if issues remain unfixed
+async fn audit_integrity(
+    app_config: &AppConfig,
+    backoffices: &[BackofficeConfig],
+    fix: Option<IntegrityFix>,
+) -> Result<()> {
+    let mut registry = DataSourceRegistry::new();
+    registry.set_http_timeout(Duration::from_secs(
+        app_config.server.limits.data_source_timeout_secs,
+    ));
+    let mut unfixed = 0;
+    for backoffice in backoffices {
+        let report = integrity::audit_backoffice(backoffice, &registry, fix).await?;
+        println!(
+            "{}: {} checked, {} issues",
+            backoffice.id,
+            report.checked,
+            report.issues.len()
+        );
+        for issue in &report.issues {
+            println!(
+                "  {} {}.{} -> {}: {} rows, {} fixed",
+                issue.relationship_id,
+                issue.table,
+                issue.field,
+                issue.references,
+                issue.count,
+                issue.fixed
+            );
+            unfixed += issue.count - issue.fixed;
+        }
+        for skipped in &report.skipped {
+            println!("  skipped {}", skipped);
+        }
+    }
+
+    if unfixed > 0 {
+        return Err(anyhow!("{} rows break referential integrity", unfixed));
+    }
+    Ok(())
+}
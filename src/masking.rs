@@ -0,0 +1,121 @@
+//! Redaction of sensitive field values
+
+use crate::auth::AuthContext;
+use crate::config::FieldConfig;
+use crate::pii;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Mask used when a sensitive field has no `mask` pattern
+pub const DEFAULT_MASK: &str = "****";
+
+/// Mask sensitive fields in query results unless the caller may see them,
+/// and scrub personal data out of fields with `no_pii` rules in `scrub` mode
+pub fn mask_rows(rows: &mut [HashMap<String, Value>], fields: &[FieldConfig], auth: &AuthContext) {
+    pii::scrub_rows(rows, fields);
+
+    let hidden: Vec<&FieldConfig> = fields
+        .iter()
+        .filter(|f| f.sensitive && !can_reveal(f, auth))
+        .collect();
+
+    if hidden.is_empty() {
+        return;
+    }
+
+    for row in rows.iter_mut() {
+        mask_fields(row, &hidden);
+    }
+}
+
+/// Copy of a record with every sensitive field masked, e.g. for audit logs
+pub fn masked(record: &HashMap<String, Value>, fields: &[FieldConfig]) -> HashMap<String, Value> {
+    let sensitive: Vec<&FieldConfig> = fields.iter().filter(|f| f.sensitive).collect();
+    let mut record = record.clone();
+    mask_fields(&mut record, &sensitive);
+    record
+}
+
+fn can_reveal(field: &FieldConfig, auth: &AuthContext) -> bool {
+    !field.unmask_scopes.is_empty() && auth.has_any_scope(&field.unmask_scopes)
+}
+
+fn mask_fields(record: &mut HashMap<String, Value>, fields: &[&FieldConfig]) {
+    for field in fields {
+        if let Some(value) = record.get_mut(&field.id) {
+            if !value.is_null() {
+                *value = Value::String(mask_value(value, field.mask.as_deref()));
+            }
+        }
+    }
+}
+
+/// Apply a mask pattern to a value
+///
+/// `{lastN}` and `{firstN}` in the pattern are replaced with the last/first
+/// N characters of the value, e.g. `****{last4}`.
+pub fn mask_value(value: &Value, pattern: Option<&str>) -> String {
+    let pattern = pattern.unwrap_or(DEFAULT_MASK);
+    let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let chars: Vec<char> = text.chars().collect();
+
+    let mut output = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start..];
+
+        let Some(end) = after.find('}') else {
+            output.push_str(after);
+            rest = "";
+            break;
+        };
+
+        let placeholder = &after[1..end];
+        // Never reveal more than half of the value
+        let reveal = |n: usize| n.min(chars.len() / 2);
+        if let Some(n) = placeholder
+            .strip_prefix("last")
+            .and_then(|n| n.parse().ok())
+        {
+            let n = reveal(n);
+            output.extend(&chars[chars.len() - n..]);
+        } else if let Some(n) = placeholder
+            .strip_prefix("first")
+            .and_then(|n| n.parse().ok())
+        {
+            let n = reveal(n);
+            output.extend(&chars[..n]);
+        } else {
+            output.push_str(&after[..=end]);
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_mask_value_patterns() {
+        assert_eq!(mask_value(&json!("hunter2"), None), "****");
+        assert_eq!(
+            mask_value(&json!("4111111111111111"), Some("****{last4}")),
+            "****1111"
+        );
+        assert_eq!(
+            mask_value(&json!("sk_live_abcdef"), Some("{first3}...")),
+            "sk_..."
+        );
+        // Short values never reveal more than half
+        assert_eq!(mask_value(&json!("12"), Some("*{last4}")), "*2");
+    }
+}
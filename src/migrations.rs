@@ -0,0 +1,217 @@
+//! Database migrations
+//!
+//! Database data sources can point `migrations.path` at a directory of sqlx
+//! migrations, e.g. for the backoffice's own support tables. Pending
+//! migrations are applied on startup when `run_on_startup` is set or with the
+//! `migrate` subcommand, and the status endpoint lists which versions have
+//! been applied.
+
+use crate::config::{BackofficeConfig, DataSourceConfig, DatabaseType, MigrationsConfig};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::Connection;
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::info;
+
+/// A migration found in the migrations directory
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+/// Open a single connection to the primary of the configured backend
+macro_rules! with_connection {
+    ($db_type:expr, $url:expr, $conn:ident => $body:expr) => {
+        match $db_type {
+            DatabaseType::Postgres => {
+                let mut $conn = sqlx::PgConnection::connect($url)
+                    .await
+                    .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
+                $body
+            }
+            DatabaseType::MySQL => {
+                let mut $conn = sqlx::MySqlConnection::connect($url)
+                    .await
+                    .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
+                $body
+            }
+            DatabaseType::Sqlite => {
+                let mut $conn = sqlx::SqliteConnection::connect($url)
+                    .await
+                    .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
+                $body
+            }
+        }
+    };
+}
+
+fn migrations_config(
+    config: &DataSourceConfig,
+) -> Result<(&str, &DatabaseType, &MigrationsConfig)> {
+    match config {
+        DataSourceConfig::Database {
+            connection_string,
+            db_type,
+            migrations: Some(migrations),
+            ..
+        } => Ok((connection_string, db_type, migrations)),
+        DataSourceConfig::Database { .. } => {
+            Err(anyhow!("No migrations are configured for this data source"))
+        }
+        _ => Err(anyhow!(
+            "Migrations are only supported for database data sources"
+        )),
+    }
+}
+
+async fn load(path: &str) -> Result<Migrator> {
+    Migrator::new(Path::new(path))
+        .await
+        .map_err(|e| anyhow!("Failed to load migrations from {}: {}", path, e))
+}
+
+async fn applied_versions<C: Migrate>(conn: &mut C) -> Result<HashSet<i64>> {
+    conn.ensure_migrations_table().await?;
+    Ok(conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect())
+}
+
+fn statuses(migrator: &Migrator, applied: &HashSet<i64>) -> Vec<MigrationStatus> {
+    migrator
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| MigrationStatus {
+            version: m.version,
+            description: m.description.to_string(),
+            applied: applied.contains(&m.version),
+        })
+        .collect()
+}
+
+/// Every migration of a data source and whether it has been applied
+pub async fn status(config: &DataSourceConfig) -> Result<Vec<MigrationStatus>> {
+    let (url, db_type, migrations) = migrations_config(config)?;
+    let migrator = load(&migrations.path).await?;
+
+    with_connection!(db_type, url, conn => {
+        let applied = applied_versions(&mut conn).await?;
+        Ok(statuses(&migrator, &applied))
+    })
+}
+
+/// Apply pending migrations, returning the ones that were applied
+pub async fn run(config: &DataSourceConfig) -> Result<Vec<MigrationStatus>> {
+    let (url, db_type, migrations) = migrations_config(config)?;
+    let migrator = load(&migrations.path).await?;
+
+    with_connection!(db_type, url, conn => {
+        let before = applied_versions(&mut conn).await?;
+        migrator
+            .run_direct(&mut conn)
+            .await
+            .map_err(|e| anyhow!("Migration failed: {}", e))?;
+        Ok(statuses(&migrator, &before)
+            .into_iter()
+            .filter(|m| !m.applied)
+            .map(|m| MigrationStatus { applied: true, ..m })
+            .collect())
+    })
+}
+
+/// Apply the migrations of every database data source that has them; with
+/// `startup_only`, only those marked `run_on_startup`
+pub async fn run_all(backoffices: &[BackofficeConfig], startup_only: bool) -> Result<()> {
+    for backoffice in backoffices {
+        let mut names: Vec<_> = backoffice.data_sources.keys().collect();
+        names.sort();
+        for name in names {
+            let config = &backoffice.data_sources[name];
+            let selected = match config {
+                DataSourceConfig::Database {
+                    migrations: Some(migrations),
+                    ..
+                } => !startup_only || migrations.run_on_startup,
+                _ => false,
+            };
+            if !selected {
+                continue;
+            }
+
+            let applied = run(config).await.map_err(|e| {
+                anyhow!(
+                    "Migrations of data source '{}' in backoffice '{}' failed: {}",
+                    name,
+                    backoffice.id,
+                    e
+                )
+            })?;
+            info!(
+                backoffice = %backoffice.id,
+                data_source = %name,
+                applied = applied.len(),
+                "Migrations up to date"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_and_status() {
+        let dir = std::env::temp_dir().join(format!("pmp-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("1_create_notes.sql"),
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT);",
+        )
+        .unwrap();
+        let db = dir.join("app.db");
+        let config = DataSourceConfig::Database {
+            connection_string: format!("sqlite://{}?mode=rwc", db.display()),
+            db_type: DatabaseType::Sqlite,
+            read_replicas: vec![],
+            migrations: Some(MigrationsConfig {
+                path: dir.display().to_string(),
+                run_on_startup: true,
+            }),
+            init: Default::default(),
+        };
+
+        let pending = status(&config).await.unwrap();
+        assert_eq!(
+            pending,
+            vec![MigrationStatus {
+                version: 1,
+                description: "create notes".to_string(),
+                applied: false,
+            }]
+        );
+
+        assert_eq!(run(&config).await.unwrap().len(), 1);
+        assert!(status(&config).await.unwrap()[0].applied);
+        assert!(run(&config).await.unwrap().is_empty());
+
+        std::fs::write(
+            dir.join("2_add_title.sql"),
+            "ALTER TABLE notes ADD COLUMN title TEXT;",
+        )
+        .unwrap();
+        let applied = run(&config).await.unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].version, 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,443 @@
+//! OpenAPI and Postman exports
+//!
+//! The generated spec is the generic API of `openapi.yaml` plus one path per
+//! configured action, with the schemas of its records and inputs derived
+//! from the action's fields. `pmp-backoffice-generator export-openapi` and
+//! `export-postman` write them from the configs alone, without starting the
+//! server, so API docs can be published in CI.
+
+use crate::config::{ActionConfig, ActionType, BackofficeConfig, FieldConfig, FieldType};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+/// The generic API, served at `/openapi.yaml`
+pub const SPEC: &str = include_str!("../openapi.yaml");
+
+/// Path of the generic action endpoint in [`SPEC`]
+const ACTION_PATH: &str =
+    "/api/backoffices/{backoffice_id}/sections/{section_id}/actions/{action_id}";
+
+const POSTMAN_SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// A schema name for an id, e.g. `OrderItems` for `order_items`
+pub fn type_name(id: &str) -> String {
+    id.split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Fields of the records a section's queries return: those of its list,
+/// view and custom actions
+pub fn record_fields(actions: &[ActionConfig]) -> Vec<&FieldConfig> {
+    let mut seen = HashSet::new();
+    actions
+        .iter()
+        .filter(|a| {
+            matches!(
+                a.action_type,
+                ActionType::List { .. } | ActionType::View { .. } | ActionType::Custom { .. }
+            )
+        })
+        .flat_map(|a| a.action_type.fields())
+        .filter(|f| seen.insert(f.id.as_str()))
+        .collect()
+}
+
+/// JSON schema of a field's values
+pub fn field_schema(field: &FieldConfig) -> Value {
+    let mut schema = match &field.field_type {
+        FieldType::Number { config } => {
+            let mut schema = json!({
+                "type": if config.allow_decimals { "number" } else { "integer" }
+            });
+            if let Some(min) = config.min {
+                schema["minimum"] = json!(min);
+            }
+            if let Some(max) = config.max {
+                schema["maximum"] = json!(max);
+            }
+            schema
+        }
+        FieldType::Range { config } => {
+            json!({ "type": "number", "minimum": config.min, "maximum": config.max })
+        }
+        FieldType::Currency { .. }
+        | FieldType::Rating { .. }
+        | FieldType::Percentage { .. }
+        | FieldType::Slider { .. } => json!({ "type": "number" }),
+        FieldType::Boolean { .. } => json!({ "type": "boolean" }),
+        FieldType::Email { .. } => json!({ "type": "string", "format": "email" }),
+        FieldType::Password { .. } => json!({ "type": "string", "format": "password" }),
+        FieldType::Url { .. } => json!({ "type": "string", "format": "uri" }),
+        FieldType::Date { .. } => json!({ "type": "string", "format": "date" }),
+        FieldType::DateTime { .. } => json!({ "type": "string", "format": "date-time" }),
+        FieldType::Select { config } => {
+            let values: Vec<&str> = config.options.iter().map(|o| o.value.as_str()).collect();
+            let schema = json!({ "type": "string", "enum": values });
+            if config.multiple {
+                json!({ "type": "array", "items": schema })
+            } else {
+                schema
+            }
+        }
+        FieldType::Radio { config } => {
+            let values: Vec<&str> = config.options.iter().map(|o| o.value.as_str()).collect();
+            json!({ "type": "string", "enum": values })
+        }
+        FieldType::MultiCheckbox { config } => {
+            let values: Vec<&str> = config.options.iter().map(|o| o.value.as_str()).collect();
+            json!({ "type": "array", "items": { "type": "string", "enum": values } })
+        }
+        FieldType::Tags { .. } => json!({ "type": "array", "items": { "type": "string" } }),
+        FieldType::Geolocation { .. } => json!({
+            "type": "object",
+            "properties": { "lat": { "type": "number" }, "lng": { "type": "number" } }
+        }),
+        // Any JSON value
+        FieldType::Json { .. }
+        | FieldType::DateTimeRange { .. }
+        | FieldType::ColorPalette { .. }
+        | FieldType::File { .. }
+        | FieldType::Image { .. }
+        | FieldType::Video { .. }
+        | FieldType::Audio { .. } => json!({}),
+        _ => json!({ "type": "string" }),
+    };
+    schema["title"] = json!(field.name);
+    if let Some(help_text) = &field.help_text {
+        schema["description"] = json!(help_text);
+    }
+    if let Some(default) = &field.default_value {
+        schema["default"] = default.clone();
+    }
+    schema
+}
+
+/// Object schema of some fields; only inputs list their required fields
+pub fn object_schema<'a>(fields: impl IntoIterator<Item = &'a FieldConfig>, input: bool) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for field in fields {
+        if input && !field.editable {
+            continue;
+        }
+        if input && field.required {
+            required.push(field.id.clone());
+        }
+        properties.insert(field.id.clone(), field_schema(field));
+    }
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+/// The generic spec with a path per action of `backoffices`
+pub fn spec(backoffices: &[BackofficeConfig]) -> Result<Value> {
+    let mut spec: Value = serde_yaml::from_str(SPEC)?;
+    let generic = spec["paths"][ACTION_PATH].clone();
+    if generic.is_null() {
+        return Err(anyhow!("{} is missing from openapi.yaml", ACTION_PATH));
+    }
+
+    let mut paths = Map::new();
+    let mut schemas = Map::new();
+    let mut tags = Vec::new();
+    for backoffice in backoffices {
+        tags.push(json!({
+            "name": backoffice.name,
+            "description": backoffice.description.clone().unwrap_or_default(),
+        }));
+        for section in &backoffice.sections {
+            let prefix = format!("{}{}", type_name(&backoffice.id), type_name(&section.id));
+            let records = record_fields(&section.actions);
+            let record = (!records.is_empty()).then(|| {
+                schemas.insert(prefix.clone(), object_schema(records, false));
+                json!({ "$ref": format!("#/components/schemas/{}", prefix) })
+            });
+
+            for action in &section.actions {
+                let path = format!(
+                    "/api/backoffices/{}/sections/{}/actions/{}",
+                    backoffice.id, section.id, action.id
+                );
+                let operation_id = format!("{}_{}_{}", backoffice.id, section.id, action.id);
+                let summary = format!("{}: {}", section.name, action.name);
+                let (method, mut operation) = match &action.action_type {
+                    ActionType::Form { fields, .. } | ActionType::Pipeline { fields, .. } => {
+                        let input = format!("{}{}Input", prefix, type_name(&action.id));
+                        schemas.insert(input.clone(), object_schema(fields, true));
+                        let mut operation = generic["post"].clone();
+                        operation["requestBody"]["content"]["application/json"]["schema"] =
+                            json!({ "$ref": format!("#/components/schemas/{}", input) });
+                        ("post", operation)
+                    }
+                    action_type => {
+                        let mut operation = generic["get"].clone();
+                        let rows = match action_type {
+                            ActionType::List { .. }
+                            | ActionType::View { .. }
+                            | ActionType::Custom { .. } => record.clone(),
+                            _ => None,
+                        };
+                        if let Some(rows) = rows {
+                            let response = match action_type {
+                                ActionType::List { .. } => "ListActionResponse",
+                                _ => "ViewActionResponse",
+                            };
+                            operation["responses"]["200"]["content"]["application/json"]
+                                ["schema"] = json!({
+                                "allOf": [
+                                    { "$ref": format!("#/components/schemas/{}", response) },
+                                    {
+                                        "type": "object",
+                                        "properties": {
+                                            "data": { "type": "array", "items": rows }
+                                        }
+                                    }
+                                ]
+                            });
+                        }
+                        ("get", operation)
+                    }
+                };
+                // The ids are part of the path
+                if let Some(parameters) = operation["parameters"].as_array_mut() {
+                    parameters.retain(|p| p["in"] != "path");
+                }
+                operation["operationId"] = json!(operation_id);
+                operation["summary"] = json!(summary);
+                operation["tags"] = json!([backoffice.name]);
+                let mut item = Map::new();
+                item.insert(method.to_string(), operation);
+                paths.insert(path, Value::Object(item));
+            }
+        }
+    }
+
+    if let Some(spec_paths) = spec["paths"].as_object_mut() {
+        spec_paths.extend(paths);
+    }
+    if let Some(spec_schemas) = spec["components"]["schemas"].as_object_mut() {
+        spec_schemas.extend(schemas);
+    }
+    if let Some(spec_tags) = spec["tags"].as_array_mut() {
+        spec_tags.extend(tags);
+    }
+    Ok(spec)
+}
+
+/// An example value of a field, for request bodies
+fn example(field: &FieldConfig) -> Value {
+    if let Some(default) = &field.default_value {
+        return default.clone();
+    }
+    let schema = field_schema(field);
+    if let Some(first) = schema["enum"].get(0) {
+        return first.clone();
+    }
+    match schema["type"].as_str() {
+        Some("number") | Some("integer") => json!(0),
+        Some("boolean") => json!(false),
+        Some("array") => json!([]),
+        Some("object") => json!({}),
+        Some("string") => match schema["format"].as_str() {
+            Some("date") => json!("2024-01-01"),
+            Some("date-time") => json!("2024-01-01T00:00:00Z"),
+            _ => json!(""),
+        },
+        _ => Value::Null,
+    }
+}
+
+/// A Postman v2.1 collection with a request per action of `backoffices`,
+/// sent to `{{base_url}}` with the bearer `{{token}}`
+pub fn postman(backoffices: &[BackofficeConfig], base_url: &str) -> Value {
+    let folders: Vec<Value> = backoffices
+        .iter()
+        .map(|backoffice| {
+            let sections: Vec<Value> = backoffice
+                .sections
+                .iter()
+                .map(|section| {
+                    let requests: Vec<Value> = section
+                        .actions
+                        .iter()
+                        .map(|action| {
+                            let segments = [
+                                "api",
+                                "backoffices",
+                                &backoffice.id,
+                                "sections",
+                                &section.id,
+                                "actions",
+                                &action.id,
+                            ];
+                            let mut request = json!({
+                                "method": "GET",
+                                "header": [],
+                                "url": {
+                                    "raw": format!("{{{{base_url}}}}/{}", segments.join("/")),
+                                    "host": ["{{base_url}}"],
+                                    "path": segments,
+                                },
+                            });
+                            if let ActionType::Form { fields, .. }
+                            | ActionType::Pipeline { fields, .. } = &action.action_type
+                            {
+                                let body: Map<String, Value> = fields
+                                    .iter()
+                                    .filter(|f| f.editable)
+                                    .map(|f| (f.id.clone(), example(f)))
+                                    .collect();
+                                request["method"] = json!("POST");
+                                request["header"] = json!([
+                                    { "key": "Content-Type", "value": "application/json" }
+                                ]);
+                                request["body"] = json!({
+                                    "mode": "raw",
+                                    "raw": serde_json::to_string_pretty(&body)
+                                        .unwrap_or_default(),
+                                    "options": { "raw": { "language": "json" } },
+                                });
+                            }
+                            json!({ "name": action.name, "request": request })
+                        })
+                        .collect();
+                    json!({ "name": section.name, "item": requests })
+                })
+                .collect();
+            json!({
+                "name": backoffice.name,
+                "description": backoffice.description.clone().unwrap_or_default(),
+                "item": sections,
+            })
+        })
+        .collect();
+
+    json!({
+        "info": {
+            "name": "PMP Backoffice Generator",
+            "schema": POSTMAN_SCHEMA,
+        },
+        "auth": {
+            "type": "bearer",
+            "bearer": [{ "key": "token", "value": "{{token}}", "type": "string" }],
+        },
+        "variable": [
+            { "key": "base_url", "value": base_url },
+            { "key": "token", "value": "" },
+        ],
+        "item": folders,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backoffice() -> BackofficeConfig {
+        serde_yaml::from_str(
+            r#"
+id: shop
+name: Shop
+data_sources: {}
+sections:
+  - id: order_items
+    name: Order items
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        required_scopes: []
+        fields:
+          - { id: id, name: Id, field_type: number, config: { allow_decimals: false } }
+          - { id: status, name: Status, field_type: select, config: { options: [{ value: open, label: Open }, { value: paid, label: Paid }] } }
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        required_scopes: []
+        fields:
+          - { id: sku, name: SKU, field_type: text, required: true }
+          - { id: shipped_on, name: Shipped on, field_type: date }
+          - { id: id, name: Id, field_type: number, editable: false }
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_spec() {
+        assert_eq!(type_name("order_items"), "OrderItems");
+        let spec = spec(&[backoffice()]).unwrap();
+
+        let list = &spec["paths"]["/api/backoffices/shop/sections/order_items/actions/list"]["get"];
+        assert_eq!(list["operationId"], "shop_order_items_list");
+        assert_eq!(list["tags"], json!(["Shop"]));
+        assert!(list["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|p| p["in"] != "path"));
+        assert_eq!(
+            list["responses"]["200"]["content"]["application/json"]["schema"]["allOf"][1]
+                ["properties"]["data"]["items"]["$ref"],
+            "#/components/schemas/ShopOrderItems"
+        );
+        let record = &spec["components"]["schemas"]["ShopOrderItems"];
+        assert_eq!(record["properties"]["id"]["type"], "integer");
+        assert_eq!(
+            record["properties"]["status"]["enum"],
+            json!(["open", "paid"])
+        );
+
+        let create =
+            &spec["paths"]["/api/backoffices/shop/sections/order_items/actions/create"]["post"];
+        assert_eq!(
+            create["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ShopOrderItemsCreateInput"
+        );
+        let input = &spec["components"]["schemas"]["ShopOrderItemsCreateInput"];
+        assert_eq!(input["required"], json!(["sku"]));
+        assert_eq!(input["properties"]["shipped_on"]["format"], "date");
+        assert!(input["properties"]["id"].is_null());
+
+        // The generic API is kept
+        assert!(!spec["paths"][ACTION_PATH].is_null());
+        assert!(spec["tags"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|t| t["name"] == "Shop"));
+    }
+
+    #[test]
+    fn test_postman() {
+        let collection = postman(&[backoffice()], "http://localhost:3000");
+        assert_eq!(collection["info"]["schema"], POSTMAN_SCHEMA);
+        assert_eq!(collection["variable"][0]["value"], "http://localhost:3000");
+
+        let section = &collection["item"][0]["item"][0];
+        assert_eq!(section["name"], "Order items");
+        let list = &section["item"][0]["request"];
+        assert_eq!(list["method"], "GET");
+        assert_eq!(
+            list["url"]["raw"],
+            "{{base_url}}/api/backoffices/shop/sections/order_items/actions/list"
+        );
+        let create = &section["item"][1]["request"];
+        assert_eq!(create["method"], "POST");
+        let body: Value = serde_json::from_str(create["body"]["raw"].as_str().unwrap()).unwrap();
+        assert_eq!(body, json!({ "sku": "", "shipped_on": "2024-01-01" }));
+    }
+}
@@ -0,0 +1,142 @@
+//! Field-level read/write permissions
+
+use crate::auth::AuthContext;
+use crate::config::{FieldConfig, FieldWritePolicy};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Whether the caller may read the field
+pub fn can_read(field: &FieldConfig, auth: &AuthContext) -> bool {
+    auth.has_all_scopes(&field.required_scopes)
+}
+
+/// Whether the caller may write the field
+pub fn can_write(field: &FieldConfig, auth: &AuthContext) -> bool {
+    can_read(field, auth) && auth.has_all_scopes(&field.write_scopes)
+}
+
+/// Field definitions the caller may read
+pub fn readable_fields(fields: &[FieldConfig], auth: &AuthContext) -> Vec<FieldConfig> {
+    fields
+        .iter()
+        .filter(|f| can_read(f, auth))
+        .cloned()
+        .collect()
+}
+
+/// Remove fields the caller may not read from query results
+pub fn strip_unreadable(
+    rows: &mut [HashMap<String, Value>],
+    fields: &[FieldConfig],
+    auth: &AuthContext,
+) {
+    let hidden: Vec<&str> = fields
+        .iter()
+        .filter(|f| !can_read(f, auth))
+        .map(|f| f.id.as_str())
+        .collect();
+
+    if hidden.is_empty() {
+        return;
+    }
+
+    for row in rows.iter_mut() {
+        for id in &hidden {
+            row.remove(*id);
+        }
+    }
+}
+
+/// Enforce write permissions on a mutation payload
+///
+/// With `FieldWritePolicy::Drop` forbidden fields are removed from `data`;
+/// with `FieldWritePolicy::Reject` their ids are returned as an error.
+pub fn enforce_writable(
+    data: &mut HashMap<String, Value>,
+    fields: &[FieldConfig],
+    auth: &AuthContext,
+    policy: &FieldWritePolicy,
+) -> Result<(), Vec<String>> {
+    let forbidden: Vec<String> = fields
+        .iter()
+        .filter(|f| data.contains_key(&f.id) && !can_write(f, auth))
+        .map(|f| f.id.clone())
+        .collect();
+
+    if forbidden.is_empty() {
+        return Ok(());
+    }
+
+    match policy {
+        FieldWritePolicy::Reject => Err(forbidden),
+        FieldWritePolicy::Drop => {
+            debug!(fields = ?forbidden, "Dropping fields the caller cannot write");
+            for id in &forbidden {
+                data.remove(id);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FieldType;
+
+    fn field(id: &str, required_scopes: &[&str], write_scopes: &[&str]) -> FieldConfig {
+        FieldConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            field_type: FieldType::Text {
+                config: Default::default(),
+            },
+            required: false,
+            editable: true,
+            visible: true,
+            default_value: None,
+            placeholder: None,
+            help_text: None,
+            validations: vec![],
+            relationship_id: None,
+            sensitive: false,
+            mask: None,
+            unmask_scopes: vec![],
+            required_scopes: required_scopes.iter().map(|s| s.to_string()).collect(),
+            write_scopes: write_scopes.iter().map(|s| s.to_string()).collect(),
+            visible_when: None,
+            required_when: None,
+        }
+    }
+
+    #[test]
+    fn test_field_permissions() {
+        let fields = vec![
+            field("name", &[], &[]),
+            field("salary", &["hr"], &[]),
+            field("status", &[], &["admin"]),
+        ];
+        let auth = AuthContext::from_claims(serde_json::json!({"scope": "support"}));
+
+        let mut rows = vec![HashMap::from([
+            ("name".to_string(), Value::from("Ada")),
+            ("salary".to_string(), Value::from(100)),
+        ])];
+        strip_unreadable(&mut rows, &fields, &auth);
+        assert!(!rows[0].contains_key("salary"));
+        assert_eq!(readable_fields(&fields, &auth).len(), 2);
+
+        let mut data = HashMap::from([
+            ("name".to_string(), Value::from("Ada")),
+            ("status".to_string(), Value::from("active")),
+        ]);
+        assert_eq!(
+            enforce_writable(&mut data, &fields, &auth, &FieldWritePolicy::Reject),
+            Err(vec!["status".to_string()])
+        );
+        enforce_writable(&mut data, &fields, &auth, &FieldWritePolicy::Drop).unwrap();
+        assert!(!data.contains_key("status"));
+        assert!(data.contains_key("name"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Phone numbers
+//!
+//! Numbers are checked with libphonenumber's metadata: national numbers
+//! against the rules of the field's `country_code`, and numbers with an
+//! international prefix against those of their own country. Valid numbers
+//! are stored in E.164, e.g. `+14155552671`, so the same number is always
+//! written the same way.
+
+use crate::config::{FieldConfig, FieldType, PhoneFieldConfig, ValidationType};
+use phonenumber::{country, Mode, PhoneNumber};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Why a value isn't an acceptable phone number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhoneError {
+    Invalid,
+    /// The number has an extension and the field doesn't allow them
+    Extension,
+}
+
+impl PhoneError {
+    /// Validation error code
+    pub fn code(self) -> &'static str {
+        match self {
+            PhoneError::Invalid => "phone",
+            PhoneError::Extension => "phone_extension",
+        }
+    }
+}
+
+/// Parse a phone number for a field
+///
+/// `country_code` is an ISO 3166 code (`US`) or a calling code (`+1`), used
+/// for numbers written without an international prefix.
+pub fn parse(text: &str, config: &PhoneFieldConfig) -> Result<PhoneNumber, PhoneError> {
+    let country = config.country_code.as_deref().map(str::trim);
+    let region = country.and_then(|c| c.to_ascii_uppercase().parse::<country::Id>().ok());
+    let calling_code = country
+        .filter(|_| region.is_none())
+        .map(|c| c.trim_start_matches('+'))
+        .filter(|c| !c.is_empty() && c.chars().all(|d| d.is_ascii_digit()));
+
+    let text = text.trim();
+    let number = match calling_code {
+        Some(code) if !text.starts_with('+') && !text.starts_with("00") => {
+            phonenumber::parse(None, format!("+{} {}", code, text))
+        }
+        _ => phonenumber::parse(region, text),
+    }
+    .map_err(|_| PhoneError::Invalid)?;
+
+    if !number.is_valid() {
+        return Err(PhoneError::Invalid);
+    }
+    if number.extension().is_some() && !config.allow_extensions {
+        return Err(PhoneError::Extension);
+    }
+    Ok(number)
+}
+
+/// A number in E.164, followed by `;ext=` and its extension if it has one
+pub fn e164(number: &PhoneNumber) -> String {
+    let formatted = number.format().mode(Mode::E164).to_string();
+    match number.extension() {
+        Some(extension) => format!("{};ext={}", formatted, extension.as_ref()),
+        None => formatted,
+    }
+}
+
+/// Phone configuration of a field: its own for Phone fields, the defaults
+/// for other fields with a `phone` rule
+pub fn field_config(field: &FieldConfig) -> Option<PhoneFieldConfig> {
+    match &field.field_type {
+        FieldType::Phone { config } => Some(config.clone()),
+        _ => field
+            .validations
+            .iter()
+            .any(|rule| matches!(rule.rule_type, ValidationType::Phone))
+            .then(PhoneFieldConfig::default),
+    }
+}
+
+/// Rewrite the valid phone numbers of a payload in E.164
+///
+/// Values that don't parse are left as they were, for validation to report.
+pub fn normalize(data: &mut HashMap<String, Value>, fields: &[FieldConfig]) {
+    for field in fields {
+        let Some(Value::String(text)) = data.get_mut(&field.id) else {
+            continue;
+        };
+        let Some(config) = field_config(field) else {
+            continue;
+        };
+        if let Ok(number) = parse(text, &config) {
+            *text = e164(&number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(country_code: Option<&str>, allow_extensions: bool) -> PhoneFieldConfig {
+        PhoneFieldConfig {
+            country_code: country_code.map(String::from),
+            allow_extensions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let us = config(Some("US"), false);
+        let number = parse("(415) 555-2671", &us).unwrap();
+        assert_eq!(e164(&number), "+14155552671");
+        // International numbers follow their own country's rules
+        let number = parse("+44 20 7946 0958", &us).unwrap();
+        assert_eq!(e164(&number), "+442079460958");
+        assert_eq!(parse("555-0000", &us).unwrap_err(), PhoneError::Invalid);
+        assert_eq!(parse("not a number", &us).unwrap_err(), PhoneError::Invalid);
+
+        let number = parse("020 7946 0958", &config(Some("+44"), false)).unwrap();
+        assert_eq!(e164(&number), "+442079460958");
+        // Without a country only international numbers parse
+        assert!(parse("(415) 555-2671", &config(None, false)).is_err());
+
+        assert_eq!(
+            parse("415-555-2671 ext. 12", &us).unwrap_err(),
+            PhoneError::Extension
+        );
+        let number = parse("415-555-2671 ext. 12", &config(Some("US"), true)).unwrap();
+        assert_eq!(e164(&number), "+14155552671;ext=12");
+    }
+}
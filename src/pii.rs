@@ -0,0 +1,200 @@
+//! Personal data in free text
+//!
+//! `no_pii` validation rules look for emails, card numbers, SSNs and phone
+//! numbers in text fields such as notes and comments. Depending on the
+//! rule's mode, values containing them are rejected, accepted with a
+//! warning, or accepted and scrubbed out of responses.
+
+use crate::config::{FieldConfig, PiiKind, PiiMode, ValidationType};
+use crate::validation::validate_luhn;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+/// Every kind, in the order overlapping matches are claimed
+const ALL_KINDS: [PiiKind; 4] = [
+    PiiKind::CreditCard,
+    PiiKind::Ssn,
+    PiiKind::Email,
+    PiiKind::Phone,
+];
+
+fn patterns() -> &'static [(PiiKind, Regex)] {
+    static PATTERNS: OnceLock<Vec<(PiiKind, Regex)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (PiiKind::CreditCard, r"\b(?:\d[ -]?){12,18}\d\b"),
+            (
+                PiiKind::Ssn,
+                r"\b(?:00[1-9]|0[1-9]\d|[1-578]\d\d|6[0-57-9]\d|66[0-57-9])-\d{2}-\d{4}\b",
+            ),
+            (
+                PiiKind::Email,
+                r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+            ),
+            (
+                PiiKind::Phone,
+                r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{3}\)|\b\d{3})[ .-]?\d{3}[ .-]?\d{4}\b",
+            ),
+        ]
+        .into_iter()
+        .map(|(kind, pattern)| (kind, Regex::new(pattern).unwrap()))
+        .collect()
+    })
+}
+
+/// Name of a kind, as rules and messages write it
+pub fn name(kind: PiiKind) -> &'static str {
+    match kind {
+        PiiKind::Email => "email",
+        PiiKind::CreditCard => "credit_card",
+        PiiKind::Ssn => "ssn",
+        PiiKind::Phone => "phone",
+    }
+}
+
+/// Where personal data of the given kinds (all with none) appears in text
+pub fn find(text: &str, kinds: &[PiiKind]) -> Vec<(Range<usize>, PiiKind)> {
+    let mut found: Vec<(Range<usize>, PiiKind)> = Vec::new();
+    for (kind, pattern) in patterns() {
+        if !kinds.is_empty() && !kinds.contains(kind) {
+            continue;
+        }
+        for m in pattern.find_iter(text) {
+            // Digit runs are only card numbers when their checksum holds
+            if *kind == PiiKind::CreditCard && !validate_luhn(m.as_str()) {
+                continue;
+            }
+            let overlaps = found
+                .iter()
+                .any(|(range, _)| range.start < m.end() && m.start() < range.end);
+            if !overlaps {
+                found.push((m.range(), *kind));
+            }
+        }
+    }
+    found.sort_by_key(|(range, _)| range.start);
+    found
+}
+
+/// The kinds of personal data found in text, in a fixed order
+pub fn detect(text: &str, kinds: &[PiiKind]) -> Vec<PiiKind> {
+    let found = find(text, kinds);
+    ALL_KINDS
+        .into_iter()
+        .filter(|kind| found.iter().any(|(_, found)| found == kind))
+        .collect()
+}
+
+/// Text with its personal data replaced by `[kind]`, e.g. `[email]`
+pub fn scrub(text: &str, kinds: &[PiiKind]) -> String {
+    let mut scrubbed = String::with_capacity(text.len());
+    let mut end = 0;
+    for (range, kind) in find(text, kinds) {
+        scrubbed.push_str(&text[end..range.start]);
+        scrubbed.push_str(&format!("[{}]", name(kind)));
+        end = range.end;
+    }
+    scrubbed.push_str(&text[end..]);
+    scrubbed
+}
+
+/// Kinds a field's `no_pii` rules in a mode look for, if it has any
+pub fn rule_kinds(field: &FieldConfig, mode: PiiMode) -> Option<Vec<PiiKind>> {
+    let mut rules = field
+        .validations
+        .iter()
+        .filter_map(|rule| match &rule.rule_type {
+            ValidationType::NoPii { kinds, mode: m } if *m == mode => Some(kinds),
+            _ => None,
+        })
+        .peekable();
+    rules.peek()?;
+    let mut kinds = Vec::new();
+    for rule_kinds in rules {
+        // A rule looking for every kind covers the others
+        if rule_kinds.is_empty() {
+            return Some(Vec::new());
+        }
+        kinds.extend(rule_kinds.iter().copied());
+    }
+    Some(kinds)
+}
+
+/// Scrub the personal data out of text fields with a `no_pii` rule in
+/// `scrub` mode
+pub fn scrub_rows(rows: &mut [HashMap<String, Value>], fields: &[FieldConfig]) {
+    let scrubbed: Vec<(&str, Vec<PiiKind>)> = fields
+        .iter()
+        .filter_map(|f| Some((f.id.as_str(), rule_kinds(f, PiiMode::Scrub)?)))
+        .collect();
+    if scrubbed.is_empty() {
+        return;
+    }
+
+    for row in rows.iter_mut() {
+        for (id, kinds) in &scrubbed {
+            if let Some(Value::String(text)) = row.get_mut(*id) {
+                *text = scrub(text, kinds);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_detect_and_scrub() {
+        let note =
+            "Call 555-123-4567 or mail ada@example.com, card 4532 0151 1283 0366, SSN 123-45-6789";
+        assert_eq!(
+            detect(note, &[]),
+            vec![
+                PiiKind::CreditCard,
+                PiiKind::Ssn,
+                PiiKind::Email,
+                PiiKind::Phone
+            ]
+        );
+        assert_eq!(
+            scrub(note, &[]),
+            "Call [phone] or mail [email], card [credit_card], SSN [ssn]"
+        );
+        assert_eq!(detect(note, &[PiiKind::Email]), vec![PiiKind::Email]);
+
+        // Order numbers failing the Luhn check aren't cards
+        assert!(detect("Order 4532015112830367 shipped", &[]).is_empty());
+        assert!(detect("Meeting at 10:30 in room 204", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_scrub_rows() {
+        let fields: Vec<FieldConfig> = serde_yaml::from_str(
+            r#"
+- id: notes
+  name: Notes
+  field_type: text
+  validations:
+    - rule_type: { type: no_pii, kinds: [email], mode: scrub }
+- id: comment
+  name: Comment
+  field_type: text
+  validations:
+    - rule_type: { type: no_pii }
+"#,
+        )
+        .unwrap();
+        let mut rows = vec![HashMap::from([
+            ("notes".to_string(), json!("ada@example.com, 555-123-4567")),
+            ("comment".to_string(), json!("ada@example.com")),
+        ])];
+        scrub_rows(&mut rows, &fields);
+        assert_eq!(rows[0]["notes"], json!("[email], 555-123-4567"));
+        assert_eq!(rows[0]["comment"], json!("ada@example.com"));
+    }
+}
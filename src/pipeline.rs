@@ -0,0 +1,247 @@
+//! Pipeline actions
+//!
+//! `type: pipeline` actions run a list of steps in order when submitted:
+//! queries reading rows, transforms reshaping the rows of an earlier step
+//! and mutations writing, once or once per row of an earlier step. Each
+//! step may use a different data source, so simple cross-system workflows
+//! ("fetch from the API, write into the database") need no code.
+//!
+//! Step params are templates over the submitted inputs, the outputs of
+//! earlier steps and the current row. Rendered values are sent as params,
+//! never spliced into query text, so SQL steps bind them as placeholders.
+//! Steps are not transactional: a failing step stops the pipeline and the
+//! outputs of the steps before it are reported with the error.
+
+use crate::config::{BackofficeConfig, PipelineStep, PipelineStepKind};
+use crate::data_source::{value_as_text, DataSourceRegistry};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use tracing::debug;
+
+/// A step that failed, with the outputs of the steps that completed
+#[derive(Debug)]
+pub struct StepError {
+    pub step: String,
+    pub error: anyhow::Error,
+    pub outputs: Map<String, Value>,
+}
+
+/// Run the steps of a pipeline, returning each step's output by step id
+///
+/// Every output is an array: the rows of queries and transforms, and the
+/// results of mutations.
+pub async fn run(
+    steps: &[PipelineStep],
+    default_source: &str,
+    backoffice: &BackofficeConfig,
+    registry: &DataSourceRegistry,
+    input: &HashMap<String, Value>,
+) -> Result<Map<String, Value>, StepError> {
+    let mut context = json!({ "input": input, "steps": {} });
+
+    for step in steps {
+        let output = match run_step(step, default_source, backoffice, registry, &context).await {
+            Ok(output) => output,
+            Err(error) => {
+                return Err(StepError {
+                    step: step.id.clone(),
+                    error,
+                    outputs: outputs(context),
+                })
+            }
+        };
+        debug!(step = %step.id, "Pipeline step completed");
+        context["steps"][&step.id] = output;
+    }
+
+    Ok(outputs(context))
+}
+
+fn outputs(mut context: Value) -> Map<String, Value> {
+    match context["steps"].take() {
+        Value::Object(outputs) => outputs,
+        _ => Map::new(),
+    }
+}
+
+async fn run_step(
+    step: &PipelineStep,
+    default_source: &str,
+    backoffice: &BackofficeConfig,
+    registry: &DataSourceRegistry,
+    context: &Value,
+) -> Result<Value> {
+    let source = |name: &Option<String>| {
+        let name = name.as_deref().unwrap_or(default_source);
+        backoffice
+            .data_sources
+            .get(name)
+            .ok_or_else(|| anyhow!("Data source '{}' not found", name))
+    };
+
+    match &step.kind {
+        PipelineStepKind::Query {
+            data_source,
+            query,
+            params,
+        } => {
+            let data_source = registry.create(source(data_source)?).await?;
+            let rows = data_source
+                .execute_query(query, Some(&render_params(params, context)))
+                .await?;
+            Ok(Value::Array(
+                rows.into_iter()
+                    .map(|row| Value::Object(row.into_iter().collect()))
+                    .collect(),
+            ))
+        }
+        PipelineStepKind::Transform { from, map } => {
+            let rows = step_rows(context, from)?
+                .iter()
+                .map(|row| {
+                    let context = with_row(context, row);
+                    Value::Object(
+                        map.iter()
+                            .map(|(key, template)| (key.clone(), render(template, &context)))
+                            .collect(),
+                    )
+                })
+                .collect();
+            Ok(Value::Array(rows))
+        }
+        PipelineStepKind::Mutation {
+            data_source,
+            query,
+            params,
+            for_each,
+        } => {
+            let data_source = registry.create(source(data_source)?).await?;
+            let contexts = match for_each {
+                Some(from) => step_rows(context, from)?
+                    .iter()
+                    .map(|row| with_row(context, row))
+                    .collect(),
+                None => vec![context.clone()],
+            };
+
+            let mut results = Vec::with_capacity(contexts.len());
+            for context in &contexts {
+                results.push(
+                    data_source
+                        .execute_mutation(query, &render_params(params, context))
+                        .await?,
+                );
+            }
+            Ok(Value::Array(results))
+        }
+    }
+}
+
+/// Output of an earlier step
+fn step_rows<'a>(context: &'a Value, step: &str) -> Result<&'a Vec<Value>> {
+    context["steps"]
+        .get(step)
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("Step '{}' must run before the steps using it", step))
+}
+
+fn with_row(context: &Value, row: &Value) -> Value {
+    let mut context = context.clone();
+    context["row"] = row.clone();
+    context
+}
+
+fn render_params(params: &HashMap<String, Value>, context: &Value) -> HashMap<String, Value> {
+    params
+        .iter()
+        .map(|(name, template)| (name.clone(), render(template, context)))
+        .collect()
+}
+
+/// Render the `{{path}}` templates of a value, recursing into arrays and
+/// objects
+///
+/// A string that is a single template takes the referenced value as is;
+/// otherwise templates are replaced with the text of their values. Missing
+/// paths render as null, or as empty text.
+pub fn render(template: &Value, context: &Value) -> Value {
+    match template {
+        Value::String(s) => render_str(s, context),
+        Value::Array(items) => Value::Array(items.iter().map(|v| render(v, context)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render(v, context)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn render_str(template: &str, context: &Value) -> Value {
+    let trimmed = template.trim();
+    if let Some(path) = trimmed
+        .strip_prefix("{{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+        .filter(|path| !path.contains("{{") && !path.contains("}}"))
+    {
+        return lookup(context, path.trim()).cloned().unwrap_or(Value::Null);
+    }
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let path = rest[start + 2..start + end].trim();
+        out.push_str(&lookup(context, path).map(value_as_text).unwrap_or_default());
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    Value::String(out)
+}
+
+/// Value at a dotted path, indexing arrays with numeric segments
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            Value::Object(map) => map.get(segment),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_templates() {
+        let context = json!({
+            "input": {"since": "2024-01-01", "limit": 10},
+            "steps": {"fetch": [{"id": 7, "name": "Ada"}]},
+            "row": {"first": "Ada", "last": "Lovelace"},
+        });
+
+        assert_eq!(render(&json!("{{ input.limit }}"), &context), json!(10));
+        assert_eq!(render(&json!("{{steps.fetch.0.id}}"), &context), json!(7));
+        assert_eq!(
+            render(&json!("{{row.first}} {{row.last}}"), &context),
+            json!("Ada Lovelace")
+        );
+        assert_eq!(
+            render(
+                &json!({"ids": ["{{steps.fetch.0.id}}"], "fixed": true}),
+                &context
+            ),
+            json!({"ids": [7], "fixed": true})
+        );
+        assert_eq!(render(&json!("{{input.missing}}"), &context), Value::Null);
+        assert_eq!(
+            render(&json!("since {{input.missing}}!"), &context),
+            json!("since !")
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! WASM plugin host
+//!
+//! Plugins are sandboxed WebAssembly modules that talk to the host through a
+//! small JSON ABI. A module must export:
+//!
+//! - `memory`: its linear memory
+//! - `alloc(len: i32) -> i32`: reserve `len` bytes and return a pointer
+//!
+//! Every hook/data source export has the signature `(ptr: i32, len: i32) -> i64`.
+//! The input is UTF-8 JSON written at `ptr`; the return value packs the output
+//! location as `(out_ptr << 32) | out_len`, pointing at UTF-8 JSON. Modules get
+//! no imports, so they cannot touch the filesystem or network.
+
+use crate::config::{BackofficeConfig, PluginConfig, PluginHook};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::{debug, warn};
+
+/// Default fuel budget for a single plugin call
+pub const DEFAULT_FUEL: u64 = 100_000_000;
+
+/// Largest linear memory a plugin instance may grow to
+const MAX_MEMORY_BYTES: usize = 64 << 20;
+
+/// Largest table a plugin instance may grow to
+const MAX_TABLE_ELEMENTS: usize = 10_000;
+
+/// Outcome of the `before_mutation` hooks
+#[derive(Debug)]
+pub enum MutationHookResult {
+    /// Continue with the (possibly rewritten) payload
+    Continue(HashMap<String, Value>),
+    /// A plugin rejected the mutation with the given message
+    Veto(String),
+}
+
+/// Plugins of a backoffice that implement `hook` for the given section
+fn plugins_for<'a>(
+    backoffice: &'a BackofficeConfig,
+    section_id: &'a str,
+    hook: PluginHook,
+) -> impl Iterator<Item = &'a PluginConfig> {
+    backoffice.plugins.iter().filter(move |p| {
+        p.hooks.contains(&hook)
+            && (p.sections.is_empty() || p.sections.iter().any(|s| s == section_id))
+    })
+}
+
+/// Run the `transform_row` hooks over query results
+///
+/// A plugin may return a rewritten row object, or `null` to drop the row.
+pub async fn transform_rows(
+    backoffice: &BackofficeConfig,
+    section_id: &str,
+    mut rows: Vec<HashMap<String, Value>>,
+) -> Result<Vec<HashMap<String, Value>>> {
+    for plugin in plugins_for(backoffice, section_id, PluginHook::TransformRow) {
+        debug!(plugin = %plugin.id, rows = rows.len(), "Running transform_row hook");
+
+        let path = plugin.path.clone();
+        let fuel = plugin.fuel.unwrap_or(DEFAULT_FUEL);
+        let plugin_id = plugin.id.clone();
+
+        rows = tokio::task::spawn_blocking(move || {
+            let mut instance = runtime::PluginInstance::new(&path, fuel)?;
+            let mut transformed = Vec::with_capacity(rows.len());
+
+            for row in rows {
+                let input = Value::Object(row.into_iter().collect());
+                match instance.call("transform_row", &input)? {
+                    Value::Object(obj) => transformed.push(obj.into_iter().collect()),
+                    Value::Null => {}
+                    other => {
+                        return Err(anyhow!(
+                            "Plugin {} returned a non-object row: {}",
+                            plugin_id,
+                            other
+                        ))
+                    }
+                }
+            }
+
+            Ok::<_, anyhow::Error>(transformed)
+        })
+        .await
+        .map_err(|e| anyhow!("Plugin task failed: {}", e))??;
+    }
+
+    Ok(rows)
+}
+
+/// Run the `before_mutation` hooks over a mutation payload
+///
+/// A plugin may return `{"data": {...}}` to rewrite the payload,
+/// `{"error": "..."}` to veto the operation, or `null` to leave it unchanged.
+/// The hooks run before field permissions, row filters and validation, so
+/// those apply to the payload as rewritten.
+pub async fn before_mutation(
+    backoffice: &BackofficeConfig,
+    section_id: &str,
+    action_id: &str,
+    mut data: HashMap<String, Value>,
+) -> Result<MutationHookResult> {
+    for plugin in plugins_for(backoffice, section_id, PluginHook::BeforeMutation) {
+        debug!(plugin = %plugin.id, "Running before_mutation hook");
+
+        let input = json!({
+            "section_id": section_id,
+            "action_id": action_id,
+            "data": data,
+        });
+
+        let output = call_export(
+            &plugin.path,
+            "before_mutation",
+            input,
+            plugin.fuel.unwrap_or(DEFAULT_FUEL),
+        )
+        .await?;
+
+        match output {
+            Value::Null => {}
+            Value::Object(mut obj) => {
+                if let Some(error) = obj.remove("error") {
+                    let message = error
+                        .as_str()
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| error.to_string());
+                    warn!(plugin = %plugin.id, message = %message, "Plugin vetoed mutation");
+                    return Ok(MutationHookResult::Veto(message));
+                }
+                if let Some(Value::Object(new_data)) = obj.remove("data") {
+                    data = new_data.into_iter().collect();
+                }
+            }
+            other => {
+                return Err(anyhow!(
+                    "Plugin {} returned an invalid before_mutation result: {}",
+                    plugin.id,
+                    other
+                ))
+            }
+        }
+    }
+
+    Ok(MutationHookResult::Continue(data))
+}
+
+/// Call a single export of a plugin module with a JSON input
+pub async fn call_export(path: &str, export: &str, input: Value, fuel: u64) -> Result<Value> {
+    let path = path.to_string();
+    let export = export.to_string();
+
+    tokio::task::spawn_blocking(move || call_export_blocking(&path, &export, &input, fuel))
+        .await
+        .map_err(|e| anyhow!("Plugin task failed: {}", e))?
+}
+
+/// Call a single export on the current thread
+fn call_export_blocking(path: &str, export: &str, input: &Value, fuel: u64) -> Result<Value> {
+    let mut instance = runtime::PluginInstance::new(path, fuel)?;
+    instance.call(export, input)
+}
+
+#[cfg(feature = "wasm-plugins")]
+mod runtime {
+    use anyhow::{anyhow, Result};
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+    use wasmtime::{
+        Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    };
+
+    fn engine() -> &'static Engine {
+        static ENGINE: OnceLock<Engine> = OnceLock::new();
+        ENGINE.get_or_init(|| {
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            Engine::new(&config).expect("valid wasmtime configuration")
+        })
+    }
+
+    /// Compile a module once and reuse it for subsequent calls
+    fn load_module(path: &str) -> Result<Module> {
+        static MODULES: OnceLock<Mutex<HashMap<String, Module>>> = OnceLock::new();
+        let modules = MODULES.get_or_init(Default::default);
+
+        if let Some(module) = modules.lock().unwrap().get(path) {
+            return Ok(module.clone());
+        }
+
+        let module = Module::from_file(engine(), path)
+            .map_err(|e| anyhow!("Failed to load WASM plugin {}: {}", path, e))?;
+        modules
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), module.clone());
+
+        Ok(module)
+    }
+
+    /// An instantiated plugin module with its own store
+    pub struct PluginInstance {
+        store: Store<StoreLimits>,
+        instance: Instance,
+        memory: Memory,
+        fuel: u64,
+    }
+
+    impl PluginInstance {
+        pub fn new(path: &str, fuel: u64) -> Result<Self> {
+            let module = load_module(path)?;
+            let limits = StoreLimitsBuilder::new()
+                .memory_size(super::MAX_MEMORY_BYTES)
+                .table_elements(super::MAX_TABLE_ELEMENTS)
+                .instances(1)
+                .build();
+            let mut store = Store::new(engine(), limits);
+            store.limiter(|limits| limits);
+            store.set_fuel(fuel)?;
+
+            let instance = Instance::new(&mut store, &module, &[])
+                .map_err(|e| anyhow!("Failed to instantiate WASM plugin {}: {}", path, e))?;
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or_else(|| anyhow!("WASM plugin {} does not export memory", path))?;
+
+            Ok(Self {
+                store,
+                instance,
+                memory,
+                fuel,
+            })
+        }
+
+        pub fn call(&mut self, export: &str, input: &Value) -> Result<Value> {
+            self.store.set_fuel(self.fuel)?;
+
+            let alloc = self
+                .instance
+                .get_typed_func::<i32, i32>(&mut self.store, "alloc")
+                .map_err(|e| anyhow!("WASM plugin does not export alloc: {}", e))?;
+            let func = self
+                .instance
+                .get_typed_func::<(i32, i32), i64>(&mut self.store, export)
+                .map_err(|e| anyhow!("WASM plugin does not export {}: {}", export, e))?;
+
+            let bytes = serde_json::to_vec(input)?;
+            let len = i32::try_from(bytes.len()).map_err(|_| anyhow!("Plugin input too large"))?;
+            let ptr = alloc.call(&mut self.store, len)?;
+            self.memory
+                .write(&mut self.store, ptr as u32 as usize, &bytes)
+                .map_err(|e| anyhow!("Failed to write plugin input: {}", e))?;
+
+            let packed = func
+                .call(&mut self.store, (ptr, len))
+                .map_err(|e| anyhow!("WASM plugin {} failed: {}", export, e))?;
+            let out_ptr = (packed as u64 >> 32) as usize;
+            let out_len = (packed as u64 & 0xffff_ffff) as usize;
+            if out_ptr.saturating_add(out_len) > self.memory.data_size(&self.store) {
+                return Err(anyhow!(
+                    "WASM plugin {} returned output out of bounds",
+                    export
+                ));
+            }
+
+            let mut output = vec![0u8; out_len];
+            self.memory
+                .read(&self.store, out_ptr, &mut output)
+                .map_err(|e| anyhow!("Failed to read plugin output: {}", e))?;
+
+            if output.is_empty() {
+                return Ok(Value::Null);
+            }
+
+            serde_json::from_slice(&output)
+                .map_err(|e| anyhow!("WASM plugin {} returned invalid JSON: {}", export, e))
+        }
+    }
+}
+
+// Stub implementation when feature is disabled
+#[cfg(not(feature = "wasm-plugins"))]
+mod runtime {
+    use anyhow::{anyhow, Result};
+    use serde_json::Value;
+
+    pub struct PluginInstance;
+
+    impl PluginInstance {
+        pub fn new(_path: &str, _fuel: u64) -> Result<Self> {
+            Err(anyhow!(
+                "WASM plugin support not enabled. Enable the 'wasm-plugins' feature in Cargo.toml"
+            ))
+        }
+
+        pub fn call(&mut self, _export: &str, _input: &Value) -> Result<Value> {
+            Err(anyhow!("WASM plugin support not enabled"))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "wasm-plugins"))]
+mod tests {
+    use super::*;
+
+    // Returns the fixed JSON document stored at offset 1024, whatever the input
+    const CONSTANT_PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 1024) "{\"ok\":true}")
+          (func (export "alloc") (param i32) (result i32) i32.const 2048)
+          (func (export "transform_row") (param i32 i32) (result i64)
+            i64.const 4398046511115))
+    "#;
+
+    // Grows its memory by 2048 pages (128 MiB), returning `{"grown":false}`
+    // when that fails
+    const GROWING_PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 1024) "{\"grown\":false}")
+          (func (export "alloc") (param i32) (result i32) i32.const 2048)
+          (func (export "transform_row") (param i32 i32) (result i64)
+            (if (result i64) (i32.eq (memory.grow (i32.const 2048)) (i32.const -1))
+              (then (i64.const 4398046511119))
+              (else (i64.const 0)))))
+    "#;
+
+    /// A module written to a file of its own, removed on drop
+    struct PluginFile(std::path::PathBuf);
+
+    impl PluginFile {
+        fn new(source: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("pmp-plugin-{}.wat", uuid::Uuid::new_v4()));
+            std::fs::write(&path, source).unwrap();
+            Self(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for PluginFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_export_round_trip() {
+        let plugin = PluginFile::new(CONSTANT_PLUGIN);
+
+        let output = call_export(
+            plugin.path(),
+            "transform_row",
+            json!({"name": "test"}),
+            DEFAULT_FUEL,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output, json!({"ok": true}));
+    }
+
+    #[tokio::test]
+    async fn test_missing_export_fails() {
+        let plugin = PluginFile::new(CONSTANT_PLUGIN);
+
+        let result = call_export(plugin.path(), "query", json!({}), DEFAULT_FUEL).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_memory_growth_is_limited() {
+        let plugin = PluginFile::new(GROWING_PLUGIN);
+
+        let output = call_export(plugin.path(), "transform_row", json!({}), DEFAULT_FUEL)
+            .await
+            .unwrap();
+        assert_eq!(output, json!({"grown": false}));
+    }
+}
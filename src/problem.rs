@@ -0,0 +1,476 @@
+//! Problem details for error responses
+//!
+//! Error responses of the API are RFC 7807 `application/problem+json`
+//! documents, whichever handler or layer produced them:
+//!
+//! ```json
+//! {"type": "/api/errors#validation_failed", "title": "Validation failed",
+//!  "status": 400, "detail": "Validation failed", "code": "validation_failed",
+//!  "instance": "/api/backoffices/shop/sections/orders/actions/create",
+//!  "validation_errors": [...]}
+//! ```
+//!
+//! Handlers build `{"error": "..."}` bodies (or plain text, as axum's
+//! rejections do) and the layer turns them into problems: the message is the
+//! `detail`, and other members, such as `validation_errors`, are kept as
+//! extension members, as is the `request_id` of the request. Every `code` is
+//! one of the catalog served at `/api/errors`, which `type` points into.
+
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Request},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+pub const CONTENT_TYPE: &str = "application/problem+json";
+
+/// An entry of the error-code catalog
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    /// Status the code is answered with
+    pub status: u16,
+    pub title: &'static str,
+    pub description: &'static str,
+}
+
+const fn entry(
+    code: &'static str,
+    status: u16,
+    title: &'static str,
+    description: &'static str,
+) -> ErrorCode {
+    ErrorCode {
+        code,
+        status,
+        title,
+        description,
+    }
+}
+
+/// Every error code; those of a status come first, then the more specific
+/// ones handlers name or whose members identify them
+pub const CATALOG: &[ErrorCode] = &[
+    entry(
+        "bad_request",
+        400,
+        "Bad request",
+        "The request is malformed or has an invalid parameter, body or configuration reference.",
+    ),
+    entry(
+        "unauthorized",
+        401,
+        "Unauthorized",
+        "The bearer token is missing, invalid or expired.",
+    ),
+    entry(
+        "forbidden",
+        403,
+        "Forbidden",
+        "The caller lacks the scopes the action, field, record or transition needs.",
+    ),
+    entry(
+        "not_found",
+        404,
+        "Not found",
+        "The backoffice, section, action, record, config version or route doesn't exist.",
+    ),
+    entry(
+        "method_not_allowed",
+        405,
+        "Method not allowed",
+        "The route doesn't support the request's method.",
+    ),
+    entry(
+        "request_timeout",
+        408,
+        "Request timeout",
+        "The request took longer than `server.limits.request_timeout_secs`.",
+    ),
+    entry(
+        "conflict",
+        409,
+        "Conflict",
+        "The record changed since it was read, or the change conflicts with existing records.",
+    ),
+    entry(
+        "precondition_failed",
+        412,
+        "Precondition failed",
+        "The record's ETag doesn't match `If-Match`.",
+    ),
+    entry(
+        "payload_too_large",
+        413,
+        "Payload too large",
+        "The body is larger than the server or field allows.",
+    ),
+    entry(
+        "unsupported_media_type",
+        415,
+        "Unsupported media type",
+        "The body's content type isn't accepted.",
+    ),
+    entry(
+        "unprocessable_entity",
+        422,
+        "Unprocessable entity",
+        "The body is well-formed but can't be processed.",
+    ),
+    entry(
+        "rate_limited",
+        429,
+        "Too many requests",
+        "The caller sent too many requests; see `Retry-After`.",
+    ),
+    entry(
+        "internal_error",
+        500,
+        "Internal server error",
+        "The server or a data source failed unexpectedly.",
+    ),
+    entry(
+        "not_implemented",
+        501,
+        "Not implemented",
+        "The data source doesn't support the operation.",
+    ),
+    entry(
+        "bad_gateway",
+        502,
+        "Bad gateway",
+        "A data source or storage backend answered with an error.",
+    ),
+    entry(
+        "service_unavailable",
+        503,
+        "Service unavailable",
+        "A data source is unavailable, e.g. its circuit breaker is open; see `Retry-After`.",
+    ),
+    entry(
+        "gateway_timeout",
+        504,
+        "Gateway timeout",
+        "A data source didn't answer in time.",
+    ),
+    entry(
+        "validation_failed",
+        400,
+        "Validation failed",
+        "Fields failed their validation rules; see `validation_errors`.",
+    ),
+    entry(
+        "relationship_validation_failed",
+        400,
+        "Relationship validation failed",
+        "Referenced records don't exist; see `relationship_errors`.",
+    ),
+    entry(
+        "invalid_action",
+        400,
+        "Invalid action",
+        "The upload's action isn't a form action.",
+    ),
+    entry(
+        "invalid_field",
+        400,
+        "Invalid field",
+        "The upload's field isn't a file or image field.",
+    ),
+    entry(
+        "empty_file",
+        400,
+        "Empty file",
+        "The uploaded file is empty.",
+    ),
+    entry(
+        "write_forbidden",
+        403,
+        "Write forbidden",
+        "The caller lacks the scopes to write the field.",
+    ),
+    entry(
+        "file_too_large",
+        413,
+        "File too large",
+        "The uploaded file is larger than the field's maximum size.",
+    ),
+    entry(
+        "file_type",
+        415,
+        "File type not accepted",
+        "The uploaded file's type isn't one the field accepts.",
+    ),
+    entry(
+        "virus_found",
+        422,
+        "Virus found",
+        "The uploaded file is infected; see `signature`.",
+    ),
+    entry(
+        "storage_failed",
+        502,
+        "Storage failed",
+        "The uploaded file couldn't be stored.",
+    ),
+    entry(
+        "scan_failed",
+        503,
+        "Scan failed",
+        "The uploaded file couldn't be scanned for viruses.",
+    ),
+];
+
+fn find(code: &str) -> Option<&'static ErrorCode> {
+    CATALOG.iter().find(|entry| entry.code == code)
+}
+
+/// The code of an error response, from the code its handler named, its
+/// members or its status
+pub fn code_for(status: StatusCode, members: &Map<String, Value>) -> &'static ErrorCode {
+    members
+        .get("code")
+        .and_then(Value::as_str)
+        .and_then(find)
+        .or_else(|| {
+            members
+                .contains_key("validation_errors")
+                .then(|| find("validation_failed"))
+                .flatten()
+        })
+        .or_else(|| {
+            members
+                .contains_key("relationship_errors")
+                .then(|| find("relationship_validation_failed"))
+                .flatten()
+        })
+        .or_else(|| CATALOG.iter().find(|entry| entry.status == status.as_u16()))
+        .unwrap_or_else(|| {
+            let code = if status.is_server_error() {
+                "internal_error"
+            } else {
+                "bad_request"
+            };
+            find(code).expect("generic codes are in the catalog")
+        })
+}
+
+/// The problem document of an error body
+pub fn problem(status: StatusCode, body: Value, instance: &str) -> Value {
+    let mut members = match body {
+        Value::Object(members) => members,
+        Value::String(text) if !text.trim().is_empty() => {
+            Map::from_iter([("error".to_string(), Value::String(text))])
+        }
+        _ => Map::new(),
+    };
+    let code = code_for(status, &members);
+    members.remove("code");
+    let detail = members.remove("error").map(|error| match error {
+        Value::String(text) => text,
+        error => error.to_string(),
+    });
+
+    let mut problem = Map::new();
+    problem.insert("type".into(), format!("/api/errors#{}", code.code).into());
+    problem.insert("title".into(), code.title.into());
+    problem.insert("status".into(), status.as_u16().into());
+    if let Some(detail) = detail {
+        problem.insert("detail".into(), detail.into());
+    }
+    problem.insert("instance".into(), instance.into());
+    problem.insert("code".into(), code.code.into());
+    for (name, value) in members {
+        problem.entry(name).or_insert(value);
+    }
+    Value::Object(problem)
+}
+
+/// Turn the error responses of API routes into problem documents
+pub async fn problems(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let instance = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| path.clone(), |uri| uri.path().to_string());
+    let response = next.run(request).await;
+
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) || !path.starts_with("/api") {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let is_json = content_type.starts_with("application/json");
+    if !is_json && !content_type.is_empty() && !content_type.starts_with("text/plain") {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!(error = %e, "Failed to read error response body");
+            Default::default()
+        }
+    };
+    let body = if is_json {
+        serde_json::from_slice(&bytes).unwrap_or_default()
+    } else {
+        Value::String(String::from_utf8_lossy(&bytes).into_owned())
+    };
+    let mut problem = problem(status, body, &instance);
+    if let Some(id) = crate::request_id::current() {
+        problem["request_id"] = id.into();
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE));
+    Response::from_parts(parts, Body::from(problem.to_string()))
+}
+
+/// The error-code catalog
+pub async fn catalog_handler() -> impl IntoResponse {
+    Json(CATALOG)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::RouterBuilder;
+    use crate::test_support::sqlite_backoffice;
+    use serde_json::json;
+
+    #[test]
+    fn test_problem() {
+        let invalid =
+            json!({"error": "Validation failed", "validation_errors": [{"field": "name"}]});
+        assert_eq!(
+            problem(StatusCode::BAD_REQUEST, invalid, "/api/x"),
+            json!({
+                "type": "/api/errors#validation_failed",
+                "title": "Validation failed",
+                "status": 400,
+                "detail": "Validation failed",
+                "instance": "/api/x",
+                "code": "validation_failed",
+                "validation_errors": [{"field": "name"}]
+            })
+        );
+
+        // Codes handlers name win over the status
+        let infected = json!({"error": "The file is infected", "code": "virus_found"});
+        let infected = problem(StatusCode::UNPROCESSABLE_ENTITY, infected, "/api/x");
+        assert_eq!(infected["type"], "/api/errors#virus_found");
+        assert_eq!(infected["title"], "Virus found");
+
+        let text = problem(
+            StatusCode::NOT_FOUND,
+            json!("Backoffice not found"),
+            "/api/x",
+        );
+        assert_eq!(text["code"], "not_found");
+        assert_eq!(text["detail"], "Backoffice not found");
+
+        let gone = problem(StatusCode::GONE, Value::Null, "/api/x");
+        assert_eq!(gone["code"], "bad_request");
+        assert_eq!(gone["status"], 410);
+        assert!(gone.get("detail").is_none());
+
+        let mut codes: Vec<&str> = CATALOG.iter().map(|entry| entry.code).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), CATALOG.len());
+    }
+
+    #[tokio::test]
+    async fn test_problem_responses() {
+        use tower::ServiceExt;
+
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: crm
+name: CRM
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text, required: true }
+"#,
+            &[],
+        )
+        .await;
+        let router = RouterBuilder::new(state.config.clone(), state.backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .build();
+        let send = |method: &str, uri: &str, body: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let content_type = response.headers()["content-type"].clone();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let body: Value = serde_json::from_slice(&bytes).unwrap();
+                (status, content_type, body)
+            }
+        };
+        let create = "/api/backoffices/crm/sections/users/actions/create";
+
+        let (status, content_type, body) = send("POST", create, "{}").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(content_type, crate::problem::CONTENT_TYPE);
+        assert_eq!(body["type"], "/api/errors#validation_failed");
+        assert_eq!(body["status"], 400);
+        assert_eq!(body["instance"], create);
+        assert_eq!(body["validation_errors"][0]["field"], "name");
+
+        // axum's plain text rejections become problems too
+        let (status, content_type, body) = send("POST", create, "{").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(content_type, crate::problem::CONTENT_TYPE);
+        assert_eq!(body["code"], "bad_request");
+        assert!(body["detail"].is_string());
+
+        let (status, _, body) = send("GET", "/api/v1/backoffices/missing", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["instance"], "/api/v1/backoffices/missing");
+
+        let (status, content_type, body) = send("GET", "/api/errors", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/json");
+        let codes: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["code"].as_str().unwrap())
+            .collect();
+        assert!(codes.contains(&"validation_failed"));
+    }
+}
@@ -0,0 +1,648 @@
+//! Real-time events over WebSocket
+//!
+//! `GET /api/ws` upgrades to a WebSocket on which clients subscribe to
+//! channels by sending `{"action": "subscribe", "channel": ...}` messages:
+//!
+//! - `section` (with `backoffice_id` and `section_id`): records of the
+//!   section were created, updated or deleted
+//! - `dashboard` (with `backoffice_id`): dashboard widgets whose data changed
+//! - `audit` (with `backoffice_id`): audit entries as they are logged
+//! - `tasks`: outcomes of background tasks such as data source health checks
+//!
+//! Each subscription is checked against the caller's scopes the way the
+//! matching endpoint checks them. Mutation events carry record ids but no
+//! values; clients refetch the records, so row filters, field permissions
+//! and masking apply as usual.
+
+use crate::audit::{AuditLogEntry, AuditOperation};
+use crate::auth::AuthContext;
+use crate::config::{ActionType, SectionConfig};
+use crate::server::AppState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+/// Events a lagging connection may fall behind by before it misses some
+const EVENT_CAPACITY: usize = 1024;
+
+/// Something that happened on the server, before it's routed to subscribers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PushEvent {
+    /// Records of a section changed; `record_id` is unset for bulk changes
+    Mutation {
+        backoffice_id: String,
+        section_id: String,
+        operation: AuditOperation,
+        record_id: Option<String>,
+        user_id: Option<String>,
+    },
+    /// A background task finished
+    Task {
+        task: String,
+        ok: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        finished_at: DateTime<Utc>,
+    },
+}
+
+impl PushEvent {
+    pub fn mutation(
+        backoffice_id: &str,
+        section_id: &str,
+        operation: AuditOperation,
+        record_id: Option<String>,
+        user_id: Option<String>,
+    ) -> Self {
+        PushEvent::Mutation {
+            backoffice_id: backoffice_id.to_string(),
+            section_id: section_id.to_string(),
+            operation,
+            record_id,
+            user_id,
+        }
+    }
+
+    pub fn task(task: &str, error: Option<String>) -> Self {
+        PushEvent::Task {
+            task: task.to_string(),
+            ok: error.is_none(),
+            error,
+            finished_at: Utc::now(),
+        }
+    }
+}
+
+/// Fan-out of events to every open connection
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<PushEvent>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl EventHub {
+    /// Send an event to the connections subscribed to it, if any
+    pub fn publish(&self, event: PushEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PushEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// A channel as clients name it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "channel", rename_all = "snake_case")]
+pub enum Channel {
+    Section {
+        backoffice_id: String,
+        section_id: String,
+    },
+    Dashboard {
+        backoffice_id: String,
+    },
+    Audit {
+        backoffice_id: String,
+    },
+    Tasks,
+}
+
+/// A channel the caller was allowed to subscribe to, with what it may see
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub channel: Channel,
+    /// `(widget, section)` of the dashboard widgets the caller may see
+    widgets: Vec<(String, String)>,
+    /// Sections whose audit entries the caller may see
+    sections: Vec<String>,
+}
+
+/// Whether the caller may read a section: they hold the scopes of one of
+/// its list or view actions
+fn readable(section: &SectionConfig, auth: &AuthContext) -> bool {
+    section.actions.iter().any(|action| {
+        matches!(
+            action.action_type,
+            ActionType::List { .. } | ActionType::View { .. }
+        ) && auth.has_all_scopes(&action.required_scopes)
+    })
+}
+
+/// Check a subscription against the caller's scopes
+pub fn authorize(
+    state: &AppState,
+    channel: Channel,
+    auth: &AuthContext,
+) -> Result<Subscription, String> {
+    let backoffice_id = match &channel {
+        Channel::Section { backoffice_id, .. }
+        | Channel::Dashboard { backoffice_id }
+        | Channel::Audit { backoffice_id } => Some(backoffice_id),
+        Channel::Tasks => None,
+    };
+    let backoffice = match backoffice_id {
+        Some(id) => Some(
+            state
+                .backoffice(id)
+                .ok_or_else(|| "Backoffice not found".to_string())?,
+        ),
+        None => None,
+    };
+    let mut subscription = Subscription {
+        channel: channel.clone(),
+        widgets: Vec::new(),
+        sections: Vec::new(),
+    };
+
+    match (&channel, backoffice) {
+        (Channel::Section { section_id, .. }, Some(backoffice)) => {
+            let section = backoffice
+                .sections
+                .iter()
+                .find(|s| s.id == *section_id)
+                .ok_or_else(|| "Section not found".to_string())?;
+            if !readable(section, auth) {
+                return Err("Missing required scopes".to_string());
+            }
+        }
+        (Channel::Dashboard { .. }, Some(backoffice)) => {
+            let dashboard = backoffice
+                .dashboard
+                .as_ref()
+                .ok_or_else(|| "Backoffice has no dashboard".to_string())?;
+            subscription.widgets = dashboard
+                .widgets
+                .iter()
+                .filter(|widget| {
+                    backoffice
+                        .sections
+                        .iter()
+                        .find(|s| s.id == widget.section)
+                        .and_then(|s| s.actions.iter().find(|a| a.id == widget.action))
+                        .is_none_or(|action| auth.has_all_scopes(&action.required_scopes))
+                })
+                .map(|widget| (widget.id.clone(), widget.section.clone()))
+                .collect();
+        }
+        (Channel::Audit { .. }, Some(backoffice)) => {
+            // As the audit search: sections whose every action is permitted
+            subscription.sections = backoffice
+                .sections
+                .iter()
+                .filter(|s| {
+                    s.actions
+                        .iter()
+                        .all(|a| auth.has_all_scopes(&a.required_scopes))
+                })
+                .map(|s| s.id.clone())
+                .collect();
+        }
+        _ => {
+            if !auth.has_all_scopes(&state.config.push.task_scopes) {
+                return Err("Missing required scopes".to_string());
+            }
+        }
+    }
+    Ok(subscription)
+}
+
+impl Subscription {
+    /// The message this subscription sends for an event, if any
+    pub fn message(&self, event: &PushEvent) -> Option<Value> {
+        match (&self.channel, event) {
+            (
+                Channel::Section {
+                    backoffice_id,
+                    section_id,
+                },
+                PushEvent::Mutation {
+                    backoffice_id: event_backoffice,
+                    section_id: event_section,
+                    ..
+                },
+            ) if backoffice_id == event_backoffice && section_id == event_section => {
+                serde_json::to_value(event).ok()
+            }
+            (
+                Channel::Dashboard { backoffice_id },
+                PushEvent::Mutation {
+                    backoffice_id: event_backoffice,
+                    section_id,
+                    ..
+                },
+            ) if backoffice_id == event_backoffice => {
+                let widgets: Vec<&str> = self
+                    .widgets
+                    .iter()
+                    .filter(|(_, section)| section == section_id)
+                    .map(|(widget, _)| widget.as_str())
+                    .collect();
+                (!widgets.is_empty()).then(|| {
+                    serde_json::json!({
+                        "event": "dashboard",
+                        "backoffice_id": backoffice_id,
+                        "widgets": widgets
+                    })
+                })
+            }
+            (Channel::Tasks, PushEvent::Task { .. }) => serde_json::to_value(event).ok(),
+            _ => None,
+        }
+    }
+
+    /// The message this subscription sends for a logged audit entry, if any
+    pub fn audit_message(&self, entry: &AuditLogEntry) -> Option<Value> {
+        match &self.channel {
+            Channel::Audit { backoffice_id } if self.sections.contains(&entry.section_id) => {
+                Some(serde_json::json!({
+                    "event": "audit",
+                    "backoffice_id": backoffice_id,
+                    "entry": entry
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "websocket-push")]
+pub use socket::ws_handler;
+
+#[cfg(feature = "websocket-push")]
+mod socket {
+    use super::*;
+    use axum::{
+        body::Body,
+        extract::{Query, Request, State},
+        http::{header, HeaderValue, StatusCode},
+        response::{IntoResponse, Json, Response},
+    };
+    use futures_util::{SinkExt, StreamExt};
+    use hyper_util::rt::TokioIo;
+    use std::sync::Arc;
+    use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+    use tokio_tungstenite::tungstenite::protocol::Role;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+    use tracing::{debug, info, warn};
+
+    #[derive(Debug, Clone, Copy, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum SubscriptionAction {
+        Subscribe,
+        Unsubscribe,
+    }
+
+    /// A message sent by the client
+    #[derive(Debug, Deserialize)]
+    struct ClientMessage {
+        action: SubscriptionAction,
+        #[serde(flatten)]
+        channel: Channel,
+    }
+
+    /// Apply a client message to a connection's subscriptions, returning the
+    /// reply to send
+    fn handle_message(
+        state: &AppState,
+        subscriptions: &mut Vec<Subscription>,
+        text: &str,
+        auth: &AuthContext,
+    ) -> Value {
+        let message: ClientMessage = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                return serde_json::json!({
+                    "event": "error",
+                    "error": format!("Invalid message: {}", e)
+                })
+            }
+        };
+        match message.action {
+            SubscriptionAction::Subscribe => {
+                match authorize(state, message.channel.clone(), auth) {
+                    Ok(subscription) => {
+                        subscriptions.retain(|s| s.channel != message.channel);
+                        subscriptions.push(subscription);
+                        serde_json::json!({"event": "subscribed", "subscription": message.channel})
+                    }
+                    Err(error) => serde_json::json!({
+                        "event": "error",
+                        "subscription": message.channel,
+                        "error": error
+                    }),
+                }
+            }
+            SubscriptionAction::Unsubscribe => {
+                subscriptions.retain(|s| s.channel != message.channel);
+                serde_json::json!({"event": "unsubscribed", "subscription": message.channel})
+            }
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct WsQuery {
+        /// Bearer token, for browsers, which can't set headers on WebSockets
+        access_token: Option<String>,
+    }
+
+    /// Upgrade to the push WebSocket
+    pub async fn ws_handler(
+        State(state): State<Arc<AppState>>,
+        Query(query): Query<WsQuery>,
+        mut request: Request,
+    ) -> Response {
+        let mut headers = request.headers().clone();
+        if let Some(token) = &query.access_token {
+            if let Ok(value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+                headers.entry(header::AUTHORIZATION).or_insert(value);
+            }
+        }
+        let auth = match AuthContext::from_headers(&headers, state.config.security.as_ref()) {
+            Ok(auth) => auth,
+            Err(e) => {
+                warn!(error = %e, "Rejected WebSocket with invalid credentials");
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                )
+                    .into_response();
+            }
+        };
+
+        let upgrade = headers
+            .get(header::UPGRADE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+        let key = headers.get(header::SEC_WEBSOCKET_KEY).cloned();
+        let on_upgrade = request
+            .extensions_mut()
+            .remove::<hyper::upgrade::OnUpgrade>();
+        let (Some(key), Some(on_upgrade), true) = (key, on_upgrade, upgrade) else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Expected a WebSocket upgrade request"})),
+            )
+                .into_response();
+        };
+
+        tokio::spawn(async move {
+            match on_upgrade.await {
+                Ok(upgraded) => {
+                    let socket = WebSocketStream::from_raw_socket(
+                        TokioIo::new(upgraded),
+                        Role::Server,
+                        None,
+                    )
+                    .await;
+                    serve(socket, state, auth).await;
+                }
+                Err(e) => warn!(error = %e, "WebSocket upgrade failed"),
+            }
+        });
+
+        Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(
+                header::SEC_WEBSOCKET_ACCEPT,
+                derive_accept_key(key.as_bytes()),
+            )
+            .body(Body::empty())
+            .unwrap_or_default()
+    }
+
+    /// Relay events to a connection until it closes
+    async fn serve<S>(socket: WebSocketStream<S>, state: Arc<AppState>, auth: AuthContext)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let (mut sink, mut incoming) = socket.split();
+        let mut events = state.events.subscribe();
+        let mut audit = state.audit_logger.subscribe();
+        let mut subscriptions = Vec::new();
+        info!(user_id = ?auth.user_id, "Push client connected");
+
+        loop {
+            let messages: Vec<Value> = tokio::select! {
+                message = incoming.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        vec![handle_message(&state, &mut subscriptions, &text, &auth)]
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        debug!(error = %e, "Push connection failed");
+                        break;
+                    }
+                },
+                event = events.recv() => match event {
+                    Ok(event) => subscriptions.iter().filter_map(|s| s.message(&event)).collect(),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        vec![serde_json::json!({"event": "lagged", "missed": missed})]
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                entry = audit.recv() => match entry {
+                    Ok(entry) => subscriptions
+                        .iter()
+                        .filter_map(|s| s.audit_message(&entry))
+                        .collect(),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        vec![serde_json::json!({"event": "lagged", "missed": missed})]
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+            for message in messages {
+                if sink.send(Message::Text(message.to_string())).await.is_err() {
+                    return;
+                }
+            }
+        }
+        info!(user_id = ?auth.user_id, "Push client disconnected");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_routing() {
+        let channel: Channel = serde_json::from_value(json!({
+            "channel": "dashboard",
+            "backoffice_id": "shop"
+        }))
+        .unwrap();
+        let dashboard = Subscription {
+            channel,
+            widgets: vec![
+                ("revenue".to_string(), "orders".to_string()),
+                ("signups".to_string(), "customers".to_string()),
+            ],
+            sections: vec![],
+        };
+        let section = Subscription {
+            channel: Channel::Section {
+                backoffice_id: "shop".to_string(),
+                section_id: "orders".to_string(),
+            },
+            widgets: vec![],
+            sections: vec![],
+        };
+
+        let created = PushEvent::mutation(
+            "shop",
+            "orders",
+            AuditOperation::Create,
+            Some("7".to_string()),
+            Some("ada".to_string()),
+        );
+        let message = section.message(&created).unwrap();
+        assert_eq!(message["event"], "mutation");
+        assert_eq!(message["operation"], "create");
+        assert_eq!(message["record_id"], "7");
+        assert_eq!(
+            dashboard.message(&created).unwrap()["widgets"],
+            json!(["revenue"])
+        );
+
+        let elsewhere = PushEvent::mutation("shop", "products", AuditOperation::Update, None, None);
+        assert!(section.message(&elsewhere).is_none());
+        assert!(dashboard.message(&elsewhere).is_none());
+        assert!(section
+            .message(&PushEvent::task("health_checks", None))
+            .is_none());
+
+        let audit = Subscription {
+            channel: Channel::Audit {
+                backoffice_id: "shop".to_string(),
+            },
+            widgets: vec![],
+            sections: vec!["orders".to_string()],
+        };
+        let entry = crate::audit::AuditLogger::delete_entry(
+            "orders".to_string(),
+            "7".to_string(),
+            None,
+            None,
+        );
+        assert_eq!(
+            audit.audit_message(&entry).unwrap()["entry"]["record_id"],
+            "7"
+        );
+        assert!(section.audit_message(&entry).is_none());
+    }
+
+    #[cfg(feature = "websocket-push")]
+    #[tokio::test]
+    async fn test_push_websocket() {
+        use crate::server::RouterBuilder;
+        use crate::test_support::sqlite_backoffice;
+        use futures_util::{SinkExt, StreamExt};
+        use std::net::SocketAddr;
+        use std::time::Duration;
+        use tokio_tungstenite::tungstenite::Message;
+
+        async fn next_json<S, E>(socket: &mut S) -> Value
+        where
+            S: futures_util::Stream<Item = Result<Message, E>> + Unpin,
+            E: std::fmt::Debug,
+        {
+            let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            serde_json::from_str(message.to_text().unwrap()).unwrap()
+        }
+
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: crm
+name: CRM
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text, required: true }
+"#,
+            &["CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)"],
+        )
+        .await;
+        let app = RouterBuilder::new(state.config.clone(), state.backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .build();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap()
+        });
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/ws", addr))
+            .await
+            .unwrap();
+        for subscribe in [
+            serde_json::json!({"action": "subscribe", "channel": "section", "backoffice_id": "crm", "section_id": "orders"}),
+            serde_json::json!({"action": "subscribe", "channel": "section", "backoffice_id": "crm", "section_id": "users"}),
+        ] {
+            socket
+                .send(Message::Text(subscribe.to_string()))
+                .await
+                .unwrap();
+        }
+        let reply = next_json(&mut socket).await;
+        assert_eq!(reply["event"], "error");
+        assert_eq!(reply["error"], "Section not found");
+        assert_eq!(next_json(&mut socket).await["event"], "subscribed");
+
+        let response = reqwest::Client::new()
+            .post(format!(
+                "http://{}/api/backoffices/crm/sections/users/actions/create",
+                addr
+            ))
+            .json(&serde_json::json!({"name": "Ada"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        let event = next_json(&mut socket).await;
+        assert_eq!(event["event"], "mutation");
+        assert_eq!(event["section_id"], "users");
+        assert_eq!(event["operation"], "create");
+    }
+}
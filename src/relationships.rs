@@ -1,8 +1,16 @@
-use crate::config::{BackofficeConfig, RelationshipConfig, RelationshipType};
-use crate::data_source::DataSource;
+use crate::auth::AuthContext;
+use crate::config::{
+    ActionConfig, ActionType, BackofficeConfig, DataSourceConfig, FieldConfig, FieldWritePolicy,
+    OnDelete, RelationshipConfig, RelationshipType, SectionConfig,
+};
+use crate::crud::{self, quote_identifier, CrudOperation};
+use crate::data_source::{DataSource, Mutation};
+use crate::validation::{self, ValidationError, ValidationFunctions};
+use crate::{masking, permissions, row_filters, seeds};
 use anyhow::{anyhow, Result};
+use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tracing::{debug, info, warn};
 
 /// Validate foreign key relationships before mutation
@@ -51,11 +59,8 @@ pub async fn validate_foreign_keys(
                 RelationshipType::OneToOne | RelationshipType::ManyToOne => {
                     // Check if a record exists with the given ID
                     format!(
-                        "SELECT {} FROM {} WHERE {} = '{}'",
-                        relationship.to_field,
-                        relationship.to_section,
-                        relationship.to_field,
-                        fk_value.as_str().unwrap_or("")
+                        "SELECT {} FROM {} WHERE {} = {{{{value}}}}",
+                        relationship.to_field, relationship.to_section, relationship.to_field,
                     )
                 }
                 RelationshipType::OneToMany => {
@@ -75,7 +80,8 @@ pub async fn validate_foreign_keys(
             );
 
             // Execute the query
-            match data_source.execute_query(&query, None).await {
+            let params = HashMap::from([("value".to_string(), fk_value.clone())]);
+            match data_source.execute_query(&query, Some(&params)).await {
                 Ok(results) => {
                     if results.is_empty() {
                         errors.push(RelationshipError {
@@ -106,86 +112,438 @@ pub async fn validate_foreign_keys(
         }
     }
 
+    // Moving a node of a tree under its own subtree would detach it
+    for relationship in backoffice
+        .relationships
+        .iter()
+        .filter(|r| r.from_section == section_id && is_tree(r))
+    {
+        if let Some(error) = find_cycle(data, relationship, backoffice, data_sources).await? {
+            errors.push(error);
+        }
+    }
+
     Ok(errors)
 }
 
-/// Handle cascade delete operations
+/// Most keys looked up by one query of `validate_foreign_keys_batch`
+const FOREIGN_KEY_BATCH: usize = 500;
+
+/// Validate the foreign keys of many payloads, such as the rows of an import
+///
+/// Each relationship's referenced records are looked up with one
+/// `IN (...)` query per batch of distinct keys rather than one per payload.
+/// Errors come with the index of their payload.
+pub async fn validate_foreign_keys_batch(
+    rows: &[&HashMap<String, Value>],
+    section_id: &str,
+    backoffice: &BackofficeConfig,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+) -> Result<Vec<(usize, RelationshipError)>> {
+    let mut errors = Vec::new();
+
+    for relationship in backoffice.relationships.iter().filter(|r| {
+        r.from_section == section_id
+            && matches!(
+                r.relationship_type,
+                RelationshipType::OneToOne | RelationshipType::ManyToOne
+            )
+    }) {
+        let mut keys: Vec<(String, &Value)> = Vec::new();
+        for value in rows
+            .iter()
+            .filter_map(|row| row.get(&relationship.from_field))
+        {
+            if let Some(key) = lookup_key(value) {
+                if !keys.iter().any(|(k, _)| *k == key) {
+                    keys.push((key, value));
+                }
+            }
+        }
+        if keys.is_empty() {
+            continue;
+        }
+
+        let target_section = backoffice
+            .sections
+            .iter()
+            .find(|s| s.id == relationship.to_section)
+            .ok_or_else(|| anyhow!("Target section not found: {}", relationship.to_section))?;
+        let target_action = target_section
+            .actions
+            .first()
+            .ok_or_else(|| anyhow!("No actions found in target section"))?;
+        let data_source = data_sources
+            .get(&target_action.data_source)
+            .ok_or_else(|| anyhow!("Data source not found: {}", target_action.data_source))?;
+
+        let mut found = HashSet::new();
+        let mut failure = None;
+        for chunk in keys.chunks(FOREIGN_KEY_BATCH) {
+            let mut params = HashMap::new();
+            let query = format!(
+                "SELECT {} FROM {} WHERE {} IN ({})",
+                relationship.to_field,
+                relationship.to_section,
+                relationship.to_field,
+                placeholders("key", chunk.iter().map(|(_, value)| *value), &mut params)
+            );
+            debug!(
+                relationship = %relationship.id,
+                keys = chunk.len(),
+                "Validating foreign keys"
+            );
+            match data_source.execute_query(&query, Some(&params)).await {
+                Ok(results) => found.extend(
+                    results
+                        .iter()
+                        .filter_map(|record| record.get(&relationship.to_field))
+                        .filter_map(lookup_key),
+                ),
+                Err(e) => {
+                    warn!(
+                        error = %e,
+                        relationship = %relationship.id,
+                        "Failed to validate foreign keys"
+                    );
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        for (index, row) in rows.iter().enumerate() {
+            let Some(key) = row.get(&relationship.from_field).and_then(lookup_key) else {
+                continue;
+            };
+            let message = match &failure {
+                Some(e) => format!("Failed to validate relationship: {}", e),
+                None if found.contains(&key) => continue,
+                None => format!(
+                    "Referenced {} with {} = {} does not exist",
+                    relationship.to_section, relationship.to_field, key
+                ),
+            };
+            errors.push((
+                index,
+                RelationshipError {
+                    relationship_id: relationship.id.clone(),
+                    field: relationship.from_field.clone(),
+                    message,
+                },
+            ));
+        }
+    }
+
+    // Cycles depend on each row's own place in the tree
+    for relationship in backoffice
+        .relationships
+        .iter()
+        .filter(|r| r.from_section == section_id && is_tree(r))
+    {
+        for (index, row) in rows.iter().enumerate() {
+            if let Some(error) = find_cycle(row, relationship, backoffice, data_sources).await? {
+                errors.push((index, error));
+            }
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Deepest hierarchy walked when looking for cycles
+const MAX_TREE_DEPTH: usize = 1000;
+
+/// Whether a relationship links records of a section to their parent in the
+/// same section, e.g. categories with a `parent_id`
+pub fn is_tree(relationship: &RelationshipConfig) -> bool {
+    relationship.from_section == relationship.to_section
+        && matches!(
+            relationship.relationship_type,
+            RelationshipType::ManyToOne | RelationshipType::OneToOne
+        )
+}
+
+/// Check that a record's new parent is not the record itself or one of its
+/// descendants, by walking up the ancestors of the parent
+async fn find_cycle(
+    data: &HashMap<String, Value>,
+    relationship: &RelationshipConfig,
+    backoffice: &BackofficeConfig,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+) -> Result<Option<RelationshipError>> {
+    let (Some(record), Some(mut parent)) = (
+        data.get(&relationship.to_field).and_then(lookup_key),
+        data.get(&relationship.from_field).cloned(),
+    ) else {
+        return Ok(None);
+    };
+    let data_source = source_data_source(relationship, backoffice, data_sources)?;
+    let table = section_table(backoffice, &relationship.from_section);
+    let cycle = |message: &str| {
+        Some(RelationshipError {
+            relationship_id: relationship.id.clone(),
+            field: relationship.from_field.clone(),
+            message: message.to_string(),
+        })
+    };
+
+    for _ in 0..MAX_TREE_DEPTH {
+        let Some(key) = lookup_key(&parent) else {
+            return Ok(None);
+        };
+        if key == record {
+            return Ok(cycle(
+                "A record cannot be moved under itself or its descendants",
+            ));
+        }
+
+        let query = format!(
+            "SELECT {} FROM {} WHERE {} = {{{{parent}}}}",
+            relationship.from_field, table, relationship.to_field,
+        );
+        debug!(relationship = %relationship.id, query = %query, "Walking up the tree");
+        let params = HashMap::from([("parent".to_string(), parent.clone())]);
+        let rows = data_source.execute_query(&query, Some(&params)).await?;
+        match rows
+            .first()
+            .and_then(|row| row.get(&relationship.from_field))
+        {
+            Some(grandparent) if !grandparent.is_null() => parent = grandparent.clone(),
+            _ => return Ok(None),
+        }
+    }
+
+    Ok(cycle(&format!(
+        "The hierarchy is deeper than {} levels or already contains a cycle",
+        MAX_TREE_DEPTH
+    )))
+}
+
+/// Table (or collection) of a section, defaulting to its id
+pub(crate) fn section_table<'a>(backoffice: &'a BackofficeConfig, section_id: &'a str) -> &'a str {
+    backoffice
+        .sections
+        .iter()
+        .find(|s| s.id == section_id)
+        .and_then(|s| s.table.as_deref())
+        .unwrap_or(section_id)
+}
+
+/// Arrange records into a tree following a tree relationship
+///
+/// Without `root`, the top level holds records without a parent among
+/// `rows`; with it, the children of that record. Every node reports
+/// `has_children`; nodes above `max_depth` also carry their `children`.
+pub fn build_tree(
+    rows: Vec<HashMap<String, Value>>,
+    relationship: &RelationshipConfig,
+    root: Option<&Value>,
+    max_depth: Option<usize>,
+) -> Vec<Value> {
+    let keys: HashSet<String> = rows
+        .iter()
+        .filter_map(|row| row.get(&relationship.to_field).and_then(lookup_key))
+        .collect();
+    let mut children: HashMap<Option<String>, Vec<usize>> = HashMap::new();
+    for (index, row) in rows.iter().enumerate() {
+        // Records whose parent is not visible become top-level records
+        let parent = row
+            .get(&relationship.from_field)
+            .and_then(lookup_key)
+            .filter(|key| keys.contains(key));
+        children.entry(parent).or_default().push(index);
+    }
+
+    fn nodes(
+        parent: Option<String>,
+        depth: usize,
+        rows: &[HashMap<String, Value>],
+        children: &HashMap<Option<String>, Vec<usize>>,
+        relationship: &RelationshipConfig,
+        max_depth: Option<usize>,
+        ancestors: &mut HashSet<String>,
+    ) -> Vec<Value> {
+        let Some(indexes) = children.get(&parent) else {
+            return Vec::new();
+        };
+        indexes
+            .iter()
+            .map(|&index| {
+                let row = &rows[index];
+                let key = row.get(&relationship.to_field).and_then(lookup_key);
+                let mut node: serde_json::Map<String, Value> = row.clone().into_iter().collect();
+                node.insert(
+                    "has_children".to_string(),
+                    Value::Bool(key.as_ref().is_some_and(|k| {
+                        children
+                            .get(&Some(k.clone()))
+                            .is_some_and(|c| !c.is_empty())
+                    })),
+                );
+
+                // Existing cycles in the data are cut where they close
+                let expand = max_depth.is_none_or(|max| depth < max)
+                    && key.as_ref().is_some_and(|k| ancestors.insert(k.clone()));
+                if expand {
+                    let descendants = nodes(
+                        key.clone(),
+                        depth + 1,
+                        rows,
+                        children,
+                        relationship,
+                        max_depth,
+                        ancestors,
+                    );
+                    node.insert("children".to_string(), Value::Array(descendants));
+                    if let Some(key) = &key {
+                        ancestors.remove(key);
+                    }
+                }
+                Value::Object(node)
+            })
+            .collect()
+    }
+
+    nodes(
+        root.and_then(lookup_key),
+        1,
+        &rows,
+        &children,
+        relationship,
+        max_depth,
+        &mut HashSet::new(),
+    )
+}
+
+/// Resolve what deleting a record does to the records referencing it
+///
+/// Relationships targeting the section apply their `on_delete` policy:
+/// `cascade` deletes dependents (recursively, deepest first, so a tree
+/// relationship deletes the whole subtree), `set_null` clears their
+/// `from_field` and `restrict` fails with [`RestrictedDeleteError`] while
+/// any dependent exists.
 pub async fn handle_cascade_delete(
     record_id: &str,
     section_id: &str,
     backoffice: &BackofficeConfig,
     data_sources: &HashMap<String, Box<dyn DataSource>>,
+) -> Result<Vec<CascadeOperation>> {
+    let mut visited = HashSet::from([(section_id.to_string(), record_id.to_string())]);
+    delete_operations(
+        record_id,
+        section_id,
+        backoffice,
+        data_sources,
+        &mut visited,
+    )
+    .await
+}
+
+/// Operations for deleting one record; `visited` keeps cycles in the data
+/// from recursing forever
+async fn delete_operations(
+    record_id: &str,
+    section_id: &str,
+    backoffice: &BackofficeConfig,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+    visited: &mut HashSet<(String, String)>,
 ) -> Result<Vec<CascadeOperation>> {
     let mut operations = Vec::new();
+    let mut restricted = Vec::new();
 
-    // Find all relationships where this section is the target and cascade_delete is true
-    let cascade_relationships: Vec<&RelationshipConfig> = backoffice
+    // Find all relationships where this section is the target and that have a delete policy
+    let policies: Vec<(&RelationshipConfig, OnDelete)> = backoffice
         .relationships
         .iter()
-        .filter(|r| r.to_section == section_id && r.cascade_delete)
+        .filter(|r| r.to_section == section_id)
+        .filter_map(|r| Some((r, r.on_delete()?)))
         .collect();
 
-    for relationship in cascade_relationships {
+    for (relationship, policy) in policies {
         info!(
             relationship = %relationship.id,
             record_id = %record_id,
-            "Processing cascade delete"
+            policy = ?policy,
+            "Processing delete policy"
         );
 
-        match &relationship.relationship_type {
-            RelationshipType::OneToMany | RelationshipType::OneToOne => {
-                // Find all records in the source section that reference this record
-                let source_section = backoffice
-                    .sections
-                    .iter()
-                    .find(|s| s.id == relationship.from_section)
-                    .ok_or_else(|| {
-                        anyhow!("Source section not found: {}", relationship.from_section)
-                    })?;
-
-                let source_action = source_section.actions.first().ok_or_else(|| {
-                    anyhow!(
-                        "No actions found in source section: {}",
-                        relationship.from_section
-                    )
-                })?;
-
-                let data_source =
-                    data_sources
-                        .get(&source_action.data_source)
-                        .ok_or_else(|| {
-                            anyhow!("Data source not found: {}", source_action.data_source)
-                        })?;
-
-                // Query for dependent records
+        match (&relationship.relationship_type, policy) {
+            (
+                RelationshipType::ManyToMany {
+                    junction_table,
+                    from_junction_field,
+                    ..
+                },
+                OnDelete::Restrict,
+            ) => {
                 let query = format!(
-                    "SELECT * FROM {} WHERE {} = '{}'",
-                    relationship.from_section, relationship.from_field, record_id
+                    "SELECT * FROM {} WHERE {} = {{{{id}}}}",
+                    junction_table, from_junction_field,
                 );
-
-                debug!(query = %query, "Finding dependent records");
-
-                match data_source.execute_query(&query, None).await {
+                let params = HashMap::from([("id".to_string(), Value::from(record_id))]);
+                let links = source_data_source(relationship, backoffice, data_sources)?
+                    .execute_query(&query, Some(&params))
+                    .await?;
+                if !links.is_empty() {
+                    restricted.push(Dependents {
+                        relationship_id: relationship.id.clone(),
+                        section: junction_table.clone(),
+                        ids: links
+                            .iter()
+                            .filter_map(|link| link.get(from_junction_field).cloned())
+                            .collect(),
+                    });
+                }
+            }
+            (RelationshipType::ManyToMany { junction_table, .. }, _) => {
+                // For ManyToMany, delete entries in the junction table
+                operations.push(CascadeOperation {
+                    operation_type: CascadeOperationType::DeleteJunction,
+                    section: junction_table.clone(),
+                    record_id: record_id.to_string(),
+                    relationship_id: relationship.id.clone(),
+                });
+            }
+            (RelationshipType::ManyToOne, OnDelete::Cascade) if !is_tree(relationship) => {
+                // ManyToOne cascade delete would delete the referenced record
+                // This is usually not desired, so we skip it
+                debug!(
+                    relationship = %relationship.id,
+                    "Skipping cascade delete for ManyToOne relationship"
+                );
+            }
+            (_, OnDelete::Cascade) => {
+                match find_dependents(relationship, record_id, backoffice, data_sources).await {
                     Ok(dependent_records) => {
                         for record in dependent_records {
-                            if let Some(dependent_id) = record.get("id") {
-                                operations.push(CascadeOperation {
-                                    operation_type: CascadeOperationType::Delete,
-                                    section: relationship.from_section.clone(),
-                                    record_id: dependent_id.to_string(),
-                                    relationship_id: relationship.id.clone(),
-                                });
-
-                                // Recursively handle cascades for this record
-                                let nested_ops = Box::pin(handle_cascade_delete(
-                                    &dependent_id.to_string(),
-                                    &relationship.from_section,
-                                    backoffice,
-                                    data_sources,
-                                ))
-                                .await?;
-                                operations.extend(nested_ops);
+                            let Some(dependent_id) = record.get("id").and_then(lookup_key) else {
+                                continue;
+                            };
+                            if !visited
+                                .insert((relationship.from_section.clone(), dependent_id.clone()))
+                            {
+                                continue;
                             }
+
+                            // Recursively handle cascades for this record, whose
+                            // own dependents go first
+                            let nested_ops = Box::pin(delete_operations(
+                                &dependent_id,
+                                &relationship.from_section,
+                                backoffice,
+                                data_sources,
+                                visited,
+                            ))
+                            .await?;
+                            operations.extend(nested_ops);
+
+                            operations.push(CascadeOperation {
+                                operation_type: CascadeOperationType::Delete,
+                                section: relationship.from_section.clone(),
+                                record_id: dependent_id,
+                                relationship_id: relationship.id.clone(),
+                            });
                         }
                     }
                     Err(e) => {
@@ -197,19 +555,25 @@ pub async fn handle_cascade_delete(
                     }
                 }
             }
-            RelationshipType::ManyToOne => {
-                // ManyToOne cascade delete would delete the referenced record
-                // This is usually not desired, so we skip it
-                debug!(
-                    relationship = %relationship.id,
-                    "Skipping cascade delete for ManyToOne relationship"
-                );
+            (_, OnDelete::Restrict) => {
+                // Failing to look dependents up must not let the delete through
+                let dependent_records =
+                    find_dependents(relationship, record_id, backoffice, data_sources).await?;
+                if !dependent_records.is_empty() {
+                    restricted.push(Dependents {
+                        relationship_id: relationship.id.clone(),
+                        section: relationship.from_section.clone(),
+                        ids: dependent_records
+                            .iter()
+                            .filter_map(|record| record.get("id").cloned())
+                            .collect(),
+                    });
+                }
             }
-            RelationshipType::ManyToMany { junction_table, .. } => {
-                // For ManyToMany, delete entries in the junction table
+            (_, OnDelete::SetNull) => {
                 operations.push(CascadeOperation {
-                    operation_type: CascadeOperationType::DeleteJunction,
-                    section: junction_table.clone(),
+                    operation_type: CascadeOperationType::SetNull,
+                    section: relationship.from_section.clone(),
                     record_id: record_id.to_string(),
                     relationship_id: relationship.id.clone(),
                 });
@@ -217,23 +581,79 @@ pub async fn handle_cascade_delete(
         }
     }
 
+    if !restricted.is_empty() {
+        return Err(RestrictedDeleteError {
+            dependents: restricted,
+        }
+        .into());
+    }
+
     Ok(operations)
 }
 
-/// Execute cascade operations
-pub async fn execute_cascade_operations(
-    operations: &[CascadeOperation],
+/// The data source of a relationship's `from_section`
+fn source_data_source<'a>(
+    relationship: &RelationshipConfig,
+    backoffice: &BackofficeConfig,
+    data_sources: &'a HashMap<String, Box<dyn DataSource>>,
+) -> Result<&'a dyn DataSource> {
+    let source_section = backoffice
+        .sections
+        .iter()
+        .find(|s| s.id == relationship.from_section)
+        .ok_or_else(|| anyhow!("Source section not found: {}", relationship.from_section))?;
+
+    let source_action = source_section.actions.first().ok_or_else(|| {
+        anyhow!(
+            "No actions found in source section: {}",
+            relationship.from_section
+        )
+    })?;
+
+    data_sources
+        .get(&source_action.data_source)
+        .map(|ds| ds.as_ref())
+        .ok_or_else(|| anyhow!("Data source not found: {}", source_action.data_source))
+}
+
+/// Records of the `from_section` referencing a record through `from_field`
+async fn find_dependents(
+    relationship: &RelationshipConfig,
+    record_id: &str,
     backoffice: &BackofficeConfig,
     data_sources: &HashMap<String, Box<dyn DataSource>>,
-) -> Result<()> {
-    for operation in operations {
-        info!(
-            operation_type = ?operation.operation_type,
-            section = %operation.section,
-            record_id = %operation.record_id,
-            "Executing cascade operation"
-        );
+) -> Result<Vec<HashMap<String, Value>>> {
+    let data_source = source_data_source(relationship, backoffice, data_sources)?;
+
+    // Query for dependent records
+    let query = format!(
+        "SELECT * FROM {} WHERE {} = {{{{id}}}}",
+        relationship.from_section, relationship.from_field
+    );
 
+    debug!(query = %query, "Finding dependent records");
+    let params = HashMap::from([("id".to_string(), Value::from(record_id))]);
+    data_source.execute_query(&query, Some(&params)).await
+}
+
+/// A cascade operation resolved to a mutation of a data source
+#[derive(Debug, Clone)]
+pub struct CascadeMutation {
+    pub data_source: String,
+    pub mutation: Mutation,
+    /// Deletes a record, run with [`DataSource::execute_delete`] outside of
+    /// transactions
+    pub delete: bool,
+}
+
+/// Resolve cascade operations to the mutations that carry them out
+pub fn cascade_mutations(
+    operations: &[CascadeOperation],
+    backoffice: &BackofficeConfig,
+) -> Result<Vec<CascadeMutation>> {
+    let mut mutations = Vec::new();
+
+    for operation in operations {
         let section = backoffice
             .sections
             .iter()
@@ -245,23 +665,22 @@ pub async fn execute_cascade_operations(
             .first()
             .ok_or_else(|| anyhow!("No actions found in section: {}", operation.section))?;
 
-        let data_source = data_sources
-            .get(&action.data_source)
-            .ok_or_else(|| anyhow!("Data source not found: {}", action.data_source))?;
-
         match operation.operation_type {
             CascadeOperationType::Delete => {
-                let query = format!(
-                    "DELETE FROM {} WHERE id = '{}'",
-                    operation.section, operation.record_id
-                );
-
-                debug!(query = %query, "Executing cascade delete");
+                let query = format!("DELETE FROM {} WHERE id = {{{{id}}}}", operation.section);
 
                 let mut data = HashMap::new();
                 data.insert("id".to_string(), Value::String(operation.record_id.clone()));
 
-                data_source.execute_mutation(&query, &data).await?;
+                mutations.push(CascadeMutation {
+                    data_source: action.data_source.clone(),
+                    mutation: Mutation {
+                        query,
+                        data,
+                        ..Default::default()
+                    },
+                    delete: true,
+                });
             }
             CascadeOperationType::DeleteJunction => {
                 // Find the relationship to get junction table details
@@ -280,33 +699,92 @@ pub async fn execute_cascade_operations(
                 } = &relationship.relationship_type
                 {
                     let query = format!(
-                        "DELETE FROM {} WHERE {} = '{}'",
-                        junction_table, from_junction_field, operation.record_id
+                        "DELETE FROM {} WHERE {} = {{{{id}}}}",
+                        junction_table, from_junction_field
                     );
 
-                    debug!(query = %query, "Executing junction table delete");
-
                     let mut data = HashMap::new();
                     data.insert(
                         from_junction_field.clone(),
                         Value::String(operation.record_id.clone()),
                     );
+                    data.insert("id".to_string(), Value::String(operation.record_id.clone()));
 
-                    data_source.execute_mutation(&query, &data).await?;
+                    mutations.push(CascadeMutation {
+                        data_source: action.data_source.clone(),
+                        mutation: Mutation {
+                            query,
+                            data,
+                            ..Default::default()
+                        },
+                        delete: false,
+                    });
                 }
             }
             CascadeOperationType::SetNull => {
-                // Set foreign key to NULL instead of deleting
+                let relationship = backoffice
+                    .relationships
+                    .iter()
+                    .find(|r| r.id == operation.relationship_id)
+                    .ok_or_else(|| {
+                        anyhow!("Relationship not found: {}", operation.relationship_id)
+                    })?;
+
+                // Clear the foreign key instead of deleting
                 let query = format!(
-                    "UPDATE {} SET {} = NULL WHERE id = '{}'",
-                    operation.section, "foreign_key_field", operation.record_id
+                    "UPDATE {} SET {} = NULL WHERE {} = {{{{id}}}}",
+                    operation.section, relationship.from_field, relationship.from_field,
                 );
 
-                warn!(query = %query, "SetNull cascade not fully implemented");
+                let mut data = HashMap::new();
+                data.insert(relationship.from_field.clone(), Value::Null);
+                data.insert("id".to_string(), Value::String(operation.record_id.clone()));
+
+                mutations.push(CascadeMutation {
+                    data_source: action.data_source.clone(),
+                    mutation: Mutation {
+                        query,
+                        data,
+                        ..Default::default()
+                    },
+                    delete: false,
+                });
             }
         }
     }
 
+    Ok(mutations)
+}
+
+/// Execute cascade operations one by one
+pub async fn execute_cascade_operations(
+    operations: &[CascadeOperation],
+    backoffice: &BackofficeConfig,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+) -> Result<()> {
+    for cascade in cascade_mutations(operations, backoffice)? {
+        info!(
+            data_source = %cascade.data_source,
+            query = %cascade.mutation.query,
+            "Executing cascade operation"
+        );
+
+        let data_source = data_sources
+            .get(&cascade.data_source)
+            .ok_or_else(|| anyhow!("Data source not found: {}", cascade.data_source))?;
+
+        let mutation = &cascade.mutation;
+        if cascade.delete {
+            data_source
+                .execute_delete(&mutation.query, &mutation.data)
+                .await?;
+        } else {
+            data_source
+                .execute_mutation(&mutation.query, &mutation.data)
+                .await?;
+        }
+    }
+
     Ok(())
 }
 
@@ -357,9 +835,10 @@ pub async fn validate_many_to_many(
                             })?;
 
                     let query = format!(
-                        "SELECT {} FROM {} WHERE {} = '{}'",
-                        relationship.to_field, relationship.to_section, relationship.to_field, id
+                        "SELECT {} FROM {} WHERE {} = {{{{id}}}}",
+                        relationship.to_field, relationship.to_section, relationship.to_field
                     );
+                    let params = HashMap::from([("id".to_string(), Value::from(id))]);
 
                     debug!(
                         relationship = %relationship.id,
@@ -367,7 +846,7 @@ pub async fn validate_many_to_many(
                         "Validating ManyToMany reference"
                     );
 
-                    match data_source.execute_query(&query, None).await {
+                    match data_source.execute_query(&query, Some(&params)).await {
                         Ok(results) => {
                             if results.is_empty() {
                                 errors.push(RelationshipError {
@@ -396,6 +875,611 @@ pub async fn validate_many_to_many(
     Ok(errors)
 }
 
+/// A relationship embedded in query responses, named in `expand`
+#[derive(Debug, Clone)]
+pub struct Expansion<'a> {
+    /// Name used in `expand`, and key of the records under `_expanded`
+    pub key: String,
+    pub relationship: &'a RelationshipConfig,
+    pub target_section: &'a SectionConfig,
+    /// The target section's list action, whose data source and fields are used
+    pub target_action: &'a ActionConfig,
+}
+
+/// Resolve a comma-separated `expand` parameter
+///
+/// Each name is either the id of a relationship leaving the section or the
+/// id of a field with a `relationship_id`.
+pub fn resolve_expansions<'a>(
+    expand: &str,
+    section: &SectionConfig,
+    backoffice: &'a BackofficeConfig,
+) -> Result<Vec<Expansion<'a>>> {
+    let mut expansions = Vec::new();
+
+    for name in expand.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let relationship_id = section
+            .actions
+            .iter()
+            .flat_map(|a| a.action_type.fields())
+            .find(|f| f.id == name)
+            .and_then(|f| f.relationship_id.as_deref())
+            .unwrap_or(name);
+
+        let relationship = backoffice
+            .relationships
+            .iter()
+            .find(|r| r.id == relationship_id && r.from_section == section.id)
+            .ok_or_else(|| anyhow!("Unknown relationship to expand: {}", name))?;
+
+        let target_section = backoffice
+            .sections
+            .iter()
+            .find(|s| s.id == relationship.to_section)
+            .ok_or_else(|| anyhow!("Target section not found: {}", relationship.to_section))?;
+
+        let target_action = target_section
+            .actions
+            .iter()
+            .find(|a| matches!(a.action_type, ActionType::List { .. }))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Section '{}' has no list action to expand '{}' from",
+                    target_section.id,
+                    name
+                )
+            })?;
+
+        expansions.push(Expansion {
+            key: name.to_string(),
+            relationship,
+            target_section,
+            target_action,
+        });
+    }
+
+    Ok(expansions)
+}
+
+/// Embed related records into rows under `_expanded.<key>`
+///
+/// Every expansion costs one lookup, whatever the number of rows. To-one
+/// relationships embed a record (or null), to-many relationships a list.
+/// Related records go through the target section's row filters, field
+/// permissions and masking, and are narrowed to the relationship's
+/// `display_fields` when it declares them.
+pub async fn expand_rows(
+    rows: &mut [HashMap<String, Value>],
+    expansions: &[Expansion<'_>],
+    backoffice: &BackofficeConfig,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+    auth: &AuthContext,
+) -> Result<()> {
+    for expansion in expansions {
+        let relationship = expansion.relationship;
+
+        // Unique keys referenced by the rows, with a value to bind for each
+        let mut keys: Vec<(String, Value)> = Vec::new();
+        let mut seen = HashSet::new();
+        for row in rows.iter() {
+            for value in referenced_values(row.get(&relationship.from_field)) {
+                if let Some(key) = lookup_key(value) {
+                    if seen.insert(key.clone()) {
+                        keys.push((key, value.clone()));
+                    }
+                }
+            }
+        }
+
+        let related = if keys.is_empty() {
+            Vec::new()
+        } else {
+            fetch_related(expansion, &keys, backoffice, data_sources, auth).await?
+        };
+
+        let mut by_key: HashMap<String, Vec<&HashMap<String, Value>>> = HashMap::new();
+        for record in &related {
+            if let Some(key) = record.get(&relationship.to_field).and_then(lookup_key) {
+                by_key.entry(key).or_default().push(record);
+            }
+        }
+
+        for row in rows.iter_mut() {
+            let matches: Vec<Value> = referenced_values(row.get(&relationship.from_field))
+                .filter_map(lookup_key)
+                .flat_map(|key| by_key.get(&key).into_iter().flatten())
+                .map(|record| serde_json::to_value(record).unwrap_or(Value::Null))
+                .collect();
+            let embedded = match relationship.relationship_type {
+                RelationshipType::ManyToOne | RelationshipType::OneToOne => {
+                    matches.into_iter().next().unwrap_or(Value::Null)
+                }
+                RelationshipType::OneToMany | RelationshipType::ManyToMany { .. } => {
+                    Value::Array(matches)
+                }
+            };
+
+            let expanded = row
+                .entry("_expanded".to_string())
+                .or_insert_with(|| Value::Object(Default::default()));
+            if let Value::Object(expanded) = expanded {
+                expanded.insert(expansion.key.clone(), embedded);
+            }
+        }
+
+        debug!(
+            relationship = %relationship.id,
+            keys = keys.len(),
+            related = related.len(),
+            "Expanded relationship"
+        );
+    }
+
+    Ok(())
+}
+
+/// Values a row's relationship field refers to; ManyToMany fields hold arrays
+fn referenced_values(value: Option<&Value>) -> impl Iterator<Item = &Value> {
+    let values: Vec<&Value> = match value {
+        Some(Value::Array(values)) => values.iter().collect(),
+        Some(value) => vec![value],
+        None => Vec::new(),
+    };
+    values.into_iter()
+}
+
+/// Placeholders for a list of values, e.g. `{{key_0}}, {{key_1}}`, binding
+/// them in `params`
+pub(crate) fn placeholders<'a>(
+    prefix: &str,
+    values: impl IntoIterator<Item = &'a Value>,
+    params: &mut HashMap<String, Value>,
+) -> String {
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let name = format!("{}_{}", prefix, index);
+            params.insert(name.clone(), value.clone());
+            format!("{{{{{}}}}}", name)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Key comparing `1` and `"1"` as equal, since sources disagree on id types
+pub(crate) fn lookup_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Load the target records of an expansion in one query
+///
+/// Database sources are queried with `to_field IN (...)` against the target
+/// section's table; other sources run the target list action and keep the
+/// matching records.
+async fn fetch_related(
+    expansion: &Expansion<'_>,
+    keys: &[(String, Value)],
+    backoffice: &BackofficeConfig,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+    auth: &AuthContext,
+) -> Result<Vec<HashMap<String, Value>>> {
+    let (relationship, section, action) = (
+        expansion.relationship,
+        expansion.target_section,
+        expansion.target_action,
+    );
+    let config = backoffice
+        .data_sources
+        .get(&action.data_source)
+        .ok_or_else(|| anyhow!("Data source not found: {}", action.data_source))?;
+    let data_source = data_sources
+        .get(&action.data_source)
+        .ok_or_else(|| anyhow!("Data source not found: {}", action.data_source))?;
+    let conditions = row_filters::resolve(&section.row_filters, auth)?;
+
+    let mut params = HashMap::new();
+    let query = match config {
+        DataSourceConfig::Database { db_type, .. } => {
+            let table = section.table.as_deref().unwrap_or(&section.id);
+            let column = quote_identifier(&relationship.to_field, db_type);
+            let placeholders =
+                placeholders("expand", keys.iter().map(|(_, value)| value), &mut params);
+            format!(
+                "SELECT * FROM {} WHERE {} IN ({})",
+                quote_identifier(table, db_type),
+                column,
+                placeholders
+            )
+        }
+        _ => crud::action_target(section, action, config, CrudOperation::List, &params)?,
+    };
+    let query = row_filters::scope_query(&query, &conditions, config, &mut params);
+
+    debug!(relationship = %relationship.id, query = %query, "Loading related records");
+    let mut related = data_source.execute_query(&query, Some(&params)).await?;
+
+    let wanted: HashSet<&str> = keys.iter().map(|(key, _)| key.as_str()).collect();
+    related.retain(|record| {
+        record
+            .get(&relationship.to_field)
+            .and_then(lookup_key)
+            .is_some_and(|key| wanted.contains(key.as_str()))
+    });
+    row_filters::retain_rows(&mut related, &conditions);
+
+    let fields = action.action_type.fields();
+    permissions::strip_unreadable(&mut related, fields, auth);
+    masking::mask_rows(&mut related, fields, auth);
+
+    if let Some(display_fields) = &relationship.display_fields {
+        for record in related.iter_mut() {
+            record.retain(|name, _| {
+                name == "id" || name == &relationship.to_field || display_fields.contains(name)
+            });
+        }
+    }
+
+    Ok(related)
+}
+
+/// Records related to one record of the expansion's section
+///
+/// The related keys come from the record's `from_field` (the record id
+/// itself when that field is `id`) or, for ManyToMany, from the junction
+/// table. Records are loaded like an expansion.
+pub async fn related_records(
+    record_id: &Value,
+    expansion: &Expansion<'_>,
+    backoffice: &BackofficeConfig,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+    auth: &AuthContext,
+) -> Result<Vec<HashMap<String, Value>>> {
+    let mut related = related_records_batch(
+        std::slice::from_ref(record_id),
+        expansion,
+        backoffice,
+        data_sources,
+        auth,
+    )
+    .await?;
+    Ok(lookup_key(record_id)
+        .and_then(|key| related.remove(&key))
+        .unwrap_or_default())
+}
+
+/// Records related to several records of the expansion's section
+///
+/// Works like [`related_records`] with one lookup per step for all the
+/// records. The result maps the key of each record id to its related
+/// records; records without any are left out.
+pub async fn related_records_batch(
+    record_ids: &[Value],
+    expansion: &Expansion<'_>,
+    backoffice: &BackofficeConfig,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+    auth: &AuthContext,
+) -> Result<HashMap<String, Vec<HashMap<String, Value>>>> {
+    let relationship = expansion.relationship;
+    let source = source_data_source(relationship, backoffice, data_sources)?;
+    let mut params = HashMap::new();
+    let ids = placeholders(
+        "id",
+        record_ids.iter().filter(|id| lookup_key(id).is_some()),
+        &mut params,
+    );
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    // Related values of every record, keyed by the record id
+    let mut referenced: Vec<(String, Value)> = Vec::new();
+    match &relationship.relationship_type {
+        RelationshipType::ManyToMany {
+            junction_table,
+            from_junction_field,
+            to_junction_field,
+        } => {
+            let query = format!(
+                "SELECT {}, {} FROM {} WHERE {} IN ({})",
+                from_junction_field, to_junction_field, junction_table, from_junction_field, ids
+            );
+            for mut link in source.execute_query(&query, Some(&params)).await? {
+                let record = link.get(from_junction_field).and_then(lookup_key);
+                if let (Some(record), Some(value)) = (record, link.remove(to_junction_field)) {
+                    referenced.push((record, value));
+                }
+            }
+        }
+        _ if relationship.from_field == "id" => {
+            referenced.extend(
+                record_ids
+                    .iter()
+                    .filter_map(|id| Some((lookup_key(id)?, id.clone()))),
+            );
+        }
+        _ => {
+            let query = format!(
+                "SELECT id, {} FROM {} WHERE id IN ({})",
+                relationship.from_field,
+                section_table(backoffice, &relationship.from_section),
+                ids
+            );
+            for row in source.execute_query(&query, Some(&params)).await? {
+                if let Some(record) = row.get("id").and_then(lookup_key) {
+                    for value in referenced_values(row.get(&relationship.from_field)) {
+                        referenced.push((record.clone(), value.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut keys: Vec<(String, Value)> = Vec::new();
+    let mut wanted: HashMap<String, HashSet<String>> = HashMap::new();
+    for (record, value) in referenced {
+        if let Some(key) = lookup_key(&value) {
+            if !keys.iter().any(|(k, _)| *k == key) {
+                keys.push((key.clone(), value));
+            }
+            wanted.entry(record).or_default().insert(key);
+        }
+    }
+    if keys.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let related = fetch_related(expansion, &keys, backoffice, data_sources, auth).await?;
+    Ok(wanted
+        .into_iter()
+        .map(|(record, wanted)| {
+            let records = related
+                .iter()
+                .filter(|row| {
+                    row.get(&relationship.to_field)
+                        .and_then(lookup_key)
+                        .is_some_and(|key| wanted.contains(&key))
+                })
+                .cloned()
+                .collect();
+            (record, records)
+        })
+        .collect())
+}
+
+/// Child records or related ids sent with a parent create
+#[derive(Debug, Clone)]
+pub struct NestedRecords<'a> {
+    pub relationship: &'a RelationshipConfig,
+    /// Child records for OneToMany, ids of related records for ManyToMany
+    pub records: Vec<Value>,
+}
+
+/// Take the nested arrays out of a create payload
+///
+/// Arrays under the id of a OneToMany or ManyToMany relationship leaving the
+/// section are nested records rather than columns of the parent.
+pub fn take_nested<'a>(
+    data: &mut HashMap<String, Value>,
+    section_id: &str,
+    backoffice: &'a BackofficeConfig,
+) -> Vec<NestedRecords<'a>> {
+    backoffice
+        .relationships
+        .iter()
+        .filter(|r| {
+            r.from_section == section_id
+                && matches!(
+                    r.relationship_type,
+                    RelationshipType::OneToMany | RelationshipType::ManyToMany { .. }
+                )
+        })
+        .filter_map(|relationship| match data.remove(&relationship.id) {
+            Some(Value::Array(records)) => Some(NestedRecords {
+                relationship,
+                records,
+            }),
+            Some(other) => {
+                data.insert(relationship.id.clone(), other);
+                None
+            }
+            None => None,
+        })
+        .collect()
+}
+
+/// Validate nested records against the form of their section
+///
+/// Child records get the section's row filters applied and are checked like
+/// a submitted form, except for the foreign key filled in on insert. Errors
+/// are reported with `relationship[index].field` paths.
+pub async fn validate_nested(
+    nested: &mut [NestedRecords<'_>],
+    backoffice: &BackofficeConfig,
+    functions: &ValidationFunctions,
+    auth: &AuthContext,
+    write_policy: &FieldWritePolicy,
+) -> Result<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    for nested in nested.iter_mut() {
+        let relationship = nested.relationship;
+        let path = |index: usize, field: &str| format!("{}[{}].{}", relationship.id, index, field);
+        let error = |index: usize, field: &str, code: &str| {
+            ValidationError::new(path(index, field), code, HashMap::new())
+        };
+
+        if matches!(
+            relationship.relationship_type,
+            RelationshipType::ManyToMany { .. }
+        ) {
+            for (index, id) in nested.records.iter().enumerate() {
+                if lookup_key(id).is_none() {
+                    errors.push(error(index, &relationship.to_field, "related_id"));
+                }
+            }
+            continue;
+        }
+
+        let (section, action) = child_form(relationship, backoffice)?;
+        let conditions = row_filters::resolve(&section.row_filters, auth)?;
+        let fields: Vec<FieldConfig> = action
+            .action_type
+            .fields()
+            .iter()
+            .filter(|f| f.id != relationship.to_field)
+            .cloned()
+            .collect();
+
+        for (index, record) in nested.records.iter_mut().enumerate() {
+            let Value::Object(object) = record else {
+                errors.push(error(index, "", "object"));
+                continue;
+            };
+            let mut data: HashMap<String, Value> = object.clone().into_iter().collect();
+
+            if let Err(forbidden) =
+                permissions::enforce_writable(&mut data, &fields, auth, write_policy)
+            {
+                errors.extend(
+                    forbidden
+                        .iter()
+                        .map(|field| error(index, field, "write_forbidden")),
+                );
+                continue;
+            }
+            row_filters::apply_to_payload(&mut data, &conditions);
+            errors.extend(
+                validation::validate_data_with_functions(&data, &fields, functions)
+                    .await?
+                    .into_iter()
+                    .map(|e| {
+                        let field = path(index, &e.field);
+                        e.at(field)
+                    }),
+            );
+
+            *record = Value::Object(data.into_iter().collect());
+        }
+    }
+
+    Ok(errors)
+}
+
+/// The section and form action child records of a relationship are created with
+fn child_form<'a>(
+    relationship: &RelationshipConfig,
+    backoffice: &'a BackofficeConfig,
+) -> Result<(&'a SectionConfig, &'a ActionConfig)> {
+    let section = backoffice
+        .sections
+        .iter()
+        .find(|s| s.id == relationship.to_section)
+        .ok_or_else(|| anyhow!("Target section not found: {}", relationship.to_section))?;
+    let action = seeds::seed_action(section).ok_or_else(|| {
+        anyhow!(
+            "Section '{}' has no form action to create nested records with",
+            section.id
+        )
+    })?;
+    Ok((section, action))
+}
+
+/// The mutations creating a parent with its nested records, in order
+///
+/// The parent comes first. Its `from_field` value is copied into every child
+/// and junction row; when that field is the generated `id`, it is read back
+/// from the parent insert inside the transaction.
+pub fn nested_mutations(
+    parent: Mutation,
+    nested: &[NestedRecords<'_>],
+    backoffice: &BackofficeConfig,
+    data_source: &str,
+) -> Result<Vec<Mutation>> {
+    let config = backoffice
+        .data_sources
+        .get(data_source)
+        .ok_or_else(|| anyhow!("Data source not found: {}", data_source))?;
+    let parent_key = |key: &str, relationship: &RelationshipConfig| match parent
+        .data
+        .get(&relationship.from_field)
+    {
+        Some(value) if !value.is_null() => (Some(value.clone()), Vec::new()),
+        _ => (None, vec![(key.to_string(), 0)]),
+    };
+
+    let mut mutations = Vec::new();
+    for nested in nested {
+        let relationship = nested.relationship;
+        match &relationship.relationship_type {
+            RelationshipType::ManyToMany {
+                junction_table,
+                from_junction_field,
+                to_junction_field,
+            } => {
+                let DataSourceConfig::Database { db_type, .. } = config else {
+                    return Err(anyhow!(
+                        "Nested ManyToMany records need a database data source"
+                    ));
+                };
+                let query = format!(
+                    "INSERT INTO {} ({}, {}) VALUES ({{{{parent_id}}}}, {{{{related_id}}}})",
+                    quote_identifier(junction_table, db_type),
+                    quote_identifier(from_junction_field, db_type),
+                    quote_identifier(to_junction_field, db_type)
+                );
+                for id in &nested.records {
+                    let (value, generated_ids) = parent_key("parent_id", relationship);
+                    let mut data = HashMap::from([("related_id".to_string(), id.clone())]);
+                    if let Some(value) = value {
+                        data.insert("parent_id".to_string(), value);
+                    }
+                    mutations.push(Mutation {
+                        query: query.clone(),
+                        data,
+                        generated_ids,
+                        ..Default::default()
+                    });
+                }
+            }
+            _ => {
+                let (section, action) = child_form(relationship, backoffice)?;
+                if action.data_source != data_source {
+                    return Err(anyhow!(
+                        "Nested records of '{}' must use the parent's data source '{}'",
+                        relationship.id,
+                        data_source
+                    ));
+                }
+                for record in &nested.records {
+                    let mut data: HashMap<String, Value> = match record {
+                        Value::Object(object) => object.clone().into_iter().collect(),
+                        _ => return Err(anyhow!("Nested records must be objects")),
+                    };
+                    let (value, generated_ids) = parent_key(&relationship.to_field, relationship);
+                    data.insert(relationship.to_field.clone(), value.unwrap_or(Value::Null));
+                    let query =
+                        crud::action_target(section, action, config, CrudOperation::Create, &data)?;
+                    mutations.push(Mutation {
+                        query,
+                        data,
+                        generated_ids,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+    }
+
+    mutations.insert(0, parent);
+    Ok(mutations)
+}
+
 /// Relationship validation error
 #[derive(Debug, Clone)]
 pub struct RelationshipError {
@@ -417,6 +1501,20 @@ pub struct CascadeOperation {
 pub enum CascadeOperationType {
     Delete,
     DeleteJunction,
-    #[allow(dead_code)]
     SetNull,
 }
+
+/// Records still referencing a record through a `restrict` relationship
+#[derive(Debug, Clone, Serialize)]
+pub struct Dependents {
+    pub relationship_id: String,
+    pub section: String,
+    pub ids: Vec<Value>,
+}
+
+/// Returned when `restrict` relationships block a delete
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Record is still referenced by {} dependent record(s)", .dependents.iter().map(|d| d.ids.len()).sum::<usize>())]
+pub struct RestrictedDeleteError {
+    pub dependents: Vec<Dependents>,
+}
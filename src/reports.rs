@@ -0,0 +1,340 @@
+//! Report actions
+//!
+//! `type: report` actions run a curated, read-only SQL query whose
+//! parameters are declared as fields: the UI renders them as a form, and
+//! request params are coerced to the parameters' types and validated like
+//! form data before reaching the query. Undeclared params never reach it.
+//!
+//! Report queries must be a single `SELECT` (or `WITH ... SELECT`)
+//! statement, and their rows can be downloaded as CSV, JSON or JSONL.
+
+use crate::config::{DataSourceConfig, FieldConfig, FieldType, FileFormat};
+use crate::data_source::{csv_line, value_as_text};
+use crate::i18n;
+use crate::validation::ValidationError;
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+/// Query param selecting a download format
+pub const FORMAT_PARAM: &str = "format";
+
+/// Words of statements that write data or schema, lock rows or run code
+const WRITE_KEYWORDS: &[&str] = &[
+    "insert", "update", "delete", "merge", "upsert", "drop", "alter", "create", "truncate",
+    "grant", "revoke", "copy", "call", "exec", "execute", "attach", "detach", "pragma", "vacuum",
+    "into", "lock",
+];
+
+/// Values of a report's parameters, parsed from request params
+///
+/// Empty params are treated as missing and missing ones take the
+/// parameter's default value. Params that don't parse as the parameter's
+/// type are reported as validation errors.
+pub fn parameter_values(
+    params: &HashMap<String, String>,
+    parameters: &[FieldConfig],
+) -> Result<HashMap<String, Value>, Vec<ValidationError>> {
+    let mut values = HashMap::new();
+    let mut errors = Vec::new();
+
+    for parameter in parameters {
+        let raw = params
+            .get(&parameter.id)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty());
+        let value = match raw {
+            Some(raw) => match parse_text(raw, &parameter.field_type) {
+                Some(value) => value,
+                None => {
+                    errors.push(ValidationError::new(
+                        &parameter.id,
+                        "invalid_value",
+                        i18n::params([
+                            ("field", parameter.name.clone().into()),
+                            ("value", raw.into()),
+                        ]),
+                    ));
+                    continue;
+                }
+            },
+            None => match &parameter.default_value {
+                Some(default) => default.clone(),
+                None => continue,
+            },
+        };
+        values.insert(parameter.id.clone(), value);
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Text typed by a field's type: numbers for numeric fields, booleans for
+/// boolean ones and strings otherwise, or `None` when it doesn't parse
+pub(crate) fn parse_text(raw: &str, field_type: &FieldType) -> Option<Value> {
+    match field_type {
+        FieldType::Number { .. }
+        | FieldType::Currency { .. }
+        | FieldType::Range { .. }
+        | FieldType::Rating { .. }
+        | FieldType::Percentage { .. }
+        | FieldType::Slider { .. } => raw.parse::<i64>().map(Value::from).ok().or_else(|| {
+            raw.parse::<f64>()
+                .ok()
+                .and_then(|n| n.is_finite().then(|| n.into()))
+        }),
+        FieldType::Boolean { .. } => match raw.to_ascii_lowercase().as_str() {
+            "true" | "1" | "on" | "yes" => Some(Value::Bool(true)),
+            "false" | "0" | "off" | "no" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => Some(Value::String(raw.to_string())),
+    }
+}
+
+/// Fail unless the query is a single read-only SQL statement
+pub fn ensure_read_only(query: &str, config: &DataSourceConfig) -> Result<()> {
+    if !matches!(config, DataSourceConfig::Database { .. }) {
+        bail!("Report actions require a database data source");
+    }
+
+    let code = strip_literals(query);
+    if code.trim().trim_end_matches(';').contains(';') {
+        bail!("Report queries must be a single statement");
+    }
+
+    let mut words = code
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| !w.is_empty())
+        .map(str::to_ascii_lowercase);
+    if !matches!(words.next().as_deref(), Some("select" | "with")) {
+        bail!("Report queries must be a SELECT statement");
+    }
+    if let Some(word) = words.find(|w| WRITE_KEYWORDS.contains(&w.as_str())) {
+        bail!(
+            "Report queries must be read-only, found '{}'",
+            word.to_ascii_uppercase()
+        );
+    }
+    Ok(())
+}
+
+/// The query without string literals, quoted identifiers and comments,
+/// which may contain anything without changing what the statement does
+fn strip_literals(query: &str) -> String {
+    let mut code = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                // Doubled quotes escape themselves inside the literal
+                while let Some(next) = chars.next() {
+                    if next == c {
+                        if chars.peek() == Some(&c) {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                code.push(' ');
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+                code.push(' ');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                code.push(' ');
+            }
+            _ => code.push(c),
+        }
+    }
+    code
+}
+
+/// The download format asked for by the request, if any
+pub fn export_format(
+    params: &HashMap<String, String>,
+    allowed: &[FileFormat],
+) -> Result<Option<FileFormat>> {
+    let Some(name) = params.get(FORMAT_PARAM) else {
+        return Ok(None);
+    };
+    let format = match name.as_str() {
+        "csv" => FileFormat::Csv,
+        "json" => FileFormat::Json,
+        "jsonl" => FileFormat::Jsonl,
+        other => bail!("Unknown report format '{}'", other),
+    };
+    if !allowed.contains(&format) {
+        bail!("Report can't be exported as '{}'", name);
+    }
+    Ok(Some(format))
+}
+
+/// Content type, file extension and body of rows downloaded in a format
+///
+/// CSV columns follow the report's fields, or every column of the rows in
+/// alphabetical order when it declares none.
+pub fn export(
+    rows: &[HashMap<String, Value>],
+    fields: &[FieldConfig],
+    format: &FileFormat,
+) -> Result<(&'static str, &'static str, String)> {
+    let to_object = |row: &HashMap<String, Value>| -> Value {
+        Value::Object(row.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+    };
+
+    Ok(match format {
+        FileFormat::Json => (
+            "application/json",
+            "json",
+            serde_json::to_string_pretty(&Value::Array(rows.iter().map(to_object).collect()))?,
+        ),
+        FileFormat::Jsonl => (
+            "application/x-ndjson",
+            "jsonl",
+            rows.iter()
+                .map(|row| to_object(row).to_string() + "\n")
+                .collect(),
+        ),
+        FileFormat::Csv => {
+            let columns: Vec<String> = if fields.is_empty() {
+                rows.iter()
+                    .flat_map(|row| row.keys().cloned())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect()
+            } else {
+                fields.iter().map(|f| f.id.clone()).collect()
+            };
+            let headers: Vec<String> = if fields.is_empty() {
+                columns.clone()
+            } else {
+                fields.iter().map(|f| f.name.clone()).collect()
+            };
+
+            let mut out = csv_line(&headers);
+            for row in rows {
+                let cells: Vec<String> = columns
+                    .iter()
+                    .map(|c| row.get(c).map(value_as_text).unwrap_or_default())
+                    .collect();
+                out.push_str(&csv_line(&cells));
+            }
+            ("text/csv; charset=utf-8", "csv", out)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseType;
+    use serde_json::json;
+
+    fn parameter(yaml: &str) -> FieldConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_parameter_values() {
+        let parameters = vec![
+            parameter("{id: min_total, name: Minimum total, field_type: number}"),
+            parameter("{id: active, name: Active, field_type: boolean, default_value: true}"),
+            parameter("{id: country, name: Country, field_type: text}"),
+        ];
+        let params: HashMap<String, String> = [
+            ("min_total", "12.5"),
+            ("country", "ES"),
+            ("other", "ignored"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let values = parameter_values(&params, &parameters).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values["min_total"], json!(12.5));
+        assert_eq!(values["active"], json!(true));
+        assert_eq!(values["country"], json!("ES"));
+
+        let params = HashMap::from([("min_total".to_string(), "lots".to_string())]);
+        let errors = parameter_values(&params, &parameters).unwrap_err();
+        assert_eq!(errors[0].field, "min_total");
+    }
+
+    #[test]
+    fn test_ensure_read_only() {
+        let config = DataSourceConfig::Database {
+            connection_string: "sqlite::memory:".to_string(),
+            db_type: DatabaseType::Sqlite,
+            read_replicas: vec![],
+            migrations: None,
+            init: Default::default(),
+        };
+
+        for query in [
+            "SELECT * FROM orders WHERE total > {{min_total}}",
+            "WITH t AS (SELECT 1) SELECT * FROM t;",
+            "SELECT 'drop table; delete' AS note -- update\nFROM orders",
+            "SELECT \"created_at\", replace(name, 'a', 'b') FROM orders",
+        ] {
+            assert!(ensure_read_only(query, &config).is_ok(), "{}", query);
+        }
+        for query in [
+            "DELETE FROM orders",
+            "SELECT 1; DROP TABLE orders",
+            "WITH gone AS (DELETE FROM orders RETURNING *) SELECT * FROM gone",
+            "SELECT * INTO backup FROM orders",
+            "SELECT * FROM orders FOR UPDATE",
+        ] {
+            assert!(ensure_read_only(query, &config).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let rows = vec![HashMap::from([
+            ("id".to_string(), json!(1)),
+            ("name".to_string(), json!("Smith, J")),
+        ])];
+        let fields = vec![
+            parameter("{id: name, name: Name, field_type: text}"),
+            parameter("{id: id, name: ID, field_type: number}"),
+        ];
+
+        let (content_type, extension, body) = export(&rows, &fields, &FileFormat::Csv).unwrap();
+        assert_eq!(
+            (content_type, extension),
+            ("text/csv; charset=utf-8", "csv")
+        );
+        assert_eq!(body, "Name,ID\n\"Smith, J\",1\n");
+
+        let (_, _, body) = export(&rows, &[], &FileFormat::Jsonl).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(body.trim()).unwrap(),
+            json!({"id": 1, "name": "Smith, J"})
+        );
+
+        let params = HashMap::from([(FORMAT_PARAM.to_string(), "jsonl".to_string())]);
+        assert!(export_format(&params, &[FileFormat::Csv]).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+//! Request ids
+//!
+//! Every request carries an `X-Request-Id`, the client's or a generated
+//! UUID, which the response echoes. While the request is handled the id is
+//! the field of a `request` tracing span, so every log line of the request
+//! has it, and it is available through [`current`]: error responses and
+//! audit entries record it, and API, GraphQL and JSON-RPC data sources
+//! forward it, so one id follows a request across services.
+
+use axum::{extract::Request, middleware::Next, response::Response};
+use std::future::Future;
+use tracing::Instrument;
+
+pub const HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id of the request being handled, if any
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Run `future` as the handling of the request with `id`
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    let span = tracing::info_span!("request", request_id = %id);
+    REQUEST_ID.scope(id, future.instrument(span)).await
+}
+
+/// Add the current request's id to an outgoing HTTP request
+pub fn forward(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match current() {
+        Some(id) => request.header(HEADER, id),
+        None => request,
+    }
+}
+
+/// Handle a request within the scope of its `X-Request-Id`
+pub async fn propagate(request: Request, next: Next) -> Response {
+    let Some(id) = request
+        .headers()
+        .get(HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    else {
+        return next.run(request).await;
+    };
+    scope(id, next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope() {
+        assert_eq!(current(), None);
+        let id = scope("req-1".to_string(), async { current() }).await;
+        assert_eq!(id.as_deref(), Some("req-1"));
+
+        let request = scope("req-2".to_string(), async {
+            forward(reqwest::Client::new().get("http://localhost/"))
+                .build()
+                .unwrap()
+        })
+        .await;
+        assert_eq!(request.headers()[HEADER], "req-2");
+    }
+}
@@ -0,0 +1,367 @@
+//! Row-level security
+//!
+//! Sections can declare `row_filters` that bind a record field to a claim of
+//! the caller's token, e.g. `tenant_id = claims.tenant_id`. Reads only return
+//! matching rows, writes are pinned to the caller's values, and updates and
+//! deletes only touch records the caller can see.
+
+use crate::auth::AuthContext;
+use crate::config::{ActionType, DataSourceConfig, DatabaseType, RowFilterConfig, SectionConfig};
+use crate::crud::{self, CrudOperation};
+use crate::data_source::DataSource;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// A row filter resolved against the caller's claims
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowCondition {
+    pub field: String,
+    pub value: Value,
+}
+
+/// Resolve a section's row filters for the caller
+///
+/// Returns no conditions when security is disabled. Fails when the caller's
+/// token lacks a claim a filter depends on.
+pub fn resolve(filters: &[RowFilterConfig], auth: &AuthContext) -> Result<Vec<RowCondition>> {
+    if !auth.is_enforced() {
+        return Ok(Vec::new());
+    }
+
+    filters
+        .iter()
+        .map(|filter| {
+            let value = claim(&auth.claims, &filter.claim)
+                .filter(|v| !v.is_null())
+                .ok_or_else(|| anyhow!("Token is missing the '{}' claim", filter.claim))?;
+            Ok(RowCondition {
+                field: filter.field.clone(),
+                value: value.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Look up a dotted claim path, e.g. `org.id`
+fn claim<'a>(claims: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(claims, |value, key| value.get(key))
+}
+
+/// Push the conditions down into a query when the data source understands
+/// SQL, binding the caller's values through `params` (see `sql_params`)
+///
+/// Other data sources get the query unchanged and rely on `retain_rows`.
+pub fn scope_query(
+    query: &str,
+    conditions: &[RowCondition],
+    config: &DataSourceConfig,
+    params: &mut HashMap<String, Value>,
+) -> String {
+    let DataSourceConfig::Database { db_type, .. } = config else {
+        return query.to_string();
+    };
+    if conditions.is_empty() {
+        return query.to_string();
+    }
+
+    let query = query.trim().trim_end_matches(';');
+    format!(
+        "SELECT * FROM ({}) AS scoped WHERE {}",
+        query,
+        sql_params(conditions, db_type, params)
+    )
+}
+
+/// Drop rows that do not match the conditions
+pub fn retain_rows(rows: &mut Vec<HashMap<String, Value>>, conditions: &[RowCondition]) {
+    if conditions.is_empty() {
+        return;
+    }
+
+    let before = rows.len();
+    rows.retain(|row| {
+        conditions
+            .iter()
+            .all(|c| row.get(&c.field).is_some_and(|v| same_value(v, &c.value)))
+    });
+    debug!(
+        removed = before - rows.len(),
+        "Applied row-level security filters"
+    );
+}
+
+/// Pin the filtered fields of a mutation payload to the caller's values
+pub fn apply_to_payload(data: &mut HashMap<String, Value>, conditions: &[RowCondition]) {
+    for condition in conditions {
+        data.insert(condition.field.clone(), condition.value.clone());
+    }
+}
+
+/// Render conditions as a SQL condition on quoted columns, adding the
+/// caller's values to `params` as `row_filter_<n>`
+pub fn sql_params(
+    conditions: &[RowCondition],
+    db_type: &DatabaseType,
+    params: &mut HashMap<String, Value>,
+) -> String {
+    conditions
+        .iter()
+        .enumerate()
+        .map(|(index, c)| {
+            let name = format!("row_filter_{}", index);
+            params.insert(name.clone(), c.value.clone());
+            format!(
+                "{} = {{{{{}}}}}",
+                crud::quote_identifier(&c.field, db_type),
+                name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// Whether the caller can see the record with the given id
+///
+/// Databases are asked for the record in the section's table, with the id
+/// and the conditions bound as params. Other sources read it through the
+/// section's view action on `data_source_name`, and it is checked with
+/// `retain_rows`.
+pub async fn record_visible(
+    data_source: &dyn DataSource,
+    section: &SectionConfig,
+    data_source_name: &str,
+    config: &DataSourceConfig,
+    record_id: &Value,
+    conditions: &[RowCondition],
+) -> Result<bool> {
+    if conditions.is_empty() {
+        return Ok(true);
+    }
+
+    let mut params = HashMap::from([("id".to_string(), record_id.clone())]);
+    if let (DataSourceConfig::Database { db_type, .. }, Some(query)) =
+        (config, crud::record_query(section, config))
+    {
+        let query = format!(
+            "{} AND {}",
+            query,
+            sql_params(conditions, db_type, &mut params)
+        );
+        debug!(query = %query, "Checking record visibility");
+        return Ok(!data_source
+            .execute_query(&query, Some(&params))
+            .await?
+            .is_empty());
+    }
+
+    let view = section
+        .actions
+        .iter()
+        .find(|a| {
+            matches!(a.action_type, ActionType::View { .. }) && a.data_source == data_source_name
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "Section '{}' needs a view action on '{}' to check its row filters",
+                section.id,
+                data_source_name
+            )
+        })?;
+    let query = crud::action_target(section, view, config, CrudOperation::View, &params)?;
+    debug!(query = %query, "Checking record visibility");
+    let mut rows = data_source.execute_query(&query, Some(&params)).await?;
+    // Sources may answer more than the record
+    rows.retain(|row| row.get("id").is_none_or(|id| same_value(id, record_id)));
+    retain_rows(&mut rows, conditions);
+    Ok(!rows.is_empty())
+}
+
+/// Compare values loosely so that `3` matches `"3"`
+fn same_value(a: &Value, b: &Value) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a, b) {
+        (Value::String(s), Value::Number(n)) | (Value::Number(n), Value::String(s)) => {
+            s.parse::<f64>().ok() == n.as_f64()
+        }
+        (Value::String(s), Value::Bool(b)) | (Value::Bool(b), Value::String(s)) => {
+            s.parse::<bool>().ok() == Some(*b)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filters() -> Vec<RowFilterConfig> {
+        vec![RowFilterConfig {
+            field: "tenant_id".to_string(),
+            claim: "org.tenant".to_string(),
+        }]
+    }
+
+    #[test]
+    fn test_resolve_and_retain() {
+        let auth = AuthContext::from_claims(json!({"org": {"tenant": 7}}));
+        let conditions = resolve(&filters(), &auth).unwrap();
+        assert_eq!(conditions[0].value, json!(7));
+
+        let mut rows = vec![
+            HashMap::from([("tenant_id".to_string(), json!("7"))]),
+            HashMap::from([("tenant_id".to_string(), json!(8))]),
+            HashMap::new(),
+        ];
+        retain_rows(&mut rows, &conditions);
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[test]
+    fn test_missing_claim_is_rejected() {
+        let auth = AuthContext::from_claims(json!({"sub": "u1"}));
+        assert!(resolve(&filters(), &auth).is_err());
+
+        // Without security there is nothing to filter on
+        assert!(resolve(&filters(), &AuthContext::anonymous(false))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_scope_query_only_wraps_sql() {
+        let conditions = vec![RowCondition {
+            field: "tenant_id".to_string(),
+            value: json!("a'b"),
+        }];
+        let db = DataSourceConfig::Database {
+            connection_string: "sqlite::memory:".to_string(),
+            db_type: crate::config::DatabaseType::Sqlite,
+            read_replicas: vec![],
+            migrations: None,
+            init: Default::default(),
+        };
+        let mut params = HashMap::new();
+        assert_eq!(
+            scope_query("SELECT * FROM users;", &conditions, &db, &mut params),
+            r#"SELECT * FROM (SELECT * FROM users) AS scoped WHERE "tenant_id" = {{row_filter_0}}"#
+        );
+        // The caller's value is bound, never part of the SQL
+        assert_eq!(params["row_filter_0"], json!("a'b"));
+
+        let api = DataSourceConfig::Api {
+            base_url: "https://api.example.com".to_string(),
+            headers: None,
+            auth: None,
+            pagination: None,
+            response: None,
+            init: Default::default(),
+        };
+        assert_eq!(
+            scope_query("/users", &conditions, &api, &mut params),
+            "/users"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_visible() {
+        let dir = std::env::temp_dir().join(format!("pmp-row-filters-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let app = axum::Router::new().route(
+            "/accounts/:id",
+            axum::routing::get(
+                |axum::extract::Path(id): axum::extract::Path<String>| async move {
+                    let tenant = if id == "a1" { 7 } else { 8 };
+                    axum::Json(json!([{"id": id, "tenant_id": tenant}]))
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let backoffice: crate::config::BackofficeConfig = serde_yaml::from_str(&format!(
+            r#"
+id: bank
+name: Bank
+data_sources:
+  db:
+    type: database
+    connection_string: "sqlite://{}?mode=rwc"
+    db_type: sqlite
+  api:
+    type: api
+    base_url: "{}"
+sections:
+  - id: accounts
+    name: Accounts
+    table: bank_accounts
+    actions:
+      - {{ id: view, name: View, type: view, data_source: api, endpoint: "/accounts/{{id}}", required_scopes: [], fields: [] }}
+"#,
+            dir.join("bank.db").display(),
+            api_url
+        ))
+        .unwrap();
+        let section = &backoffice.sections[0];
+        let conditions = vec![RowCondition {
+            field: "tenant_id".to_string(),
+            value: json!(7),
+        }];
+
+        let registry = crate::data_source::DataSourceRegistry::new();
+        let db_config = &backoffice.data_sources["db"];
+        let db = registry.create(db_config).await.unwrap();
+        for statement in [
+            "CREATE TABLE bank_accounts (id TEXT PRIMARY KEY, tenant_id INTEGER)",
+            "INSERT INTO bank_accounts VALUES ('a1', 7), ('a2', 8)",
+        ] {
+            db.execute_mutation(statement, &HashMap::new())
+                .await
+                .unwrap();
+        }
+        // Ids are bound, never part of the SQL
+        for (id, expected) in [
+            ("a1", true),
+            ("a2", false),
+            ("a2' OR '1'='1", false),
+            ("a2\\' OR 1=1 -- ", false),
+        ] {
+            let visible = record_visible(
+                db.as_ref(),
+                section,
+                "db",
+                db_config,
+                &json!(id),
+                &conditions,
+            )
+            .await
+            .unwrap();
+            assert_eq!(visible, expected, "{}", id);
+        }
+
+        let api_config = &backoffice.data_sources["api"];
+        let api = registry.create(api_config).await.unwrap();
+        for (id, expected) in [("a1", true), ("a2", false)] {
+            let visible = record_visible(
+                api.as_ref(),
+                section,
+                "api",
+                api_config,
+                &json!(id),
+                &conditions,
+            )
+            .await
+            .unwrap();
+            assert_eq!(visible, expected, "{}", id);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
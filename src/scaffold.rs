@@ -0,0 +1,531 @@
+//! Config scaffolding from GraphQL schema introspection
+//!
+//! Every root query field returning a list of objects becomes a section with a
+//! list action, and `create<Type>`/`update<Type>` mutations become form actions
+//! in that section. Scalars map to field types, enums to select fields and
+//! non-null types to required fields.
+
+use crate::config::{
+    ActionConfig, ActionType, BooleanFieldConfig, DateFieldConfig, FieldConfig, FieldType,
+    FormActionConfig, FormMode, ListActionConfig, NumberFieldConfig, SectionConfig,
+    SelectFieldConfig, SelectOption, TextFieldConfig,
+};
+use crate::data_source::DataSource;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Introspection query covering root fields, arguments, input objects and enums
+pub const INTROSPECTION_QUERY: &str = "query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    types {
+      kind
+      name
+      enumValues { name }
+      fields {
+        name
+        args { name type { ...TypeRef } }
+        type { ...TypeRef }
+      }
+      inputFields { name type { ...TypeRef } }
+    }
+  }
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType { kind name ofType { kind name ofType { kind name ofType { kind name } } } }
+}";
+
+/// Pagination variables the GraphQL source sends with paginated queries
+const PAGINATION_ARGS: [&str; 4] = ["limit", "offset", "page", "pageSize"];
+
+/// Introspect a GraphQL data source and generate sections for it
+pub async fn scaffold_graphql(
+    source: &dyn DataSource,
+    data_source: &str,
+) -> Result<Vec<SectionConfig>> {
+    let rows = source.execute_query(INTROSPECTION_QUERY, None).await?;
+    let schema = rows
+        .first()
+        .and_then(|row| row.get("__schema"))
+        .ok_or_else(|| anyhow!("Introspection response has no __schema"))?;
+
+    Ok(graphql_sections(schema, data_source))
+}
+
+/// Generate sections from an introspected `__schema`
+pub fn graphql_sections(schema: &Value, data_source: &str) -> Vec<SectionConfig> {
+    let types: HashMap<&str, &Value> = schema["types"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| Some((t["name"].as_str()?, t)))
+        .collect();
+    let root_fields = |root: &str| -> Vec<&Value> {
+        schema[root]["name"]
+            .as_str()
+            .and_then(|name| types.get(name))
+            .and_then(|t| t["fields"].as_array())
+            .map(|fields| fields.iter().collect())
+            .unwrap_or_default()
+    };
+    let mutations = root_fields("mutationType");
+
+    root_fields("queryType")
+        .into_iter()
+        .filter_map(|query| {
+            let returns = TypeRef::new(&query["type"]);
+            let object = types.get(returns.name.as_str())?;
+            if !returns.list || returns.kind != "OBJECT" {
+                return None;
+            }
+
+            let section_id = query["name"].as_str()?;
+            let columns: Vec<(&str, TypeRef)> = object["fields"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|f| Some((f["name"].as_str()?, TypeRef::new(&f["type"]))))
+                .filter(|(_, ty)| ty.is_leaf() && !ty.list)
+                .collect();
+            if columns.is_empty() {
+                return None;
+            }
+
+            let mut actions = vec![list_action(query, &columns, &types, data_source)];
+            for (prefix, mode) in [("create", FormMode::Create), ("update", FormMode::Update)] {
+                let name = format!("{}{}", prefix, returns.name).to_lowercase();
+                if let Some(mutation) = mutations
+                    .iter()
+                    .find(|m| m["name"].as_str().map(str::to_lowercase) == Some(name.clone()))
+                {
+                    actions.extend(form_action(prefix, mode, mutation, &types, data_source));
+                }
+            }
+
+            Some(SectionConfig {
+                id: section_id.to_string(),
+                name: title_case(section_id),
+                icon: None,
+                actions,
+                audit: None,
+                row_filters: Vec::new(),
+                table: None,
+                seeds: Vec::new(),
+                updated_at_field: None,
+                search: None,
+                duplicate: None,
+                enabled_when: None,
+            })
+        })
+        .collect()
+}
+
+/// List action selecting every scalar and enum column of a query field
+fn list_action(
+    query: &Value,
+    columns: &[(&str, TypeRef)],
+    types: &HashMap<&str, &Value>,
+    data_source: &str,
+) -> ActionConfig {
+    let name = query["name"].as_str().unwrap_or_default();
+
+    // Declare the pagination variables the field accepts
+    let pagination: Vec<(&str, String)> = query["args"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|arg| {
+            let arg_name = arg["name"].as_str()?;
+            PAGINATION_ARGS
+                .contains(&arg_name)
+                .then(|| (arg_name, type_string(&arg["type"])))
+        })
+        .collect();
+    let (variables, arguments) = if pagination.is_empty() {
+        (String::new(), String::new())
+    } else {
+        (
+            format!(
+                "({})",
+                pagination
+                    .iter()
+                    .map(|(arg, ty)| format!("${}: {}", arg, ty))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            format!(
+                "({})",
+                pagination
+                    .iter()
+                    .map(|(arg, _)| format!("{}: ${}", arg, arg))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )
+    };
+    let selection: Vec<&str> = columns.iter().map(|(column, _)| *column).collect();
+
+    ActionConfig {
+        id: "list".to_string(),
+        name: format!("List {}", title_case(name)),
+        action_type: ActionType::List {
+            fields: columns
+                .iter()
+                .map(|(column, ty)| field(column, ty, types))
+                .collect(),
+            config: ListActionConfig {
+                enable_pagination: !pagination.is_empty(),
+                ..Default::default()
+            },
+        },
+        data_source: data_source.to_string(),
+        // Aliasing the field as `items` lets the source unwrap the rows
+        query: Some(format!(
+            "query{} {{ items: {}{} {{ {} }} }}",
+            variables,
+            name,
+            arguments,
+            selection.join(" ")
+        )),
+        endpoint: None,
+        method: None,
+        generated: false,
+        cache: None,
+        cache_ttl_secs: None,
+        enabled_when: None,
+        required_scopes: Vec::new(),
+    }
+}
+
+/// Form action calling a mutation with one variable per scalar argument or
+/// input object field
+fn form_action(
+    id: &str,
+    mode: FormMode,
+    mutation: &Value,
+    types: &HashMap<&str, &Value>,
+    data_source: &str,
+) -> Option<ActionConfig> {
+    let name = mutation["name"].as_str()?;
+    let mut fields = Vec::new();
+    let mut variables = Vec::new();
+    let mut arguments = Vec::new();
+
+    for arg in mutation["args"].as_array().into_iter().flatten() {
+        let arg_name = arg["name"].as_str()?;
+        let ty = TypeRef::new(&arg["type"]);
+
+        if ty.kind == "INPUT_OBJECT" && !ty.list {
+            let mut inputs = Vec::new();
+            for input in types
+                .get(ty.name.as_str())
+                .and_then(|t| t["inputFields"].as_array())
+                .into_iter()
+                .flatten()
+            {
+                let input_name = input["name"].as_str()?;
+                let input_ty = TypeRef::new(&input["type"]);
+                if !input_ty.is_leaf() || input_ty.list {
+                    continue;
+                }
+                variables.push(format!("${}: {}", input_name, type_string(&input["type"])));
+                inputs.push(format!("{}: ${}", input_name, input_name));
+                fields.push(field(input_name, &input_ty, types));
+            }
+            arguments.push(format!("{}: {{ {} }}", arg_name, inputs.join(", ")));
+        } else if ty.is_leaf() && !ty.list {
+            variables.push(format!("${}: {}", arg_name, type_string(&arg["type"])));
+            arguments.push(format!("{}: ${}", arg_name, arg_name));
+            fields.push(field(arg_name, &ty, types));
+        }
+    }
+    if fields.is_empty() {
+        return None;
+    }
+
+    // Select the id of returned objects, or just the type name
+    let returns = TypeRef::new(&mutation["type"]);
+    let selection = match types.get(returns.name.as_str()) {
+        Some(t) if returns.kind == "OBJECT" => {
+            let has_id = t["fields"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|f| f["name"] == "id");
+            if has_id {
+                " { id }"
+            } else {
+                " { __typename }"
+            }
+        }
+        _ => "",
+    };
+
+    Some(ActionConfig {
+        id: id.to_string(),
+        name: title_case(name),
+        action_type: ActionType::Form {
+            fields,
+            config: FormActionConfig {
+                form_mode: mode,
+                ..Default::default()
+            },
+            layout: None,
+        },
+        data_source: data_source.to_string(),
+        query: Some(format!(
+            "mutation({}) {{ {}({}){} }}",
+            variables.join(", "),
+            name,
+            arguments.join(", "),
+            selection
+        )),
+        endpoint: None,
+        method: None,
+        generated: false,
+        cache: None,
+        cache_ttl_secs: None,
+        enabled_when: None,
+        required_scopes: Vec::new(),
+    })
+}
+
+/// Field config for a scalar or enum value
+fn field(name: &str, ty: &TypeRef, types: &HashMap<&str, &Value>) -> FieldConfig {
+    let field_type = match (ty.kind.as_str(), ty.name.as_str()) {
+        ("ENUM", enum_name) => FieldType::Select {
+            config: SelectFieldConfig {
+                options: types
+                    .get(enum_name)
+                    .and_then(|t| t["enumValues"].as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|v| v["name"].as_str())
+                    .map(|v| SelectOption {
+                        value: v.to_string(),
+                        label: title_case(&v.to_lowercase()),
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        },
+        (_, "Int") => FieldType::Number {
+            config: NumberFieldConfig {
+                allow_decimals: false,
+                ..Default::default()
+            },
+        },
+        (_, "Float") => FieldType::Number {
+            config: NumberFieldConfig::default(),
+        },
+        (_, "Boolean") => FieldType::Boolean {
+            config: BooleanFieldConfig::default(),
+        },
+        (_, "Date") => FieldType::Date {
+            config: DateFieldConfig::default(),
+        },
+        (_, "DateTime") => FieldType::DateTime {
+            config: DateFieldConfig::default(),
+        },
+        _ => FieldType::Text {
+            config: TextFieldConfig::default(),
+        },
+    };
+
+    FieldConfig {
+        id: name.to_string(),
+        name: title_case(name),
+        field_type,
+        required: ty.required,
+        editable: ty.name != "ID",
+        visible: true,
+        default_value: None,
+        placeholder: None,
+        help_text: None,
+        validations: Vec::new(),
+        relationship_id: None,
+        sensitive: false,
+        mask: None,
+        unmask_scopes: Vec::new(),
+        required_scopes: Vec::new(),
+        write_scopes: Vec::new(),
+        visible_when: None,
+        required_when: None,
+    }
+}
+
+/// Named type behind `NON_NULL` and `LIST` wrappers
+#[derive(Debug)]
+struct TypeRef {
+    kind: String,
+    name: String,
+    required: bool,
+    list: bool,
+}
+
+impl TypeRef {
+    fn new(ty: &Value) -> Self {
+        let required = ty["kind"] == "NON_NULL";
+        let mut list = false;
+        let mut ty = ty;
+        while ty["name"].is_null() && ty["ofType"].is_object() {
+            list |= ty["kind"] == "LIST";
+            ty = &ty["ofType"];
+        }
+
+        Self {
+            kind: ty["kind"].as_str().unwrap_or_default().to_string(),
+            name: ty["name"].as_str().unwrap_or_default().to_string(),
+            required,
+            list,
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.kind == "SCALAR" || self.kind == "ENUM"
+    }
+}
+
+/// GraphQL type notation of a type reference, e.g. `[ID!]!`
+fn type_string(ty: &Value) -> String {
+    match ty["kind"].as_str() {
+        Some("NON_NULL") => format!("{}!", type_string(&ty["ofType"])),
+        Some("LIST") => format!("[{}]", type_string(&ty["ofType"])),
+        _ => ty["name"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+/// Human-readable label for a camelCase or snake_case name
+fn title_case(name: &str) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if c == '_' || c == '-' {
+            words.push(String::new());
+            previous_lower = false;
+            continue;
+        }
+        if words.is_empty() || (c.is_uppercase() && previous_lower) {
+            words.push(String::new());
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        if let Some(word) = words.last_mut() {
+            word.push(c);
+        }
+    }
+
+    words
+        .iter()
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn named(kind: &str, name: &str) -> Value {
+        json!({"kind": kind, "name": name, "ofType": null})
+    }
+
+    fn non_null(ty: Value) -> Value {
+        json!({"kind": "NON_NULL", "name": null, "ofType": ty})
+    }
+
+    fn list(ty: Value) -> Value {
+        json!({"kind": "LIST", "name": null, "ofType": ty})
+    }
+
+    fn schema() -> Value {
+        json!({
+            "queryType": {"name": "Query"},
+            "mutationType": {"name": "Mutation"},
+            "types": [
+                {"kind": "OBJECT", "name": "Query", "fields": [
+                    {"name": "users", "args": [
+                        {"name": "limit", "type": named("SCALAR", "Int")},
+                        {"name": "offset", "type": named("SCALAR", "Int")}
+                    ], "type": non_null(list(non_null(named("OBJECT", "User"))))},
+                    {"name": "user", "args": [
+                        {"name": "id", "type": non_null(named("SCALAR", "ID"))}
+                    ], "type": named("OBJECT", "User")}
+                ]},
+                {"kind": "OBJECT", "name": "Mutation", "fields": [
+                    {"name": "createUser", "args": [
+                        {"name": "input", "type": non_null(named("INPUT_OBJECT", "CreateUserInput"))}
+                    ], "type": named("OBJECT", "User")}
+                ]},
+                {"kind": "OBJECT", "name": "User", "fields": [
+                    {"name": "id", "args": [], "type": non_null(named("SCALAR", "ID"))},
+                    {"name": "fullName", "args": [], "type": named("SCALAR", "String")},
+                    {"name": "age", "args": [], "type": named("SCALAR", "Int")},
+                    {"name": "role", "args": [], "type": non_null(named("ENUM", "Role"))},
+                    {"name": "friends", "args": [], "type": list(named("OBJECT", "User"))}
+                ]},
+                {"kind": "INPUT_OBJECT", "name": "CreateUserInput", "inputFields": [
+                    {"name": "fullName", "type": non_null(named("SCALAR", "String"))},
+                    {"name": "role", "type": named("ENUM", "Role")}
+                ]},
+                {"kind": "ENUM", "name": "Role", "enumValues": [
+                    {"name": "ADMIN"}, {"name": "SUPPORT_AGENT"}
+                ]}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_graphql_sections() {
+        let sections = graphql_sections(&schema(), "api");
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].id, "users");
+        assert_eq!(sections[0].actions.len(), 2);
+
+        let list = &sections[0].actions[0];
+        assert_eq!(
+            list.query.as_deref(),
+            Some(
+                "query($limit: Int, $offset: Int) { items: users(limit: $limit, offset: $offset) { id fullName age role } }"
+            )
+        );
+        let ActionType::List { fields, config } = &list.action_type else {
+            panic!("expected a list action");
+        };
+        assert!(config.enable_pagination);
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[1].name, "Full Name");
+        assert!(fields[0].required && !fields[0].editable);
+        assert!(matches!(
+            &fields[3].field_type,
+            FieldType::Select { config } if config.options[1].value == "SUPPORT_AGENT"
+                && config.options[1].label == "Support Agent"
+        ));
+
+        let create = &sections[0].actions[1];
+        assert_eq!(create.id, "create");
+        assert_eq!(
+            create.query.as_deref(),
+            Some(
+                "mutation($fullName: String!, $role: Role) { createUser(input: { fullName: $fullName, role: $role }) { id } }"
+            )
+        );
+        let ActionType::Form { fields, .. } = &create.action_type else {
+            panic!("expected a form action");
+        };
+        assert!(fields[0].required);
+        assert!(!fields[1].required);
+    }
+}
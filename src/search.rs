@@ -0,0 +1,642 @@
+//! Global search of list actions
+//!
+//! List actions declaring `searchable_fields` accept a `q` parameter matched
+//! case-insensitively against any of those fields. Like row filters, the
+//! search is pushed down into the query when the data source can run it: a
+//! `LIKE`/`ILIKE` over SQL, a `multi_match` for Elasticsearch and `$regex`
+//! conditions for MongoDB. API data sources receive `q` as a query parameter
+//! and search on their own; the rows of other sources are filtered in memory.
+//!
+//! Sections declaring a `search` backend answer `q` with full-text search
+//! instead: a PostgreSQL `tsvector` column, an Elasticsearch index or a
+//! Meilisearch index returns the ids of the best matches with highlighted
+//! snippets, and the list keeps those rows in rank order. When the backend
+//! fails, the search falls back to the `LIKE` matching above.
+
+use crate::config::{
+    BackofficeConfig, DataSourceConfig, DatabaseType, ListActionConfig, SearchBackend,
+    SearchConfig, SectionConfig,
+};
+use crate::crud::quote_identifier;
+use crate::data_source::{self, DataSourceRegistry};
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Request parameter holding the search text
+pub const SEARCH_PARAM: &str = "q";
+
+/// Bound parameter holding the SQL `LIKE` pattern
+const PATTERN_PARAM: &str = "__search_pattern";
+
+/// Bound parameters of PostgreSQL full-text searches
+const TEXT_PARAM: &str = "__search_text";
+const LANGUAGE_PARAM: &str = "__search_language";
+
+/// Column of PostgreSQL full-text hits holding the record id
+const HIT_ID_COLUMN: &str = "__id";
+
+/// Row key holding a row's highlighted snippets, by field
+pub const HIGHLIGHT_KEY: &str = "_highlight";
+
+/// Tags around the matched terms of snippets
+const HIGHLIGHT_PRE: &str = "<mark>";
+const HIGHLIGHT_POST: &str = "</mark>";
+
+/// Words kept around matches in Meilisearch snippets
+const CROP_LENGTH: usize = 20;
+
+/// A record matched by a full-text search backend
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub id: Value,
+    /// Snippets of the matching fields, with matches in `<mark>` tags
+    pub highlights: HashMap<String, String>,
+}
+
+/// Fields searched and highlighted for a list: those of the section's
+/// search backend, or the list's `searchable_fields`
+pub fn search_fields<'a>(section: &'a SectionConfig, list: &'a ListActionConfig) -> &'a [String] {
+    match &section.search {
+        Some(search) if !search.fields.is_empty() => &search.fields,
+        _ => &list.searchable_fields,
+    }
+}
+
+/// Whether the data source searches by itself, without in-memory filtering
+pub fn pushes_down(config: &DataSourceConfig) -> bool {
+    matches!(
+        config,
+        DataSourceConfig::Database { .. }
+            | DataSourceConfig::Elasticsearch { .. }
+            | DataSourceConfig::MongoDB { .. }
+            | DataSourceConfig::Api { .. }
+    )
+}
+
+/// Add the search to a query, binding the SQL pattern into `params`
+pub fn search_query(
+    query: &str,
+    fields: &[String],
+    term: &str,
+    config: &DataSourceConfig,
+    params: &mut HashMap<String, Value>,
+) -> Result<String> {
+    match config {
+        DataSourceConfig::Database { db_type, .. } => {
+            params.insert(PATTERN_PARAM.to_string(), Value::String(like_pattern(term)));
+            Ok(sql_search(query, fields, db_type))
+        }
+        DataSourceConfig::Elasticsearch { .. } => elasticsearch_search(query, fields, term),
+        DataSourceConfig::MongoDB { .. } => mongo_search(query, fields, term),
+        _ => Ok(query.to_string()),
+    }
+}
+
+/// Wrap a SQL query so only rows with a field containing the pattern remain
+fn sql_search(query: &str, fields: &[String], db_type: &DatabaseType) -> String {
+    let (text_type, like) = match db_type {
+        DatabaseType::Postgres => ("TEXT", "ILIKE"),
+        DatabaseType::MySQL => ("CHAR", "LIKE"),
+        DatabaseType::Sqlite => ("TEXT", "LIKE"),
+    };
+    let conditions = fields
+        .iter()
+        .map(|field| {
+            format!(
+                "CAST({} AS {}) {} {{{{{}}}}} ESCAPE '!'",
+                quote_identifier(field, db_type),
+                text_type,
+                like,
+                PATTERN_PARAM
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" OR ");
+
+    let query = query.trim().trim_end_matches(';');
+    format!("SELECT * FROM ({}) AS searched WHERE {}", query, conditions)
+}
+
+/// `%term%`, with `!` escaping the wildcards of the term
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('!', "!!")
+        .replace('%', "!%")
+        .replace('_', "!_");
+    format!("%{}%", escaped)
+}
+
+/// Combine the search body's query with a `multi_match` over the fields
+fn elasticsearch_search(query: &str, fields: &[String], term: &str) -> Result<String> {
+    let mut body: Value = if query.trim().is_empty() || query.trim() == "{}" {
+        json!({})
+    } else {
+        serde_json::from_str(query)
+            .map_err(|e| anyhow!("Invalid Elasticsearch query JSON: {}", e))?
+    };
+    let object = body
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Elasticsearch query must be a JSON object"))?;
+    let original = object
+        .remove("query")
+        .unwrap_or_else(|| json!({ "match_all": {} }));
+    object.insert(
+        "query".to_string(),
+        json!({
+            "bool": {
+                "must": [
+                    original,
+                    { "multi_match": { "query": term, "fields": fields, "lenient": true } },
+                ]
+            }
+        }),
+    );
+    Ok(body.to_string())
+}
+
+/// Add case-insensitive `$regex` conditions to a find filter, or a `$match`
+/// stage to the end of an aggregation pipeline
+fn mongo_search(query: &str, fields: &[String], term: &str) -> Result<String> {
+    let pattern = regex::escape(term);
+    let search = json!({
+        "$or": fields
+            .iter()
+            .map(|field| json!({ field: { "$regex": pattern, "$options": "i" } }))
+            .collect::<Vec<_>>()
+    });
+
+    let query = query.trim();
+    if query.is_empty() || query == "{}" {
+        return Ok(search.to_string());
+    }
+    let parsed: Value =
+        serde_json::from_str(query).map_err(|e| anyhow!("Invalid MongoDB query JSON: {}", e))?;
+    Ok(match parsed {
+        Value::Array(mut pipeline) => {
+            pipeline.push(json!({ "$match": search }));
+            Value::Array(pipeline)
+        }
+        filter => json!({ "$and": [filter, search] }),
+    }
+    .to_string())
+}
+
+/// Keep rows with a field containing the term, ignoring case
+pub fn retain_rows(rows: &mut Vec<HashMap<String, Value>>, fields: &[String], term: &str) {
+    let term = term.to_lowercase();
+    rows.retain(|row| {
+        fields.iter().any(|field| match row.get(field) {
+            Some(Value::String(s)) => s.to_lowercase().contains(&term),
+            Some(Value::Null) | None => false,
+            Some(value) => value.to_string().to_lowercase().contains(&term),
+        })
+    });
+}
+
+/// Ask the section's full-text backend for the best matches of a term
+pub async fn full_text_hits(
+    search: &SearchConfig,
+    fields: &[String],
+    backoffice: &BackofficeConfig,
+    section: &SectionConfig,
+    default_source: &str,
+    registry: &DataSourceRegistry,
+    term: &str,
+) -> Result<Vec<SearchHit>> {
+    match &search.backend {
+        SearchBackend::Postgres {
+            column,
+            language,
+            data_source,
+        } => {
+            let name = data_source.as_deref().unwrap_or(default_source);
+            let config = backoffice
+                .data_sources
+                .get(name)
+                .ok_or_else(|| anyhow!("Search data source '{}' not found", name))?;
+            if !matches!(
+                config,
+                DataSourceConfig::Database {
+                    db_type: DatabaseType::Postgres,
+                    ..
+                }
+            ) {
+                bail!("Search data source '{}' is not a PostgreSQL database", name);
+            }
+
+            let table = section.table.as_deref().unwrap_or(&section.id);
+            let query = postgres_hits_query(table, column, fields, search.limit);
+            let params = HashMap::from([
+                (TEXT_PARAM.to_string(), Value::String(term.to_string())),
+                (LANGUAGE_PARAM.to_string(), Value::String(language.clone())),
+            ]);
+            let rows = registry
+                .create(config)
+                .await?
+                .execute_query(&query, Some(&params))
+                .await?;
+            Ok(rows
+                .into_iter()
+                .filter_map(|mut row| {
+                    let id = row.remove(HIT_ID_COLUMN)?;
+                    Some(SearchHit {
+                        id,
+                        highlights: snippets(row),
+                    })
+                })
+                .collect())
+        }
+        SearchBackend::Elasticsearch { data_source } => {
+            let config = backoffice
+                .data_sources
+                .get(data_source)
+                .ok_or_else(|| anyhow!("Search data source '{}' not found", data_source))?;
+            if !matches!(config, DataSourceConfig::Elasticsearch { .. }) {
+                bail!("Search data source '{}' is not Elasticsearch", data_source);
+            }
+
+            let body = elasticsearch_hits_body(fields, term, search.limit);
+            let rows = registry
+                .create(config)
+                .await?
+                .execute_query(&body.to_string(), None)
+                .await?;
+            Ok(rows
+                .into_iter()
+                .filter_map(|mut row| {
+                    let id = row.remove("id").or_else(|| row.remove("_id"))?;
+                    let highlights = match row.remove(HIGHLIGHT_KEY) {
+                        Some(Value::Object(highlight)) => highlight
+                            .into_iter()
+                            .map(|(field, fragments)| {
+                                let fragments = match fragments {
+                                    Value::Array(fragments) => fragments
+                                        .iter()
+                                        .filter_map(|f| f.as_str())
+                                        .collect::<Vec<_>>()
+                                        .join(" … "),
+                                    other => other.as_str().unwrap_or_default().to_string(),
+                                };
+                                (field, fragments)
+                            })
+                            .collect(),
+                        _ => HashMap::new(),
+                    };
+                    Some(SearchHit { id, highlights })
+                })
+                .collect())
+        }
+        SearchBackend::Meilisearch {
+            url,
+            index,
+            api_key,
+            primary_key,
+        } => {
+            let client = reqwest::Client::builder()
+                .timeout(data_source::DEFAULT_HTTP_TIMEOUT)
+                .build()?;
+            let mut request = client
+                .post(format!(
+                    "{}/indexes/{}/search",
+                    url.trim_end_matches('/'),
+                    data_source::url_encode(index)
+                ))
+                .json(&meilisearch_body(fields, term, search.limit));
+            if let Some(key) = api_key {
+                request = request.bearer_auth(key);
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| anyhow!("Meilisearch request failed: {}", e))?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_default();
+                bail!("Meilisearch returned error {}: {}", status, error_text);
+            }
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| anyhow!("Failed to parse Meilisearch response: {}", e))?;
+            Ok(meilisearch_hits(&body, primary_key))
+        }
+    }
+}
+
+/// Ids of the rows whose `tsvector` column matches, best ranked first, with
+/// a `ts_headline` snippet per field
+fn postgres_hits_query(table: &str, column: &str, fields: &[String], limit: usize) -> String {
+    let db_type = DatabaseType::Postgres;
+    let column = format!("fts.{}", quote_identifier(column, &db_type));
+    let mut columns = vec![format!(
+        "fts.{} AS {}",
+        quote_identifier("id", &db_type),
+        quote_identifier(HIT_ID_COLUMN, &db_type)
+    )];
+    for field in fields {
+        columns.push(format!(
+            "ts_headline({{{{{}}}}}::regconfig, CAST(fts.{} AS TEXT), query, \
+             'StartSel={}, StopSel={}, MaxFragments=2') AS {}",
+            LANGUAGE_PARAM,
+            quote_identifier(field, &db_type),
+            HIGHLIGHT_PRE,
+            HIGHLIGHT_POST,
+            quote_identifier(field, &db_type)
+        ));
+    }
+
+    format!(
+        "SELECT {} FROM {} AS fts, \
+         websearch_to_tsquery({{{{{}}}}}::regconfig, {{{{{}}}}}) AS query \
+         WHERE {} @@ query ORDER BY ts_rank({}, query) DESC LIMIT {}",
+        columns.join(", "),
+        quote_identifier(table, &db_type),
+        LANGUAGE_PARAM,
+        TEXT_PARAM,
+        column,
+        column,
+        limit
+    )
+}
+
+/// Search body of an Elasticsearch index, highlighting the fields
+fn elasticsearch_hits_body(fields: &[String], term: &str, limit: usize) -> Value {
+    let searched: Vec<&str> = if fields.is_empty() {
+        vec!["*"]
+    } else {
+        fields.iter().map(String::as_str).collect()
+    };
+    json!({
+        "query": { "multi_match": { "query": term, "fields": searched, "lenient": true } },
+        "size": limit,
+        "_source": ["id"],
+        "highlight": {
+            "pre_tags": [HIGHLIGHT_PRE],
+            "post_tags": [HIGHLIGHT_POST],
+            "fields": fields
+                .iter()
+                .map(|field| (field.clone(), json!({})))
+                .collect::<serde_json::Map<_, _>>(),
+        },
+    })
+}
+
+/// Search request of a Meilisearch index, highlighting and cropping the fields
+fn meilisearch_body(fields: &[String], term: &str, limit: usize) -> Value {
+    json!({
+        "q": term,
+        "limit": limit,
+        "attributesToHighlight": fields,
+        "attributesToCrop": fields,
+        "cropLength": CROP_LENGTH,
+        "highlightPreTag": HIGHLIGHT_PRE,
+        "highlightPostTag": HIGHLIGHT_POST,
+    })
+}
+
+/// Hits of a Meilisearch response, with the snippets of its `_formatted`
+/// copies of the documents
+fn meilisearch_hits(body: &Value, primary_key: &str) -> Vec<SearchHit> {
+    body.get("hits")
+        .and_then(Value::as_array)
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| {
+                    let id = hit.get(primary_key)?.clone();
+                    let formatted = match hit.get("_formatted") {
+                        Some(Value::Object(formatted)) => formatted.clone().into_iter().collect(),
+                        _ => HashMap::new(),
+                    };
+                    Some(SearchHit {
+                        id,
+                        highlights: snippets(formatted),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Text values containing a highlighted match
+fn snippets(values: HashMap<String, Value>) -> HashMap<String, String> {
+    values
+        .into_iter()
+        .filter_map(|(field, value)| match value {
+            Value::String(s) if s.contains(HIGHLIGHT_PRE) => Some((field, s)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Limit a SQL query to the hits' records, so only those are fetched
+///
+/// The ids are bound through `params` as `__search_hit_<n>`.
+pub fn hits_query(
+    query: &str,
+    hits: &[SearchHit],
+    config: &DataSourceConfig,
+    params: &mut HashMap<String, Value>,
+) -> String {
+    let DataSourceConfig::Database { db_type, .. } = config else {
+        return query.to_string();
+    };
+    let condition = if hits.is_empty() {
+        "1 = 0".to_string()
+    } else {
+        let placeholders = hits
+            .iter()
+            .enumerate()
+            .map(|(index, hit)| {
+                let name = format!("__search_hit_{}", index);
+                params.insert(name.clone(), hit.id.clone());
+                format!("{{{{{}}}}}", name)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} IN ({})", quote_identifier("id", db_type), placeholders)
+    };
+    let query = query.trim().trim_end_matches(';');
+    format!("SELECT * FROM ({}) AS searched WHERE {}", query, condition)
+}
+
+/// Ids compare as text, since search indexes often store them as strings
+fn id_key(id: &Value) -> String {
+    match id {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn row_id(row: &HashMap<String, Value>) -> Option<&Value> {
+    row.get("id").or_else(|| row.get("_id"))
+}
+
+/// Keep the rows matched by the hits, best match first
+pub fn rank_rows(rows: &mut Vec<HashMap<String, Value>>, hits: &[SearchHit]) {
+    let ranks: HashMap<String, usize> = hits
+        .iter()
+        .enumerate()
+        .map(|(rank, hit)| (id_key(&hit.id), rank))
+        .collect();
+    let rank = |row: &HashMap<String, Value>| row_id(row).and_then(|id| ranks.get(&id_key(id)));
+    rows.retain(|row| rank(row).is_some());
+    rows.sort_by_key(|row| rank(row).copied());
+}
+
+/// Add the hits' snippets to their rows under `_highlight`, except those of
+/// `hidden` fields
+pub fn attach_highlights(rows: &mut [HashMap<String, Value>], hits: &[SearchHit], hidden: &[&str]) {
+    let highlights: HashMap<String, &HashMap<String, String>> = hits
+        .iter()
+        .map(|hit| (id_key(&hit.id), &hit.highlights))
+        .collect();
+    for row in rows.iter_mut() {
+        let Some(snippets) = row_id(row).and_then(|id| highlights.get(&id_key(id))) else {
+            continue;
+        };
+        let visible: serde_json::Map<String, Value> = snippets
+            .iter()
+            .filter(|(field, _)| !hidden.contains(&field.as_str()))
+            .map(|(field, snippet)| (field.clone(), Value::String(snippet.clone())))
+            .collect();
+        if !visible.is_empty() {
+            row.insert(HIGHLIGHT_KEY.to_string(), Value::Object(visible));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Vec<String> {
+        vec!["name".to_string(), "email".to_string()]
+    }
+
+    #[test]
+    fn test_search_queries() {
+        let db = DataSourceConfig::Database {
+            connection_string: "postgres://localhost/app".to_string(),
+            db_type: DatabaseType::Postgres,
+            read_replicas: vec![],
+            migrations: None,
+            init: Default::default(),
+        };
+        let mut params = HashMap::new();
+        let query = search_query("SELECT * FROM users;", &fields(), "50%", &db, &mut params);
+        assert_eq!(
+            query.unwrap(),
+            "SELECT * FROM (SELECT * FROM users) AS searched WHERE \
+             CAST(\"name\" AS TEXT) ILIKE {{__search_pattern}} ESCAPE '!' OR \
+             CAST(\"email\" AS TEXT) ILIKE {{__search_pattern}} ESCAPE '!'"
+        );
+        assert_eq!(params[PATTERN_PARAM], "%50!%%");
+
+        let search: Value =
+            serde_json::from_str(&mongo_search(r#"{"active": true}"#, &fields(), "a.b").unwrap())
+                .unwrap();
+        assert_eq!(search["$and"][0], json!({"active": true}));
+        assert_eq!(search["$and"][1]["$or"][1]["email"]["$regex"], "a\\.b");
+
+        let body: Value = serde_json::from_str(
+            &elasticsearch_search(
+                r#"{"query": {"term": {"active": true}}, "size": 5}"#,
+                &fields(),
+                "ann",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(body["size"], 5);
+        assert_eq!(
+            body["query"]["bool"]["must"][1]["multi_match"]["query"],
+            "ann"
+        );
+    }
+
+    #[test]
+    fn test_retain_rows() {
+        let mut rows = vec![
+            HashMap::from([("name".to_string(), json!("Ann Lee"))]),
+            HashMap::from([("email".to_string(), json!("ANNA@example.com"))]),
+            HashMap::from([("name".to_string(), json!("Bob"))]),
+        ];
+        retain_rows(&mut rows, &fields(), "ann");
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[test]
+    fn test_full_text_hits() {
+        let query = postgres_hits_query("users", "document", &fields()[..1], 50);
+        assert_eq!(
+            query,
+            "SELECT fts.\"id\" AS \"__id\", ts_headline({{__search_language}}::regconfig, \
+             CAST(fts.\"name\" AS TEXT), query, \
+             'StartSel=<mark>, StopSel=</mark>, MaxFragments=2') AS \"name\" \
+             FROM \"users\" AS fts, \
+             websearch_to_tsquery({{__search_language}}::regconfig, {{__search_text}}) AS query \
+             WHERE fts.\"document\" @@ query ORDER BY ts_rank(fts.\"document\", query) DESC LIMIT 50"
+        );
+
+        let body = elasticsearch_hits_body(&fields(), "ann", 10);
+        assert_eq!(body["highlight"]["fields"]["email"], json!({}));
+
+        let hits = meilisearch_hits(
+            &json!({"hits": [
+                {"sku": "b", "_formatted": {"sku": "b", "name": "<mark>Ann</mark> Lee"}},
+                {"sku": "a", "_formatted": {"sku": "a", "name": "Annex"}},
+                {"name": "no primary key"},
+            ]}),
+            "sku",
+        );
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].highlights["name"], "<mark>Ann</mark> Lee");
+        assert!(hits[1].highlights.is_empty());
+
+        let db = DataSourceConfig::Database {
+            connection_string: "sqlite::memory:".to_string(),
+            db_type: DatabaseType::Sqlite,
+            read_replicas: vec![],
+            migrations: None,
+            init: Default::default(),
+        };
+        let hits = vec![
+            SearchHit {
+                id: json!("2"),
+                highlights: HashMap::from([
+                    ("name".to_string(), "<mark>Ann</mark>".to_string()),
+                    ("email".to_string(), "<mark>ann</mark>@x".to_string()),
+                ]),
+            },
+            SearchHit {
+                id: json!(1),
+                highlights: HashMap::new(),
+            },
+        ];
+        let mut params = HashMap::new();
+        assert_eq!(
+            hits_query("SELECT * FROM users", &hits, &db, &mut params),
+            "SELECT * FROM (SELECT * FROM users) AS searched \
+             WHERE \"id\" IN ({{__search_hit_0}}, {{__search_hit_1}})"
+        );
+        assert_eq!(params["__search_hit_0"], json!("2"));
+        assert_eq!(params["__search_hit_1"], json!(1));
+        assert_eq!(
+            hits_query("SELECT * FROM users", &[], &db, &mut params),
+            "SELECT * FROM (SELECT * FROM users) AS searched WHERE 1 = 0"
+        );
+
+        let mut rows: Vec<HashMap<String, Value>> = (1..=3)
+            .map(|id| HashMap::from([("id".to_string(), json!(id))]))
+            .collect();
+        rank_rows(&mut rows, &hits);
+        assert_eq!(
+            rows.iter().map(|r| r["id"].clone()).collect::<Vec<_>>(),
+            [json!(2), json!(1)]
+        );
+        attach_highlights(&mut rows, &hits, &["email"]);
+        assert_eq!(rows[0][HIGHLIGHT_KEY], json!({"name": "<mark>Ann</mark>"}));
+        assert!(!rows[1].contains_key(HIGHLIGHT_KEY));
+    }
+}
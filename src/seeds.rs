@@ -0,0 +1,253 @@
+//! Seed data
+//!
+//! Sections list fixture files under `seeds` (YAML, JSON, JSONL or CSV).
+//! Seeding submits every record through the section's form action with the
+//! same validation and query as a submitted form, so fixtures for demo
+//! environments and integration tests cannot bypass the section's rules.
+
+use crate::config::{ActionConfig, ActionType, BackofficeConfig, FileFormat, SectionConfig};
+use crate::crud::{self, CrudOperation};
+use crate::data_source::{self, DataSource, DataSourceRegistry};
+use crate::validation::{self, ValidationError, ValidationFunctions};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Outcome of seeding one section
+#[derive(Debug, Clone, Serialize)]
+pub struct SeedReport {
+    pub section_id: String,
+    pub inserted: usize,
+    pub failed: Vec<SeedFailure>,
+}
+
+/// A fixture record that was not inserted
+#[derive(Debug, Clone, Serialize)]
+pub struct SeedFailure {
+    pub file: String,
+    /// 1-based position of the record in its file
+    pub record: usize,
+    pub errors: Vec<String>,
+}
+
+/// The action seeds are inserted through: the section's first form
+pub fn seed_action(section: &SectionConfig) -> Option<&ActionConfig> {
+    section
+        .actions
+        .iter()
+        .find(|a| matches!(a.action_type, ActionType::Form { .. }))
+}
+
+/// Read the records of a fixture file, by extension
+pub async fn load_fixture(path: &str) -> Result<Vec<HashMap<String, Value>>> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| anyhow!("Failed to read seed file '{}': {}", path, e))?;
+
+    let extension = Path::new(path).extension().and_then(|e| e.to_str());
+    let records = match extension {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&text).map_err(anyhow::Error::from),
+        Some("json") => data_source::parse_rows(&text, &FileFormat::Json),
+        Some("jsonl") | Some("ndjson") => data_source::parse_rows(&text, &FileFormat::Jsonl),
+        Some("csv") => data_source::parse_rows(&text, &FileFormat::Csv),
+        _ => Err(anyhow!(
+            "expected a .yaml, .yml, .json, .jsonl or .csv file"
+        )),
+    };
+    records.map_err(|e| anyhow!("Failed to parse seed file '{}': {}", path, e))
+}
+
+fn messages(errors: Vec<ValidationError>) -> Vec<String> {
+    errors
+        .into_iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect()
+}
+
+/// Insert the fixtures of every section of a backoffice that declares seeds
+///
+/// Records that fail validation or insertion are reported and skipped; an
+/// error is only returned when a fixture cannot be read or a section cannot
+/// be seeded at all.
+pub async fn seed_backoffice(
+    backoffice: &BackofficeConfig,
+    registry: &DataSourceRegistry,
+) -> Result<Vec<SeedReport>> {
+    let sections: Vec<&SectionConfig> = backoffice
+        .sections
+        .iter()
+        .filter(|s| !s.seeds.is_empty())
+        .collect();
+    if sections.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let functions = ValidationFunctions::from_backoffice(backoffice)?;
+    let mut data_sources: HashMap<String, Box<dyn DataSource>> = HashMap::new();
+    for (name, config) in &backoffice.data_sources {
+        let data_source = registry
+            .create(config)
+            .await
+            .map_err(|e| anyhow!("Failed to create data source '{}': {}", name, e))?;
+        data_sources.insert(name.clone(), data_source);
+    }
+
+    let mut reports = Vec::with_capacity(sections.len());
+    for section in sections {
+        let action = seed_action(section)
+            .ok_or_else(|| anyhow!("Section '{}' has seeds but no form action", section.id))?;
+        let fields = match &action.action_type {
+            ActionType::Form { fields, .. } => fields,
+            _ => unreachable!("seed actions are forms"),
+        };
+        let config = backoffice
+            .data_sources
+            .get(&action.data_source)
+            .ok_or_else(|| anyhow!("Data source '{}' not found", action.data_source))?;
+        let data_source = &data_sources[&action.data_source];
+
+        let mut report = SeedReport {
+            section_id: section.id.clone(),
+            inserted: 0,
+            failed: Vec::new(),
+        };
+        for file in &section.seeds {
+            for (index, record) in load_fixture(file).await?.into_iter().enumerate() {
+                let fail = |errors: Vec<String>| SeedFailure {
+                    file: file.clone(),
+                    record: index + 1,
+                    errors,
+                };
+
+                let errors =
+                    validation::validate_data_with_functions(&record, fields, &functions).await?;
+                if !errors.is_empty() {
+                    report.failed.push(fail(messages(errors)));
+                    continue;
+                }
+                let errors = validation::validate_data_async(
+                    &record,
+                    fields,
+                    section,
+                    &action.data_source,
+                    backoffice.data_sources.get(&action.data_source),
+                    &data_sources,
+                )
+                .await?;
+                if !errors.is_empty() {
+                    report.failed.push(fail(messages(errors)));
+                    continue;
+                }
+
+                let inserted = crud::action_target(
+                    section,
+                    action,
+                    config,
+                    CrudOperation::for_action(action, Some(&record)),
+                    &record,
+                );
+                let inserted = match inserted {
+                    Ok(query) => data_source.execute_mutation(&query, &record).await,
+                    Err(e) => Err(e),
+                };
+                match inserted {
+                    Ok(_) => report.inserted += 1,
+                    Err(e) => report.failed.push(fail(vec![e.to_string()])),
+                }
+            }
+        }
+
+        if report.failed.is_empty() {
+            info!(section = %section.id, inserted = report.inserted, "Seeded section");
+        } else {
+            warn!(
+                section = %section.id,
+                inserted = report.inserted,
+                failed = report.failed.len(),
+                "Seeded section with failures"
+            );
+        }
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_seed_backoffice() {
+        let dir = std::env::temp_dir().join(format!("pmp-seeds-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("app.db");
+        let yaml = dir.join("users.yaml");
+        let csv = dir.join("users.csv");
+        std::fs::write(&yaml, "- name: alice\n  age: 30\n- age: 5\n").unwrap();
+        std::fs::write(&csv, "name,age:int\nbob,40\n").unwrap();
+
+        let backoffice: BackofficeConfig = serde_yaml::from_str(&format!(
+            r#"
+id: demo
+name: Demo
+data_sources:
+  db:
+    type: database
+    connection_string: "sqlite://{db}?mode=rwc"
+    db_type: sqlite
+sections:
+  - id: users
+    name: Users
+    seeds: ["{yaml}", "{csv}"]
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - {{ id: name, name: Name, field_type: text, required: true }}
+          - {{ id: age, name: Age, field_type: number }}
+"#,
+            db = db.display(),
+            yaml = yaml.display(),
+            csv = csv.display(),
+        ))
+        .unwrap();
+
+        let registry = DataSourceRegistry::new();
+        let source = registry
+            .create(&backoffice.data_sources["db"])
+            .await
+            .unwrap();
+        source
+            .execute_mutation(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age INTEGER)",
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+
+        let reports = seed_backoffice(&backoffice, &registry).await.unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].inserted, 2);
+        // The second YAML record is missing its required name
+        assert_eq!(reports[0].failed.len(), 1);
+        assert_eq!(reports[0].failed[0].record, 2);
+
+        let rows = source
+            .execute_query("SELECT name, age FROM users ORDER BY name", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["name"], serde_json::json!("bob"));
+        assert_eq!(rows[1]["age"], serde_json::json!(40));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
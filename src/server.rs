@@ -1,21 +1,72 @@
-use crate::audit::{AuditLogger, AuditOperation};
-use crate::config::{ActionType, AppConfig, BackofficeConfig};
-use crate::data_source;
+use crate::antivirus::{self, Verdict};
+use crate::audit::{
+    self, AuditFilter, AuditLogger, AuditOperation, AuditSortField, RequestMetadata,
+};
+use crate::auth::AuthContext;
+use crate::cache::QueryCache;
+use crate::card;
+use crate::conditional;
+use crate::config::{
+    ActionConfig, ActionType, AppConfig, BackofficeConfig, CompressionAlgorithm, CompressionConfig,
+    DataSourceConfig, FieldConfig, FieldType, FieldWritePolicy, FileFormat, FormMode, InitMode,
+    LayoutConfig, PipelineStep, SectionConfig, WidgetConfig,
+};
+use crate::config_history::{ConfigHistory, History};
+use crate::crud::{self, CrudOperation};
+use crate::currency;
+use crate::data_source::{self, DataSourceFactory, DataSourceRegistry};
+use crate::duplicate;
+use crate::feature_flags::FeatureFlags;
+use crate::form_state;
+use crate::health::{DataSourceHealth, HealthState};
+use crate::hooks::{self, HookContext, HookOutcome, Hooks};
+use crate::i18n;
+use crate::imports;
+use crate::integrity::{self, IntegrityFix};
+use crate::masking;
+use crate::migrations;
+use crate::permissions;
+use crate::phone;
+use crate::pipeline;
+use crate::plugins::{self, MutationHookResult};
+use crate::push::{EventHub, PushEvent};
 use crate::relationships;
+use crate::reports;
+use crate::row_filters;
+use crate::scaffold;
+use crate::search;
+use crate::seeds;
+use crate::signature;
+use crate::state_machine;
+use crate::summary;
 use crate::validation;
 use anyhow::Result;
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::{Html, IntoResponse, Json},
-    routing::get,
+    extract::{DefaultBodyLimit, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Json, Response,
+    },
+    routing::{delete, get, patch, post, MethodRouter, Route},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::{Layer, Service};
+use tower_http::compression::predicate::{
+    DefaultPredicate, NotForContentType, Predicate, SizeAbove,
+};
+use tower_http::compression::CompressionLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::services::ServeDir;
+use tower_http::timeout::TimeoutLayer;
 use tracing::{debug, error, info, warn};
 
 /// Application state
@@ -24,48 +75,639 @@ pub struct AppState {
     pub config: AppConfig,
     pub backoffices: Vec<BackofficeConfig>,
     pub audit_logger: Arc<AuditLogger>,
+    /// Lifecycle hooks registered by library users
+    pub hooks: Vec<Arc<dyn Hooks>>,
+    /// Creates data sources, including registered custom ones
+    pub data_sources: Arc<DataSourceRegistry>,
+    /// Cached results of actions with a `cache` block
+    pub query_cache: Arc<QueryCache>,
+    /// Events for push WebSocket subscribers
+    pub events: EventHub,
+    /// Flags gating sections and actions
+    pub feature_flags: Arc<FeatureFlags>,
+    /// Validation functions of each backoffice, compiled once by id
+    pub validation_functions: HashMap<String, Arc<validation::ValidationFunctions>>,
+    /// GraphQL schemas of the backoffices, built on first use
+    #[cfg(feature = "graphql")]
+    pub graphql_schemas: Arc<crate::graphql::Schemas>,
 }
 
-/// Start the web server
-pub async fn start_server(config: AppConfig, backoffices: Vec<BackofficeConfig>) -> Result<()> {
-    let backoffice_count = backoffices.len();
+impl AppState {
+    /// Find a backoffice by id
+    pub fn backoffice(&self, backoffice_id: &str) -> Option<&BackofficeConfig> {
+        self.backoffices.iter().find(|b| b.id == backoffice_id)
+    }
+
+    /// Create a data source declared by a backoffice
+    pub async fn data_source(
+        &self,
+        backoffice_id: &str,
+        name: &str,
+    ) -> Result<Box<dyn data_source::DataSource>> {
+        let backoffice = self
+            .backoffice(backoffice_id)
+            .ok_or_else(|| anyhow::anyhow!("Backoffice not found: {}", backoffice_id))?;
+        let config = backoffice
+            .data_sources
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Data source not found: {}", name))?;
+
+        self.data_sources.create(config).await
+    }
+
+    /// Compiled validation functions of a backoffice
+    ///
+    /// A backoffice whose functions failed to compile gets none, so its
+    /// `custom_function` rules fail closed.
+    pub fn validation_functions(
+        &self,
+        backoffice_id: &str,
+    ) -> Arc<validation::ValidationFunctions> {
+        self.validation_functions
+            .get(backoffice_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Compile the validation functions of every backoffice
+fn compile_validation_functions(
+    backoffices: &[BackofficeConfig],
+) -> HashMap<String, Arc<validation::ValidationFunctions>> {
+    backoffices
+        .iter()
+        .filter_map(
+            |backoffice| match validation::ValidationFunctions::from_backoffice(backoffice) {
+                Ok(functions) => Some((backoffice.id.clone(), Arc::new(functions))),
+                Err(e) => {
+                    error!(backoffice = %backoffice.id, error = %e, "Failed to compile validation functions");
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+type RouterCustomizer = Box<dyn FnOnce(Router<Arc<AppState>>) -> Router<Arc<AppState>> + Send>;
+
+/// Builder for embedding the backoffice routes in another axum application
+pub struct RouterBuilder {
+    config: AppConfig,
+    backoffices: Vec<BackofficeConfig>,
+    audit_logger: Option<Arc<AuditLogger>>,
+    base_path: Option<String>,
+    static_dir: String,
+    hooks: Vec<Arc<dyn Hooks>>,
+    data_sources: DataSourceRegistry,
+    customizers: Vec<RouterCustomizer>,
+}
+
+impl RouterBuilder {
+    pub fn new(config: AppConfig, backoffices: Vec<BackofficeConfig>) -> Self {
+        Self {
+            config,
+            backoffices,
+            audit_logger: None,
+            base_path: None,
+            static_dir: "static".to_string(),
+            hooks: Vec::new(),
+            data_sources: DataSourceRegistry::new(),
+            customizers: Vec::new(),
+        }
+    }
+
+    /// Use a custom audit logger instead of the configured `audit_storage`
+    pub fn audit_logger(mut self, audit_logger: Arc<AuditLogger>) -> Self {
+        self.audit_logger = Some(audit_logger);
+        self
+    }
+
+    /// Mount every route under a prefix, e.g. `/admin`
+    ///
+    /// The bundled UI calls `/api/...` with absolute paths, so only the API is
+    /// usable when a base path is set.
+    pub fn base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.base_path = Some(base_path.into());
+        self
+    }
+
+    /// Directory served under `/static`
+    pub fn static_dir(mut self, static_dir: impl Into<String>) -> Self {
+        self.static_dir = static_dir.into();
+        self
+    }
+
+    /// Register lifecycle hooks run by the generated handlers
+    pub fn hooks(mut self, hooks: impl Hooks + 'static) -> Self {
+        self.hooks.push(Arc::new(hooks));
+        self
+    }
+
+    /// Register a factory for `type: custom` data sources
+    pub fn data_source_factory(
+        mut self,
+        name: impl Into<String>,
+        factory: impl DataSourceFactory + 'static,
+    ) -> Self {
+        self.data_sources.register(name, factory);
+        self
+    }
+
+    /// Register an extra route whose handlers can extract `State<Arc<AppState>>`
+    pub fn route(self, path: &str, method_router: MethodRouter<Arc<AppState>>) -> Self {
+        let path = path.to_string();
+        self.map_router(move |router| router.route(&path, method_router))
+    }
+
+    /// Merge a router of extra routes sharing the backoffice state
+    pub fn merge(self, other: Router<Arc<AppState>>) -> Self {
+        self.map_router(move |router| router.merge(other))
+    }
+
+    /// Wrap the backoffice routes in a middleware layer
+    pub fn layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        self.map_router(move |router| router.layer(layer))
+    }
+
+    /// Apply an arbitrary transformation to the router before state is attached
+    pub fn map_router<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(Router<Arc<AppState>>) -> Router<Arc<AppState>> + Send + 'static,
+    {
+        self.customizers.push(Box::new(f));
+        self
+    }
+
+    /// Build the shared application state
+    pub(crate) fn state(&mut self) -> Arc<AppState> {
+        self.data_sources.set_http_timeout(Duration::from_secs(
+            self.config.server.limits.data_source_timeout_secs,
+        ));
+        self.data_sources
+            .set_circuit_breaker(self.config.server.circuit_breaker.clone());
+        let data_sources = Arc::new(std::mem::take(&mut self.data_sources));
+        let audit_logger = self.audit_logger.take().unwrap_or_else(|| {
+            Arc::new(
+                AuditLogger::from_config(self.config.audit_storage.as_ref(), data_sources.clone())
+                    .with_max_file_bytes(self.config.audit.max_file_bytes),
+            )
+        });
+
+        let backoffices = std::mem::take(&mut self.backoffices);
+        Arc::new(AppState {
+            config: self.config.clone(),
+            validation_functions: compile_validation_functions(&backoffices),
+            backoffices,
+            audit_logger,
+            hooks: std::mem::take(&mut self.hooks),
+            data_sources,
+            query_cache: Arc::new(QueryCache::new()),
+            events: EventHub::default(),
+            feature_flags: Arc::new(FeatureFlags::new(&self.config.feature_flags)),
+            #[cfg(feature = "graphql")]
+            graphql_schemas: Default::default(),
+        })
+    }
+
+    pub fn build(mut self) -> Router {
+        debug!(
+            backoffices = self.backoffices.len(),
+            "Creating application state"
+        );
+        let state = self.state();
+        if tokio::runtime::Handle::try_current().is_ok() {
+            tokio::spawn(init_eager_data_sources(state.clone()));
+        }
+        if let Some(secs) = self
+            .config
+            .server
+            .health_checks
+            .interval_secs
+            .filter(|secs| *secs > 0)
+        {
+            if tokio::runtime::Handle::try_current().is_ok() {
+                spawn_health_checks(Arc::downgrade(&state), Duration::from_secs(secs));
+            }
+        }
+        if state.feature_flags.polls_unleash() && tokio::runtime::Handle::try_current().is_ok() {
+            let secs = self
+                .config
+                .feature_flags
+                .unleash
+                .as_ref()
+                .map_or(0, |unleash| unleash.refresh_interval_secs);
+            spawn_flag_refresh(Arc::downgrade(&state), Duration::from_secs(secs.max(1)));
+        }
+        for backoffice in &state.backoffices {
+            let flags = backoffice.sections.iter().flat_map(|section| {
+                std::iter::once(&section.enabled_when)
+                    .chain(section.actions.iter().map(|action| &action.enabled_when))
+            });
+            for flag in flags.flatten() {
+                if !state.feature_flags.is_declared(flag) {
+                    warn!(backoffice = %backoffice.id, flag = %flag, "Undeclared feature flag, its items are disabled");
+                }
+            }
+        }
+        let audit = &self.config.audit;
+        if let Some(secs) = audit.interval_secs.filter(|secs| *secs > 0) {
+            if (audit.retention_days.is_some() || audit.compress)
+                && tokio::runtime::Handle::try_current().is_ok()
+            {
+                spawn_audit_maintenance(Arc::downgrade(&state), Duration::from_secs(secs));
+            }
+        }
+        #[cfg(feature = "grpc")]
+        if let Some(grpc) = &self.config.grpc {
+            if tokio::runtime::Handle::try_current().is_ok() {
+                let host = grpc.host.as_deref().unwrap_or(&self.config.server.host);
+                let addr = format!("{}:{}", host, grpc.port);
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = crate::grpc::serve(state, addr).await {
+                        error!(error = %e, "gRPC API stopped");
+                    }
+                });
+            }
+        }
+
+        debug!("Setting up API routes");
+        let mut router = Router::new()
+            .route("/", get(index_handler))
+            .route("/api/config", get(config_handler))
+            .route("/api/errors", get(crate::problem::catalog_handler))
+            .route("/api/backoffices", get(backoffices_handler))
+            .route("/api/backoffices/:id", get(backoffice_handler))
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id",
+                get(execute_action_handler)
+                    .post(execute_mutation_handler)
+                    .delete(execute_delete_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id/stream",
+                get(stream_action_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id/cache",
+                delete(invalidate_cache_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id/bulk-update",
+                post(bulk_update_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id/evaluate",
+                post(evaluate_form_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id/validate",
+                post(validate_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id/validate-batch",
+                post(validate_batch_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id/import",
+                post(import_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id/fields/:field_id/upload",
+                post(upload_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/tree/:relationship_id",
+                get(tree_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/records/:id/relationships/:relationship_id",
+                get(related_records_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/records/:id/history",
+                get(record_history_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/records/:id/restore",
+                post(restore_record_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/records/:id/fields/:field_id",
+                patch(patch_field_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/records/:id/duplicate",
+                post(duplicate_record_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/sections/:section_id/records/:id/transitions",
+                get(record_transitions_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/data-sources/:data_source_id/scaffold",
+                get(scaffold_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/data-sources/:data_source_id/presign",
+                post(presign_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/data-sources/:data_source_id/migrations",
+                get(migrations_status_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/data-sources/status",
+                get(data_sources_status_handler),
+            )
+            .route("/api/backoffices/:backoffice_id/dashboard", get(dashboard_handler))
+            .route("/api/backoffices/:backoffice_id/seed", post(seed_handler))
+            .route("/api/backoffices/:backoffice_id/audit", get(audit_search_handler))
+            .route(
+                "/api/admin/backoffices/:backoffice_id/versions",
+                get(config_versions_handler),
+            )
+            .route(
+                "/api/admin/backoffices/:backoffice_id/rollback",
+                post(config_rollback_handler),
+            )
+            .route(
+                "/api/backoffices/:backoffice_id/integrity",
+                get(integrity_handler).post(integrity_fix_handler),
+            )
+            .route("/api/docs", get(api_docs_handler))
+            .route("/openapi.yaml", get(openapi_spec_handler))
+            .nest_service("/static", ServeDir::new(&self.static_dir));
+        #[cfg(feature = "websocket-push")]
+        {
+            router = router.route("/api/ws", get(crate::push::ws_handler));
+        }
+        #[cfg(feature = "graphql")]
+        {
+            router = router.route(
+                "/api/graphql/:backoffice_id",
+                get(crate::graphql::graphiql_handler).post(crate::graphql::graphql_handler),
+            );
+        }
+
+        for customize in self.customizers {
+            router = customize(router);
+        }
 
-    debug!(backoffices = backoffice_count, "Creating application state");
+        let mut router = router
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::feature_flags::gate,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::json_api::documents,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::versioning::envelope,
+            ))
+            .with_state(state);
+        let limits = &self.config.server.limits;
+        router = router.layer(DefaultBodyLimit::max(limits.max_body_bytes));
+        if let Some(max) = limits.max_concurrent_requests {
+            router = router.layer(GlobalConcurrencyLimitLayer::new(max));
+        }
+        if let Some(secs) = limits.request_timeout_secs {
+            router = router.layer(TimeoutLayer::new(Duration::from_secs(secs)));
+        }
+        // Errors of every handler and layer above, e.g. timeouts, become
+        // problem documents
+        router = router.layer(axum::middleware::from_fn(crate::problem::problems));
+        if self.config.server.compression.enabled {
+            router = router.layer(compression_layer(&self.config.server.compression));
+        }
+        // Every request gets an `X-Request-Id`, echoed in the response, that
+        // its logs, errors and audit entries carry
+        router = router
+            .layer(axum::middleware::from_fn(crate::request_id::propagate))
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid));
+        let access_log = &self.config.server.access_log;
+        if access_log.enabled {
+            let access_log = crate::access_log::AccessLog::new(
+                access_log,
+                self.config.security.clone(),
+                self.config.server.trusted_proxies,
+            );
+            router = router.layer(axum::middleware::from_fn_with_state(
+                Arc::new(access_log),
+                crate::access_log::log,
+            ));
+        }
+        let router = crate::versioning::versioned(router);
+
+        match self
+            .base_path
+            .as_deref()
+            .map(|p| p.trim_end_matches('/'))
+            .filter(|p| !p.is_empty())
+        {
+            Some(base_path) => Router::new().nest(base_path, router),
+            None => router,
+        }
+    }
+}
+
+/// Construct the data sources marked `init: eager`
+///
+/// A source that fails leaves its backoffice degraded; it is retried by the
+/// health checks and on first use.
+async fn init_eager_data_sources(state: Arc<AppState>) {
+    for backoffice in &state.backoffices {
+        for (name, config) in &backoffice.data_sources {
+            if config.init() != InitMode::Eager {
+                continue;
+            }
+            match state.data_sources.create(config).await {
+                Ok(_) => info!(
+                    backoffice = %backoffice.id,
+                    data_source = %name,
+                    "Initialized data source"
+                ),
+                Err(e) => warn!(
+                    backoffice = %backoffice.id,
+                    data_source = %name,
+                    error = %e,
+                    "Eager data source failed to initialize, backoffice degraded"
+                ),
+            }
+        }
+    }
+}
+
+/// Fetch the Unleash feature flags each `interval` while the state lives
+fn spawn_flag_refresh(state: Weak<AppState>, interval: Duration) {
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            if let Err(e) = state.feature_flags.refresh(&client).await {
+                warn!(error = %e, "Failed to refresh feature flags from Unleash");
+            }
+        }
+    });
+}
+
+/// Probe every configured data source each `interval` while the state lives
+fn spawn_health_checks(state: Weak<AppState>, interval: Duration) {
+    tokio::spawn(async move {
+        let start = tokio::time::Instant::now() + interval;
+        let mut ticker = tokio::time::interval_at(start, interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+
+            // Backoffices sharing a data source configuration share its health
+            let mut seen = HashSet::new();
+            let checks = state
+                .backoffices
+                .iter()
+                .flat_map(|b| b.data_sources.values())
+                .filter(|config| seen.insert(data_source::config_key(config)))
+                .map(|config| state.data_sources.check_health(config));
+            futures_util::future::join_all(checks).await;
+            debug!("Data source health checks finished");
 
-    let audit_logger = Arc::new(AuditLogger::new("logs/audit"));
+            let unhealthy: Vec<String> = state
+                .backoffices
+                .iter()
+                .flat_map(|b| {
+                    b.data_sources
+                        .iter()
+                        .map(move |(name, config)| (b, name, config))
+                })
+                .filter(|(_, _, config)| {
+                    state.data_sources.health(config).state == HealthState::Unhealthy
+                })
+                .map(|(backoffice, name, _)| format!("{}/{}", backoffice.id, name))
+                .collect();
+            let error = (!unhealthy.is_empty())
+                .then(|| format!("Unhealthy data sources: {}", unhealthy.join(", ")));
+            state
+                .events
+                .publish(PushEvent::task("health_checks", error));
+        }
+    });
+}
 
-    let state = Arc::new(AppState {
-        config: config.clone(),
-        backoffices,
-        audit_logger,
+/// Apply audit retention and compression now and each `interval` while the
+/// state lives
+fn spawn_audit_maintenance(state: Weak<AppState>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            let outcome = state
+                .audit_logger
+                .run_maintenance(&state.config.audit)
+                .await;
+            if let Err(e) = &outcome {
+                warn!(error = %e, "Audit log maintenance failed");
+            }
+            state.events.publish(PushEvent::task(
+                "audit_maintenance",
+                outcome.err().map(|e| e.to_string()),
+            ));
+        }
     });
+}
+
+/// Compression for responses that benefit from it
+///
+/// Besides the default exclusions (images, event streams, gRPC), archives and
+/// binary downloads are already compressed and sent as is.
+fn compression_layer(config: &CompressionConfig) -> CompressionLayer<impl Predicate> {
+    let predicate = DefaultPredicate::new()
+        .and(SizeAbove::new(config.min_size))
+        .and(NotForContentType::const_new("application/zip"))
+        .and(NotForContentType::const_new("application/gzip"))
+        .and(NotForContentType::const_new("application/x-gzip"))
+        .and(NotForContentType::const_new("application/zstd"))
+        .and(NotForContentType::const_new("application/pdf"))
+        .and(NotForContentType::const_new("application/octet-stream"))
+        .and(NotForContentType::const_new(
+            "application/vnd.openxmlformats",
+        ))
+        .and(NotForContentType::const_new("video/"))
+        .and(NotForContentType::const_new("audio/"));
+
+    CompressionLayer::new()
+        .gzip(config.algorithms.contains(&CompressionAlgorithm::Gzip))
+        .br(config.algorithms.contains(&CompressionAlgorithm::Br))
+        .zstd(config.algorithms.contains(&CompressionAlgorithm::Zstd))
+        .compress_when(predicate)
+}
+
+/// Build the backoffice router with default settings
+pub fn build_router(config: AppConfig, backoffices: Vec<BackofficeConfig>) -> Router {
+    RouterBuilder::new(config, backoffices).build()
+}
 
-    // Build the router
-    debug!("Setting up API routes");
-    let app = Router::new()
-        .route("/", get(index_handler))
-        .route("/api/config", get(config_handler))
-        .route("/api/backoffices", get(backoffices_handler))
-        .route("/api/backoffices/:id", get(backoffice_handler))
-        .route(
-            "/api/backoffices/:backoffice_id/sections/:section_id/actions/:action_id",
-            get(execute_action_handler)
-                .post(execute_mutation_handler)
-                .delete(execute_delete_handler),
-        )
-        .route("/api/docs", get(api_docs_handler))
-        .route("/openapi.yaml", get(openapi_spec_handler))
-        .nest_service("/static", ServeDir::new("static"))
-        .with_state(state);
+/// Start the web server
+pub async fn start_server(config: AppConfig, backoffices: Vec<BackofficeConfig>) -> Result<()> {
+    let backoffice_count = backoffices.len();
+    let app = build_router(config.clone(), backoffices);
 
     info!("Routes configured:");
     info!("  GET  /                     - Main application page");
     info!("  GET  /api/config           - Application configuration");
+    info!("  GET  /api/errors           - Error-code catalog of problem responses");
     info!("  GET  /api/backoffices      - List all backoffices");
     info!("  GET  /api/backoffices/:id  - Get backoffice by ID");
+    info!("  GET  .../actions/:id/stream - Live action rows (server-sent events)");
+    info!("  GET  .../data-sources/:id/scaffold - Sections generated from a GraphQL schema");
+    info!("  POST .../data-sources/:id/presign - Presigned object storage URLs");
+    info!("  POST .../actions/:id/evaluate - Conditional form state for a partial payload");
+    info!("  POST .../actions/:id/validate - Validate a payload without saving it");
+    info!("  POST .../actions/:id/validate-batch - Validate many payloads, e.g. before an import");
+    info!("  POST .../actions/:id/fields/:id/upload - Virus-scanned file uploads");
+    info!("  GET  .../data-sources/:id/migrations - Applied database migrations");
+    info!("  GET  .../data-sources/status - Data source health");
+    info!("  POST /api/backoffices/:id/seed - Insert section seed fixtures");
+    info!("  GET  /api/backoffices/:id/integrity - Orphaned rows and dangling junction entries");
+    info!("  GET  /api/backoffices/:id/audit - Search audit entries");
+    info!("  GET  /api/admin/backoffices/:id/versions - Config version history");
+    info!("  POST /api/admin/backoffices/:id/rollback - Restore a config version");
+    info!("  GET  /api/ws               - Real-time events (WebSocket)");
+    info!("  POST /api/graphql/:id      - GraphQL API (GraphiQL on GET)");
+    info!("  *    /api/v1/*, /api/v2/*  - Versioned routes (response envelopes)");
+    info!(
+        "  *    .../actions/:id       - JSON:API documents with Accept: application/vnd.api+json"
+    );
     info!("  GET  /api/docs             - API documentation (Swagger UI)");
     info!("  GET  /openapi.yaml         - OpenAPI specification");
     info!("  *    /static/*             - Static files");
+    if let Some(grpc) = &config.grpc {
+        info!(
+            "  gRPC :{}                - BackofficeService (proto/backoffice.proto)",
+            grpc.port
+        );
+    }
 
     let addr = format!("{}:{}", config.server.host, config.server.port);
     debug!(address = %addr, "Binding TCP listener");
@@ -93,7 +735,12 @@ pub async fn start_server(config: AppConfig, backoffices: Vec<BackofficeConfig>)
 
     info!("Server is now accepting connections...");
 
-    match axum::serve(listener, app).await {
+    match axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    {
         Ok(_) => {
             info!("Server stopped");
             Ok(())
@@ -115,7 +762,7 @@ async fn openapi_spec_handler() -> impl IntoResponse {
     (
         StatusCode::OK,
         [("content-type", "application/x-yaml")],
-        include_str!("../openapi.yaml"),
+        crate::openapi::SPEC,
     )
 }
 
@@ -165,7 +812,12 @@ async fn config_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse
 
 /// Get all backoffices
 async fn backoffices_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    Json(state.backoffices.clone())
+    let backoffices: Vec<BackofficeConfig> = state
+        .backoffices
+        .iter()
+        .map(|backoffice| state.feature_flags.backoffice(backoffice))
+        .collect();
+    Json(backoffices)
 }
 
 /// Get a specific backoffice
@@ -174,7 +826,11 @@ async fn backoffice_handler(
     Path(id): Path<String>,
 ) -> impl IntoResponse {
     match state.backoffices.iter().find(|b| b.id == id) {
-        Some(backoffice) => (StatusCode::OK, Json(backoffice)).into_response(),
+        Some(backoffice) => (
+            StatusCode::OK,
+            Json(state.feature_flags.backoffice(backoffice)),
+        )
+            .into_response(),
         None => (StatusCode::NOT_FOUND, "Backoffice not found").into_response(),
     }
 }
@@ -187,14 +843,51 @@ struct ActionQuery {
     sort_by: Option<String>,
     #[allow(dead_code)]
     sort_order: Option<String>,
+    /// Comma-separated relationships (or relationship fields) to embed
+    expand: Option<String>,
+    /// Locales preferred for messages, `Accept-Language` by default
+    locale: Option<String>,
     #[serde(flatten)]
     params: HashMap<String, String>,
 }
 
 /// Execute a query action (GET)
+///
+/// Responses carry an `ETag` (and `Last-Modified` when the section has an
+/// `updated_at_field`) and honor conditional requests with `304`.
 async fn execute_action_handler(
     State(state): State<Arc<AppState>>,
     Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    Query(mut query): Query<ActionQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if query.locale.is_none() {
+        query.locale = headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+    }
+    let updated_at_field = state
+        .backoffice(&backoffice_id)
+        .and_then(|b| b.sections.iter().find(|s| s.id == section_id))
+        .and_then(|s| s.updated_at_field.clone());
+    let response = query_action(
+        State(state),
+        Path((backoffice_id, section_id, action_id)),
+        auth,
+        Query(query),
+    )
+    .await
+    .into_response();
+
+    conditional::respond(&headers, response, updated_at_field.as_deref()).await
+}
+
+async fn query_action(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
+    auth: AuthContext,
     Query(query): Query<ActionQuery>,
 ) -> impl IntoResponse {
     use crate::config::ActionType;
@@ -234,6 +927,13 @@ async fn execute_action_handler(
                 .into_response()
         }
     };
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response();
+    }
 
     // Get the data source
     let ds_config = match backoffice.data_sources.get(&action.data_source) {
@@ -247,394 +947,1040 @@ async fn execute_action_handler(
         }
     };
 
-    // Create data source instance
-    let data_source = match data_source::create_data_source(ds_config).await {
-        Ok(ds) => ds,
+    // Resolve the relationships to embed
+    let expansions = match query
+        .expand
+        .as_deref()
+        .map(|expand| relationships::resolve_expansions(expand, section, backoffice))
+        .transpose()
+    {
+        Ok(expansions) => expansions.unwrap_or_default(),
         Err(e) => {
             return (
-                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_REQUEST,
                 Json(serde_json::json!({"error": e.to_string()})),
             )
                 .into_response()
         }
     };
+    if let Some(expansion) = expansions
+        .iter()
+        .find(|e| !auth.has_all_scopes(&e.target_action.required_scopes))
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": format!("Insufficient scopes to expand '{}'", expansion.key)
+            })),
+        )
+            .into_response();
+    }
+
+    // Resolve row-level security for the caller
+    let row_conditions = match row_filters::resolve(&section.row_filters, &auth) {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            warn!(error = %e, "Row filters could not be resolved");
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    };
 
-    // Execute the query
-    let query_str = action
-        .query
-        .as_deref()
-        .or(action.endpoint.as_deref())
-        .unwrap_or("");
-    let params_converted: HashMap<String, Value> = query
+    // Create data source instance
+    let data_source = match state.data_sources.create(ds_config).await {
+        Ok(ds) => ds,
+        Err(e) => return data_source_error_response(&e),
+    };
+
+    let mut params_converted: HashMap<String, Value> = query
         .params
         .iter()
         .map(|(k, v)| (k.clone(), Value::String(v.clone())))
         .collect();
 
-    match &action.action_type {
-        ActionType::List { fields, config } => {
-            match data_source
-                .execute_query(query_str, Some(&params_converted))
+    // Reports only see their declared parameters, validated like form data
+    let mut export_format = None;
+    if let ActionType::Report {
+        parameters, config, ..
+    } = &action.action_type
+    {
+        export_format = match reports::export_format(&query.params, &config.export_formats) {
+            Ok(format) => format,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                )
+                    .into_response()
+            }
+        };
+        let validation_errors = match reports::parameter_values(&query.params, parameters) {
+            Ok(values) => {
+                params_converted = values;
+                let functions = state.validation_functions(&backoffice.id);
+                match validation::validate_data_with_functions(
+                    &params_converted,
+                    parameters,
+                    &functions,
+                )
                 .await
-            {
-                Ok(mut result) => {
-                    let total_items = result.len();
-
-                    // Handle pagination if enabled
-                    if config.enable_pagination {
-                        let page = query.page.unwrap_or(1);
-                        let page_size = query.page_size.unwrap_or(config.page_size);
-                        let start = (page - 1) * page_size;
-
-                        result = result.into_iter().skip(start).take(page_size).collect();
-
-                        (
-                            StatusCode::OK,
-                            Json(serde_json::json!({
-                                "data": result,
-                                "fields": fields,
-                                "config": config,
-                                "pagination": {
-                                    "page": page,
-                                    "page_size": page_size,
-                                    "total_items": total_items,
-                                    "total_pages": total_items.div_ceil(page_size),
-                                }
-                            })),
-                        )
-                            .into_response()
-                    } else {
-                        (
-                            StatusCode::OK,
-                            Json(serde_json::json!({
-                                "data": result,
-                                "fields": fields,
-                                "config": config,
-                            })),
+                {
+                    Ok(errors) => errors,
+                    Err(e) => {
+                        error!(error = %e, "Validation error");
+                        return (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(serde_json::json!({"error": format!("Validation error: {}", e)})),
                         )
-                            .into_response()
+                            .into_response();
                     }
                 }
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": e.to_string()})),
-                )
-                    .into_response(),
             }
+            Err(errors) => errors,
+        };
+        if !validation_errors.is_empty() {
+            warn!(
+                error_count = validation_errors.len(),
+                "Report parameter validation failed"
+            );
+            let error_messages =
+                localized_errors(&state, validation_errors, query.locale.as_deref());
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "Validation failed",
+                    "validation_errors": error_messages
+                })),
+            )
+                .into_response();
         }
-        ActionType::View { fields } | ActionType::Custom { fields } => {
-            match data_source
-                .execute_query(query_str, Some(&params_converted))
-                .await
-            {
-                Ok(result) => (
-                    StatusCode::OK,
-                    Json(serde_json::json!({"data": result, "fields": fields})),
+    }
+
+    let hook_ctx = HookContext {
+        backoffice,
+        section,
+        action,
+        auth: &auth,
+    };
+    if !matches!(
+        action.action_type,
+        ActionType::Form { .. } | ActionType::Pipeline { .. }
+    ) {
+        match hooks::before_query(&state.hooks, &hook_ctx, &mut params_converted).await {
+            Ok(HookOutcome::Continue) => {}
+            Ok(HookOutcome::Veto(message)) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": message})),
                 )
-                    .into_response(),
-                Err(e) => (
+                    .into_response();
+            }
+            Err(e) => {
+                error!(error = %e, "before_query hook failed");
+                return (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": e.to_string()})),
+                    Json(serde_json::json!({"error": format!("Hook error: {}", e)})),
                 )
-                    .into_response(),
+                    .into_response();
             }
         }
-        ActionType::Form { fields, config } => {
-            // For form actions in GET, return the form configuration
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "fields": fields,
-                    "config": config,
-                })),
-            )
-                .into_response()
-        }
     }
-}
 
-#[derive(Debug, Deserialize, Serialize)]
-struct MutationData {
-    #[serde(flatten)]
-    data: HashMap<String, Value>,
-}
-
-/// Execute a mutation action (POST)
-async fn execute_mutation_handler(
-    State(state): State<Arc<AppState>>,
-    Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
-    Json(payload): Json<MutationData>,
-) -> impl IntoResponse {
-    info!(
-        backoffice_id = %backoffice_id,
-        section_id = %section_id,
-        action_id = %action_id,
-        "Processing mutation request"
-    );
-
-    // Find the backoffice
-    let backoffice = match state.backoffices.iter().find(|b| b.id == backoffice_id) {
-        Some(b) => b,
-        None => {
+    // Execute the query
+    let target = match crud::action_target(
+        section,
+        action,
+        ds_config,
+        CrudOperation::for_action(action, None),
+        &params_converted,
+    ) {
+        Ok(target) => target,
+        Err(e) => {
             return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({"error": "Backoffice not found"})),
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e.to_string()})),
             )
                 .into_response()
         }
     };
-
-    // Find the section
-    let section = match backoffice.sections.iter().find(|s| s.id == section_id) {
-        Some(s) => s,
-        None => {
+    if matches!(action.action_type, ActionType::Report { .. }) {
+        if let Err(e) = reports::ensure_read_only(&target, ds_config) {
             return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({"error": "Section not found"})),
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e.to_string()})),
             )
-                .into_response()
+                .into_response();
         }
-    };
+    }
+    let query_str =
+        row_filters::scope_query(&target, &row_conditions, ds_config, &mut params_converted);
 
-    // Find the action
-    let action = match section.actions.iter().find(|a| a.id == action_id) {
-        Some(a) => a,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({"error": "Action not found"})),
-            )
-                .into_response()
+    // Global search, ranked by the section's full-text backend when it has
+    // one and otherwise matching the list's searchable fields
+    let (search_term, search_fields) = match &action.action_type {
+        ActionType::List { config, .. } => (
+            query
+                .params
+                .get(search::SEARCH_PARAM)
+                .map(|q| q.trim())
+                .filter(|q| !q.is_empty()),
+            search::search_fields(section, config),
+        ),
+        _ => (None, &[][..]),
+    };
+    let hits = match (search_term, &section.search) {
+        (Some(q), Some(full_text)) => match search::full_text_hits(
+            full_text,
+            search_fields,
+            backoffice,
+            section,
+            &action.data_source,
+            &state.data_sources,
+            q,
+        )
+        .await
+        {
+            Ok(hits) => Some(hits),
+            Err(e) => {
+                warn!(error = %e, section = %section.id, "Full-text search failed, falling back to LIKE search");
+                None
+            }
+        },
+        _ => None,
+    };
+    let search = search_term
+        .filter(|_| hits.is_none() && !search_fields.is_empty())
+        .map(|q| (search_fields, q));
+    let query_str = match (&hits, search) {
+        (Some(hits), _) => search::hits_query(&query_str, hits, ds_config, &mut params_converted),
+        (None, Some((fields, q))) => {
+            match search::search_query(&query_str, fields, q, ds_config, &mut params_converted) {
+                Ok(query) => query,
+                Err(e) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response()
+                }
+            }
         }
+        (None, None) => query_str,
     };
 
-    // Get fields from the action for validation
-    let fields = match &action.action_type {
-        ActionType::Form { fields, .. } => fields,
-        ActionType::Custom { fields } => fields,
-        _ => {
-            warn!("Mutation attempted on non-form action");
-            &vec![]
+    // Summaries are computed by the data source when it can
+    let summarized = match &action.action_type {
+        ActionType::Summary { config, .. }
+            if summary::pushes_down(ds_config, !row_conditions.is_empty()) =>
+        {
+            match summary::summary_query(&query_str, config, ds_config) {
+                Ok(query) => Some(query),
+                Err(e) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response()
+                }
+            }
         }
+        _ => None,
+    };
+    let pushed_down = summarized.is_some();
+    let query_str = summarized.unwrap_or(query_str);
+    let cached_query = || {
+        state.query_cache.fetch(
+            backoffice,
+            &section_id,
+            action,
+            &query_str,
+            &params_converted,
+            || data_source.execute_query(&query_str, Some(&params_converted)),
+        )
     };
 
-    // Step 1: Validate data against field configurations
-    info!("Validating request data");
-    match validation::validate_data(&payload.data, fields) {
-        Ok(validation_errors) => {
-            if !validation_errors.is_empty() {
-                let error_messages: Vec<serde_json::Value> = validation_errors
-                    .iter()
-                    .map(|e| {
-                        serde_json::json!({
-                            "field": e.field,
-                            "message": e.message
-                        })
-                    })
-                    .collect();
+    match &action.action_type {
+        ActionType::List { fields, config } => {
+            let pagination = config.enable_pagination.then(|| {
+                data_source::PaginationParams::new(
+                    query.page.unwrap_or(1).max(1),
+                    query
+                        .page_size
+                        .unwrap_or(config.page_size)
+                        .clamp(1, data_source::MAX_PAGE_SIZE),
+                )
+            });
+            // The data source fetches only the page unless the rows are
+            // still filtered or ranked here, which needs all of them
+            let memory_search = search.filter(|_| !search::pushes_down(ds_config));
+            let source_page = pagination.as_ref().filter(|_| {
+                hits.is_none()
+                    && memory_search.is_none()
+                    && (row_conditions.is_empty()
+                        || matches!(ds_config, DataSourceConfig::Database { .. }))
+            });
+            let fetched = match source_page {
+                Some(p) => {
+                    state
+                        .query_cache
+                        .fetch_page(
+                            backoffice,
+                            &section_id,
+                            action,
+                            &query_str,
+                            &params_converted,
+                            p,
+                            || data_source.execute_page(&query_str, Some(&params_converted), p),
+                        )
+                        .await
+                }
+                None => cached_query().await.map(|rows| data_source::Page {
+                    rows,
+                    ..Default::default()
+                }),
+            };
+            match fetched {
+                Ok(page) => {
+                    // Let plugins rewrite or drop rows
+                    let mut result =
+                        match plugins::transform_rows(backoffice, &section_id, page.rows).await {
+                            Ok(rows) => rows,
+                            Err(e) => {
+                                error!(error = %e, "Plugin transform_row hook failed");
+                                return (
+                                    StatusCode::INTERNAL_SERVER_ERROR,
+                                    Json(serde_json::json!({"error": e.to_string()})),
+                                )
+                                    .into_response();
+                            }
+                        };
+                    row_filters::retain_rows(&mut result, &row_conditions);
+                    if let Some(hits) = &hits {
+                        search::rank_rows(&mut result, hits);
+                    } else if let Some((search_fields, q)) = memory_search {
+                        search::retain_rows(&mut result, search_fields, q);
+                    }
+                    if let Err(e) = hooks::after_query(&state.hooks, &hook_ctx, &mut result).await {
+                        error!(error = %e, "after_query hook failed");
+                        return (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(serde_json::json!({"error": format!("Hook error: {}", e)})),
+                        )
+                            .into_response();
+                    }
+                    permissions::strip_unreadable(&mut result, fields, &auth);
+                    masking::mask_rows(&mut result, fields, &auth);
+                    if let Some(hits) = &hits {
+                        // Snippets would reveal sensitive and unreadable values
+                        let hidden: Vec<&str> = fields
+                            .iter()
+                            .filter(|f| f.sensitive || !permissions::can_read(f, &auth))
+                            .map(|f| f.id.as_str())
+                            .collect();
+                        search::attach_highlights(&mut result, hits, &hidden);
+                    }
+                    let fields = permissions::readable_fields(fields, &auth);
 
-                warn!(error_count = validation_errors.len(), "Validation failed");
+                    // Handle pagination if enabled
+                    if let Some(pagination) = &pagination {
+                        let total_items = match source_page {
+                            Some(_) => page.total,
+                            None => {
+                                let total = result.len() as u64;
+                                result = result
+                                    .into_iter()
+                                    .skip(pagination.offset)
+                                    .take(pagination.page_size)
+                                    .collect();
+                                Some(total)
+                            }
+                        };
+                        if let Err(response) =
+                            expand_rows(&state, backoffice, &expansions, &mut result, &auth).await
+                        {
+                            return response;
+                        }
+                        annotate_currencies(&state, backoffice, &fields, &mut result).await;
 
+                        let page_size = pagination.page_size as u64;
+                        let mut pagination = serde_json::json!({
+                            "page": pagination.page,
+                            "page_size": page_size,
+                            "total_items": total_items,
+                            "total_pages": total_items.map(|total| total.div_ceil(page_size)),
+                        });
+                        if let Some(next_cursor) = page.next_cursor {
+                            pagination["next_cursor"] = serde_json::json!(next_cursor);
+                        }
+                        (
+                            StatusCode::OK,
+                            Json(serde_json::json!({
+                                "data": result,
+                                "fields": fields,
+                                "config": config,
+                                "pagination": pagination,
+                            })),
+                        )
+                            .into_response()
+                    } else {
+                        if let Err(response) =
+                            expand_rows(&state, backoffice, &expansions, &mut result, &auth).await
+                        {
+                            return response;
+                        }
+                        annotate_currencies(&state, backoffice, &fields, &mut result).await;
+                        (
+                            StatusCode::OK,
+                            Json(serde_json::json!({
+                                "data": result,
+                                "fields": fields,
+                                "config": config,
+                            })),
+                        )
+                            .into_response()
+                    }
+                }
+                Err(e) => data_source_error_response(&e),
+            }
+        }
+        ActionType::View { fields, .. } | ActionType::Custom { fields } => match cached_query()
+            .await
+        {
+            Ok(result) => match plugins::transform_rows(backoffice, &section_id, result).await {
+                Ok(mut result) => {
+                    row_filters::retain_rows(&mut result, &row_conditions);
+                    if let Err(e) = hooks::after_query(&state.hooks, &hook_ctx, &mut result).await {
+                        error!(error = %e, "after_query hook failed");
+                        return (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(serde_json::json!({"error": format!("Hook error: {}", e)})),
+                        )
+                            .into_response();
+                    }
+                    permissions::strip_unreadable(&mut result, fields, &auth);
+                    masking::mask_rows(&mut result, fields, &auth);
+                    if let Err(response) =
+                        expand_rows(&state, backoffice, &expansions, &mut result, &auth).await
+                    {
+                        return response;
+                    }
+                    let fields = permissions::readable_fields(fields, &auth);
+                    annotate_currencies(&state, backoffice, &fields, &mut result).await;
+                    let mut body = serde_json::json!({"data": result, "fields": fields});
+                    if let Some(layout) = readable_layout(&action.action_type, &fields) {
+                        body["layout"] = serde_json::json!(layout);
+                    }
+                    (StatusCode::OK, Json(body)).into_response()
+                }
+                Err(e) => {
+                    error!(error = %e, "Plugin transform_row hook failed");
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response()
+                }
+            },
+            Err(e) => data_source_error_response(&e),
+        },
+        ActionType::Summary { fields, config } => {
+            let rows = match cached_query().await {
+                Ok(rows) => rows,
+                Err(e) => return data_source_error_response(&e),
+            };
+            let mut result = if pushed_down {
+                summary::summary_rows(rows, config, ds_config)
+            } else {
+                let mut rows = rows;
+                row_filters::retain_rows(&mut rows, &row_conditions);
+                match summary::aggregate_rows(&rows, config) {
+                    Ok(result) => result,
+                    Err(e) => {
+                        return (
+                            StatusCode::BAD_REQUEST,
+                            Json(serde_json::json!({"error": e.to_string()})),
+                        )
+                            .into_response()
+                    }
+                }
+            };
+            if let Err(e) = hooks::after_query(&state.hooks, &hook_ctx, &mut result).await {
+                error!(error = %e, "after_query hook failed");
                 return (
-                    StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({
-                        "error": "Validation failed",
-                        "validation_errors": error_messages
-                    })),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": format!("Hook error: {}", e)})),
                 )
                     .into_response();
             }
-        }
-        Err(e) => {
-            error!(error = %e, "Validation error");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": format!("Validation error: {}", e)})),
+            permissions::strip_unreadable(&mut result, fields, &auth);
+            masking::mask_rows(&mut result, fields, &auth);
+            let chart = summary::chart(&result, config);
+            let fields = permissions::readable_fields(fields, &auth);
+            annotate_currencies(&state, backoffice, &fields, &mut result).await;
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "data": result,
+                    "fields": fields,
+                    "config": config,
+                    "chart": chart,
+                })),
             )
-                .into_response();
+                .into_response()
         }
-    }
-
-    // Step 2: Create data sources map for relationship validation
-    let mut data_sources_map: HashMap<String, Box<dyn data_source::DataSource>> = HashMap::new();
-    for (name, ds_config) in &backoffice.data_sources {
-        match data_source::create_data_source(ds_config).await {
-            Ok(ds) => {
-                data_sources_map.insert(name.clone(), ds);
-            }
-            Err(e) => {
-                error!(
-                    data_source = %name,
-                    error = %e,
-                    "Failed to create data source"
-                );
+        ActionType::Report {
+            fields,
+            parameters,
+            config,
+        } => {
+            let rows = match cached_query().await {
+                Ok(rows) => rows,
+                Err(e) => return data_source_error_response(&e),
+            };
+            let mut result = match plugins::transform_rows(backoffice, &section_id, rows).await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    error!(error = %e, "Plugin transform_row hook failed");
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response();
+                }
+            };
+            row_filters::retain_rows(&mut result, &row_conditions);
+            if let Err(e) = hooks::after_query(&state.hooks, &hook_ctx, &mut result).await {
+                error!(error = %e, "after_query hook failed");
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": format!("Failed to create data source: {}", e)})),
+                    Json(serde_json::json!({"error": format!("Hook error: {}", e)})),
                 )
                     .into_response();
             }
-        }
-    }
-
-    // Step 3: Validate foreign key relationships
-    info!("Validating foreign key relationships");
-    match relationships::validate_foreign_keys(
-        &payload.data,
-        &section_id,
-        backoffice,
-        &data_sources_map,
-    )
-    .await
-    {
-        Ok(relationship_errors) => {
-            if !relationship_errors.is_empty() {
-                let error_messages: Vec<serde_json::Value> = relationship_errors
-                    .iter()
-                    .map(|e| {
-                        serde_json::json!({
-                            "relationship_id": e.relationship_id,
-                            "field": e.field,
-                            "message": e.message
-                        })
-                    })
-                    .collect();
-
-                warn!(
-                    error_count = relationship_errors.len(),
-                    "Relationship validation failed"
-                );
+            permissions::strip_unreadable(&mut result, fields, &auth);
+            masking::mask_rows(&mut result, fields, &auth);
+            if let Some(max_rows) = config.max_rows {
+                result.truncate(max_rows);
+            }
+            let fields = permissions::readable_fields(fields, &auth);
 
+            let Some(format) = export_format else {
                 return (
-                    StatusCode::BAD_REQUEST,
+                    StatusCode::OK,
                     Json(serde_json::json!({
-                        "error": "Relationship validation failed",
-                        "relationship_errors": error_messages
+                        "data": result,
+                        "fields": fields,
+                        "parameters": parameters,
+                        "config": config,
                     })),
                 )
                     .into_response();
+            };
+            match reports::export(&result, &fields, &format) {
+                Ok((content_type, extension, body)) => (
+                    StatusCode::OK,
+                    [
+                        (header::CONTENT_TYPE, content_type.to_string()),
+                        (
+                            header::CONTENT_DISPOSITION,
+                            format!("attachment; filename=\"{}.{}\"", action.id, extension),
+                        ),
+                    ],
+                    body,
+                )
+                    .into_response(),
+                Err(e) => {
+                    error!(error = %e, "Report export failed");
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response()
+                }
             }
         }
+        ActionType::Form { fields, config, .. } => {
+            // For form actions in GET, return the form configuration
+            let fields = permissions::readable_fields(fields, &auth);
+            let mut body = serde_json::json!({
+                "fields": fields,
+                "config": config,
+            });
+            if let Some(layout) = readable_layout(&action.action_type, &fields) {
+                body["layout"] = serde_json::json!(layout);
+            }
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        ActionType::Pipeline { fields, .. } => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "fields": permissions::readable_fields(fields, &auth),
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Validate a pipeline's inputs and run its steps
+#[allow(clippy::too_many_arguments)]
+async fn run_pipeline(
+    state: &AppState,
+    backoffice: &BackofficeConfig,
+    section: &SectionConfig,
+    action: &ActionConfig,
+    fields: &[FieldConfig],
+    steps: &[PipelineStep],
+    auth: &AuthContext,
+    locale: Option<&str>,
+    input: HashMap<String, Value>,
+) -> Response {
+    // Steps may write anywhere, so the action's scopes are always required
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response();
+    }
+
+    let functions = state.validation_functions(&backoffice.id);
+    let validation_errors =
+        validation::validate_data_with_functions(&input, fields, &functions).await;
+    match validation_errors {
+        Ok(errors) if !errors.is_empty() => {
+            warn!(
+                error_count = errors.len(),
+                "Pipeline input validation failed"
+            );
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "Validation failed",
+                    "validation_errors": localized_errors(state, errors, locale)
+                })),
+            )
+                .into_response();
+        }
+        Ok(_) => {}
         Err(e) => {
-            error!(error = %e, "Relationship validation error");
+            error!(error = %e, "Validation error");
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": format!("Relationship validation error: {}", e)})),
+                Json(serde_json::json!({"error": format!("Validation error: {}", e)})),
             )
                 .into_response();
         }
     }
 
-    // Step 4: Validate many-to-many relationships
-    match relationships::validate_many_to_many(
-        &payload.data,
-        &section_id,
+    let outcome = pipeline::run(
+        steps,
+        &action.data_source,
         backoffice,
-        &data_sources_map,
+        &state.data_sources,
+        &input,
     )
-    .await
-    {
-        Ok(m2m_errors) => {
-            if !m2m_errors.is_empty() {
-                let error_messages: Vec<serde_json::Value> = m2m_errors
-                    .iter()
-                    .map(|e| {
-                        serde_json::json!({
-                            "relationship_id": e.relationship_id,
-                            "field": e.field,
-                            "message": e.message
-                        })
-                    })
-                    .collect();
+    .await;
+    // Completed steps may have written even when a later one failed
+    state
+        .query_cache
+        .invalidate_section(backoffice, &section.id)
+        .await;
+    state.events.publish(PushEvent::mutation(
+        &backoffice.id,
+        &section.id,
+        AuditOperation::Update,
+        None,
+        auth.user_id.clone(),
+    ));
 
-                warn!(
-                    error_count = m2m_errors.len(),
-                    "Many-to-many relationship validation failed"
-                );
-
-                return (
-                    StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({
-                        "error": "Many-to-many relationship validation failed",
-                        "relationship_errors": error_messages
-                    })),
-                )
-                    .into_response();
-            }
+    match outcome {
+        Ok(outputs) => {
+            info!(action = %action.id, steps = steps.len(), "Pipeline completed");
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({"success": true, "steps": outputs})),
+            )
+                .into_response()
         }
-        Err(e) => {
-            error!(error = %e, "Many-to-many validation error");
+        Err(failure) => {
+            error!(step = %failure.step, error = %failure.error, "Pipeline step failed");
+            (
+                data_source_error_response(&failure.error).status(),
+                Json(serde_json::json!({
+                    "error": format!("Step '{}' failed: {}", failure.step, failure.error),
+                    "failed_step": failure.step,
+                    "steps": failure.outputs,
+                })),
+            )
+                .into_response()
         }
     }
+}
 
-    // Get the data source for execution
-    let data_source = match data_sources_map.get(&action.data_source) {
-        Some(ds) => ds,
-        None => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": "Data source not found"})),
-            )
-                .into_response()
+/// Response to writes attempted through a report action
+fn report_read_only() -> Response {
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        Json(serde_json::json!({"error": "Report actions are read-only"})),
+    )
+        .into_response()
+}
+
+/// Layout of a form or view action, restricted to the fields the caller
+/// can read
+fn readable_layout(action_type: &ActionType, readable: &[FieldConfig]) -> Option<LayoutConfig> {
+    match action_type {
+        ActionType::Form {
+            layout: Some(layout),
+            ..
         }
-    };
+        | ActionType::View {
+            layout: Some(layout),
+            ..
+        } => Some(layout.for_fields(readable)),
+        _ => None,
+    }
+}
 
-    // Step 5: Execute the mutation
-    let query_str = action
-        .query
-        .as_deref()
-        .or(action.endpoint.as_deref())
-        .unwrap_or("");
+/// Embed related records into query results, creating only the data
+/// sources the expansions read from
+async fn expand_rows(
+    state: &AppState,
+    backoffice: &BackofficeConfig,
+    expansions: &[relationships::Expansion<'_>],
+    rows: &mut [HashMap<String, Value>],
+    auth: &AuthContext,
+) -> Result<(), Response> {
+    if expansions.is_empty() {
+        return Ok(());
+    }
 
-    info!(query = %query_str, "Executing mutation");
+    let names: Vec<&str> = expansions
+        .iter()
+        .map(|e| e.target_action.data_source.as_str())
+        .collect();
+    let data_sources = create_data_sources(state, backoffice, &names).await?;
 
-    match data_source.execute_mutation(query_str, &payload.data).await {
-        Ok(result) => {
-            info!("Mutation executed successfully");
+    relationships::expand_rows(rows, expansions, backoffice, &data_sources, auth)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Relationship expansion failed");
+            data_source_error_response(&e)
+        })
+}
 
-            // Log audit trail if enabled
-            if AuditLogger::should_audit(&section.audit, &AuditOperation::Create) {
-                let record_id = result.as_str().map(|s| s.to_string());
-                let audit_entry = AuditLogger::create_entry(
-                    section_id.clone(),
-                    record_id.clone(),
-                    &payload.data,
-                    None, // TODO: Extract user ID from auth header
-                );
+/// Add formatted currency amounts to query results, converted to the
+/// backoffice's reporting currency when it has one
+async fn annotate_currencies(
+    state: &AppState,
+    backoffice: &BackofficeConfig,
+    fields: &[FieldConfig],
+    rows: &mut [HashMap<String, Value>],
+) {
+    if !currency::has_currency(fields) {
+        return;
+    }
+    let conversion = match &backoffice.currency {
+        Some(config) => {
+            // Without its provider, the configured rates are used
+            let provider = match &config.provider {
+                Some(provider) => state
+                    .data_source(&backoffice.id, &provider.data_source)
+                    .await
+                    .map_err(|e| warn!(error = %e, "Exchange rate provider unavailable"))
+                    .ok(),
+                None => None,
+            };
+            Some(currency::conversion(config, &backoffice.id, provider.as_deref()).await)
+        }
+        None => None,
+    };
+    currency::annotate_rows(rows, fields, conversion.as_ref());
+}
 
-                if let Err(e) = state.audit_logger.log(audit_entry) {
-                    warn!(error = %e, "Failed to log audit entry");
-                }
-            }
+/// Create the named data sources of a backoffice, skipping unknown names
+async fn create_data_sources(
+    state: &AppState,
+    backoffice: &BackofficeConfig,
+    names: &[&str],
+) -> Result<HashMap<String, Box<dyn data_source::DataSource>>, Response> {
+    let mut data_sources: HashMap<String, Box<dyn data_source::DataSource>> = HashMap::new();
+    for &name in names {
+        if data_sources.contains_key(name) {
+            continue;
+        }
+        let Some(config) = backoffice.data_sources.get(name) else {
+            continue;
+        };
+        let data_source = state
+            .data_sources
+            .create(config)
+            .await
+            .map_err(|e| data_source_error_response(&e))?;
+        data_sources.insert(name.to_string(), data_source);
+    }
+    Ok(data_sources)
+}
 
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({"success": true, "data": result})),
+#[derive(Debug, Deserialize)]
+struct RelatedQuery {
+    page: Option<usize>,
+    page_size: Option<usize>,
+    /// Case-insensitive text matched against the displayed fields
+    search: Option<String>,
+}
+
+/// A relationship of a section's records and the data sources it's loaded
+/// from
+struct RelatedSource<'a> {
+    backoffice: &'a BackofficeConfig,
+    section: &'a SectionConfig,
+    expansion: relationships::Expansion<'a>,
+    data_sources: HashMap<String, Box<dyn data_source::DataSource>>,
+}
+
+/// Resolve a relationship leaving a section, checking the caller may read
+/// its target
+async fn related_source<'a>(
+    state: &'a AppState,
+    backoffice_id: &str,
+    section_id: &str,
+    relationship_id: &str,
+    auth: &AuthContext,
+) -> Result<RelatedSource<'a>, Response> {
+    let not_found = |what: &str| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("{} not found", what)})),
+        )
+            .into_response()
+    };
+    let Some(backoffice) = state.backoffice(backoffice_id) else {
+        return Err(not_found("Backoffice"));
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return Err(not_found("Section"));
+    };
+    if !backoffice
+        .relationships
+        .iter()
+        .any(|r| r.id == relationship_id && r.from_section == section_id)
+    {
+        return Err(not_found("Relationship"));
+    }
+    let expansion = match relationships::resolve_expansions(relationship_id, section, backoffice) {
+        Ok(mut expansions) => expansions.remove(0),
+        Err(e) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e.to_string()})),
             )
-                .into_response()
+                .into_response())
         }
+    };
+    if !auth.has_all_scopes(&expansion.target_action.required_scopes) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response());
+    }
+
+    let mut names = vec![expansion.target_action.data_source.as_str()];
+    names.extend(section.actions.first().map(|a| a.data_source.as_str()));
+    let data_sources = create_data_sources(state, backoffice, &names).await?;
+    Ok(RelatedSource {
+        backoffice,
+        section,
+        expansion,
+        data_sources,
+    })
+}
+
+/// Default page size of a relationship's records: that of its target list
+fn related_page_size(expansion: &relationships::Expansion<'_>) -> usize {
+    match &expansion.target_action.action_type {
+        ActionType::List { config, .. } => config.page_size,
+        _ => 20,
+    }
+}
+
+/// Records related to one record through a relationship, paginated
+///
+/// Meant for relationship widgets such as selects and autocompletes. Related
+/// records are loaded like `expand` results: through the target section's
+/// row filters, field permissions and masking, narrowed to `display_fields`.
+async fn related_records_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, record_id, relationship_id)): Path<(
+        String,
+        String,
+        String,
+        String,
+    )>,
+    auth: AuthContext,
+    Query(query): Query<RelatedQuery>,
+) -> Response {
+    let RelatedSource {
+        backoffice,
+        section,
+        expansion,
+        data_sources,
+    } = match related_source(&state, &backoffice_id, &section_id, &relationship_id, &auth).await {
+        Ok(source) => source,
+        Err(response) => return response,
+    };
+    let source_name = section.actions.first().map(|a| a.data_source.as_str());
+
+    // Only relationships of records the caller can see are listed
+    let record_id = Value::String(record_id);
+    let row_conditions = match row_filters::resolve(&section.row_filters, &auth) {
+        Ok(conditions) => conditions,
         Err(e) => {
-            error!(error = %e, "Mutation execution failed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
+            warn!(error = %e, "Row filters could not be resolved");
+            return (
+                StatusCode::FORBIDDEN,
                 Json(serde_json::json!({"error": e.to_string()})),
             )
-                .into_response()
+                .into_response();
+        }
+    };
+    if let Some((name, source)) = source_name.and_then(|name| Some((name, data_sources.get(name)?)))
+    {
+        match row_filters::record_visible(
+            source.as_ref(),
+            section,
+            name,
+            &backoffice.data_sources[name],
+            &record_id,
+            &row_conditions,
+        )
+        .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({"error": "Record not found"})),
+                )
+                    .into_response()
+            }
+            Err(e) => return data_source_error_response(&e),
+        }
+    }
+
+    let mut related = match relationships::related_records(
+        &record_id,
+        &expansion,
+        backoffice,
+        &data_sources,
+        &auth,
+    )
+    .await
+    {
+        Ok(related) => related,
+        Err(e) => {
+            error!(error = %e, "Failed to load related records");
+            return data_source_error_response(&e);
         }
+    };
+
+    if let Some(search) = query.search.as_deref().map(str::to_lowercase) {
+        related.retain(|record| {
+            record.iter().any(|(name, value)| {
+                let displayed = match &expansion.relationship.display_fields {
+                    Some(display_fields) => display_fields.contains(name),
+                    None => true,
+                };
+                displayed
+                    && match value {
+                        Value::String(s) => s.to_lowercase().contains(&search),
+                        Value::Number(n) => n.to_string().contains(&search),
+                        _ => false,
+                    }
+            })
+        });
     }
+
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query
+        .page_size
+        .unwrap_or_else(|| related_page_size(&expansion))
+        .clamp(1, data_source::MAX_PAGE_SIZE);
+    let total_items = related.len();
+    let data: Vec<_> = related
+        .into_iter()
+        .skip((page - 1).saturating_mul(page_size))
+        .take(page_size)
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": data,
+            "fields": permissions::readable_fields(
+                expansion.target_action.action_type.fields(),
+                &auth,
+            ),
+            "pagination": {
+                "page": page,
+                "page_size": page_size,
+                "total_items": total_items,
+                "total_pages": total_items.div_ceil(page_size),
+            }
+        })),
+    )
+        .into_response()
 }
 
-/// Execute a delete action (DELETE)
-async fn execute_delete_handler(
+#[derive(Debug, Deserialize, Serialize)]
+struct MutationData {
+    #[serde(flatten)]
+    data: HashMap<String, Value>,
+    /// Persist even though rules with `severity: warning` failed
+    #[serde(default)]
+    accept_warnings: bool,
+}
+
+/// Execute a mutation action (POST)
+async fn execute_mutation_handler(
     State(state): State<Arc<AppState>>,
     Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
-    Query(query): Query<ActionQuery>,
+    auth: AuthContext,
+    request: RequestMetadata,
+    Json(payload): Json<MutationData>,
 ) -> impl IntoResponse {
+    mutate(
+        state,
+        (backoffice_id, section_id, action_id),
+        auth,
+        request,
+        payload,
+        false,
+    )
+    .await
+}
+
+/// Validate and persist a mutation
+///
+/// `partial` updates only validate and write the fields present in the
+/// payload, as inline edits of a single field do.
+async fn mutate(
+    state: Arc<AppState>,
+    (backoffice_id, section_id, action_id): (String, String, String),
+    auth: AuthContext,
+    request: RequestMetadata,
+    mut payload: MutationData,
+    partial: bool,
+) -> Response {
     info!(
         backoffice_id = %backoffice_id,
         section_id = %section_id,
         action_id = %action_id,
-        "Processing delete request"
+        "Processing mutation request"
     );
 
     // Find the backoffice
@@ -661,266 +2007,7225 @@ async fn execute_delete_handler(
         }
     };
 
-    // Extract record ID from query params
-    let record_id = match query.params.get("id") {
-        Some(id) => id,
+    // Find the action
+    let action = match section.actions.iter().find(|a| a.id == action_id) {
+        Some(a) => a,
         None => {
             return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": "Record ID is required"})),
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Action not found"})),
             )
                 .into_response()
         }
     };
-
-    // Create data sources map
-    let mut data_sources_map: HashMap<String, Box<dyn data_source::DataSource>> = HashMap::new();
-    for (name, ds_config) in &backoffice.data_sources {
-        match data_source::create_data_source(ds_config).await {
-            Ok(ds) => {
-                data_sources_map.insert(name.clone(), ds);
-            }
-            Err(e) => {
-                error!(
-                    data_source = %name,
-                    error = %e,
-                    "Failed to create data source"
-                );
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({"error": format!("Failed to create data source: {}", e)})),
-                )
-                    .into_response();
-            }
-        }
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response();
     }
 
-    // Step 1: Handle cascade delete operations
-    info!(record_id = %record_id, "Processing cascade delete");
-    match relationships::handle_cascade_delete(
-        record_id,
-        &section_id,
-        backoffice,
-        &data_sources_map,
-    )
-    .await
-    {
-        Ok(cascade_ops) => {
-            if !cascade_ops.is_empty() {
-                info!(
-                    operation_count = cascade_ops.len(),
-                    "Executing cascade delete operations"
-                );
+    // Get fields from the action for validation
+    let fields = match &action.action_type {
+        ActionType::Form { fields, .. } => fields,
+        ActionType::Custom { fields } => fields,
+        ActionType::Report { .. } => return report_read_only(),
+        ActionType::Pipeline { fields, steps } if !partial => {
+            return run_pipeline(
+                &state,
+                backoffice,
+                section,
+                action,
+                fields,
+                steps,
+                &auth,
+                request.locale.as_deref(),
+                payload.data,
+            )
+            .await
+        }
+        _ => {
+            warn!("Mutation attempted on non-form action");
+            return (
+                StatusCode::METHOD_NOT_ALLOWED,
+                Json(serde_json::json!({"error": "Action does not accept mutations"})),
+            )
+                .into_response();
+        }
+    };
+    let submitted: Vec<FieldConfig>;
+    let fields = if partial {
+        submitted = fields
+            .iter()
+            .filter(|f| payload.data.contains_key(&f.id))
+            .cloned()
+            .collect();
+        &submitted
+    } else {
+        fields
+    };
 
-                // Execute cascade operations
-                if let Err(e) = relationships::execute_cascade_operations(
-                    &cascade_ops,
-                    backoffice,
-                    &data_sources_map,
-                )
-                .await
-                {
-                    error!(error = %e, "Failed to execute cascade operations");
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(serde_json::json!({
-                            "error": format!("Failed to execute cascade operations: {}", e)
-                        })),
-                    )
-                        .into_response();
-                }
-            }
+    // Let plugins rewrite or veto the payload before anything else, so field
+    // permissions, row filters and validation apply to what they return
+    match plugins::before_mutation(backoffice, &section_id, &action_id, payload.data).await {
+        Ok(MutationHookResult::Continue(data)) => payload.data = data,
+        Ok(MutationHookResult::Veto(message)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": message})),
+            )
+                .into_response();
         }
         Err(e) => {
-            error!(error = %e, "Failed to process cascade delete");
+            error!(error = %e, "Plugin before_mutation hook failed");
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": format!("Failed to process cascade delete: {}", e)})),
+                Json(serde_json::json!({"error": format!("Plugin error: {}", e)})),
             )
                 .into_response();
         }
     }
 
-    // Step 2: Delete the record itself
-    let action = match section.actions.iter().find(|a| a.id == action_id) {
-        Some(a) => a,
-        None => {
+    // Child records and related ids sent along with a new parent
+    let mut nested = relationships::take_nested(&mut payload.data, &section_id, backoffice);
+    if !nested.is_empty()
+        && CrudOperation::for_action(action, Some(&payload.data)) != CrudOperation::Create
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Nested records can only be sent when creating a record"
+            })),
+        )
+            .into_response();
+    }
+
+    // Reject (or drop) fields the caller is not allowed to write
+    let write_policy = state
+        .config
+        .security
+        .as_ref()
+        .map(|s| s.unauthorized_field_writes.clone())
+        .unwrap_or_default();
+    if let Err(forbidden) =
+        permissions::enforce_writable(&mut payload.data, fields, &auth, &write_policy)
+    {
+        warn!(fields = ?forbidden, "Mutation touches fields the caller cannot write");
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Insufficient scopes to write fields",
+                "fields": forbidden
+            })),
+        )
+            .into_response();
+    }
+
+    // Pin row-level security fields to the caller's claims
+    let row_conditions = match row_filters::resolve(&section.row_filters, &auth) {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            warn!(error = %e, "Row filters could not be resolved");
             return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({"error": "Action not found"})),
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({"error": e.to_string()})),
             )
-                .into_response()
+                .into_response();
         }
     };
+    row_filters::apply_to_payload(&mut payload.data, &row_conditions);
 
-    let data_source = match data_sources_map.get(&action.data_source) {
-        Some(ds) => ds,
-        None => {
+    let hook_ctx = HookContext {
+        backoffice,
+        section,
+        action,
+        auth: &auth,
+    };
+    match hooks::before_mutation(&state.hooks, &hook_ctx, &mut payload.data).await {
+        Ok(HookOutcome::Continue) => {}
+        Ok(HookOutcome::Veto(message)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": message})),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            error!(error = %e, "before_mutation hook failed");
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": "Data source not found"})),
+                Json(serde_json::json!({"error": format!("Hook error: {}", e)})),
             )
-                .into_response()
+                .into_response();
         }
-    };
+    }
 
-    // Build delete query
-    let delete_query = format!("DELETE FROM {} WHERE id = '{}'", section_id, record_id);
+    // Step 1: Validate data against field configurations
+    info!("Validating request data");
+    let validation_functions = state.validation_functions(&backoffice.id);
+    // Failed rules with `severity: warning` are collected to confirm later
+    let mut warnings = match validation::validate_data_with_functions(
+        &payload.data,
+        fields,
+        &validation_functions,
+    )
+    .await
+    {
+        Ok(validation_errors) => {
+            let (validation_errors, warnings) = validation::split_warnings(validation_errors);
+            if !validation_errors.is_empty() {
+                warn!(error_count = validation_errors.len(), "Validation failed");
+                let error_messages =
+                    localized_errors(&state, validation_errors, request.locale.as_deref());
+
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": "Validation failed",
+                        "validation_errors": error_messages
+                    })),
+                )
+                    .into_response();
+            }
+            warnings
+        }
+        Err(e) => {
+            error!(error = %e, "Validation error");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Validation error: {}", e)})),
+            )
+                .into_response();
+        }
+    };
+    // Valid phone numbers are stored in E.164, and card numbers masked
+    phone::normalize(&mut payload.data, fields);
+    card::mask_payload(&mut payload.data, fields);
+    match relationships::validate_nested(
+        &mut nested,
+        backoffice,
+        &validation_functions,
+        &auth,
+        &write_policy,
+    )
+    .await
+    {
+        Ok(validation_errors) if !validation_errors.is_empty() => {
+            warn!(
+                error_count = validation_errors.len(),
+                "Nested record validation failed"
+            );
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "Validation failed",
+                    "validation_errors": localized_errors(
+                        &state,
+                        validation_errors,
+                        request.locale.as_deref(),
+                    )
+                })),
+            )
+                .into_response();
+        }
+        Ok(_) => {}
+        Err(e) => {
+            error!(error = %e, "Nested record validation error");
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    }
+
+    // Step 2: Create data sources map for relationship validation
+    let mut data_sources_map: HashMap<String, Box<dyn data_source::DataSource>> = HashMap::new();
+    for (name, ds_config) in &backoffice.data_sources {
+        match state.data_sources.create(ds_config).await {
+            Ok(ds) => {
+                data_sources_map.insert(name.clone(), ds);
+            }
+            Err(e) => {
+                error!(
+                    data_source = %name,
+                    error = %e,
+                    "Failed to create data source"
+                );
+                if e.is::<data_source::CircuitOpenError>() {
+                    return data_source_error_response(&e);
+                }
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": format!("Failed to create data source: {}", e)})),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    // Updates may only target records the caller can see
+    if let Some(record_id) = payload.data.get("id").filter(|id| !id.is_null()) {
+        if let Some(section_data_source) = data_sources_map.get(&action.data_source) {
+            match row_filters::record_visible(
+                section_data_source.as_ref(),
+                section,
+                &action.data_source,
+                &backoffice.data_sources[&action.data_source],
+                record_id,
+                &row_conditions,
+            )
+            .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    return (
+                        StatusCode::NOT_FOUND,
+                        Json(serde_json::json!({"error": "Record not found"})),
+                    )
+                        .into_response()
+                }
+                Err(e) => {
+                    error!(error = %e, "Failed to check record visibility");
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response();
+                }
+            }
+        }
+    }
+
+    // Step 3: Run validations that query data sources (uniqueness, remote checks)
+    match validation::validate_data_async(
+        &payload.data,
+        fields,
+        section,
+        &action.data_source,
+        backoffice.data_sources.get(&action.data_source),
+        &data_sources_map,
+    )
+    .await
+    {
+        Ok(validation_errors) => {
+            let (validation_errors, async_warnings) = validation::split_warnings(validation_errors);
+            warnings.extend(async_warnings);
+            if !validation_errors.is_empty() {
+                warn!(error_count = validation_errors.len(), "Validation failed");
+                let error_messages =
+                    localized_errors(&state, validation_errors, request.locale.as_deref());
+
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": "Validation failed",
+                        "validation_errors": error_messages
+                    })),
+                )
+                    .into_response();
+            }
+        }
+        Err(e) => {
+            error!(error = %e, "Validation error");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Validation error: {}", e)})),
+            )
+                .into_response();
+        }
+    }
+
+    // Step 4: Validate foreign key relationships
+    info!("Validating foreign key relationships");
+    match relationships::validate_foreign_keys(
+        &payload.data,
+        &section_id,
+        backoffice,
+        &data_sources_map,
+    )
+    .await
+    {
+        Ok(relationship_errors) => {
+            if !relationship_errors.is_empty() {
+                let error_messages: Vec<serde_json::Value> = relationship_errors
+                    .iter()
+                    .map(|e| {
+                        serde_json::json!({
+                            "relationship_id": e.relationship_id,
+                            "field": e.field,
+                            "message": e.message
+                        })
+                    })
+                    .collect();
+
+                warn!(
+                    error_count = relationship_errors.len(),
+                    "Relationship validation failed"
+                );
+
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": "Relationship validation failed",
+                        "relationship_errors": error_messages
+                    })),
+                )
+                    .into_response();
+            }
+        }
+        Err(e) => {
+            error!(error = %e, "Relationship validation error");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Relationship validation error: {}", e)})),
+            )
+                .into_response();
+        }
+    }
+
+    // Step 5: Validate many-to-many relationships
+    match relationships::validate_many_to_many(
+        &payload.data,
+        &section_id,
+        backoffice,
+        &data_sources_map,
+    )
+    .await
+    {
+        Ok(m2m_errors) => {
+            if !m2m_errors.is_empty() {
+                let error_messages: Vec<serde_json::Value> = m2m_errors
+                    .iter()
+                    .map(|e| {
+                        serde_json::json!({
+                            "relationship_id": e.relationship_id,
+                            "field": e.field,
+                            "message": e.message
+                        })
+                    })
+                    .collect();
+
+                warn!(
+                    error_count = m2m_errors.len(),
+                    "Many-to-many relationship validation failed"
+                );
+
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": "Many-to-many relationship validation failed",
+                        "relationship_errors": error_messages
+                    })),
+                )
+                    .into_response();
+            }
+        }
+        Err(e) => {
+            error!(error = %e, "Many-to-many validation error");
+        }
+    }
+
+    // Soft checks only let the mutation through once the client accepts them
+    if !warnings.is_empty() && !payload.accept_warnings {
+        warn!(
+            warning_count = warnings.len(),
+            "Mutation needs warnings accepted"
+        );
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": "Warnings must be accepted",
+                "warnings": localized_errors(&state, warnings, request.locale.as_deref())
+            })),
+        )
+            .into_response();
+    }
+
+    // Signatures drawn as strokes are kept as the URL of their PNG
+    if let Err(e) = store_signatures(
+        &state,
+        &section_id,
+        fields,
+        &mut payload.data,
+        &data_sources_map,
+    )
+    .await
+    {
+        if e.is::<signature::TooManyPoints>() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+        error!(error = %e, "Storing signatures failed");
+        return (
+            StatusCode::BAD_GATEWAY,
+            Json(serde_json::json!({"error": format!("Signature could not be stored: {}", e)})),
+        )
+            .into_response();
+    }
+
+    // Get the data source for execution
+    let data_source = match data_sources_map.get(&action.data_source) {
+        Some(ds) => ds,
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Data source not found"})),
+            )
+                .into_response()
+        }
+    };
+
+    // Step 6: Execute the mutation
+    let query_str = match backoffice
+        .data_sources
+        .get(&action.data_source)
+        .ok_or_else(|| anyhow::anyhow!("Data source not found"))
+        .and_then(|ds_config| {
+            if partial {
+                crud::partial_update_target(section, action, ds_config, &payload.data)
+            } else {
+                crud::action_target(
+                    section,
+                    action,
+                    ds_config,
+                    CrudOperation::for_action(action, Some(&payload.data)),
+                    &payload.data,
+                )
+            }
+        }) {
+        Ok(query) => query,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    };
+
+    info!(query = %query_str, "Executing mutation");
+    debug!(
+        payload = %crate::access_log::Redactor::new(&state.config.server.access_log)
+            .payload(&masking::masked(&payload.data, fields)),
+        "Mutation payload"
+    );
+
+    // Updates are audited with the values they replace, and their state
+    // changes checked against the current states
+    let operation = CrudOperation::for_action(action, Some(&payload.data));
+    let sets_state = state_machine::sets_state(fields, &payload.data);
+    let old_record = match payload.data.get("id") {
+        Some(record_id)
+            if operation == CrudOperation::Update
+                && (sets_state
+                    || AuditLogger::should_audit(&section.audit, &AuditOperation::Update)) =>
+        {
+            fetch_record(backoffice, section, action, data_source.as_ref(), record_id).await
+        }
+        _ => None,
+    };
+    if sets_state {
+        if operation == CrudOperation::Update && old_record.is_none() {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Record not found"})),
+            )
+                .into_response();
+        }
+        if let Err(errors) = state_machine::check(fields, old_record.as_ref(), &payload.data, &auth)
+        {
+            warn!(errors = ?errors, "Illegal state transition");
+            return (
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({
+                    "error": "Illegal state transition",
+                    "transition_errors": errors
+                })),
+            )
+                .into_response();
+        }
+    }
+
+    // A parent with nested records is created in one transaction
+    let outcome = if nested.is_empty() {
+        data_source
+            .execute_mutation(&query_str, &payload.data)
+            .await
+    } else {
+        let parent = data_source::Mutation {
+            query: query_str.clone(),
+            data: payload.data.clone(),
+            ..Default::default()
+        };
+        let mutations =
+            match relationships::nested_mutations(parent, &nested, backoffice, &action.data_source)
+            {
+                Ok(mutations) if data_source.supports_transactions() => mutations,
+                Ok(_) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({
+                            "error": "Nested records need a data source that supports transactions"
+                        })),
+                    )
+                        .into_response()
+                }
+                Err(e) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response()
+                }
+            };
+        info!(
+            mutations = mutations.len(),
+            "Creating record with nested records in a transaction"
+        );
+        data_source
+            .execute_transaction(&mutations)
+            .await
+            .map(|results| results.into_iter().next().unwrap_or(Value::Null))
+    };
+
+    match outcome {
+        Ok(result) => {
+            info!("Mutation executed successfully");
+
+            hooks::after_mutation(&state.hooks, &hook_ctx, &payload.data, &result).await;
+            state
+                .query_cache
+                .invalidate_section(backoffice, &section_id)
+                .await;
+            for nested in &nested {
+                state
+                    .query_cache
+                    .invalidate_section(backoffice, &nested.relationship.to_section)
+                    .await;
+                state.events.publish(PushEvent::mutation(
+                    &backoffice_id,
+                    &nested.relationship.to_section,
+                    AuditOperation::Create,
+                    None,
+                    auth.user_id.clone(),
+                ));
+            }
+            let (push_operation, record_id) = if operation == CrudOperation::Update {
+                (
+                    AuditOperation::Update,
+                    payload.data.get("id").map(audit_record_id),
+                )
+            } else {
+                (AuditOperation::Create, result.as_str().map(str::to_string))
+            };
+            state.events.publish(PushEvent::mutation(
+                &backoffice_id,
+                &section_id,
+                push_operation,
+                record_id,
+                auth.user_id.clone(),
+            ));
+
+            // Log audit trail if enabled
+            let audit_entry = if operation == CrudOperation::Update {
+                AuditLogger::should_audit(&section.audit, &AuditOperation::Update).then(|| {
+                    let sensitive = section_fields(section);
+                    let old = old_record.map(|old| masking::masked(&old, &sensitive));
+                    // The record after the update: its old values overlaid
+                    // with the submitted ones
+                    let mut new = old.clone().unwrap_or_default();
+                    new.extend(masking::masked(&payload.data, &sensitive));
+                    AuditLogger::update_entry(
+                        section_id.clone(),
+                        payload
+                            .data
+                            .get("id")
+                            .map(audit_record_id)
+                            .unwrap_or_default(),
+                        old.as_ref(),
+                        &new,
+                        auth.user_id.clone(),
+                    )
+                })
+            } else {
+                AuditLogger::should_audit(&section.audit, &AuditOperation::Create).then(|| {
+                    AuditLogger::create_entry(
+                        section_id.clone(),
+                        result.as_str().map(|s| s.to_string()),
+                        &masking::masked(&payload.data, fields),
+                        auth.user_id.clone(),
+                    )
+                })
+            };
+            if let Some(mut audit_entry) = audit_entry {
+                request.attach(&mut audit_entry, &action_id);
+                if let Err(e) = state.audit_logger.log(audit_entry).await {
+                    warn!(error = %e, "Failed to log audit entry");
+                }
+            }
+
+            let mut body = serde_json::json!({"success": true, "data": result});
+            warnings.extend(validation::warnings(&payload.data, fields));
+            if !warnings.is_empty() {
+                body["warnings"] = localized_errors(&state, warnings, request.locale.as_deref());
+            }
+            if !nested.is_empty() {
+                body["nested"] = nested
+                    .iter()
+                    .map(|n| (n.relationship.id.clone(), n.records.len().into()))
+                    .collect::<serde_json::Map<_, _>>()
+                    .into();
+            }
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Mutation execution failed");
+            data_source_error_response(&e)
+        }
+    }
+}
+
+/// Run a query action as `GET .../actions/:action_id` does, taking the
+/// query parameters as a map; used by the GraphQL and gRPC APIs
+#[cfg(any(feature = "graphql", feature = "grpc"))]
+pub(crate) async fn run_query(
+    state: Arc<AppState>,
+    ids: (String, String, String),
+    auth: AuthContext,
+    mut params: HashMap<String, String>,
+) -> Response {
+    let number = |value: Option<String>| value.and_then(|v| v.parse().ok());
+    let query = ActionQuery {
+        page: number(params.remove("page")),
+        page_size: number(params.remove("page_size")),
+        sort_by: params.remove("sort_by"),
+        sort_order: params.remove("sort_order"),
+        expand: params.remove("expand"),
+        locale: params.remove("locale"),
+        params,
+    };
+    query_action(State(state), Path(ids), auth, Query(query))
+        .await
+        .into_response()
+}
+
+/// Run a mutation action as `POST .../actions/:action_id` does; used by the
+/// GraphQL and gRPC APIs
+#[cfg(any(feature = "graphql", feature = "grpc"))]
+pub(crate) async fn run_mutation(
+    state: Arc<AppState>,
+    ids: (String, String, String),
+    auth: AuthContext,
+    request: RequestMetadata,
+    data: HashMap<String, Value>,
+    accept_warnings: bool,
+) -> Response {
+    let payload = MutationData {
+        data,
+        accept_warnings,
+    };
+    mutate(state, ids, auth, request, payload, false).await
+}
+
+/// Delete a record as `DELETE .../actions/:action_id?id=` does; used by the
+/// GraphQL API
+#[cfg(feature = "graphql")]
+pub(crate) async fn run_delete(
+    state: Arc<AppState>,
+    ids: (String, String, String),
+    auth: AuthContext,
+    request: RequestMetadata,
+    record_id: String,
+) -> Response {
+    let query = ActionQuery {
+        page: None,
+        page_size: None,
+        sort_by: None,
+        sort_order: None,
+        expand: None,
+        locale: request.locale.clone(),
+        params: HashMap::from([("id".to_string(), record_id)]),
+    };
+    execute_delete_handler(State(state), Path(ids), auth, request, Query(query))
+        .await
+        .into_response()
+}
+
+/// Records related to several records through a relationship
+#[cfg(feature = "graphql")]
+pub(crate) struct RelatedBatch {
+    /// Related records by the key of the record id; records without any are
+    /// left out
+    pub records: HashMap<String, Vec<Value>>,
+    /// Page size of the relationship's target list
+    pub page_size: usize,
+}
+
+/// Load a relationship for several records at once
+///
+/// Used by the GraphQL API. Scopes, row filters, field permissions and
+/// masking of the target apply like on the relationships endpoint. The
+/// records aren't checked against the row filters of their own section, so
+/// the ids must come from the caller's own query results.
+#[cfg(feature = "graphql")]
+pub(crate) async fn run_related_batch(
+    state: &AppState,
+    (backoffice_id, section_id, relationship_id): (&str, &str, &str),
+    record_ids: &[Value],
+    auth: &AuthContext,
+) -> Result<RelatedBatch, Response> {
+    let source = related_source(state, backoffice_id, section_id, relationship_id, auth).await?;
+    let related = relationships::related_records_batch(
+        record_ids,
+        &source.expansion,
+        source.backoffice,
+        &source.data_sources,
+        auth,
+    )
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to load related records");
+        data_source_error_response(&e)
+    })?;
+    Ok(RelatedBatch {
+        records: related
+            .into_iter()
+            .map(|(key, rows)| {
+                let rows = rows
+                    .into_iter()
+                    .map(|row| Value::Object(row.into_iter().collect()))
+                    .collect();
+                (key, rows)
+            })
+            .collect(),
+        page_size: related_page_size(&source.expansion),
+    })
+}
+
+/// Status and JSON body of a response; bodies that aren't JSON are a string
+#[cfg(any(feature = "graphql", feature = "grpc"))]
+pub(crate) async fn response_json(response: Response) -> (StatusCode, Value) {
+    let status = response.status();
+    let body = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+        Err(e) => serde_json::json!({"error": e.to_string()}),
+    };
+    (status, body)
+}
+
+/// Validation errors as responses list them, in the request's locale
+fn localized_errors(
+    state: &AppState,
+    mut errors: Vec<validation::ValidationError>,
+    locale: Option<&str>,
+) -> Value {
+    i18n::localize(&mut errors, &state.config.i18n, locale);
+    serde_json::json!(errors)
+}
+
+/// Fields of every action of a section, so audit entries mask every
+/// sensitive column of a record
+fn section_fields(section: &crate::config::SectionConfig) -> Vec<crate::config::FieldConfig> {
+    section
+        .actions
+        .iter()
+        .flat_map(|a| a.action_type.fields())
+        .cloned()
+        .collect()
+}
+
+/// Record ids are logged as text
+fn audit_record_id(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
+/// Current values of a record, read before it is updated or deleted so its
+/// audit entry can record them, or before it is duplicated
+///
+/// Databases are read with a `SELECT *` by id; other sources through the
+/// section's view action on the same data source. Failures are logged and
+/// `None` is returned, so audit entries are written without old values.
+async fn fetch_record(
+    backoffice: &BackofficeConfig,
+    section: &crate::config::SectionConfig,
+    action: &crate::config::ActionConfig,
+    data_source: &dyn data_source::DataSource,
+    record_id: &Value,
+) -> Option<HashMap<String, Value>> {
+    let config = backoffice.data_sources.get(&action.data_source)?;
+    let params = HashMap::from([("id".to_string(), record_id.clone())]);
+    let query = match crud::record_query(section, config) {
+        Some(query) => query,
+        None => {
+            let view = section.actions.iter().find(|a| {
+                matches!(a.action_type, ActionType::View { .. })
+                    && a.data_source == action.data_source
+            })?;
+            match crud::action_target(section, view, config, CrudOperation::View, &params) {
+                Ok(query) => query,
+                Err(e) => {
+                    warn!(error = %e, "Cannot read the record");
+                    return None;
+                }
+            }
+        }
+    };
+
+    match data_source.execute_query(&query, Some(&params)).await {
+        Ok(rows) => rows.into_iter().next(),
+        Err(e) => {
+            warn!(error = %e, "Failed to read the record");
+            None
+        }
+    }
+}
+
+/// Error response for a failed data source call
+///
+/// Errors reported by the remote service itself (e.g. JSON-RPC error objects)
+/// are returned as structured errors with a 502 status.
+fn data_source_error_response(e: &anyhow::Error) -> Response {
+    if let Some(open) = e.downcast_ref::<data_source::CircuitOpenError>() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [("retry-after", open.retry_after.as_secs().to_string())],
+            Json(serde_json::json!({"error": open.to_string()})),
+        )
+            .into_response();
+    }
+    match e.downcast_ref::<data_source::JsonRpcError>() {
+        Some(rpc) => (
+            StatusCode::BAD_GATEWAY,
+            Json(serde_json::json!({
+                "error": rpc.to_string(),
+                "rpc_code": rpc.code,
+                "data": rpc.data,
+            })),
+        )
+            .into_response(),
+        None => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeQuery {
+    /// Return the subtree below this record instead of the whole tree
+    root: Option<String>,
+    /// Levels to include; deeper nodes only report `has_children`
+    depth: Option<usize>,
+}
+
+/// Records of a section arranged by a self-referencing relationship
+///
+/// Rows come from the section's list action and go through its row
+/// filters, field permissions and masking.
+async fn tree_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, relationship_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    Query(query): Query<TreeQuery>,
+) -> Response {
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": message})),
+        )
+            .into_response()
+    };
+    let not_found = |what: &str| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("{} not found", what)})),
+        )
+            .into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return not_found("Backoffice");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return not_found("Section");
+    };
+    let Some(relationship) = backoffice
+        .relationships
+        .iter()
+        .find(|r| r.id == relationship_id && r.from_section == section_id)
+    else {
+        return not_found("Relationship");
+    };
+    if !relationships::is_tree(relationship) {
+        return bad_request(format!(
+            "Relationship '{}' does not link '{}' records to a parent in the same section",
+            relationship_id, section_id
+        ));
+    }
+    let Some((action, fields)) = section.actions.iter().find_map(|a| match &a.action_type {
+        ActionType::List { fields, .. } => Some((a, fields)),
+        _ => None,
+    }) else {
+        return bad_request(format!("Section '{}' has no list action", section_id));
+    };
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response();
+    }
+    let Some(ds_config) = backoffice.data_sources.get(&action.data_source) else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": "Data source not found"})),
+        )
+            .into_response();
+    };
+
+    let row_conditions = match row_filters::resolve(&section.row_filters, &auth) {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            warn!(error = %e, "Row filters could not be resolved");
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    };
+    let data_source = match state.data_sources.create(ds_config).await {
+        Ok(ds) => ds,
+        Err(e) => return data_source_error_response(&e),
+    };
+    let mut params = HashMap::new();
+    let target = match crud::action_target(section, action, ds_config, CrudOperation::List, &params)
+    {
+        Ok(target) => target,
+        Err(e) => return bad_request(e.to_string()),
+    };
+    let query_str = row_filters::scope_query(&target, &row_conditions, ds_config, &mut params);
+
+    let mut rows = match data_source.execute_query(&query_str, Some(&params)).await {
+        Ok(rows) => rows,
+        Err(e) => return data_source_error_response(&e),
+    };
+    row_filters::retain_rows(&mut rows, &row_conditions);
+    permissions::strip_unreadable(&mut rows, fields, &auth);
+    masking::mask_rows(&mut rows, fields, &auth);
+
+    let root = query.root.map(Value::String);
+    let tree = relationships::build_tree(rows, relationship, root.as_ref(), query.depth);
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": tree,
+            "fields": permissions::readable_fields(fields, &auth),
+        })),
+    )
+        .into_response()
+}
+
+/// Stream rows pushed by a live data source as server-sent events
+///
+/// Each row passes the same row filters, field permissions and masking as the
+/// list response. Data source errors are sent as `error` events.
+/// Drop the cached results of an action
+async fn invalidate_cache_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+) -> Response {
+    let not_found = |what: &str| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("{} not found", what)})),
+        )
+            .into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return not_found("Backoffice");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return not_found("Section");
+    };
+    let Some(action) = section.actions.iter().find(|a| a.id == action_id) else {
+        return not_found("Action");
+    };
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response();
+    }
+    if action.cache_config().is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Action results are not cached"})),
+        )
+            .into_response();
+    }
+
+    match state
+        .query_cache
+        .invalidate_action(backoffice, &section_id, action)
+        .await
+    {
+        Ok(()) => {
+            info!(section = %section_id, action = %action_id, "Invalidated action cache");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Cache invalidation failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn stream_action_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+) -> Response {
+    use futures_util::StreamExt;
+
+    let not_found = |what: &str| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("{} not found", what)})),
+        )
+            .into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return not_found("Backoffice");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return not_found("Section");
+    };
+    let Some(action) = section.actions.iter().find(|a| a.id == action_id) else {
+        return not_found("Action");
+    };
+
+    let fields = match &action.action_type {
+        ActionType::List { fields, .. }
+        | ActionType::View { fields, .. }
+        | ActionType::Custom { fields } => fields.clone(),
+        ActionType::Form { .. }
+        | ActionType::Summary { .. }
+        | ActionType::Report { .. }
+        | ActionType::Pipeline { .. } => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "Only list, view and custom actions can be streamed"
+                })),
+            )
+                .into_response()
+        }
+    };
+
+    let row_conditions = match row_filters::resolve(&section.row_filters, &auth) {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            warn!(error = %e, "Row filters could not be resolved");
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    };
+
+    let data_source = match state.data_source(&backoffice_id, &action.data_source).await {
+        Ok(ds) => ds,
+        Err(e) => return data_source_error_response(&e),
+    };
+    let rows = match data_source.subscribe(&action.target()).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    };
+
+    info!(
+        backoffice_id = %backoffice_id,
+        section_id = %section_id,
+        action_id = %action_id,
+        "Client subscribed to live rows"
+    );
+
+    let events = rows.filter_map(move |row| {
+        let event = match row {
+            Ok(row) => {
+                let mut rows = vec![row];
+                row_filters::retain_rows(&mut rows, &row_conditions);
+                permissions::strip_unreadable(&mut rows, &fields, &auth);
+                masking::mask_rows(&mut rows, &fields, &auth);
+                rows.pop().map(|row| Event::default().json_data(row))
+            }
+            Err(e) => {
+                warn!(error = %e, "Live data source error");
+                Some(Ok(Event::default().event("error").data(e.to_string())))
+            }
+        };
+        std::future::ready(event)
+    });
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Whether the caller holds the scope of administrative endpoints
+fn is_admin(state: &AppState, auth: &AuthContext) -> bool {
+    state
+        .config
+        .security
+        .as_ref()
+        .is_none_or(|security| auth.has_scope(&security.admin_scope))
+}
+
+/// Response to callers without the scope of administrative endpoints
+fn admin_required() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({"error": "Missing required scopes"})),
+    )
+        .into_response()
+}
+
+/// Generate section configs from a GraphQL data source's schema
+///
+/// Callers need the admin scope.
+async fn scaffold_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, data_source_id)): Path<(String, String)>,
+    auth: AuthContext,
+) -> impl IntoResponse {
+    if !is_admin(&state, &auth) {
+        return admin_required();
+    }
+    let is_graphql = state
+        .backoffice(&backoffice_id)
+        .and_then(|b| b.data_sources.get(&data_source_id))
+        .map(|ds| matches!(ds, DataSourceConfig::GraphQL { .. }));
+    match is_graphql {
+        Some(true) => {}
+        Some(false) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Only GraphQL data sources can be scaffolded"})),
+            )
+                .into_response()
+        }
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Data source not found"})),
+            )
+                .into_response()
+        }
+    }
+
+    let data_source = match state.data_source(&backoffice_id, &data_source_id).await {
+        Ok(ds) => ds,
+        Err(e) => return data_source_error_response(&e),
+    };
+
+    match scaffold::scaffold_graphql(data_source.as_ref(), &data_source_id).await {
+        Ok(sections) => {
+            info!(
+                backoffice_id = %backoffice_id,
+                data_source = %data_source_id,
+                sections = sections.len(),
+                "Scaffolded sections from GraphQL schema"
+            );
+            Json(serde_json::json!({"sections": sections})).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "GraphQL introspection failed");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// List a database data source's migrations and whether each is applied
+///
+/// Callers need the admin scope.
+async fn migrations_status_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, data_source_id)): Path<(String, String)>,
+    auth: AuthContext,
+) -> impl IntoResponse {
+    if !is_admin(&state, &auth) {
+        return admin_required();
+    }
+    let ds_config = match state
+        .backoffice(&backoffice_id)
+        .and_then(|b| b.data_sources.get(&data_source_id))
+    {
+        Some(ds) => ds,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Data source not found"})),
+            )
+                .into_response()
+        }
+    };
+    if !matches!(
+        ds_config,
+        DataSourceConfig::Database {
+            migrations: Some(_),
+            ..
+        }
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "No migrations are configured for this data source"})),
+        )
+            .into_response();
+    }
+
+    match migrations::status(ds_config).await {
+        Ok(migrations) => {
+            let pending = migrations.iter().filter(|m| !m.applied).count();
+            Json(serde_json::json!({"migrations": migrations, "pending": pending})).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to read migration status");
+            data_source_error_response(&e)
+        }
+    }
+}
+
+/// Health of a data source, as reported by the status endpoint
+#[derive(Serialize)]
+struct DataSourceStatus<'a> {
+    name: &'a str,
+    #[serde(rename = "type")]
+    source_type: Value,
+    init: InitMode,
+    #[serde(flatten)]
+    health: DataSourceHealth,
+}
+
+/// Health of every data source of a backoffice
+///
+/// Callers without the admin scope only see each source's health state,
+/// not its errors, latencies and counters.
+async fn data_sources_status_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+) -> impl IntoResponse {
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Backoffice not found"})),
+        )
+            .into_response();
+    };
+
+    let mut data_sources: Vec<DataSourceStatus> = backoffice
+        .data_sources
+        .iter()
+        .map(|(name, config)| DataSourceStatus {
+            name,
+            source_type: serde_json::to_value(config)
+                .ok()
+                .and_then(|c| c.get("type").cloned())
+                .unwrap_or(Value::Null),
+            init: config.init(),
+            health: state.data_sources.health(config),
+        })
+        .collect();
+    data_sources.sort_by(|a, b| a.name.cmp(b.name));
+
+    // Eager sources are expected to work from startup on
+    let degraded = data_sources
+        .iter()
+        .any(|s| s.init == InitMode::Eager && s.health.state == HealthState::Unhealthy);
+
+    let data_sources = if is_admin(&state, &auth) {
+        serde_json::json!(data_sources)
+    } else {
+        data_sources
+            .iter()
+            .map(|s| serde_json::json!({"name": s.name, "state": s.health.state}))
+            .collect()
+    };
+
+    Json(serde_json::json!({ "degraded": degraded, "data_sources": data_sources })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct DashboardQuery {
+    /// Comma-separated ids of the widgets to run; all of them by default
+    widgets: Option<String>,
+}
+
+/// Run the widgets of a backoffice's dashboard concurrently and return
+/// their datasets
+///
+/// Widgets whose action needs scopes the caller lacks are left out. A
+/// failing widget carries its `error` without failing the others.
+async fn dashboard_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+    Query(query): Query<DashboardQuery>,
+) -> impl IntoResponse {
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Backoffice not found"})),
+        )
+            .into_response();
+    };
+    let Some(dashboard) = &backoffice.dashboard else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Backoffice has no dashboard"})),
+        )
+            .into_response();
+    };
+
+    let requested: Option<Vec<&str>> = query
+        .widgets
+        .as_deref()
+        .map(|ids| ids.split(',').map(str::trim).collect());
+    let permitted = |widget: &WidgetConfig| {
+        let disabled =
+            state
+                .feature_flags
+                .disabled(backoffice, &widget.section, Some(&widget.action));
+        disabled.is_none()
+            && backoffice
+                .sections
+                .iter()
+                .find(|s| s.id == widget.section)
+                .and_then(|s| s.actions.iter().find(|a| a.id == widget.action))
+                .is_none_or(|action| auth.has_all_scopes(&action.required_scopes))
+    };
+    let datasets = futures_util::future::join_all(
+        dashboard
+            .widgets
+            .iter()
+            .filter(|w| {
+                requested
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&w.id.as_str()))
+            })
+            .filter(|w| permitted(w))
+            .map(|w| widget_dataset(&state, &backoffice_id, w, &auth)),
+    )
+    .await;
+
+    Json(serde_json::json!({ "widgets": datasets })).into_response()
+}
+
+/// A widget's configuration with the rows (and, for summaries, the chart
+/// dataset) of its action, or the action's error
+async fn widget_dataset(
+    state: &Arc<AppState>,
+    backoffice_id: &str,
+    widget: &WidgetConfig,
+    auth: &AuthContext,
+) -> Value {
+    let query = ActionQuery {
+        page: None,
+        page_size: None,
+        sort_by: None,
+        sort_order: None,
+        expand: None,
+        locale: None,
+        params: widget.params.clone(),
+    };
+    let response = query_action(
+        State(state.clone()),
+        Path((
+            backoffice_id.to_string(),
+            widget.section.clone(),
+            widget.action.clone(),
+        )),
+        auth.clone(),
+        Query(query),
+    )
+    .await
+    .into_response();
+
+    let status = response.status();
+    let body: Value = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        Err(_) => Value::Null,
+    };
+    let mut dataset = serde_json::to_value(widget).unwrap_or_else(|_| serde_json::json!({}));
+    if status.is_success() {
+        dataset["data"] = body["data"].clone();
+        if let Some(chart) = body.get("chart") {
+            dataset["dataset"] = chart.clone();
+        }
+    } else {
+        warn!(widget = %widget.id, status = %status, "Dashboard widget failed");
+        dataset["error"] = body
+            .get("error")
+            .cloned()
+            .unwrap_or_else(|| Value::String(status.to_string()));
+    }
+    dataset
+}
+
+/// Insert the seed fixtures of every section of a backoffice
+///
+/// Callers need the scopes of each seeded section's form action.
+async fn seed_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+) -> impl IntoResponse {
+    let backoffice = match state.backoffice(&backoffice_id) {
+        Some(b) => b,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Backoffice not found"})),
+            )
+                .into_response()
+        }
+    };
+
+    let permitted = backoffice
+        .sections
+        .iter()
+        .filter(|s| !s.seeds.is_empty())
+        .filter_map(seeds::seed_action)
+        .all(|action| auth.has_all_scopes(&action.required_scopes));
+    if !permitted {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response();
+    }
+
+    match seeds::seed_backoffice(backoffice, &state.data_sources).await {
+        Ok(reports) => {
+            for report in reports.iter().filter(|r| r.inserted > 0) {
+                state
+                    .query_cache
+                    .invalidate_section(backoffice, &report.section_id)
+                    .await;
+            }
+            let inserted: usize = reports.iter().map(|r| r.inserted).sum();
+            let failed: usize = reports.iter().map(|r| r.failed.len()).sum();
+            info!(
+                backoffice_id = %backoffice_id,
+                inserted = inserted,
+                failed = failed,
+                "Seeded backoffice"
+            );
+            Json(serde_json::json!({
+                "sections": reports,
+                "inserted": inserted,
+                "failed": failed,
+            }))
+            .into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Seeding failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IntegrityQuery {
+    fix: Option<IntegrityFix>,
+}
+
+/// Report orphaned rows and dangling junction entries of a backoffice
+///
+/// Callers need the scopes of every action of the related sections.
+async fn integrity_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+) -> Response {
+    audit_integrity(&state, &backoffice_id, &auth, None).await
+}
+
+/// Audit a backoffice and remediate its issues with `?fix=set_null|delete`
+async fn integrity_fix_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+    Query(query): Query<IntegrityQuery>,
+) -> Response {
+    let Some(fix) = query.fix else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Missing fix; expected set_null or delete"})),
+        )
+            .into_response();
+    };
+    audit_integrity(&state, &backoffice_id, &auth, Some(fix)).await
+}
+
+async fn audit_integrity(
+    state: &AppState,
+    backoffice_id: &str,
+    auth: &AuthContext,
+    fix: Option<IntegrityFix>,
+) -> Response {
+    let Some(backoffice) = state.backoffice(backoffice_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Backoffice not found"})),
+        )
+            .into_response();
+    };
+
+    let related: HashSet<&str> = backoffice
+        .relationships
+        .iter()
+        .flat_map(|r| [r.from_section.as_str(), r.to_section.as_str()])
+        .collect();
+    let permitted = backoffice
+        .sections
+        .iter()
+        .filter(|s| related.contains(s.id.as_str()))
+        .flat_map(|s| &s.actions)
+        .all(|action| auth.has_all_scopes(&action.required_scopes));
+    if !permitted {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response();
+    }
+
+    match integrity::audit_backoffice(backoffice, &state.data_sources, fix).await {
+        Ok(report) => {
+            if fix.is_some() {
+                for section in &related {
+                    state
+                        .query_cache
+                        .invalidate_section(backoffice, section)
+                        .await;
+                }
+            }
+            Json(report).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Integrity audit failed");
+            data_source_error_response(&e)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditSearchQuery {
+    section: Option<String>,
+    operation: Option<AuditOperation>,
+    record_id: Option<String>,
+    user: Option<String>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD` (start of day)
+    from: Option<String>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD` (end of day)
+    to: Option<String>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    #[serde(default)]
+    sort: AuditSortField,
+    /// `asc` or `desc` (default)
+    order: Option<String>,
+}
+
+/// Parse an audit date bound; plain dates cover the whole day
+fn parse_audit_bound(value: &str, end_of_day: bool) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&chrono::Utc));
+    }
+    let day = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let time = if end_of_day {
+        day.and_hms_nano_opt(23, 59, 59, 999_999_999)?
+    } else {
+        day.and_hms_opt(0, 0, 0)?
+    };
+    Some(time.and_utc())
+}
+
+/// Search the audit entries of a backoffice in the configured audit storage
+///
+/// Only entries of sections whose actions the caller has every scope of
+/// are returned.
+async fn audit_search_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+    Query(query): Query<AuditSearchQuery>,
+) -> Response {
+    let bad_request = |message: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": message})),
+        )
+            .into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Backoffice not found"})),
+        )
+            .into_response();
+    };
+
+    let sections: Vec<String> = backoffice
+        .sections
+        .iter()
+        .filter(|s| {
+            s.actions
+                .iter()
+                .all(|a| auth.has_all_scopes(&a.required_scopes))
+        })
+        .map(|s| s.id.clone())
+        .collect();
+    if let Some(section) = &query.section {
+        if !backoffice.sections.iter().any(|s| s.id == *section) {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Section not found"})),
+            )
+                .into_response();
+        }
+        if !sections.contains(section) {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({"error": "Missing required scopes"})),
+            )
+                .into_response();
+        }
+    }
+
+    let mut bounds = [None, None];
+    for (bound, (value, end_of_day)) in bounds
+        .iter_mut()
+        .zip([(&query.from, false), (&query.to, true)])
+    {
+        if let Some(value) = value {
+            match parse_audit_bound(value, end_of_day) {
+                Some(parsed) => *bound = Some(parsed),
+                None => {
+                    return bad_request(format!(
+                        "Invalid date '{}'; expected RFC 3339 or YYYY-MM-DD",
+                        value
+                    ))
+                }
+            }
+        }
+    }
+    let descending = match query.order.as_deref() {
+        None | Some("desc") => true,
+        Some("asc") => false,
+        Some(other) => {
+            return bad_request(format!("Invalid order '{}'; expected asc or desc", other))
+        }
+    };
+
+    let filter = AuditFilter {
+        sections,
+        section_id: query.section,
+        operation: query.operation,
+        record_id: query.record_id,
+        user_id: query.user,
+        from: bounds[0],
+        to: bounds[1],
+    };
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query
+        .page_size
+        .unwrap_or(50)
+        .clamp(1, data_source::MAX_PAGE_SIZE);
+    let pagination = data_source::PaginationParams::new(page, page_size);
+
+    match state
+        .audit_logger
+        .search(&filter, query.sort, descending, &pagination)
+        .await
+    {
+        Ok(results) => Json(serde_json::json!({
+            "data": results.entries,
+            "pagination": {
+                "page": page,
+                "page_size": page_size,
+                "total_items": results.total,
+                "total_pages": results.total.div_ceil(page_size),
+            }
+        }))
+        .into_response(),
+        Err(e) => {
+            error!(error = %e, "Audit search failed");
+            data_source_error_response(&e)
+        }
+    }
+}
+
+/// History of the config file of a backoffice and the caller allowed to
+/// read or restore it
+async fn config_history_of(
+    state: &AppState,
+    backoffice_id: &str,
+    auth: &AuthContext,
+) -> Result<(ConfigHistory, History), Response> {
+    let history = ConfigHistory::new(&state.config.config_history);
+    let error = |status: StatusCode, message: String| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    if !history.is_enabled() {
+        return Err(error(
+            StatusCode::NOT_FOUND,
+            "Config history is disabled".to_string(),
+        ));
+    }
+    if !auth.has_scope(history.scope()) {
+        return Err(error(
+            StatusCode::FORBIDDEN,
+            "Missing required scopes".to_string(),
+        ));
+    }
+    match history.find(backoffice_id).await {
+        Ok(Some(versions)) => Ok((history, versions)),
+        Ok(None) => Err(error(
+            StatusCode::NOT_FOUND,
+            "Backoffice not found".to_string(),
+        )),
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+/// Versions of a backoffice's config file, newest first
+async fn config_versions_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+) -> Response {
+    match config_history_of(&state, &backoffice_id, &auth).await {
+        Ok((_, history)) => {
+            let versions: Vec<_> = history.versions.into_iter().rev().collect();
+            Json(serde_json::json!({"file": history.file, "versions": versions})).into_response()
+        }
+        Err(response) => response,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RollbackQuery {
+    version: u64,
+}
+
+/// Write a prior version back to a backoffice's config file
+///
+/// Only authenticated callers may roll back, so security must be enabled.
+/// The restored file is served from the next start, which the 202 response
+/// says with `restart_required`.
+async fn config_rollback_handler(
+    State(state): State<Arc<AppState>>,
+    Path(backoffice_id): Path<String>,
+    auth: AuthContext,
+    Query(query): Query<RollbackQuery>,
+) -> Response {
+    if !auth.is_enforced() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Config rollback needs security enabled"})),
+        )
+            .into_response();
+    }
+    let (config_history, history) = match config_history_of(&state, &backoffice_id, &auth).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+    let Some(version) = history
+        .versions
+        .iter()
+        .find(|version| version.version == query.version)
+    else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "Version not found"})),
+        )
+            .into_response();
+    };
+
+    let author = auth.user_id.as_deref().unwrap_or("anonymous");
+    match config_history.restore(&history.file, version, author).await {
+        Ok(Some(restored)) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "version": restored,
+                "restart_required": true,
+                "message": "Restart the server to serve the restored config",
+            })),
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": format!("Version {} is the current version", query.version)
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    page: Option<usize>,
+    page_size: Option<usize>,
+    /// `asc` (default, oldest first) or `desc`
+    order: Option<String>,
+}
+
+/// Timeline of a record replayed from its audit entries
+///
+/// Each event has who made the change, its field-level diff and the values
+/// the record had afterwards. Requires the scopes of every action of the
+/// section, like the audit search.
+async fn record_history_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, record_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    let not_found = |what: &str| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": format!("{} not found", what)})),
+        )
+            .into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return not_found("Backoffice");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return not_found("Section");
+    };
+    if !section
+        .actions
+        .iter()
+        .all(|a| auth.has_all_scopes(&a.required_scopes))
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "Missing required scopes"})),
+        )
+            .into_response();
+    }
+    let descending = match query.order.as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!("Invalid order '{}'; expected asc or desc", other)
+                })),
+            )
+                .into_response()
+        }
+    };
+
+    let filter = AuditFilter {
+        sections: vec![section_id.clone()],
+        section_id: Some(section_id),
+        record_id: Some(record_id),
+        ..Default::default()
+    };
+    // The whole timeline is replayed, so values are right on every page
+    let entries = match state
+        .audit_logger
+        .search(
+            &filter,
+            AuditSortField::Timestamp,
+            false,
+            &data_source::PaginationParams::new(1, audit::MAX_SCANNED_ENTRIES),
+        )
+        .await
+    {
+        Ok(results) => results.entries,
+        Err(e) => {
+            error!(error = %e, "Record history lookup failed");
+            return data_source_error_response(&e);
+        }
+    };
+
+    let mut events = audit::record_history(entries);
+    if descending {
+        events.reverse();
+    }
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query
+        .page_size
+        .unwrap_or(50)
+        .clamp(1, data_source::MAX_PAGE_SIZE);
+    let total = events.len();
+    let events: Vec<_> = events
+        .into_iter()
+        .skip((page - 1).saturating_mul(page_size))
+        .take(page_size)
+        .collect();
+
+    Json(serde_json::json!({
+        "data": events,
+        "pagination": {
+            "page": page,
+            "page_size": page_size,
+            "total_items": total,
+            "total_pages": total.div_ceil(page_size),
+        }
+    }))
+    .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct RestoreQuery {
+    /// Id of the audit entry whose old values are re-applied
+    version: String,
+    /// Form action the values go through; the section's first form by default
+    action: Option<String>,
+}
+
+/// Put a record back to the values it had before an audited change
+///
+/// The old values of the chosen entry go through the form action like any
+/// update, so validation, hooks, permissions and auditing apply; the new
+/// audit entry names the restored one in `restored_from`. Sensitive fields
+/// are masked in audit entries and left untouched. Sections need
+/// `audit.enable_rollback`.
+async fn restore_record_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, record_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    mut request: RequestMetadata,
+    Query(query): Query<RestoreQuery>,
+) -> Response {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "Backoffice not found");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return error(StatusCode::NOT_FOUND, "Section not found");
+    };
+    if !section.audit.as_ref().is_some_and(|a| a.enable_rollback) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Rollback is not enabled for this section",
+        );
+    }
+    let Some(action) = section.actions.iter().find(|a| match &query.action {
+        Some(id) => a.id == *id,
+        None => matches!(a.action_type, ActionType::Form { .. }),
+    }) else {
+        return error(StatusCode::NOT_FOUND, "Form action not found");
+    };
+    if !matches!(action.action_type, ActionType::Form { .. }) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Records are restored through form actions",
+        );
+    }
+    // Old values are audit data, readable with the audit history's scopes
+    if !section
+        .actions
+        .iter()
+        .all(|a| auth.has_all_scopes(&a.required_scopes))
+    {
+        return error(StatusCode::FORBIDDEN, "Missing required scopes");
+    }
+
+    let filter = AuditFilter {
+        sections: vec![section_id.clone()],
+        section_id: Some(section_id.clone()),
+        record_id: Some(record_id.clone()),
+        ..Default::default()
+    };
+    let entries = match state
+        .audit_logger
+        .search(
+            &filter,
+            AuditSortField::Timestamp,
+            false,
+            &data_source::PaginationParams::new(1, audit::MAX_SCANNED_ENTRIES),
+        )
+        .await
+    {
+        Ok(results) => results.entries,
+        Err(e) => {
+            error!(error = %e, "Audit lookup for restore failed");
+            return data_source_error_response(&e);
+        }
+    };
+    let Some(entry) = entries.into_iter().find(|e| e.id == query.version) else {
+        return error(StatusCode::NOT_FOUND, "Audit entry not found");
+    };
+    if entry.operation == AuditOperation::Delete {
+        return error(StatusCode::CONFLICT, "Deleted records can't be restored");
+    }
+    let Some(old_values) = entry.old_values else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "The audit entry has no old values to restore",
+        );
+    };
+
+    let mut data: HashMap<String, Value> = action
+        .action_type
+        .fields()
+        .iter()
+        .filter(|f| !f.sensitive && f.id != "id")
+        .filter_map(|f| Some((f.id.clone(), old_values.get(&f.id)?.clone())))
+        .collect();
+    data.insert(
+        "id".to_string(),
+        old_values
+            .get("id")
+            .cloned()
+            .unwrap_or(Value::String(record_id)),
+    );
+    info!(
+        section_id = %section_id,
+        version = %query.version,
+        "Restoring record from audit entry"
+    );
+    request
+        .extra
+        .insert("restored_from".to_string(), query.version);
+
+    execute_mutation_handler(
+        State(state.clone()),
+        Path((backoffice_id, section_id, action.id.clone())),
+        auth,
+        request,
+        Json(MutationData {
+            data,
+            accept_warnings: true,
+        }),
+    )
+    .await
+    .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldPatchQuery {
+    /// Form action the change goes through; the section's first form with
+    /// the field by default
+    action: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldPatch {
+    value: Value,
+    #[serde(default)]
+    accept_warnings: bool,
+}
+
+/// Change a single field of a record, as inline cell edits in lists do
+///
+/// The change goes through the form action like any update, with only the
+/// edited field validated and written, so permissions, row filters, hooks,
+/// auditing and cache invalidation apply. Fields that aren't `editable`
+/// can't be changed.
+async fn patch_field_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, record_id, field_id)): Path<(String, String, String, String)>,
+    auth: AuthContext,
+    request: RequestMetadata,
+    Query(query): Query<FieldPatchQuery>,
+    Json(patch): Json<FieldPatch>,
+) -> Response {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "Backoffice not found");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return error(StatusCode::NOT_FOUND, "Section not found");
+    };
+    let Some(action) = section.actions.iter().find(|a| match &query.action {
+        Some(id) => a.id == *id,
+        None => {
+            matches!(a.action_type, ActionType::Form { .. })
+                && a.action_type.fields().iter().any(|f| f.id == field_id)
+        }
+    }) else {
+        return error(StatusCode::NOT_FOUND, "Form action not found");
+    };
+    if !matches!(action.action_type, ActionType::Form { .. }) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Fields are changed through form actions",
+        );
+    }
+    let Some(field) = action
+        .action_type
+        .fields()
+        .iter()
+        .find(|f| f.id == field_id)
+    else {
+        return error(StatusCode::NOT_FOUND, "Field not found");
+    };
+    if !field.editable || field.id == "id" {
+        return error(StatusCode::BAD_REQUEST, "Field is not editable");
+    }
+
+    info!(section_id = %section_id, field_id = %field_id, "Patching record field");
+    let data = HashMap::from([
+        ("id".to_string(), Value::String(record_id)),
+        (field_id, patch.value),
+    ]);
+    mutate(
+        state.clone(),
+        (backoffice_id, section_id, action.id.clone()),
+        auth,
+        request,
+        MutationData {
+            data,
+            accept_warnings: patch.accept_warnings,
+        },
+        true,
+    )
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct DuplicateQuery {
+    /// Form action inserting the copy; the one of the section's `duplicate`
+    /// config by default
+    action: Option<String>,
+}
+
+/// Copy a record as a new one
+///
+/// The copy goes through a form action like any create, so validation,
+/// hooks, permissions and auditing apply; its audit entry names the
+/// original in `duplicated_from`. Only fields the caller can read and write
+/// are copied. Sections need a `duplicate` config.
+async fn duplicate_record_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, record_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    mut request: RequestMetadata,
+    Query(query): Query<DuplicateQuery>,
+) -> Response {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "Backoffice not found");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return error(StatusCode::NOT_FOUND, "Section not found");
+    };
+    let Some(config) = &section.duplicate else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Duplication is not enabled for this section",
+        );
+    };
+    let action_id = query.action.as_ref().or(config.action.as_ref());
+    let Some(action) = section.actions.iter().find(|a| match action_id {
+        Some(id) => a.id == *id,
+        None => matches!(
+            &a.action_type,
+            ActionType::Form { config, .. } if matches!(config.form_mode, FormMode::Create)
+        ),
+    }) else {
+        return error(StatusCode::NOT_FOUND, "Form action not found");
+    };
+    if !matches!(action.action_type, ActionType::Form { .. }) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Records are duplicated through form actions",
+        );
+    }
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return error(StatusCode::FORBIDDEN, "Missing required scopes");
+    }
+
+    // Only records the caller can see may be copied
+    let record_id = Value::String(record_id);
+    let record = match visible_record(&state, backoffice, section, action, &auth, &record_id).await
+    {
+        Ok(record) => record,
+        Err(response) => return response,
+    };
+
+    let fields: Vec<FieldConfig> = action
+        .action_type
+        .fields()
+        .iter()
+        .filter(|f| permissions::can_read(f, &auth) && permissions::can_write(f, &auth))
+        .cloned()
+        .collect();
+    let data = duplicate::copy(&record, &fields, config);
+    info!(section_id = %section_id, record_id = %record_id, "Duplicating record");
+    request.extra.insert(
+        duplicate::DUPLICATED_FROM.to_string(),
+        audit_record_id(&record_id),
+    );
+
+    mutate(
+        state.clone(),
+        (backoffice_id, section_id, action.id.clone()),
+        auth,
+        request,
+        MutationData {
+            data,
+            accept_warnings: false,
+        },
+        false,
+    )
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkUpdateRequest {
+    /// Field values the records must have, like the filters of lists
+    #[serde(default)]
+    filter: HashMap<String, Value>,
+    data: HashMap<String, Value>,
+    /// Fail unless exactly this many records match the filter
+    expected_count: Option<u64>,
+    /// Update every matching record, however many there are
+    #[serde(default)]
+    confirm: bool,
+}
+
+/// Set fields of every record matching a filter with a single update
+///
+/// The data is validated with the form action's fields it sets, which must
+/// be editable; field permissions and row filters apply, and the update is
+/// audited once with the filter and the number of records updated. As a
+/// safeguard, requests need an `expected_count` matching the number of
+/// records the filter selects, or `confirm: true`. Database data sources
+/// only; per-record hooks don't run.
+async fn bulk_update_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    request: RequestMetadata,
+    Json(mut payload): Json<BulkUpdateRequest>,
+) -> Response {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "Backoffice not found");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return error(StatusCode::NOT_FOUND, "Section not found");
+    };
+    let Some(action) = section.actions.iter().find(|a| a.id == action_id) else {
+        return error(StatusCode::NOT_FOUND, "Action not found");
+    };
+    let ActionType::Form { fields, .. } = &action.action_type else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Bulk updates go through form actions",
+        );
+    };
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return error(StatusCode::FORBIDDEN, "Missing required scopes");
+    }
+    if payload.expected_count.is_none() && !payload.confirm {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Bulk updates need an expected_count or confirm: true",
+        );
+    }
+    let Some(DataSourceConfig::Database { db_type, .. }) =
+        backoffice.data_sources.get(&action.data_source)
+    else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Bulk updates need a database data source",
+        );
+    };
+
+    // Only editable fields are set; state fields move record by record
+    let mut submitted = Vec::new();
+    for id in payload.data.keys() {
+        match fields.iter().find(|f| &f.id == id) {
+            Some(field) if field.editable && state_machine::state_config(field).is_none() => {
+                submitted.push(field.clone())
+            }
+            _ => {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Field '{}' can't be bulk updated", id),
+                )
+            }
+        }
+    }
+    if submitted.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "No fields to update");
+    }
+    // Records are selected by the form's fields or the section's list filters
+    let filterable = |name: &str| {
+        name == "id"
+            || fields.iter().any(|f| f.id == name)
+            || section.actions.iter().any(|a| match &a.action_type {
+                ActionType::List { config, .. } => config.filters.iter().any(|f| f.field == name),
+                _ => false,
+            })
+    };
+    if let Some(name) = payload.filter.keys().find(|name| !filterable(name)) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            &format!("Unknown filter field '{}'", name),
+        );
+    }
+
+    let write_policy = state
+        .config
+        .security
+        .as_ref()
+        .map(|s| s.unauthorized_field_writes.clone())
+        .unwrap_or_default();
+    if let Err(forbidden) =
+        permissions::enforce_writable(&mut payload.data, &submitted, &auth, &write_policy)
+    {
+        warn!(fields = ?forbidden, "Bulk update touches fields the caller cannot write");
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Insufficient scopes to write fields",
+                "fields": forbidden
+            })),
+        )
+            .into_response();
+    }
+    let functions = state.validation_functions(&backoffice.id);
+    let validation_errors =
+        validation::validate_data_with_functions(&payload.data, &submitted, &functions).await;
+    match validation_errors {
+        Ok(errors) if !errors.is_empty() => {
+            warn!(error_count = errors.len(), "Bulk update validation failed");
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "Validation failed",
+                    "validation_errors": localized_errors(&state, errors, request.locale.as_deref())
+                })),
+            )
+                .into_response();
+        }
+        Ok(_) => {}
+        Err(e) => {
+            error!(error = %e, "Validation error");
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Validation error: {}", e),
+            );
+        }
+    }
+    phone::normalize(&mut payload.data, &submitted);
+    card::mask_payload(&mut payload.data, &submitted);
+
+    // Only records the caller can see are updated, and they stay theirs
+    let row_conditions = match row_filters::resolve(&section.row_filters, &auth) {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            warn!(error = %e, "Row filters could not be resolved");
+            return error(StatusCode::FORBIDDEN, &e.to_string());
+        }
+    };
+    row_filters::apply_to_payload(&mut payload.data, &row_conditions);
+    let bulk = match crud::bulk_update(
+        section,
+        action,
+        db_type,
+        &payload.data,
+        &payload.filter,
+        &row_conditions,
+    ) {
+        Ok(bulk) => bulk,
+        Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+
+    let data_source = match state
+        .data_sources
+        .create(&backoffice.data_sources[&action.data_source])
+        .await
+    {
+        Ok(ds) => ds,
+        Err(e) => {
+            error!(error = %e, "Failed to create data source");
+            return data_source_error_response(&e);
+        }
+    };
+    // With an expected count the update rolls back unless it affects exactly
+    // that many records, so none can change between counting and updating
+    info!(section_id = %section_id, query = %bulk.update, "Executing bulk update");
+    let update = data_source::Mutation {
+        query: bulk.update,
+        data: bulk.params,
+        expected_rows: payload.expected_count,
+        ..Default::default()
+    };
+    let affected = match data_source
+        .execute_transaction(std::slice::from_ref(&update))
+        .await
+    {
+        Ok(results) => results.first().and_then(Value::as_u64).unwrap_or_default(),
+        Err(e) => {
+            if let Some(mismatch) = e.downcast_ref::<data_source::UnexpectedRowCount>() {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(serde_json::json!({
+                        "error": mismatch.to_string(),
+                        "count": mismatch.affected
+                    })),
+                )
+                    .into_response();
+            }
+            error!(error = %e, "Bulk update failed");
+            return data_source_error_response(&e);
+        }
+    };
+    state
+        .query_cache
+        .invalidate_section(backoffice, &section_id)
+        .await;
+    state.events.publish(PushEvent::mutation(
+        &backoffice_id,
+        &section_id,
+        AuditOperation::Update,
+        None,
+        auth.user_id.clone(),
+    ));
+
+    if AuditLogger::should_audit(&section.audit, &AuditOperation::Update) {
+        let sensitive = section_fields(section);
+        let mut entry = AuditLogger::bulk_update_entry(
+            section_id.clone(),
+            &masking::masked(&payload.filter, &sensitive),
+            &masking::masked(&payload.data, &sensitive),
+            affected,
+            auth.user_id.clone(),
+        );
+        request.attach(&mut entry, &action_id);
+        if let Err(e) = state.audit_logger.log(entry).await {
+            warn!(error = %e, "Failed to log audit entry");
+        }
+    }
+
+    Json(serde_json::json!({"success": true, "affected": affected})).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportQuery {
+    /// Format of the file, instead of the one of the action's import config
+    format: Option<FileFormat>,
+    /// Validate every row and report the errors without writing anything
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Create a record per row of an uploaded CSV, JSON or JSONL file
+///
+/// Columns are mapped to the form action's fields by its `import` config,
+/// and every row is validated as a submitted form would be, with field
+/// permissions and row filters applied; errors are reported by row. Rows
+/// are created all or nothing, in a transaction when the data source
+/// supports them. Per-record hooks don't run.
+async fn import_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
+    Query(query): Query<ImportQuery>,
+    auth: AuthContext,
+    request: RequestMetadata,
+    body: String,
+) -> Response {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "Backoffice not found");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return error(StatusCode::NOT_FOUND, "Section not found");
+    };
+    let Some(action) = section.actions.iter().find(|a| a.id == action_id) else {
+        return error(StatusCode::NOT_FOUND, "Action not found");
+    };
+    let ActionType::Form { fields, config, .. } = &action.action_type else {
+        return error(StatusCode::BAD_REQUEST, "Imports go through form actions");
+    };
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return error(StatusCode::FORBIDDEN, "Missing required scopes");
+    }
+    let Some(ds_config) = backoffice.data_sources.get(&action.data_source) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "Data source not found");
+    };
+
+    let format = query.format.as_ref().unwrap_or(&config.import.format);
+    let mut imported = match imports::read(&body, format, &config.import, fields) {
+        Ok(imported) => imported,
+        Err(e) => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                &format!("Failed to read the file: {}", e),
+            )
+        }
+    };
+    if imported.rows.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "The file has no rows");
+    }
+
+    let write_policy = state
+        .config
+        .security
+        .as_ref()
+        .map(|s| s.unauthorized_field_writes.clone())
+        .unwrap_or_default();
+    let row_conditions = match row_filters::resolve(&section.row_filters, &auth) {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            warn!(error = %e, "Row filters could not be resolved");
+            return error(StatusCode::FORBIDDEN, &e.to_string());
+        }
+    };
+    let functions = state.validation_functions(&backoffice.id);
+    let names: Vec<&str> = backoffice.data_sources.keys().map(String::as_str).collect();
+    let data_sources = match create_data_sources(&state, backoffice, &names).await {
+        Ok(data_sources) => data_sources,
+        Err(response) => return response,
+    };
+
+    // Every row is checked, so the report covers the whole file
+    let mut errors = std::mem::take(&mut imported.errors);
+    for (row, data) in &mut imported.rows {
+        let mut report = |error: validation::ValidationError| {
+            // A value that could not be read is only reported once
+            if !errors
+                .iter()
+                .any(|e| e.row == *row && e.error.field == error.field)
+            {
+                errors.push(imports::ImportError { row: *row, error });
+            }
+        };
+        if let Err(forbidden) = permissions::enforce_writable(data, fields, &auth, &write_policy) {
+            for field in forbidden {
+                report(validation::ValidationError::new(
+                    field,
+                    "write_forbidden",
+                    HashMap::new(),
+                ));
+            }
+            continue;
+        }
+        row_filters::apply_to_payload(data, &row_conditions);
+
+        let checks = match validation::validate_data_with_functions(data, fields, &functions).await
+        {
+            Ok(checks) if checks.is_empty() => {
+                phone::normalize(data, fields);
+                card::mask_payload(data, fields);
+                validation::validate_data_async(
+                    data,
+                    fields,
+                    section,
+                    &action.data_source,
+                    backoffice.data_sources.get(&action.data_source),
+                    &data_sources,
+                )
+                .await
+            }
+            checks => checks,
+        };
+        match checks {
+            Ok(checks) => checks.into_iter().for_each(&mut report),
+            Err(e) => {
+                error!(error = %e, "Validation error");
+                return error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &format!("Validation error: {}", e),
+                );
+            }
+        }
+        if let Err(transitions) = state_machine::check(fields, None, data, &auth) {
+            for transition in transitions {
+                report(validation::ValidationError::new(
+                    &transition.field,
+                    "initial_state",
+                    i18n::params([("state", transition.to.into())]),
+                ));
+            }
+        }
+    }
+    // Foreign keys are looked up for all rows at once
+    let rows: Vec<&HashMap<String, Value>> = imported.rows.iter().map(|(_, data)| data).collect();
+    match relationships::validate_foreign_keys_batch(&rows, &section_id, backoffice, &data_sources)
+        .await
+    {
+        Ok(checks) => {
+            for (index, check) in checks {
+                let row = imported.rows[index].0;
+                if !errors
+                    .iter()
+                    .any(|e| e.row == row && e.error.field == check.field)
+                {
+                    errors.push(imports::ImportError {
+                        row,
+                        error: relationship_error(check, "foreign_key"),
+                    });
+                }
+            }
+        }
+        Err(e) => {
+            error!(error = %e, "Relationship validation error");
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Relationship validation error: {}", e),
+            );
+        }
+    }
+    errors.sort_by_key(|e| e.row);
+    i18n::localize(
+        errors.iter_mut().map(|e| &mut e.error),
+        &state.config.i18n,
+        request.locale.as_deref(),
+    );
+
+    let rows = imported.rows.len();
+    let mut report = serde_json::json!({
+        "rows": rows,
+        "errors": errors,
+        "ignored_columns": imported.ignored_columns,
+    });
+    if query.dry_run {
+        report["dry_run"] = true.into();
+        report["valid"] = errors.is_empty().into();
+        return Json(report).into_response();
+    }
+    if !errors.is_empty() {
+        warn!(error_count = errors.len(), "Import validation failed");
+        report["error"] = "Validation failed".into();
+        return (StatusCode::BAD_REQUEST, Json(report)).into_response();
+    }
+
+    let mut mutations = Vec::with_capacity(rows);
+    for (_, data) in &imported.rows {
+        match crud::action_target(section, action, ds_config, CrudOperation::Create, data) {
+            Ok(query) => mutations.push(data_source::Mutation {
+                query,
+                data: data.clone(),
+                ..Default::default()
+            }),
+            Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+        }
+    }
+    let data_source = &data_sources[&action.data_source];
+    info!(section_id = %section_id, rows, "Importing records");
+    let results = if data_source.supports_transactions() {
+        data_source.execute_transaction(&mutations).await
+    } else {
+        let mut results = Vec::with_capacity(rows);
+        for mutation in &mutations {
+            match data_source
+                .execute_mutation(&mutation.query, &mutation.data)
+                .await
+            {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    // Earlier rows can't be rolled back without a transaction
+                    error!(error = %e, imported = results.len(), "Import failed");
+                    report["imported"] = results.len().into();
+                    report["error"] = format!("Import failed: {}", e).into();
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(report)).into_response();
+                }
+            }
+        }
+        Ok(results)
+    };
+    let results = match results {
+        Ok(results) => results,
+        Err(e) => {
+            error!(error = %e, "Import failed");
+            return data_source_error_response(&e);
+        }
+    };
+    state
+        .query_cache
+        .invalidate_section(backoffice, &section_id)
+        .await;
+    state.events.publish(PushEvent::mutation(
+        &backoffice_id,
+        &section_id,
+        AuditOperation::Create,
+        None,
+        auth.user_id.clone(),
+    ));
+
+    if AuditLogger::should_audit(&section.audit, &AuditOperation::Create) {
+        for ((_, data), result) in imported.rows.iter().zip(&results) {
+            let mut entry = AuditLogger::create_entry(
+                section_id.clone(),
+                result.as_str().map(|s| s.to_string()),
+                &masking::masked(data, fields),
+                auth.user_id.clone(),
+            );
+            request.attach(&mut entry, &action_id);
+            if let Err(e) = state.audit_logger.log(entry).await {
+                warn!(error = %e, "Failed to log audit entry");
+            }
+        }
+    }
+
+    report["success"] = true.into();
+    report["imported"] = rows.into();
+    Json(report).into_response()
+}
+
+/// A record read through an action's data source, if the caller's row
+/// filters let them see it
+async fn visible_record(
+    state: &AppState,
+    backoffice: &BackofficeConfig,
+    section: &SectionConfig,
+    action: &ActionConfig,
+    auth: &AuthContext,
+    record_id: &Value,
+) -> Result<HashMap<String, Value>, Response> {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let Some(ds_config) = backoffice.data_sources.get(&action.data_source) else {
+        return Err(error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Data source not found",
+        ));
+    };
+    let data_source = match state.data_sources.create(ds_config).await {
+        Ok(ds) => ds,
+        Err(e) => {
+            error!(error = %e, "Failed to create data source");
+            return Err(data_source_error_response(&e));
+        }
+    };
+
+    let row_conditions = match row_filters::resolve(&section.row_filters, auth) {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            warn!(error = %e, "Row filters could not be resolved");
+            return Err(error(StatusCode::FORBIDDEN, &e.to_string()));
+        }
+    };
+    match row_filters::record_visible(
+        data_source.as_ref(),
+        section,
+        &action.data_source,
+        ds_config,
+        record_id,
+        &row_conditions,
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => return Err(error(StatusCode::NOT_FOUND, "Record not found")),
+        Err(e) => {
+            error!(error = %e, "Failed to check record visibility");
+            return Err(error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()));
+        }
+    }
+    fetch_record(backoffice, section, action, data_source.as_ref(), record_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Record not found"))
+}
+
+#[derive(Debug, Deserialize)]
+struct TransitionsQuery {
+    /// Form action whose state fields are listed; the section's first form
+    /// with a state field by default
+    action: Option<String>,
+}
+
+/// The transitions a caller may make from a record's current states
+///
+/// Lists every state field of the form the caller can read, with the
+/// transitions out of the record's state whose scopes the caller holds;
+/// fields the caller cannot write have none.
+async fn record_transitions_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, record_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    Query(query): Query<TransitionsQuery>,
+) -> Response {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "Backoffice not found");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return error(StatusCode::NOT_FOUND, "Section not found");
+    };
+    let Some(action) = section.actions.iter().find(|a| match &query.action {
+        Some(id) => a.id == *id,
+        None => {
+            matches!(a.action_type, ActionType::Form { .. })
+                && a.action_type
+                    .fields()
+                    .iter()
+                    .any(|f| state_machine::state_config(f).is_some())
+        }
+    }) else {
+        return error(StatusCode::NOT_FOUND, "Form action not found");
+    };
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return error(StatusCode::FORBIDDEN, "Missing required scopes");
+    }
+
+    let record = match visible_record(
+        &state,
+        backoffice,
+        section,
+        action,
+        &auth,
+        &Value::String(record_id),
+    )
+    .await
+    {
+        Ok(record) => record,
+        Err(response) => return response,
+    };
+
+    let fields: Vec<Value> = action
+        .action_type
+        .fields()
+        .iter()
+        .filter(|f| permissions::can_read(f, &auth))
+        .filter_map(|f| {
+            let config = state_machine::state_config(f)?;
+            let current = state_machine::current_state(&record, &f.id);
+            let transitions: Vec<Value> = match &current {
+                Some(current) if permissions::can_write(f, &auth) => {
+                    state_machine::permitted(config, current, &auth)
+                        .into_iter()
+                        .map(|t| {
+                            let label = t.label.clone().or_else(|| {
+                                config
+                                    .states
+                                    .iter()
+                                    .find(|s| s.value == t.to)
+                                    .map(|s| s.label.clone())
+                            });
+                            serde_json::json!({"to": t.to, "label": label})
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            Some(serde_json::json!({
+                "field": f.id,
+                "current": current,
+                "transitions": transitions,
+            }))
+        })
+        .collect();
+
+    Json(serde_json::json!({ "fields": fields })).into_response()
+}
+
+/// Evaluate a form's conditions against the values entered so far
+///
+/// Returns whether each field the caller can read is visible, required and
+/// disabled, as validation will see them when the form is submitted.
+async fn evaluate_form_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    Json(payload): Json<MutationData>,
+) -> Response {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "Backoffice not found");
+    };
+    let Some(section) = backoffice.sections.iter().find(|s| s.id == section_id) else {
+        return error(StatusCode::NOT_FOUND, "Section not found");
+    };
+    let Some(action) = section.actions.iter().find(|a| a.id == action_id) else {
+        return error(StatusCode::NOT_FOUND, "Action not found");
+    };
+    let (ActionType::Form { fields, .. }
+    | ActionType::Custom { fields }
+    | ActionType::Pipeline { fields, .. }) = &action.action_type
+    else {
+        return error(StatusCode::BAD_REQUEST, "Action has no form");
+    };
+    if !auth.has_all_scopes(&action.required_scopes) {
+        return error(StatusCode::FORBIDDEN, "Missing required scopes");
+    }
+
+    let fields = form_state::evaluate(fields, &payload.data, &auth);
+    Json(serde_json::json!({ "fields": fields })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidateQuery {
+    /// Only check the fields present in the payload, as a form step does
+    #[serde(default)]
+    partial: bool,
+}
+
+/// What checking payloads against a form action without saving them needs
+struct DryRun<'a> {
+    backoffice: &'a BackofficeConfig,
+    section: &'a crate::config::SectionConfig,
+    action: &'a ActionConfig,
+    fields: &'a [FieldConfig],
+    auth: &'a AuthContext,
+    write_policy: FieldWritePolicy,
+    row_conditions: Vec<row_filters::RowCondition>,
+    functions: Arc<validation::ValidationFunctions>,
+    data_sources: HashMap<String, Box<dyn data_source::DataSource>>,
+}
+
+impl<'a> DryRun<'a> {
+    /// Look up a form action and prepare to validate payloads for it
+    async fn new(
+        state: &'a AppState,
+        (backoffice_id, section_id, action_id): &'a (String, String, String),
+        auth: &'a AuthContext,
+    ) -> Result<DryRun<'a>, Response> {
+        let error = |status: StatusCode, message: &str| {
+            (status, Json(serde_json::json!({"error": message}))).into_response()
+        };
+        let Some(backoffice) = state.backoffice(backoffice_id) else {
+            return Err(error(StatusCode::NOT_FOUND, "Backoffice not found"));
+        };
+        let Some(section) = backoffice.sections.iter().find(|s| s.id == *section_id) else {
+            return Err(error(StatusCode::NOT_FOUND, "Section not found"));
+        };
+        let Some(action) = section.actions.iter().find(|a| a.id == *action_id) else {
+            return Err(error(StatusCode::NOT_FOUND, "Action not found"));
+        };
+        let (ActionType::Form { fields, .. }
+        | ActionType::Custom { fields }
+        | ActionType::Pipeline { fields, .. }) = &action.action_type
+        else {
+            return Err(error(StatusCode::BAD_REQUEST, "Action has no form"));
+        };
+        if !auth.has_all_scopes(&action.required_scopes) {
+            return Err(error(StatusCode::FORBIDDEN, "Missing required scopes"));
+        }
+
+        let write_policy = state
+            .config
+            .security
+            .as_ref()
+            .map(|s| s.unauthorized_field_writes.clone())
+            .unwrap_or_default();
+        let row_conditions = match row_filters::resolve(&section.row_filters, auth) {
+            Ok(conditions) => conditions,
+            Err(e) => {
+                warn!(error = %e, "Row filters could not be resolved");
+                return Err(error(StatusCode::FORBIDDEN, &e.to_string()));
+            }
+        };
+        let functions = state.validation_functions(&backoffice.id);
+        let names: Vec<&str> = backoffice.data_sources.keys().map(String::as_str).collect();
+        let data_sources = create_data_sources(state, backoffice, &names).await?;
+
+        Ok(DryRun {
+            backoffice,
+            section,
+            action,
+            fields,
+            auth,
+            write_policy,
+            row_conditions,
+            functions,
+            data_sources,
+        })
+    }
+
+    /// Every problem with a payload, warnings included, checking `fields`
+    ///
+    /// Foreign keys are left to the caller when `foreign_keys` is false, so
+    /// they can be checked for many payloads at once.
+    async fn check(
+        &self,
+        data: &mut HashMap<String, Value>,
+        fields: &[FieldConfig],
+        foreign_keys: bool,
+    ) -> Result<Vec<validation::ValidationError>> {
+        let (backoffice, section_id) = (self.backoffice, self.section.id.as_str());
+        let mut nested = relationships::take_nested(data, section_id, backoffice);
+
+        let mut errors = Vec::new();
+        if let Err(forbidden) =
+            permissions::enforce_writable(data, fields, self.auth, &self.write_policy)
+        {
+            for field in forbidden {
+                errors.push(validation::ValidationError::new(
+                    field,
+                    "write_forbidden",
+                    HashMap::new(),
+                ));
+            }
+        }
+        row_filters::apply_to_payload(data, &self.row_conditions);
+
+        let checks =
+            validation::validate_data_with_functions(data, fields, &self.functions).await?;
+        let passed = checks.iter().all(|e| e.is_warning());
+        errors.extend(checks);
+        if passed {
+            // Uniqueness is checked against values as they would be stored
+            phone::normalize(data, fields);
+            card::mask_payload(data, fields);
+            errors.extend(
+                validation::validate_data_async(
+                    data,
+                    fields,
+                    self.section,
+                    &self.action.data_source,
+                    backoffice.data_sources.get(&self.action.data_source),
+                    &self.data_sources,
+                )
+                .await?,
+            );
+        }
+        errors.extend(
+            relationships::validate_nested(
+                &mut nested,
+                backoffice,
+                &self.functions,
+                self.auth,
+                &self.write_policy,
+            )
+            .await?,
+        );
+
+        let mut relationship_checks = Vec::new();
+        if foreign_keys {
+            let checks = relationships::validate_foreign_keys(
+                data,
+                section_id,
+                backoffice,
+                &self.data_sources,
+            )
+            .await?;
+            relationship_checks.extend(checks.into_iter().map(|c| (c, "foreign_key")));
+        }
+        match relationships::validate_many_to_many(data, section_id, backoffice, &self.data_sources)
+            .await
+        {
+            Ok(checks) => {
+                relationship_checks.extend(checks.into_iter().map(|c| (c, "many_to_many")))
+            }
+            Err(e) => error!(error = %e, "Many-to-many validation error"),
+        }
+        for (check, code) in relationship_checks {
+            errors.push(relationship_error(check, code));
+        }
+
+        errors.extend(
+            validation::warnings(data, fields)
+                .into_iter()
+                .map(|w| w.warn()),
+        );
+        Ok(errors)
+    }
+}
+
+fn relationship_error(
+    check: relationships::RelationshipError,
+    code: &str,
+) -> validation::ValidationError {
+    validation::ValidationError::new(&check.field, code, HashMap::new())
+        .with_message(&check.message)
+}
+
+/// Validation outcome of a payload as the validate endpoints report it
+fn validation_result(
+    state: &AppState,
+    errors: Vec<validation::ValidationError>,
+    locale: Option<&str>,
+) -> Value {
+    let (errors, warnings) = validation::split_warnings(errors);
+    serde_json::json!({
+        "valid": errors.is_empty(),
+        "validation_errors": localized_errors(state, errors, locale),
+        "warnings": localized_errors(state, warnings, locale),
+    })
+}
+
+/// Validate a payload as a mutation would, without executing it
+///
+/// Runs the field rules, uniqueness and remote checks, nested records and
+/// relationship checks, with field permissions and row filters applied.
+/// Every problem is reported rather than the first failing step's.
+async fn validate_handler(
+    State(state): State<Arc<AppState>>,
+    Path(ids): Path<(String, String, String)>,
+    Query(query): Query<ValidateQuery>,
+    auth: AuthContext,
+    request: RequestMetadata,
+    Json(payload): Json<MutationData>,
+) -> Response {
+    let dry_run = match DryRun::new(&state, &ids, &auth).await {
+        Ok(dry_run) => dry_run,
+        Err(response) => return response,
+    };
+    let mut data = payload.data;
+    let submitted: Vec<FieldConfig>;
+    let fields = if query.partial {
+        submitted = dry_run
+            .fields
+            .iter()
+            .filter(|f| data.contains_key(&f.id))
+            .cloned()
+            .collect();
+        &submitted
+    } else {
+        dry_run.fields
+    };
+
+    match dry_run.check(&mut data, fields, true).await {
+        Ok(errors) => {
+            Json(validation_result(&state, errors, request.locale.as_deref())).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Validation error");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Validation error: {}", e)})),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Most payloads a batch validation takes
+const MAX_VALIDATE_BATCH: usize = 10_000;
+
+/// Validate many payloads without executing them, as an import preflight
+///
+/// Each payload is checked as the validate endpoint does, except that
+/// foreign keys are looked up for the whole batch at once. Results are
+/// listed in the order of the payloads, with their index.
+async fn validate_batch_handler(
+    State(state): State<Arc<AppState>>,
+    Path(ids): Path<(String, String, String)>,
+    auth: AuthContext,
+    request: RequestMetadata,
+    Json(payloads): Json<Vec<MutationData>>,
+) -> Response {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    if payloads.len() > MAX_VALIDATE_BATCH {
+        return error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            &format!("Batches take at most {} payloads", MAX_VALIDATE_BATCH),
+        );
+    }
+    let dry_run = match DryRun::new(&state, &ids, &auth).await {
+        Ok(dry_run) => dry_run,
+        Err(response) => return response,
+    };
+
+    let mut rows: Vec<HashMap<String, Value>> = payloads.into_iter().map(|p| p.data).collect();
+    let mut errors = Vec::with_capacity(rows.len());
+    for data in rows.iter_mut() {
+        match dry_run.check(data, dry_run.fields, false).await {
+            Ok(checks) => errors.push(checks),
+            Err(e) => {
+                error!(error = %e, "Validation error");
+                return error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &format!("Validation error: {}", e),
+                );
+            }
+        }
+    }
+    let rows: Vec<&HashMap<String, Value>> = rows.iter().collect();
+    match relationships::validate_foreign_keys_batch(
+        &rows,
+        &dry_run.section.id,
+        dry_run.backoffice,
+        &dry_run.data_sources,
+    )
+    .await
+    {
+        Ok(checks) => {
+            for (index, check) in checks {
+                errors[index].push(relationship_error(check, "foreign_key"));
+            }
+        }
+        Err(e) => {
+            error!(error = %e, "Relationship validation error");
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Relationship validation error: {}", e),
+            );
+        }
+    }
+
+    let locale = request.locale.as_deref();
+    let results: Vec<Value> = errors
+        .into_iter()
+        .enumerate()
+        .map(|(index, errors)| {
+            let mut result = validation_result(&state, errors, locale);
+            result["index"] = index.into();
+            result
+        })
+        .collect();
+    let invalid = results.iter().filter(|r| r["valid"] == false).count();
+    Json(serde_json::json!({
+        "valid": invalid == 0,
+        "invalid": invalid,
+        "results": results,
+    }))
+    .into_response()
+}
+
+/// Lifetime of the presigned URL uploads are stored with
+const UPLOAD_PRESIGN_EXPIRY_SECS: u64 = 300;
+
+#[derive(Debug, Deserialize)]
+struct UploadQuery {
+    filename: String,
+}
+
+/// Store a file uploaded to a File or Image field of a form action in the
+/// field's `storage`, returning the key the record should keep
+///
+/// The body is the file itself. Its size and type are checked against the
+/// field, and with `antivirus` configured it is scanned first: infected
+/// files are rejected with 422 and the matched signature, and files clamd
+/// could not scan with 503, unless the field has `skip_virus_scan`.
+async fn upload_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, action_id, field_id)): Path<(String, String, String, String)>,
+    Query(query): Query<UploadQuery>,
+    auth: AuthContext,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    let error = |status: StatusCode, code: &str, message: &str| {
+        (
+            status,
+            Json(serde_json::json!({"error": message, "code": code, "field": field_id})),
+        )
+            .into_response()
+    };
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "not_found", "Backoffice not found");
+    };
+    let (action, field) = match upload_field(backoffice, &section_id, &action_id, &field_id) {
+        Ok(found) => found,
+        Err((status, code, message)) => return error(status, code, message),
+    };
+    if !auth.has_all_scopes(&action.required_scopes) || !permissions::can_write(field, &auth) {
+        return error(
+            StatusCode::FORBIDDEN,
+            "write_forbidden",
+            "Missing required scopes",
+        );
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let extension = query
+        .filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+    let (storage, max_size_mb, accepted, skip_virus_scan) = match &field.field_type {
+        FieldType::File { config } => {
+            let accepted = config.accepted_types.as_ref().is_none_or(|types| {
+                types.iter().any(|t| {
+                    let t = t.to_lowercase();
+                    match (t.strip_prefix('.'), t.strip_suffix("/*")) {
+                        (Some(ext), _) => ext == extension,
+                        (_, Some(kind)) => content_type.starts_with(&format!("{}/", kind)),
+                        _ => content_type == t,
+                    }
+                })
+            });
+            (
+                &config.storage,
+                config.max_size_mb,
+                accepted,
+                config.skip_virus_scan,
+            )
+        }
+        FieldType::Image { config } => {
+            let accepted = content_type.starts_with("image/")
+                && config.accepted_formats.as_ref().is_none_or(|formats| {
+                    formats.iter().any(|f| f.eq_ignore_ascii_case(&extension))
+                });
+            (
+                &config.storage,
+                config.max_size_mb,
+                accepted,
+                config.skip_virus_scan,
+            )
+        }
+        _ => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "invalid_field",
+                "Only File and Image fields take uploads",
+            )
+        }
+    };
+    let Some(storage) = storage else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "invalid_field",
+            "The field has no storage for uploads",
+        );
+    };
+    if body.is_empty() || query.filename.trim().is_empty() {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "empty_file",
+            "A file and its filename are required",
+        );
+    }
+    if let Some(max_size_mb) = max_size_mb {
+        if body.len() as f64 > max_size_mb * 1024.0 * 1024.0 {
+            return error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "file_too_large",
+                &format!("Files can be at most {} MB", max_size_mb),
+            );
+        }
+    }
+    if !accepted {
+        return error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "file_type",
+            &format!("Files of type {} are not accepted", content_type),
+        );
+    }
+
+    let scanned = match &state.config.antivirus {
+        Some(antivirus) if !skip_virus_scan => match antivirus::scan(antivirus, &body).await {
+            Ok(Verdict::Clean) => true,
+            Ok(Verdict::Infected(signature)) => {
+                warn!(
+                    field = %field_id,
+                    filename = %query.filename,
+                    signature = %signature,
+                    "Rejected infected upload"
+                );
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(serde_json::json!({
+                        "error": "The file is infected",
+                        "code": "virus_found",
+                        "field": field_id,
+                        "signature": signature,
+                    })),
+                )
+                    .into_response();
+            }
+            Err(e) => {
+                error!(error = %e, field = %field_id, "Virus scan failed");
+                return error(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "scan_failed",
+                    "The file could not be scanned for viruses",
+                );
+            }
+        },
+        _ => false,
+    };
+
+    // Keys are unique, keeping a readable filename
+    let filename: String = query
+        .filename
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    let key = format!(
+        "{}/{}/{}-{}",
+        section_id,
+        field_id,
+        uuid::Uuid::new_v4(),
+        filename
+    );
+    let data_source = match state.data_source(&backoffice_id, storage).await {
+        Ok(ds) => ds,
+        Err(e) => return data_source_error_response(&e),
+    };
+    let client = state.data_sources.http_client();
+    if let Err(e) = store_object(
+        &client,
+        data_source.as_ref(),
+        &key,
+        &content_type,
+        body.to_vec(),
+    )
+    .await
+    {
+        error!(error = %e, key = %key, "Storing upload failed");
+        return error(
+            StatusCode::BAD_GATEWAY,
+            "storage_failed",
+            "The file could not be stored",
+        );
+    }
+
+    info!(
+        backoffice_id = %backoffice_id,
+        field = %field_id,
+        key = %key,
+        size = body.len(),
+        scanned,
+        "Stored upload"
+    );
+    (
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "key": key,
+            "filename": query.filename,
+            "content_type": content_type,
+            "size": body.len(),
+            "scanned": scanned,
+        })),
+    )
+        .into_response()
+}
+
+/// The form action and File or Image field uploads of a section go to,
+/// or the status, code and message to refuse them with
+fn upload_field<'a>(
+    backoffice: &'a BackofficeConfig,
+    section_id: &str,
+    action_id: &str,
+    field_id: &str,
+) -> Result<(&'a ActionConfig, &'a FieldConfig), (StatusCode, &'static str, &'static str)> {
+    let action = backoffice
+        .sections
+        .iter()
+        .find(|s| s.id == section_id)
+        .and_then(|s| s.actions.iter().find(|a| a.id == action_id))
+        .ok_or((StatusCode::NOT_FOUND, "not_found", "Action not found"))?;
+    let ActionType::Form { fields, .. } = &action.action_type else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "invalid_action",
+            "Uploads go through form actions",
+        ));
+    };
+    let field = fields.iter().find(|f| f.id == field_id).ok_or((
+        StatusCode::NOT_FOUND,
+        "not_found",
+        "Field not found",
+    ))?;
+    if !matches!(
+        field.field_type,
+        FieldType::File { .. } | FieldType::Image { .. }
+    ) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "invalid_field",
+            "Only File and Image fields take uploads",
+        ));
+    }
+    Ok((action, field))
+}
+
+/// Store an object in an object storage data source with a presigned PUT,
+/// returning its URL (the presigned one without the signature)
+async fn store_object(
+    client: &reqwest::Client,
+    data_source: &dyn data_source::DataSource,
+    key: &str,
+    content_type: &str,
+    body: Vec<u8>,
+) -> Result<String> {
+    let presigned = data_source
+        .presign(
+            key,
+            data_source::PresignMethod::Put,
+            Duration::from_secs(UPLOAD_PRESIGN_EXPIRY_SECS),
+        )
+        .await?;
+    let mut request = client
+        .put(&presigned.url)
+        .header(reqwest::header::CONTENT_TYPE, content_type);
+    for (name, value) in &presigned.headers {
+        request = request.header(name, value);
+    }
+    let response = request.body(body).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Storage answered {}", response.status());
+    }
+    let url = presigned.url.split('?').next().unwrap_or_default();
+    Ok(url.to_string())
+}
+
+/// Render the signatures of a payload submitted as strokes to PNGs in their
+/// field's storage, replacing the strokes with the image's URL
+async fn store_signatures(
+    state: &AppState,
+    section_id: &str,
+    fields: &[FieldConfig],
+    data: &mut HashMap<String, Value>,
+    data_sources: &HashMap<String, Box<dyn data_source::DataSource>>,
+) -> Result<()> {
+    for field in fields {
+        let FieldType::Signature { config } = &field.field_type else {
+            continue;
+        };
+        let Some(storage) = &config.storage else {
+            continue;
+        };
+        let Some(strokes) = data.get(&field.id).and_then(signature::strokes) else {
+            continue;
+        };
+        let data_source = data_sources
+            .get(storage)
+            .ok_or_else(|| anyhow::anyhow!("Data source not found: {}", storage))?;
+        let config = config.clone();
+        let png = tokio::task::spawn_blocking(move || signature::render_png(&strokes, &config))
+            .await
+            .map_err(|e| anyhow::anyhow!("Signature rendering failed: {}", e))??;
+        let key = format!("{}/{}/{}.png", section_id, field.id, uuid::Uuid::new_v4());
+        let client = state.data_sources.http_client();
+        let url = store_object(&client, data_source.as_ref(), &key, "image/png", png).await?;
+        debug!(field = %field.id, key = %key, "Stored signature");
+        data.insert(field.id.clone(), Value::String(url));
+    }
+    Ok(())
+}
+
+/// Default lifetime of presigned URLs in seconds
+const DEFAULT_PRESIGN_EXPIRY_SECS: u64 = 900;
+
+/// Longest lifetime S3 accepts for presigned URLs (7 days)
+const MAX_PRESIGN_EXPIRY_SECS: u64 = 604_800;
+
+#[derive(Debug, Deserialize)]
+struct PresignRequest {
+    /// Section, form action and File or Image field the object belongs to
+    section: String,
+    action: String,
+    field: String,
+    /// Key under the field's `<section>/<field>/` prefix
+    key: String,
+    #[serde(default)]
+    method: data_source::PresignMethod,
+    expires_in: Option<u64>,
+}
+
+/// Issue a presigned URL for an object of a File or Image field stored in
+/// an object storage data source
+///
+/// The caller needs the form action's scopes and, to upload, to be allowed
+/// to write the field (to download, to read it). Keys are confined to the
+/// prefix uploads of the field are stored under.
+async fn presign_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, data_source_id)): Path<(String, String)>,
+    auth: AuthContext,
+    Json(request): Json<PresignRequest>,
+) -> impl IntoResponse {
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({"error": message}))).into_response()
+    };
+    let expires_in = request.expires_in.unwrap_or(DEFAULT_PRESIGN_EXPIRY_SECS);
+    if request.key.is_empty() || expires_in == 0 || expires_in > MAX_PRESIGN_EXPIRY_SECS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "A key and an expiry between 1 and {} seconds are required",
+                    MAX_PRESIGN_EXPIRY_SECS
+                )
+            })),
+        )
+            .into_response();
+    }
+
+    let Some(backoffice) = state.backoffice(&backoffice_id) else {
+        return error(StatusCode::NOT_FOUND, "Backoffice not found");
+    };
+    if !backoffice.data_sources.contains_key(&data_source_id) {
+        return error(StatusCode::NOT_FOUND, "Data source not found");
+    }
+    let (action, field) = match upload_field(
+        backoffice,
+        &request.section,
+        &request.action,
+        &request.field,
+    ) {
+        Ok(found) => found,
+        Err((status, _, message)) => return error(status, message),
+    };
+    let (storage, skip_virus_scan) = match &field.field_type {
+        FieldType::File { config } => (config.storage.as_deref(), config.skip_virus_scan),
+        FieldType::Image { config } => (config.storage.as_deref(), config.skip_virus_scan),
+        _ => (None, false),
+    };
+    if storage != Some(data_source_id.as_str()) {
+        return error(
+            StatusCode::NOT_FOUND,
+            "The field isn't stored in this data source",
+        );
+    }
+    let allowed = match request.method {
+        data_source::PresignMethod::Put => permissions::can_write(field, &auth),
+        data_source::PresignMethod::Get => permissions::can_read(field, &auth),
+    };
+    if !auth.has_all_scopes(&action.required_scopes) || !allowed {
+        return error(StatusCode::FORBIDDEN, "Missing required scopes");
+    }
+    let prefix = format!("{}/{}/", request.section, request.field);
+    let contained = request.key.strip_prefix(&prefix).is_some_and(|name| {
+        !name.is_empty() && name.split('/').all(|part| !matches!(part, "" | "." | ".."))
+    });
+    if !contained {
+        return error(
+            StatusCode::FORBIDDEN,
+            &format!("Keys of the field must be under {}", prefix),
+        );
+    }
+    // Direct uploads would skip the scan, so scanned fields only take uploads
+    // through the server
+    if request.method == data_source::PresignMethod::Put
+        && state.config.antivirus.is_some()
+        && !skip_virus_scan
+    {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Files of the field are scanned for viruses, upload them through the upload endpoint",
+        );
+    }
+
+    let data_source = match state.data_source(&backoffice_id, &data_source_id).await {
+        Ok(ds) => ds,
+        Err(e) => return data_source_error_response(&e),
+    };
+
+    match data_source
+        .presign(
+            &request.key,
+            request.method,
+            std::time::Duration::from_secs(expires_in),
+        )
+        .await
+    {
+        Ok(presigned) => {
+            info!(
+                backoffice_id = %backoffice_id,
+                data_source = %data_source_id,
+                key = %request.key,
+                method = ?request.method,
+                "Issued presigned URL"
+            );
+            Json(presigned).into_response()
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": e.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+/// Execute a delete action (DELETE)
+async fn execute_delete_handler(
+    State(state): State<Arc<AppState>>,
+    Path((backoffice_id, section_id, action_id)): Path<(String, String, String)>,
+    auth: AuthContext,
+    request: RequestMetadata,
+    Query(query): Query<ActionQuery>,
+) -> impl IntoResponse {
+    info!(
+        backoffice_id = %backoffice_id,
+        section_id = %section_id,
+        action_id = %action_id,
+        "Processing delete request"
+    );
+
+    // Find the backoffice
+    let backoffice = match state.backoffices.iter().find(|b| b.id == backoffice_id) {
+        Some(b) => b,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Backoffice not found"})),
+            )
+                .into_response()
+        }
+    };
+
+    // Find the section
+    let section = match backoffice.sections.iter().find(|s| s.id == section_id) {
+        Some(s) => s,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Section not found"})),
+            )
+                .into_response()
+        }
+    };
+
+    // Extract record ID from query params
+    let record_id = match query.params.get("id") {
+        Some(id) => id,
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Record ID is required"})),
+            )
+                .into_response()
+        }
+    };
+
+    // Create data sources map
+    let mut data_sources_map: HashMap<String, Box<dyn data_source::DataSource>> = HashMap::new();
+    for (name, ds_config) in &backoffice.data_sources {
+        match state.data_sources.create(ds_config).await {
+            Ok(ds) => {
+                data_sources_map.insert(name.clone(), ds);
+            }
+            Err(e) => {
+                error!(
+                    data_source = %name,
+                    error = %e,
+                    "Failed to create data source"
+                );
+                if e.is::<data_source::CircuitOpenError>() {
+                    return data_source_error_response(&e);
+                }
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": format!("Failed to create data source: {}", e)})),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    let action = match section.actions.iter().find(|a| a.id == action_id) {
+        Some(a) => a,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Action not found"})),
+            )
+                .into_response()
+        }
+    };
+    match action.action_type {
+        ActionType::Report { .. } => return report_read_only(),
+        ActionType::Pipeline { .. } => {
+            return (
+                StatusCode::METHOD_NOT_ALLOWED,
+                Json(serde_json::json!({"error": "Pipeline actions run with POST"})),
+            )
+                .into_response()
+        }
+        _ => {}
+    }
+
+    let data_source = match data_sources_map.get(&action.data_source) {
+        Some(ds) => ds,
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Data source not found"})),
+            )
+                .into_response()
+        }
+    };
+
+    // Only records the caller can see may be deleted
+    let row_conditions = match row_filters::resolve(&section.row_filters, &auth) {
+        Ok(conditions) => conditions,
+        Err(e) => {
+            warn!(error = %e, "Row filters could not be resolved");
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    };
+    match row_filters::record_visible(
+        data_source.as_ref(),
+        section,
+        &action.data_source,
+        &backoffice.data_sources[&action.data_source],
+        &Value::String(record_id.clone()),
+        &row_conditions,
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Record not found"})),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to check record visibility");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    }
+
+    let hook_ctx = HookContext {
+        backoffice,
+        section,
+        action,
+        auth: &auth,
+    };
+    match hooks::on_delete(&state.hooks, &hook_ctx, record_id).await {
+        Ok(HookOutcome::Continue) => {}
+        Ok(HookOutcome::Veto(message)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": message})),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            error!(error = %e, "on_delete hook failed");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Hook error: {}", e)})),
+            )
+                .into_response();
+        }
+    }
+
+    // Step 1: Resolve cascade delete operations
+    info!(record_id = %record_id, "Processing cascade delete");
+    let (cascade_ops, cascade_mutations) = match relationships::handle_cascade_delete(
+        record_id,
+        &section_id,
+        backoffice,
+        &data_sources_map,
+    )
+    .await
+    .and_then(|ops| {
+        let mutations = relationships::cascade_mutations(&ops, backoffice)?;
+        Ok((ops, mutations))
+    }) {
+        Ok(resolved) => resolved,
+        Err(e) if e.is::<relationships::RestrictedDeleteError>() => {
+            let restricted = e
+                .downcast_ref::<relationships::RestrictedDeleteError>()
+                .expect("checked above");
+            warn!(record_id = %record_id, error = %e, "Delete restricted by dependents");
+            return (
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({
+                    "error": e.to_string(),
+                    "dependents": restricted.dependents,
+                })),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to process cascade delete");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Failed to process cascade delete: {}", e)})),
+            )
+                .into_response();
+        }
+    };
+
+    // Step 2: Delete the record itself. Actions with an HTTP method call
+    // their own endpoint template (e.g. `DELETE /users/{id}`) instead of SQL.
+    // Generated actions build the statement from the section's table.
+    let mut delete_data = HashMap::new();
+    delete_data.insert("id".to_string(), Value::String(record_id.clone()));
+
+    let mut delete_query = match (action.method.as_ref(), action.generated) {
+        (Some(_), _) => action.target(),
+        (None, true) => {
+            let generated = backoffice
+                .data_sources
+                .get(&action.data_source)
+                .ok_or_else(|| anyhow::anyhow!("Data source not found"))
+                .and_then(|ds_config| {
+                    crud::action_target(
+                        section,
+                        action,
+                        ds_config,
+                        CrudOperation::Delete,
+                        &delete_data,
+                    )
+                });
+            match generated {
+                Ok(query) => query,
+                Err(e) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response()
+                }
+            }
+        }
+        (None, false) => format!("DELETE FROM {} WHERE id = {{{{id}}}}", section_id),
+    };
+    if let (None, Some(DataSourceConfig::Database { db_type, .. })) = (
+        action.method.as_ref(),
+        backoffice.data_sources.get(&action.data_source),
+    ) {
+        if !row_conditions.is_empty() {
+            delete_query.push_str(" AND ");
+            delete_query.push_str(&row_filters::sql_params(
+                &row_conditions,
+                db_type,
+                &mut delete_data,
+            ));
+        }
+    }
+
+    // Cascades on the record's own data source run in one transaction with
+    // the delete when the source supports it, so a failure leaves no partial
+    // changes behind. Otherwise they run one by one before the delete.
+    let transactional = data_source.supports_transactions()
+        && cascade_mutations
+            .iter()
+            .all(|c| c.data_source == action.data_source);
+
+    let old_record = if AuditLogger::should_audit(&section.audit, &AuditOperation::Delete) {
+        fetch_record(
+            backoffice,
+            section,
+            action,
+            data_source.as_ref(),
+            &Value::String(record_id.clone()),
+        )
+        .await
+        .map(|old| masking::masked(&old, &section_fields(section)))
+    } else {
+        None
+    };
+
+    let outcome = if transactional {
+        info!(
+            query = %delete_query,
+            cascade_count = cascade_mutations.len(),
+            "Executing delete in a transaction"
+        );
+        let mut mutations: Vec<data_source::Mutation> =
+            cascade_mutations.into_iter().map(|c| c.mutation).collect();
+        mutations.push(data_source::Mutation {
+            query: delete_query,
+            data: delete_data,
+            ..Default::default()
+        });
+        data_source
+            .execute_transaction(&mutations)
+            .await
+            .map(|mut results| results.pop().unwrap_or(Value::Null))
+    } else {
+        if !cascade_ops.is_empty() {
+            info!(
+                operation_count = cascade_ops.len(),
+                "Executing cascade delete operations"
+            );
+
+            if let Err(e) = relationships::execute_cascade_operations(
+                &cascade_ops,
+                backoffice,
+                &data_sources_map,
+            )
+            .await
+            {
+                error!(error = %e, "Failed to execute cascade operations");
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "error": format!("Failed to execute cascade operations: {}", e)
+                    })),
+                )
+                    .into_response();
+            }
+        }
+
+        info!(query = %delete_query, "Executing delete");
+        data_source
+            .execute_delete(&delete_query, &delete_data)
+            .await
+    };
+
+    match outcome {
+        Ok(result) => {
+            info!("Delete executed successfully");
+
+            // Cascades may have changed other sections too
+            state
+                .query_cache
+                .invalidate_section(backoffice, &section_id)
+                .await;
+            for op in &cascade_ops {
+                state
+                    .query_cache
+                    .invalidate_section(backoffice, &op.section)
+                    .await;
+                let operation = match op.operation_type {
+                    relationships::CascadeOperationType::SetNull => AuditOperation::Update,
+                    _ => AuditOperation::Delete,
+                };
+                state.events.publish(PushEvent::mutation(
+                    &backoffice_id,
+                    &op.section,
+                    operation,
+                    Some(op.record_id.clone()),
+                    auth.user_id.clone(),
+                ));
+            }
+            state.events.publish(PushEvent::mutation(
+                &backoffice_id,
+                &section_id,
+                AuditOperation::Delete,
+                Some(record_id.clone()),
+                auth.user_id.clone(),
+            ));
+
+            // Log audit trail if enabled
+            if AuditLogger::should_audit(&section.audit, &AuditOperation::Delete) {
+                let mut audit_entry = AuditLogger::delete_entry(
+                    section_id.clone(),
+                    record_id.clone(),
+                    old_record.as_ref(),
+                    auth.user_id.clone(),
+                );
+                request.attach(&mut audit_entry, &action_id);
+
+                if let Err(e) = state.audit_logger.log(audit_entry).await {
+                    warn!(error = %e, "Failed to log audit entry");
+                }
+            }
+
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "success": true,
+                    "data": result,
+                    "message": format!("Record {} deleted successfully", record_id)
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Delete execution failed");
+            data_source_error_response(&e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        ActionConfig, ActionType, BackofficeConfig, DataSourceConfig, FieldConfig, SectionConfig,
+    };
+    use crate::test_support::{sqlite_backoffice, sqlite_backoffice_with, test_config, TempDir};
+
+    fn create_test_state() -> Arc<AppState> {
+        let config = test_config();
+
+        let audit_logger = Arc::new(AuditLogger::new("logs/audit/test"));
+
+        let backoffice = BackofficeConfig {
+            id: "test".to_string(),
+            name: "Test Backoffice".to_string(),
+            description: Some("Test description".to_string()),
+            data_sources: HashMap::from([(
+                "test_api".to_string(),
+                DataSourceConfig::Api {
+                    base_url: "https://api.example.com".to_string(),
+                    headers: Some(HashMap::new()),
+                    auth: None,
+                    pagination: None,
+                    response: None,
+                    init: Default::default(),
+                },
+            )]),
+            relationships: vec![],
+            plugins: vec![],
+            validation_functions: HashMap::new(),
+            script_hooks: vec![],
+            dashboard: None,
+            currency: None,
+            api: Default::default(),
+            sections: vec![SectionConfig {
+                id: "test_section".to_string(),
+                name: "Test Section".to_string(),
+                icon: Some("fa-test".to_string()),
+                actions: vec![ActionConfig {
+                    id: "test_action".to_string(),
+                    name: "Test Action".to_string(),
+                    action_type: ActionType::List {
+                        fields: vec![FieldConfig {
+                            id: "id".to_string(),
+                            name: "ID".to_string(),
+                            field_type: crate::config::FieldType::Text {
+                                config: Default::default(),
+                            },
+                            required: false,
+                            editable: false,
+                            visible: true,
+                            default_value: None,
+                            placeholder: None,
+                            help_text: None,
+                            validations: vec![],
+                            relationship_id: None,
+                            sensitive: false,
+                            mask: None,
+                            unmask_scopes: vec![],
+                            required_scopes: vec![],
+                            write_scopes: vec![],
+                            visible_when: None,
+                            required_when: None,
+                        }],
+                        config: Default::default(),
+                    },
+                    data_source: "test_api".to_string(),
+                    required_scopes: vec![],
+                    query: Some("SELECT * FROM users".to_string()),
+                    endpoint: None,
+                    method: None,
+                    generated: false,
+                    cache: None,
+                    cache_ttl_secs: None,
+                    enabled_when: None,
+                }],
+                audit: None,
+                row_filters: vec![],
+                table: None,
+                seeds: vec![],
+                updated_at_field: None,
+                search: None,
+                duplicate: None,
+                enabled_when: None,
+            }],
+        };
+
+        Arc::new(AppState {
+            config,
+            validation_functions: compile_validation_functions(std::slice::from_ref(&backoffice)),
+            backoffices: vec![backoffice],
+            audit_logger,
+            hooks: vec![],
+            data_sources: Arc::new(DataSourceRegistry::new()),
+            query_cache: Arc::new(QueryCache::new()),
+            events: EventHub::default(),
+            feature_flags: Default::default(),
+            #[cfg(feature = "graphql")]
+            graphql_schemas: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_config_handler() {
+        let state = create_test_state();
+        let response = config_handler(State(state.clone())).await;
+        let json = response.into_response();
+        assert_eq!(json.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_backoffices_handler() {
+        let state = create_test_state();
+        let response = backoffices_handler(State(state.clone())).await;
+        let json = response.into_response();
+        assert_eq!(json.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_backoffice_handler_found() {
+        let state = create_test_state();
+        let response = backoffice_handler(State(state.clone()), Path("test".to_string())).await;
+        let json = response.into_response();
+        assert_eq!(json.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_backoffice_handler_not_found() {
+        let state = create_test_state();
+        let response =
+            backoffice_handler(State(state.clone()), Path("nonexistent".to_string())).await;
+        let json = response.into_response();
+        assert_eq!(json.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_stream_requires_live_data_source() {
+        let state = create_test_state();
+        let path = |action: &str| {
+            Path((
+                "test".to_string(),
+                "test_section".to_string(),
+                action.to_string(),
+            ))
+        };
+
+        let response = stream_action_handler(
+            State(state.clone()),
+            path("missing"),
+            AuthContext::anonymous(false),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = stream_action_handler(
+            State(state),
+            path("test_action"),
+            AuthContext::anonymous(false),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_scaffold_requires_graphql_data_source() {
+        let state = create_test_state();
+        let path = |ds: &str| Path(("test".to_string(), ds.to_string()));
+
+        let response = scaffold_handler(
+            State(state.clone()),
+            path("missing"),
+            AuthContext::anonymous(false),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = scaffold_handler(
+            State(state.clone()),
+            path("test_api"),
+            AuthContext::anonymous(false),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let caller = |scope: &str| AuthContext::from_claims(serde_json::json!({"scope": scope}));
+        let response = scaffold_handler(State(state.clone()), path("missing"), caller("read"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = scaffold_handler(State(state), path("missing"), caller("admin"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_migrations_require_configured_database() {
+        let state = create_test_state();
+        let status = |ds: &str, auth: AuthContext| {
+            migrations_status_handler(
+                State(state.clone()),
+                Path(("test".to_string(), ds.to_string())),
+                auth,
+            )
+        };
+
+        let response = status("missing", AuthContext::anonymous(false))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = status("test_api", AuthContext::anonymous(false))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = status("test_api", AuthContext::anonymous(true))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_data_sources_status() {
+        let state = create_test_state();
+        let status = |id: &str| {
+            data_sources_status_handler(
+                State(state.clone()),
+                Path(id.to_string()),
+                AuthContext::anonymous(false),
+            )
+        };
+
+        let response = status("missing").await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = status("test").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data_sources"][0]["name"], "test_api");
+        assert_eq!(json["data_sources"][0]["type"], "api");
+        assert_eq!(json["data_sources"][0]["state"], "unknown");
+
+        let response = data_sources_status_handler(
+            State(state.clone()),
+            Path("test".to_string()),
+            AuthContext::anonymous(true),
+        )
+        .await
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["data_sources"][0],
+            serde_json::json!({"name": "test_api", "state": "unknown"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_eager_data_source_degrades_backoffice() {
+        let mut backoffice = create_test_state().backoffices[0].clone();
+        let missing =
+            std::env::temp_dir().join(format!("pmp-missing-{}/app.db", uuid::Uuid::new_v4()));
+        let db: DataSourceConfig = serde_yaml::from_str(&format!(
+            "{{type: database, db_type: sqlite, init: eager, connection_string: 'sqlite://{}?mode=ro'}}",
+            missing.display()
+        ))
+        .unwrap();
+        assert_eq!(db.init(), InitMode::Eager);
+        backoffice.data_sources.insert("db".to_string(), db);
+
+        let state = RouterBuilder::new(create_test_state().config.clone(), vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .state();
+        init_eager_data_sources(state.clone()).await;
+
+        let response = data_sources_status_handler(
+            State(state),
+            Path("test".to_string()),
+            AuthContext::anonymous(false),
+        )
+        .await
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["degraded"], true);
+        assert_eq!(json["data_sources"][0]["name"], "db");
+        assert_eq!(json["data_sources"][0]["state"], "unhealthy");
+        assert_eq!(json["data_sources"][1]["init"], "lazy");
+    }
+
+    #[tokio::test]
+    async fn test_expand_relationships() {
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: blog
+name: Blog
+relationships:
+  - id: author
+    name: Author
+    relationship_type: manytoone
+    from_section: posts
+    from_field: author_id
+    to_section: users
+    to_field: id
+    display_fields: [name]
+sections:
+  - id: posts
+    name: Posts
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: title, name: Title, field_type: text }
+          - { id: author_id, name: Author, field_type: number, relationship_id: author }
+  - id: users
+    name: Users
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        config: { searchable_fields: [name] }
+        fields:
+          - { id: name, name: Name, field_type: text }
+"#,
+            &[
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT)",
+                "CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT, author_id INTEGER)",
+                "INSERT INTO users VALUES (1, 'alice', 'alice@example.com'), (2, 'bob', NULL)",
+                "INSERT INTO posts VALUES (1, 'a', 1), (2, 'b', 2), (3, 'c', 1), (4, 'd', NULL)",
+            ],
+        )
+        .await;
+
+        let list = |uri: &str| {
+            query_action(
+                State(state.clone()),
+                Path(("blog".to_string(), "posts".to_string(), "list".to_string())),
+                AuthContext::anonymous(false),
+                Query::try_from_uri(&uri.parse().unwrap()).unwrap(),
+            )
+        };
+
+        // By relationship id or by the field carrying it
+        for expand in ["author", "author_id"] {
+            let response = list(&format!("/?expand={}", expand)).await.into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: Value = serde_json::from_slice(&body).unwrap();
+            let rows = json["data"].as_array().unwrap();
+            assert_eq!(
+                rows[0]["_expanded"][expand],
+                serde_json::json!({"id": 1, "name": "alice"})
+            );
+            assert_eq!(rows[1]["_expanded"][expand]["name"], "bob");
+            assert_eq!(rows[3]["_expanded"][expand], Value::Null);
+        }
+
+        let response = list("/?expand=missing").await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Global search over the searchable fields
+        let response = query_action(
+            State(state.clone()),
+            Path(("blog".to_string(), "users".to_string(), "list".to_string())),
+            AuthContext::anonymous(false),
+            Query::try_from_uri(&"/?q=LIC".parse().unwrap()).unwrap(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["data"],
+            serde_json::json!([{"id": 1, "name": "alice"}])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_pages_at_the_data_source() {
+        // API stand-in recording the page it is asked for
+        let requested = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = requested.clone();
+        let api = Router::new().route(
+            "/orders",
+            get(move |Query(params): Query<HashMap<String, String>>| {
+                let seen = seen.clone();
+                async move {
+                    seen.lock().unwrap().push(params);
+                    Json(serde_json::json!({
+                        "data": [{"id": 3}, {"id": 4}],
+                        "meta": {"total": 5}
+                    }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, api).await.unwrap() });
+
+        let backoffice: BackofficeConfig = serde_yaml::from_str(&format!(
+            r#"
+id: shop
+name: Shop
+data_sources:
+  api:
+    type: api
+    base_url: "{}"
+    pagination: {{ style: page, total_field: meta.total }}
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: api
+        endpoint: orders
+        required_scopes: []
+        config: {{ enable_pagination: true }}
+        fields: []
+"#,
+            api_url
+        ))
+        .unwrap();
+        let state = RouterBuilder::new(create_test_state().config.clone(), vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .state();
+
+        let response = query_action(
+            State(state),
+            Path(("shop".to_string(), "orders".to_string(), "list".to_string())),
+            AuthContext::anonymous(false),
+            Query::try_from_uri(&"/?page=2&page_size=2".parse().unwrap()).unwrap(),
+        )
+        .await
+        .into_response();
+        let (status, json) = response_json(response).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let requested = requested.lock().unwrap();
+        assert_eq!(requested.len(), 1);
+        assert_eq!(requested[0]["page"], "2");
+        assert_eq!(requested[0]["page_size"], "2");
+        assert_eq!(json["data"], serde_json::json!([{"id": 3}, {"id": 4}]));
+        assert_eq!(json["pagination"]["total_items"], 5);
+        assert_eq!(json["pagination"]["total_pages"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_full_text_search() {
+        use axum::http::{header, HeaderMap};
+
+        // Meilisearch stand-in ranking ann above alice
+        let meilisearch = Router::new().route(
+            "/indexes/users/search",
+            post(|headers: HeaderMap, Json(body): Json<Value>| async move {
+                assert_eq!(headers[header::AUTHORIZATION], "Bearer key");
+                assert_eq!(body["q"], "an");
+                Json(serde_json::json!({"hits": [
+                    {"id": 2, "_formatted": {
+                        "name": "<mark>an</mark>n",
+                        "email": "<mark>an</mark>n@example.com",
+                    }},
+                    {"id": 1, "_formatted": {"name": "alice"}},
+                ]}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let meilisearch_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, meilisearch).await.unwrap() });
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let down_url = format!("http://{}", closed.local_addr().unwrap());
+        drop(closed);
+
+        let section = |id: &str, url: &str| {
+            format!(
+                r#"
+  - id: {id}
+    name: Users
+    table: users
+    search: {{ backend: meilisearch, url: "{url}", index: users, api_key: key }}
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        config: {{ searchable_fields: [name, email] }}
+        fields:
+          - {{ id: name, name: Name, field_type: text }}
+          - {{ id: email, name: Email, field_type: text, sensitive: true }}
+"#
+            )
+        };
+        let (state, _, _dir) = sqlite_backoffice(
+            &format!(
+                "id: crm\nname: CRM\nsections:{}{}",
+                section("users", &meilisearch_url),
+                section("fallback", &down_url)
+            ),
+            &[
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT)",
+                "INSERT INTO users VALUES (1, 'alice', 'alice@example.com'), \
+                 (2, 'ann', 'ann@example.com'), (3, 'bob', 'bob@example.com')",
+            ],
+        )
+        .await;
+
+        let search = |section: &str| {
+            query_action(
+                State(state.clone()),
+                Path(("crm".to_string(), section.to_string(), "list".to_string())),
+                AuthContext::anonymous(false),
+                Query::try_from_uri(&"/?q=an".parse().unwrap()).unwrap(),
+            )
+        };
+
+        // Ranked by the backend, with snippets of non-sensitive fields
+        let response = search("users").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        let rows = json["data"].as_array().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["name"], "ann");
+        assert_eq!(
+            rows[0][search::HIGHLIGHT_KEY],
+            serde_json::json!({"name": "<mark>an</mark>n"})
+        );
+        assert_eq!(rows[1]["name"], "alice");
+        assert!(rows[1].get(search::HIGHLIGHT_KEY).is_none());
+
+        // Unreachable backends fall back to LIKE search
+        let response = search("fallback").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        let rows = json["data"].as_array().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["name"], "ann");
+        assert!(rows[0].get(search::HIGHLIGHT_KEY).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_currency_conversion() {
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: fx
+name: FX
+currency:
+  reporting_currency: USD
+  rates: { EUR: 0.8, GBP: 0.9 }
+  provider: { data_source: db, query: "SELECT code AS currency, rate FROM rates" }
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: revenue
+        name: Revenue
+        type: summary
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - id: revenue
+            name: Revenue
+            field_type: currency
+            config: { currency_code: EUR, allow_negative: false, decimal_places: 2, locale: de-DE }
+        config:
+          aggregates:
+            - { function: sum, field: total, alias: revenue }
+"#,
+            &[
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL)",
+                "INSERT INTO orders VALUES (1, 1000), (2, 234.5)",
+                "CREATE TABLE rates (code TEXT, rate REAL)",
+                "INSERT INTO rates VALUES ('EUR', 0.5)",
+            ],
+        )
+        .await;
+
+        let response = query_action(
+            State(state.clone()),
+            Path((
+                "fx".to_string(),
+                "orders".to_string(),
+                "revenue".to_string(),
+            )),
+            AuthContext::anonymous(false),
+            Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        let row = &json["data"][0];
+        assert_eq!(row["revenue"], 1234.5);
+        assert_eq!(row["_formatted"]["revenue"], "1.234,50 €");
+        // The provider's rate takes precedence over the configured one
+        assert_eq!(
+            row["_converted"]["revenue"],
+            serde_json::json!({"amount": 2469.0, "currency": "USD", "formatted": "$2,469.00"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_summary_action() {
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: shop
+name: Shop
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: by_status
+        name: By status
+        type: summary
+        data_source: db
+        generated: true
+        required_scopes: []
+        config:
+          group_by: [status]
+          aggregates:
+            - { function: count, alias: orders }
+            - { function: sum, field: total, alias: revenue }
+      - id: totals
+        name: Totals
+        type: summary
+        data_source: db
+        query: "SELECT * FROM orders WHERE total > 1"
+        required_scopes: []
+        config:
+          aggregates:
+            - { function: avg, field: total }
+      - id: audit
+        name: Audit
+        type: summary
+        data_source: db
+        generated: true
+        required_scopes: [admin]
+        config:
+          aggregates:
+            - { function: count }
+dashboard:
+  widgets:
+    - { id: status, title: Status, section: orders, action: by_status, refresh_secs: 30 }
+    - { id: average, title: Average, section: orders, action: totals, chart: number }
+    - { id: audit, title: Audit, section: orders, action: audit }
+    - { id: broken, title: Broken, section: orders, action: missing }
+"#,
+            &[
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, status TEXT, total INTEGER)",
+                "INSERT INTO orders VALUES (1, 'paid', 10), (2, 'open', 1), (3, 'paid', 5)",
+            ],
+        )
+        .await;
+
+        let summarize = |action: &str| {
+            query_action(
+                State(state.clone()),
+                Path(("shop".to_string(), "orders".to_string(), action.to_string())),
+                AuthContext::anonymous(false),
+                Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+            )
+        };
+
+        let response = summarize("by_status").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["data"],
+            serde_json::json!([
+                {"status": "open", "orders": 1, "revenue": 1},
+                {"status": "paid", "orders": 2, "revenue": 15},
+            ])
+        );
+        assert_eq!(
+            json["chart"],
+            serde_json::json!({
+                "labels": ["open", "paid"],
+                "series": [
+                    {"name": "orders", "data": [1.0, 2.0]},
+                    {"name": "revenue", "data": [1.0, 15.0]},
+                ],
+            })
+        );
+
+        let response = summarize("totals").await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"], serde_json::json!([{"avg_total": 7.5}]));
+        assert_eq!(json["chart"]["labels"], serde_json::json!(["All"]));
+
+        // Dashboards run every widget the caller may see
+        let dashboard = |auth: AuthContext, uri: &str| {
+            dashboard_handler(
+                State(state.clone()),
+                Path("shop".to_string()),
+                auth,
+                Query::try_from_uri(&uri.parse().unwrap()).unwrap(),
+            )
+        };
+        let response = dashboard(AuthContext::anonymous(true), "/")
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        let widgets = json["widgets"].as_array().unwrap();
+        assert_eq!(widgets.len(), 3);
+        assert_eq!(widgets[0]["refresh_secs"], 30);
+        assert_eq!(widgets[0]["layout"]["width"], 6);
+        assert_eq!(
+            widgets[0]["dataset"]["labels"],
+            serde_json::json!(["open", "paid"])
+        );
+        assert_eq!(widgets[1]["chart"], "number");
+        assert_eq!(widgets[1]["data"], serde_json::json!([{"avg_total": 7.5}]));
+        assert_eq!(widgets[2]["id"], "broken");
+        assert_eq!(widgets[2]["error"], "Action not found");
+
+        let response = dashboard(AuthContext::anonymous(false), "/?widgets=audit")
+            .await
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["widgets"][0]["data"],
+            serde_json::json!([{"count": 3}])
+        );
+        assert_eq!(json["widgets"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_report_action() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: shop
+name: Shop
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: big_orders
+        name: Big orders
+        type: report
+        data_source: db
+        query: "SELECT id, status, total FROM orders WHERE total >= {{min_total}} ORDER BY id"
+        required_scopes: []
+        fields:
+          - { id: id, name: ID, field_type: number }
+          - { id: total, name: Total, field_type: number }
+        parameters:
+          - id: min_total
+            name: Minimum total
+            field_type: number
+            required: true
+            validations:
+              - rule_type: { type: min, value: 0 }
+        config:
+          export_formats: [csv]
+      - id: purge
+        name: Purge
+        type: report
+        data_source: db
+        query: "WITH gone AS (DELETE FROM orders RETURNING *) SELECT * FROM gone"
+        required_scopes: []
+"#,
+            &[
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, status TEXT, total INTEGER)",
+                "INSERT INTO orders VALUES (1, 'paid', 10), (2, 'open', 1), (3, 'paid', 5)",
+            ],
+        )
+        .await;
+
+        let report = |action: &str, uri: &str| {
+            query_action(
+                State(state.clone()),
+                Path(("shop".to_string(), "orders".to_string(), action.to_string())),
+                AuthContext::anonymous(false),
+                Query::try_from_uri(&uri.parse().unwrap()).unwrap(),
+            )
+        };
+
+        let response = report("big_orders", "/?min_total=5&status=open")
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["data"],
+            serde_json::json!([
+                {"id": 1, "status": "paid", "total": 10},
+                {"id": 3, "status": "paid", "total": 5},
+            ])
+        );
+        assert_eq!(json["parameters"][0]["id"], "min_total");
+
+        let response = report("big_orders", "/?min_total=6&format=csv")
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"big_orders.csv\""
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "ID,Total\n1,10\n");
+
+        for uri in ["/", "/?min_total=-1", "/?min_total=many"] {
+            let response = report("big_orders", uri).await.into_response();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["validation_errors"][0]["field"], "min_total");
+        }
+        let response = report("big_orders", "/?min_total=1&format=json")
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = report("purge", "/").await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let rows = db
+            .execute_query("SELECT COUNT(*) AS n FROM orders", None)
+            .await
+            .unwrap();
+        assert_eq!(rows[0]["n"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_action() {
+        use axum::extract::Query as QueryParams;
+
+        // CRM stand-in listing users changed since a date
+        let crm = Router::new().route(
+            "/users",
+            get(
+                |QueryParams(params): QueryParams<HashMap<String, String>>| async move {
+                    assert_eq!(params["since"], "2024-01-01");
+                    Json(serde_json::json!([
+                        {"id": 7, "first": "Ada", "last": "Lovelace"},
+                        {"id": 9, "first": "Alan", "last": "Turing"},
+                    ]))
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let crm_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, crm).await.unwrap() });
+
+        let (state, db, _dir) = sqlite_backoffice(
+            &format!(
+                r#"
+id: crm
+name: CRM
+data_sources:
+  api:
+    type: api
+    base_url: "{}"
+sections:
+  - id: contacts
+    name: Contacts
+    actions:
+      - id: sync
+        name: Sync
+        type: pipeline
+        data_source: db
+        required_scopes: [admin]
+        fields:
+          - {{ id: since, name: Since, field_type: date, required: true }}
+        steps:
+          - id: users
+            type: query
+            data_source: api
+            query: "GET /users"
+            params: {{ since: "{{{{input.since}}}}" }}
+          - id: contacts
+            type: transform
+            from: users
+            map: {{ external_id: "{{{{row.id}}}}", name: "{{{{row.first}}}} {{{{row.last}}}}" }}
+          - id: save
+            type: mutation
+            for_each: contacts
+            query: "INSERT INTO contacts (external_id, name) VALUES ({{{{external_id}}}}, {{{{name}}}})"
+            params: {{ external_id: "{{{{row.external_id}}}}", name: "{{{{row.name}}}}" }}
+          - id: missing
+            type: mutation
+            query: "INSERT INTO missing_table VALUES ({{{{count}}}})"
+            params: {{ count: 1 }}
+"#,
+                crm_url
+            ),
+            &["CREATE TABLE contacts (external_id INTEGER, name TEXT)"],
+        )
+        .await;
+
+        let run = |auth: AuthContext, input: Value| {
+            execute_mutation_handler(
+                State(state.clone()),
+                Path(("crm".into(), "contacts".into(), "sync".into())),
+                auth,
+                RequestMetadata::default(),
+                Json(serde_json::from_value(input).unwrap()),
+            )
+        };
+
+        let response = run(
+            AuthContext::anonymous(true),
+            serde_json::json!({"since": "2024-01-01"}),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = run(AuthContext::anonymous(false), serde_json::json!({}))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // The last step fails after the others wrote their rows
+        let response = run(
+            AuthContext::anonymous(false),
+            serde_json::json!({"since": "2024-01-01"}),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["failed_step"], "missing");
+        assert_eq!(
+            json["steps"]["contacts"],
+            serde_json::json!([
+                {"external_id": 7, "name": "Ada Lovelace"},
+                {"external_id": 9, "name": "Alan Turing"},
+            ])
+        );
+        assert_eq!(json["steps"]["save"].as_array().unwrap().len(), 2);
+
+        let rows = db
+            .execute_query("SELECT * FROM contacts ORDER BY external_id", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["name"], "Alan Turing");
+    }
+
+    #[tokio::test]
+    async fn test_action_layout() {
+        let dir = TempDir::new();
+        let config = |groups: &str| {
+            format!(
+                r#"
+id: crm
+name: CRM
+data_sources:
+  db:
+    type: database
+    connection_string: "sqlite://{}?mode=rwc"
+    db_type: sqlite
+sections:
+  - id: customers
+    name: Customers
+    actions:
+      - id: edit
+        name: Edit
+        type: form
+        data_source: db
+        query: "UPDATE customers SET name = {{{{name}}}}"
+        required_scopes: []
+        fields:
+          - {{ id: name, name: Name, field_type: text }}
+          - {{ id: email, name: Email, field_type: email }}
+          - {{ id: iban, name: IBAN, field_type: text, required_scopes: [billing] }}
+        layout:
+          style: tabs
+          groups: {}
+"#,
+                dir.join("crm.db").display(),
+                groups
+            )
+        };
+        let backoffice: BackofficeConfig = serde_yaml::from_str(&config(
+            r#"
+            - { id: billing, label: Billing, fields: [iban], collapsible: true }
+            - { id: contact, label: Contact, fields: [email] }
+            - { id: general, label: General, fields: [name], order: 1 }"#,
+        ))
+        .unwrap();
+        backoffice.validate_layouts().unwrap();
+
+        for groups in [
+            "[{ id: general, label: General, fields: [phone] }]",
+            "[{ id: a, label: A, fields: [name] }, { id: b, label: B, fields: [name] }]",
+            "[{ id: a, label: A, fields: [name] }, { id: a, label: B, fields: [email] }]",
+        ] {
+            let invalid: BackofficeConfig = serde_yaml::from_str(&config(groups)).unwrap();
+            assert!(invalid.validate_layouts().is_err(), "{}", groups);
+        }
+
+        let state = RouterBuilder::new(create_test_state().config.clone(), vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .state();
+        let response = query_action(
+            State(state),
+            Path(("crm".into(), "customers".into(), "edit".into())),
+            AuthContext::anonymous(true),
+            Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+        )
+        .await
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+
+        // Ordered groups first, and no group for the unreadable IBAN
+        assert_eq!(json["layout"]["style"], "tabs");
+        let groups: Vec<&str> = json["layout"]["groups"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|g| g["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(groups, vec!["general", "contact"]);
+    }
+
+    #[tokio::test]
+    async fn test_delete_policies() {
+        let dir = TempDir::new();
+        let db = dir.join("blog.db");
+        let state = |on_delete: &str| {
+            let backoffice: BackofficeConfig = serde_yaml::from_str(&format!(
+                r#"
+id: blog
+name: Blog
+data_sources:
+  db:
+    type: database
+    connection_string: "sqlite://{}?mode=rwc"
+    db_type: sqlite
+relationships:
+  - id: author
+    name: Author
+    relationship_type: onetomany
+    from_section: posts
+    from_field: author_id
+    to_section: users
+    to_field: id
+    on_delete: {}
+sections:
+  - id: posts
+    name: Posts
+    actions:
+      - {{ id: list, name: List, type: list, data_source: db, query: "SELECT * FROM posts", required_scopes: [], fields: [] }}
+  - id: users
+    name: Users
+    actions:
+      - {{ id: list, name: List, type: list, data_source: db, query: "SELECT * FROM users", required_scopes: [], fields: [] }}
+"#,
+                db.display(),
+                on_delete
+            ))
+            .unwrap();
+            RouterBuilder::new(create_test_state().config.clone(), vec![backoffice])
+                .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+                .state()
+        };
+        let delete = |state: Arc<AppState>, id: &str| {
+            execute_delete_handler(
+                State(state),
+                Path(("blog".to_string(), "users".to_string(), "list".to_string())),
+                AuthContext::anonymous(false),
+                RequestMetadata::default(),
+                Query::try_from_uri(&format!("/?id={}", id).parse().unwrap()).unwrap(),
+            )
+        };
+
+        let restrict = state("restrict");
+        let source = restrict
+            .data_sources
+            .create(&restrict.backoffices[0].data_sources["db"])
+            .await
+            .unwrap();
+        for statement in [
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+            "CREATE TABLE posts (id INTEGER PRIMARY KEY, author_id INTEGER)",
+            "INSERT INTO users VALUES (1, 'alice'), (2, 'bob')",
+            "INSERT INTO posts VALUES (1, 1), (2, 2), (3, 1)",
+        ] {
+            source
+                .execute_mutation(statement, &HashMap::new())
+                .await
+                .unwrap();
+        }
+
+        let response = delete(restrict.clone(), "1").await.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["dependents"],
+            serde_json::json!([{"relationship_id": "author", "section": "posts", "ids": [1, 3]}])
+        );
+
+        let response = delete(state("set_null"), "1").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let posts = source
+            .execute_query("SELECT id, author_id FROM posts ORDER BY id", None)
+            .await
+            .unwrap();
+        let authors: Vec<&Value> = posts.iter().map(|p| &p["author_id"]).collect();
+        assert_eq!(authors, [&Value::Null, &serde_json::json!(2), &Value::Null]);
+
+        let response = delete(state("cascade"), "2").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let posts = source
+            .execute_query("SELECT id FROM posts", None)
+            .await
+            .unwrap();
+        assert_eq!(posts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_nested_create() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: shop
+name: Shop
+relationships:
+  - id: items
+    name: Items
+    relationship_type: onetomany
+    from_section: orders
+    from_field: id
+    to_section: order_items
+    to_field: order_id
+  - id: tags
+    name: Tags
+    relationship_type: !manytomany
+      junction_table: order_tags
+      from_junction_field: order_id
+      to_junction_field: tag_id
+    from_section: orders
+    from_field: id
+    to_section: tags
+    to_field: id
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: customer, name: Customer, field_type: text, required: true }
+  - id: order_items
+    name: Items
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: order_id, name: Order, field_type: number, required: true }
+          - { id: sku, name: SKU, field_type: text, required: true }
+  - id: tags
+    name: Tags
+    actions: []
+"#,
+            &[
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY AUTOINCREMENT, customer TEXT)",
+            "CREATE TABLE order_items (id INTEGER PRIMARY KEY, order_id INTEGER NOT NULL, sku TEXT)",
+            "CREATE TABLE order_tags (order_id INTEGER, tag_id INTEGER, PRIMARY KEY (order_id, tag_id))",
+            "INSERT INTO orders (customer) VALUES ('existing')",
+            ],
+        )
+        .await;
+
+        let create = |payload: Value| {
+            execute_mutation_handler(
+                State(state.clone()),
+                Path((
+                    "shop".to_string(),
+                    "orders".to_string(),
+                    "create".to_string(),
+                )),
+                AuthContext::anonymous(false),
+                RequestMetadata::default(),
+                Json(serde_json::from_value(payload).unwrap()),
+            )
+        };
+        let count = |table: &str| {
+            let (db, query) = (&db, format!("SELECT COUNT(*) AS n FROM {}", table));
+            async move { db.execute_query(&query, None).await.unwrap()[0]["n"].clone() }
+        };
+
+        let response = create(serde_json::json!({
+            "customer": "acme",
+            "items": [{"sku": "a"}, {"sku": "b"}],
+            "tags": [1, 2],
+        }))
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["nested"], serde_json::json!({"items": 2, "tags": 2}));
+        let items = db
+            .execute_query("SELECT order_id FROM order_items", None)
+            .await
+            .unwrap();
+        assert!(items.iter().all(|i| i["order_id"] == serde_json::json!(2)));
+        assert_eq!(count("order_tags").await, serde_json::json!(2));
+
+        // Invalid children are rejected before anything is written
+        let response = create(serde_json::json!({"customer": "x", "items": [{}]}))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["validation_errors"][0]["field"], "items[0].sku");
+
+        // A failing junction row rolls back the parent and its children
+        let response = create(serde_json::json!({
+            "customer": "dup",
+            "items": [{"sku": "c"}],
+            "tags": [3, 3],
+        }))
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(count("orders").await, serde_json::json!(2));
+        assert_eq!(count("order_items").await, serde_json::json!(2));
+    }
+
+    #[tokio::test]
+    async fn test_tree_relationships() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: shop
+name: Shop
+relationships:
+  - id: parent
+    name: Parent
+    relationship_type: manytoone
+    from_section: categories
+    from_field: parent_id
+    to_section: categories
+    to_field: id
+    on_delete: cascade
+sections:
+  - id: categories
+    name: Categories
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+          - { id: parent_id, name: Parent, field_type: text, relationship_id: parent }
+      - id: edit
+        name: Edit
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: parent_id, name: Parent, field_type: text }
+"#,
+            &[
+            "CREATE TABLE categories (id TEXT PRIMARY KEY, name TEXT, parent_id TEXT)",
+            "INSERT INTO categories VALUES ('a', 'A', NULL), ('b', 'B', 'a'), ('c', 'C', 'b'), ('d', 'D', NULL)",
+            ],
+        )
+        .await;
+
+        let tree = |uri: &str| {
+            let state = state.clone();
+            let uri = uri.to_string();
+            async move {
+                let response = tree_handler(
+                    State(state),
+                    Path(("shop".into(), "categories".into(), "parent".into())),
+                    AuthContext::anonymous(false),
+                    Query::try_from_uri(&uri.parse().unwrap()).unwrap(),
+                )
+                .await;
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<Value>(&body).unwrap()["data"].clone()
+            }
+        };
+
+        let roots = tree("/").await;
+        assert_eq!(roots.as_array().unwrap().len(), 2);
+        assert_eq!(roots[0]["children"][0]["children"][0]["id"], "c");
+        assert_eq!(roots[1]["has_children"], false);
+
+        let roots = tree("/?depth=1").await;
+        assert_eq!(roots[0]["has_children"], true);
+        assert!(roots[0].get("children").is_none());
+
+        let subtree = tree("/?root=b").await;
+        assert_eq!(subtree[0]["id"], "c");
+
+        // Moving a node under its own descendant is a cycle
+        let response = execute_mutation_handler(
+            State(state.clone()),
+            Path(("shop".into(), "categories".into(), "edit".into())),
+            AuthContext::anonymous(false),
+            RequestMetadata::default(),
+            Json(serde_json::from_value(serde_json::json!({"id": "a", "parent_id": "c"})).unwrap()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["relationship_errors"][0]["field"], "parent_id");
+
+        // Deleting a node deletes its subtree
+        let response = execute_delete_handler(
+            State(state.clone()),
+            Path(("shop".into(), "categories".into(), "list".into())),
+            AuthContext::anonymous(false),
+            RequestMetadata::default(),
+            Query::try_from_uri(&"/?id=a".parse().unwrap()).unwrap(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let rows = db
+            .execute_query("SELECT id FROM categories", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_related_records() {
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: blog
+name: Blog
+relationships:
+  - id: user_posts
+    name: Posts
+    relationship_type: onetomany
+    from_section: users
+    from_field: id
+    to_section: posts
+    to_field: user_id
+    display_fields: [title]
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+  - id: posts
+    name: Posts
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: title, name: Title, field_type: text }
+          - { id: user_id, name: Author, field_type: text }
+"#,
+            &[
+            "CREATE TABLE users (id TEXT PRIMARY KEY, name TEXT)",
+            "CREATE TABLE posts (id TEXT PRIMARY KEY, title TEXT, user_id TEXT)",
+            "INSERT INTO users VALUES ('u1', 'Ann'), ('u2', 'Bob')",
+            "INSERT INTO posts VALUES ('p1', 'Rust tips', 'u1'), ('p2', 'Cooking', 'u1'), ('p3', 'Rust news', 'u1'), ('p4', 'Other', 'u2')",
+            ],
+        )
+        .await;
+
+        let related = |record_id: &str, uri: &str| {
+            let state = state.clone();
+            let record_id = record_id.to_string();
+            let uri = uri.to_string();
+            async move {
+                let response = related_records_handler(
+                    State(state),
+                    Path((
+                        "blog".into(),
+                        "users".into(),
+                        record_id,
+                        "user_posts".into(),
+                    )),
+                    AuthContext::anonymous(false),
+                    Query::try_from_uri(&uri.parse().unwrap()).unwrap(),
+                )
+                .await;
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+
+        let (status, json) = related("u1", "/").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["pagination"]["total_items"], 3);
+        assert_eq!(json["data"][0]["title"], "Rust tips");
+
+        let (_, json) = related("u1", "/?search=rust&page_size=1&page=2").await;
+        assert_eq!(json["pagination"]["total_items"], 2);
+        assert_eq!(json["pagination"]["total_pages"], 2);
+        assert_eq!(json["data"][0]["title"], "Rust news");
+
+        let uri = format!("/?page_size=100000&page={}", usize::MAX);
+        let (status, json) = related("u1", &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["pagination"]["page_size"], 1000);
+        assert_eq!(json["data"], serde_json::json!([]));
+
+        let (status, json) = related("missing", "/").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["data"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_seed_without_fixtures() {
+        let state = create_test_state();
+
+        let response = seed_handler(
+            State(state.clone()),
+            Path("missing".to_string()),
+            AuthContext::anonymous(false),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // No section declares seeds, so nothing is inserted
+        let response = seed_handler(
+            State(state),
+            Path("test".to_string()),
+            AuthContext::anonymous(false),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_audit_search() {
+        let dir = TempDir::new();
+        let base = create_test_state();
+        let state = RouterBuilder::new(base.config.clone(), base.backoffices.clone())
+            .audit_logger(Arc::new(AuditLogger::new(dir.to_path_buf())))
+            .state();
+
+        let data = HashMap::from([("name".to_string(), serde_json::json!("a"))]);
+        for (section, record, user) in [
+            ("test_section", "1", "alice"),
+            ("test_section", "2", "bob"),
+            ("test_section", "1", "bob"),
+            ("other_section", "1", "alice"),
+        ] {
+            let entry = AuditLogger::create_entry(
+                section.to_string(),
+                Some(record.to_string()),
+                &data,
+                Some(user.to_string()),
+            );
+            state.audit_logger.log(entry).await.unwrap();
+        }
+
+        let search = |uri: &str| {
+            let state = state.clone();
+            let uri = uri.to_string();
+            async move {
+                let response = audit_search_handler(
+                    State(state),
+                    Path("test".to_string()),
+                    AuthContext::anonymous(false),
+                    Query::try_from_uri(&uri.parse().unwrap()).unwrap(),
+                )
+                .await;
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, serde_json::from_slice::<Value>(&body).unwrap())
+            }
+        };
+
+        // Entries of sections outside the backoffice are never returned
+        let (status, json) = search("/").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["pagination"]["total_items"], 3);
+        assert_eq!(json["data"][0]["user_id"], "bob");
+
+        let (_, json) = search("/?record_id=1&sort=user_id&order=asc&page_size=1").await;
+        assert_eq!(json["pagination"]["total_pages"], 2);
+        assert_eq!(json["data"][0]["user_id"], "alice");
+
+        let (_, json) = search("/?user=bob&operation=create&to=2000-01-01").await;
+        assert_eq!(json["pagination"]["total_items"], 0);
+
+        let (status, _) = search("/?from=yesterday").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    /// Audited `users` section with a generated edit form, on a sqlite
+    /// database holding user 1 (alice)
+    async fn audited_users() -> (Arc<AppState>, Box<dyn data_source::DataSource>, TempDir) {
+        sqlite_backoffice(
+            r#"
+id: crm
+name: CRM
+sections:
+  - id: users
+    name: Users
+    audit: { enable_rollback: true }
+    actions:
+      - id: edit
+        name: Edit
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+          - { id: password, name: Password, field_type: password, sensitive: true }
+"#,
+            &[
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, password TEXT)",
+                "INSERT INTO users VALUES (1, 'alice', 'secret')",
+            ],
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_audit_old_values() {
+        let (state, _db, _dir) = audited_users().await;
+        let path = || Path(("crm".to_string(), "users".to_string(), "edit".to_string()));
+
+        let response = execute_mutation_handler(
+            State(state.clone()),
+            path(),
+            AuthContext::anonymous(false),
+            RequestMetadata::default(),
+            Json(serde_json::from_value(serde_json::json!({"id": 1, "name": "alicia"})).unwrap()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = execute_delete_handler(
+            State(state.clone()),
+            path(),
+            AuthContext::anonymous(false),
+            RequestMetadata::default(),
+            Query::try_from_uri(&"/?id=1".parse().unwrap()).unwrap(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let page = state
+            .audit_logger
+            .search(
+                &AuditFilter {
+                    sections: vec!["users".to_string()],
+                    ..Default::default()
+                },
+                AuditSortField::Timestamp,
+                false,
+                &crate::data_source::PaginationParams::new(1, 10),
+            )
+            .await
+            .unwrap();
+        let [update, delete] = &page.entries[..] else {
+            panic!("expected two audit entries, got {:?}", page.entries);
+        };
+        assert_eq!(update.operation, AuditOperation::Update);
+        let old = update.old_values.as_ref().unwrap();
+        assert_eq!(old["name"], "alice");
+        assert_ne!(old["password"], "secret");
+        assert_eq!(update.new_values.as_ref().unwrap()["name"], "alicia");
+        assert_eq!(update.changes.len(), 1);
+        assert_eq!(update.changes[0].field, "name");
+
+        assert_eq!(delete.operation, AuditOperation::Delete);
+        assert_eq!(delete.old_values.as_ref().unwrap()["name"], "alicia");
+
+        let response = record_history_handler(
+            State(state.clone()),
+            Path(("crm".to_string(), "users".to_string(), "1".to_string())),
+            AuthContext::anonymous(false),
+            Query::try_from_uri(&"/?order=desc&page_size=1".parse().unwrap()).unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["pagination"]["total_items"], 2);
+        assert_eq!(json["data"][0]["operation"], "delete");
+        assert_eq!(json["data"][0]["values"], Value::Null);
+        let response = record_history_handler(
+            State(state.clone()),
+            Path(("crm".to_string(), "users".to_string(), "1".to_string())),
+            AuthContext::anonymous(false),
+            Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+        )
+        .await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"][0]["values"]["name"], "alicia");
+        assert_eq!(json["data"][0]["changes"][0]["old_value"], "alice");
+    }
+
+    #[tokio::test]
+    async fn test_restore_record() {
+        let (state, db, _dir) = audited_users().await;
+
+        let response = execute_mutation_handler(
+            State(state.clone()),
+            Path(("crm".to_string(), "users".to_string(), "edit".to_string())),
+            AuthContext::anonymous(false),
+            RequestMetadata::default(),
+            Json(
+                serde_json::from_value(
+                    serde_json::json!({"id": 1, "name": "mallory", "password": "new"}),
+                )
+                .unwrap(),
+            ),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let entries = |state: Arc<AppState>| async move {
+            let filter = AuditFilter {
+                sections: vec!["users".to_string()],
+                ..Default::default()
+            };
+            let pagination = crate::data_source::PaginationParams::new(1, 10);
+            let search =
+                state
+                    .audit_logger
+                    .search(&filter, AuditSortField::Timestamp, false, &pagination);
+            search.await.unwrap().entries
+        };
+        let version = entries(state.clone()).await[0].id.clone();
+
+        let restore = |uri: String| {
+            restore_record_handler(
+                State(state.clone()),
+                Path(("crm".to_string(), "users".to_string(), "1".to_string())),
+                AuthContext::anonymous(false),
+                RequestMetadata::default(),
+                Query::try_from_uri(&uri.parse().unwrap()).unwrap(),
+            )
+        };
+        let response = restore(format!("/?version={}", version)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let user = db
+            .execute_query("SELECT name, password FROM users WHERE id = 1", None)
+            .await
+            .unwrap();
+        // Sensitive fields aren't restored from their masked audit values
+        assert_eq!(user[0]["name"], "alice");
+        assert_eq!(user[0]["password"], "new");
+        let restored = entries(state.clone()).await.pop().unwrap();
+        assert_eq!(restored.operation, AuditOperation::Update);
+        assert_eq!(restored.metadata["restored_from"], version);
+
+        let response = restore("/?version=missing".to_string()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_patch_field() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: crm
+name: CRM
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: edit
+        name: Edit
+        type: form
+        data_source: db
+        query: "UPDATE users SET name = {{name}}, email = {{email}} WHERE id = {{id}}"
+        required_scopes: []
+        fields:
+          - id: name
+            name: Name
+            field_type: text
+            required: true
+            validations:
+              - rule_type: { type: min_length, value: 2 }
+          - { id: email, name: Email, field_type: email, required: true }
+          - { id: created_at, name: Created, field_type: text, editable: false }
+"#,
+            &[
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT, created_at TEXT)",
+            "INSERT INTO users VALUES (1, 'alice', 'alice@example.com', '2024-01-01')",
+            ],
+        )
+        .await;
+        let patch = |field: &str, value: Value| {
+            patch_field_handler(
+                State(state.clone()),
+                Path((
+                    "crm".to_string(),
+                    "users".to_string(),
+                    "1".to_string(),
+                    field.to_string(),
+                )),
+                AuthContext::anonymous(false),
+                RequestMetadata::default(),
+                Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+                Json(FieldPatch {
+                    value,
+                    accept_warnings: false,
+                }),
+            )
+        };
+
+        // Only the patched column is written, and the other required field
+        // isn't asked for
+        let response = patch("name", serde_json::json!("alicia")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let user = db
+            .execute_query("SELECT name, email FROM users WHERE id = 1", None)
+            .await
+            .unwrap();
+        assert_eq!(user[0]["name"], "alicia");
+        assert_eq!(user[0]["email"], "alice@example.com");
+
+        let response = patch("name", serde_json::json!("a")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = patch("created_at", serde_json::json!("2025-01-01")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = patch("missing", serde_json::json!("x")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_record() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: crm
+name: CRM
+sections:
+  - id: plans
+    name: Plans
+    audit: {}
+    duplicate:
+      strip: [slug]
+      regenerate: { name: copy_suffix }
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text, required: true }
+          - { id: slug, name: Slug, field_type: text }
+          - { id: price, name: Price, field_type: number }
+  - id: users
+    name: Users
+    actions: []
+"#,
+            &[
+            "CREATE TABLE plans (id INTEGER PRIMARY KEY, name TEXT, slug TEXT UNIQUE, price REAL)",
+            "INSERT INTO plans VALUES (1, 'Pro', 'pro', 20)",
+            ],
+        )
+        .await;
+        let duplicate = |section: &str, id: &str| {
+            duplicate_record_handler(
+                State(state.clone()),
+                Path(("crm".to_string(), section.to_string(), id.to_string())),
+                AuthContext::anonymous(false),
+                RequestMetadata::default(),
+                Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+            )
+        };
+
+        let response = duplicate("plans", "1").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let plans = db
+            .execute_query("SELECT name, slug, price FROM plans ORDER BY id", None)
+            .await
+            .unwrap();
+        assert_eq!(plans.len(), 2);
+        assert_eq!(plans[1]["name"], "Pro (copy)");
+        assert_eq!(plans[1]["slug"], Value::Null);
+        assert_eq!(plans[1]["price"], plans[0]["price"]);
+
+        let filter = AuditFilter {
+            sections: vec!["plans".to_string()],
+            ..Default::default()
+        };
+        let pagination = crate::data_source::PaginationParams::new(1, 10);
+        let entries = state
+            .audit_logger
+            .search(&filter, AuditSortField::Timestamp, false, &pagination)
+            .await
+            .unwrap()
+            .entries;
+        assert_eq!(entries[0].operation, AuditOperation::Create);
+        assert_eq!(entries[0].metadata[duplicate::DUPLICATED_FROM], "1");
+
+        let response = duplicate("plans", "9").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = duplicate("users", "1").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_state_transitions() {
+        let (state, _, _dir) = sqlite_backoffice(
+            r#"
+id: blog
+name: Blog
+sections:
+  - id: posts
+    name: Posts
+    actions:
+      - id: edit
+        name: Edit
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - id: status
+            name: Status
+            field_type: state
+            config:
+              states:
+                - { value: draft, label: Draft }
+                - { value: review, label: In review }
+                - { value: published, label: Published }
+              transitions:
+                - { from: draft, to: review, label: Submit }
+                - { from: review, to: draft }
+                - { from: review, to: published, required_scopes: [publish] }
+"#,
+            &[
+                "CREATE TABLE posts (id INTEGER PRIMARY KEY, status TEXT)",
+                "INSERT INTO posts VALUES (1, 'draft')",
+            ],
+        )
+        .await;
+        state.backoffices[0].validate_state_fields().unwrap();
+        let update = |status: &str| {
+            execute_mutation_handler(
+                State(state.clone()),
+                Path(("blog".to_string(), "posts".to_string(), "edit".to_string())),
+                AuthContext::anonymous(true),
+                RequestMetadata::default(),
+                Json(
+                    serde_json::from_value(serde_json::json!({"id": 1, "status": status})).unwrap(),
+                ),
+            )
+        };
+        let body = |response: Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let response = update("published").await.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let json = body(response).await;
+        assert_eq!(json["transition_errors"][0]["from"], "draft");
+        assert_eq!(
+            json["transition_errors"][0]["allowed"],
+            serde_json::json!(["review"])
+        );
+
+        let response = update("review").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = record_transitions_handler(
+            State(state.clone()),
+            Path(("blog".to_string(), "posts".to_string(), "1".to_string())),
+            AuthContext::anonymous(true),
+            Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body(response).await;
+        // Publishing needs a scope the caller lacks
+        assert_eq!(
+            json["fields"],
+            serde_json::json!([{
+                "field": "status",
+                "current": "review",
+                "transitions": [{"to": "draft", "label": "Draft"}],
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bulk_update() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: shop
+name: Shop
+sections:
+  - id: orders
+    name: Orders
+    audit: {}
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: status, name: Status, field_type: text }
+        config:
+          filters:
+            - { id: country, name: Country, field: country, filter_type: text }
+      - id: edit
+        name: Edit
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - id: status
+            name: Status
+            field_type: text
+            validations:
+              - rule_type: { type: min_length, value: 3 }
+          - { id: total, name: Total, field_type: number, editable: false }
+"#,
+            &[
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, status TEXT, country TEXT, total REAL)",
+            "INSERT INTO orders VALUES (1, 'paid', 'ES', 10), (2, 'paid', 'ES', 20), \
+             (3, 'paid', 'FR', 30), (4, 'new', 'ES', 40)",
+            ],
+        )
+        .await;
+        let bulk = |body: Value| {
+            bulk_update_handler(
+                State(state.clone()),
+                Path(("shop".to_string(), "orders".to_string(), "edit".to_string())),
+                AuthContext::anonymous(false),
+                RequestMetadata::default(),
+                Json(serde_json::from_value(body).unwrap()),
+            )
+        };
+        let filter = serde_json::json!({"country": "ES", "status": "paid"});
+
+        let response =
+            bulk(serde_json::json!({"filter": filter, "data": {"status": "shipped"}})).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = bulk(serde_json::json!({
+            "filter": filter, "data": {"status": "shipped"}, "expected_count": 3
+        }))
+        .await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(response_json(response).await.1["count"], 2);
+        let shipped = db
+            .execute_query("SELECT id FROM orders WHERE status = 'shipped'", None)
+            .await
+            .unwrap();
+        assert!(shipped.is_empty());
+        let response = bulk(serde_json::json!({
+            "filter": filter, "data": {"status": "x"}, "confirm": true
+        }))
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = bulk(serde_json::json!({
+            "filter": filter, "data": {"total": 0}, "confirm": true
+        }))
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = bulk(serde_json::json!({
+            "filter": filter, "data": {"status": "shipped"}, "expected_count": 2
+        }))
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let orders = db
+            .execute_query("SELECT id FROM orders WHERE status = 'shipped'", None)
+            .await
+            .unwrap();
+        assert_eq!(orders.len(), 2);
+
+        let filter = AuditFilter {
+            sections: vec!["orders".to_string()],
+            ..Default::default()
+        };
+        let pagination = crate::data_source::PaginationParams::new(1, 10);
+        let entries = state
+            .audit_logger
+            .search(&filter, AuditSortField::Timestamp, false, &pagination)
+            .await
+            .unwrap()
+            .entries;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].metadata["affected_count"], "2");
+    }
+
+    #[tokio::test]
+    async fn test_row_filters_bind_claims() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: bank
+name: Bank
+sections:
+  - id: accounts
+    name: Accounts
+    row_filters:
+      - { field: tenant_id, claim: tenant }
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+          - { id: tenant_id, name: Tenant, field_type: text }
+      - id: edit
+        name: Edit
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+"#,
+            &[
+                "CREATE TABLE accounts (id INTEGER PRIMARY KEY, name TEXT, tenant_id TEXT)",
+                "INSERT INTO accounts VALUES (1, 'a', 't1'), (2, 'a', 't2'), (3, 'b', 't2')",
+            ],
+        )
+        .await;
+        let tenant = |tenant: &str| AuthContext::from_claims(serde_json::json!({"tenant": tenant}));
+        let ids = || ("bank".to_string(), "accounts".to_string());
+        let list = |auth: AuthContext| {
+            let state = state.clone();
+            async move {
+                let response = query_action(
+                    State(state),
+                    Path((ids().0, ids().1, "list".to_string())),
+                    auth,
+                    Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+                )
+                .await
+                .into_response();
+                response_json(response).await.1["data"]
+                    .as_array()
+                    .map_or(0, Vec::len)
+            }
+        };
+
+        assert_eq!(list(tenant("t2")).await, 2);
+        // Claims are bound, never part of the SQL
+        assert_eq!(list(tenant("t1' OR '1'='1")).await, 0);
+        assert_eq!(list(tenant("t1\\' OR 1=1 -- ")).await, 0);
+
+        let response = bulk_update_handler(
+            State(state.clone()),
+            Path((ids().0, ids().1, "edit".to_string())),
+            tenant("t1"),
+            RequestMetadata::default(),
+            Json(
+                serde_json::from_value(serde_json::json!({
+                    "filter": {"name": "a"}, "data": {"name": "renamed"}, "confirm": true
+                }))
+                .unwrap(),
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for id in ["2", "1"] {
+            let response = execute_delete_handler(
+                State(state.clone()),
+                Path((ids().0, ids().1, "edit".to_string())),
+                tenant("t1"),
+                RequestMetadata::default(),
+                Query::try_from_uri(&format!("/?id={}", id).parse().unwrap()).unwrap(),
+            )
+            .await
+            .into_response();
+            let expected = match id {
+                "1" => StatusCode::OK,
+                _ => StatusCode::NOT_FOUND,
+            };
+            assert_eq!(response.status(), expected, "{}", id);
+        }
+
+        let rows = db
+            .execute_query("SELECT id, name FROM accounts ORDER BY id", None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["name"], "a");
+    }
+
+    #[tokio::test]
+    async fn test_import() {
+        let mut config = test_config();
+        config.i18n.messages.insert(
+            "es".to_string(),
+            HashMap::from([("required".to_string(), "{field} es obligatorio".to_string())]),
+        );
+        let (state, db, _dir) = sqlite_backoffice_with(
+            config,
+            r#"
+id: shop
+name: Shop
+sections:
+  - id: customers
+    name: Customers
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text, required: true }
+          - { id: joined, name: Joined, field_type: date }
+        config:
+          import:
+            columns: { Customer: name, Since: joined }
+            date_formats: { joined: "%d/%m/%Y" }
+"#,
+            &["CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT, joined TEXT)"],
+            |builder| builder,
+        )
+        .await;
+        let import_in = |locale: Option<&str>, dry_run: bool, body: &str| {
+            import_handler(
+                State(state.clone()),
+                Path((
+                    "shop".to_string(),
+                    "customers".to_string(),
+                    "create".to_string(),
+                )),
+                Query(ImportQuery {
+                    format: None,
+                    dry_run,
+                }),
+                AuthContext::anonymous(false),
+                RequestMetadata {
+                    locale: locale.map(String::from),
+                    ..Default::default()
+                },
+                body.to_string(),
+            )
+        };
+        let import = |dry_run: bool, body: &str| import_in(None, dry_run, body);
+        let count = || async {
+            db.execute_query("SELECT id FROM customers", None)
+                .await
+                .unwrap()
+                .len()
+        };
+        async fn response_json(response: Response) -> Value {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice(&body).unwrap()
+        }
+        let messy = "Customer,Since,Notes\nAda,10/12/2020,vip\n,01/01/2021,\nBob,2021-01-01,\n";
+
+        // Dry runs report every error without writing
+        let response = import(true, messy).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_json(response).await;
+        assert_eq!(body["valid"], serde_json::json!(false));
+        assert_eq!(body["ignored_columns"], serde_json::json!(["Notes"]));
+        let errors: Vec<_> = body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["row"].as_u64().unwrap(), e["field"].as_str().unwrap()))
+            .collect();
+        assert_eq!(errors, vec![(2, "name"), (3, "joined")]);
+        assert_eq!(body["errors"][0]["code"], "required");
+        assert_eq!(body["errors"][0]["message"], "Name is required");
+        assert_eq!(
+            body["errors"][1]["params"],
+            serde_json::json!({"field": "Joined", "value": "2021-01-01", "format": "%d/%m/%Y"})
+        );
+        assert_eq!(count().await, 0);
+
+        // Messages follow the locale the request prefers
+        let body = response_json(import_in(Some("es-ES,en;q=0.5"), true, messy).await).await;
+        assert_eq!(body["errors"][0]["message"], "Name es obligatorio");
+
+        // Without dry run nothing is written unless every row is valid
+        let response = import(false, messy).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(count().await, 0);
+
+        let clean = "Customer,Since\nAda,10/12/2020\nBob,01/01/2021\n";
+        let response = import(true, clean).await;
+        assert_eq!(
+            response_json(response).await["valid"],
+            serde_json::json!(true)
+        );
+        assert_eq!(count().await, 0);
+        let response = import(false, clean).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response_json(response).await["imported"],
+            serde_json::json!(2)
+        );
+        let rows = db
+            .execute_query("SELECT joined FROM customers WHERE name = 'Ada'", None)
+            .await
+            .unwrap();
+        assert_eq!(rows[0]["joined"], serde_json::json!("2020-12-10"));
+    }
+
+    /// Object storage presigning PUTs to a local bucket
+    struct Bucket(String);
+
+    #[async_trait::async_trait]
+    impl data_source::DataSource for Bucket {
+        async fn execute_query(
+            &self,
+            _query: &str,
+            _params: Option<&HashMap<String, Value>>,
+        ) -> Result<Vec<HashMap<String, Value>>> {
+            Ok(Vec::new())
+        }
+
+        async fn execute_query_paginated(
+            &self,
+            _query: &str,
+            _params: Option<&HashMap<String, Value>>,
+            _pagination: Option<&data_source::PaginationParams>,
+        ) -> Result<Vec<HashMap<String, Value>>> {
+            Ok(Vec::new())
+        }
+
+        async fn execute_mutation(
+            &self,
+            _query: &str,
+            _data: &HashMap<String, Value>,
+        ) -> Result<Value> {
+            Ok(Value::Null)
+        }
+
+        async fn presign(
+            &self,
+            key: &str,
+            _method: data_source::PresignMethod,
+            expires_in: Duration,
+        ) -> Result<data_source::PresignedUrl> {
+            Ok(data_source::PresignedUrl {
+                url: format!("{}/{}", self.0, key),
+                method: "PUT".to_string(),
+                headers: HashMap::new(),
+                expires_in: expires_in.as_secs(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_is_scanned() {
+        use axum::body::Bytes;
+        use axum::routing::put;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let stored = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        let bucket = Router::new().route(
+            "/*key",
+            put({
+                let stored = stored.clone();
+                move |Path(key): Path<String>, body: Bytes| async move {
+                    assert_eq!(body, "plain text");
+                    stored.lock().unwrap().push(key);
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bucket_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, bucket).await.unwrap() });
+
+        // clamd flagging files containing EICAR
+        let clamd = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let clamd_address = clamd.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = clamd.accept().await.unwrap();
+                let mut content = vec![0u8; 10];
+                socket.read_exact(&mut content).await.unwrap();
+                loop {
+                    let length = socket.read_u32().await.unwrap() as usize;
+                    if length == 0 {
+                        break;
+                    }
+                    let mut chunk = vec![0u8; length];
+                    socket.read_exact(&mut chunk).await.unwrap();
+                    content.extend(chunk);
+                }
+                let reply: &[u8] = if content.windows(5).any(|w| w == b"EICAR") {
+                    b"stream: Eicar-Test-Signature FOUND\0"
+                } else {
+                    b"stream: OK\0"
+                };
+                socket.write_all(reply).await.unwrap();
+            }
+        });
+
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: docs
+name: Docs
+data_sources:
+  bucket: { type: custom, factory: bucket }
+sections:
+  - id: contracts
+    name: Contracts
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: bucket
+        query: contracts
+        required_scopes: []
+        fields:
+          - id: scan
+            name: Scan
+            field_type: file
+            config: { storage: bucket, accepted_types: [text/plain, .pdf], max_size_mb: 1 }
+          - id: trusted
+            name: Trusted
+            field_type: file
+            config: { storage: bucket, skip_virus_scan: true }
+"#,
+        )
+        .unwrap();
+        let mut config = create_test_state().config.clone();
+        config.antivirus = Some(crate::config::AntivirusConfig {
+            address: clamd_address,
+            timeout_secs: 5,
+            chunk_bytes: 1024,
+        });
+        let state = RouterBuilder::new(config, vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .data_source_factory("bucket", move |_: &Value| {
+                Ok(Box::new(Bucket(bucket_url.clone())) as Box<dyn data_source::DataSource>)
+            })
+            .state();
+        let upload = |field: &str, filename: &str, content_type: &str, body: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+            upload_handler(
+                State(state.clone()),
+                Path((
+                    "docs".to_string(),
+                    "contracts".to_string(),
+                    "create".to_string(),
+                    field.to_string(),
+                )),
+                Query(UploadQuery {
+                    filename: filename.to_string(),
+                }),
+                AuthContext::anonymous(false),
+                headers,
+                Bytes::from_static(body.as_bytes()),
+            )
+        };
+        async fn response_json(response: Response) -> Value {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        let response = upload("scan", "a.txt", "text/plain", "an EICAR test").await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response_json(response).await;
+        assert_eq!(body["code"], "virus_found");
+        assert_eq!(body["signature"], "Eicar-Test-Signature");
+        assert!(stored.lock().unwrap().is_empty());
+
+        let response = upload("scan", "a.png", "image/png", "plain text").await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let response = upload("scan", "notes v1.txt", "text/plain", "plain text").await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response_json(response).await;
+        assert_eq!(body["scanned"], true);
+        let key = body["key"].as_str().unwrap();
+        assert!(key.starts_with("contracts/scan/") && key.ends_with("-notes_v1.txt"));
+        assert_eq!(stored.lock().unwrap().as_slice(), [key.to_string()]);
+
+        // Only an explicit bypass stores files unscanned
+        let response = upload("trusted", "b.txt", "text/plain", "plain text").await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response_json(response).await["scanned"], false);
+
+        // Presigned uploads would skip the scan
+        let presign = |field: &str| {
+            presign_handler(
+                State(state.clone()),
+                Path(("docs".to_string(), "bucket".to_string())),
+                AuthContext::anonymous(false),
+                Json(PresignRequest {
+                    section: "contracts".to_string(),
+                    action: "create".to_string(),
+                    field: field.to_string(),
+                    key: format!("contracts/{}/c.txt", field),
+                    method: data_source::PresignMethod::Put,
+                    expires_in: None,
+                }),
+            )
+        };
+        let response = presign("scan").await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = presign("trusted").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_presign_is_limited_to_upload_fields() {
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: docs
+name: Docs
+data_sources:
+  bucket: { type: custom, factory: bucket }
+  api: { type: api, base_url: "http://127.0.0.1:9" }
+sections:
+  - id: contracts
+    name: Contracts
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: bucket
+        query: contracts
+        required_scopes: [contracts]
+        fields:
+          - id: scan
+            name: Scan
+            field_type: file
+            config: { storage: bucket }
+          - id: signed
+            name: Signed
+            field_type: file
+            write_scopes: [legal]
+            config: { storage: bucket }
+          - id: remote
+            name: Remote
+            field_type: file
+            config: { storage: api }
+          - { id: title, name: Title, field_type: text }
+"#,
+        )
+        .unwrap();
+        let state = RouterBuilder::new(create_test_state().config.clone(), vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .data_source_factory("bucket", |_: &Value| {
+                Ok(Box::new(Bucket("http://bucket".to_string()))
+                    as Box<dyn data_source::DataSource>)
+            })
+            .state();
+        let presign = |ds: &str, field: &str, key: &str, scope: &str, method, expires_in| {
+            presign_handler(
+                State(state.clone()),
+                Path(("docs".to_string(), ds.to_string())),
+                AuthContext::from_claims(serde_json::json!({"scope": scope})),
+                Json(PresignRequest {
+                    section: "contracts".to_string(),
+                    action: "create".to_string(),
+                    field: field.to_string(),
+                    key: key.to_string(),
+                    method,
+                    expires_in,
+                }),
+            )
+        };
+        let (get, put) = (
+            data_source::PresignMethod::Get,
+            data_source::PresignMethod::Put,
+        );
+        let status = |response: Response| response.status();
 
-    info!(query = %delete_query, "Executing delete");
+        let response = presign(
+            "bucket",
+            "scan",
+            "contracts/scan/a.pdf",
+            "contracts",
+            put,
+            None,
+        );
+        assert_eq!(status(response.await.into_response()), StatusCode::OK);
+        let response = presign(
+            "missing",
+            "scan",
+            "contracts/scan/a.pdf",
+            "contracts",
+            put,
+            None,
+        );
+        assert_eq!(
+            status(response.await.into_response()),
+            StatusCode::NOT_FOUND
+        );
+        let response = presign(
+            "bucket",
+            "title",
+            "contracts/title/a",
+            "contracts",
+            put,
+            None,
+        );
+        assert_eq!(
+            status(response.await.into_response()),
+            StatusCode::BAD_REQUEST
+        );
+        let response = presign(
+            "bucket",
+            "remote",
+            "contracts/remote/a",
+            "contracts",
+            put,
+            None,
+        );
+        assert_eq!(
+            status(response.await.into_response()),
+            StatusCode::NOT_FOUND
+        );
+        let expiry = Some(MAX_PRESIGN_EXPIRY_SECS + 1);
+        let response = presign(
+            "bucket",
+            "scan",
+            "contracts/scan/a.pdf",
+            "contracts",
+            put,
+            expiry,
+        );
+        assert_eq!(
+            status(response.await.into_response()),
+            StatusCode::BAD_REQUEST
+        );
 
-    let mut delete_data = HashMap::new();
-    delete_data.insert("id".to_string(), Value::String(record_id.clone()));
+        // The action's scopes, and the field's to upload
+        let response = presign("bucket", "scan", "contracts/scan/a.pdf", "", get, None);
+        assert_eq!(
+            status(response.await.into_response()),
+            StatusCode::FORBIDDEN
+        );
+        let response = presign(
+            "bucket",
+            "signed",
+            "contracts/signed/a",
+            "contracts",
+            put,
+            None,
+        );
+        assert_eq!(
+            status(response.await.into_response()),
+            StatusCode::FORBIDDEN
+        );
+        let response = presign(
+            "bucket",
+            "signed",
+            "contracts/signed/a",
+            "contracts",
+            get,
+            None,
+        );
+        assert_eq!(status(response.await.into_response()), StatusCode::OK);
 
-    match data_source
-        .execute_mutation(&delete_query, &delete_data)
-        .await
-    {
-        Ok(result) => {
-            info!("Delete executed successfully");
+        // Keys stay under the field's prefix
+        for key in [
+            "other/a.pdf",
+            "contracts/scan/",
+            "contracts/scan/../../other/a.pdf",
+        ] {
+            let response = presign("bucket", "scan", key, "contracts", put, None);
+            assert_eq!(
+                status(response.await.into_response()),
+                StatusCode::FORBIDDEN
+            );
+        }
+    }
 
-            // Log audit trail if enabled
-            if AuditLogger::should_audit(&section.audit, &AuditOperation::Delete) {
-                let audit_entry = AuditLogger::delete_entry(
-                    section_id.clone(),
-                    record_id.clone(),
-                    None, // TODO: Fetch old data before delete if needed
-                    None, // TODO: Extract user ID from auth header
-                );
+    #[tokio::test]
+    async fn test_router_builder_base_path() {
+        use tower::ServiceExt;
 
-                if let Err(e) = state.audit_logger.log(audit_entry) {
-                    warn!(error = %e, "Failed to log audit entry");
-                }
-            }
+        let state = create_test_state();
+        let router = RouterBuilder::new(state.config.clone(), state.backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .base_path("/admin/")
+            .build();
 
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "success": true,
-                    "data": result,
-                    "message": format!("Record {} deleted successfully", record_id)
-                })),
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/api/backoffices/test")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
             )
-                .into_response()
-        }
-        Err(e) => {
-            error!(error = %e, "Delete execution failed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": e.to_string()})),
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key("x-request-id"));
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/api/backoffices/test")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
             )
-                .into_response()
-        }
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::{
-        ActionConfig, ActionType, BackofficeConfig, DataSourceConfig, FieldConfig, SectionConfig,
-        ServerConfig,
-    };
+    #[tokio::test]
+    async fn test_api_versions() {
+        use tower::ServiceExt;
 
-    fn create_test_state() -> Arc<AppState> {
-        let config = AppConfig {
-            server: ServerConfig {
-                host: "127.0.0.1".to_string(),
-                port: 3000,
-            },
-            security: Some(crate::config::SecurityConfig {
-                enabled: false,
-                jwt_secret: None,
-            }),
-        };
+        async fn get(router: &Router, uri: &str) -> (StatusCode, HeaderMap, Value) {
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(uri)
+                        .body(axum::body::Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, headers, serde_json::from_slice(&bytes).unwrap())
+        }
 
-        let audit_logger = Arc::new(AuditLogger::new("logs/audit/test"));
+        let state = create_test_state();
+        let mut backoffices = state.backoffices.clone();
+        backoffices[0].api.version = 2;
+        backoffices[0].api.deprecated = vec![1];
+        let router = RouterBuilder::new(state.config.clone(), backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .build();
 
-        let backoffice = BackofficeConfig {
-            id: "test".to_string(),
-            name: "Test Backoffice".to_string(),
-            description: Some("Test description".to_string()),
-            data_sources: HashMap::from([(
-                "test_api".to_string(),
-                DataSourceConfig::Api {
-                    base_url: "https://api.example.com".to_string(),
-                    headers: Some(HashMap::new()),
-                    auth: None,
-                },
-            )]),
-            relationships: vec![],
-            sections: vec![SectionConfig {
-                id: "test_section".to_string(),
-                name: "Test Section".to_string(),
-                icon: Some("fa-test".to_string()),
-                actions: vec![ActionConfig {
-                    id: "test_action".to_string(),
-                    name: "Test Action".to_string(),
-                    action_type: ActionType::List {
-                        fields: vec![FieldConfig {
-                            id: "id".to_string(),
-                            name: "ID".to_string(),
-                            field_type: crate::config::FieldType::Text {
-                                config: Default::default(),
-                            },
-                            required: false,
-                            editable: false,
-                            visible: true,
-                            default_value: None,
-                            placeholder: None,
-                            help_text: None,
-                            validations: vec![],
-                            relationship_id: None,
-                        }],
-                        config: Default::default(),
-                    },
-                    data_source: "test_api".to_string(),
-                    required_scopes: vec![],
-                    query: Some("SELECT * FROM users".to_string()),
-                    endpoint: None,
-                }],
-                audit: None,
-            }],
+        // v1 keeps the handlers' bodies, and is deprecated here
+        let (status, headers, body) = get(&router, "/api/v1/backoffices/test").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], "test");
+        assert_eq!(headers["deprecation"], "true");
+        assert_eq!(
+            headers["link"],
+            "</api/v2/backoffices/test>; rel=\"successor-version\""
+        );
+
+        // Unversioned routes answer with the pinned version
+        let (_, headers, body) = get(&router, "/api/backoffices/test").await;
+        assert_eq!(body["data"]["id"], "test");
+        assert_eq!(body["meta"], serde_json::json!({}));
+        assert!(!headers.contains_key("deprecation"));
+
+        // Errors are problem documents in every version
+        let (status, headers, body) = get(
+            &router,
+            "/api/v2/backoffices/test/sections/missing/actions/list",
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(headers["content-type"], "application/problem+json");
+        assert_eq!(body["status"], 404);
+        assert_eq!(body["detail"], "Section not found");
+
+        backoffices[0].api.versions = vec![2];
+        let router = RouterBuilder::new(state.config.clone(), backoffices)
+            .audit_logger(state.audit_logger.clone())
+            .build();
+        let (status, _, _) = get(&router, "/api/v1/backoffices/test").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+    #[tokio::test]
+    async fn test_request_id_propagation() {
+        use tower::ServiceExt;
+
+        // Users service answering with the request id it was sent
+        let users = Router::new().route(
+            "/users",
+            get(|headers: HeaderMap| async move {
+                let id = headers.get("x-request-id").and_then(|v| v.to_str().ok());
+                Json(serde_json::json!([{"id": 1, "request_id": id}]))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let users_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, users).await.unwrap() });
+
+        let backoffice: BackofficeConfig = serde_yaml::from_str(&format!(
+            r#"
+id: crm
+name: CRM
+data_sources:
+  api:
+    type: api
+    base_url: "{}"
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: api
+        endpoint: /users
+        required_scopes: []
+        fields:
+          - {{ id: request_id, name: Request, field_type: text }}
+"#,
+            users_url
+        ))
+        .unwrap();
+        let router = RouterBuilder::new(create_test_state().config.clone(), vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .build();
+        let get = |uri: &str, id: Option<&str>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(id) = id {
+                request = request.header("x-request-id", id);
+            }
+            let request = request.body(axum::body::Body::empty()).unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let id = response.headers()["x-request-id"].clone();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (id, serde_json::from_slice::<Value>(&bytes).unwrap())
+            }
         };
 
-        Arc::new(AppState {
-            config,
-            backoffices: vec![backoffice],
-            audit_logger,
-        })
+        let (id, body) = get(
+            "/api/backoffices/crm/sections/users/actions/list",
+            Some("req-42"),
+        )
+        .await;
+        assert_eq!(id, "req-42");
+        assert_eq!(body["data"][0]["request_id"], "req-42");
+
+        // Generated ids are forwarded and reported in errors
+        let (id, body) = get("/api/backoffices/crm/sections/users/actions/list", None).await;
+        assert_eq!(body["data"][0]["request_id"], id.to_str().unwrap());
+        let (id, body) = get("/api/backoffices/crm/sections/missing/actions/list", None).await;
+        assert_eq!(body["request_id"], id.to_str().unwrap());
     }
 
     #[tokio::test]
-    async fn test_config_handler() {
+    async fn test_access_log() {
+        use std::sync::Mutex;
+        use tower::ServiceExt;
+
+        #[derive(Clone, Default)]
+        struct Lines(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Lines {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let lines = Lines::default();
+        let writer = lines.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
         let state = create_test_state();
-        let response = config_handler(State(state.clone())).await;
-        let json = response.into_response();
-        assert_eq!(json.status(), StatusCode::OK);
+        let mut config = state.config.clone();
+        config.server.access_log.format = crate::config::AccessLogFormat::Json;
+        let router = RouterBuilder::new(config, state.backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .build();
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/api/backoffices/missing?api_key=abc123&q=ada")
+                    .header("x-request-id", "req-7")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let output = String::from_utf8(lines.0.lock().unwrap().clone()).unwrap();
+        let line = output
+            .lines()
+            .find(|line| line.contains(" access: "))
+            .expect("an access log line");
+        let entry: Value = serde_json::from_str(line.split_once(" access: ").unwrap().1).unwrap();
+        assert_eq!(entry["method"], "GET");
+        assert_eq!(entry["path"], "/api/backoffices/missing");
+        assert_eq!(entry["query"], "api_key=[REDACTED]&q=ada");
+        assert_eq!(entry["status"], 404);
+        assert_eq!(entry["request_id"], "req-7");
+        assert!(entry["latency_ms"].is_number());
+        assert!(!output.contains("abc123"));
     }
 
     #[tokio::test]
-    async fn test_backoffices_handler() {
+    async fn test_router_builder_custom_route() {
+        use tower::ServiceExt;
+
+        async fn section_count(
+            State(state): State<Arc<AppState>>,
+            Path(id): Path<String>,
+        ) -> impl IntoResponse {
+            let sections = state.backoffice(&id).map(|b| b.sections.len());
+            Json(serde_json::json!({ "sections": sections }))
+        }
+
         let state = create_test_state();
-        let response = backoffices_handler(State(state.clone())).await;
-        let json = response.into_response();
-        assert_eq!(json.status(), StatusCode::OK);
+        let router = RouterBuilder::new(state.config.clone(), state.backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .route("/api/custom/:id/sections", get(section_count))
+            .build();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/api/custom/test/sections")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["sections"], 1);
     }
 
     #[tokio::test]
-    async fn test_backoffice_handler_found() {
+    async fn test_response_compression() {
+        use tower::ServiceExt;
+
+        async fn rows() -> impl IntoResponse {
+            Json(serde_json::json!({ "data": vec!["row"; 1000] }))
+        }
+        async fn archive() -> impl IntoResponse {
+            ([("content-type", "application/zip")], vec![0u8; 4096])
+        }
+
         let state = create_test_state();
-        let response = backoffice_handler(State(state.clone()), Path("test".to_string())).await;
-        let json = response.into_response();
-        assert_eq!(json.status(), StatusCode::OK);
+        let router = RouterBuilder::new(state.config.clone(), state.backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .route("/rows", get(rows))
+            .route("/archive", get(archive))
+            .route(
+                "/small",
+                get(|| async { Json(serde_json::json!({ "ok": true })) }),
+            )
+            .build();
+        let request = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .header("accept-encoding", "gzip")
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let response = router.clone().oneshot(request("/rows")).await.unwrap();
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+
+        let response = router.clone().oneshot(request("/archive")).await.unwrap();
+        assert!(response.headers().get("content-encoding").is_none());
+
+        // Small responses are not worth compressing
+        let response = router.oneshot(request("/small")).await.unwrap();
+        assert!(response.headers().get("content-encoding").is_none());
     }
 
     #[tokio::test]
-    async fn test_backoffice_handler_not_found() {
+    async fn test_request_limits() {
+        use tower::ServiceExt;
+
         let state = create_test_state();
-        let response =
-            backoffice_handler(State(state.clone()), Path("nonexistent".to_string())).await;
-        let json = response.into_response();
-        assert_eq!(json.status(), StatusCode::NOT_FOUND);
+        let mut config = state.config.clone();
+        config.server.limits.max_body_bytes = 16;
+        config.server.limits.request_timeout_secs = Some(1);
+        let router = RouterBuilder::new(config, state.backoffices.clone())
+            .audit_logger(state.audit_logger.clone())
+            .route("/echo", post(|body: String| async move { body }))
+            .route(
+                "/slow",
+                get(|| async { tokio::time::sleep(Duration::from_secs(5)).await }),
+            )
+            .build();
+        let echo = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri("/echo")
+                .body(axum::body::Body::from(body))
+                .unwrap()
+        };
+
+        let response = router.clone().oneshot(echo("small")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = router
+            .clone()
+            .oneshot(echo("this body is over the limit"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let request = Request::builder()
+            .uri("/slow")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+    }
+
+    #[test]
+    fn test_circuit_open_is_service_unavailable() {
+        let e = anyhow::Error::from(data_source::CircuitOpenError {
+            retry_after: Duration::from_secs(12),
+        });
+        let response = data_source_error_response(&e);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "12");
     }
 
     #[test]
@@ -930,4 +9235,642 @@ mod tests {
         assert_eq!(state.backoffices.len(), cloned.backoffices.len());
         assert_eq!(state.config.server.port, cloned.config.server.port);
     }
+
+    #[tokio::test]
+    async fn test_signature_is_rendered() {
+        use axum::body::Bytes;
+        use axum::routing::put;
+
+        let stored = Arc::new(std::sync::Mutex::new(Vec::<(String, Bytes)>::new()));
+        let bucket = Router::new().route(
+            "/*key",
+            put({
+                let stored = stored.clone();
+                move |Path(key): Path<String>, body: Bytes| async move {
+                    stored.lock().unwrap().push((key, body));
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bucket_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, bucket).await.unwrap() });
+
+        let expected_url = bucket_url.clone();
+        let (state, db, _dir) = sqlite_backoffice_with(
+            test_config(),
+            r#"
+id: docs
+name: Docs
+data_sources:
+  bucket: { type: custom, factory: bucket }
+sections:
+  - id: contracts
+    name: Contracts
+    actions:
+      - id: sign
+        name: Sign
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+          - id: signature
+            name: Signature
+            field_type: signature
+            config: { width: 40, height: 20, pen_color: '#000', background_color: '#fff', line_width: 2, storage: bucket }
+"#,
+            &["CREATE TABLE contracts (id INTEGER PRIMARY KEY, name TEXT, signature TEXT)"],
+            |builder| {
+                builder.data_source_factory("bucket", move |_: &Value| {
+                    Ok(Box::new(Bucket(bucket_url.clone())) as Box<dyn data_source::DataSource>)
+                })
+            },
+        )
+        .await;
+
+        let response = execute_mutation_handler(
+            State(state.clone()),
+            Path(("docs".into(), "contracts".into(), "sign".into())),
+            AuthContext::anonymous(false),
+            RequestMetadata::default(),
+            Json(
+                serde_json::from_value(serde_json::json!({
+                    "name": "Lease",
+                    "signature": [[[2, 10], [38, 10]], [{"x": 20, "y": 2}, {"x": 20, "y": 18}]],
+                }))
+                .unwrap(),
+            ),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let stored = stored.lock().unwrap().clone();
+        assert_eq!(stored.len(), 1);
+        let (key, png) = &stored[0];
+        assert!(key.starts_with("contracts/signature/") && key.ends_with(".png"));
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let rows = db
+            .execute_query("SELECT signature FROM contracts", None)
+            .await
+            .unwrap();
+        assert_eq!(
+            rows[0]["signature"],
+            serde_json::json!(format!("{}/{}", expected_url, key))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_form() {
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: crm
+name: CRM
+data_sources:
+  db:
+    type: database
+    connection_string: "sqlite::memory:"
+    db_type: sqlite
+sections:
+  - id: customers
+    name: Customers
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: kind, name: Kind, field_type: text, required: true }
+          - id: company
+            name: Company
+            field_type: text
+            visible_when: { field: kind, operator: equals, value: business }
+            required_when: { field: kind, operator: equals, value: business }
+          - { id: id, name: ID, field_type: number, editable: false }
+"#,
+        )
+        .unwrap();
+        let state = RouterBuilder::new(create_test_state().config.clone(), vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .state();
+        let evaluate = |data: Value| {
+            evaluate_form_handler(
+                State(state.clone()),
+                Path(("crm".into(), "customers".into(), "create".into())),
+                AuthContext::anonymous(false),
+                Json(serde_json::from_value(data).unwrap()),
+            )
+        };
+
+        let response = evaluate(serde_json::json!({"kind": "business"})).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json["fields"]["company"],
+            serde_json::json!({"visible": true, "required": true, "disabled": false})
+        );
+        assert_eq!(json["fields"]["id"]["disabled"], true);
+
+        let response = evaluate(serde_json::json!({"kind": "personal"})).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["fields"]["company"]["visible"], false);
+        assert_eq!(json["fields"]["company"]["required"], false);
+
+        // Validation requires the company of business customers only
+        let response = execute_mutation_handler(
+            State(state.clone()),
+            Path(("crm".into(), "customers".into(), "create".into())),
+            AuthContext::anonymous(false),
+            RequestMetadata::default(),
+            Json(serde_json::from_value(serde_json::json!({"kind": "business"})).unwrap()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["validation_errors"][0]["field"], "company");
+    }
+
+    #[tokio::test]
+    async fn test_warning_severity() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: shop
+name: Shop
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - id: total
+            name: Total
+            field_type: number
+            validations:
+              - rule_type: { type: min, value: 0 }
+              - rule_type: { type: max, value: 1000 }
+                severity: warning
+                message: "{field} is unusually high"
+"#,
+            &["CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL)"],
+        )
+        .await;
+        let create = |payload: Value| {
+            execute_mutation_handler(
+                State(state.clone()),
+                Path(("shop".into(), "orders".into(), "create".into())),
+                AuthContext::anonymous(false),
+                RequestMetadata::default(),
+                Json(serde_json::from_value(payload).unwrap()),
+            )
+        };
+        let body = |response: Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        // Errors still block whatever the client accepts
+        let response = create(serde_json::json!({"total": -1, "accept_warnings": true}))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = create(serde_json::json!({"total": 5000}))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let json = body(response).await;
+        assert_eq!(json["warnings"][0]["code"], "max");
+        assert_eq!(json["warnings"][0]["message"], "Total is unusually high");
+        let rows = db
+            .execute_query("SELECT * FROM orders", None)
+            .await
+            .unwrap();
+        assert!(rows.is_empty());
+
+        let response = create(serde_json::json!({"total": 5000, "accept_warnings": true}))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body(response).await;
+        assert_eq!(json["warnings"][0]["field"], "total");
+        let rows = db
+            .execute_query("SELECT total FROM orders", None)
+            .await
+            .unwrap();
+        assert_eq!(rows[0]["total"], serde_json::json!(5000.0));
+    }
+
+    #[tokio::test]
+    async fn test_validate_without_saving() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: crm
+name: CRM
+sections:
+  - id: users
+    name: Users
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text, required: true }
+          - id: email
+            name: Email
+            field_type: email
+            validations:
+              - rule_type: { type: unique_in, field_list: [] }
+"#,
+            &[
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT)",
+                "INSERT INTO users (name, email) VALUES ('Ada', 'ada@example.com')",
+            ],
+        )
+        .await;
+        let validate = |partial: bool, payload: Value| {
+            let uri = format!("/?partial={}", partial).parse().unwrap();
+            validate_handler(
+                State(state.clone()),
+                Path(("crm".into(), "users".into(), "create".into())),
+                Query::try_from_uri(&uri).unwrap(),
+                AuthContext::anonymous(false),
+                RequestMetadata::default(),
+                Json(serde_json::from_value(payload).unwrap()),
+            )
+        };
+        let body = |response: Response| async move {
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        };
+
+        let json =
+            body(validate(false, serde_json::json!({"email": "ada@example.com"})).await).await;
+        assert_eq!(json["valid"], false);
+        let codes: Vec<&str> = json["validation_errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["code"].as_str().unwrap())
+            .collect();
+        assert_eq!(codes, ["required"]);
+
+        // A form step only checks its own fields, uniqueness included
+        let json =
+            body(validate(true, serde_json::json!({"email": "ada@example.com"})).await).await;
+        assert_eq!(json["validation_errors"][0]["code"], "unique_in");
+
+        let json = body(
+            validate(
+                false,
+                serde_json::json!({"name": "Bob", "email": "bob@example.com"}),
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(json["valid"], true);
+        let rows = db.execute_query("SELECT * FROM users", None).await.unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_validate_batch() {
+        let (state, db, _dir) = sqlite_backoffice(
+            r#"
+id: shop
+name: Shop
+relationships:
+  - id: customer
+    name: Customer
+    relationship_type: manytoone
+    from_section: orders
+    from_field: customer_id
+    to_section: customers
+    to_field: id
+sections:
+  - id: customers
+    name: Customers
+    actions:
+      - id: list
+        name: List
+        type: list
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: name, name: Name, field_type: text }
+  - id: orders
+    name: Orders
+    actions:
+      - id: create
+        name: Create
+        type: form
+        data_source: db
+        generated: true
+        required_scopes: []
+        fields:
+          - { id: customer_id, name: Customer, field_type: number, required: true }
+          - { id: total, name: Total, field_type: number, validations: [{ rule_type: { type: min, value: 0 } }] }
+"#,
+            &[
+            "CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT)",
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, customer_id INTEGER, total REAL)",
+            "INSERT INTO customers (id, name) VALUES (1, 'Ada'), (2, 'Bob')",
+            ],
+        )
+        .await;
+
+        let response = validate_batch_handler(
+            State(state.clone()),
+            Path(("shop".into(), "orders".into(), "create".into())),
+            AuthContext::anonymous(false),
+            RequestMetadata::default(),
+            Json(
+                serde_json::from_value(serde_json::json!([
+                    {"customer_id": 1, "total": 10},
+                    {"customer_id": 99, "total": 10},
+                    {"total": -5},
+                    {"customer_id": 2, "total": 3},
+                ]))
+                .unwrap(),
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["valid"], false);
+        assert_eq!(json["invalid"], 2);
+        let results = json["results"].as_array().unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0]["valid"], true);
+        assert_eq!(results[1]["index"], 1);
+        assert_eq!(results[1]["validation_errors"][0]["code"], "foreign_key");
+        assert_eq!(results[1]["validation_errors"][0]["field"], "customer_id");
+        let codes: Vec<&str> = results[2]["validation_errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["code"].as_str().unwrap())
+            .collect();
+        assert_eq!(codes, ["required", "min"]);
+        assert_eq!(results[3]["valid"], true);
+        let rows = db
+            .execute_query("SELECT * FROM orders", None)
+            .await
+            .unwrap();
+        assert!(rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_action_required_scopes() {
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: shop
+name: Shop
+data_sources:
+  api:
+    type: api
+    base_url: "http://127.0.0.1:1"
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - { id: list, name: List, type: list, data_source: api, endpoint: /orders, required_scopes: [orders:read], fields: [] }
+      - { id: create, name: Create, type: form, data_source: api, endpoint: /orders, required_scopes: [orders:write], fields: [] }
+"#,
+        )
+        .unwrap();
+        let state = Arc::new(AppState {
+            backoffices: vec![backoffice],
+            ..(*create_test_state()).clone()
+        });
+        let caller = |scope: &str| AuthContext::from_claims(serde_json::json!({"scope": scope}));
+        let list = |auth: AuthContext| {
+            query_action(
+                State(state.clone()),
+                Path(("shop".into(), "orders".into(), "list".into())),
+                auth,
+                Query::try_from_uri(&"/".parse().unwrap()).unwrap(),
+            )
+        };
+        let create = |auth: AuthContext| {
+            execute_mutation_handler(
+                State(state.clone()),
+                Path(("shop".into(), "orders".into(), "create".into())),
+                auth,
+                RequestMetadata::default(),
+                Json(serde_json::from_value(serde_json::json!({"customer": "acme"})).unwrap()),
+            )
+        };
+
+        // Callers holding the scopes get past the check to the unreachable API
+        let response = list(caller("orders:write")).await.into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = list(caller("orders:read")).await.into_response();
+        assert_ne!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = create(caller("orders:read")).await.into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = create(caller("orders:write")).await.into_response();
+        assert_ne!(response.status(), StatusCode::FORBIDDEN);
+
+        // Only form-like actions take writes
+        let response = execute_mutation_handler(
+            State(state.clone()),
+            Path(("shop".into(), "orders".into(), "list".into())),
+            caller("orders:read"),
+            RequestMetadata::default(),
+            Json(serde_json::from_value(serde_json::json!({"customer": "acme"})).unwrap()),
+        )
+        .await
+        .into_response();
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(body["error"], "Action does not accept mutations");
+    }
+
+    #[tokio::test]
+    async fn test_config_rollback() {
+        use tower::ServiceExt;
+
+        let dir = TempDir::new();
+        let backoffices = dir.join("backoffices");
+        std::fs::create_dir_all(&backoffices).unwrap();
+        let file = backoffices.join("shop.yaml");
+        let mut config = create_test_state().config.clone();
+        config.config_history.enabled = true;
+        config.config_history.path = dir.join("history").to_string_lossy().into_owned();
+        let history = ConfigHistory::new(&config.config_history);
+
+        let original = "id: shop\nname: Shop\ndata_sources: {}\nsections: []\n";
+        std::fs::write(&file, original).unwrap();
+        history.load(&backoffices).await.unwrap();
+        std::fs::write(&file, original.replace("Shop", "Store")).unwrap();
+        let loaded = history.load(&backoffices).await.unwrap();
+
+        // Without security anyone could roll back, so nobody can
+        let open = RouterBuilder::new(config.clone(), loaded.clone())
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .build();
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/admin/backoffices/shop/rollback?version=1")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = open.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        config.security = Some(crate::config::SecurityConfig {
+            enabled: true,
+            jwt_secret: Some("secret".to_string()),
+            unauthorized_field_writes: Default::default(),
+            admin_scope: "admin".to_string(),
+        });
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &serde_json::json!({"sub": "ops", "scope": "admin", "exp": 4102444800u64}),
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        let router = RouterBuilder::new(config, loaded)
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .build();
+        let send = |method: &str, uri: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+            }
+        };
+
+        let (status, body) = send("GET", "/api/admin/backoffices/shop/versions").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["versions"][0]["version"], 2);
+        assert_eq!(body["versions"][0]["author"], "disk");
+        assert_eq!(
+            body["versions"][0]["diff"],
+            "@@ -2 +2 @@\n-name: Shop\n+name: Store"
+        );
+        assert_eq!(body["versions"][1]["version"], 1);
+
+        let (status, body) = send("POST", "/api/admin/backoffices/shop/rollback?version=1").await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["restart_required"], true);
+        assert_eq!(body["version"]["version"], 3);
+        assert_eq!(body["version"]["author"], "ops");
+        assert_eq!(body["version"]["reason"], "rollback to version 1");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), original);
+
+        let (status, _) = send("POST", "/api/admin/backoffices/shop/rollback?version=1").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send("POST", "/api/admin/backoffices/shop/rollback?version=9").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send("GET", "/api/admin/backoffices/other/versions").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_feature_flags() {
+        use tower::ServiceExt;
+
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: shop
+name: Shop
+data_sources:
+  api:
+    type: api
+    base_url: "http://127.0.0.1:9"
+sections:
+  - id: orders
+    name: Orders
+    actions:
+      - { id: list, name: List, type: custom, fields: [], data_source: api, endpoint: /orders, required_scopes: [] }
+      - { id: export, name: Export, type: custom, fields: [], data_source: api, endpoint: /export, required_scopes: [], enabled_when: exports }
+  - id: reports
+    name: Reports
+    enabled_when: new_reports
+    actions:
+      - { id: list, name: List, type: custom, fields: [], data_source: api, endpoint: /reports, required_scopes: [] }
+"#,
+        )
+        .unwrap();
+        let mut config = create_test_state().config.clone();
+        config.feature_flags =
+            serde_yaml::from_str("flags: { new_reports: false, exports: false }").unwrap();
+        let router = RouterBuilder::new(config, vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new("logs/audit/test")))
+            .build();
+        let get = |uri: &str| {
+            let request = Request::builder()
+                .uri(uri)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+            }
+        };
+
+        let (status, body) = get("/api/backoffices/shop").await;
+        assert_eq!(status, StatusCode::OK);
+        let sections = body["sections"].as_array().unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0]["actions"].as_array().unwrap().len(), 1);
+        let (_, body) = get("/api/backoffices").await;
+        assert_eq!(body[0]["sections"].as_array().unwrap().len(), 1);
+
+        let (status, body) = get("/api/backoffices/shop/sections/reports/actions/list").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["detail"], "Section not found");
+        let (status, body) = get("/api/backoffices/shop/sections/orders/actions/export").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["detail"], "Action not found");
+        let (status, _) = get("/api/v1/backoffices/shop/sections/reports/actions/list").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
 }
@@ -0,0 +1,222 @@
+//! Signature rendering
+//!
+//! Signature fields are drawn in the browser and submitted as strokes: lists
+//! of points in the field's canvas coordinates. Fields with a `storage` get
+//! their strokes rasterized to a PNG of the field's size and colors, which
+//! is stored in object storage and replaced on the record by its URL, so
+//! signed documents can be exported.
+
+use crate::config::SignatureFieldConfig;
+use anyhow::{bail, Result};
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use serde_json::Value;
+use std::io::Write;
+
+/// A stroke: the points the pen went through
+pub type Stroke = Vec<(f64, f64)>;
+
+/// Most points a signature may have across its strokes
+pub const MAX_POINTS: usize = 10_000;
+
+/// Returned for signatures with more than [`MAX_POINTS`] points
+#[derive(Debug, thiserror::Error)]
+#[error("Signatures may have at most {MAX_POINTS} points")]
+pub struct TooManyPoints;
+
+/// Strokes out of a submitted signature
+///
+/// Signatures are lists of strokes, each a list of `[x, y]` or `{x, y}`
+/// points or an object with such `points`. Other values, such as the URL
+/// of an already rendered signature, aren't strokes.
+pub fn strokes(value: &Value) -> Option<Vec<Stroke>> {
+    let strokes = value.as_array()?;
+    if strokes.is_empty() {
+        return None;
+    }
+    strokes
+        .iter()
+        .map(|stroke| {
+            let points = match stroke {
+                Value::Object(stroke) => stroke.get("points")?.as_array()?,
+                stroke => stroke.as_array()?,
+            };
+            points.iter().map(point).collect()
+        })
+        .collect()
+}
+
+fn point(value: &Value) -> Option<(f64, f64)> {
+    match value {
+        Value::Array(xy) if xy.len() == 2 => Some((xy[0].as_f64()?, xy[1].as_f64()?)),
+        Value::Object(xy) => Some((xy.get("x")?.as_f64()?, xy.get("y")?.as_f64()?)),
+        _ => None,
+    }
+}
+
+/// Rasterize strokes to a PNG of the field's size, drawn with its pen
+/// color and line width over its background color
+///
+/// Rendering is CPU bound; async callers run it on a blocking thread.
+pub fn render_png(strokes: &[Stroke], config: &SignatureFieldConfig) -> Result<Vec<u8>> {
+    if strokes.iter().map(Vec::len).sum::<usize>() > MAX_POINTS {
+        return Err(TooManyPoints.into());
+    }
+    let (width, height) = (config.width as usize, config.height as usize);
+    if width == 0 || height == 0 {
+        bail!("Signature fields need a width and a height");
+    }
+    let pen = color(&config.pen_color)?;
+    let background = color(&config.background_color)?;
+
+    // Pen coverage of each pixel, antialiased over the stroke's edge
+    let radius = f64::from(config.line_width.max(1)) / 2.0;
+    let mut coverage = vec![0f64; width * height];
+    for stroke in strokes {
+        let segments = stroke.windows(2).map(|pair| (pair[0], pair[1])).chain(
+            stroke
+                .first()
+                .filter(|_| stroke.len() == 1)
+                .map(|p| (*p, *p)),
+        );
+        for (from, to) in segments {
+            let reach = radius + 1.0;
+            let left = (from.0.min(to.0) - reach).floor().max(0.0) as usize;
+            let top = (from.1.min(to.1) - reach).floor().max(0.0) as usize;
+            let right = ((from.0.max(to.0) + reach).ceil().max(0.0) as usize).min(width);
+            let bottom = ((from.1.max(to.1) + reach).ceil().max(0.0) as usize).min(height);
+            for y in top..bottom {
+                for x in left..right {
+                    let center = (x as f64 + 0.5, y as f64 + 0.5);
+                    let covered = (radius + 0.5 - distance(center, from, to)).clamp(0.0, 1.0);
+                    let pixel = &mut coverage[y * width + x];
+                    *pixel = pixel.max(covered);
+                }
+            }
+        }
+    }
+
+    // Rows of RGB pixels, each after a filter type byte (none)
+    let mut pixels = Vec::with_capacity((width * 3 + 1) * height);
+    for row in coverage.chunks(width) {
+        pixels.push(0);
+        for covered in row {
+            for channel in 0..3 {
+                let blended = f64::from(pen[channel]) * covered
+                    + f64::from(background[channel]) * (1.0 - covered);
+                pixels.push(blended.round() as u8);
+            }
+        }
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&pixels)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(config.width.to_be_bytes());
+    header.extend(config.height.to_be_bytes());
+    // 8-bit RGB, deflate, no filtering, no interlacing
+    header.extend([8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &encoder.finish()?);
+    chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+/// Distance from a point to a segment
+fn distance(point: (f64, f64), from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((point.0 - from.0) * dx + (point.1 - from.1) * dy) / length).clamp(0.0, 1.0)
+    };
+    ((point.0 - from.0 - t * dx).powi(2) + (point.1 - from.1 - t * dy).powi(2)).sqrt()
+}
+
+/// RGB of a `#RGB` or `#RRGGBB` color
+fn color(hex: &str) -> Result<[u8; 3]> {
+    let digits = hex.trim().trim_start_matches('#');
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Invalid color {}", hex);
+    }
+    let digits = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        6 => digits.to_string(),
+        _ => bail!("Invalid color {}", hex),
+    };
+    let mut rgb = [0u8; 3];
+    for (i, channel) in rgb.iter_mut().enumerate() {
+        *channel = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16)?;
+    }
+    Ok(rgb)
+}
+
+/// Append a PNG chunk: its length, type, data and the CRC of type and data
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend(crc.sum().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use serde_json::json;
+    use std::io::Read;
+
+    #[test]
+    fn test_render_png() {
+        let signature = json!([[[2, 5], [8, 5]], {"points": [{"x": 5, "y": 1}]}]);
+        let strokes = strokes(&signature).unwrap();
+        assert_eq!(strokes[1], vec![(5.0, 1.0)]);
+        assert!(super::strokes(&json!("https://bucket/signature.png")).is_none());
+        assert!(super::strokes(&json!([[[1, "a"]]])).is_none());
+
+        let config = SignatureFieldConfig {
+            width: 10,
+            height: 8,
+            pen_color: "#00f".to_string(),
+            background_color: "#FFFFFF".to_string(),
+            line_width: 4,
+            storage: None,
+        };
+        let png = render_png(&strokes, &config).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 10, 0, 0, 0, 8]);
+
+        let length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let mut pixels = Vec::new();
+        ZlibDecoder::new(&png[41..41 + length])
+            .read_to_end(&mut pixels)
+            .unwrap();
+        assert_eq!(pixels.len(), (10 * 3 + 1) * 8);
+        let pixel = |x: usize, y: usize| {
+            let at = y * 31 + 1 + x * 3;
+            [pixels[at], pixels[at + 1], pixels[at + 2]]
+        };
+        assert_eq!(pixel(4, 4), [0, 0, 255]);
+        assert_eq!(pixel(4, 7), [255, 255, 255]);
+        // The single point stroke is a dot
+        assert_eq!(pixel(4, 0), [0, 0, 255]);
+
+        let config = SignatureFieldConfig {
+            pen_color: "blue".to_string(),
+            ..config
+        };
+        assert!(render_png(&strokes, &config).is_err());
+
+        let long = vec![vec![(1.0, 1.0); MAX_POINTS + 1]];
+        let error = render_png(&long, &config).unwrap_err();
+        assert!(error.is::<TooManyPoints>());
+    }
+}
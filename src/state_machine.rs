@@ -0,0 +1,174 @@
+//! State machine fields
+//!
+//! `field_type: state` fields hold one of a list of states, and records
+//! only move between states along the configured transitions, some of
+//! which need scopes. Updates changing a state field are checked against
+//! the record's current state; creates only need a declared state.
+
+use crate::auth::AuthContext;
+use crate::config::{FieldConfig, FieldType, StateFieldConfig, StateTransition};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A state change the caller may not make
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransitionError {
+    pub field: String,
+    pub from: Option<String>,
+    pub to: String,
+    /// States the caller may move the record to instead
+    pub allowed: Vec<String>,
+}
+
+/// Config of a state field
+pub fn state_config(field: &FieldConfig) -> Option<&StateFieldConfig> {
+    match &field.field_type {
+        FieldType::State { config } => Some(config),
+        _ => None,
+    }
+}
+
+/// Whether the data sets any of the state fields
+pub fn sets_state(fields: &[FieldConfig], data: &HashMap<String, Value>) -> bool {
+    fields
+        .iter()
+        .any(|f| state_config(f).is_some() && data.contains_key(&f.id))
+}
+
+/// Transitions out of a state the caller has the scopes for
+pub fn permitted<'a>(
+    config: &'a StateFieldConfig,
+    from: &str,
+    auth: &AuthContext,
+) -> Vec<&'a StateTransition> {
+    config
+        .transitions
+        .iter()
+        .filter(|t| t.from == from && auth.has_all_scopes(&t.required_scopes))
+        .collect()
+}
+
+/// Check the state changes of a mutation
+///
+/// `current` is the record before an update, or `None` for a create;
+/// records without a state can take any declared one. Setting a field to
+/// its current state is always allowed.
+pub fn check(
+    fields: &[FieldConfig],
+    current: Option<&HashMap<String, Value>>,
+    data: &HashMap<String, Value>,
+    auth: &AuthContext,
+) -> Result<(), Vec<TransitionError>> {
+    let mut errors = Vec::new();
+
+    for field in fields {
+        let (Some(config), Some(to)) = (state_config(field), data.get(&field.id)) else {
+            continue;
+        };
+        let to = state_text(to);
+        let from = current.and_then(|c| current_state(c, &field.id));
+        if from.as_ref() == Some(&to) {
+            continue;
+        }
+
+        let declared = config.states.iter().any(|s| s.value == to);
+        let (legal, allowed) = match &from {
+            Some(from) => {
+                let transitions = permitted(config, from, auth);
+                (
+                    transitions.iter().any(|t| t.to == to),
+                    transitions.iter().map(|t| t.to.clone()).collect(),
+                )
+            }
+            None => (
+                true,
+                config.states.iter().map(|s| s.value.clone()).collect(),
+            ),
+        };
+        if !declared || !legal {
+            errors.push(TransitionError {
+                field: field.id.clone(),
+                from,
+                to,
+                allowed,
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// States are compared as text, so numeric state codes work too
+fn state_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Current state of a record's field, as text
+pub fn current_state(record: &HashMap<String, Value>, field: &str) -> Option<String> {
+    record.get(field).filter(|v| !v.is_null()).map(state_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_transitions() {
+        let fields: Vec<FieldConfig> = serde_yaml::from_str(
+            r#"
+- id: status
+  name: Status
+  field_type: state
+  config:
+    states:
+      - { value: draft, label: Draft }
+      - { value: review, label: In review }
+      - { value: published, label: Published }
+    transitions:
+      - { from: draft, to: review }
+      - { from: review, to: draft }
+      - { from: review, to: published, required_scopes: [publish] }
+"#,
+        )
+        .unwrap();
+        let auth = AuthContext::anonymous(true);
+        let record = |status: &str| HashMap::from([("status".to_string(), json!(status))]);
+
+        assert!(check(&fields, Some(&record("draft")), &record("review"), &auth).is_ok());
+        assert!(check(&fields, Some(&record("draft")), &record("draft"), &auth).is_ok());
+        assert!(check(&fields, None, &record("review"), &auth).is_ok());
+
+        let errors =
+            check(&fields, Some(&record("draft")), &record("published"), &auth).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![TransitionError {
+                field: "status".to_string(),
+                from: Some("draft".to_string()),
+                to: "published".to_string(),
+                allowed: vec!["review".to_string()],
+            }]
+        );
+
+        // Transitions needing scopes the caller lacks are not permitted
+        let errors = check(
+            &fields,
+            Some(&record("review")),
+            &record("published"),
+            &auth,
+        )
+        .unwrap_err();
+        assert_eq!(errors[0].allowed, vec!["draft".to_string()]);
+
+        assert!(check(&fields, None, &record("archived"), &auth).is_err());
+    }
+}
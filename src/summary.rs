@@ -0,0 +1,526 @@
+//! Summary actions
+//!
+//! `type: summary` actions group the rows of their query by `group_by`
+//! fields and compute `count`, `sum`, `avg`, `min` and `max` aggregates per
+//! group. The grouping runs in the data source where it can: a `GROUP BY`
+//! over the SQL query, a `composite` aggregation for Elasticsearch and a
+//! `$group` stage for MongoDB. Rows of other sources are aggregated in
+//! memory, as are Elasticsearch and MongoDB rows filtered by row-level
+//! security, since those filters are only applied in memory.
+//!
+//! Besides the summary rows, responses carry them shaped for charts: one
+//! label per group and one series of values per aggregate.
+
+use crate::config::{
+    AggregateConfig, AggregateFunction, DataSourceConfig, DatabaseType, SummaryActionConfig,
+};
+use crate::crud::quote_identifier;
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Row key under which Elasticsearch returns a response's aggregations
+const AGGREGATIONS_KEY: &str = "_aggregations";
+
+/// Name of the Elasticsearch aggregation computing the summary
+const SUMMARY_AGGREGATION: &str = "summary";
+
+/// Groups asked from Elasticsearch when the action sets no limit
+const DEFAULT_ELASTICSEARCH_GROUPS: usize = 1000;
+
+/// Label of the single group of summaries without `group_by`
+const TOTAL_LABEL: &str = "All";
+
+type Row = HashMap<String, Value>;
+
+/// Whether the data source computes the summary itself; `row_filtered` is
+/// whether row-level security applies to the caller
+pub fn pushes_down(config: &DataSourceConfig, row_filtered: bool) -> bool {
+    match config {
+        DataSourceConfig::Database { .. } => true,
+        DataSourceConfig::Elasticsearch { .. } | DataSourceConfig::MongoDB { .. } => !row_filtered,
+        _ => false,
+    }
+}
+
+/// Turn the action's query into one computing the summary
+pub fn summary_query(
+    query: &str,
+    config: &SummaryActionConfig,
+    data_source: &DataSourceConfig,
+) -> Result<String> {
+    match data_source {
+        DataSourceConfig::Database { db_type, .. } => sql_summary(query, config, db_type),
+        DataSourceConfig::Elasticsearch { .. } => elasticsearch_summary(query, config),
+        DataSourceConfig::MongoDB { .. } => mongo_summary(query, config),
+        _ => Ok(query.to_string()),
+    }
+}
+
+/// The aggregated field, which only `count` may leave out
+fn aggregate_field(aggregate: &AggregateConfig) -> Result<Option<&str>> {
+    match (&aggregate.field, aggregate.function) {
+        (Some(field), _) => Ok(Some(field)),
+        (None, AggregateFunction::Count) => Ok(None),
+        (None, function) => bail!("The {} aggregate needs a field", function.as_str()),
+    }
+}
+
+/// Wrap a SQL query in a `GROUP BY` over the group fields
+fn sql_summary(
+    query: &str,
+    config: &SummaryActionConfig,
+    db_type: &DatabaseType,
+) -> Result<String> {
+    let groups: Vec<String> = config
+        .group_by
+        .iter()
+        .map(|field| quote_identifier(field, db_type))
+        .collect();
+    let mut columns = groups.clone();
+    for aggregate in &config.aggregates {
+        let argument = match aggregate_field(aggregate)? {
+            Some(field) => quote_identifier(field, db_type),
+            None => "*".to_string(),
+        };
+        columns.push(format!(
+            "{}({}) AS {}",
+            aggregate.function.as_str().to_uppercase(),
+            argument,
+            quote_identifier(&aggregate.name(), db_type)
+        ));
+    }
+
+    let query = query.trim().trim_end_matches(';');
+    let mut sql = format!(
+        "SELECT {} FROM ({}) AS summarized",
+        columns.join(", "),
+        query
+    );
+    if !groups.is_empty() {
+        let groups = groups.join(", ");
+        sql.push_str(&format!(" GROUP BY {} ORDER BY {}", groups, groups));
+    }
+    if let Some(limit) = config.limit {
+        sql.push_str(&format!(" LIMIT {}", limit));
+    }
+    Ok(sql)
+}
+
+/// Search body with no hits and a `composite` aggregation per group (or a
+/// `filter` one over every row), holding the metric aggregations
+fn elasticsearch_summary(query: &str, config: &SummaryActionConfig) -> Result<String> {
+    let body: Value = if query.trim().is_empty() || query.trim() == "{}" {
+        json!({})
+    } else {
+        serde_json::from_str(query)
+            .map_err(|e| anyhow!("Invalid Elasticsearch query JSON: {}", e))?
+    };
+    let filter = body
+        .get("query")
+        .cloned()
+        .unwrap_or_else(|| json!({ "match_all": {} }));
+
+    let mut metrics = Map::new();
+    for aggregate in &config.aggregates {
+        // Counts of rows are the buckets' doc_count
+        let Some(field) = aggregate_field(aggregate)? else {
+            continue;
+        };
+        let metric = match aggregate.function {
+            AggregateFunction::Count => "value_count",
+            function => function.as_str(),
+        };
+        metrics.insert(aggregate.name(), json!({ metric: { "field": field } }));
+    }
+
+    let mut summary = if config.group_by.is_empty() {
+        json!({ "filter": { "match_all": {} } })
+    } else {
+        let sources: Vec<Value> = config
+            .group_by
+            .iter()
+            .map(|field| json!({ field: { "terms": { "field": field, "missing_bucket": true } } }))
+            .collect();
+        json!({
+            "composite": {
+                "size": config.limit.unwrap_or(DEFAULT_ELASTICSEARCH_GROUPS),
+                "sources": sources,
+            }
+        })
+    };
+    if !metrics.is_empty() {
+        summary["aggs"] = Value::Object(metrics);
+    }
+
+    Ok(json!({
+        "size": 0,
+        "query": filter,
+        "aggs": { SUMMARY_AGGREGATION: summary },
+    })
+    .to_string())
+}
+
+/// Aggregation pipeline ending in `$group`, `$project` and `$sort` stages,
+/// after the action's filter or pipeline
+fn mongo_summary(query: &str, config: &SummaryActionConfig) -> Result<String> {
+    let query = query.trim();
+    let mut pipeline = if query.is_empty() || query == "{}" {
+        Vec::new()
+    } else {
+        match serde_json::from_str(query)
+            .map_err(|e| anyhow!("Invalid MongoDB query JSON: {}", e))?
+        {
+            Value::Array(pipeline) => pipeline,
+            filter => vec![json!({ "$match": filter })],
+        }
+    };
+
+    let mut group = Map::new();
+    let mut project = Map::new();
+    project.insert("_id".to_string(), json!(0));
+    group.insert(
+        "_id".to_string(),
+        if config.group_by.is_empty() {
+            Value::Null
+        } else {
+            Value::Object(
+                config
+                    .group_by
+                    .iter()
+                    .map(|field| (field.clone(), json!(format!("${}", field))))
+                    .collect(),
+            )
+        },
+    );
+    for field in &config.group_by {
+        project.insert(field.clone(), json!(format!("$_id.{}", field)));
+    }
+    for aggregate in &config.aggregates {
+        let accumulator = match (aggregate.function, aggregate_field(aggregate)?) {
+            (AggregateFunction::Count, None) => json!({ "$sum": 1 }),
+            // Missing and null values sort below every other value
+            (AggregateFunction::Count, Some(field)) => {
+                json!({ "$sum": { "$cond": [{ "$gt": [format!("${}", field), null] }, 1, 0] } })
+            }
+            (function, Some(field)) => {
+                json!({ format!("${}", function.as_str()): format!("${}", field) })
+            }
+            (_, None) => unreachable!("only count aggregates lack a field"),
+        };
+        group.insert(aggregate.name(), accumulator);
+        project.insert(aggregate.name(), json!(1));
+    }
+
+    pipeline.push(json!({ "$group": group }));
+    pipeline.push(json!({ "$project": project }));
+    if !config.group_by.is_empty() {
+        let sort: Map<String, Value> = config
+            .group_by
+            .iter()
+            .map(|field| (field.clone(), json!(1)))
+            .collect();
+        pipeline.push(json!({ "$sort": sort }));
+    }
+    if let Some(limit) = config.limit {
+        pipeline.push(json!({ "$limit": limit }));
+    }
+    Ok(Value::Array(pipeline).to_string())
+}
+
+/// Summary rows out of the rows returned for a pushed-down summary query
+pub fn summary_rows(
+    rows: Vec<HashMap<String, Value>>,
+    config: &SummaryActionConfig,
+    data_source: &DataSourceConfig,
+) -> Vec<HashMap<String, Value>> {
+    if !matches!(data_source, DataSourceConfig::Elasticsearch { .. }) {
+        return rows;
+    }
+
+    let Some(summary) = rows
+        .iter()
+        .find_map(|row| row.get(AGGREGATIONS_KEY)?.get(SUMMARY_AGGREGATION))
+    else {
+        return Vec::new();
+    };
+    let bucket_row = |bucket: &Value| {
+        let mut row = HashMap::new();
+        for field in &config.group_by {
+            let value = bucket
+                .get("key")
+                .and_then(|key| key.get(field))
+                .cloned()
+                .unwrap_or(Value::Null);
+            row.insert(field.clone(), value);
+        }
+        for aggregate in &config.aggregates {
+            let value = match aggregate.field {
+                None => bucket.get("doc_count").cloned(),
+                Some(_) => bucket
+                    .get(aggregate.name())
+                    .and_then(|m| m.get("value"))
+                    .cloned(),
+            };
+            row.insert(aggregate.name(), value.unwrap_or(Value::Null));
+        }
+        row
+    };
+
+    match summary.get("buckets").and_then(Value::as_array) {
+        Some(buckets) => buckets.iter().map(bucket_row).collect(),
+        None => vec![bucket_row(summary)],
+    }
+}
+
+/// Compute the summary of rows in memory
+pub fn aggregate_rows(
+    rows: &[HashMap<String, Value>],
+    config: &SummaryActionConfig,
+) -> Result<Vec<HashMap<String, Value>>> {
+    for aggregate in &config.aggregates {
+        aggregate_field(aggregate)?;
+    }
+
+    let mut groups: Vec<(Vec<Value>, Vec<&Row>)> = Vec::new();
+    for row in rows {
+        let key: Vec<Value> = config
+            .group_by
+            .iter()
+            .map(|field| row.get(field).cloned().unwrap_or(Value::Null))
+            .collect();
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, members)) => members.push(row),
+            None => groups.push((key, vec![row])),
+        }
+    }
+    // Summaries of all rows have one group, even without rows
+    if config.group_by.is_empty() && groups.is_empty() {
+        groups.push((Vec::new(), Vec::new()));
+    }
+    groups.sort_by(|(a, _), (b, _)| {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| compare_values(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    if let Some(limit) = config.limit {
+        groups.truncate(limit);
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(key, members)| {
+            let mut row: HashMap<String, Value> =
+                config.group_by.iter().cloned().zip(key).collect();
+            for aggregate in &config.aggregates {
+                row.insert(aggregate.name(), aggregate_value(aggregate, &members));
+            }
+            row
+        })
+        .collect())
+}
+
+fn aggregate_value(aggregate: &AggregateConfig, rows: &[&Row]) -> Value {
+    let Some(field) = &aggregate.field else {
+        return json!(rows.len());
+    };
+    let values: Vec<&Value> = rows
+        .iter()
+        .filter_map(|row| row.get(field))
+        .filter(|value| !value.is_null())
+        .collect();
+
+    match aggregate.function {
+        AggregateFunction::Count => json!(values.len()),
+        AggregateFunction::Sum | AggregateFunction::Avg => {
+            let numbers: Vec<f64> = values.iter().filter_map(|v| number(v)).collect();
+            if numbers.is_empty() {
+                return Value::Null;
+            }
+            if aggregate.function == AggregateFunction::Avg {
+                return json!(numbers.iter().sum::<f64>() / numbers.len() as f64);
+            }
+            // Integer sums stay integers
+            let integers: Option<Vec<i64>> = values.iter().map(|v| v.as_i64()).collect();
+            match integers {
+                Some(integers) => json!(integers.iter().sum::<i64>()),
+                None => json!(numbers.iter().sum::<f64>()),
+            }
+        }
+        AggregateFunction::Min => values
+            .into_iter()
+            .min_by(|a, b| compare_values(a, b))
+            .cloned()
+            .unwrap_or(Value::Null),
+        AggregateFunction::Max => values
+            .into_iter()
+            .max_by(|a, b| compare_values(a, b))
+            .cloned()
+            .unwrap_or(Value::Null),
+    }
+}
+
+/// Numbers, including decimals returned as text (e.g. Postgres `NUMERIC`)
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Nulls first, then numbers by value, then anything else as text
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (a, b) => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+/// Summary rows as chart data: a label per group and a series of numbers
+/// per aggregate
+pub fn chart(rows: &[HashMap<String, Value>], config: &SummaryActionConfig) -> Value {
+    let labels: Vec<Value> = rows
+        .iter()
+        .map(|row| match config.group_by.as_slice() {
+            [] => json!(TOTAL_LABEL),
+            [field] => row.get(field).cloned().unwrap_or(Value::Null),
+            fields => json!(fields
+                .iter()
+                .map(|field| match row.get(field) {
+                    Some(Value::String(s)) => s.clone(),
+                    Some(Value::Null) | None => String::new(),
+                    Some(value) => value.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" / ")),
+        })
+        .collect();
+    let series: Vec<Value> = config
+        .aggregates
+        .iter()
+        .map(|aggregate| {
+            let name = aggregate.name();
+            let data: Vec<Value> = rows
+                .iter()
+                .map(|row| {
+                    row.get(&name)
+                        .and_then(number)
+                        .map_or(Value::Null, |n| json!(n))
+                })
+                .collect();
+            json!({ "name": name, "data": data })
+        })
+        .collect();
+    json!({ "labels": labels, "series": series })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SummaryActionConfig {
+        serde_yaml::from_str(
+            r#"
+group_by: [status]
+aggregates:
+  - { function: count, alias: orders }
+  - { function: sum, field: total }
+  - { function: max, field: total, alias: largest }
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_summary_queries() {
+        assert_eq!(
+            sql_summary("SELECT * FROM orders;", &config(), &DatabaseType::Postgres).unwrap(),
+            "SELECT \"status\", COUNT(*) AS \"orders\", SUM(\"total\") AS \"sum_total\", \
+             MAX(\"total\") AS \"largest\" FROM (SELECT * FROM orders) AS summarized \
+             GROUP BY \"status\" ORDER BY \"status\""
+        );
+
+        let body: Value =
+            serde_json::from_str(&elasticsearch_summary("{}", &config()).unwrap()).unwrap();
+        let summary = &body["aggs"]["summary"];
+        assert_eq!(body["size"], 0);
+        assert_eq!(
+            summary["composite"]["sources"][0]["status"]["terms"]["field"],
+            "status"
+        );
+        assert_eq!(
+            summary["aggs"]["sum_total"],
+            json!({"sum": {"field": "total"}})
+        );
+        assert!(summary["aggs"].get("orders").is_none());
+
+        let pipeline: Value =
+            serde_json::from_str(&mongo_summary(r#"{"paid": true}"#, &config()).unwrap()).unwrap();
+        assert_eq!(pipeline[0], json!({"$match": {"paid": true}}));
+        assert_eq!(pipeline[1]["$group"]["_id"], json!({"status": "$status"}));
+        assert_eq!(pipeline[1]["$group"]["largest"], json!({"$max": "$total"}));
+        assert_eq!(pipeline[2]["$project"]["status"], "$_id.status");
+
+        let mut invalid = config();
+        invalid.aggregates[1].field = None;
+        assert!(sql_summary("SELECT 1", &invalid, &DatabaseType::Sqlite).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_rows() {
+        let rows: Vec<HashMap<String, Value>> = [
+            json!({"status": "paid", "total": 10}),
+            json!({"status": "open", "total": 2.5}),
+            json!({"status": "paid", "total": 5}),
+            json!({"status": "open", "total": null}),
+        ]
+        .into_iter()
+        .map(|row| serde_json::from_value(row).unwrap())
+        .collect();
+
+        let summary = aggregate_rows(&rows, &config()).unwrap();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0]["status"], "open");
+        assert_eq!(summary[0]["orders"], 2);
+        assert_eq!(summary[0]["sum_total"], 2.5);
+        assert_eq!(summary[1]["sum_total"], 15);
+        assert_eq!(summary[1]["largest"], 10);
+
+        let chart = chart(&summary, &config());
+        assert_eq!(chart["labels"], json!(["open", "paid"]));
+        assert_eq!(
+            chart["series"][1],
+            json!({"name": "sum_total", "data": [2.5, 15.0]})
+        );
+
+        // Elasticsearch buckets
+        let aggregations = HashMap::from([(
+            AGGREGATIONS_KEY.to_string(),
+            json!({"summary": {"buckets": [
+                {"key": {"status": "paid"}, "doc_count": 2,
+                 "sum_total": {"value": 15.0}, "largest": {"value": 10.0}},
+            ]}}),
+        )]);
+        let es = DataSourceConfig::Elasticsearch {
+            nodes: vec![],
+            index: "orders".to_string(),
+            auth: None,
+            tls: None,
+            init: Default::default(),
+        };
+        let summary = summary_rows(vec![aggregations], &config(), &es);
+        assert_eq!(summary[0]["orders"], 2);
+        assert_eq!(summary[0]["sum_total"], 15.0);
+    }
+}
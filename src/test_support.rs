@@ -0,0 +1,111 @@
+//! Fixtures shared by the handler tests of several modules
+
+use crate::audit::AuditLogger;
+use crate::config::{AppConfig, BackofficeConfig, SecurityConfig, ServerConfig};
+use crate::data_source::DataSource;
+use crate::server::{AppState, RouterBuilder};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Configuration with security disabled and `admin` as the admin scope
+pub(crate) fn test_config() -> AppConfig {
+    AppConfig {
+        server: ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+            compression: Default::default(),
+            limits: Default::default(),
+            circuit_breaker: Default::default(),
+            health_checks: Default::default(),
+            trusted_proxies: 0,
+            access_log: Default::default(),
+        },
+        security: Some(SecurityConfig {
+            enabled: false,
+            jwt_secret: None,
+            unauthorized_field_writes: Default::default(),
+            admin_scope: "admin".to_string(),
+        }),
+        audit_storage: None,
+        audit: Default::default(),
+        i18n: Default::default(),
+        antivirus: None,
+        push: Default::default(),
+        grpc: None,
+        config_history: Default::default(),
+        config_store: None,
+        feature_flags: Default::default(),
+    }
+}
+
+/// Temporary directory removed when dropped
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("pmp-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Backoffice defined by `yaml` whose `db` data source is a fresh sqlite
+/// database seeded with `statements`
+///
+/// The audit log is written next to the database, and both are removed when
+/// the returned directory is dropped.
+pub(crate) async fn sqlite_backoffice(
+    yaml: &str,
+    statements: &[&str],
+) -> (Arc<AppState>, Box<dyn DataSource>, TempDir) {
+    sqlite_backoffice_with(test_config(), yaml, statements, |builder| builder).await
+}
+
+/// [`sqlite_backoffice`] with a custom configuration and router builder
+pub(crate) async fn sqlite_backoffice_with(
+    config: AppConfig,
+    yaml: &str,
+    statements: &[&str],
+    customize: impl FnOnce(RouterBuilder) -> RouterBuilder,
+) -> (Arc<AppState>, Box<dyn DataSource>, TempDir) {
+    let dir = TempDir::new();
+    let mut backoffice: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    backoffice["data_sources"]["db"] = serde_yaml::from_str(&format!(
+        r#"{{ type: database, db_type: sqlite, connection_string: "sqlite://{}?mode=rwc" }}"#,
+        dir.join("db.sqlite").display()
+    ))
+    .unwrap();
+    let backoffice: BackofficeConfig = serde_yaml::from_value(backoffice).unwrap();
+    let state = customize(
+        RouterBuilder::new(config, vec![backoffice])
+            .audit_logger(Arc::new(AuditLogger::new(dir.join("audit")))),
+    )
+    .state();
+    let db = state
+        .data_sources
+        .create(&state.backoffices[0].data_sources["db"])
+        .await
+        .unwrap();
+    for statement in statements {
+        db.execute_mutation(statement, &HashMap::new())
+            .await
+            .unwrap();
+    }
+    (state, db, dir)
+}
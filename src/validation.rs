@@ -1,25 +1,241 @@
-use crate::config::{ConditionOperator, FieldConfig, ValidationCondition, ValidationType};
+use crate::card;
+use crate::config::{
+    BackofficeConfig, ConditionOperator, DataSourceConfig, FieldConfig, FieldType, PiiKind,
+    PiiMode, RemoteExpectation, SectionConfig, Severity, ValidationCondition,
+    ValidationFunctionConfig, ValidationRule, ValidationType,
+};
+use crate::crud;
+use crate::data_source::{sql_literal, url_encode, DataSource};
+use crate::email;
+use crate::expression;
+use crate::form_state;
+use crate::i18n::{self, number, params};
+use crate::phone;
+use crate::pii;
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, NaiveDate, Utc};
 use regex::Regex;
+use serde::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 
+/// Maximum number of Rhai operations a validation script may run
+const MAX_SCRIPT_OPERATIONS: u64 = 100_000;
+
+/// Custom validation functions registered by a backoffice
+pub struct ValidationFunctions {
+    engine: rhai::Engine,
+    functions: HashMap<String, CompiledFunction>,
+}
+
+enum CompiledFunction {
+    Rhai(rhai::AST),
+    Wasm {
+        path: String,
+        export: String,
+        fuel: u64,
+    },
+}
+
+impl Default for ValidationFunctions {
+    fn default() -> Self {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+
+        Self {
+            engine,
+            functions: HashMap::new(),
+        }
+    }
+}
+
+impl ValidationFunctions {
+    /// Compile the validation functions declared in a backoffice
+    pub fn from_backoffice(backoffice: &BackofficeConfig) -> Result<Self> {
+        let mut registry = Self::default();
+
+        for (name, function) in &backoffice.validation_functions {
+            let compiled = match function {
+                ValidationFunctionConfig::Rhai { script } => {
+                    let ast = registry.engine.compile(script).map_err(|e| {
+                        anyhow!("Failed to compile validation function {}: {}", name, e)
+                    })?;
+                    CompiledFunction::Rhai(ast)
+                }
+                ValidationFunctionConfig::Wasm { plugin, export } => {
+                    let plugin_config = backoffice
+                        .plugins
+                        .iter()
+                        .find(|p| &p.id == plugin)
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "Plugin {} not found for validation function {}",
+                                plugin,
+                                name
+                            )
+                        })?;
+                    CompiledFunction::Wasm {
+                        path: plugin_config.path.clone(),
+                        export: export.clone(),
+                        fuel: plugin_config.fuel.unwrap_or(crate::plugins::DEFAULT_FUEL),
+                    }
+                }
+            };
+            registry.functions.insert(name.clone(), compiled);
+        }
+
+        Ok(registry)
+    }
+
+    /// Register a Rhai validation function
+    #[allow(dead_code)]
+    pub fn register_script(&mut self, name: &str, script: &str) -> Result<()> {
+        let ast = self
+            .engine
+            .compile(script)
+            .map_err(|e| anyhow!("Failed to compile validation function {}: {}", name, e))?;
+        self.functions
+            .insert(name.to_string(), CompiledFunction::Rhai(ast));
+        Ok(())
+    }
+
+    /// Run a named function; `Err` carries the validation message
+    ///
+    /// Functions signal success with `()`/`true`/`null`, failure with `false`
+    /// (generic message) or a string (custom message). WASM exports run on
+    /// the blocking thread pool.
+    async fn call(
+        &self,
+        name: &str,
+        value: &Value,
+        field: &FieldConfig,
+        all_data: &HashMap<String, Value>,
+    ) -> Result<()> {
+        let function = self
+            .functions
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown validation function: {}", name))?;
+
+        let outcome = match function {
+            CompiledFunction::Rhai(ast) => {
+                let mut scope = rhai::Scope::new();
+                scope.push_dynamic("value", rhai::serde::to_dynamic(value)?);
+                scope.push_dynamic("data", rhai::serde::to_dynamic(all_data)?);
+                scope.push("field", field.id.clone());
+
+                let result = self
+                    .engine
+                    .eval_ast_with_scope::<rhai::Dynamic>(&mut scope, ast)
+                    .map_err(|e| anyhow!("Validation function {} failed: {}", name, e))?;
+                rhai::serde::from_dynamic::<Value>(&result)?
+            }
+            CompiledFunction::Wasm { path, export, fuel } => {
+                let input = serde_json::json!({
+                    "value": value,
+                    "data": all_data,
+                    "field": field.id,
+                });
+                crate::plugins::call_export(path, export, input, *fuel).await?
+            }
+        };
+
+        match outcome {
+            Value::Null | Value::Bool(true) => Ok(()),
+            Value::Bool(false) => Err(invalid("custom_function", field, [])),
+            Value::String(message) => Err(anyhow!(message)),
+            Value::Object(obj) => match obj.get("error") {
+                Some(Value::String(message)) => Err(anyhow!(message.clone())),
+                Some(other) if !other.is_null() => Err(anyhow!(other.to_string())),
+                _ => Ok(()),
+            },
+            other => Err(anyhow!(
+                "Validation function {} returned an unexpected value: {}",
+                name,
+                other
+            )),
+        }
+    }
+}
+
 /// Validate data against field configurations
+#[allow(dead_code)]
 pub fn validate_data(
     data: &HashMap<String, Value>,
     fields: &[FieldConfig],
+) -> Result<Vec<ValidationError>> {
+    Ok(checks(data, fields)
+        .into_iter()
+        .filter_map(|check| match check {
+            Check::Failed(error) => Some(error),
+            Check::Rule { field, rule, value } => {
+                validate_rule(value, &rule.rule_type, field, data)
+                    .err()
+                    .map(|e| rule_error(field, rule, e))
+            }
+        })
+        .collect())
+}
+
+/// Validate data, resolving `custom_function` rules against `functions`
+pub async fn validate_data_with_functions(
+    data: &HashMap<String, Value>,
+    fields: &[FieldConfig],
+    functions: &ValidationFunctions,
 ) -> Result<Vec<ValidationError>> {
     let mut errors = Vec::new();
+    for check in checks(data, fields) {
+        let (field, rule, value) = match check {
+            Check::Failed(error) => {
+                errors.push(error);
+                continue;
+            }
+            Check::Rule { field, rule, value } => (field, rule, value),
+        };
+        let outcome = match &rule.rule_type {
+            ValidationType::CustomFunction { function_name } => {
+                functions.call(function_name, value, field, data).await
+            }
+            rule_type => validate_rule(value, rule_type, field, data),
+        };
+        if let Err(e) = outcome {
+            errors.push(rule_error(field, rule, e));
+        }
+    }
+    Ok(errors)
+}
+
+/// A failure found without running any rule, or a rule to run on a value
+enum Check<'a> {
+    Failed(ValidationError),
+    Rule {
+        field: &'a FieldConfig,
+        rule: &'a ValidationRule,
+        value: &'a Value,
+    },
+}
+
+/// The checks of the synchronous phase, in field order
+fn checks<'a>(data: &'a HashMap<String, Value>, fields: &'a [FieldConfig]) -> Vec<Check<'a>> {
+    let mut checks = Vec::new();
 
     for field in fields {
+        // Hidden fields aren't filled in, so aren't validated
+        if !form_state::visible(field, data) {
+            continue;
+        }
+        let required = form_state::required(field, data);
+
         // Check required fields
-        if field.required && (!data.contains_key(&field.id) || data[&field.id].is_null()) {
-            errors.push(ValidationError {
-                field: field.id.clone(),
-                message: format!("{} is required", field.name),
-            });
+        if required && (!data.contains_key(&field.id) || data[&field.id].is_null()) {
+            checks.push(Check::Failed(ValidationError::new(
+                &field.id,
+                "required",
+                params([("field", field.name.clone().into())]),
+            )));
             continue;
         }
 
@@ -31,10 +247,28 @@ pub fn validate_data(
         let value = &data[&field.id];
 
         // Skip null values for non-required fields
-        if value.is_null() && !field.required {
+        if value.is_null() && !required {
             continue;
         }
 
+        // Domains the field's own lists rule out
+        if let (FieldType::Email { config }, Some(s)) = (&field.field_type, value.as_str()) {
+            let refused = email::addresses(s, config)
+                .into_iter()
+                .filter_map(email::domain)
+                .find(|domain| !email::domain_allowed(domain, config));
+            if let Some(domain) = refused {
+                checks.push(Check::Failed(ValidationError::new(
+                    &field.id,
+                    "email_domain",
+                    params([
+                        ("field", field.name.clone().into()),
+                        ("domain", domain.into()),
+                    ]),
+                )));
+            }
+        }
+
         // Validate each validation rule
         for validation in &field.validations {
             // Check if validation condition is met
@@ -48,27 +282,489 @@ pub fn validate_data(
                 }
             }
 
-            if let Err(e) = validate_rule(value, &validation.rule_type, field, data) {
-                errors.push(ValidationError {
-                    field: field.id.clone(),
-                    message: validation.message.clone().unwrap_or_else(|| e.to_string()),
+            checks.push(Check::Rule {
+                field,
+                rule: validation,
+                value,
+            });
+        }
+    }
+
+    checks
+}
+
+/// Warnings about valid data, such as the personal data found by `no_pii`
+/// rules in `flag` mode
+pub fn warnings(data: &HashMap<String, Value>, fields: &[FieldConfig]) -> Vec<ValidationError> {
+    let mut warnings = Vec::new();
+    for field in fields {
+        let Some(Value::String(text)) = data.get(&field.id) else {
+            continue;
+        };
+        for validation in &field.validations {
+            let ValidationType::NoPii {
+                kinds,
+                mode: PiiMode::Flag,
+            } = &validation.rule_type
+            else {
+                continue;
+            };
+            if let Some(condition) = &validation.condition {
+                if !evaluate_condition(data, condition) {
+                    continue;
+                }
+            }
+            let found = pii::detect(text, kinds);
+            if !found.is_empty() {
+                let params = params([
+                    ("field", field.name.clone().into()),
+                    ("kinds", pii_names(&found)),
+                ]);
+                let warning = ValidationError::new(&field.id, "pii", params);
+                warnings.push(match &validation.message {
+                    Some(message) => warning.with_message(message),
+                    None => warning,
                 });
             }
         }
     }
+    warnings
+}
+
+fn pii_names(kinds: &[PiiKind]) -> Value {
+    kinds
+        .iter()
+        .map(|kind| pii::name(*kind))
+        .collect::<Vec<_>>()
+        .join(", ")
+        .into()
+}
+
+/// Run the validations that need to query a data source
+///
+/// This is the async phase of validation and runs after `validate_data`
+/// has passed. `section_data_source` names the data source of the section
+/// being mutated, and `config` is its configuration when it has one.
+pub async fn validate_data_async(
+    data: &HashMap<String, Value>,
+    fields: &[FieldConfig],
+    section: &SectionConfig,
+    section_data_source: &str,
+    config: Option<&DataSourceConfig>,
+    data_sources: &HashMap<String, Box<dyn DataSource>>,
+) -> Result<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    for field in fields {
+        let value = match data.get(&field.id) {
+            Some(value) if !value.is_null() => value,
+            _ => continue,
+        };
+
+        if let (FieldType::Email { config }, Some(s)) = (&field.field_type, value.as_str()) {
+            if config.verify_mx {
+                for domain in email::addresses(s, config)
+                    .into_iter()
+                    .filter_map(email::domain)
+                {
+                    if !email::accepts_mail(&domain, config).await {
+                        errors.push(ValidationError::new(
+                            &field.id,
+                            "email_mx",
+                            params([
+                                ("field", field.name.clone().into()),
+                                ("domain", domain.into()),
+                            ]),
+                        ));
+                        break;
+                    }
+                }
+            }
+        }
+
+        for validation in &field.validations {
+            if let Some(condition) = &validation.condition {
+                if !evaluate_condition(data, condition) {
+                    continue;
+                }
+            }
+
+            let result = match &validation.rule_type {
+                ValidationType::UniqueIn { field_list } => {
+                    let data_source = data_sources
+                        .get(section_data_source)
+                        .ok_or_else(|| anyhow!("Data source not found: {}", section_data_source))?;
+                    validate_unique(
+                        value,
+                        field,
+                        field_list,
+                        data,
+                        section,
+                        config,
+                        data_source.as_ref(),
+                    )
+                    .await
+                }
+                ValidationType::Remote {
+                    data_source,
+                    query,
+                    endpoint,
+                    expect,
+                    field: result_field,
+                    timeout_ms,
+                    cache_ttl_secs,
+                } => {
+                    let remote = data_sources
+                        .get(data_source)
+                        .ok_or_else(|| anyhow!("Data source not found: {}", data_source))?;
+                    let request = RemoteRequest {
+                        data_source,
+                        query: query.as_deref(),
+                        endpoint: endpoint.as_deref(),
+                        expect,
+                        result_field: result_field.as_deref(),
+                        timeout: Duration::from_millis(
+                            timeout_ms.unwrap_or(DEFAULT_REMOTE_TIMEOUT_MS),
+                        ),
+                        cache_ttl: Duration::from_secs(
+                            cache_ttl_secs.unwrap_or(DEFAULT_REMOTE_CACHE_TTL_SECS),
+                        ),
+                    };
+                    validate_remote(value, field, data, &request, remote.as_ref()).await
+                }
+                _ => continue,
+            };
+
+            if let Err(e) = result {
+                errors.push(rule_error(field, validation, e));
+            }
+        }
+    }
 
     Ok(errors)
 }
 
+/// Check that no other record shares this field's value (and the values of `field_list`)
+///
+/// The record being updated, identified by the `id` in the payload, is
+/// excluded. Values are bound as params against the section's table; config
+/// loading only allows `unique_in` on database data sources.
+async fn validate_unique(
+    value: &Value,
+    field: &FieldConfig,
+    field_list: &[String],
+    all_data: &HashMap<String, Value>,
+    section: &SectionConfig,
+    config: Option<&DataSourceConfig>,
+    data_source: &dyn DataSource,
+) -> Result<()> {
+    let Some(DataSourceConfig::Database { db_type, .. }) = config else {
+        return Err(anyhow!(
+            "unique_in needs a database data source for section '{}'",
+            section.id
+        ));
+    };
+    let column = |name: &str| crud::quote_identifier(name, db_type);
+
+    let mut params = HashMap::from([("unique_0".to_string(), value.clone())]);
+    let mut conditions = vec![format!("{} = {{{{unique_0}}}}", column(&field.id))];
+    for other in field_list.iter().filter(|f| **f != field.id) {
+        match all_data.get(other) {
+            Some(Value::Null) => conditions.push(format!("{} IS NULL", column(other))),
+            Some(other_value) => {
+                let name = format!("unique_{}", params.len());
+                conditions.push(format!("{} = {{{{{}}}}}", column(other), name));
+                params.insert(name, other_value.clone());
+            }
+            None => {}
+        }
+    }
+
+    if let Some(id) = all_data.get("id").filter(|id| !id.is_null()) {
+        conditions.push(format!("{} <> {{{{unique_id}}}}", column("id")));
+        params.insert("unique_id".to_string(), id.clone());
+    }
+
+    let query = format!(
+        "SELECT {} FROM {} WHERE {}",
+        column("id"),
+        column(section.table.as_deref().unwrap_or(&section.id)),
+        conditions.join(" AND ")
+    );
+    debug!(field = %field.id, query = %query, "Checking uniqueness");
+
+    let existing = data_source
+        .execute_query(&query, Some(&params))
+        .await
+        .map_err(|e| anyhow!("Failed to check uniqueness of {}: {}", field.name, e))?;
+
+    if !existing.is_empty() {
+        if field_list.is_empty() {
+            return Err(invalid("unique_in", field, []));
+        }
+        return Err(invalid(
+            "unique_together",
+            field,
+            [("fields", field_list.to_vec().into())],
+        ));
+    }
+
+    Ok(())
+}
+
+/// Default timeout for a remote validation call
+const DEFAULT_REMOTE_TIMEOUT_MS: u64 = 5_000;
+
+/// Default lifetime of a cached remote validation result
+const DEFAULT_REMOTE_CACHE_TTL_SECS: u64 = 300;
+
+/// A resolved `remote` validation rule
+struct RemoteRequest<'a> {
+    data_source: &'a str,
+    query: Option<&'a str>,
+    endpoint: Option<&'a str>,
+    expect: &'a RemoteExpectation,
+    result_field: Option<&'a str>,
+    timeout: Duration,
+    cache_ttl: Duration,
+}
+
+/// Whether a remote validation found the value valid, and when it was checked
+type RemoteOutcome = (Instant, bool);
+
+/// Cached remote validation outcomes, keyed by data source and rendered request
+fn remote_cache() -> &'static Mutex<HashMap<String, RemoteOutcome>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, RemoteOutcome>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// Validate a value by calling another data source
+async fn validate_remote(
+    value: &Value,
+    field: &FieldConfig,
+    all_data: &HashMap<String, Value>,
+    request: &RemoteRequest<'_>,
+    data_source: &dyn DataSource,
+) -> Result<()> {
+    let rendered = match (request.query, request.endpoint) {
+        (Some(query), _) => query.replace("{value}", &sql_literal(value)),
+        (None, Some(endpoint)) => endpoint.replace("{value}", &url_encode(&value_to_string(value))),
+        (None, None) => {
+            return Err(anyhow!(
+                "Remote validation for {} needs a query or endpoint",
+                field.name
+            ))
+        }
+    };
+
+    let cache_key = format!(
+        "{}\n{:?}\n{}",
+        request.data_source, request.expect, rendered
+    );
+    if let Some((checked_at, outcome)) = remote_cache().lock().unwrap().get(&cache_key) {
+        if checked_at.elapsed() < request.cache_ttl {
+            debug!(field = %field.id, "Using cached remote validation result");
+            return match outcome {
+                true => Ok(()),
+                false => Err(invalid("remote", field, [])),
+            };
+        }
+    }
+
+    debug!(field = %field.id, request = %rendered, "Running remote validation");
+
+    let mut params = all_data.clone();
+    params.insert("value".to_string(), value.clone());
+
+    let rows = tokio::time::timeout(
+        request.timeout,
+        data_source.execute_query(&rendered, Some(&params)),
+    )
+    .await
+    .map_err(|_| anyhow!("Remote validation of {} timed out", field.name))?
+    .map_err(|e| anyhow!("Remote validation of {} failed: {}", field.name, e))?;
+
+    let valid = match request.expect {
+        RemoteExpectation::Exists => !rows.is_empty(),
+        RemoteExpectation::Empty => rows.is_empty(),
+        RemoteExpectation::Truthy => {
+            let result_field = request.result_field.ok_or_else(|| {
+                anyhow!(
+                    "Remote validation for {} needs a field to check",
+                    field.name
+                )
+            })?;
+            rows.first()
+                .and_then(|row| row.get(result_field))
+                .map(is_truthy)
+                .unwrap_or(false)
+        }
+    };
+
+    // Only definite answers are cached; timeouts and errors are retried
+    remote_cache()
+        .lock()
+        .unwrap()
+        .insert(cache_key, (Instant::now(), valid));
+
+    match valid {
+        true => Ok(()),
+        false => Err(invalid("remote", field, [])),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().map(|n| n != 0.0).unwrap_or(false),
+        Value::String(s) => !s.is_empty() && s != "false" && s != "0",
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
 /// Validation error structure
-#[derive(Debug, Clone)]
+///
+/// `code` and `params` identify the message for frontends and locales; the
+/// message is rendered in English, unless the rule sets its own.
+#[derive(Debug, Clone, Serialize)]
 pub struct ValidationError {
     pub field: String,
     pub message: String,
+    pub code: String,
+    pub params: HashMap<String, Value>,
+    /// Whether the message was set by the rule, and so isn't localized
+    #[serde(skip)]
+    custom: bool,
+    /// Whether the rule only warns about the value
+    #[serde(skip)]
+    warning: bool,
+}
+
+impl ValidationError {
+    /// An error with the built-in message of a code
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<String>,
+        params: HashMap<String, Value>,
+    ) -> Self {
+        let code = code.into();
+        let message = i18n::render(i18n::built_in(&code).unwrap_or(&code), &params);
+        Self {
+            field: field.into(),
+            message,
+            code,
+            params,
+            custom: false,
+            warning: false,
+        }
+    }
+
+    /// Replace the message with a template of the error's params
+    pub fn with_message(mut self, template: &str) -> Self {
+        self.message = i18n::render(template, &self.params);
+        self.custom = true;
+        self
+    }
+
+    /// The same error reported for another field, e.g. a nested one
+    pub fn at(mut self, field: impl Into<String>) -> Self {
+        self.field = field.into();
+        self
+    }
+
+    /// Whether the message can be rendered from a locale's templates
+    pub fn localizable(&self) -> bool {
+        !self.custom
+    }
+
+    /// Whether the error comes from a rule with `severity: warning`
+    pub fn is_warning(&self) -> bool {
+        self.warning
+    }
+
+    /// The same error as a warning
+    pub fn warn(mut self) -> Self {
+        self.warning = true;
+        self
+    }
+}
+
+/// Split validation errors into blocking errors and warnings
+pub fn split_warnings(
+    errors: Vec<ValidationError>,
+) -> (Vec<ValidationError>, Vec<ValidationError>) {
+    errors.into_iter().partition(|e| !e.is_warning())
+}
+
+/// A value failing a rule, with the code and params of its message
+#[derive(Debug)]
+struct Invalid {
+    code: &'static str,
+    params: HashMap<String, Value>,
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let template = i18n::built_in(self.code).unwrap_or(self.code);
+        f.write_str(&i18n::render(template, &self.params))
+    }
+}
+
+impl std::error::Error for Invalid {}
+
+/// A rule failure of a field, with the field's name as the `field` param
+fn invalid<const N: usize>(
+    code: &'static str,
+    field: &FieldConfig,
+    pairs: [(&str, Value); N],
+) -> anyhow::Error {
+    let mut params = params(pairs);
+    params.insert("field".to_string(), field.name.clone().into());
+    Invalid { code, params }.into()
+}
+
+/// The error of a field failing a rule
+///
+/// Other failures than `Invalid` ones, such as the messages of custom
+/// functions, are kept as they are under the rule's code.
+fn rule_error(field: &FieldConfig, rule: &ValidationRule, e: anyhow::Error) -> ValidationError {
+    let error = match e.downcast::<Invalid>() {
+        Ok(invalid) => ValidationError::new(&field.id, invalid.code, invalid.params),
+        Err(e) => {
+            let code = serde_json::to_value(&rule.rule_type)
+                .ok()
+                .and_then(|rule| rule["type"].as_str().map(str::to_string))
+                .unwrap_or_else(|| "invalid".to_string());
+            ValidationError::new(
+                &field.id,
+                code,
+                params([("field", field.name.clone().into())]),
+            )
+            .with_message(&e.to_string())
+        }
+    };
+    let mut error = match &rule.message {
+        Some(message) => error.with_message(message),
+        None => error,
+    };
+    error.warning = rule.severity == Severity::Warning;
+    error
 }
 
 /// Evaluate a validation condition
-fn evaluate_condition(data: &HashMap<String, Value>, condition: &ValidationCondition) -> bool {
+pub(crate) fn evaluate_condition(
+    data: &HashMap<String, Value>,
+    condition: &ValidationCondition,
+) -> bool {
     let field_value = data.get(&condition.field);
 
     match &condition.operator {
@@ -143,18 +839,14 @@ fn validate_rule(
     match rule {
         ValidationType::Required { value: required } => {
             if *required && value.is_null() {
-                return Err(anyhow!("{} is required", field.name));
+                return Err(invalid("required", field, []));
             }
             Ok(())
         }
         ValidationType::MinLength { value: min } => {
             if let Some(s) = value.as_str() {
                 if s.len() < *min {
-                    return Err(anyhow!(
-                        "{} must be at least {} characters",
-                        field.name,
-                        min
-                    ));
+                    return Err(invalid("min_length", field, [("min", (*min).into())]));
                 }
             }
             Ok(())
@@ -162,7 +854,7 @@ fn validate_rule(
         ValidationType::MaxLength { value: max } => {
             if let Some(s) = value.as_str() {
                 if s.len() > *max {
-                    return Err(anyhow!("{} must be at most {} characters", field.name, max));
+                    return Err(invalid("max_length", field, [("max", (*max).into())]));
                 }
             }
             Ok(())
@@ -171,10 +863,7 @@ fn validate_rule(
             if let Some(s) = value.as_str() {
                 let re = Regex::new(regex).map_err(|e| anyhow!("Invalid regex pattern: {}", e))?;
                 if !re.is_match(s) {
-                    return Err(anyhow!(
-                        "{} does not match the required pattern",
-                        field.name
-                    ));
+                    return Err(invalid("pattern", field, []));
                 }
             }
             Ok(())
@@ -182,7 +871,7 @@ fn validate_rule(
         ValidationType::Min { value: min } => {
             if let Some(n) = value.as_f64() {
                 if n < *min {
-                    return Err(anyhow!("{} must be at least {}", field.name, min));
+                    return Err(invalid("min", field, [("min", number(*min))]));
                 }
             }
             Ok(())
@@ -190,7 +879,7 @@ fn validate_rule(
         ValidationType::Max { value: max } => {
             if let Some(n) = value.as_f64() {
                 if n > *max {
-                    return Err(anyhow!("{} must be at most {}", field.name, max));
+                    return Err(invalid("max", field, [("max", number(*max))]));
                 }
             }
             Ok(())
@@ -202,7 +891,7 @@ fn validate_rule(
                 )
                 .unwrap();
                 if !email_regex.is_match(s) {
-                    return Err(anyhow!("{} must be a valid email address", field.name));
+                    return Err(invalid("email", field, []));
                 }
             }
             Ok(())
@@ -212,63 +901,88 @@ fn validate_rule(
                 let url_regex =
                     Regex::new(r"^https?://[a-zA-Z0-9-._~:/?#\[\]@!$&'()*+,;=%]+$").unwrap();
                 if !url_regex.is_match(s) {
-                    return Err(anyhow!("{} must be a valid URL", field.name));
+                    return Err(invalid("url", field, []));
                 }
             }
             Ok(())
         }
         ValidationType::Phone => {
-            if let Some(s) = value.as_str() {
-                let phone_regex = Regex::new(r"^\+?[1-9]\d{1,14}$").unwrap();
-                if !phone_regex.is_match(s) {
-                    return Err(anyhow!("{} must be a valid phone number", field.name));
+            if let (Some(s), Some(config)) = (value.as_str(), phone::field_config(field)) {
+                if let Err(e) = phone::parse(s, &config) {
+                    return Err(invalid(e.code(), field, []));
                 }
             }
             Ok(())
         }
+        // Run by `validate_data_with_functions`
         ValidationType::CustomFunction { function_name } => {
-            warn!(
-                function = %function_name,
-                "Custom validation functions not yet supported"
-            );
-            Ok(())
+            Err(anyhow!("Unknown validation function: {}", function_name))
         }
+        ValidationType::Expression { expr } => match expression::evaluate(expr, value, all_data) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(invalid("expression", field, [])),
+            Err(e) => {
+                warn!(field = %field.id, error = %e, "Expression validation failed");
+                Err(invalid("expression", field, []))
+            }
+        },
         ValidationType::DependsOn {
             field: dep_field,
             expected_value,
         } => {
             if let Some(dep_value) = all_data.get(dep_field) {
                 if dep_value != expected_value {
-                    return Err(anyhow!(
-                        "{} depends on {} having a specific value",
-                        field.name,
-                        dep_field
+                    return Err(invalid(
+                        "depends_on",
+                        field,
+                        [("other", dep_field.clone().into())],
                     ));
                 }
             }
             Ok(())
         }
-        ValidationType::UniqueIn { field_list } => {
-            // This would require database access to check uniqueness
-            // For now, we'll log a warning
-            warn!(
-                fields = ?field_list,
-                "UniqueIn validation requires database access - not validated"
-            );
+        ValidationType::UniqueIn { .. } | ValidationType::Remote { .. } => {
+            // Need data source access; checked by `validate_data_async`
             Ok(())
         }
         ValidationType::MatchField { field: match_field } => {
             if let Some(match_value) = all_data.get(match_field) {
                 if value != match_value {
-                    return Err(anyhow!("{} must match {}", field.name, match_field));
+                    return Err(invalid(
+                        "match_field",
+                        field,
+                        [("other", match_field.clone().into())],
+                    ));
                 }
             }
             Ok(())
         }
-        ValidationType::CreditCard => {
+        ValidationType::CreditCard { allowed_brands, .. } => {
             if let Some(s) = value.as_str() {
+                // Numbers stored masked come back as they are on updates
+                if card::is_masked(s) {
+                    return Ok(());
+                }
                 if !validate_luhn(s) {
-                    return Err(anyhow!("{} must be a valid credit card number", field.name));
+                    return Err(invalid("credit_card", field, []));
+                }
+                let brand = card::brand(s);
+                if !allowed_brands.is_empty()
+                    && !brand.is_some_and(|brand| allowed_brands.contains(&brand))
+                {
+                    let allowed: Vec<String> =
+                        allowed_brands.iter().map(|b| card::name(*b)).collect();
+                    return Err(invalid(
+                        "card_brand",
+                        field,
+                        [
+                            (
+                                "brand",
+                                brand.map_or("unknown".to_string(), card::name).into(),
+                            ),
+                            ("allowed", allowed.join(", ").into()),
+                        ],
+                    ));
                 }
             }
             Ok(())
@@ -278,7 +992,7 @@ fn validate_rule(
                 let ipv4_regex =
                     Regex::new(r"^((25[0-5]|(2[0-4]|1\d|[1-9]|)\d)\.?\b){4}$").unwrap();
                 if !ipv4_regex.is_match(s) {
-                    return Err(anyhow!("{} must be a valid IPv4 address", field.name));
+                    return Err(invalid("ipv4", field, []));
                 }
             }
             Ok(())
@@ -289,7 +1003,7 @@ fn validate_rule(
                     r"^(([0-9a-fA-F]{1,4}:){7,7}[0-9a-fA-F]{1,4}|([0-9a-fA-F]{1,4}:){1,7}:|([0-9a-fA-F]{1,4}:){1,6}:[0-9a-fA-F]{1,4}|([0-9a-fA-F]{1,4}:){1,5}(:[0-9a-fA-F]{1,4}){1,2}|([0-9a-fA-F]{1,4}:){1,4}(:[0-9a-fA-F]{1,4}){1,3}|([0-9a-fA-F]{1,4}:){1,3}(:[0-9a-fA-F]{1,4}){1,4}|([0-9a-fA-F]{1,4}:){1,2}(:[0-9a-fA-F]{1,4}){1,5}|[0-9a-fA-F]{1,4}:((:[0-9a-fA-F]{1,4}){1,6})|:((:[0-9a-fA-F]{1,4}){1,7}|:)|fe80:(:[0-9a-fA-F]{0,4}){0,4}%[0-9a-zA-Z]{1,}|::(ffff(:0{1,4}){0,1}:){0,1}((25[0-5]|(2[0-4]|1{0,1}[0-9]){0,1}[0-9])\.){3,3}(25[0-5]|(2[0-4]|1{0,1}[0-9]){0,1}[0-9])|([0-9a-fA-F]{1,4}:){1,4}:((25[0-5]|(2[0-4]|1{0,1}[0-9]){0,1}[0-9])\.){3,3}(25[0-5]|(2[0-4]|1{0,1}[0-9]){0,1}[0-9]))$"
                 ).unwrap();
                 if !ipv6_regex.is_match(s) {
-                    return Err(anyhow!("{} must be a valid IPv6 address", field.name));
+                    return Err(invalid("ipv6", field, []));
                 }
             }
             Ok(())
@@ -297,7 +1011,7 @@ fn validate_rule(
         ValidationType::Uuid => {
             if let Some(s) = value.as_str() {
                 if uuid::Uuid::parse_str(s).is_err() {
-                    return Err(anyhow!("{} must be a valid UUID", field.name));
+                    return Err(invalid("uuid", field, []));
                 }
             }
             Ok(())
@@ -316,7 +1030,14 @@ fn validate_rule(
                     DateTime::parse_from_rfc3339(end),
                 ) {
                     if start_date >= end_date {
-                        return Err(anyhow!("Start date must be before end date"));
+                        return Err(invalid(
+                            "date_range",
+                            field,
+                            [
+                                ("start", start_field.clone().into()),
+                                ("end", end_field.clone().into()),
+                            ],
+                        ));
                     }
                 }
             }
@@ -348,28 +1069,23 @@ fn validate_rule(
         } => {
             if let Some(s) = value.as_str() {
                 if s.len() < *min_length {
-                    return Err(anyhow!(
-                        "Password must be at least {} characters",
-                        min_length
+                    return Err(invalid(
+                        "password_min_length",
+                        field,
+                        [("min", (*min_length).into())],
                     ));
                 }
                 if *require_uppercase && !s.chars().any(|c| c.is_uppercase()) {
-                    return Err(anyhow!(
-                        "Password must contain at least one uppercase letter"
-                    ));
+                    return Err(invalid("password_uppercase", field, []));
                 }
                 if *require_lowercase && !s.chars().any(|c| c.is_lowercase()) {
-                    return Err(anyhow!(
-                        "Password must contain at least one lowercase letter"
-                    ));
+                    return Err(invalid("password_lowercase", field, []));
                 }
                 if *require_number && !s.chars().any(|c| c.is_numeric()) {
-                    return Err(anyhow!("Password must contain at least one number"));
+                    return Err(invalid("password_number", field, []));
                 }
                 if *require_special && !s.chars().any(|c| !c.is_alphanumeric()) {
-                    return Err(anyhow!(
-                        "Password must contain at least one special character"
-                    ));
+                    return Err(invalid("password_special", field, []));
                 }
             }
             Ok(())
@@ -377,10 +1093,7 @@ fn validate_rule(
         ValidationType::AlphaNumeric => {
             if let Some(s) = value.as_str() {
                 if !s.chars().all(|c| c.is_alphanumeric()) {
-                    return Err(anyhow!(
-                        "{} must contain only alphanumeric characters",
-                        field.name
-                    ));
+                    return Err(invalid("alpha_numeric", field, []));
                 }
             }
             Ok(())
@@ -388,7 +1101,7 @@ fn validate_rule(
         ValidationType::Luhn => {
             if let Some(s) = value.as_str() {
                 if !validate_luhn(s) {
-                    return Err(anyhow!("{} failed Luhn check", field.name));
+                    return Err(invalid("luhn", field, []));
                 }
             }
             Ok(())
@@ -397,7 +1110,7 @@ fn validate_rule(
             if let Some(s) = value.as_str() {
                 let mac_regex = Regex::new(r"^([0-9A-Fa-f]{2}[:-]){5}([0-9A-Fa-f]{2})$").unwrap();
                 if !mac_regex.is_match(s) {
-                    return Err(anyhow!("{} must be a valid MAC address", field.name));
+                    return Err(invalid("mac_address", field, []));
                 }
             }
             Ok(())
@@ -411,7 +1124,7 @@ fn validate_rule(
                 .unwrap();
                 let normalized = s.replace(&['-', ' '][..], "");
                 if !isbn_regex.is_match(&normalized) {
-                    return Err(anyhow!("{} must be a valid ISBN", field.name));
+                    return Err(invalid("isbn", field, []));
                 }
             }
             Ok(())
@@ -420,7 +1133,7 @@ fn validate_rule(
             if let Some(s) = value.as_str() {
                 let iban_regex = Regex::new(r"^[A-Z]{2}[0-9]{2}[A-Z0-9]{1,30}$").unwrap();
                 if !iban_regex.is_match(s) {
-                    return Err(anyhow!("{} must be a valid IBAN", field.name));
+                    return Err(invalid("iban", field, []));
                 }
             }
             Ok(())
@@ -429,7 +1142,7 @@ fn validate_rule(
             if let Some(s) = value.as_str() {
                 let ssn_regex = Regex::new(r"^\d{3}-\d{2}-\d{4}$").unwrap();
                 if !ssn_regex.is_match(s) {
-                    return Err(anyhow!("{} must be a valid SSN (XXX-XX-XXXX)", field.name));
+                    return Err(invalid("ssn", field, []));
                 }
             }
             Ok(())
@@ -447,10 +1160,10 @@ fn validate_rule(
                     _ => true, // Unknown country codes pass
                 };
                 if !is_valid {
-                    return Err(anyhow!(
-                        "{} must be a valid {} postal code",
-                        field.name,
-                        country_code
+                    return Err(invalid(
+                        "postal_code",
+                        field,
+                        [("country", country_code.clone().into())],
                     ));
                 }
             }
@@ -460,7 +1173,7 @@ fn validate_rule(
             if let Some(s) = value.as_str() {
                 let base64_regex = Regex::new(r"^[A-Za-z0-9+/]*={0,2}$").unwrap();
                 if !base64_regex.is_match(s) {
-                    return Err(anyhow!("{} must be valid Base64", field.name));
+                    return Err(invalid("base64", field, []));
                 }
             }
             Ok(())
@@ -468,7 +1181,7 @@ fn validate_rule(
         ValidationType::Json => {
             if let Some(s) = value.as_str() {
                 if serde_json::from_str::<Value>(s).is_err() {
-                    return Err(anyhow!("{} must be valid JSON", field.name));
+                    return Err(invalid("json", field, []));
                 }
             }
             Ok(())
@@ -477,7 +1190,7 @@ fn validate_rule(
             if let Some(s) = value.as_str() {
                 let hex_regex = Regex::new(r"^[0-9a-fA-F]+$").unwrap();
                 if !hex_regex.is_match(s) {
-                    return Err(anyhow!("{} must be valid hexadecimal", field.name));
+                    return Err(invalid("hex", field, []));
                 }
             }
             Ok(())
@@ -485,7 +1198,7 @@ fn validate_rule(
         ValidationType::Ascii => {
             if let Some(s) = value.as_str() {
                 if !s.is_ascii() {
-                    return Err(anyhow!("{} must contain only ASCII characters", field.name));
+                    return Err(invalid("ascii", field, []));
                 }
             }
             Ok(())
@@ -493,7 +1206,7 @@ fn validate_rule(
         ValidationType::NotEmpty => {
             if let Some(s) = value.as_str() {
                 if s.trim().is_empty() {
-                    return Err(anyhow!("{} must not be empty", field.name));
+                    return Err(invalid("not_empty", field, []));
                 }
             }
             Ok(())
@@ -502,12 +1215,12 @@ fn validate_rule(
             if let Some(s) = value.as_str() {
                 if let Ok(date) = DateTime::parse_from_rfc3339(s) {
                     if date <= Utc::now() {
-                        return Err(anyhow!("{} must be a future date", field.name));
+                        return Err(invalid("future", field, []));
                     }
                 } else if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
                     let now = Utc::now().date_naive();
                     if date <= now {
-                        return Err(anyhow!("{} must be a future date", field.name));
+                        return Err(invalid("future", field, []));
                     }
                 }
             }
@@ -517,12 +1230,12 @@ fn validate_rule(
             if let Some(s) = value.as_str() {
                 if let Ok(date) = DateTime::parse_from_rfc3339(s) {
                     if date >= Utc::now() {
-                        return Err(anyhow!("{} must be a past date", field.name));
+                        return Err(invalid("past", field, []));
                     }
                 } else if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
                     let now = Utc::now().date_naive();
                     if date >= now {
-                        return Err(anyhow!("{} must be a past date", field.name));
+                        return Err(invalid("past", field, []));
                     }
                 }
             }
@@ -535,7 +1248,7 @@ fn validate_rule(
                     let age_days = (now - date).num_days();
                     let min_days = (*years as i64) * 365;
                     if age_days < min_days {
-                        return Err(anyhow!("Must be at least {} years old", years));
+                        return Err(invalid("min_age", field, [("years", (*years).into())]));
                     }
                 }
             }
@@ -548,7 +1261,7 @@ fn validate_rule(
                     let age_days = (now - date).num_days();
                     let max_days = (*years as i64) * 365;
                     if age_days > max_days {
-                        return Err(anyhow!("Must be at most {} years old", years));
+                        return Err(invalid("max_age", field, [("years", (*years).into())]));
                     }
                 }
             }
@@ -557,21 +1270,30 @@ fn validate_rule(
         ValidationType::Between { min, max } => {
             if let Some(n) = value.as_f64() {
                 if n < *min || n > *max {
-                    return Err(anyhow!(
-                        "{} must be between {} and {}",
-                        field.name,
-                        min,
-                        max
+                    return Err(invalid(
+                        "between",
+                        field,
+                        [("min", number(*min)), ("max", number(*max))],
                     ));
                 }
             }
             Ok(())
         }
+        ValidationType::NoPii { kinds, mode } => {
+            // Other modes accept the value; see `warnings` and `pii::scrub_rows`
+            if let (Some(s), PiiMode::Reject) = (value.as_str(), mode) {
+                let found = pii::detect(s, kinds);
+                if !found.is_empty() {
+                    return Err(invalid("pii", field, [("kinds", pii_names(&found))]));
+                }
+            }
+            Ok(())
+        }
     }
 }
 
 /// Luhn algorithm implementation for credit card and similar validation
-fn validate_luhn(s: &str) -> bool {
+pub(crate) fn validate_luhn(s: &str) -> bool {
     let digits: Vec<u32> = s
         .chars()
         .filter(|c| c.is_numeric())
@@ -607,6 +1329,7 @@ fn validate_luhn(s: &str) -> bool {
 mod tests {
     use super::*;
     use crate::config::{FieldConfig, FieldType, TextFieldConfig, ValidationRule};
+    use std::sync::Arc;
 
     #[test]
     fn test_email_validation() {
@@ -626,8 +1349,16 @@ mod tests {
                 rule_type: ValidationType::Email,
                 message: None,
                 condition: None,
+                severity: Severity::Error,
             }],
             relationship_id: None,
+            sensitive: false,
+            mask: None,
+            unmask_scopes: vec![],
+            required_scopes: vec![],
+            write_scopes: vec![],
+            visible_when: None,
+            required_when: None,
         };
 
         let mut data = HashMap::new();
@@ -644,6 +1375,294 @@ mod tests {
         assert_eq!(errors.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_custom_function_validation() {
+        let field = FieldConfig {
+            id: "quantity".to_string(),
+            name: "Quantity".to_string(),
+            field_type: FieldType::Number {
+                config: Default::default(),
+            },
+            required: false,
+            editable: true,
+            visible: true,
+            default_value: None,
+            placeholder: None,
+            help_text: None,
+            validations: vec![ValidationRule {
+                rule_type: ValidationType::CustomFunction {
+                    function_name: "even".to_string(),
+                },
+                message: None,
+                condition: None,
+                severity: Severity::Error,
+            }],
+            relationship_id: None,
+            sensitive: false,
+            mask: None,
+            unmask_scopes: vec![],
+            required_scopes: vec![],
+            write_scopes: vec![],
+            visible_when: None,
+            required_when: None,
+        };
+
+        let mut functions = ValidationFunctions::default();
+        functions
+            .register_script(
+                "even",
+                r#"if value % 2 == 0 { () } else { "must be even" }"#,
+            )
+            .unwrap();
+
+        let mut data = HashMap::new();
+        data.insert("quantity".to_string(), Value::from(4));
+        let errors = validate_data_with_functions(&data, std::slice::from_ref(&field), &functions)
+            .await
+            .unwrap();
+        assert!(errors.is_empty());
+
+        data.insert("quantity".to_string(), Value::from(3));
+        let errors = validate_data_with_functions(&data, std::slice::from_ref(&field), &functions)
+            .await
+            .unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "must be even");
+
+        // Unregistered functions fail instead of silently passing
+        let errors = validate_data(&data, &[field]).unwrap();
+        assert_eq!(errors.len(), 1);
+    }
+
+    /// Queries run against a [`RecordingDataSource`], with their params
+    type Recorded = Arc<Mutex<Vec<(String, HashMap<String, Value>)>>>;
+
+    /// Data source that records queries and returns canned rows
+    struct RecordingDataSource {
+        rows: Vec<HashMap<String, Value>>,
+        queries: Recorded,
+    }
+
+    fn recording_sources(
+        name: &str,
+        rows: Vec<HashMap<String, Value>>,
+        queries: &Recorded,
+    ) -> HashMap<String, Box<dyn DataSource>> {
+        let source = RecordingDataSource {
+            rows,
+            queries: queries.clone(),
+        };
+        HashMap::from([(name.to_string(), Box::new(source) as Box<dyn DataSource>)])
+    }
+
+    #[async_trait::async_trait]
+    impl DataSource for RecordingDataSource {
+        async fn execute_query(
+            &self,
+            query: &str,
+            params: Option<&HashMap<String, Value>>,
+        ) -> Result<Vec<HashMap<String, Value>>> {
+            let params = params.cloned().unwrap_or_default();
+            self.queries
+                .lock()
+                .unwrap()
+                .push((query.to_string(), params));
+            Ok(self.rows.clone())
+        }
+
+        async fn execute_query_paginated(
+            &self,
+            query: &str,
+            params: Option<&HashMap<String, Value>>,
+            _pagination: Option<&crate::data_source::PaginationParams>,
+        ) -> Result<Vec<HashMap<String, Value>>> {
+            self.execute_query(query, params).await
+        }
+
+        async fn execute_mutation(
+            &self,
+            _query: &str,
+            _data: &HashMap<String, Value>,
+        ) -> Result<Value> {
+            Ok(Value::Null)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unique_in_validation() {
+        let field = FieldConfig {
+            id: "slug".to_string(),
+            name: "Slug".to_string(),
+            field_type: FieldType::Text {
+                config: TextFieldConfig::default(),
+            },
+            required: false,
+            editable: true,
+            visible: true,
+            default_value: None,
+            placeholder: None,
+            help_text: None,
+            validations: vec![ValidationRule {
+                rule_type: ValidationType::UniqueIn {
+                    field_list: vec!["tenant_id".to_string()],
+                },
+                message: None,
+                condition: None,
+                severity: Severity::Error,
+            }],
+            relationship_id: None,
+            sensitive: false,
+            mask: None,
+            unmask_scopes: vec![],
+            required_scopes: vec![],
+            write_scopes: vec![],
+            visible_when: None,
+            required_when: None,
+        };
+
+        let mut data = HashMap::new();
+        data.insert("id".to_string(), Value::from(7));
+        data.insert("slug".to_string(), Value::from("o'neil"));
+        data.insert("tenant_id".to_string(), Value::from(3));
+
+        let backoffice: BackofficeConfig = serde_yaml::from_str(
+            r#"
+id: blog
+name: Blog
+data_sources:
+  main: { type: database, connection_string: "sqlite::memory:", db_type: postgres }
+  api: { type: api, base_url: "http://localhost" }
+sections:
+  - { id: posts, name: Posts, table: blog_posts, actions: [] }
+"#,
+        )
+        .unwrap();
+        let section = &backoffice.sections[0];
+        let main = backoffice.data_sources.get("main");
+
+        let queries: Recorded = Default::default();
+        let sources = recording_sources("main", vec![], &queries);
+        let errors = validate_data_async(
+            &data,
+            std::slice::from_ref(&field),
+            section,
+            "main",
+            main,
+            &sources,
+        )
+        .await
+        .unwrap();
+        assert!(errors.is_empty());
+        let (query, params) = queries.lock().unwrap()[0].clone();
+        assert_eq!(
+            query,
+            r#"SELECT "id" FROM "blog_posts" WHERE "slug" = {{unique_0}} AND "tenant_id" = {{unique_1}} AND "id" <> {{unique_id}}"#
+        );
+        assert_eq!(params["unique_0"], Value::from("o'neil"));
+        assert_eq!(params["unique_1"], Value::from(3));
+        assert_eq!(params["unique_id"], Value::from(7));
+
+        let taken = vec![HashMap::from([("id".to_string(), Value::from(8))])];
+        let sources = recording_sources("main", taken, &queries);
+        let errors = validate_data_async(
+            &data,
+            std::slice::from_ref(&field),
+            section,
+            "main",
+            main,
+            &sources,
+        )
+        .await
+        .unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "slug");
+
+        // Other data sources can't run the check, so their configs are rejected
+        let mut backoffice = backoffice;
+        backoffice.sections[0].actions = serde_yaml::from_str(
+            r#"
+- id: create
+  name: Create
+  type: form
+  data_source: api
+  required_scopes: []
+  fields:
+    - { id: slug, name: Slug, field_type: text, validations: [{ rule_type: { type: unique_in, field_list: [] } }] }
+"#,
+        )
+        .unwrap();
+        assert!(backoffice.validate_unique_rules().is_err());
+        backoffice.sections[0].actions[0].data_source = "main".to_string();
+        assert!(backoffice.validate_unique_rules().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_remote_validation_is_cached() {
+        let field = FieldConfig {
+            id: "vat".to_string(),
+            name: "VAT number".to_string(),
+            field_type: FieldType::Text {
+                config: TextFieldConfig::default(),
+            },
+            required: false,
+            editable: true,
+            visible: true,
+            default_value: None,
+            placeholder: None,
+            help_text: None,
+            validations: vec![ValidationRule {
+                rule_type: ValidationType::Remote {
+                    data_source: "vat_api".to_string(),
+                    query: None,
+                    endpoint: Some("/check/{value}".to_string()),
+                    expect: RemoteExpectation::Truthy,
+                    field: Some("valid".to_string()),
+                    timeout_ms: None,
+                    cache_ttl_secs: None,
+                },
+                message: None,
+                condition: None,
+                severity: Severity::Error,
+            }],
+            relationship_id: None,
+            sensitive: false,
+            mask: None,
+            unmask_scopes: vec![],
+            required_scopes: vec![],
+            write_scopes: vec![],
+            visible_when: None,
+            required_when: None,
+        };
+
+        let mut data = HashMap::new();
+        data.insert("vat".to_string(), Value::from("ES B/123"));
+
+        let section: SectionConfig =
+            serde_yaml::from_str("{ id: orgs, name: Orgs, actions: [] }").unwrap();
+        let queries: Recorded = Default::default();
+        let rows = vec![HashMap::from([("valid".to_string(), Value::Bool(false))])];
+        let sources = recording_sources("vat_api", rows, &queries);
+
+        for _ in 0..2 {
+            let errors = validate_data_async(
+                &data,
+                std::slice::from_ref(&field),
+                &section,
+                "main",
+                None,
+                &sources,
+            )
+            .await
+            .unwrap();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].message, "VAT number is invalid");
+        }
+
+        let queries = queries.lock().unwrap();
+        let queries: Vec<&str> = queries.iter().map(|(query, _)| query.as_str()).collect();
+        assert_eq!(queries, vec!["/check/ES%20B%2F123"]);
+    }
+
     #[test]
     fn test_luhn_algorithm() {
         assert!(validate_luhn("4532015112830366")); // Valid Visa
@@ -666,6 +1685,13 @@ mod tests {
             help_text: None,
             validations: vec![],
             relationship_id: None,
+            sensitive: false,
+            mask: None,
+            unmask_scopes: vec![],
+            required_scopes: vec![],
+            write_scopes: vec![],
+            visible_when: None,
+            required_when: None,
         };
 
         let data = HashMap::new();
@@ -673,4 +1699,142 @@ mod tests {
         assert_eq!(errors.len(), 1);
         assert_eq!(errors[0].field, "name");
     }
+
+    #[test]
+    fn test_no_pii_validation() {
+        let fields: Vec<FieldConfig> = serde_yaml::from_str(
+            r#"
+- id: notes
+  name: Notes
+  field_type: text
+  validations:
+    - rule_type: { type: no_pii }
+- id: comment
+  name: Comment
+  field_type: text
+  validations:
+    - rule_type: { type: no_pii, kinds: [ssn, email], mode: flag }
+"#,
+        )
+        .unwrap();
+        let data = HashMap::from([
+            (
+                "notes".to_string(),
+                serde_json::json!("Reach me at ada@example.com"),
+            ),
+            (
+                "comment".to_string(),
+                serde_json::json!("SSN 123-45-6789, call 555-123-4567"),
+            ),
+        ]);
+
+        let errors = validate_data(&data, &fields).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "pii");
+        assert_eq!(
+            errors[0].message,
+            "Notes must not contain personal data (email)"
+        );
+
+        // Flagged fields are valid, with a warning naming what was found
+        let warnings = warnings(&data, &fields);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "comment");
+        assert_eq!(warnings[0].params["kinds"], serde_json::json!("ssn"));
+    }
+
+    #[test]
+    fn test_email_domain_lists() {
+        let fields: Vec<FieldConfig> = serde_yaml::from_str(
+            r#"
+- id: email
+  name: Email
+  field_type: email
+  config: { domain_blacklist: [mailinator.com] }
+"#,
+        )
+        .unwrap();
+        let data = |email: &str| HashMap::from([("email".to_string(), serde_json::json!(email))]);
+
+        assert!(validate_data(&data("ada@example.com"), &fields)
+            .unwrap()
+            .is_empty());
+        let errors = validate_data(&data("spam@Mailinator.com"), &fields).unwrap();
+        assert_eq!(errors[0].code, "email_domain");
+        assert_eq!(
+            errors[0].message,
+            "Email can't use addresses at mailinator.com"
+        );
+    }
+
+    #[test]
+    fn test_credit_card_brands() {
+        let fields: Vec<FieldConfig> = serde_yaml::from_str(
+            r#"
+- id: card
+  name: Card
+  field_type: text
+  validations:
+    - rule_type: { type: credit_card, allowed_brands: [visa, mastercard] }
+"#,
+        )
+        .unwrap();
+        let data = |card: &str| HashMap::from([("card".to_string(), serde_json::json!(card))]);
+
+        assert!(validate_data(&data("4111 1111 1111 1111"), &fields)
+            .unwrap()
+            .is_empty());
+        let errors = validate_data(&data("378282246310005"), &fields).unwrap();
+        assert_eq!(errors[0].code, "card_brand");
+        assert_eq!(
+            errors[0].message,
+            "Card: amex cards are not accepted (visa, mastercard)"
+        );
+        // Masked numbers of stored records pass
+        assert!(validate_data(&data("************1111"), &fields)
+            .unwrap()
+            .is_empty());
+
+        let mut payload = data("4111-1111-1111-1111");
+        card::mask_payload(&mut payload, &fields);
+        assert_eq!(payload["card"], serde_json::json!("************1111"));
+    }
+
+    #[test]
+    fn test_expression_validation() {
+        let fields: Vec<FieldConfig> = serde_yaml::from_str(
+            r#"
+- id: price
+  name: Price
+  field_type: number
+- id: discount
+  name: Discount
+  field_type: number
+  validations:
+    - rule_type: { type: expression, expr: "discount <= price * 0.5" }
+      message: Discount can't exceed half the price
+"#,
+        )
+        .unwrap();
+        let data = |price: f64, discount: f64| {
+            HashMap::from([
+                ("price".to_string(), serde_json::json!(price)),
+                ("discount".to_string(), serde_json::json!(discount)),
+            ])
+        };
+
+        assert!(validate_data(&data(100.0, 50.0), &fields)
+            .unwrap()
+            .is_empty());
+        let errors = validate_data(&data(100.0, 60.0), &fields).unwrap();
+        assert_eq!(errors[0].code, "expression");
+        assert_eq!(errors[0].message, "Discount can't exceed half the price");
+        // Expressions that can't be evaluated fail the rule
+        let errors = validate_data(
+            &HashMap::from([("discount".to_string(), serde_json::json!(10))]),
+            &fields,
+        )
+        .unwrap();
+        assert_eq!(errors[0].code, "expression");
+    }
 }
@@ -0,0 +1,187 @@
+//! API versioning
+//!
+//! Every route is served under `/api/v1/...` and `/api/v2/...` besides the
+//! unversioned `/api/...`, each version with its own response envelope:
+//!
+//! - **v1**: bodies as the handlers build them, e.g. `{"data": [...],
+//!   "pagination": {...}}`
+//! - **v2**: `{"data": ..., "meta": {...}}`, with everything besides the
+//!   data in `meta`
+//!
+//! Errors are problem documents (see [`crate::problem`]) in every version.
+//!
+//! Unversioned routes of a backoffice answer with the version it pins in
+//! `api.version`, so clients naming a version keep working while the
+//! backoffice moves to another. Versions missing from `api.versions` are
+//! 404, and those in `api.deprecated` carry a `Deprecation` header with a
+//! `Link` to the newest served version.
+
+use crate::server::AppState;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+    Router,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Envelope versions the server has
+pub const VERSIONS: [u8; 2] = [1, 2];
+
+/// Version a request named with its `/api/vN` prefix
+#[derive(Debug, Clone, Copy)]
+struct Requested(u8);
+
+/// Serve `router` under `/api/vN` for every version as well as unversioned
+pub fn versioned(router: Router) -> Router {
+    let mut versioned = Router::new();
+    for version in VERSIONS {
+        let service = tower::ServiceBuilder::new()
+            .map_request(move |mut request: Request| {
+                // Nesting stripped the prefix; the routes expect `/api/...`
+                let path = request
+                    .uri()
+                    .path_and_query()
+                    .map_or("/", |path| path.as_str());
+                if let Ok(uri) = format!("/api{}", path).parse() {
+                    *request.uri_mut() = uri;
+                }
+                request.extensions_mut().insert(Requested(version));
+                request
+            })
+            .service(router.clone());
+        versioned = versioned.nest_service(&format!("/api/v{}", version), service);
+    }
+    versioned.fallback_service(router)
+}
+
+/// Id of the backoffice a path is under
+fn backoffice_id(path: &str) -> Option<&str> {
+    path.strip_prefix("/api/backoffices/")?
+        .split('/')
+        .next()
+        .filter(|id| !id.is_empty())
+}
+
+/// Answer with the envelope version of the request and mark deprecated ones
+pub async fn envelope(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let requested = request.extensions().get::<Requested>().map(|r| r.0);
+    let path = request.uri().path().to_string();
+    let api = backoffice_id(&path)
+        .and_then(|id| state.backoffice(id))
+        .map(|backoffice| &backoffice.api);
+
+    let version = match (requested, api) {
+        (Some(version), Some(api)) if !api.versions.contains(&version) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": format!("API version {} is not served for this backoffice", version)
+                })),
+            )
+                .into_response();
+        }
+        (Some(version), _) => version,
+        (None, Some(api)) => api.version,
+        (None, None) => 1,
+    };
+    let successor = api
+        .filter(|api| api.deprecated.contains(&version))
+        .and_then(|api| api.versions.iter().max())
+        .map(|latest| format!("/api/v{}{}", latest, path.trim_start_matches("/api")));
+
+    let mut response = next.run(request).await;
+    if version == 2 && response.status().is_success() {
+        response = rewrap(response).await;
+    }
+    if let Some(successor) = successor {
+        let headers = response.headers_mut();
+        headers.insert("deprecation", HeaderValue::from_static("true"));
+        if let Ok(link) = format!("<{}>; rel=\"successor-version\"", successor).parse() {
+            headers.insert(header::LINK, link);
+        }
+    }
+    response
+}
+
+/// A successful JSON response in the v2 envelope; other responses, such as
+/// event streams and downloads, are left as they are
+async fn rewrap(response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => match serde_json::from_slice::<Value>(&bytes) {
+            Ok(body) => body,
+            Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+        },
+        Err(e) => {
+            warn!(error = %e, "Failed to read response body for its envelope");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = v2(body).to_string();
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// The v2 envelope of a successful v1 body
+fn v2(body: Value) -> Value {
+    match body {
+        Value::Object(mut body) => {
+            body.remove("success");
+            match body.remove("data") {
+                Some(data) => json!({"data": data, "meta": body}),
+                // Bodies without `data` are the data, e.g. a backoffice
+                None => json!({"data": body, "meta": {}}),
+            }
+        }
+        body => json!({"data": body, "meta": {}}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v2_envelope() {
+        let list = json!({"data": [{"id": 1}], "pagination": {"page": 1}});
+        assert_eq!(
+            v2(list),
+            json!({"data": [{"id": 1}], "meta": {"pagination": {"page": 1}}})
+        );
+        let created = json!({"success": true, "data": {"id": 2}, "warnings": []});
+        assert_eq!(
+            v2(created),
+            json!({"data": {"id": 2}, "meta": {"warnings": []}})
+        );
+        assert_eq!(
+            v2(json!({"id": "crm"})),
+            json!({"data": {"id": "crm"}, "meta": {}})
+        );
+
+        assert_eq!(v2(json!([1, 2])), json!({"data": [1, 2], "meta": {}}));
+
+        assert_eq!(
+            backoffice_id("/api/backoffices/crm/sections/users"),
+            Some("crm")
+        );
+        assert_eq!(backoffice_id("/api/backoffices"), None);
+    }
+}
@@ -1,3 +1,11 @@
+// The UI is written against the v1 response envelope, whatever version
+// backoffices pin for unversioned routes
+const API_BASE = '/api/v1';
+
+// Ask for plain JSON even from backoffices answering with JSON:API by default
+const JSON_HEADERS = { Accept: 'application/json' };
+$.ajaxSetup({ headers: JSON_HEADERS });
+
 // Global state
 let currentBackoffice = null;
 let currentSection = null;
@@ -5,6 +13,10 @@ let currentAction = null;
 let backoffices = [];
 let currentPage = 1;
 let currentFilters = {};
+let dashboardTimers = [];
+let pushSocket = null;
+let pushChannel = null;
+let pushHandler = null;
 
 // Dark mode functionality
 function initDarkMode() {
@@ -157,7 +169,7 @@ $(document).ready(function() {
 
 // Load all backoffices
 function loadBackoffices() {
-    $.get('/api/backoffices', function(data) {
+    $.get(`${API_BASE}/backoffices`, function(data) {
         backoffices = data;
         renderBackofficeTabs();
 
@@ -200,8 +212,10 @@ function selectBackoffice(backofficeId) {
     // Render sections
     renderSections();
 
-    // Select first section if available
-    if (currentBackoffice.sections.length > 0) {
+    // Start on the dashboard, or the first section
+    if (currentBackoffice.dashboard) {
+        showDashboard();
+    } else if (currentBackoffice.sections.length > 0) {
         selectSection(currentBackoffice.sections[0].id);
     }
 }
@@ -213,6 +227,16 @@ function renderSections() {
 
     if (!currentBackoffice) return;
 
+    if (currentBackoffice.dashboard) {
+        $list.append(
+            $('<li>')
+                .addClass('cursor-pointer p-3 rounded-lg hover:bg-indigo-50 transition-colors')
+                .attr('data-id', '__dashboard')
+                .html('<i class="fas fa-chart-pie mr-2"></i>Dashboard')
+                .click(showDashboard)
+        );
+    }
+
     currentBackoffice.sections.forEach(function(section) {
         const icon = section.icon || 'fa-folder';
         const $item = $('<li>')
@@ -235,6 +259,8 @@ function selectSection(sectionId) {
 
     if (!currentSection) return;
 
+    clearDashboardTimers();
+
     // Update active section
     $('#sections-list li').removeClass('bg-indigo-100 font-semibold');
     $(`#sections-list li[data-id="${sectionId}"]`).addClass('bg-indigo-100 font-semibold');
@@ -298,11 +324,18 @@ function executeAction(action) {
             loadListData(action);
             break;
         case 'form':
+        case 'pipeline':
             showForm(action);
             break;
         case 'view':
             loadViewData(action);
             break;
+        case 'summary':
+            loadSummaryData(action);
+            break;
+        case 'report':
+            showReport(action);
+            break;
         default:
             showError('Action type not supported: ' + action.type);
     }
@@ -311,7 +344,7 @@ function executeAction(action) {
 // Load list data
 function loadListData(action, page = 1) {
     currentPage = page;
-    const url = `/api/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
 
     const params = { ...currentFilters };
     if (action.config && action.config.enable_pagination) {
@@ -324,7 +357,7 @@ function loadListData(action, page = 1) {
     $.get(url, params, function(response) {
         renderTable(response.data, response.fields, response.config, response.pagination);
     }).fail(function(err) {
-        showError('Failed to load data: ' + (err.responseJSON?.error || err.responseText));
+        showError('Failed to load data: ' + errorMessage(err));
     });
 }
 
@@ -404,6 +437,25 @@ function renderTable(data, fields, config, pagination) {
 
     $searchRow.append($searchIcon).append($searchInput).append($filterToggle).append($exportContainer).append($importBtn);
 
+    // Bulk update of every record matching the list filters
+    const bulkAction = currentSection.actions.find(a =>
+        a.type === 'form' && a.config && a.config.form_mode === 'update'
+    );
+    const bulkFilter = Object.fromEntries(
+        Object.entries(currentFilters).filter(([key, value]) => key !== 'q' && typeof value !== 'object')
+    );
+    if (bulkAction && Object.keys(bulkFilter).length > 0) {
+        const expectedCount = pagination ? pagination.total_items : data.length;
+        $searchRow.append(
+            $('<button>')
+                .addClass('px-4 py-2 bg-purple-600 text-white rounded-lg hover:bg-purple-700 transition-colors flex items-center gap-2')
+                .html('<i class="fas fa-pen-square"></i> Update Matching')
+                .click(function() {
+                    bulkUpdateMatching(bulkAction, bulkFilter, expectedCount);
+                })
+        );
+    }
+
     // Bulk actions row (initially hidden)
     const $bulkRow = $('<div>')
         .attr('id', 'bulk-actions-bar')
@@ -550,6 +602,14 @@ function renderTable(data, fields, config, pagination) {
                 .attr('data-field-id', field.id)
                 .attr('data-row-id', row.id || index);
 
+            renderCurrency($cell, row, field);
+
+            // Full-text search snippets, with matches in <mark> tags
+            const snippet = row._highlight && row._highlight[field.id];
+            if (snippet) {
+                renderHighlight($cell, snippet);
+            }
+
             // Add inline editing for editable fields
             if (field.editable) {
                 $cell.addClass('editable-cell cursor-pointer hover:bg-blue-50')
@@ -580,6 +640,19 @@ function renderTable(data, fields, config, pagination) {
             );
         }
 
+        // Duplicate button for sections copying records
+        if (currentSection.duplicate && row.id !== undefined) {
+            $actionCell.append(
+                $('<button>')
+                    .addClass('text-gray-600 hover:text-gray-900 mr-3')
+                    .attr('title', 'Duplicate')
+                    .html('<i class="fas fa-copy"></i>')
+                    .click(function() {
+                        duplicateRecord(row);
+                    })
+            );
+        }
+
         // Delete button (look for form action with delete mode)
         const deleteAction = currentSection.actions.find(a =>
             a.type === 'form' && a.config && a.config.form_mode === 'delete'
@@ -605,8 +678,37 @@ function renderTable(data, fields, config, pagination) {
     $tableContainer.append($table);
     $dataArea.append($tableContainer);
 
+    // Lists with searchable fields are searched server-side through `q`
+    const serverSearch = config && config.searchable_fields && config.searchable_fields.length > 0;
+    if (serverSearch) {
+        let searchTimer = null;
+        $searchInput.val(currentFilters.q || '');
+        $searchInput.on('input', function() {
+            const term = $(this).val().trim();
+            clearTimeout(searchTimer);
+            searchTimer = setTimeout(function() {
+                if (term) {
+                    currentFilters.q = term;
+                } else {
+                    delete currentFilters.q;
+                }
+                loadListData(currentAction, 1);
+            }, 300);
+        });
+        if (currentFilters.q) {
+            setTimeout(function() {
+                const input = $searchInput.get(0);
+                input.focus();
+                input.setSelectionRange(input.value.length, input.value.length);
+            }, 0);
+        }
+    }
+
     // Add search functionality
     $searchInput.on('input', function() {
+        if (serverSearch) {
+            return;
+        }
         const searchTerm = $(this).val().toLowerCase().trim();
         let visibleCount = 0;
 
@@ -727,7 +829,8 @@ function renderFilters(filters) {
 
 // Apply filters
 function applyFilters(filters) {
-    currentFilters = {};
+    // Keep the search text across filter changes
+    currentFilters = currentFilters.q ? { q: currentFilters.q } : {};
     filters.forEach(function(filter) {
         const value = $('#filter-' + filter.id).val();
         if (value) {
@@ -797,6 +900,32 @@ function renderPagination(pagination) {
 }
 
 // Format field value based on field type
+// Render a search snippet as text, turning only its <mark> tags into elements
+function renderHighlight($cell, snippet) {
+    $cell.empty();
+    snippet.split(/(<mark>.*?<\/mark>)/).forEach(function(part) {
+        const match = part.match(/^<mark>(.*)<\/mark>$/);
+        if (match) {
+            $cell.append($('<mark>').text(match[1]));
+        } else if (part) {
+            $cell.append(document.createTextNode(part));
+        }
+    });
+}
+
+// Show a currency amount as the server formatted it, and its amount in the
+// reporting currency on hover
+function renderCurrency($element, row, field) {
+    const formatted = row._formatted && row._formatted[field.id];
+    if (formatted) {
+        $element.text(formatted);
+    }
+    const converted = row._converted && row._converted[field.id];
+    if (converted) {
+        $element.attr('title', converted.formatted);
+    }
+}
+
 function formatFieldValue(value, field) {
     if (field.field_type === 'boolean') {
         return value ? 'Yes' : 'No';
@@ -804,6 +933,10 @@ function formatFieldValue(value, field) {
     if (field.field_type === 'date' || field.field_type === 'datetime') {
         return value ? new Date(value).toLocaleString() : '';
     }
+    if (field.field_type === 'state') {
+        const state = (field.config?.states || []).find(s => s.value === value);
+        return state ? state.label : value;
+    }
     return value;
 }
 
@@ -821,24 +954,104 @@ function showForm(action, data = {}) {
     }
 
     $('#modal-title').text(title);
-    renderForm(action.fields, data, config);
+    renderForm(action.fields, data, config, action.layout);
     $('#formModal').addClass('active');
 
     $('#dynamic-form').off('submit').on('submit', function(e) {
         e.preventDefault();
         submitForm(action, data);
     });
+
+    if (data.id !== undefined && action.fields.some(f => f.field_type === 'state')) {
+        limitStateTransitions(action, data.id);
+    }
+
+    $('#dynamic-form').off('focusout.validate').on('focusout.validate', 'input, select, textarea', function() {
+        if (this.name) {
+            validateField(action, data, this.name);
+        }
+    });
+
+    if (action.fields.some(f => f.visible_when || f.required_when)) {
+        applyFormState(action, data);
+        $('#dynamic-form').off('change.conditions').on('change.conditions', function() {
+            applyFormState(action, data);
+        });
+    }
+}
+
+// Check a field with the server's rules as the user leaves it
+function validateField(action, data, fieldId) {
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}/validate?partial=true`;
+    const payload = { [fieldId]: $('#' + fieldId).val() };
+    if (data.id !== undefined) {
+        payload.id = data.id;
+    }
+
+    $.ajax({
+        url: url,
+        method: 'POST',
+        contentType: 'application/json',
+        data: JSON.stringify(payload),
+        success: function(response) {
+            const $group = $(`[data-field="${fieldId}"]`);
+            $group.find('.field-error').remove();
+            response.validation_errors.concat(response.warnings)
+                .filter(e => e.field === fieldId)
+                .forEach(function(e) {
+                    $group.append($('<p>').addClass('field-error text-xs text-red-600 mt-1').text(e.message));
+                });
+        }
+    });
+}
+
+// Show, hide, require and disable fields as the server evaluates the form's conditions
+function applyFormState(action, data) {
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}/evaluate`;
+    const values = Object.assign({}, data);
+    $('#dynamic-form').serializeArray().forEach(function(field) {
+        values[field.name] = field.value;
+    });
+
+    $.ajax({
+        url: url,
+        method: 'POST',
+        contentType: 'application/json',
+        data: JSON.stringify(values),
+        success: function(response) {
+            Object.entries(response.fields).forEach(function([id, state]) {
+                const field = action.fields.find(f => f.id === id);
+                $(`[data-field="${id}"]`).toggle(state.visible);
+                $(`[data-field="${id}"] label[for="${id}"]`).text(field.name + (state.required ? ' *' : ''));
+                $('#' + id).prop('required', state.visible && state.required).prop('readonly', state.disabled);
+            });
+        }
+    });
 }
 
-// Render dynamic form fields
-function renderForm(fields, data, config = {}) {
+// Leave only the transitions the server permits in state field selects
+function limitStateTransitions(action, recordId) {
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/records/${encodeURIComponent(recordId)}/transitions?action=${encodeURIComponent(action.id)}`;
+
+    $.get(url, function(response) {
+        response.fields.forEach(function(field) {
+            const allowed = field.transitions.map(t => t.to);
+            $(`#${field.field} option`).each(function() {
+                const value = $(this).val();
+                $(this).prop('disabled', value !== field.current && !allowed.includes(value));
+            });
+        });
+    });
+}
+
+// Render dynamic form fields, in the action's groups or tabs if it has a layout
+function renderForm(fields, data, config = {}, layout = null) {
     const $formFields = $('#form-fields');
     $formFields.empty();
 
-    fields.forEach(function(field) {
-        if (!field.editable && config.form_mode === 'create') return;
-
-        const $fieldGroup = $('<div>').addClass('form-group');
+    const shown = fields.filter(field => field.editable || config.form_mode !== 'create');
+    renderLayout($formFields, shown, layout, function(field, $container) {
+        const $fieldGroup = $('<div>').addClass('form-group').attr('data-field', field.id);
 
         const $label = $('<label>')
             .addClass('block text-sm font-medium text-gray-700 mb-1')
@@ -866,7 +1079,7 @@ function renderForm(fields, data, config = {}) {
             );
         }
 
-        $formFields.append($fieldGroup);
+        $container.append($fieldGroup);
     });
 
     // Update submit button text
@@ -917,6 +1130,26 @@ function createFieldInput(field, value) {
             }
             break;
 
+        case 'state':
+            // Records move along transitions; new records may start anywhere
+            const targets = (field.config?.transitions || [])
+                .filter(t => t.from === value)
+                .map(t => t.to);
+            $input = $('<select>')
+                .attr('id', field.id)
+                .attr('name', field.id)
+                .addClass('w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-indigo-500');
+
+            (field.config?.states || []).forEach(function(state) {
+                $input.append($('<option>')
+                    .val(state.value)
+                    .text(state.label)
+                    .prop('selected', state.value === value)
+                    .prop('disabled', value !== '' && state.value !== value && !targets.includes(state.value))
+                );
+            });
+            break;
+
         case 'boolean':
             const trueLabel = field.config?.true_label || 'Yes';
             const falseLabel = field.config?.false_label || 'No';
@@ -1686,6 +1919,48 @@ function createFieldInput(field, value) {
                 .attr('name', field.id)
                 .val(value || '');
 
+            // Strokes are submitted as lists of [x, y] points; the server
+            // renders them to a PNG when the field has a storage
+            const signatureCanvas = $canvas[0];
+            const signatureCtx = signatureCanvas.getContext('2d');
+            signatureCtx.strokeStyle = field.config?.pen_color || '#000000';
+            signatureCtx.lineWidth = field.config?.line_width || 2;
+            signatureCtx.lineCap = 'round';
+            signatureCtx.lineJoin = 'round';
+            if (typeof value === 'string' && value) {
+                const signatureImage = new Image();
+                signatureImage.onload = () => signatureCtx.drawImage(signatureImage, 0, 0, width, height);
+                signatureImage.src = value;
+            }
+            let strokes = [];
+            let drawing = false;
+            const canvasPoint = (e) => {
+                const rect = signatureCanvas.getBoundingClientRect();
+                return [
+                    Math.round((e.clientX - rect.left) * width / rect.width),
+                    Math.round((e.clientY - rect.top) * height / rect.height)
+                ];
+            };
+            signatureCanvas.addEventListener('pointerdown', (e) => {
+                drawing = true;
+                const point = canvasPoint(e);
+                strokes.push([point]);
+                signatureCtx.beginPath();
+                signatureCtx.moveTo(point[0], point[1]);
+            });
+            signatureCanvas.addEventListener('pointermove', (e) => {
+                if (!drawing) return;
+                const point = canvasPoint(e);
+                strokes[strokes.length - 1].push(point);
+                signatureCtx.lineTo(point[0], point[1]);
+                signatureCtx.stroke();
+            });
+            ['pointerup', 'pointerleave'].forEach(type => signatureCanvas.addEventListener(type, () => {
+                if (!drawing) return;
+                drawing = false;
+                $input.val(JSON.stringify(strokes));
+            }));
+
             const $clearBtn = $('<button>')
                 .attr('type', 'button')
                 .addClass('mt-2 px-3 py-1 bg-gray-200 text-gray-700 rounded-md hover:bg-gray-300 text-sm')
@@ -1694,6 +1969,7 @@ function createFieldInput(field, value) {
                     const canvas = document.getElementById(field.id + '-canvas');
                     const ctx = canvas.getContext('2d');
                     ctx.clearRect(0, 0, canvas.width, canvas.height);
+                    strokes = [];
                     $input.val('');
                 });
 
@@ -1958,7 +2234,7 @@ function submitForm(action, existingData = {}) {
                 break;
             case 'signature':
                 const signatureValue = $('#' + field.id).val();
-                data[field.id] = signatureValue || null;
+                data[field.id] = signatureValue?.startsWith('[') ? JSON.parse(signatureValue) : signatureValue || null;
                 break;
             case 'colorpalette':
                 const paletteValue = $('#' + field.id).val();
@@ -1971,14 +2247,44 @@ function submitForm(action, existingData = {}) {
         }
     });
 
-    // Merge with existing data (for updates)
-    const payload = Object.assign({}, existingData, data);
-
     $('#submit-text').hide();
     $('#submit-loading').removeClass('hidden');
 
-    const url = `/api/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
 
+    // Files of fields with storage are uploaded (and virus scanned) first;
+    // the record keeps their keys
+    const uploads = action.fields
+        .filter(f => (f.field_type === 'file' || f.field_type === 'image') && f.config?.storage)
+        .flatMap(f => Array.from($('#' + f.id)[0]?.files || []).map(file => ({ field: f, file: file })))
+        .map(function(upload) {
+            return $.ajax({
+                url: `${url}/fields/${upload.field.id}/upload?filename=${encodeURIComponent(upload.file.name)}`,
+                method: 'POST',
+                contentType: upload.file.type || 'application/octet-stream',
+                processData: false,
+                data: upload.file
+            }).then(function(response) {
+                if (upload.field.config.multiple) {
+                    data[upload.field.id] = (data[upload.field.id] || []).concat(response.key);
+                } else {
+                    data[upload.field.id] = response.key;
+                }
+            });
+        });
+
+    $.when(...uploads).then(function() {
+        sendForm(action, url, Object.assign({}, existingData, data));
+    }, function(err) {
+        const error = err.responseJSON;
+        showError('Upload failed: ' + (error?.signature ? `${error.detail} (${error.signature})` : errorMessage(err)));
+        $('#submit-text').show();
+        $('#submit-loading').addClass('hidden');
+    });
+}
+
+// Submit a form's payload, merged with the existing data for updates
+function sendForm(action, url, payload) {
     $.ajax({
         url: url,
         method: 'POST',
@@ -1990,6 +2296,7 @@ function submitForm(action, existingData = {}) {
             if (action.config?.show_success_message !== false) {
                 showSuccess('Operation completed successfully');
             }
+            (response.warnings || []).forEach(w => showWarning(w.message));
 
             // Reload list if available
             const listAction = currentSection.actions.find(a => a.type === 'list');
@@ -1998,7 +2305,15 @@ function submitForm(action, existingData = {}) {
             }
         },
         error: function(err) {
-            showError('Operation failed: ' + (err.responseJSON?.error || err.responseText));
+            const warnings = err.responseJSON?.warnings;
+            if (err.status === 409 && warnings) {
+                const messages = warnings.map(w => w.message).join('\n');
+                if (confirm(messages + '\n\nSave anyway?')) {
+                    sendForm(action, url, Object.assign({}, payload, { accept_warnings: true }));
+                }
+                return;
+            }
+            showError('Operation failed: ' + errorMessage(err));
         },
         complete: function() {
             $('#submit-text').show();
@@ -2007,10 +2322,66 @@ function submitForm(action, existingData = {}) {
     });
 }
 
+// Set a field on every record matching the list filters
+function bulkUpdateMatching(action, filter, expectedCount) {
+    const editable = action.fields.filter(f => f.editable && f.field_type !== 'state');
+    const fieldId = prompt(`Field to update (${editable.map(f => f.id).join(', ')}):`);
+    const field = editable.find(f => f.id === fieldId);
+    if (!field) {
+        return;
+    }
+    const value = prompt(`New value of ${field.name} for the ${expectedCount} matching records:`);
+    if (value === null) {
+        return;
+    }
+
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}/bulk-update`;
+
+    $.ajax({
+        url: url,
+        method: 'POST',
+        contentType: 'application/json',
+        data: JSON.stringify({ filter: filter, data: { [field.id]: value }, expected_count: expectedCount }),
+        success: function(response) {
+            showSuccess(`${response.affected} records updated`);
+
+            const listAction = currentSection.actions.find(a => a.type === 'list');
+            if (listAction) {
+                loadListData(listAction, currentPage);
+            }
+        },
+        error: function(err) {
+            showError('Bulk update failed: ' + errorMessage(err));
+        }
+    });
+}
+
+// Copy a record through the duplicate endpoint
+function duplicateRecord(row) {
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/records/${encodeURIComponent(row.id)}/duplicate`;
+
+    $.ajax({
+        url: url,
+        method: 'POST',
+        success: function() {
+            showSuccess('Item duplicated successfully');
+
+            // Reload list
+            const listAction = currentSection.actions.find(a => a.type === 'list');
+            if (listAction) {
+                loadListData(listAction, currentPage);
+            }
+        },
+        error: function(err) {
+            showError('Duplicate failed: ' + errorMessage(err));
+        }
+    });
+}
+
 // Confirm delete
 function confirmDelete(action, data = {}) {
     if (confirm('Are you sure you want to delete this item?')) {
-        const url = `/api/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
+        const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
 
         $.ajax({
             url: url,
@@ -2027,7 +2398,7 @@ function confirmDelete(action, data = {}) {
                 }
             },
             error: function(err) {
-                showError('Delete failed: ' + (err.responseJSON?.error || err.responseText));
+                showError('Delete failed: ' + errorMessage(err));
             }
         });
     }
@@ -2035,19 +2406,19 @@ function confirmDelete(action, data = {}) {
 
 // Load view data
 function loadViewData(action) {
-    const url = `/api/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
 
     $('#data-area').html('<div class="text-center py-8"><div class="loading mx-auto"></div><p class="mt-4 text-gray-500">Loading...</p></div>');
 
     $.get(url, function(response) {
-        renderViewData(response.data, response.fields);
+        renderViewData(response.data, response.fields, response.layout);
     }).fail(function(err) {
-        showError('Failed to load data: ' + (err.responseJSON?.error || err.responseText));
+        showError('Failed to load data: ' + errorMessage(err));
     });
 }
 
 // Render view data
-function renderViewData(data, fields) {
+function renderViewData(data, fields, layout = null) {
     const $dataArea = $('#data-area');
     $dataArea.empty();
 
@@ -2061,12 +2432,14 @@ function renderViewData(data, fields) {
     data.forEach(function(item) {
         const $card = $('<div>').addClass('bg-gray-50 p-4 rounded-lg');
 
-        fields.filter(f => f.visible).forEach(function(field) {
+        renderLayout($card, fields.filter(f => f.visible), layout, function(field, $container) {
             const value = item[field.id] || '';
-            $card.append(
+            const $value = $('<span>').addClass('text-gray-900').text(formatFieldValue(value, field));
+            renderCurrency($value, item, field);
+            $container.append(
                 $('<div>').addClass('mb-2')
                     .append($('<strong>').addClass('text-gray-700').text(field.name + ': '))
-                    .append($('<span>').addClass('text-gray-900').text(formatFieldValue(value, field)))
+                    .append($value)
             );
         });
 
@@ -2076,6 +2449,355 @@ function renderViewData(data, fields) {
     $dataArea.append($details);
 }
 
+// Show the backoffice dashboard, refreshing widgets on their intervals
+function showDashboard() {
+    clearDashboardTimers();
+    currentSection = null;
+
+    $('#sections-list li').removeClass('bg-indigo-100 font-semibold');
+    $('#sections-list li[data-id="__dashboard"]').addClass('bg-indigo-100 font-semibold');
+
+    const $content = $('#content-area');
+    $content.empty();
+    $content.append($('<h1>').addClass('text-3xl font-bold text-gray-800 mb-6').text('Dashboard'));
+
+    const $grid = $('<div>').addClass('grid grid-cols-12 gap-4');
+    currentBackoffice.dashboard.widgets.forEach(function(widget) {
+        const layout = widget.layout || {};
+        const $card = $('<div>')
+            .addClass('bg-white rounded-lg shadow p-4')
+            .attr('data-widget-id', widget.id)
+            .css('grid-column', `${layout.x != null ? layout.x + 1 : 'auto'} / span ${layout.width || 6}`)
+            .css('grid-row', `${layout.y != null ? layout.y + 1 : 'auto'} / span ${layout.height || 1}`);
+        $card.append($('<h2>').addClass('text-lg font-semibold text-gray-700 mb-3').text(widget.title));
+        $card.append($('<div>').addClass('widget-body').html('<div class="loading mx-auto"></div>'));
+        $grid.append($card);
+
+        if (widget.refresh_secs) {
+            dashboardTimers.push(setInterval(function() {
+                loadDashboard([widget.id]);
+            }, widget.refresh_secs * 1000));
+        }
+    });
+    $content.append($grid);
+
+    loadDashboard();
+    pushSubscribe({ channel: 'dashboard', backoffice_id: currentBackoffice.id }, function(event) {
+        if (event.event === 'dashboard') {
+            loadDashboard(event.widgets);
+        }
+    });
+}
+
+// Fetch the datasets of some (or all) dashboard widgets
+function loadDashboard(widgetIds) {
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/dashboard`;
+    const params = widgetIds ? { widgets: widgetIds.join(',') } : {};
+
+    $.get(url, params, function(response) {
+        response.widgets.forEach(function(widget) {
+            renderWidget(widget, $(`[data-widget-id="${widget.id}"] .widget-body`));
+        });
+    }).fail(function(err) {
+        showError('Failed to load dashboard: ' + errorMessage(err));
+    });
+}
+
+// Render one widget's dataset
+function renderWidget(widget, $body) {
+    $body.empty();
+
+    if (widget.error) {
+        $body.append($('<p>').addClass('text-red-600 text-sm').text(widget.error));
+        return;
+    }
+
+    if (widget.chart === 'number') {
+        const series = widget.dataset && widget.dataset.series[0];
+        const row = (widget.data || [])[0] || {};
+        const value = series ? series.data[0] : Object.values(row)[0];
+        $body.append($('<p>').addClass('text-4xl font-bold text-indigo-600').text(value == null ? '—' : value.toLocaleString()));
+        return;
+    }
+
+    if (widget.dataset) {
+        renderSummary(widget.dataset, $body);
+        return;
+    }
+
+    // Rows of list and view actions
+    const rows = widget.data || [];
+    if (rows.length === 0) {
+        $body.html('<p class="text-gray-500 text-center py-4">No data available</p>');
+        return;
+    }
+    const columns = Object.keys(rows[0]);
+    const $table = $('<table>').addClass('min-w-full divide-y divide-gray-200 text-sm');
+    $table.append($('<thead>').append($('<tr>').append(columns.map(c => $('<th>').addClass('px-3 py-2 text-left text-gray-500').text(c)))));
+    const $tbody = $('<tbody>');
+    rows.forEach(function(row) {
+        $tbody.append($('<tr>').append(columns.map(c => $('<td>').addClass('px-3 py-2').text(row[c] == null ? '' : String(row[c])))));
+    });
+    $body.append($table.append($tbody));
+}
+
+function clearDashboardTimers() {
+    dashboardTimers.forEach(clearInterval);
+    dashboardTimers = [];
+    pushSubscribe(null);
+}
+
+// Listen to one push channel at a time over the /api/ws WebSocket,
+// replacing the previous subscription; null just unsubscribes
+function pushSubscribe(channel, onEvent) {
+    const send = function(message) {
+        if (pushSocket && pushSocket.readyState === WebSocket.OPEN) {
+            pushSocket.send(JSON.stringify(message));
+        }
+    };
+    if (pushChannel) {
+        send(Object.assign({ action: 'unsubscribe' }, pushChannel));
+    }
+    pushChannel = channel;
+    pushHandler = onEvent;
+    if (!channel || !window.WebSocket) {
+        return;
+    }
+
+    if (!pushSocket || pushSocket.readyState > WebSocket.OPEN) {
+        const scheme = location.protocol === 'https:' ? 'wss' : 'ws';
+        pushSocket = new WebSocket(`${scheme}://${location.host}/api/ws`);
+        pushSocket.onopen = function() {
+            if (pushChannel) {
+                send(Object.assign({ action: 'subscribe' }, pushChannel));
+            }
+        };
+        pushSocket.onmessage = function(message) {
+            const event = JSON.parse(message.data);
+            if (pushHandler) {
+                pushHandler(event);
+            }
+        };
+    } else {
+        send(Object.assign({ action: 'subscribe' }, channel));
+    }
+}
+
+// Load summary data
+function loadSummaryData(action) {
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}`;
+
+    $('#data-area').html('<div class="text-center py-8"><div class="loading mx-auto"></div><p class="mt-4 text-gray-500">Loading...</p></div>');
+
+    $.get(url, function(response) {
+        renderSummary(response.chart, $('#data-area'), response.data);
+    }).fail(function(err) {
+        showError('Failed to load data: ' + errorMessage(err));
+    });
+}
+
+// Render a summary as a table of groups, with a bar per aggregate value;
+// currency aggregates of the summary rows show in the reporting currency
+function renderSummary(chart, $dataArea = $('#data-area'), rows = []) {
+    $dataArea.empty();
+
+    if (!chart || chart.labels.length === 0) {
+        $dataArea.html('<p class="text-gray-500 text-center py-8">No data available</p>');
+        return;
+    }
+
+    const $table = $('<table>').addClass('min-w-full divide-y divide-gray-200');
+    const $headRow = $('<tr>').append($('<th>').addClass('px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase'));
+    chart.series.forEach(function(series) {
+        $headRow.append($('<th>').addClass('px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase').text(series.name));
+    });
+    $table.append($('<thead>').addClass('bg-gray-50').append($headRow));
+
+    const maxima = chart.series.map(series => Math.max(...series.data.map(v => v || 0), 0));
+    const $body = $('<tbody>').addClass('bg-white divide-y divide-gray-200');
+    chart.labels.forEach(function(label, i) {
+        const $row = $('<tr>').append($('<td>').addClass('px-6 py-4 text-sm font-medium text-gray-900').text(label === null ? '—' : label));
+        chart.series.forEach(function(series, s) {
+            const value = series.data[i];
+            const width = maxima[s] > 0 && value > 0 ? (value / maxima[s]) * 100 : 0;
+            const row = rows[i] || {};
+            const converted = row._converted && row._converted[series.name];
+            const formatted = converted ? converted.formatted : row._formatted && row._formatted[series.name];
+            $row.append(
+                $('<td>').addClass('px-6 py-4 text-sm text-gray-900')
+                    .append($('<div>').text(value === null ? '—' : formatted || value.toLocaleString()))
+                    .append($('<div>').addClass('h-2 bg-indigo-500 rounded mt-1').css('width', width + '%'))
+            );
+        });
+        $body.append($row);
+    });
+    $table.append($body);
+
+    $dataArea.append($table);
+}
+
+// Render a report's parameter form, running the report on submit
+function showReport(action) {
+    const $dataArea = $('#data-area');
+    $dataArea.empty();
+
+    const $form = $('<form>').addClass('grid grid-cols-1 md:grid-cols-3 gap-4 mb-6');
+    (action.parameters || []).forEach(function(parameter) {
+        const $group = $('<div>').addClass('form-group').append(
+            $('<label>')
+                .addClass('block text-sm font-medium text-gray-700 mb-1')
+                .attr('for', parameter.id)
+                .text(parameter.name + (parameter.required ? ' *' : ''))
+        );
+        const $input = createFieldInput(parameter, parameter.default_value ?? '');
+        if (parameter.required) {
+            $input.attr('required', true);
+        }
+        $form.append($group.append($input));
+    });
+
+    const $actions = $('<div>').addClass('flex items-end gap-2');
+    $actions.append(
+        $('<button>').attr('type', 'submit')
+            .addClass('px-4 py-2 bg-indigo-600 hover:bg-indigo-700 text-white rounded-md')
+            .html('<i class="fas fa-play mr-2"></i>Run')
+    );
+    (action.config?.export_formats || ['csv', 'json', 'jsonl']).forEach(function(format) {
+        $actions.append(
+            $('<button>').attr('type', 'button')
+                .addClass('px-3 py-2 border border-gray-300 rounded-md text-sm text-gray-700 hover:bg-gray-50')
+                .html(`<i class="fas fa-download mr-1"></i>${format.toUpperCase()}`)
+                .on('click', function() {
+                    if ($form[0].reportValidity()) {
+                        window.location = reportUrl(action, $form, format);
+                    }
+                })
+        );
+    });
+    $form.append($actions);
+
+    const $results = $('<div>').attr('id', 'report-results');
+    $dataArea.append($form, $results);
+
+    $form.on('submit', function(e) {
+        e.preventDefault();
+        $results.html('<div class="text-center py-8"><div class="loading mx-auto"></div><p class="mt-4 text-gray-500">Loading...</p></div>');
+
+        $.get(reportUrl(action, $form), function(response) {
+            renderReport(response, $results);
+        }).fail(function(err) {
+            const errors = err.responseJSON?.validation_errors;
+            showError(errors ? errors.map(e => e.message).join(', ') : 'Failed to run report: ' + errorMessage(err));
+            $results.empty();
+        });
+    });
+}
+
+function reportUrl(action, $form, format) {
+    const params = {};
+    $form.serializeArray().forEach(function(input) {
+        if (input.value !== '') {
+            params[input.name] = input.value;
+        }
+    });
+    if (format) {
+        params.format = format;
+    }
+    return `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${action.id}?${$.param(params)}`;
+}
+
+// Render report rows as a table, in the order of the report's fields
+function renderReport(response, $results) {
+    $results.empty();
+
+    if (!response.data || response.data.length === 0) {
+        $results.html('<p class="text-gray-500 text-center py-8">No data available</p>');
+        return;
+    }
+
+    const columns = response.fields.length > 0
+        ? response.fields.map(f => ({ id: f.id, name: f.name }))
+        : Object.keys(response.data[0]).map(key => ({ id: key, name: key }));
+
+    const $headRow = $('<tr>');
+    columns.forEach(function(column) {
+        $headRow.append($('<th>').addClass('px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase').text(column.name));
+    });
+    const $body = $('<tbody>').addClass('bg-white divide-y divide-gray-200');
+    response.data.forEach(function(row) {
+        const $row = $('<tr>');
+        columns.forEach(function(column) {
+            const value = row[column.id];
+            $row.append($('<td>').addClass('px-6 py-4 text-sm text-gray-900').text(value === null || value === undefined ? '—' : value));
+        });
+        $body.append($row);
+    });
+
+    $results.append(
+        $('<table>').addClass('min-w-full divide-y divide-gray-200')
+            .append($('<thead>').addClass('bg-gray-50').append($headRow))
+            .append($body)
+    );
+}
+
+// Render fields into a container: ungrouped fields first, then one
+// fieldset or tab per layout group, in the group's order
+function renderLayout($container, fields, layout, renderField) {
+    const groups = (layout?.groups || [])
+        .map((group, index) => ({ ...group, index }))
+        .sort((a, b) => (a.order ?? Infinity) - (b.order ?? Infinity) || a.index - b.index)
+        .map(group => ({
+            ...group,
+            fields: group.fields.map(id => fields.find(f => f.id === id)).filter(Boolean)
+        }))
+        .filter(group => group.fields.length > 0);
+    const grouped = new Set(groups.flatMap(group => group.fields.map(f => f.id)));
+
+    fields.filter(f => !grouped.has(f.id)).forEach(field => renderField(field, $container));
+    if (groups.length === 0) return;
+
+    if (layout.style === 'tabs') {
+        const $tabs = $('<div>').addClass('flex border-b border-gray-200 mb-4');
+        const $panes = $('<div>');
+        groups.forEach(function(group, i) {
+            const $pane = $('<div>').toggle(i === 0);
+            group.fields.forEach(field => renderField(field, $pane));
+            const $tab = $('<button>').attr('type', 'button')
+                .addClass('px-4 py-2 text-sm font-medium border-b-2 -mb-px')
+                .toggleClass('border-indigo-600 text-indigo-600', i === 0)
+                .toggleClass('border-transparent text-gray-500', i !== 0)
+                .text(group.label)
+                .on('click', function() {
+                    $tabs.children().removeClass('border-indigo-600 text-indigo-600').addClass('border-transparent text-gray-500');
+                    $(this).removeClass('border-transparent text-gray-500').addClass('border-indigo-600 text-indigo-600');
+                    $panes.children().hide();
+                    $pane.show();
+                });
+            $tabs.append($tab);
+            $panes.append($pane);
+        });
+        $container.append($tabs, $panes);
+        return;
+    }
+
+    groups.forEach(function(group) {
+        const $body = $('<div>');
+        group.fields.forEach(field => renderField(field, $body));
+        const $legend = $('<legend>').addClass('px-2 text-sm font-semibold text-gray-700').text(group.label);
+        if (group.collapsible) {
+            $legend.addClass('cursor-pointer')
+                .prepend($('<i>').addClass('fas fa-chevron-down mr-2'))
+                .on('click', function() {
+                    $body.toggle();
+                    $(this).find('i').toggleClass('fa-chevron-down fa-chevron-right');
+                });
+        }
+        $container.append(
+            $('<fieldset>').addClass('border border-gray-200 rounded-md p-4 mb-4').append($legend, $body)
+        );
+    });
+}
+
 // Close modal
 function closeModal() {
     $('#formModal').removeClass('active');
@@ -2088,6 +2810,9 @@ function getActionButtonClass(actionType) {
         case 'form': return 'bg-green-600 hover:bg-green-700 text-white';
         case 'list': return 'bg-indigo-600 hover:bg-indigo-700 text-white';
         case 'view': return 'bg-blue-600 hover:bg-blue-700 text-white';
+        case 'summary': return 'bg-purple-600 hover:bg-purple-700 text-white';
+        case 'report': return 'bg-teal-600 hover:bg-teal-700 text-white';
+        case 'pipeline': return 'bg-orange-600 hover:bg-orange-700 text-white';
         default: return 'bg-gray-600 hover:bg-gray-700 text-white';
     }
 }
@@ -2097,6 +2822,9 @@ function getActionIcon(actionType) {
         case 'form': return 'fa-edit';
         case 'list': return 'fa-list';
         case 'view': return 'fa-eye';
+        case 'summary': return 'fa-chart-bar';
+        case 'report': return 'fa-file-alt';
+        case 'pipeline': return 'fa-project-diagram';
         default: return 'fa-cog';
     }
 }
@@ -2237,6 +2965,11 @@ function hideToast(toastId) {
     }, 300);
 }
 
+// Message of a failed request; API errors are problem documents
+function errorMessage(err) {
+    return err.responseJSON?.detail || err.responseJSON?.title || err.responseText;
+}
+
 function showError(message) {
     showToast(message, 'error', 6000);
     console.error(message);
@@ -2586,7 +3319,7 @@ async function loadRelatedData(recordId, relationships) {
     for (const rel of relationships) {
         try {
             // Fetch related records
-            const response = await fetch(`/api/backoffices/${currentBackoffice.id}/sections/${rel.to_section}/actions/list_${rel.to_section}`);
+            const response = await fetch(`${API_BASE}/backoffices/${currentBackoffice.id}/sections/${rel.to_section}/actions/list_${rel.to_section}`, { headers: JSON_HEADERS });
             if (response.ok) {
                 const data = await response.json();
                 // Filter related records based on the relationship
@@ -2669,7 +3402,7 @@ function renderRelationshipsPanel(recordId, relationships, $container) {
 async function loadRelationshipData(recordId, relationship, $container) {
     try {
         // Build query based on relationship
-        const response = await fetch(`/api/backoffices/${currentBackoffice.id}/sections/${relationship.to_section}/actions/list_${relationship.to_section}`);
+        const response = await fetch(`${API_BASE}/backoffices/${currentBackoffice.id}/sections/${relationship.to_section}/actions/list_${relationship.to_section}`, { headers: JSON_HEADERS });
 
         if (!response.ok) {
             throw new Error('Failed to fetch related data');
@@ -2848,16 +3581,16 @@ async function handleCascadeDelete(recordId, relationships) {
     for (const rel of cascadeRels) {
         try {
             // Fetch related records
-            const response = await fetch(`/api/backoffices/${currentBackoffice.id}/sections/${rel.to_section}/actions/list_${rel.to_section}`);
+            const response = await fetch(`${API_BASE}/backoffices/${currentBackoffice.id}/sections/${rel.to_section}/actions/list_${rel.to_section}`, { headers: JSON_HEADERS });
             if (response.ok) {
                 const allData = await response.json();
                 const relatedRecords = allData.filter(record => String(record[rel.to_field]) === String(recordId));
 
                 // Delete each related record
                 for (const record of relatedRecords) {
-                    await fetch(`/api/backoffices/${currentBackoffice.id}/sections/${rel.to_section}/actions/delete_${rel.to_section}`, {
+                    await fetch(`${API_BASE}/backoffices/${currentBackoffice.id}/sections/${rel.to_section}/actions/delete_${rel.to_section}`, {
                         method: 'POST',
-                        headers: { 'Content-Type': 'application/json' },
+                        headers: { ...JSON_HEADERS, 'Content-Type': 'application/json' },
                         body: JSON.stringify({ id: record.id })
                     });
                 }
@@ -3438,24 +4171,22 @@ function makeInlineEditable($cell, field, row) {
 
         // Only save if value changed
         if (newValue !== originalValue) {
-            // Update the row data
-            row[field.id] = newValue;
-
             // Find update action
             const updateAction = currentSection.actions.find(a =>
                 a.type === 'form' && a.config && a.config.form_mode === 'update'
             );
 
             if (updateAction) {
-                // Save to backend
-                const url = `/api/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${updateAction.id}`;
+                // Save only the changed field to the backend
+                const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/records/${encodeURIComponent(row.id)}/fields/${encodeURIComponent(field.id)}?action=${encodeURIComponent(updateAction.id)}`;
 
                 $.ajax({
                     url: url,
-                    method: 'POST',
+                    method: 'PATCH',
                     contentType: 'application/json',
-                    data: JSON.stringify(row),
+                    data: JSON.stringify({ value: newValue }),
                     success: function() {
+                        row[field.id] = newValue;
                         $cell.text(formatFieldValue(newValue, field));
                         $cell.addClass('bg-green-100');
                         setTimeout(() => $cell.removeClass('bg-green-100'), 1000);
@@ -3463,11 +4194,12 @@ function makeInlineEditable($cell, field, row) {
                     },
                     error: function(err) {
                         $cell.text(formattedValue);
-                        showError('Failed to update: ' + (err.responseJSON?.error || err.responseText));
+                        showError('Failed to update: ' + errorMessage(err));
                     }
                 });
             } else {
                 // No update action, just update UI
+                row[field.id] = newValue;
                 $cell.text(formatFieldValue(newValue, field));
                 showWarning('Saved locally only (no update action configured)');
             }
@@ -3571,7 +4303,7 @@ function bulkDeleteRows() {
     showProgress('Deleting rows...', 0, selectedRows.length);
 
     selectedRows.forEach((row, index) => {
-        const url = `/api/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${deleteAction.id}`;
+        const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${deleteAction.id}`;
 
         $.ajax({
             url: url,
@@ -3726,109 +4458,111 @@ function initializeFilterPresets() {
     });
 }
 
-// ===== CSV IMPORT =====
+// ===== FILE IMPORT =====
 
 function showImportDialog() {
     const dialogHtml = `
         <div class="p-4">
-            <h3 class="text-lg font-bold mb-4">Import CSV</h3>
-            <p class="text-sm text-gray-600 mb-4">Select a CSV file to import. The first row should contain column headers.</p>
+            <h3 class="text-lg font-bold mb-4">Import File</h3>
+            <p class="text-sm text-gray-600 mb-4">Select a CSV, JSON or JSONL file to import. Validate it first to see the errors of every row without importing anything.</p>
 
-            <input type="file" id="csv-file-input" accept=".csv" class="mb-4 block w-full text-sm text-gray-500
+            <input type="file" id="csv-file-input" accept=".csv,.json,.jsonl" class="mb-4 block w-full text-sm text-gray-500
                 file:mr-4 file:py-2 file:px-4
                 file:rounded file:border-0
                 file:text-sm file:font-semibold
                 file:bg-indigo-50 file:text-indigo-700
                 hover:file:bg-indigo-100" />
 
+            <div id="import-report" class="text-sm"></div>
+
             <div class="flex justify-end gap-2 mt-6">
                 <button onclick="closeModal()" class="px-4 py-2 border border-gray-300 rounded-md text-gray-700 hover:bg-gray-50">Cancel</button>
-                <button onclick="processCSVImport()" class="px-4 py-2 bg-indigo-600 text-white rounded-md hover:bg-indigo-700">Import</button>
+                <button onclick="processImport(true)" class="px-4 py-2 border border-indigo-600 text-indigo-600 rounded-md hover:bg-indigo-50">Validate</button>
+                <button onclick="processImport(false)" class="px-4 py-2 bg-indigo-600 text-white rounded-md hover:bg-indigo-700">Import</button>
             </div>
         </div>
     `;
 
-    $('#modal-title').text('Import CSV');
+    $('#modal-title').text('Import File');
     $('#form-fields').html(dialogHtml);
     $('#submit-text').parent().hide();
     $('#formModal').addClass('active');
 }
 
-function processCSVImport() {
+// Send the selected file to the import endpoint, or only validate it
+function processImport(dryRun) {
     const fileInput = document.getElementById('csv-file-input');
     if (!fileInput.files.length) {
         showError('Please select a file');
         return;
     }
 
-    const file = fileInput.files[0];
-    const reader = new FileReader();
-
-    reader.onload = function(e) {
-        const csvContent = e.target.result;
-        const rows = csvContent.split('\n').map(row => row.split(','));
-
-        if (rows.length < 2) {
-            showError('CSV file must have at least a header row and one data row');
-            return;
-        }
-
-        const headers = rows[0].map(h => h.trim());
-        const dataRows = rows.slice(1).filter(row => row.length > 1);
-
-        closeModal();
-        showProgress('Importing rows...', 0, dataRows.length);
-
-        const createAction = currentSection.actions.find(a =>
-            a.type === 'form' && a.config && a.config.form_mode === 'create'
-        );
+    const createAction = currentSection.actions.find(a =>
+        a.type === 'form' && a.config && a.config.form_mode === 'create'
+    );
 
-        if (!createAction) {
-            showError('No create action configured for this section');
-            return;
-        }
+    if (!createAction) {
+        showError('No create action configured for this section');
+        return;
+    }
 
-        let completed = 0;
-        let failed = 0;
+    const file = fileInput.files[0];
+    const extension = file.name.split('.').pop().toLowerCase();
+    const params = new URLSearchParams({ dry_run: dryRun });
+    if (['csv', 'json', 'jsonl'].includes(extension)) {
+        params.set('format', extension);
+    }
+    const url = `${API_BASE}/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${createAction.id}/import?${params}`;
 
-        dataRows.forEach((row, index) => {
-            const rowData = {};
-            headers.forEach((header, i) => {
-                if (row[i]) {
-                    rowData[header] = row[i].trim();
+    const reader = new FileReader();
+    reader.onload = function(e) {
+        $.ajax({
+            url: url,
+            method: 'POST',
+            contentType: 'text/plain',
+            data: e.target.result,
+            success: function(response) {
+                if (dryRun) {
+                    renderImportReport(response);
+                    return;
                 }
-            });
-
-            const url = `/api/backoffices/${currentBackoffice.id}/sections/${currentSection.id}/actions/${createAction.id}`;
-
-            $.ajax({
-                url: url,
-                method: 'POST',
-                contentType: 'application/json',
-                data: JSON.stringify(rowData),
-                success: function() {
-                    completed++;
-                    updateProgress(completed + failed, dataRows.length);
-
-                    if (completed + failed === dataRows.length) {
-                        finishBulkOperation(completed, failed, true);
-                    }
-                },
-                error: function() {
-                    failed++;
-                    updateProgress(completed + failed, dataRows.length);
-
-                    if (completed + failed === dataRows.length) {
-                        finishBulkOperation(completed, failed, true);
-                    }
+                closeModal();
+                showSuccess(`Successfully imported ${response.imported} row(s)`);
+                if (currentAction) {
+                    executeAction(currentAction);
                 }
-            });
+            },
+            error: function(err) {
+                if (err.responseJSON && err.responseJSON.errors) {
+                    renderImportReport(err.responseJSON);
+                } else {
+                    showError('Import failed: ' + errorMessage(err));
+                }
+            }
         });
     };
 
     reader.readAsText(file);
 }
 
+// List the errors of an import by row
+function renderImportReport(report) {
+    const $report = $('#import-report').empty();
+    if (report.errors.length === 0) {
+        $report.append($('<p>').addClass('text-green-700').text(`All ${report.rows} row(s) are valid`));
+    } else {
+        $report.append($('<p>').addClass('text-red-700 mb-2').text(`${report.errors.length} error(s) in ${report.rows} row(s)`));
+        const $list = $('<ul>').addClass('max-h-48 overflow-y-auto text-red-600');
+        report.errors.forEach(e => {
+            $list.append($('<li>').text(`Row ${e.row}, ${e.field}: ${e.message}`));
+        });
+        $report.append($list);
+    }
+    if (report.ignored_columns && report.ignored_columns.length > 0) {
+        $report.append($('<p>').addClass('text-gray-500 mt-2').text(`Ignored columns: ${report.ignored_columns.join(', ')}`));
+    }
+}
+
 // ===== PROGRESS INDICATORS =====
 
 function showProgress(message, current, total) {
@@ -3863,12 +4597,12 @@ function hideProgress() {
     });
 }
 
-function finishBulkOperation(completed, failed, isImport = false) {
+function finishBulkOperation(completed, failed) {
     setTimeout(() => {
         hideProgress();
 
         if (failed === 0) {
-            showSuccess(`Successfully ${isImport ? 'imported' : 'processed'} ${completed} row(s)`);
+            showSuccess(`Successfully processed ${completed} row(s)`);
         } else {
             showWarning(`Completed: ${completed}, Failed: ${failed}`);
         }
@@ -12,11 +12,28 @@ fn test_create_app_config() {
         server: ServerConfig {
             host: "0.0.0.0".to_string(),
             port: 3000,
+            compression: Default::default(),
+            limits: Default::default(),
+            circuit_breaker: Default::default(),
+            health_checks: Default::default(),
+            trusted_proxies: 0,
+            access_log: Default::default(),
         },
         security: Some(SecurityConfig {
             enabled: false,
             jwt_secret: None,
+            unauthorized_field_writes: Default::default(),
+            admin_scope: "admin".to_string(),
         }),
+        audit_storage: None,
+        audit: Default::default(),
+        i18n: Default::default(),
+        antivirus: None,
+        push: Default::default(),
+        grpc: None,
+        config_history: Default::default(),
+        config_store: None,
+        feature_flags: Default::default(),
     };
 
     assert_eq!(config.server.host, "0.0.0.0");
@@ -37,15 +54,31 @@ fn test_create_backoffice_config() {
                 base_url: "https://api.example.com".to_string(),
                 headers: Some(HashMap::new()),
                 auth: None,
+                pagination: None,
+                response: None,
+                init: Default::default(),
             },
         )]),
         relationships: vec![],
+        plugins: vec![],
+        validation_functions: HashMap::new(),
+        script_hooks: vec![],
+        dashboard: None,
+        currency: None,
+        api: Default::default(),
         sections: vec![SectionConfig {
             id: "users".to_string(),
             name: "Users".to_string(),
             icon: Some("fa-users".to_string()),
             actions: vec![],
             audit: None,
+            row_filters: vec![],
+            table: None,
+            seeds: vec![],
+            updated_at_field: None,
+            search: None,
+            duplicate: None,
+            enabled_when: None,
         }],
     };
 
@@ -75,6 +108,13 @@ fn test_create_action_config() {
                 help_text: None,
                 validations: vec![],
                 relationship_id: None,
+                sensitive: false,
+                mask: None,
+                unmask_scopes: vec![],
+                required_scopes: vec![],
+                write_scopes: vec![],
+                visible_when: None,
+                required_when: None,
             }],
             config: ListActionConfig::default(),
         },
@@ -82,6 +122,11 @@ fn test_create_action_config() {
         required_scopes: vec!["read:items".to_string()],
         query: None,
         endpoint: Some("/items".to_string()),
+        method: None,
+        generated: false,
+        cache: None,
+        cache_ttl_secs: None,
+        enabled_when: None,
     };
 
     assert_eq!(action.id, "list_items");
@@ -105,6 +150,13 @@ fn test_field_config_with_validation() {
         help_text: Some("We'll never share your email".to_string()),
         validations: vec![],
         relationship_id: None,
+        sensitive: false,
+        mask: None,
+        unmask_scopes: vec![],
+        required_scopes: vec![],
+        write_scopes: vec![],
+        visible_when: None,
+        required_when: None,
     };
 
     assert_eq!(field.id, "email");
@@ -130,6 +182,11 @@ fn test_section_with_multiple_actions() {
                 required_scopes: vec![],
                 query: Some("SELECT * FROM products".to_string()),
                 endpoint: None,
+                method: None,
+                generated: false,
+                cache: None,
+                cache_ttl_secs: None,
+                enabled_when: None,
             },
             ActionConfig {
                 id: "create_product".to_string(),
@@ -137,14 +194,27 @@ fn test_section_with_multiple_actions() {
                 action_type: ActionType::Form {
                     fields: vec![],
                     config: Default::default(),
+                    layout: None,
                 },
                 data_source: "db".to_string(),
                 required_scopes: vec![],
                 query: Some("INSERT INTO products".to_string()),
                 endpoint: None,
+                method: None,
+                generated: false,
+                cache: None,
+                cache_ttl_secs: None,
+                enabled_when: None,
             },
         ],
         audit: None,
+        row_filters: vec![],
+        table: None,
+        seeds: vec![],
+        updated_at_field: None,
+        search: None,
+        duplicate: None,
+        enabled_when: None,
     };
 
     assert_eq!(section.actions.len(), 2);